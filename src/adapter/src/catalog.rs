@@ -6043,10 +6043,30 @@ impl Catalog {
 
     /// Return the current storage configuration, derived from the system configuration.
     pub fn storage_config(&self) -> StorageParameters {
+        let config = self.system_config();
         StorageParameters {
-            enable_multi_worker_storage_persist_sink: self
-                .system_config()
+            enable_multi_worker_storage_persist_sink: config
                 .enable_multi_worker_storage_persist_sink(),
+            pg_source_max_row_size_bytes: Some(config.pg_source_max_row_size_bytes()),
+            pg_source_max_transaction_changes: Some(config.pg_source_max_transaction_changes()),
+            pg_source_lsn_staleness_threshold: Some(config.pg_source_lsn_staleness_threshold()),
+            pg_source_schema_audit_interval: Some(config.pg_source_schema_audit_interval()),
+            pg_source_transaction_buffer_degraded_bytes: Some(
+                config.pg_source_transaction_buffer_degraded_bytes(),
+            ),
+            pg_source_backpressure_lag_bytes: Some(config.pg_source_backpressure_lag_bytes()),
+            pg_source_min_feedback_interval: Some(config.pg_source_min_feedback_interval()),
+            pg_source_paused_ids: config
+                .pg_source_paused_ids()
+                .iter()
+                .filter_map(|id| match id.parse() {
+                    Ok(id) => Some(id),
+                    Err(e) => {
+                        warn!("ignoring invalid pg_source_paused_ids entry {id:?}: {e}");
+                        None
+                    }
+                })
+                .collect(),
             persist: self.persist_config(),
         }
     }
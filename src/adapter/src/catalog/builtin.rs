@@ -1371,6 +1371,21 @@ pub static MZ_POSTGRES_SOURCES: Lazy<BuiltinTable> = Lazy::new(|| BuiltinTable {
         .with_column("replication_slot", ScalarType::String.nullable(false)),
     is_retained_metrics_object: false,
 });
+pub static MZ_POSTGRES_SOURCE_TABLES: Lazy<BuiltinTable> = Lazy::new(|| BuiltinTable {
+    name: "mz_postgres_source_tables",
+    schema: MZ_INTERNAL_SCHEMA,
+    desc: RelationDesc::empty()
+        .with_column("id", ScalarType::String.nullable(false))
+        .with_column("table_oid", ScalarType::Oid.nullable(false))
+        .with_column("schema_name", ScalarType::String.nullable(false))
+        .with_column("table_name", ScalarType::String.nullable(false))
+        .with_column("replica_identity", ScalarType::String.nullable(false))
+        .with_column(
+            "identity_columns",
+            ScalarType::Array(Box::new(ScalarType::String)).nullable(false),
+        ),
+    is_retained_metrics_object: false,
+});
 pub static MZ_OBJECT_DEPENDENCIES: Lazy<BuiltinTable> = Lazy::new(|| BuiltinTable {
     name: "mz_object_dependencies",
     schema: MZ_INTERNAL_SCHEMA,
@@ -1750,6 +1765,35 @@ WHERE
     mz_sources.id NOT LIKE 's%' and mz_sources.type != 'subsource'",
 };
 
+pub static MZ_POSTGRES_REPLICATION_SLOT_STATES: Lazy<BuiltinSource> = Lazy::new(|| BuiltinSource {
+    name: "mz_postgres_replication_slot_states",
+    schema: MZ_INTERNAL_SCHEMA,
+    data_source: Some(IntrospectionType::PostgresReplicationSlots),
+    desc: RelationDesc::empty()
+        .with_column("source_id", ScalarType::String.nullable(false))
+        .with_column("active", ScalarType::Bool.nullable(false))
+        .with_column("confirmed_flush_lsn", ScalarType::UInt64.nullable(true))
+        .with_column("wal_status", ScalarType::String.nullable(true))
+        .with_column("updated_at", ScalarType::TimestampTz.nullable(false)),
+    is_retained_metrics_object: false,
+});
+
+pub const MZ_POSTGRES_REPLICATION_SLOTS: BuiltinView = BuiltinView {
+    name: "mz_postgres_replication_slots",
+    schema: MZ_INTERNAL_SCHEMA,
+    sql: "CREATE VIEW mz_internal.mz_postgres_replication_slots AS
+SELECT
+    mz_postgres_sources.id AS source_id,
+    mz_postgres_sources.replication_slot AS slot_name,
+    slots.active,
+    slots.confirmed_flush_lsn::numeric AS confirmed_flush_lsn,
+    slots.wal_status,
+    slots.updated_at
+FROM mz_internal.mz_postgres_sources
+LEFT JOIN mz_internal.mz_postgres_replication_slot_states AS slots
+    ON mz_postgres_sources.id = slots.source_id",
+};
+
 pub static MZ_SINK_STATUS_HISTORY: Lazy<BuiltinSource> = Lazy::new(|| BuiltinSource {
     name: "mz_sink_status_history",
     schema: MZ_INTERNAL_SCHEMA,
@@ -3525,6 +3569,7 @@ pub static BUILTINS_STATIC: Lazy<Vec<Builtin<NameReference>>> = Lazy::new(|| {
         Builtin::Table(&MZ_TABLES),
         Builtin::Table(&MZ_SOURCES),
         Builtin::Table(&MZ_POSTGRES_SOURCES),
+        Builtin::Table(&MZ_POSTGRES_SOURCE_TABLES),
         Builtin::Table(&MZ_SINKS),
         Builtin::Table(&MZ_VIEWS),
         Builtin::Table(&MZ_MATERIALIZED_VIEWS),
@@ -3630,6 +3675,8 @@ pub static BUILTINS_STATIC: Lazy<Vec<Builtin<NameReference>>> = Lazy::new(|| {
         Builtin::View(&MZ_SINK_STATUSES),
         Builtin::Source(&MZ_SOURCE_STATUS_HISTORY),
         Builtin::View(&MZ_SOURCE_STATUSES),
+        Builtin::Source(&MZ_POSTGRES_REPLICATION_SLOT_STATES),
+        Builtin::View(&MZ_POSTGRES_REPLICATION_SLOTS),
         Builtin::Source(&MZ_STORAGE_SHARDS),
         Builtin::Source(&MZ_SOURCE_STATISTICS),
         Builtin::Source(&MZ_SINK_STATISTICS),
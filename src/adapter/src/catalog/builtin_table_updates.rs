@@ -43,9 +43,9 @@ use crate::catalog::builtin::{
     MZ_CLUSTER_REPLICA_STATUSES, MZ_COLUMNS, MZ_CONNECTIONS, MZ_DATABASES, MZ_EGRESS_IPS,
     MZ_FUNCTIONS, MZ_INDEXES, MZ_INDEX_COLUMNS, MZ_KAFKA_CONNECTIONS, MZ_KAFKA_SINKS,
     MZ_LIST_TYPES, MZ_MAP_TYPES, MZ_MATERIALIZED_VIEWS, MZ_OBJECT_DEPENDENCIES, MZ_OPERATORS,
-    MZ_POSTGRES_SOURCES, MZ_PSEUDO_TYPES, MZ_ROLES, MZ_ROLE_MEMBERS, MZ_SCHEMAS, MZ_SECRETS,
-    MZ_SESSIONS, MZ_SINKS, MZ_SOURCES, MZ_SSH_TUNNEL_CONNECTIONS, MZ_STORAGE_USAGE_BY_SHARD,
-    MZ_SUBSCRIPTIONS, MZ_TABLES, MZ_TYPES, MZ_VIEWS,
+    MZ_POSTGRES_SOURCES, MZ_POSTGRES_SOURCE_TABLES, MZ_PSEUDO_TYPES, MZ_ROLES, MZ_ROLE_MEMBERS,
+    MZ_SCHEMAS, MZ_SECRETS, MZ_SESSIONS, MZ_SINKS, MZ_SOURCES, MZ_SSH_TUNNEL_CONNECTIONS,
+    MZ_STORAGE_USAGE_BY_SHARD, MZ_SUBSCRIPTIONS, MZ_TABLES, MZ_TYPES, MZ_VIEWS,
 };
 use crate::catalog::{
     CatalogItem, CatalogState, Connection, DataSourceDesc, Database, Error, ErrorKind, Func, Index,
@@ -444,14 +444,43 @@ impl CatalogState {
         postgres: &PostgresSourceConnection,
         diff: Diff,
     ) -> Vec<BuiltinTableUpdate> {
-        vec![BuiltinTableUpdate {
+        let mut updates = vec![BuiltinTableUpdate {
             id: self.resolve_builtin_table(&MZ_POSTGRES_SOURCES),
             row: Row::pack_slice(&[
                 Datum::String(&id.to_string()),
                 Datum::String(&postgres.publication_details.slot),
             ]),
             diff,
-        }]
+        }];
+        for table in &postgres.publication_details.tables {
+            let mut row = Row::default();
+            row.packer()
+                .push_array(
+                    &[ArrayDimension {
+                        lower_bound: 1,
+                        length: table.identity_column_names.len(),
+                    }],
+                    table
+                        .identity_column_names
+                        .iter()
+                        .map(|name| Datum::String(name)),
+                )
+                .expect("identity_column_names is 1 dimensional, and its length is used for the array length");
+            let identity_columns = row.unpack_first();
+            updates.push(BuiltinTableUpdate {
+                id: self.resolve_builtin_table(&MZ_POSTGRES_SOURCE_TABLES),
+                row: Row::pack_slice(&[
+                    Datum::String(&id.to_string()),
+                    Datum::UInt32(table.oid),
+                    Datum::String(&table.namespace),
+                    Datum::String(&table.name),
+                    Datum::String(table.replica_identity.as_str()),
+                    identity_columns,
+                ]),
+                diff,
+            });
+        }
+        updates
     }
 
     fn pack_connection_update(
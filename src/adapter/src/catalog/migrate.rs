@@ -13,6 +13,7 @@ use std::collections::BTreeMap;
 use tracing::info;
 
 use mz_ore::collections::CollectionExt;
+use mz_postgres_util::PublicationFilter;
 use mz_sql::ast::display::AstDisplay;
 use mz_sql::ast::{Raw, Statement, Value};
 use mz_storage_client::types::connections::ConnectionContext;
@@ -225,7 +226,9 @@ async fn pg_source_table_metadata_rewrite(
 
         // Get the current publication tables from the upstream PG source.
         let mut current_publication_tables =
-            match mz_postgres_util::publication_info(&config, publication, None).await {
+            match mz_postgres_util::publication_info(&config, publication, &PublicationFilter::All)
+                .await
+            {
                 Ok(v) => v,
                 Err(_) => {
                     warn!(
@@ -218,6 +218,7 @@ pub struct CreateSourceStatementReady {
         (
             Vec<(GlobalId, CreateSubsourceStatement<Aug>)>,
             CreateSourceStatement<Aug>,
+            Option<u64>,
         ),
         AdapterError,
     >,
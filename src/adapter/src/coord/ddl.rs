@@ -401,7 +401,7 @@ impl Coordinator {
                 task::spawn(|| "drop_replication_slots", async move {
                     for (config, slot_name) in replication_slots_to_drop {
                         // Try to drop the replication slots, but give up after a while.
-                        let _ = Retry::default()
+                        let res = Retry::default()
                             .max_duration(Duration::from_secs(30))
                             .retry_async(|_state| async {
                                 mz_postgres_util::drop_replication_slots(
@@ -411,6 +411,14 @@ impl Coordinator {
                                 .await
                             })
                             .await;
+                        // The slot keeps retaining WAL on the upstream until someone drops it, so
+                        // make sure the failure is visible to operators.
+                        if let Err(e) = res {
+                            warn!(
+                                "failed to drop replication slot {slot_name:?}, \
+                                 it must be dropped manually upstream: {e}"
+                            );
+                        }
                     }
                 });
             }
@@ -38,6 +38,10 @@ use crate::coord::{
 use crate::util::ResultExt;
 use crate::{catalog, AdapterError, AdapterNotice};
 
+/// The estimated storage size of the initial snapshot of a source above which creating it
+/// raises a notice.
+const LARGE_SOURCE_SNAPSHOT_BYTES: u64 = 10 * 1024 * 1024 * 1024;
+
 impl Coordinator {
     pub(crate) async fn handle_message(&mut self, msg: Message) {
         match msg {
@@ -403,10 +407,15 @@ impl Coordinator {
             return;
         }
 
-        let (subsource_stmts, stmt) = match result {
+        let (subsource_stmts, stmt, estimated_storage_bytes) = match result {
             Ok(ok) => ok,
             Err(e) => return tx.send(Err(e), session),
         };
+        if let Some(estimated_bytes) = estimated_storage_bytes {
+            if estimated_bytes > LARGE_SOURCE_SNAPSHOT_BYTES {
+                session.add_notice(AdapterNotice::LargeSourceSnapshot { estimated_bytes });
+            }
+        }
 
         let mut plans: Vec<CreateSourcePlans> = vec![];
         let mut id_allocation = BTreeMap::new();
@@ -70,7 +70,9 @@ use mz_sql::session::vars::{Var, ENABLE_RBAC_CHECKS};
 use mz_ssh_util::keys::SshKeyPairSet;
 use mz_storage_client::controller::{CollectionDescription, DataSource, ReadPolicy, StorageError};
 use mz_storage_client::types::sinks::StorageSinkConnectionBuilder;
-use mz_storage_client::types::sources::{IngestionDescription, SourceExport};
+use mz_storage_client::types::sources::{
+    GenericSourceConnection, IngestionDescription, SourceExport,
+};
 
 use crate::catalog::{
     self, Catalog, CatalogItem, Cluster, Connection, DataSourceDesc, Op, SerializedReplicaLocation,
@@ -3459,13 +3461,19 @@ impl Coordinator {
     pub(super) async fn sequence_alter_source(
         &mut self,
         session: &Session,
-        AlterSourcePlan { id, size }: AlterSourcePlan,
+        AlterSourcePlan {
+            id,
+            size,
+            table_renames,
+            publication,
+        }: AlterSourcePlan,
     ) -> Result<ExecuteResponse, AdapterError> {
         let source = self
             .catalog()
             .get_entry(&id)
             .source()
-            .expect("known to be source");
+            .expect("known to be source")
+            .clone();
         match source.data_source {
             DataSourceDesc::Ingestion(_) => (),
             DataSourceDesc::Introspection(_)
@@ -3485,10 +3493,111 @@ impl Coordinator {
 
             self.maybe_alter_linked_cluster(id).await;
         }
+        if table_renames.is_some() || publication.is_some() {
+            self.alter_source_postgres_options(session, id, source, table_renames, publication)
+                .await?;
+        }
 
         Ok(ExecuteResponse::AlteredObject(ObjectType::Source))
     }
 
+    /// Replaces the `TABLE RENAMES` and the `PUBLICATION` of the PostgreSQL source `id` that are
+    /// given, both in the catalog and in its running ingestion, which restarts with them.
+    ///
+    /// The source keeps replicating from the publication it was created from until it has
+    /// validated that the new publication contains the same tables, so that it keeps its slot and
+    /// doesn't snapshot its tables anew.
+    async fn alter_source_postgres_options(
+        &mut self,
+        session: &Session,
+        id: GlobalId,
+        mut source: catalog::Source,
+        table_renames: Option<BTreeMap<u32, BTreeSet<String>>>,
+        publication: Option<String>,
+    ) -> Result<(), AdapterError> {
+        use mz_sql::ast::{
+            CreateSourceConnection, PgConfigOption, PgConfigOptionName, Statement, Value,
+            WithOptionValue,
+        };
+
+        let DataSourceDesc::Ingestion(ingestion) = &mut source.data_source else {
+            coord_bail!("cannot ALTER this type of source");
+        };
+        let GenericSourceConnection::Postgres(connection) = &mut ingestion.desc.connection else {
+            coord_bail!("POSTGRES OPTIONS can only be set on PostgreSQL sources");
+        };
+        if let Some(table_renames) = &table_renames {
+            for oid in table_renames.keys() {
+                let ingested = connection
+                    .publication_details
+                    .tables
+                    .iter()
+                    .any(|table| table.oid == *oid);
+                if !ingested {
+                    coord_bail!("source ingests no table with OID {oid}");
+                }
+            }
+            connection.publication_table_rename_map = table_renames.clone();
+        }
+        if let Some(publication) = &publication {
+            connection.publication = publication.clone();
+        }
+        let connection = ingestion.desc.connection.clone();
+
+        // Since the catalog serializes the items using only their creation statement, the new
+        // renames must be written to that statement.
+        let mut stmt = mz_sql::parse::parse(&source.create_sql)
+            .expect("invalid create sql persisted to catalog")
+            .into_element();
+        let Statement::CreateSource(create_stmt) = &mut stmt else {
+            coord_bail!("source {id} was not created with a CREATE SOURCE statement");
+        };
+        let CreateSourceConnection::Postgres { options, .. } = &mut create_stmt.connection else {
+            coord_bail!("source {id} was not created from a PostgreSQL connection");
+        };
+        if let Some(table_renames) = table_renames {
+            options.retain(|option| option.name != PgConfigOptionName::TableRenames);
+            if !table_renames.is_empty() {
+                let renames = table_renames
+                    .into_iter()
+                    .flat_map(|(oid, names)| names.into_iter().map(move |name| (oid, name)))
+                    .map(|(oid, name)| {
+                        WithOptionValue::Sequence(vec![
+                            WithOptionValue::Value(Value::Number(oid.to_string())),
+                            WithOptionValue::Value(Value::String(name)),
+                        ])
+                    })
+                    .collect();
+                options.push(PgConfigOption {
+                    name: PgConfigOptionName::TableRenames,
+                    value: Some(WithOptionValue::Sequence(renames)),
+                });
+            }
+        }
+        // The publication the source was created from stays recorded in its `DETAILS`.
+        if let Some(publication) = publication {
+            options.retain(|option| option.name != PgConfigOptionName::Publication);
+            options.push(PgConfigOption {
+                name: PgConfigOptionName::Publication,
+                value: Some(WithOptionValue::Value(Value::String(publication))),
+            });
+        }
+        source.create_sql = stmt.to_ast_string_stable();
+
+        let name = self.catalog().get_entry(&id).name().clone();
+        let op = Op::UpdateItem {
+            id,
+            name,
+            to_item: CatalogItem::Source(source),
+        };
+        self.catalog_transact(Some(session), vec![op]).await?;
+        self.controller
+            .storage
+            .alter_collection(id, connection)
+            .await?;
+        Ok(())
+    }
+
     fn extract_secret(
         &mut self,
         session: &Session,
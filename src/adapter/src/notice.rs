@@ -9,6 +9,7 @@
 
 use std::fmt;
 
+use bytesize::ByteSize;
 use chrono::{DateTime, Utc};
 
 use mz_controller::clusters::ClusterStatus;
@@ -88,6 +89,9 @@ pub enum AdapterNotice {
         member_name: String,
     },
     AutoRunOnIntrospectionCluster,
+    LargeSourceSnapshot {
+        estimated_bytes: u64,
+    },
 }
 
 impl AdapterNotice {
@@ -105,6 +109,7 @@ impl AdapterNotice {
             AdapterNotice::DroppedActiveCluster { name: _ } => Some("Choose a new active cluster by executing SET CLUSTER = <name>.".into()),
             AdapterNotice::ClusterReplicaStatusChanged { status, .. } if *status == ClusterStatus::NotReady => Some("The cluster replica may be restarting or going offline.".into()),
             AdapterNotice::RbacDisabled => Some("To enable RBAC run `ALTER SYSTEM SET enable_rbac_checks TO true` as a superuser.".into()),
+            AdapterNotice::LargeSourceSnapshot { .. } => Some("Make sure the cluster of the source has enough disk space for the initial snapshot.".into()),
             _ => None
         }
     }
@@ -213,6 +218,11 @@ impl fmt::Display for AdapterNotice {
                 f,
                 "query was automatically run on the \"mz_introspection\" cluster"
             ),
+            AdapterNotice::LargeSourceSnapshot { estimated_bytes } => write!(
+                f,
+                "the initial snapshot of the source is estimated to take up {} of storage",
+                ByteSize::b(*estimated_bytes)
+            ),
         }
     }
 }
@@ -0,0 +1,355 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+// BEGIN LINT CONFIG
+// DO NOT EDIT. Automatically generated by bin/gen-lints.
+// Have complaints about the noise? See the note in misc/python/materialize/cli/gen-lints.py first.
+#![allow(clippy::style)]
+#![allow(clippy::complexity)]
+#![allow(clippy::large_enum_variant)]
+#![allow(clippy::mutable_key_type)]
+#![allow(clippy::stable_sort_primitive)]
+#![allow(clippy::map_entry)]
+#![allow(clippy::box_default)]
+#![warn(clippy::bool_comparison)]
+#![warn(clippy::clone_on_ref_ptr)]
+#![warn(clippy::no_effect)]
+#![warn(clippy::unnecessary_unwrap)]
+#![warn(clippy::dbg_macro)]
+#![warn(clippy::todo)]
+#![warn(clippy::wildcard_dependencies)]
+#![warn(clippy::zero_prefixed_literal)]
+#![warn(clippy::borrowed_box)]
+#![warn(clippy::deref_addrof)]
+#![warn(clippy::double_must_use)]
+#![warn(clippy::double_parens)]
+#![warn(clippy::extra_unused_lifetimes)]
+#![warn(clippy::needless_borrow)]
+#![warn(clippy::needless_question_mark)]
+#![warn(clippy::needless_return)]
+#![warn(clippy::redundant_pattern)]
+#![warn(clippy::redundant_slicing)]
+#![warn(clippy::redundant_static_lifetimes)]
+#![warn(clippy::single_component_path_imports)]
+#![warn(clippy::unnecessary_cast)]
+#![warn(clippy::useless_asref)]
+#![warn(clippy::useless_conversion)]
+#![warn(clippy::builtin_type_shadow)]
+#![warn(clippy::duplicate_underscore_argument)]
+#![warn(clippy::double_neg)]
+#![warn(clippy::unnecessary_mut_passed)]
+#![warn(clippy::wildcard_in_or_patterns)]
+#![warn(clippy::collapsible_if)]
+#![warn(clippy::collapsible_else_if)]
+#![warn(clippy::crosspointer_transmute)]
+#![warn(clippy::excessive_precision)]
+#![warn(clippy::overflow_check_conditional)]
+#![warn(clippy::as_conversions)]
+#![warn(clippy::match_overlapping_arm)]
+#![warn(clippy::zero_divided_by_zero)]
+#![warn(clippy::must_use_unit)]
+#![warn(clippy::suspicious_assignment_formatting)]
+#![warn(clippy::suspicious_else_formatting)]
+#![warn(clippy::suspicious_unary_op_formatting)]
+#![warn(clippy::mut_mutex_lock)]
+#![warn(clippy::print_literal)]
+#![warn(clippy::same_item_push)]
+#![warn(clippy::useless_format)]
+#![warn(clippy::write_literal)]
+#![warn(clippy::redundant_closure)]
+#![warn(clippy::redundant_closure_call)]
+#![warn(clippy::unnecessary_lazy_evaluations)]
+#![warn(clippy::partialeq_ne_impl)]
+#![warn(clippy::redundant_field_names)]
+#![warn(clippy::transmutes_expressible_as_ptr_casts)]
+#![warn(clippy::unused_async)]
+#![warn(clippy::disallowed_methods)]
+#![warn(clippy::disallowed_macros)]
+#![warn(clippy::disallowed_types)]
+#![warn(clippy::from_over_into)]
+// END LINT CONFIG
+
+//! Integration tests for Postgres sources, from `CREATE SOURCE` to reading
+//! the ingested data.
+//!
+//! These require the Postgres server at `POSTGRES_URL` to be configured for
+//! logical replication, i.e. with `wal_level = logical`.
+
+use std::error::Error;
+use std::sync::Arc;
+
+use tokio::runtime::Runtime;
+use tokio_postgres::Client;
+
+use mz_postgres_util::PublicationFilter;
+
+pub mod util;
+
+/// Runs `sql` against the upstream Postgres server.
+fn upstream(runtime: &Arc<Runtime>, pg_client: &Client, sql: &str) -> Result<(), Box<dyn Error>> {
+    runtime.block_on(pg_client.batch_execute(sql))?;
+    Ok(())
+}
+
+/// Fetches the next `n` updates from the `SUBSCRIBE` open as cursor `c`, as
+/// `(mz_diff, id, v)` tuples, sorted.
+fn fetch(mz_client: &mut postgres::Client, n: usize) -> Vec<(i64, i32, String)> {
+    let mut updates: Vec<_> = mz_client
+        .query(&format!("FETCH {n} c WITH (timeout = '60s')"), &[])
+        .unwrap()
+        .iter()
+        .map(|row| (row.get("mz_diff"), row.get("id"), row.get("v")))
+        .collect();
+    updates.sort();
+    updates
+}
+
+/// Returns the rows of `table`, as `(id, v)` tuples, ordered by `id`.
+fn select(mz_client: &mut postgres::Client, table: &str) -> Vec<(i32, String)> {
+    mz_client
+        .query(&format!("SELECT id, v FROM {table} ORDER BY id"), &[])
+        .unwrap()
+        .iter()
+        .map(|row| (row.get("id"), row.get("v")))
+        .collect()
+}
+
+/// Tests that the rows of the upstream table at the time the source is created
+/// are ingested by the snapshot, and that replication picks up exactly where
+/// the snapshot left off.
+#[test]
+fn test_postgres_source_snapshot() {
+    let server = util::start_server(util::Config::default()).unwrap();
+    let mut mz_client = server.connect(postgres::NoTls).unwrap();
+
+    let table_name = "pg_source_snapshot";
+    let source_name = "pg_source_snapshot_src";
+    let pg_client = util::connect_postgres_upstream(&server.runtime, &mut mz_client).unwrap();
+    upstream(
+        &server.runtime,
+        &pg_client,
+        &format!(
+            "DROP TABLE IF EXISTS {table_name};
+             DROP PUBLICATION IF EXISTS {source_name};
+             CREATE TABLE {table_name} (id INT PRIMARY KEY, v TEXT);
+             ALTER TABLE {table_name} REPLICA IDENTITY FULL;
+             INSERT INTO {table_name} SELECT i, 'v' || i FROM generate_series(1, 100) AS i;
+             CREATE PUBLICATION {source_name} FOR TABLE {table_name};"
+        ),
+    )
+    .unwrap();
+
+    mz_client
+        .batch_execute(&format!(
+            "CREATE SOURCE {source_name}
+                FROM POSTGRES
+                CONNECTION pgconn
+                (PUBLICATION '{source_name}')
+                FOR TABLES ({table_name});"
+        ))
+        .unwrap();
+
+    // The snapshot contains the rows that existed before the source was created.
+    util::wait_for_view_population(&mut mz_client, table_name, 100).unwrap();
+    let expected: Vec<_> = (1..=100).map(|i| (i, format!("v{i}"))).collect();
+    assert_eq!(select(&mut mz_client, table_name), expected);
+
+    // Changes made after the snapshot are replicated on top of it, without
+    // ingesting any snapshotted row a second time.
+    upstream(
+        &server.runtime,
+        &pg_client,
+        &format!("INSERT INTO {table_name} VALUES (101, 'v101');"),
+    )
+    .unwrap();
+    util::wait_for_view_population(&mut mz_client, table_name, 101).unwrap();
+    let expected: Vec<_> = (1..=101).map(|i| (i, format!("v{i}"))).collect();
+    assert_eq!(select(&mut mz_client, table_name), expected);
+
+    mz_client
+        .batch_execute(&format!(
+            "DROP SOURCE {source_name}; DROP CONNECTION pgconn;"
+        ))
+        .unwrap();
+    upstream(
+        &server.runtime,
+        &pg_client,
+        &format!("DROP PUBLICATION {source_name}; DROP TABLE {table_name};"),
+    )
+    .unwrap();
+}
+
+/// Tests that inserts, updates and deletes made upstream after the source was
+/// created are replicated as the corresponding updates, and that dropping the
+/// source removes it along with its subsources.
+#[test]
+fn test_postgres_source_replication() {
+    let server = util::start_server(util::Config::default()).unwrap();
+    let mut mz_client = server.connect(postgres::NoTls).unwrap();
+    let mut mz_reads = server.connect(postgres::NoTls).unwrap();
+
+    let table_name = "pg_source_replication";
+    let source_name = "pg_source_replication_src";
+    let (mut pg_client, cleanup_fn) = util::create_postgres_source_with_table(
+        &server.runtime,
+        &mut mz_client,
+        table_name,
+        "(id INT PRIMARY KEY, v TEXT)",
+        source_name,
+    )
+    .unwrap();
+
+    mz_reads
+        .batch_execute(&format!(
+            "BEGIN;
+             DECLARE c CURSOR FOR SUBSCRIBE {table_name};"
+        ))
+        .unwrap();
+
+    // Inserted rows are added.
+    upstream(
+        &server.runtime,
+        &pg_client,
+        &format!("INSERT INTO {table_name} VALUES (1, 'a'), (2, 'b'), (3, 'c');"),
+    )
+    .unwrap();
+    assert_eq!(
+        fetch(&mut mz_reads, 3),
+        vec![(1, 1, "a".into()), (1, 2, "b".into()), (1, 3, "c".into())]
+    );
+
+    // Updated rows are retracted and added with their new values.
+    upstream(
+        &server.runtime,
+        &pg_client,
+        &format!("UPDATE {table_name} SET v = 'bb' WHERE id = 2;"),
+    )
+    .unwrap();
+    assert_eq!(
+        fetch(&mut mz_reads, 2),
+        vec![(-1, 2, "b".into()), (1, 2, "bb".into())]
+    );
+
+    // Deleted rows are retracted.
+    upstream(
+        &server.runtime,
+        &pg_client,
+        &format!("DELETE FROM {table_name} WHERE id IN (1, 3);"),
+    )
+    .unwrap();
+    assert_eq!(
+        fetch(&mut mz_reads, 2),
+        vec![(-1, 1, "a".into()), (-1, 3, "c".into())]
+    );
+    mz_reads.batch_execute("COMMIT").unwrap();
+
+    assert_eq!(select(&mut mz_client, table_name), vec![(2, "bb".into())]);
+
+    cleanup_fn(&mut mz_client, &mut pg_client, &server.runtime).unwrap();
+    let remaining = mz_client
+        .query_one(
+            "SELECT count(*) FROM mz_sources WHERE name IN ($1, $2)",
+            &[&source_name, &table_name],
+        )
+        .unwrap()
+        .get::<_, i64>(0);
+    assert_eq!(remaining, 0);
+}
+
+/// Tests that `publication_info` only fetches the published tables its filter
+/// selects, and that a name selects the tables of that name in every schema.
+#[test]
+fn test_postgres_publication_filter() {
+    let server = util::start_server(util::Config::default()).unwrap();
+    let mut mz_client = server.connect(postgres::NoTls).unwrap();
+
+    let publication = "pg_publication_filter";
+    let pg_client = util::connect_postgres_upstream(&server.runtime, &mut mz_client).unwrap();
+    upstream(
+        &server.runtime,
+        &pg_client,
+        &format!(
+            "DROP PUBLICATION IF EXISTS {publication};
+             DROP SCHEMA IF EXISTS pf_a CASCADE;
+             DROP SCHEMA IF EXISTS pf_b CASCADE;
+             CREATE SCHEMA pf_a;
+             CREATE SCHEMA pf_b;
+             CREATE TABLE pf_a.filtered (id INT);
+             CREATE TABLE pf_b.filtered (id INT);
+             CREATE TABLE pf_a.other (id INT);
+             CREATE TABLE pf_b.unpublished (id INT);
+             CREATE PUBLICATION {publication}
+                FOR TABLE pf_a.filtered, pf_b.filtered, pf_a.other;"
+        ),
+    )
+    .unwrap();
+
+    let publications = [publication.to_string()];
+    let tables = |filter: PublicationFilter| -> Vec<(String, String)> {
+        let tables = server
+            .runtime
+            .block_on(mz_postgres_util::client_publication_info(
+                &pg_client,
+                &publications,
+                &filter,
+            ))
+            .unwrap();
+        let mut tables: Vec<_> = tables
+            .into_iter()
+            .map(|table| (table.namespace, table.name))
+            .collect();
+        tables.sort();
+        tables
+    };
+    let table = |namespace: &str, name: &str| (namespace.to_string(), name.to_string());
+
+    assert_eq!(
+        tables(PublicationFilter::All),
+        vec![
+            table("pf_a", "filtered"),
+            table("pf_a", "other"),
+            table("pf_b", "filtered"),
+        ]
+    );
+
+    let oid: u32 = server
+        .runtime
+        .block_on(pg_client.query_one("SELECT 'pf_b.filtered'::regclass::oid", &[]))
+        .unwrap()
+        .get(0);
+    assert_eq!(
+        tables(PublicationFilter::ByOid(oid)),
+        vec![table("pf_b", "filtered")]
+    );
+
+    assert_eq!(
+        tables(PublicationFilter::ByName("filtered".into())),
+        vec![table("pf_a", "filtered"), table("pf_b", "filtered")]
+    );
+    assert_eq!(
+        tables(PublicationFilter::ByName("other".into())),
+        vec![table("pf_a", "other")]
+    );
+    // Tables that exist but aren't published are not selected.
+    assert_eq!(
+        tables(PublicationFilter::ByName("unpublished".into())),
+        vec![]
+    );
+
+    upstream(
+        &server.runtime,
+        &pg_client,
+        &format!(
+            "DROP PUBLICATION {publication};
+             DROP SCHEMA pf_a CASCADE;
+             DROP SCHEMA pf_b CASCADE;"
+        ),
+    )
+    .unwrap();
+}
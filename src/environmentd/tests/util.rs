@@ -534,27 +534,12 @@ pub fn get_explain_timestamp(table: &str, client: &mut postgres::Client) -> Epoc
     timestamp_caps.get(1).unwrap().as_str().parse().unwrap()
 }
 
-/// Helper function to create a Postgres source.
-///
-/// IMPORTANT: Make sure to call closure that is returned at
-/// the end of the test to clean up Postgres state.
-///
-/// WARNING: If multiple tests use this, and the tests are run
-/// in parallel, then make sure the test use different postgres
-/// tables.
-pub fn create_postgres_source_with_table(
+/// Helper function to connect to the Postgres server at `POSTGRES_URL`, and to
+/// create a connection named `pgconn` to it in Materialize.
+pub fn connect_postgres_upstream(
     runtime: &Arc<Runtime>,
     mz_client: &mut postgres::Client,
-    table_name: &str,
-    table_schema: &str,
-    source_name: &str,
-) -> Result<
-    (
-        Client,
-        impl FnOnce(&mut postgres::Client, &mut Client, &Arc<Runtime>) -> Result<(), Box<dyn Error>>,
-    ),
-    Box<dyn Error>,
-> {
+) -> Result<Client, Box<dyn Error>> {
     let postgres_url = env::var("POSTGRES_URL")
         .map_err(|_| anyhow!("POSTGRES_URL environment variable is not set"))?;
 
@@ -584,6 +569,42 @@ pub fn create_postgres_source_with_table(
         }
     });
 
+    let mut connection_str = format!("HOST '{host}', PORT {port}, USER {user}, DATABASE {db_name}");
+    if let Some(password) = password {
+        let password = std::str::from_utf8(password).unwrap();
+        mz_client.batch_execute(&format!("CREATE SECRET s AS '{password}'"))?;
+        connection_str = format!("{connection_str}, PASSWORD SECRET s");
+    }
+    mz_client.batch_execute(&format!(
+        "CREATE CONNECTION pgconn TO POSTGRES ({connection_str})"
+    ))?;
+
+    Ok(pg_client)
+}
+
+/// Helper function to create a Postgres source.
+///
+/// IMPORTANT: Make sure to call closure that is returned at
+/// the end of the test to clean up Postgres state.
+///
+/// WARNING: If multiple tests use this, and the tests are run
+/// in parallel, then make sure the test use different postgres
+/// tables.
+pub fn create_postgres_source_with_table(
+    runtime: &Arc<Runtime>,
+    mz_client: &mut postgres::Client,
+    table_name: &str,
+    table_schema: &str,
+    source_name: &str,
+) -> Result<
+    (
+        Client,
+        impl FnOnce(&mut postgres::Client, &mut Client, &Arc<Runtime>) -> Result<(), Box<dyn Error>>,
+    ),
+    Box<dyn Error>,
+> {
+    let pg_client = connect_postgres_upstream(runtime, mz_client)?;
+
     // Create table in Postgres with publication.
     let _ =
         runtime.block_on(pg_client.execute(&format!("DROP TABLE IF EXISTS {table_name};"), &[]))?;
@@ -601,15 +622,6 @@ pub fn create_postgres_source_with_table(
     ))?;
 
     // Create postgres source in Materialize.
-    let mut connection_str = format!("HOST '{host}', PORT {port}, USER {user}, DATABASE {db_name}");
-    if let Some(password) = password {
-        let password = std::str::from_utf8(password).unwrap();
-        mz_client.batch_execute(&format!("CREATE SECRET s AS '{password}'"))?;
-        connection_str = format!("{connection_str}, PASSWORD SECRET s");
-    }
-    mz_client.batch_execute(&format!(
-        "CREATE CONNECTION pgconn TO POSTGRES ({connection_str})"
-    ))?;
     mz_client.batch_execute(&format!(
         "CREATE SOURCE {source_name}
             FROM POSTGRES
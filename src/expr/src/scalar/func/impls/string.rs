@@ -255,7 +255,7 @@ impl LazyUnaryFunc for CastStringToArray {
         if a.is_null() {
             return Ok(Datum::Null);
         }
-        let datums = strconv::parse_array(
+        let (dims, datums) = strconv::parse_array(
             a.unwrap_str(),
             || Datum::Null,
             |elem_text| {
@@ -267,7 +267,8 @@ impl LazyUnaryFunc for CastStringToArray {
                     .eval(&[Datum::String(elem_text)], temp_storage)
             },
         )?;
-        array_create_scalar(&datums, temp_storage)
+        let datum = temp_storage.try_make_datum(|packer| packer.push_array(&dims, &datums))?;
+        Ok(datum)
     }
 
     /// The output ColumnType of this function
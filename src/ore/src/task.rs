@@ -17,14 +17,15 @@
 //!
 //! ## Named task spawning
 //!
-//! The [`spawn`] and [`spawn_blocking`] methods are wrappers around
-//! [`tokio::task::spawn`] and [`tokio::task::spawn_blocking`] that attach a
-//! name the spawned task.
+//! The [`spawn`], [`spawn_local`] and [`spawn_blocking`] methods are wrappers
+//! around [`tokio::task::spawn`], [`tokio::task::spawn_local`] and
+//! [`tokio::task::spawn_blocking`] that attach a name the spawned task.
 //!
 //! If Clippy sent you here, replace:
 //!
 //! ```ignore
 //! tokio::task::spawn(my_future)
+//! tokio::task::spawn_local(my_future)
 //! tokio::task::spawn_blocking(my_blocking_closure)
 //! ```
 //!
@@ -32,6 +33,7 @@
 //!
 //! ```ignore
 //! mz_ore::task::spawn(|| format!("taskname:{}", info), my_future)
+//! mz_ore::task::spawn_local(|| format!("taskname:{}", info), my_future)
 //! mz_ore::task::spawn_blocking(|| format!("name:{}", info), my_blocking_closure)
 //! ```
 //!
@@ -105,6 +107,57 @@ where
         .expect("task spawning cannot fail")
 }
 
+/// Spawns a new asynchronous task with a name onto the current
+/// [`LocalSet`](tokio::task::LocalSet), which runs it on the thread it is
+/// spawned from. Unlike with [`spawn`], the future does not need to be
+/// [`Send`].
+///
+/// See [`tokio::task::spawn_local`] and the [module][`self`] docs for more
+/// information.
+///
+/// # Panics
+///
+/// Panics if called outside of a [`LocalSet`](tokio::task::LocalSet).
+#[cfg(not(tokio_unstable))]
+#[track_caller]
+pub fn spawn_local<Fut, Name, NameClosure>(_nc: NameClosure, future: Fut) -> JoinHandle<Fut::Output>
+where
+    Name: AsRef<str>,
+    NameClosure: FnOnce() -> Name,
+    Fut: Future + 'static,
+    Fut::Output: 'static,
+{
+    #[allow(clippy::disallowed_methods)]
+    task::spawn_local(future)
+}
+
+/// Spawns a new asynchronous task with a name onto the current
+/// [`LocalSet`](tokio::task::LocalSet), which runs it on the thread it is
+/// spawned from. Unlike with [`spawn`], the future does not need to be
+/// [`Send`].
+///
+/// See [`tokio::task::spawn_local`] and the [module][`self`] docs for more
+/// information.
+///
+/// # Panics
+///
+/// Panics if called outside of a [`LocalSet`](tokio::task::LocalSet).
+#[cfg(tokio_unstable)]
+#[track_caller]
+pub fn spawn_local<Fut, Name, NameClosure>(nc: NameClosure, future: Fut) -> JoinHandle<Fut::Output>
+where
+    Name: AsRef<str>,
+    NameClosure: FnOnce() -> Name,
+    Fut: Future + 'static,
+    Fut::Output: 'static,
+{
+    #[allow(clippy::disallowed_methods)]
+    task::Builder::new()
+        .name(nc().as_ref())
+        .spawn_local(future)
+        .expect("task spawning cannot fail")
+}
+
 /// Runs the provided closure with a name on a thread where blocking is
 /// acceptable.
 ///
@@ -0,0 +1,428 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+// BEGIN LINT CONFIG
+// DO NOT EDIT. Automatically generated by bin/gen-lints.
+// Have complaints about the noise? See the note in misc/python/materialize/cli/gen-lints.py first.
+#![allow(clippy::style)]
+#![allow(clippy::complexity)]
+#![allow(clippy::large_enum_variant)]
+#![allow(clippy::mutable_key_type)]
+#![allow(clippy::stable_sort_primitive)]
+#![allow(clippy::map_entry)]
+#![allow(clippy::box_default)]
+#![warn(clippy::bool_comparison)]
+#![warn(clippy::clone_on_ref_ptr)]
+#![warn(clippy::no_effect)]
+#![warn(clippy::unnecessary_unwrap)]
+#![warn(clippy::dbg_macro)]
+#![warn(clippy::todo)]
+#![warn(clippy::wildcard_dependencies)]
+#![warn(clippy::zero_prefixed_literal)]
+#![warn(clippy::borrowed_box)]
+#![warn(clippy::deref_addrof)]
+#![warn(clippy::double_must_use)]
+#![warn(clippy::double_parens)]
+#![warn(clippy::extra_unused_lifetimes)]
+#![warn(clippy::needless_borrow)]
+#![warn(clippy::needless_question_mark)]
+#![warn(clippy::needless_return)]
+#![warn(clippy::redundant_pattern)]
+#![warn(clippy::redundant_slicing)]
+#![warn(clippy::redundant_static_lifetimes)]
+#![warn(clippy::single_component_path_imports)]
+#![warn(clippy::unnecessary_cast)]
+#![warn(clippy::useless_asref)]
+#![warn(clippy::useless_conversion)]
+#![warn(clippy::builtin_type_shadow)]
+#![warn(clippy::duplicate_underscore_argument)]
+#![warn(clippy::double_neg)]
+#![warn(clippy::unnecessary_mut_passed)]
+#![warn(clippy::wildcard_in_or_patterns)]
+#![warn(clippy::collapsible_if)]
+#![warn(clippy::collapsible_else_if)]
+#![warn(clippy::crosspointer_transmute)]
+#![warn(clippy::excessive_precision)]
+#![warn(clippy::overflow_check_conditional)]
+#![warn(clippy::as_conversions)]
+#![warn(clippy::match_overlapping_arm)]
+#![warn(clippy::zero_divided_by_zero)]
+#![warn(clippy::must_use_unit)]
+#![warn(clippy::suspicious_assignment_formatting)]
+#![warn(clippy::suspicious_else_formatting)]
+#![warn(clippy::suspicious_unary_op_formatting)]
+#![warn(clippy::mut_mutex_lock)]
+#![warn(clippy::print_literal)]
+#![warn(clippy::same_item_push)]
+#![warn(clippy::useless_format)]
+#![warn(clippy::write_literal)]
+#![warn(clippy::redundant_closure)]
+#![warn(clippy::redundant_closure_call)]
+#![warn(clippy::unnecessary_lazy_evaluations)]
+#![warn(clippy::partialeq_ne_impl)]
+#![warn(clippy::redundant_field_names)]
+#![warn(clippy::transmutes_expressible_as_ptr_casts)]
+#![warn(clippy::unused_async)]
+#![warn(clippy::disallowed_methods)]
+#![warn(clippy::disallowed_macros)]
+#![warn(clippy::disallowed_types)]
+
+//! Debug utility for PostgreSQL sources.
+
+use std::process;
+
+use anyhow::bail;
+use clap::Parser;
+use once_cell::sync::Lazy;
+use serde_json::json;
+use tokio_postgres::types::PgLsn;
+
+use mz_build_info::{build_info, BuildInfo};
+use mz_ore::cli::{self, CliConfig};
+use mz_postgres_util::desc::PostgresTableDesc;
+use mz_postgres_util::validation::ValidationIssue;
+use mz_postgres_util::TunnelConfig;
+use mz_repr::{Datum, Diff, Row};
+use mz_storage::source::DryRunReport;
+
+pub const BUILD_INFO: BuildInfo = build_info!();
+pub static VERSION: Lazy<String> = Lazy::new(|| BUILD_INFO.human_version());
+
+#[derive(Parser, Debug)]
+#[clap(name = "pg-debug", next_line_help = true, version = VERSION.as_str())]
+pub struct Args {
+    /// The URL of the upstream PostgreSQL database.
+    #[clap(long, env = "POSTGRES_URL")]
+    postgres_url: String,
+
+    #[clap(subcommand)]
+    action: Action,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum Action {
+    /// Prints the changes a source would ingest from a range of the replication
+    /// stream as JSON, one change per line.
+    ///
+    /// The changes are read from a temporary copy of the slot, which is
+    /// dropped with the connection that reads it, so that the source using
+    /// the slot is unaffected. Values are printed in their text
+    /// representation.
+    Replay {
+        /// The replication slot of the source.
+        #[clap(long)]
+        slot: String,
+        /// The publication the source replicates.
+        #[clap(long)]
+        publication: String,
+        /// Only replay the changes to the published tables of this name, in
+        /// any schema.
+        #[clap(long)]
+        table: Option<String>,
+        /// The LSN to replay changes from, which must not precede the
+        /// confirmed flush LSN of the slot.
+        #[clap(long)]
+        from_lsn: PgLsn,
+        /// The LSN to replay changes up to, inclusive.
+        #[clap(long)]
+        to_lsn: PgLsn,
+    },
+    /// Checks whether a source could ingest every table of a publication,
+    /// with all columns as text, and estimates the size of its snapshot.
+    ///
+    /// Nothing is written upstream, and no replication slot is created. The
+    /// report is printed as JSON, and the command fails if the source would.
+    DryRun {
+        /// The publication the source would replicate.
+        #[clap(long)]
+        publication: String,
+    },
+}
+
+#[tokio::main]
+async fn main() {
+    let args = cli::parse_args(CliConfig {
+        env_prefix: Some("MZ_PG_DEBUG_"),
+        enable_version_flag: true,
+    });
+    if let Err(err) = run(args).await {
+        eprintln!("pg-debug: {:#}", err);
+        process::exit(1);
+    }
+}
+
+async fn run(args: Args) -> Result<(), anyhow::Error> {
+    let config = mz_postgres_util::Config::new(args.postgres_url.parse()?, TunnelConfig::Direct)?;
+    match args.action {
+        Action::Replay {
+            slot,
+            publication,
+            table,
+            from_lsn,
+            to_lsn,
+        } => {
+            mz_storage::source::replay_replication(
+                config,
+                &slot,
+                &publication,
+                table.as_deref(),
+                from_lsn,
+                to_lsn,
+                |lsn, desc, row, diff| println!("{}", change_json(lsn, desc, &row, diff)),
+            )
+            .await
+        }
+        Action::DryRun { publication } => {
+            let report =
+                mz_storage::source::dry_run_postgres_publication(&config, &publication).await?;
+            println!("{}", dry_run_json(&report));
+            if report.has_errors() {
+                bail!("the source would fail");
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Renders a replayed change to the table `desc` as JSON.
+fn change_json(lsn: PgLsn, desc: &PostgresTableDesc, row: &Row, diff: Diff) -> serde_json::Value {
+    let values: serde_json::Map<_, _> = desc
+        .columns
+        .iter()
+        .zip(row.iter())
+        .map(|(column, datum)| {
+            let value = match datum {
+                Datum::String(text) => json!(text),
+                _ => json!(null),
+            };
+            (column.name.clone(), value)
+        })
+        .collect();
+    json!({
+        "lsn": lsn.to_string(),
+        "table": format!("{}.{}", desc.namespace, desc.name),
+        "diff": diff,
+        "row": values,
+    })
+}
+
+/// Renders the report of a dry run as JSON.
+fn dry_run_json(report: &DryRunReport) -> serde_json::Value {
+    let tables: serde_json::Map<_, _> = report
+        .tables
+        .iter()
+        .map(|(name, table)| {
+            let table = json!({
+                "estimated_rows": table.estimated_rows,
+                "estimated_bytes": table.estimated_bytes,
+                "cast_error": table.cast_error,
+            });
+            (name.clone(), table)
+        })
+        .collect();
+    let issues = |issues: Vec<&ValidationIssue>| -> Vec<String> {
+        issues.into_iter().map(|issue| issue.to_string()).collect()
+    };
+    json!({
+        "errors": issues(report.validation.errors().collect()),
+        "warnings": issues(report.validation.warnings().collect()),
+        "ingestion_error": report.ingestion_error,
+        "estimated_bytes": report.estimated_bytes(),
+        "tables": tables,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use mz_postgres_util::desc::{PostgresColumnDesc, ReplicaIdentity};
+    use mz_postgres_util::validation::ValidationReport;
+    use mz_storage::source::DryRunTable;
+
+    use super::*;
+
+    #[test]
+    fn parse_replay_args() {
+        let args = Args::try_parse_from([
+            "pg-debug",
+            "--postgres-url",
+            "postgres://postgres@localhost/postgres",
+            "replay",
+            "--slot",
+            "materialize_u1",
+            "--publication",
+            "mz_source",
+            "--from-lsn",
+            "0/16B3748",
+            "--to-lsn",
+            "0/16B3800",
+        ])
+        .unwrap();
+        match args.action {
+            Action::Replay {
+                slot,
+                publication,
+                table,
+                from_lsn,
+                to_lsn,
+            } => {
+                assert_eq!(slot, "materialize_u1");
+                assert_eq!(publication, "mz_source");
+                assert_eq!(table, None);
+                assert_eq!(from_lsn, PgLsn::from(0x16B3748));
+                assert_eq!(to_lsn, PgLsn::from(0x16B3800));
+            }
+            action => panic!("unexpected action {action:?}"),
+        }
+
+        let args = Args::try_parse_from([
+            "pg-debug",
+            "--postgres-url",
+            "postgres://postgres@localhost/postgres",
+            "replay",
+            "--slot",
+            "materialize_u1",
+            "--publication",
+            "mz_source",
+            "--table",
+            "orders",
+            "--from-lsn",
+            "0/16B3748",
+            "--to-lsn",
+            "0/16B3800",
+        ])
+        .unwrap();
+        match args.action {
+            Action::Replay { table, .. } => assert_eq!(table.as_deref(), Some("orders")),
+            action => panic!("unexpected action {action:?}"),
+        }
+
+        // Both ends of the range are required.
+        assert!(Args::try_parse_from([
+            "pg-debug",
+            "--postgres-url",
+            "postgres://postgres@localhost/postgres",
+            "replay",
+            "--slot",
+            "materialize_u1",
+            "--publication",
+            "mz_source",
+            "--from-lsn",
+            "0/16B3748",
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn changes_as_json() {
+        let column = |name: &str| PostgresColumnDesc {
+            name: name.to_string(),
+            col_num: None,
+            type_oid: 25,
+            type_mod: -1,
+            nullable: true,
+            domain_constraints: vec![],
+            default_expr: None,
+        };
+        let desc = PostgresTableDesc {
+            oid: 16384,
+            namespace: "public".to_string(),
+            name: "t1".to_string(),
+            columns: vec![column("f1"), column("f2")],
+            keys: Default::default(),
+            replica_identity: Default::default(),
+            identity_column_names: vec![],
+        };
+        let row = Row::pack_slice(&[Datum::String("1"), Datum::Null]);
+        assert_eq!(
+            change_json(PgLsn::from(0x16B3748), &desc, &row, -1),
+            json!({
+                "lsn": "0/16B3748",
+                "table": "public.t1",
+                "diff": -1,
+                "row": {"f1": "1", "f2": null},
+            })
+        );
+    }
+
+    #[test]
+    fn dry_run_reports_as_json() {
+        let args = Args::try_parse_from([
+            "pg-debug",
+            "--postgres-url",
+            "postgres://postgres@localhost/postgres",
+            "dry-run",
+            "--publication",
+            "mz_source",
+        ])
+        .unwrap();
+        match args.action {
+            Action::DryRun { publication } => assert_eq!(publication, "mz_source"),
+            action => panic!("unexpected action {action:?}"),
+        }
+
+        let report = DryRunReport {
+            validation: ValidationReport {
+                issues: vec![
+                    ValidationIssue::WalLevel {
+                        wal_level: "replica".into(),
+                    },
+                    ValidationIssue::ReplicaIdentityNotFull {
+                        table: "public.t1".into(),
+                        replica_identity: ReplicaIdentity::Default,
+                    },
+                ],
+            },
+            ingestion_error: None,
+            tables: BTreeMap::from([
+                (
+                    "public.t1".to_string(),
+                    DryRunTable {
+                        estimated_rows: Some(10),
+                        estimated_bytes: Some(8192),
+                        cast_error: None,
+                    },
+                ),
+                (
+                    "public.t2".to_string(),
+                    DryRunTable {
+                        estimated_rows: None,
+                        estimated_bytes: Some(16384),
+                        cast_error: Some("failed to cast".into()),
+                    },
+                ),
+            ]),
+        };
+        assert_eq!(
+            dry_run_json(&report),
+            json!({
+                "errors": ["wal_level is replica, but must be logical"],
+                "warnings": [
+                    "table public.t1 has REPLICA IDENTITY DEFAULT, but updates and deletes \
+                    require REPLICA IDENTITY FULL"
+                ],
+                "ingestion_error": null,
+                "estimated_bytes": 24576,
+                "tables": {
+                    "public.t1": {
+                        "estimated_rows": 10,
+                        "estimated_bytes": 8192,
+                        "cast_error": null,
+                    },
+                    "public.t2": {
+                        "estimated_rows": null,
+                        "estimated_bytes": 16384,
+                        "cast_error": "failed to cast",
+                    },
+                },
+            })
+        );
+    }
+}
@@ -334,6 +334,16 @@ impl<'a> CopyTextFormatParser<'a> {
             truncate: true,
         }
     }
+
+    /// Return no more than `num_columns` values from `parser`, like
+    /// [`CopyTextFormatParser::iter_raw_truncating`], for data that is known
+    /// to contain at least `num_columns` values.
+    ///
+    /// The iterator returns an error if the data turns out to contain fewer
+    /// than `num_columns` values.
+    pub fn iter_raw_with_nulls(self, num_columns: usize) -> RawWithNullsIterator<'a> {
+        RawWithNullsIterator(self.iter_raw_truncating(num_columns))
+    }
 }
 
 pub struct RawIterator<'a> {
@@ -370,6 +380,19 @@ impl<'a> RawIterator<'a> {
     }
 }
 
+/// An iterator over values that are expected to be present, returned by
+/// [`CopyTextFormatParser::iter_raw_with_nulls`].
+///
+/// Like [`RawIterator`], this does not implement [`Iterator`], as unescaped
+/// values are returned from a buffer that is reused for the next value.
+pub struct RawWithNullsIterator<'a>(RawIterator<'a>);
+
+impl<'a> RawWithNullsIterator<'a> {
+    pub fn next(&mut self) -> Option<Result<Option<&[u8]>, io::Error>> {
+        self.0.next()
+    }
+}
+
 #[derive(Debug)]
 pub enum CopyFormatParams<'a> {
     Text(CopyTextFormatParams<'a>),
@@ -600,6 +623,23 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_copy_format_text_iter_raw_with_nulls() {
+        let text = "1\\tb\t\\N\textra\n".as_bytes();
+        let mut values = CopyTextFormatParser::new(text, "\t", "\\N").iter_raw_with_nulls(2);
+        assert_eq!(values.next().unwrap().unwrap(), Some("1\tb".as_bytes()));
+        assert_eq!(values.next().unwrap().unwrap(), None);
+        assert!(values.next().is_none());
+    }
+
+    #[test]
+    fn test_copy_format_text_iter_raw_with_nulls_missing_values() {
+        let text = "1\n".as_bytes();
+        let mut values = CopyTextFormatParser::new(text, "\t", "\\N").iter_raw_with_nulls(2);
+        assert_eq!(values.next().unwrap().unwrap(), Some("1".as_bytes()));
+        assert!(matches!(values.next(), Some(Err(_))));
+    }
+
     #[test]
     fn test_copy_format_text_parser_escapes() {
         struct TestCase {
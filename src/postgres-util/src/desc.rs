@@ -12,13 +12,14 @@
 use std::collections::BTreeSet;
 
 use anyhow::bail;
-use proptest::prelude::{any, Arbitrary};
+use proptest::prelude::{any, prop_oneof, Arbitrary, Just};
 use proptest::strategy::{BoxedStrategy, Strategy};
 use serde::{Deserialize, Serialize};
 use tokio_postgres::types::Oid;
 use tracing::warn;
 
 use mz_proto::{RustType, TryFromProtoError};
+use mz_repr::ScalarType;
 
 include!(concat!(env!("OUT_DIR"), "/mz_postgres_util.desc.rs"));
 
@@ -36,21 +37,76 @@ pub struct PostgresTableDesc {
     /// Applicable keys for this table (i.e. primary key and unique
     /// constraints).
     pub keys: BTreeSet<PostgresKeyDesc>,
+    /// The table's `REPLICA IDENTITY` setting, which determines which columns
+    /// of the old row upstream includes in `UPDATE` and `DELETE` messages.
+    #[serde(default)]
+    pub replica_identity: ReplicaIdentity,
+    /// The names of the columns of the index backing the replica identity, in
+    /// index order. Empty unless `replica_identity` is
+    /// [`ReplicaIdentity::Default`] with a primary key, or
+    /// [`ReplicaIdentity::Index`].
+    #[serde(default)]
+    pub identity_column_names: Vec<String>,
 }
 
 impl PostgresTableDesc {
+    /// Returns the columns that upstream uses to identify a row in `UPDATE`
+    /// and `DELETE` messages, i.e. the columns of its `REPLICA IDENTITY`.
+    ///
+    /// With `REPLICA IDENTITY FULL` these are all of the table's columns; with
+    /// `REPLICA IDENTITY NOTHING`, or `REPLICA IDENTITY DEFAULT` on a table
+    /// without a primary key, there are none.
+    pub fn row_identity_columns(&self) -> Vec<&PostgresColumnDesc> {
+        match self.replica_identity {
+            ReplicaIdentity::Full => self.columns.iter().collect(),
+            ReplicaIdentity::Nothing => vec![],
+            ReplicaIdentity::Default | ReplicaIdentity::Index => self
+                .identity_column_names
+                .iter()
+                .filter_map(|name| self.columns.iter().find(|c| &c.name == name))
+                .collect(),
+        }
+    }
+
+    /// Returns the position among `other`'s columns of each of `self`'s
+    /// columns, or `None` if any of them is missing from `other`.
+    ///
+    /// Columns are matched by their `col_num`, which identifies them even if
+    /// they are no longer at the same position, e.g. because a column before
+    /// them was dropped and added again. Columns without a `col_num` are
+    /// matched by their position.
+    pub fn column_positions(&self, other: &PostgresTableDesc) -> Option<Vec<usize>> {
+        self.columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| match column.col_num {
+                Some(col_num) => other
+                    .columns
+                    .iter()
+                    .position(|c| c.col_num == Some(col_num)),
+                None => (i < other.columns.len()).then_some(i),
+            })
+            .collect()
+    }
+
     /// Determines if two `PostgresTableDesc` are compatible with one another in
     /// a way that Materialize can handle.
     ///
     /// Currently this means that the values are equal except for the following
     /// exceptions:
-    /// - `self`'s columns are a compatible prefix of `other`'s columns.
-    ///   Compatibility is defined as returning `true` for
-    ///   `PostgresColumnDesc::is_compatible`.
+    /// - `self`'s columns are all present in `other`, as per
+    ///   [`PostgresTableDesc::column_positions`], and compatible with the
+    ///   columns they correspond to. Compatibility is defined as returning
+    ///   `true` for `PostgresColumnDesc::is_compatible`, which allows `other`'s
+    ///   columns to have widened types, in which case this returns
+    ///   [`Compatibility::WidenColumn`].
     /// - `self`'s keys are all present in `other`
-    pub fn determine_compatibility(&self, other: &PostgresTableDesc) -> Result<(), anyhow::Error> {
+    pub fn determine_compatibility(
+        &self,
+        other: &PostgresTableDesc,
+    ) -> Result<Compatibility, anyhow::Error> {
         if self == other {
-            return Ok(());
+            return Ok(Compatibility::Compatible);
         }
 
         let PostgresTableDesc {
@@ -59,19 +115,41 @@ impl PostgresTableDesc {
             name: other_name,
             columns: other_cols,
             keys: other_keys,
+            // The replica identity only determines which values upstream sends
+            // for the old row of an update, which doesn't affect how we decode
+            // the new one.
+            replica_identity: _,
+            identity_column_names: _,
         } = other;
 
-        // Table columns cannot change position, so only need to ensure that
-        // `self.columns` is a prefix of `other_cols`.
-        if self.columns.len() <= other_cols.len()
-            && self.columns.iter().zip(other_cols.iter()).all(|(s, o)| s.is_compatible(o))
+        // The columns of `other` that correspond to ours, which are usually a
+        // prefix of its columns.
+        let matched: Vec<_> = self
+            .column_positions(other)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|i| &other_cols[i])
+            .collect();
+        if matched.len() == self.columns.len()
+            && self.columns.iter().zip(&matched).all(|(s, o)| s.is_compatible(o))
             && &self.name == other_name
             && &self.oid == other_oid
             && &self.namespace == other_namespace
             // Our keys are all still present in exactly the same shape.
             && self.keys.difference(other_keys).next().is_none()
         {
-            Ok(())
+            let widened: Vec<_> = self
+                .columns
+                .iter()
+                .zip(matched)
+                .filter(|(s, o)| s.type_oid != o.type_oid || s.type_mod != o.type_mod)
+                .map(|(s, _)| s.name.clone())
+                .collect();
+            if widened.is_empty() {
+                Ok(Compatibility::Compatible)
+            } else {
+                Ok(Compatibility::WidenColumn { columns: widened })
+            }
         } else {
             warn!(
                 "Error validating table in publication. Expected: {:?} Actual: {:?}",
@@ -86,6 +164,46 @@ impl PostgresTableDesc {
     }
 }
 
+/// How a table's upstream description relates to the one it is ingested with,
+/// as determined by [`PostgresTableDesc::determine_compatibility`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Compatibility {
+    /// The table can be ingested as before.
+    Compatible,
+    /// The table can be ingested as before, but the types of these columns
+    /// were widened upstream, as per [`can_widen`]. They are still ingested as
+    /// the same type, which holds all of their values.
+    WidenColumn {
+        /// The names of the widened columns.
+        columns: Vec<String>,
+    },
+}
+
+/// Returns whether altering a column's type from `from` to `to` only widens the
+/// range of values it can hold, without changing the type it is ingested as,
+/// e.g. from `numeric(10,2)` to `numeric(12,2)`. The values of the new type
+/// then still decode into the existing one.
+///
+/// A widening that changes the type a column is ingested as, like from
+/// `integer` to `bigint` or from `varchar(10)` to `varchar(20)`, is not
+/// accepted, as values that only fit the new type would fail to decode.
+///
+/// The element types of arrays and ranges are never widened.
+pub fn can_widen(from: &mz_pgrepr::Type, to: &mz_pgrepr::Type) -> bool {
+    use mz_pgrepr::Type::*;
+
+    match (from, to) {
+        (Array(_) | Range { .. }, _) | (_, Array(_) | Range { .. }) => false,
+        _ => {
+            from != to
+                && match (ScalarType::try_from(from), ScalarType::try_from(to)) {
+                    (Ok(from), Ok(to)) => from == to,
+                    _ => false,
+                }
+        }
+    }
+}
+
 impl RustType<ProtoPostgresTableDesc> for PostgresTableDesc {
     fn into_proto(&self) -> ProtoPostgresTableDesc {
         ProtoPostgresTableDesc {
@@ -94,6 +212,8 @@ impl RustType<ProtoPostgresTableDesc> for PostgresTableDesc {
             name: self.name.clone(),
             columns: self.columns.iter().map(|c| c.into_proto()).collect(),
             keys: self.keys.iter().map(PostgresKeyDesc::into_proto).collect(),
+            replica_identity: self.replica_identity.into_proto().into(),
+            identity_column_names: self.identity_column_names.clone(),
         }
     }
 
@@ -112,6 +232,15 @@ impl RustType<ProtoPostgresTableDesc> for PostgresTableDesc {
                 .into_iter()
                 .map(PostgresKeyDesc::from_proto)
                 .collect::<Result<_, _>>()?,
+            replica_identity: ProtoReplicaIdentity::from_i32(proto.replica_identity)
+                .ok_or_else(|| {
+                    TryFromProtoError::unknown_enum_variant(format!(
+                        "ProtoReplicaIdentity::{}",
+                        proto.replica_identity
+                    ))
+                })?
+                .into_rust()?,
+            identity_column_names: proto.identity_column_names,
         })
     }
 }
@@ -127,18 +256,100 @@ impl Arbitrary for PostgresTableDesc {
             any::<u32>(),
             any::<Vec<PostgresColumnDesc>>(),
             any::<BTreeSet<PostgresKeyDesc>>(),
+            any::<ReplicaIdentity>(),
+            any::<Vec<String>>(),
         )
-            .prop_map(|(name, namespace, oid, columns, keys)| PostgresTableDesc {
-                name,
-                namespace,
-                oid,
-                columns,
-                keys,
-            })
+            .prop_map(
+                |(name, namespace, oid, columns, keys, replica_identity, identity_column_names)| {
+                    PostgresTableDesc {
+                        name,
+                        namespace,
+                        oid,
+                        columns,
+                        keys,
+                        replica_identity,
+                        identity_column_names,
+                    }
+                },
+            )
             .boxed()
     }
 }
 
+/// The `REPLICA IDENTITY` of a [`PostgresTableDesc`]; see
+/// <https://www.postgresql.org/docs/current/sql-altertable.html#SQL-ALTERTABLE-REPLICA-IDENTITY>
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ReplicaIdentity {
+    /// The columns of the primary key, if any.
+    #[default]
+    Default,
+    /// All columns.
+    Full,
+    /// The columns of a specific unique index.
+    Index,
+    /// No columns.
+    Nothing,
+}
+
+impl ReplicaIdentity {
+    /// Returns the identity corresponding to a `pg_class.relreplident` value.
+    pub fn from_relreplident(relreplident: &str) -> Option<Self> {
+        match relreplident {
+            "d" => Some(ReplicaIdentity::Default),
+            "f" => Some(ReplicaIdentity::Full),
+            "i" => Some(ReplicaIdentity::Index),
+            "n" => Some(ReplicaIdentity::Nothing),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the identity as used in `ALTER TABLE ... REPLICA
+    /// IDENTITY`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReplicaIdentity::Default => "default",
+            ReplicaIdentity::Full => "full",
+            ReplicaIdentity::Index => "index",
+            ReplicaIdentity::Nothing => "nothing",
+        }
+    }
+}
+
+impl Arbitrary for ReplicaIdentity {
+    type Strategy = BoxedStrategy<Self>;
+    type Parameters = ();
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        prop_oneof![
+            Just(ReplicaIdentity::Default),
+            Just(ReplicaIdentity::Full),
+            Just(ReplicaIdentity::Index),
+            Just(ReplicaIdentity::Nothing),
+        ]
+        .boxed()
+    }
+}
+
+impl RustType<ProtoReplicaIdentity> for ReplicaIdentity {
+    fn into_proto(&self) -> ProtoReplicaIdentity {
+        match self {
+            ReplicaIdentity::Default => ProtoReplicaIdentity::Default,
+            ReplicaIdentity::Full => ProtoReplicaIdentity::Full,
+            ReplicaIdentity::Index => ProtoReplicaIdentity::Index,
+            ReplicaIdentity::Nothing => ProtoReplicaIdentity::Nothing,
+        }
+    }
+
+    fn from_proto(proto: ProtoReplicaIdentity) -> Result<Self, TryFromProtoError> {
+        Ok(match proto {
+            ProtoReplicaIdentity::Default => ReplicaIdentity::Default,
+            ProtoReplicaIdentity::Full => ReplicaIdentity::Full,
+            ProtoReplicaIdentity::Index => ReplicaIdentity::Index,
+            ProtoReplicaIdentity::Nothing => ReplicaIdentity::Nothing,
+        })
+    }
+}
+
 /// Describes a column in a [`PostgresTableDesc`].
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct PostgresColumnDesc {
@@ -149,12 +360,23 @@ pub struct PostgresColumnDesc {
     // TODO(migration): remove option in version v.51 (released in v0.49 + 1
     // additional release)
     pub col_num: Option<u16>,
-    /// The OID of the column's type.
+    /// The OID of the column's type. If the column's type is a domain, this is
+    /// the OID of the domain's base type.
     pub type_oid: Oid,
     /// The modifier for the column's type.
     pub type_mod: i32,
     /// True if the column lacks a `NOT NULL` constraint.
     pub nullable: bool,
+    /// The definitions of the `CHECK` constraints of the column's domain, if
+    /// its type is a domain, outermost domain last. Upstream enforces them, so
+    /// they are only recorded for reference.
+    #[serde(default)]
+    pub domain_constraints: Vec<String>,
+    /// The expression of the column's default, as deparsed by `pg_get_expr`,
+    /// if it has one. Upstream fills it in, so it is only consulted for the
+    /// rows written before the column was added.
+    #[serde(default)]
+    pub default_expr: Option<String>,
 }
 
 impl PostgresColumnDesc {
@@ -167,14 +389,25 @@ impl PostgresColumnDesc {
     fn is_compatible(&self, other: &PostgresColumnDesc) -> bool {
         self.name == other.name
             && self.col_num == other.col_num
-            && self.type_oid == other.type_oid
-            && self.type_mod == other.type_mod
+            && ((self.type_oid == other.type_oid && self.type_mod == other.type_mod)
+                || self.is_widened_by(other))
             // Columns are compatible if:
             // - self is nullable; introducing a not null constraint doesn't
             //   change this column's behavior.
             // - self and other are both not nullable
             && (self.nullable || self.nullable == other.nullable)
     }
+
+    /// Determines if the type of `other` widens the type of `self`, as per
+    /// [`can_widen`].
+    fn is_widened_by(&self, other: &PostgresColumnDesc) -> bool {
+        let from = mz_pgrepr::Type::from_oid_and_typmod(self.type_oid, self.type_mod);
+        let to = mz_pgrepr::Type::from_oid_and_typmod(other.type_oid, other.type_mod);
+        match (from, to) {
+            (Ok(from), Ok(to)) => can_widen(&from, &to),
+            _ => false,
+        }
+    }
 }
 
 impl RustType<ProtoPostgresColumnDesc> for PostgresColumnDesc {
@@ -185,6 +418,8 @@ impl RustType<ProtoPostgresColumnDesc> for PostgresColumnDesc {
             type_oid: self.type_oid,
             type_mod: self.type_mod,
             nullable: self.nullable,
+            domain_constraints: self.domain_constraints.clone(),
+            default_expr: self.default_expr.clone(),
         }
     }
 
@@ -197,6 +432,8 @@ impl RustType<ProtoPostgresColumnDesc> for PostgresColumnDesc {
             type_oid: proto.type_oid,
             type_mod: proto.type_mod,
             nullable: proto.nullable,
+            domain_constraints: proto.domain_constraints,
+            default_expr: proto.default_expr,
         })
     }
 }
@@ -212,14 +449,28 @@ impl Arbitrary for PostgresColumnDesc {
             any::<u32>(),
             any::<i32>(),
             any::<bool>(),
+            any::<Vec<String>>(),
+            any::<Option<String>>(),
         )
             .prop_map(
-                |(name, col_num, type_oid, type_mod, nullable)| PostgresColumnDesc {
+                |(
                     name,
-                    col_num: Some(col_num),
+                    col_num,
                     type_oid,
                     type_mod,
                     nullable,
+                    domain_constraints,
+                    default_expr,
+                )| {
+                    PostgresColumnDesc {
+                        name,
+                        col_num: Some(col_num),
+                        type_oid,
+                        type_mod,
+                        nullable,
+                        domain_constraints,
+                        default_expr,
+                    }
                 },
             )
             .boxed()
@@ -77,25 +77,31 @@
 
 //! PostgreSQL utility library.
 
+use std::collections::{BTreeMap, BTreeSet};
+use std::net::SocketAddr;
 use std::time::Duration;
 
 use openssl::pkey::PKey;
 use openssl::ssl::{SslConnector, SslMethod, SslVerifyMode};
 use openssl::x509::X509;
-use postgres_openssl::MakeTlsConnector;
+use postgres_openssl::{MakeTlsConnector, TlsConnector};
+use rand::Rng;
+use socket2::{SockRef, TcpKeepalive};
 use tokio::net::TcpStream as TokioTcpStream;
-use tokio_postgres::config::{Host, ReplicationMode, SslMode};
+use tokio_postgres::config::{Host, ReplicationMode, SslMode, TargetSessionAttrs};
+use tokio_postgres::error::SqlState;
 use tokio_postgres::tls::MakeTlsConnect;
-use tokio_postgres::Client;
-use tracing::warn;
+use tokio_postgres::{Client, SimpleQueryMessage};
+use tracing::{info, warn};
 
 use mz_ore::task;
 use mz_repr::GlobalId;
 use mz_ssh_util::tunnel::SshTunnelConfig;
 
-use crate::desc::{PostgresColumnDesc, PostgresKeyDesc, PostgresTableDesc};
+use crate::desc::{PostgresColumnDesc, PostgresKeyDesc, PostgresTableDesc, ReplicaIdentity};
 
 pub mod desc;
+pub mod validation;
 
 /// An error representing pg, ssh, ssl, and other failures.
 #[derive(Debug, thiserror::Error)]
@@ -126,6 +132,69 @@ macro_rules! bail_generic {
     };
 }
 
+/// How long [`publication_info`] waits for the upstream before giving up, so
+/// that a slow or overloaded server cannot block e.g. the planning of
+/// `CREATE SOURCE` indefinitely.
+pub const PUBLICATION_INFO_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The fraction of the delay between attempts of
+/// [`Config::connect_replication_with_retry`] by which it is randomized.
+const RETRY_JITTER: f64 = 0.25;
+
+/// The longest that [`Config::connect_replication_with_retry`] waits between
+/// two attempts, before the jitter is applied.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// Whether `err` is the server rejecting the credentials or role of a
+/// connection.
+fn is_authentication_failure(err: &PostgresError) -> bool {
+    match err {
+        PostgresError::Postgres(err) => err.code().map_or(false, is_authentication_code),
+        _ => false,
+    }
+}
+
+fn is_authentication_code(code: &SqlState) -> bool {
+    *code == SqlState::INVALID_PASSWORD || *code == SqlState::INVALID_AUTHORIZATION_SPECIFICATION
+}
+
+/// Makes up to `max_attempts` calls to `connect` until one succeeds, as
+/// described in [`Config::connect_replication_with_retry`]. Errors that
+/// `is_fatal` holds for are returned right away.
+async fn retry_with_backoff<T, F, Fut>(
+    max_attempts: usize,
+    backoff: &mut Duration,
+    is_fatal: fn(&PostgresError) -> bool,
+    mut connect: F,
+) -> Result<T, PostgresError>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<T, PostgresError>>,
+{
+    let mut attempt = 1;
+    loop {
+        let err = match connect().await {
+            Ok(client) => return Ok(client),
+            Err(err) => err,
+        };
+        if is_fatal(&err) {
+            return Err(err);
+        }
+        let factor = rand::thread_rng().gen_range(1.0 - RETRY_JITTER..=1.0 + RETRY_JITTER);
+        let delay = backoff.mul_f64(factor);
+        *backoff = std::cmp::min(*backoff * 2, MAX_RETRY_DELAY);
+        if attempt >= max_attempts {
+            return Err(err);
+        }
+        warn!(
+            "replication connection attempt {attempt} of {max_attempts} failed, \
+             retrying in {delay:?}: {err}"
+        );
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
 /// Creates a TLS connector for the given [`Config`].
 pub fn make_tls(config: &tokio_postgres::Config) -> Result<MakeTlsConnector, PostgresError> {
     let mut builder = SslConnector::builder(SslMethod::tls_client())?;
@@ -187,63 +256,169 @@ pub fn make_tls(config: &tokio_postgres::Config) -> Result<MakeTlsConnector, Pos
     Ok(tls_connector)
 }
 
+/// Splits the `PUBLICATION` option of a source into the names of the
+/// publications it ingests from, which are separated by commas, e.g.
+/// `'team_a, team_b'`. Whitespace around the names is ignored.
+pub fn publication_names(publication: &str) -> Vec<String> {
+    publication
+        .split(',')
+        .map(|name| name.trim())
+        .filter(|name| !name.is_empty())
+        .map(|name| name.to_string())
+        .collect()
+}
+
+/// Which of the tables of a publication [`publication_info`] fetches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PublicationFilter {
+    /// All tables of the publication.
+    All,
+    /// Only the table with the given OID.
+    ByOid(u32),
+    /// Only the tables with the given name, in any schema.
+    ByName(String),
+}
+
 /// Fetches table schema information from an upstream Postgres source for
 /// tables that are part of a publication, given a connection string and the
-/// publication name.
+/// `PUBLICATION` option of the source. If the option lists several
+/// publications (see [`publication_names`]), the tables of all of them are
+/// returned.
 ///
-/// If `oid_filter` is `None`, returns all tables, otherwise returns only the
-/// details for the identified oid.
+/// Only the tables that match `filter` are returned, which saves querying the
+/// columns of all tables of a large publication.
 ///
 /// # Errors
 ///
 /// - Invalid connection string, user information, or user permissions.
 /// - Upstream publication does not exist or contains invalid values.
+/// - A table is part of more than one of the publications.
+/// - The upstream did not respond within [`PUBLICATION_INFO_TIMEOUT`].
 pub async fn publication_info(
     config: &Config,
     publication: &str,
-    oid_filter: Option<u32>,
+    filter: &PublicationFilter,
 ) -> Result<Vec<PostgresTableDesc>, PostgresError> {
-    let client = config.connect("postgres_publication_info").await?;
+    let publications = publication_names(publication);
+    let info = async {
+        let client = config.connect("postgres_publication_info").await?;
+        publication_info_inner(&client, &publications, filter).await
+    };
+    with_publication_info_timeout(info).await
+}
 
-    client
+/// Like [`publication_info`], but fetches the tables of `publications` over
+/// the existing connection `client` instead of opening one.
+///
+/// # Errors
+///
+/// - Upstream publication does not exist or contains invalid values.
+/// - A table is part of more than one of the publications.
+/// - The upstream did not respond within [`PUBLICATION_INFO_TIMEOUT`].
+pub async fn client_publication_info(
+    client: &Client,
+    publications: &[String],
+    filter: &PublicationFilter,
+) -> Result<Vec<PostgresTableDesc>, PostgresError> {
+    with_publication_info_timeout(publication_info_inner(client, publications, filter)).await
+}
+
+async fn with_publication_info_timeout(
+    info: impl std::future::Future<Output = Result<Vec<PostgresTableDesc>, PostgresError>>,
+) -> Result<Vec<PostgresTableDesc>, PostgresError> {
+    match tokio::time::timeout(PUBLICATION_INFO_TIMEOUT, info).await {
+        Ok(info) => info,
+        Err(_) => bail_generic!(
+            "Timed out fetching publication info from Postgres. \
+             Check that the server is reachable and responsive."
+        ),
+    }
+}
+
+async fn publication_info_inner(
+    client: &Client,
+    publications: &[String],
+    filter: &PublicationFilter,
+) -> Result<Vec<PostgresTableDesc>, PostgresError> {
+    if publications.is_empty() {
+        bail_generic!("no publication specified");
+    }
+    let existing: BTreeSet<String> = client
         .query(
-            "SELECT oid FROM pg_publication WHERE pubname = $1",
-            &[&publication],
+            "SELECT pubname FROM pg_publication WHERE pubname = ANY($1)",
+            &[&publications],
         )
         .await?
-        .get(0)
-        .ok_or_else(|| anyhow::anyhow!("publication {:?} does not exist", publication))?;
+        .into_iter()
+        .map(|row| row.get("pubname"))
+        .collect();
+    if let Some(missing) = publications.iter().find(|name| !existing.contains(*name)) {
+        bail_generic!("publication {:?} does not exist", missing);
+    }
 
+    let (oid_filter, name_filter) = match filter {
+        PublicationFilter::All => (None, None),
+        PublicationFilter::ByOid(oid) => (Some(*oid), None),
+        PublicationFilter::ByName(name) => (None, Some(name.as_str())),
+    };
     let tables = client
         .query(
             "SELECT
-                c.oid, p.schemaname, p.tablename
+                c.oid, p.schemaname, p.tablename, p.pubname
             FROM
                 pg_catalog.pg_class AS c
                 JOIN pg_namespace AS n ON c.relnamespace = n.oid
                 JOIN pg_publication_tables AS p ON
                         c.relname = p.tablename AND n.nspname = p.schemaname
             WHERE
-                p.pubname = $1
-                AND ($2::oid IS NULL OR c.oid = $2::oid)",
-            &[&publication, &oid_filter],
+                p.pubname = ANY($1)
+                AND ($2::oid IS NULL OR c.oid = $2::oid)
+                AND ($3::text IS NULL OR p.tablename = $3::text)",
+            &[&publications, &oid_filter, &name_filter],
         )
         .await?;
 
+    // The publication that each table is part of, by OID. A source can only
+    // ingest a table once, so a table must be part of one publication only.
+    let mut table_publications: BTreeMap<u32, String> = BTreeMap::new();
+    for row in &tables {
+        let publication: String = row.get("pubname");
+        if let Some(other) = table_publications.insert(row.get("oid"), publication.clone()) {
+            let schema: String = row.get("schemaname");
+            let table: String = row.get("tablename");
+            bail_generic!(
+                "table {:?}.{:?} is part of both publication {:?} and publication {:?}",
+                schema,
+                table,
+                other,
+                publication
+            );
+        }
+    }
+
+    // The domains used by the tables' columns, by OID.
+    let mut domains = BTreeMap::new();
     let mut table_infos = vec![];
     for row in tables {
         let oid = row.get("oid");
 
-        let columns = client
+        let column_rows = client
             .query(
                 "SELECT
                         a.attname AS name,
                         a.atttypid AS typoid,
+                        t.typtype = 'd' AS is_domain,
                         a.attnum AS colnum,
                         a.atttypmod AS typmod,
                         a.attnotnull AS not_null,
-                        b.oid IS NOT NULL AS primary_key
+                        b.oid IS NOT NULL AS primary_key,
+                        pg_catalog.pg_get_expr(d.adbin, d.adrelid) AS default_expr
                     FROM pg_catalog.pg_attribute a
+                    JOIN pg_catalog.pg_type t ON a.atttypid = t.oid
+                    LEFT JOIN pg_catalog.pg_attrdef d
+                        ON a.atthasdef
+                        AND d.adrelid = a.attrelid
+                        AND d.adnum = a.attnum
                     LEFT JOIN pg_catalog.pg_constraint b
                         ON a.attrelid = b.conrelid
                         AND b.contype = 'p'
@@ -254,27 +429,43 @@ pub async fn publication_info(
                     ORDER BY a.attnum",
                 &[&oid],
             )
-            .await?
-            .into_iter()
-            .map(|row| {
-                let name: String = row.get("name");
-                let type_oid = row.get("typoid");
-                let col_num = Some(
-                    row.get::<_, i16>("colnum")
-                        .try_into()
-                        .expect("non-negative values"),
-                );
-                let type_mod: i32 = row.get("typmod");
-                let not_null: bool = row.get("not_null");
-                Ok(PostgresColumnDesc {
-                    name,
-                    col_num,
-                    type_oid,
-                    type_mod,
-                    nullable: !not_null,
-                })
-            })
-            .collect::<Result<Vec<_>, PostgresError>>()?;
+            .await?;
+
+        let mut columns = Vec::with_capacity(column_rows.len());
+        for row in column_rows {
+            let name: String = row.get("name");
+            let mut type_oid = row.get("typoid");
+            let col_num = Some(
+                row.get::<_, i16>("colnum")
+                    .try_into()
+                    .expect("non-negative values"),
+            );
+            let mut type_mod: i32 = row.get("typmod");
+            let not_null: bool = row.get("not_null");
+            let default_expr: Option<String> = row.get("default_expr");
+            let mut domain_constraints = vec![];
+            if row.get("is_domain") {
+                // Values of a domain are represented as values of its base
+                // type, both in snapshots and in the replication stream.
+                if !domains.contains_key(&type_oid) {
+                    let domain = resolve_domain(client, type_oid).await?;
+                    domains.insert(type_oid, domain);
+                }
+                let domain = &domains[&type_oid];
+                type_oid = domain.base_oid;
+                type_mod = domain.base_type_mod;
+                domain_constraints = domain.constraints.clone();
+            }
+            columns.push(PostgresColumnDesc {
+                name,
+                col_num,
+                type_oid,
+                type_mod,
+                nullable: !not_null,
+                domain_constraints,
+                default_expr,
+            });
+        }
 
         // PG 15 adds UNIQUE NULLS NOT DISTINCT, which would let us use `UNIQUE` constraints over
         // nullable columns as keys; i.e. aligns a PG index's NULL handling with an arrangement's
@@ -344,18 +535,276 @@ pub async fn publication_info(
             })
             .collect();
 
+        // The index backing the replica identity is the one marked with
+        // `indisreplident` for `REPLICA IDENTITY USING INDEX`, and the primary
+        // key for `REPLICA IDENTITY DEFAULT`.
+        let identity = client
+            .query_one(
+                "SELECT
+                    c.relreplident::text AS replident,
+                    i.indkey::int2[] AS identity_cols
+                FROM pg_catalog.pg_class c
+                LEFT JOIN pg_catalog.pg_index i
+                    ON i.indrelid = c.oid
+                    AND CASE c.relreplident
+                        WHEN 'i' THEN i.indisreplident
+                        WHEN 'd' THEN i.indisprimary
+                        ELSE false
+                    END
+                WHERE c.oid = $1",
+                &[&oid],
+            )
+            .await?;
+        let replident: String = identity.get("replident");
+        let replica_identity = match ReplicaIdentity::from_relreplident(&replident) {
+            Some(replica_identity) => replica_identity,
+            None => bail_generic!(
+                "table with oid {} has unknown replica identity {:?}",
+                oid,
+                replident
+            ),
+        };
+        let identity_cols: Option<Vec<i16>> = identity.get("identity_cols");
+        let identity_column_names = identity_cols
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|attnum| {
+                let attnum = u16::try_from(attnum).ok();
+                columns
+                    .iter()
+                    .find(|c| c.col_num.is_some() && c.col_num == attnum)
+                    .map(|c| c.name.clone())
+            })
+            .collect();
+
         table_infos.push(PostgresTableDesc {
             oid,
             namespace: row.get("schemaname"),
             name: row.get("tablename"),
             columns,
             keys,
+            replica_identity,
+            identity_column_names,
         });
     }
 
     Ok(table_infos)
 }
 
+/// A domain type, resolved to the type it is ultimately based on.
+struct Domain {
+    /// The OID of the innermost non-domain base type.
+    base_oid: u32,
+    /// The modifier of the base type, as declared by the innermost domain.
+    base_type_mod: i32,
+    /// The definitions of the `CHECK` constraints of every domain along the
+    /// way, innermost domain first.
+    constraints: Vec<String>,
+}
+
+/// Resolves the domain with OID `oid`, which may itself be based on other
+/// domains.
+async fn resolve_domain(client: &Client, oid: u32) -> Result<Domain, PostgresError> {
+    let row = client
+        .query_one(
+            "WITH RECURSIVE domains AS (
+                SELECT oid, typbasetype, typtypmod, 1 AS depth
+                FROM pg_catalog.pg_type
+                WHERE oid = $1
+                UNION ALL
+                SELECT t.oid, t.typbasetype, t.typtypmod, d.depth + 1
+                FROM pg_catalog.pg_type t
+                JOIN domains d ON t.oid = d.typbasetype
+                WHERE t.typtype = 'd'
+            ), innermost AS (
+                SELECT typbasetype, typtypmod
+                FROM domains
+                ORDER BY depth DESC
+                LIMIT 1
+            )
+            SELECT
+                innermost.typbasetype AS base_oid,
+                innermost.typtypmod AS base_typmod,
+                ARRAY(
+                    SELECT pg_catalog.pg_get_constraintdef(c.oid)
+                    FROM pg_catalog.pg_constraint c
+                    JOIN domains d ON c.contypid = d.oid
+                    WHERE c.contype = 'c'
+                    ORDER BY d.depth DESC, c.conname
+                ) AS constraints
+            FROM innermost",
+            &[&oid],
+        )
+        .await?;
+    Ok(Domain {
+        base_oid: row.get("base_oid"),
+        base_type_mod: row.get("base_typmod"),
+        constraints: row.get("constraints"),
+    })
+}
+
+/// Sets the `statement_timeout` of the session of `client` to `timeout`, or
+/// disables it if `timeout` is `None`.
+///
+/// `client` may be a replication connection.
+pub async fn set_statement_timeout(
+    client: &Client,
+    timeout: Option<Duration>,
+) -> Result<(), PostgresError> {
+    // The largest timeout Postgres accepts, in milliseconds.
+    const MAX_TIMEOUT_MILLIS: u128 = 2_147_483_647;
+    let millis = timeout.map_or(0, |timeout| timeout.as_millis().min(MAX_TIMEOUT_MILLIS));
+    // Replication connections only support the simple query protocol.
+    client
+        .simple_query(&format!("SET statement_timeout = {millis}"))
+        .await?;
+    Ok(())
+}
+
+/// Returns the definition of the type with the given `oid`, or `None` if it
+/// doesn't exist. Two definitions of a type are equal unless the type was
+/// altered in between, e.g. an enum gained a value or a composite type an
+/// attribute.
+///
+/// `client` may not be a replication connection.
+pub async fn type_definition(client: &Client, oid: u32) -> Result<Option<String>, PostgresError> {
+    let row = client
+        .query_opt(
+            "SELECT concat_ws(
+                ' ',
+                t.typtype,
+                format_type(t.typbasetype, t.typtypmod),
+                (
+                    SELECT array_agg(e.enumlabel ORDER BY e.enumsortorder)
+                    FROM pg_catalog.pg_enum e
+                    WHERE e.enumtypid = t.oid
+                )::text,
+                (
+                    SELECT array_agg(
+                        a.attname || ' ' || format_type(a.atttypid, a.atttypmod)
+                        ORDER BY a.attnum
+                    )
+                    FROM pg_catalog.pg_attribute a
+                    WHERE a.attrelid = t.typrelid AND a.attnum > 0 AND NOT a.attisdropped
+                )::text,
+                (
+                    SELECT array_agg(pg_get_constraintdef(c.oid) ORDER BY c.conname)
+                    FROM pg_catalog.pg_constraint c
+                    WHERE c.contypid = t.oid
+                )::text
+            ) AS definition
+            FROM pg_catalog.pg_type t
+            WHERE t.oid = $1",
+            &[&oid],
+        )
+        .await?;
+    Ok(row.map(|row| row.get("definition")))
+}
+
+/// How the rows of a table are persisted upstream, as in
+/// `pg_class.relpersistence`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TablePersistence {
+    /// Changes to the table are written to the WAL.
+    Permanent,
+    /// Changes to the table are not written to the WAL, so they are never
+    /// replicated, and the table is truncated when the server recovers from a
+    /// crash.
+    Unlogged,
+    /// The table only exists for the session that created it.
+    Temporary,
+}
+
+/// Looks up how the tables with the given `oids` are persisted. Tables that
+/// don't exist are left out.
+pub async fn table_persistence(
+    client: &Client,
+    oids: &[u32],
+) -> Result<BTreeMap<u32, TablePersistence>, PostgresError> {
+    let rows = client
+        .query(
+            "SELECT oid, relpersistence::text AS persistence
+            FROM pg_catalog.pg_class
+            WHERE oid = ANY($1)",
+            &[&oids],
+        )
+        .await?;
+    let mut persistence = BTreeMap::new();
+    for row in rows {
+        let oid: u32 = row.get("oid");
+        let table_persistence = match row.get::<_, &str>("persistence") {
+            "p" => TablePersistence::Permanent,
+            "u" => TablePersistence::Unlogged,
+            "t" => TablePersistence::Temporary,
+            other => bail_generic!("table with OID {} has unknown persistence {:?}", oid, other),
+        };
+        persistence.insert(oid, table_persistence);
+    }
+    Ok(persistence)
+}
+
+/// The ratio of the size the rows of a table take up in Materialize's storage
+/// to the size of the table in Postgres, as observed empirically.
+pub const DEFAULT_STORAGE_SIZE_RATIO: f64 = 1.2;
+
+/// Estimates how many bytes the initial snapshot of the tables of
+/// `publication` takes up in Materialize's storage, from the size of the
+/// tables in Postgres and [`DEFAULT_STORAGE_SIZE_RATIO`].
+///
+/// The size of a table in Postgres includes its indexes, TOAST table and
+/// bloat, so the estimate is only meant for pre-flight capacity checks. See
+/// [`estimate_storage_bytes`] to estimate with a different ratio.
+///
+/// # Errors
+///
+/// - Invalid connection string, user information, or user permissions.
+/// - The upstream did not respond within [`PUBLICATION_INFO_TIMEOUT`].
+pub async fn estimate_publication_storage_bytes(
+    config: &Config,
+    publication: &str,
+) -> Result<u64, anyhow::Error> {
+    let bytes = publication_size_bytes(config, publication);
+    match tokio::time::timeout(PUBLICATION_INFO_TIMEOUT, bytes).await {
+        Ok(bytes) => Ok(estimate_storage_bytes(bytes?, DEFAULT_STORAGE_SIZE_RATIO)),
+        Err(_) => anyhow::bail!(
+            "Timed out fetching the size of publication {:?} from Postgres.",
+            publication
+        ),
+    }
+}
+
+/// Returns the total size of the tables of the publications that
+/// `publication` lists in Postgres, including their indexes and TOAST tables.
+async fn publication_size_bytes(config: &Config, publication: &str) -> Result<u64, anyhow::Error> {
+    let publications = publication_names(publication);
+    let client = config.connect("postgres_publication_size").await?;
+    let row = client
+        .query_one(
+            "SELECT
+                COALESCE(SUM(pg_catalog.pg_total_relation_size(c.oid)), 0)::int8 AS bytes
+            FROM
+                pg_catalog.pg_class AS c
+                JOIN pg_namespace AS n ON c.relnamespace = n.oid
+                JOIN pg_publication_tables AS p ON
+                        c.relname = p.tablename AND n.nspname = p.schemaname
+            WHERE
+                p.pubname = ANY($1)",
+            &[&publications],
+        )
+        .await?;
+    let bytes: i64 = row.get("bytes");
+    Ok(u64::try_from(bytes)?)
+}
+
+/// Estimates how many bytes data that takes up `postgres_bytes` in Postgres
+/// takes up in Materialize's storage, given the `ratio` of the two.
+pub fn estimate_storage_bytes(postgres_bytes: u64, ratio: f64) -> u64 {
+    // Precision is irrelevant to an estimate, and the cast back saturates.
+    #[allow(clippy::as_conversions)]
+    let bytes = (postgres_bytes as f64 * ratio) as u64;
+    bytes
+}
+
 pub async fn drop_replication_slots(config: Config, slots: &[&str]) -> Result<(), PostgresError> {
     let client = config.connect("postgres_drop_replication_slots").await?;
     let replication_client = config.connect_replication().await?;
@@ -416,24 +865,65 @@ pub enum TunnelConfig {
 pub struct Config {
     inner: tokio_postgres::Config,
     tunnel: TunnelConfig,
+    /// The hostname to send in the SNI extension of the TLS handshake, and to
+    /// verify the server's certificate against, instead of the connect host.
+    tls_sni_hostname: Option<String>,
 }
 
 impl Config {
     pub fn new(inner: tokio_postgres::Config, tunnel: TunnelConfig) -> Result<Self, PostgresError> {
-        let config = Self { inner, tunnel };
+        let config = Self {
+            inner,
+            tunnel,
+            tls_sni_hostname: None,
+        };
 
-        // Early validate that the configuration contains only a single TCP
-        // server.
-        config.address()?;
+        // Early validate that the configuration contains only TCP servers, and
+        // only a single one unless they are connected to directly.
+        match config.tunnel {
+            TunnelConfig::Direct => config.addresses().map(|_| ())?,
+            _ => config.address().map(|_| ())?,
+        }
 
         Ok(config)
     }
 
+    /// Sets the hostname to send via Server Name Indication (SNI) in the TLS
+    /// handshake, which TLS-terminating proxies that front several servers use
+    /// to route the connection.
+    pub fn tls_sni_hostname(mut self, hostname: String) -> Self {
+        self.tls_sni_hostname = Some(hostname);
+        self
+    }
+
+    /// Returns the role to connect as, if configured.
+    pub fn user(&self) -> Option<&str> {
+        self.inner.get_user()
+    }
+
+    /// Returns the database to connect to, if configured.
+    pub fn dbname(&self) -> Option<&str> {
+        self.inner.get_dbname()
+    }
+
     /// Connects to the configured PostgreSQL database.
     pub async fn connect(&self, task_name: &str) -> Result<Client, PostgresError> {
         self.connect_internal(task_name, |_| ()).await
     }
 
+    /// Connects to `dbname` on the configured servers rather than to the
+    /// configured database.
+    pub async fn connect_database(
+        &self,
+        task_name: &str,
+        dbname: &str,
+    ) -> Result<Client, PostgresError> {
+        self.connect_internal(task_name, |config| {
+            config.dbname(dbname);
+        })
+        .await
+    }
+
     /// Starts a replication connection to the configured PostgreSQL database.
     pub async fn connect_replication(&self) -> Result<Client, PostgresError> {
         self.connect_internal("postgres_connect_replication", |config| {
@@ -445,13 +935,58 @@ impl Config {
         .await
     }
 
+    /// Like [`Self::connect_replication`], but makes up to `max_attempts`
+    /// attempts to connect. It waits `backoff` after a failed attempt, and
+    /// doubles `backoff` with every failed attempt, up to a minute. Callers
+    /// that keep `backoff` across calls, and reset it once they connected,
+    /// thus keep backing off across calls that fail. The delays are randomized
+    /// by up to 25% either way, so that the sources that lose their
+    /// connections at the same time, e.g. in a network partition, don't all
+    /// reconnect to the server at once.
+    ///
+    /// Authentication failures are returned right away, as retrying doesn't
+    /// fix them and only risks locking the role out. Otherwise returns the
+    /// error of the last attempt if none succeeds.
+    pub async fn connect_replication_with_retry(
+        &self,
+        max_attempts: usize,
+        backoff: &mut Duration,
+    ) -> Result<Client, PostgresError> {
+        retry_with_backoff(max_attempts, backoff, is_authentication_failure, || {
+            self.connect_replication()
+        })
+        .await
+    }
+
     fn address(&self) -> Result<(&str, u16), PostgresError> {
-        match (self.inner.get_hosts(), self.inner.get_ports()) {
-            ([Host::Tcp(host)], [port]) => Ok((host, *port)),
+        match self.addresses()?.as_slice() {
+            [address] => Ok(*address),
             _ => bail_generic!("only TCP connections to a single PostgreSQL server are supported"),
         }
     }
 
+    /// Returns the TCP servers to connect to, in the order in which they are
+    /// tried.
+    fn addresses(&self) -> Result<Vec<(&str, u16)>, PostgresError> {
+        let hosts = self.inner.get_hosts();
+        let ports = self.inner.get_ports();
+        // Like in libpq, a single port applies to every host.
+        if hosts.is_empty() || ports.len() > 1 && ports.len() != hosts.len() {
+            bail_generic!("the number of PostgreSQL hosts and ports must match");
+        }
+        hosts
+            .iter()
+            .enumerate()
+            .map(|(i, host)| {
+                let port = ports.get(i).or_else(|| ports.first()).copied();
+                match host {
+                    Host::Tcp(host) => Ok((host.as_str(), port.unwrap_or(5432))),
+                    _ => bail_generic!("only TCP connections to PostgreSQL servers are supported"),
+                }
+            })
+            .collect()
+    }
+
     async fn connect_internal<F>(
         &self,
         task_name: &str,
@@ -464,15 +999,11 @@ impl Config {
         configure(&mut postgres_config);
         let mut tls = make_tls(&postgres_config)?;
         match &self.tunnel {
-            TunnelConfig::Direct => {
-                let (client, connection) = postgres_config.connect(tls).await?;
-                task::spawn(|| task_name, connection);
-                Ok(client)
-            }
+            TunnelConfig::Direct => self.connect_direct(task_name, &postgres_config, tls).await,
             TunnelConfig::Ssh(tunnel) => {
                 let (host, port) = self.address()?;
                 let tunnel = tunnel.connect(host, port).await?;
-                let tls = MakeTlsConnect::<TokioTcpStream>::make_tls_connect(&mut tls, host)?;
+                let tls = self.connect_with_tls(&mut tls, host)?;
                 let tcp_stream = TokioTcpStream::connect(tunnel.local_addr()).await?;
                 let (client, connection) = postgres_config.connect_raw(tcp_stream, tls).await?;
                 task::spawn(|| task_name, async {
@@ -486,7 +1017,7 @@ impl Config {
             TunnelConfig::AwsPrivatelink { connection_id } => {
                 let (host, port) = self.address()?;
                 let privatelink_host = mz_cloud_resources::vpc_endpoint_name(*connection_id);
-                let tls = MakeTlsConnect::<TokioTcpStream>::make_tls_connect(&mut tls, host)?;
+                let tls = self.connect_with_tls(&mut tls, host)?;
                 let tcp_stream = TokioTcpStream::connect((privatelink_host, port)).await?;
                 let (client, connection) = postgres_config.connect_raw(tcp_stream, tls).await?;
                 task::spawn(|| task_name, connection);
@@ -494,4 +1025,180 @@ impl Config {
             }
         }
     }
+
+    /// Connects to the first of the configured servers that accepts the
+    /// connection and satisfies the configured `target_session_attrs`.
+    ///
+    /// Hostnames are resolved anew on every call, so that reconnecting follows
+    /// a failover that points a hostname at another server as soon as its DNS
+    /// record changes.
+    async fn connect_direct(
+        &self,
+        task_name: &str,
+        postgres_config: &tokio_postgres::Config,
+        mut tls: MakeTlsConnector,
+    ) -> Result<Client, PostgresError> {
+        let mut last_err = None;
+        for (host, port) in self.addresses()? {
+            let addrs = match tokio::net::lookup_host((host, port)).await {
+                Ok(addrs) => addrs,
+                Err(err) => {
+                    warn!(%host, "failed to resolve postgres host: {err}");
+                    last_err = Some(err.into());
+                    continue;
+                }
+            };
+            for addr in addrs {
+                match self
+                    .connect_addr(task_name, postgres_config, &mut tls, host, addr)
+                    .await
+                {
+                    Ok(client) => {
+                        info!(%host, %addr, "connected to postgres");
+                        return Ok(client);
+                    }
+                    Err(err) => {
+                        warn!(%host, %addr, "failed to connect to postgres: {err}");
+                        last_err = Some(err);
+                    }
+                }
+            }
+        }
+        match last_err {
+            Some(err) => Err(err),
+            None => bail_generic!("no addresses found for the PostgreSQL hosts"),
+        }
+    }
+
+    /// Connects to the server at `addr`, which `host` resolved to.
+    async fn connect_addr(
+        &self,
+        task_name: &str,
+        postgres_config: &tokio_postgres::Config,
+        tls: &mut MakeTlsConnector,
+        host: &str,
+        addr: SocketAddr,
+    ) -> Result<Client, PostgresError> {
+        let connect = TokioTcpStream::connect(addr);
+        let tcp_stream = match postgres_config.get_connect_timeout() {
+            Some(timeout) => match tokio::time::timeout(*timeout, connect).await {
+                Ok(tcp_stream) => tcp_stream?,
+                Err(_) => bail_generic!("timed out connecting to {addr}"),
+            },
+            None => connect.await?,
+        };
+        tcp_stream.set_nodelay(true)?;
+        if postgres_config.get_keepalives() {
+            let keepalive = TcpKeepalive::new().with_time(postgres_config.get_keepalives_idle());
+            SockRef::from(&tcp_stream).set_tcp_keepalive(&keepalive)?;
+        }
+        let tls = self.connect_with_tls(tls, host)?;
+        let (client, connection) = postgres_config.connect_raw(tcp_stream, tls).await?;
+        task::spawn(|| task_name, connection);
+
+        if postgres_config.get_target_session_attrs() == TargetSessionAttrs::ReadWrite {
+            let messages = client.simple_query("SHOW transaction_read_only").await?;
+            let read_only = messages.iter().any(|message| match message {
+                SimpleQueryMessage::Row(row) => row.get(0) == Some("on"),
+                _ => false,
+            });
+            if read_only {
+                bail_generic!("server at {addr} is read-only");
+            }
+        }
+        Ok(client)
+    }
+
+    /// Prepares the TLS handshake with the server at `host`, announcing the
+    /// configured SNI hostname instead of `host` if there is one.
+    fn connect_with_tls(
+        &self,
+        tls: &mut MakeTlsConnector,
+        host: &str,
+    ) -> Result<TlsConnector, PostgresError> {
+        let domain = self.tls_sni_hostname.as_deref().unwrap_or(host);
+        Ok(MakeTlsConnect::<TokioTcpStream>::make_tls_connect(
+            tls, domain,
+        )?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use tokio::time::Instant;
+
+    use super::*;
+
+    fn failed() -> PostgresError {
+        PostgresError::Generic(anyhow::anyhow!("connection refused"))
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn retries_with_backoff() {
+        let attempts = Cell::new(0);
+        let connect = || {
+            attempts.set(attempts.get() + 1);
+            let attempt = attempts.get();
+            async move {
+                if attempt < 3 {
+                    Err(failed())
+                } else {
+                    Ok(attempt)
+                }
+            }
+        };
+        let mut backoff = Duration::from_secs(1);
+        let start = Instant::now();
+        let result = retry_with_backoff(4, &mut backoff, |_| false, connect).await;
+        assert_eq!(result.unwrap(), 3);
+        // The attempts waited 1s and 2s, give or take the jitter.
+        let waited = start.elapsed();
+        assert!(waited >= Duration::from_secs(3).mul_f64(1.0 - RETRY_JITTER));
+        assert!(waited <= Duration::from_secs(3).mul_f64(1.0 + RETRY_JITTER));
+        assert_eq!(backoff, Duration::from_secs(4));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn gives_up_after_max_attempts() {
+        let attempts = Cell::new(0);
+        let connect = || {
+            attempts.set(attempts.get() + 1);
+            async { Err::<(), _>(failed()) }
+        };
+        let mut backoff = Duration::from_secs(16);
+        let result = retry_with_backoff(3, &mut backoff, |_| false, connect).await;
+        assert!(result.is_err());
+        assert_eq!(attempts.get(), 3);
+        // The backoff keeps growing across calls, up to its maximum.
+        assert_eq!(backoff, MAX_RETRY_DELAY);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn fatal_errors_are_not_retried() {
+        let attempts = Cell::new(0);
+        let connect = || {
+            attempts.set(attempts.get() + 1);
+            async { Err::<(), _>(failed()) }
+        };
+        let mut backoff = Duration::from_secs(1);
+        let start = Instant::now();
+        let result = retry_with_backoff(4, &mut backoff, |_| true, connect).await;
+        assert!(result.is_err());
+        assert_eq!(attempts.get(), 1);
+        assert_eq!(start.elapsed(), Duration::ZERO);
+        assert_eq!(backoff, Duration::from_secs(1));
+    }
+
+    #[test]
+    fn authentication_failures() {
+        assert!(is_authentication_code(&SqlState::INVALID_PASSWORD));
+        assert!(is_authentication_code(
+            &SqlState::INVALID_AUTHORIZATION_SPECIFICATION
+        ));
+        assert!(!is_authentication_code(&SqlState::TOO_MANY_CONNECTIONS));
+        assert!(!is_authentication_code(&SqlState::CONNECTION_FAILURE));
+        assert!(!is_authentication_failure(&failed()));
+    }
 }
@@ -0,0 +1,643 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Pre-flight validation of the upstream prerequisites of a PostgreSQL source.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use tokio_postgres::types::Oid;
+use tokio_postgres::{Client, SimpleQueryMessage};
+
+use crate::desc::{PostgresTableDesc, ReplicaIdentity};
+use crate::{publication_names, table_persistence, Config, PostgresError, TablePersistence};
+
+/// How severe a [`ValidationIssue`] is.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ValidationSeverity {
+    /// The source cannot be created or will fail to make progress.
+    Error,
+    /// The source can be created, but some upstream changes will not be
+    /// ingested correctly.
+    Warning,
+}
+
+/// A problem with the upstream configuration of a PostgreSQL source.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ValidationIssue {
+    /// The connection goes through PgBouncer, whose transaction and statement
+    /// pooling modes cannot provide the dedicated connection replication needs.
+    PgBouncer {
+        /// The version PgBouncer reports, if its admin console let us ask.
+        version: Option<String>,
+        /// The pooling mode of the database, if its admin console let us ask.
+        pool_mode: Option<String>,
+    },
+    /// The server's `wal_level` is not `logical`.
+    WalLevel { wal_level: String },
+    /// Every replication slot the server allows is already in use.
+    ReplicationSlotsExhausted { max: i64, used: i64 },
+    /// The user lacks the `REPLICATION` role attribute.
+    MissingReplicationAttribute { user: String },
+    /// The publication does not exist.
+    PublicationMissing { publication: String },
+    /// The publication does not publish some kinds of changes.
+    PublicationMissingActions {
+        publication: String,
+        actions: Vec<&'static str>,
+    },
+    /// The table's replica identity is not `FULL`, so upstream does not send
+    /// the complete old row of updates and deletes.
+    ReplicaIdentityNotFull {
+        table: String,
+        replica_identity: ReplicaIdentity,
+    },
+    /// The user cannot `SELECT` from the table, which is required to snapshot
+    /// it.
+    MissingSelectPrivilege { table: String },
+    /// The column's type cannot be ingested.
+    UnsupportedType {
+        table: String,
+        column: String,
+        type_oid: Oid,
+        /// Whether the type is a composite type, whose values can only be
+        /// ingested as text.
+        composite: bool,
+    },
+    /// The table is `UNLOGGED`, so its changes are never replicated.
+    UnloggedTable { table: String },
+    /// The table is temporary, so it can't be snapshotted by another session.
+    TemporaryTable { table: String },
+    /// A partition of the partitioned table is a foreign table, whose rows
+    /// can't be snapshotted and whose changes are not replicated.
+    ForeignPartition { table: String, partition: String },
+}
+
+impl ValidationIssue {
+    /// Returns how severe the issue is.
+    pub fn severity(&self) -> ValidationSeverity {
+        match self {
+            ValidationIssue::PgBouncer {
+                pool_mode: Some(pool_mode),
+                ..
+            } if pool_mode != "session" => ValidationSeverity::Error,
+            ValidationIssue::WalLevel { .. }
+            | ValidationIssue::ReplicationSlotsExhausted { .. }
+            | ValidationIssue::MissingReplicationAttribute { .. }
+            | ValidationIssue::PublicationMissing { .. }
+            | ValidationIssue::MissingSelectPrivilege { .. }
+            | ValidationIssue::UnsupportedType { .. }
+            | ValidationIssue::UnloggedTable { .. }
+            | ValidationIssue::TemporaryTable { .. }
+            | ValidationIssue::ForeignPartition { .. } => ValidationSeverity::Error,
+            ValidationIssue::PgBouncer { .. }
+            | ValidationIssue::PublicationMissingActions { .. }
+            | ValidationIssue::ReplicaIdentityNotFull { .. } => ValidationSeverity::Warning,
+        }
+    }
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ValidationIssue::PgBouncer { version, pool_mode } => {
+                write!(f, "connected through PgBouncer")?;
+                if let Some(version) = version {
+                    write!(f, " ({version})")?;
+                }
+                match pool_mode.as_deref() {
+                    Some("session") => write!(
+                        f,
+                        " with pool_mode = session; replication works through it, but \
+                        takes up a server connection of the pool for as long as the \
+                        source exists"
+                    ),
+                    Some(pool_mode) => write!(
+                        f,
+                        " with pool_mode = {pool_mode}, but replication requires a dedicated \
+                        connection to PostgreSQL; connect to PostgreSQL directly, or through \
+                        a PgBouncer pool with pool_mode = session"
+                    ),
+                    None => write!(
+                        f,
+                        ", whose pool_mode could not be determined; replication requires a \
+                        dedicated connection to PostgreSQL, so make sure that the pool has \
+                        pool_mode = session"
+                    ),
+                }
+            }
+            ValidationIssue::WalLevel { wal_level } => write!(
+                f,
+                "wal_level is {wal_level}, but must be logical"
+            ),
+            ValidationIssue::ReplicationSlotsExhausted { max, used } => write!(
+                f,
+                "all replication slots are in use ({used} of max_replication_slots {max})"
+            ),
+            ValidationIssue::MissingReplicationAttribute { user } => {
+                write!(f, "user {user} does not have the REPLICATION attribute")
+            }
+            ValidationIssue::PublicationMissing { publication } => {
+                write!(f, "publication {publication:?} does not exist")
+            }
+            ValidationIssue::PublicationMissingActions {
+                publication,
+                actions,
+            } => write!(
+                f,
+                "publication {publication:?} does not publish {}",
+                actions.join(", ")
+            ),
+            ValidationIssue::ReplicaIdentityNotFull {
+                table,
+                replica_identity,
+            } => write!(
+                f,
+                "table {table} has REPLICA IDENTITY {}, but updates and deletes require REPLICA IDENTITY FULL",
+                replica_identity.as_str().to_uppercase()
+            ),
+            ValidationIssue::MissingSelectPrivilege { table } => {
+                write!(f, "user does not have SELECT privilege on table {table}")
+            }
+            ValidationIssue::UnsupportedType {
+                table,
+                column,
+                type_oid,
+                composite: false,
+            } => write!(
+                f,
+                "column {table}.{column} has unsupported type (OID {type_oid})"
+            ),
+            ValidationIssue::UnsupportedType {
+                table,
+                column,
+                type_oid,
+                composite: true,
+            } => write!(
+                f,
+                "column {table}.{column} has composite type (OID {type_oid}), which is not supported"
+            ),
+            ValidationIssue::UnloggedTable { table } => write!(
+                f,
+                "table {table} is UNLOGGED, so its changes are not replicated and it is \
+                truncated when PostgreSQL recovers from a crash; make it LOGGED, or set ON \
+                UNLOGGED TABLE = 'snapshot' to only ingest its snapshot"
+            ),
+            ValidationIssue::TemporaryTable { table } => {
+                write!(f, "table {table} is temporary, so it cannot be replicated")
+            }
+            ValidationIssue::ForeignPartition { table, partition } => write!(
+                f,
+                "partition {partition} of table {table} is a foreign table, whose rows can't be \
+                snapshotted and whose changes are not replicated; detach it from {table}"
+            ),
+        }
+    }
+}
+
+/// The result of [`validate_postgres_source`].
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ValidationReport {
+    /// Every issue found, in the order the checks ran.
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    /// Returns the issues that prevent the source from working.
+    pub fn errors(&self) -> impl Iterator<Item = &ValidationIssue> {
+        self.with_severity(ValidationSeverity::Error)
+    }
+
+    /// Returns the issues that prevent the source from ingesting some changes
+    /// correctly.
+    pub fn warnings(&self) -> impl Iterator<Item = &ValidationIssue> {
+        self.with_severity(ValidationSeverity::Warning)
+    }
+
+    /// Reports whether any issue is an error.
+    pub fn has_errors(&self) -> bool {
+        self.errors().next().is_some()
+    }
+
+    fn with_severity(
+        &self,
+        severity: ValidationSeverity,
+    ) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues
+            .iter()
+            .filter(move |issue| issue.severity() == severity)
+    }
+}
+
+/// Checks all upstream prerequisites of ingesting `tables` from the
+/// publications that `publication` lists over a single connection, reporting every problem found rather than only
+/// the first one.
+///
+/// # Errors
+///
+/// Only if connecting or querying upstream fails; problems with the upstream
+/// configuration are reported in the returned [`ValidationReport`].
+pub async fn validate_postgres_source(
+    config: &Config,
+    publication: &str,
+    tables: &[PostgresTableDesc],
+) -> Result<ValidationReport, PostgresError> {
+    let client = config.connect("postgres_validate_source").await?;
+    let mut report = ValidationReport::default();
+    check_pgbouncer(config, &mut report).await?;
+    check_wal_level(&client, &mut report).await?;
+    check_replication_slots(&client, &mut report).await?;
+    check_replication_attribute(&client, &mut report).await?;
+    for publication in publication_names(publication) {
+        check_publication(&client, &publication, &mut report).await?;
+    }
+    for table in tables {
+        check_table(&client, table, &mut report).await?;
+    }
+    Ok(report)
+}
+
+/// Checks whether the connection goes through PgBouncer, which in
+/// transaction or statement pooling mode hands each transaction to any server
+/// connection, breaking the long-lived connection that replication runs on.
+///
+/// PgBouncer forwards queries like `SHOW server_version` to the server, so it
+/// is detected through its admin console instead: the virtual `pgbouncer`
+/// database, which answers `SHOW VERSION` and refuses users that are not in
+/// its `admin_users` or `stats_users` with "not allowed". PostgreSQL itself
+/// either has no such database or fails the query.
+pub async fn check_pgbouncer(
+    config: &Config,
+    report: &mut ValidationReport,
+) -> Result<(), PostgresError> {
+    let console = match config
+        .connect_database("postgres_validate_pgbouncer", PGBOUNCER_CONSOLE_DATABASE)
+        .await
+    {
+        Ok(console) => console,
+        Err(PostgresError::Postgres(err))
+            if err.as_db_error().map(|err| err.message()) == Some("not allowed") =>
+        {
+            report.issues.push(ValidationIssue::PgBouncer {
+                version: None,
+                pool_mode: None,
+            });
+            return Ok(());
+        }
+        // Anything else, like the database not existing, means the server is
+        // not PgBouncer, or is one that we can't tell apart from PostgreSQL.
+        Err(_) => return Ok(()),
+    };
+    // The console only supports the simple query protocol.
+    let version = match console.simple_query("SHOW VERSION").await {
+        Ok(messages) => simple_query_rows(&messages)
+            .into_iter()
+            .find_map(|mut row| row.remove("version")),
+        Err(_) => return Ok(()),
+    };
+    let version = match version {
+        Some(version) if version.contains("PgBouncer") => version,
+        _ => return Ok(()),
+    };
+    // Both listings are restricted to `admin_users` and `stats_users`, so the
+    // pool mode is unknown if they fail.
+    let databases = console
+        .simple_query("SHOW DATABASES")
+        .await
+        .map(|messages| simple_query_rows(&messages))
+        .unwrap_or_default();
+    let settings = console
+        .simple_query("SHOW CONFIG")
+        .await
+        .map(|messages| simple_query_rows(&messages))
+        .unwrap_or_default();
+    // Like PostgreSQL, PgBouncer defaults the database to the user name.
+    let database = config.dbname().or(config.user()).unwrap_or_default();
+    report.issues.push(ValidationIssue::PgBouncer {
+        version: Some(version),
+        pool_mode: pgbouncer_pool_mode(database, &databases, &settings),
+    });
+    Ok(())
+}
+
+/// The name of the virtual database that PgBouncer serves its admin console
+/// on.
+const PGBOUNCER_CONSOLE_DATABASE: &str = "pgbouncer";
+
+/// Returns the rows that a simple query returned, as maps from column names to
+/// the non-`NULL` values.
+fn simple_query_rows(messages: &[SimpleQueryMessage]) -> Vec<BTreeMap<String, String>> {
+    messages
+        .iter()
+        .filter_map(|message| match message {
+            SimpleQueryMessage::Row(row) => Some(
+                row.columns()
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, column)| {
+                        let value = row.get(i)?;
+                        Some((column.name().to_string(), value.to_string()))
+                    })
+                    .collect(),
+            ),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Determines the pool mode of `database` from the rows of PgBouncer's `SHOW
+/// DATABASES` and `SHOW CONFIG`. A database without a `pool_mode` of its own
+/// uses the global one.
+fn pgbouncer_pool_mode(
+    database: &str,
+    databases: &[BTreeMap<String, String>],
+    settings: &[BTreeMap<String, String>],
+) -> Option<String> {
+    let database_mode = databases
+        .iter()
+        .find(|row| row.get("name").map(String::as_str) == Some(database))
+        .and_then(|row| row.get("pool_mode"))
+        .filter(|pool_mode| !pool_mode.is_empty());
+    let global_mode = || {
+        settings
+            .iter()
+            .find(|row| row.get("key").map(String::as_str) == Some("pool_mode"))
+            .and_then(|row| row.get("value"))
+    };
+    database_mode.or_else(global_mode).cloned()
+}
+
+/// Checks that the server decodes the WAL for logical replication.
+pub async fn check_wal_level(
+    client: &Client,
+    report: &mut ValidationReport,
+) -> Result<(), PostgresError> {
+    let wal_level: String = client
+        .query_one("SELECT current_setting('wal_level') AS wal_level", &[])
+        .await?
+        .get("wal_level");
+    if wal_level != "logical" {
+        report.issues.push(ValidationIssue::WalLevel { wal_level });
+    }
+    Ok(())
+}
+
+/// Checks that the server has room for another replication slot.
+pub async fn check_replication_slots(
+    client: &Client,
+    report: &mut ValidationReport,
+) -> Result<(), PostgresError> {
+    let row = client
+        .query_one(
+            "SELECT
+                current_setting('max_replication_slots')::int8 AS max,
+                (SELECT count(*) FROM pg_catalog.pg_replication_slots) AS used",
+            &[],
+        )
+        .await?;
+    let max: i64 = row.get("max");
+    let used: i64 = row.get("used");
+    if used >= max {
+        report
+            .issues
+            .push(ValidationIssue::ReplicationSlotsExhausted { max, used });
+    }
+    Ok(())
+}
+
+/// Checks that the connecting user may start replication. On Amazon RDS and
+/// Aurora, where roles can't be given the `REPLICATION` attribute, membership
+/// in the `rds_replication` role grants it instead.
+pub async fn check_replication_attribute(
+    client: &Client,
+    report: &mut ValidationReport,
+) -> Result<(), PostgresError> {
+    let row = client
+        .query_one(
+            "SELECT
+                current_user::text AS username,
+                rolreplication OR rolsuper OR EXISTS (
+                    SELECT 1 FROM pg_catalog.pg_roles rds
+                    WHERE rds.rolname = 'rds_replication'
+                        AND pg_catalog.pg_has_role(current_user, rds.oid, 'MEMBER')
+                ) AS can_replicate
+            FROM pg_catalog.pg_roles
+            WHERE rolname = current_user",
+            &[],
+        )
+        .await?;
+    if !row.get::<_, bool>("can_replicate") {
+        report
+            .issues
+            .push(ValidationIssue::MissingReplicationAttribute {
+                user: row.get("username"),
+            });
+    }
+    Ok(())
+}
+
+/// Checks that `publication` exists and publishes every kind of change.
+pub async fn check_publication(
+    client: &Client,
+    publication: &str,
+    report: &mut ValidationReport,
+) -> Result<(), PostgresError> {
+    let rows = client
+        .query(
+            "SELECT pubinsert, pubupdate, pubdelete, pubtruncate
+            FROM pg_catalog.pg_publication
+            WHERE pubname = $1",
+            &[&publication],
+        )
+        .await?;
+    let row = match rows.first() {
+        Some(row) => row,
+        None => {
+            report.issues.push(ValidationIssue::PublicationMissing {
+                publication: publication.to_string(),
+            });
+            return Ok(());
+        }
+    };
+    let actions: Vec<_> = ["insert", "update", "delete", "truncate"]
+        .into_iter()
+        .filter(|action| !row.get::<_, bool>(format!("pub{action}").as_str()))
+        .collect();
+    if !actions.is_empty() {
+        report
+            .issues
+            .push(ValidationIssue::PublicationMissingActions {
+                publication: publication.to_string(),
+                actions,
+            });
+    }
+    Ok(())
+}
+
+/// Checks that `table` can be snapshotted and replicated.
+pub async fn check_table(
+    client: &Client,
+    table: &PostgresTableDesc,
+    report: &mut ValidationReport,
+) -> Result<(), PostgresError> {
+    let name = format!("{}.{}", table.namespace, table.name);
+
+    if table.replica_identity != ReplicaIdentity::Full {
+        report.issues.push(ValidationIssue::ReplicaIdentityNotFull {
+            table: name.clone(),
+            replica_identity: table.replica_identity,
+        });
+    }
+
+    let can_select: bool = client
+        .query_one(
+            "SELECT has_table_privilege($1::oid, 'SELECT') AS can_select",
+            &[&table.oid],
+        )
+        .await?
+        .get("can_select");
+    if !can_select {
+        report.issues.push(ValidationIssue::MissingSelectPrivilege {
+            table: name.clone(),
+        });
+    }
+
+    let persistence = table_persistence(client, &[table.oid]).await?;
+    match persistence.get(&table.oid) {
+        Some(TablePersistence::Unlogged) => {
+            report.issues.push(ValidationIssue::UnloggedTable {
+                table: name.clone(),
+            });
+        }
+        Some(TablePersistence::Temporary) => {
+            report.issues.push(ValidationIssue::TemporaryTable {
+                table: name.clone(),
+            });
+        }
+        Some(TablePersistence::Permanent) | None => {}
+    }
+
+    let foreign_partitions = client
+        .query(
+            "WITH RECURSIVE tree AS (
+                SELECT $1::oid AS oid
+                UNION ALL
+                SELECT i.inhrelid
+                FROM tree
+                JOIN pg_catalog.pg_inherits AS i ON i.inhparent = tree.oid
+            )
+            SELECT n.nspname || '.' || c.relname AS partition
+            FROM tree
+            JOIN pg_catalog.pg_class AS c ON c.oid = tree.oid
+            JOIN pg_catalog.pg_namespace AS n ON n.oid = c.relnamespace
+            WHERE c.relkind = 'f'
+            ORDER BY c.oid",
+            &[&table.oid],
+        )
+        .await?;
+    for row in foreign_partitions {
+        report.issues.push(ValidationIssue::ForeignPartition {
+            table: name.clone(),
+            partition: row.get("partition"),
+        });
+    }
+
+    for column in &table.columns {
+        if mz_pgrepr::Type::from_oid_and_typmod(column.type_oid, column.type_mod).is_err() {
+            let composite = client
+                .query_opt(
+                    "SELECT typtype = 'c' AS composite FROM pg_catalog.pg_type WHERE oid = $1",
+                    &[&column.type_oid],
+                )
+                .await?
+                .map_or(false, |row| row.get("composite"));
+            report.issues.push(ValidationIssue::UnsupportedType {
+                table: name.clone(),
+                column: column.name.clone(),
+                type_oid: column.type_oid,
+                composite,
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(rows: &[&[(&str, &str)]]) -> Vec<BTreeMap<String, String>> {
+        rows.iter()
+            .map(|row| {
+                row.iter()
+                    .map(|(column, value)| (column.to_string(), value.to_string()))
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn pgbouncer_pool_modes() {
+        let databases = rows(&[
+            &[("name", "pgbouncer"), ("pool_mode", "statement")],
+            &[("name", "postgres"), ("pool_mode", "")],
+            &[("name", "materialize"), ("pool_mode", "session")],
+            &[("name", "other")],
+        ]);
+        let settings = rows(&[
+            &[("key", "listen_port"), ("value", "6432")],
+            &[("key", "pool_mode"), ("value", "transaction")],
+        ]);
+        let pool_mode = |database| pgbouncer_pool_mode(database, &databases, &settings);
+        // A database's own pool mode takes precedence over the global one.
+        assert_eq!(pool_mode("materialize").as_deref(), Some("session"));
+        assert_eq!(pool_mode("postgres").as_deref(), Some("transaction"));
+        assert_eq!(pool_mode("other").as_deref(), Some("transaction"));
+        assert_eq!(pool_mode("missing").as_deref(), Some("transaction"));
+        // Without access to the listings, the pool mode is unknown.
+        assert_eq!(pgbouncer_pool_mode("materialize", &[], &[]), None);
+    }
+
+    #[test]
+    fn pgbouncer_severity() {
+        let issue = |pool_mode: Option<&str>| ValidationIssue::PgBouncer {
+            version: Some("PgBouncer 1.21.0".into()),
+            pool_mode: pool_mode.map(String::from),
+        };
+        assert_eq!(
+            issue(Some("session")).severity(),
+            ValidationSeverity::Warning
+        );
+        assert_eq!(issue(None).severity(), ValidationSeverity::Warning);
+        assert_eq!(
+            issue(Some("transaction")).severity(),
+            ValidationSeverity::Error
+        );
+        assert_eq!(
+            issue(Some("statement")).severity(),
+            ValidationSeverity::Error
+        );
+        assert_eq!(
+            issue(Some("transaction")).to_string(),
+            "connected through PgBouncer (PgBouncer 1.21.0) with pool_mode = transaction, but \
+            replication requires a dedicated connection to PostgreSQL; connect to PostgreSQL \
+            directly, or through a PgBouncer pool with pool_mode = session"
+        );
+        let unknown = ValidationIssue::PgBouncer {
+            version: None,
+            pool_mode: None,
+        };
+        assert_eq!(
+            unknown.to_string(),
+            "connected through PgBouncer, whose pool_mode could not be determined; replication \
+            requires a dedicated connection to PostgreSQL, so make sure that the pool has \
+            pool_mode = session"
+        );
+    }
+}
@@ -39,6 +39,15 @@ impl DatumVec {
             inner,
         }
     }
+    /// Borrow an instance with a specific lifetime, with room for at least `len` datums.
+    ///
+    /// Useful when the number of datums that will be pushed is known upfront, so that the
+    /// allocation is grown at most once.
+    pub fn borrow_with_len<'a>(&'a mut self, len: usize) -> DatumVecBorrow<'a> {
+        let mut borrow = self.borrow();
+        borrow.reserve(len);
+        borrow
+    }
     /// Borrow an instance with a specific lifetime, and pre-populate with a `Row`.
     pub fn borrow_with<'a>(&'a mut self, row: &'a Row) -> DatumVecBorrow<'a> {
         let mut borrow = self.borrow();
@@ -114,5 +123,11 @@ mod test {
             assert_eq!(borrow.len(), 3);
             assert_eq!(borrow[2], Datum::String("second"));
         }
+
+        {
+            let borrow = d.borrow_with_len(16);
+            assert_eq!(borrow.len(), 0);
+            assert!(borrow.capacity() >= 16);
+        }
     }
 }
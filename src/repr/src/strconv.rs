@@ -744,11 +744,15 @@ where
     }
 }
 
+/// Parses an array literal into its dimensions and its elements, in row-major order.
+///
+/// Multi-dimensional literals must be rectangular: the sub-arrays at each level of nesting must
+/// all have the same length. An empty literal has no dimensions.
 pub fn parse_array<'a, T, E>(
     s: &'a str,
     make_null: impl FnMut() -> T,
     gen_elem: impl FnMut(Cow<'a, str>) -> Result<T, E>,
-) -> Result<Vec<T>, ParseError>
+) -> Result<(Vec<ArrayDimension>, Vec<T>), ParseError>
 where
     E: fmt::Display,
 {
@@ -760,7 +764,7 @@ fn parse_array_inner<'a, T, E>(
     s: &'a str,
     mut make_null: impl FnMut() -> T,
     mut gen_elem: impl FnMut(Cow<'a, str>) -> Result<T, E>,
-) -> Result<Vec<T>, String>
+) -> Result<(Vec<ArrayDimension>, Vec<T>), String>
 where
     E: fmt::Display,
 {
@@ -772,14 +776,59 @@ where
     }
 
     let mut gen = |elem| gen_elem(elem).map_err_to_string();
+    let mut shape = ArrayShape::default();
+    parse_sub_array(buf, 0, &mut shape, &mut elems, &mut make_null, &mut gen)?;
+
+    if buf.next().is_some() {
+        bail!("malformed array literal: junk after closing right brace");
+    }
+
+    // Arrays without elements, like `{{}}`, have no dimensions.
+    if elems.is_empty() {
+        return Ok((vec![], elems));
+    }
+    let dims = shape
+        .lengths
+        .into_iter()
+        .flatten()
+        .map(|length| ArrayDimension {
+            lower_bound: 1,
+            length,
+        })
+        .collect();
+    Ok((dims, elems))
+}
+
+const MISMATCHED_ARRAY_DIMENSIONS: &str =
+    "malformed array literal: multidimensional arrays must have sub-arrays with matching dimensions";
+
+/// The shape of the array literal parsed so far.
+#[derive(Default)]
+struct ArrayShape {
+    /// The length of each dimension, outermost first, once a sub-array has been closed at it.
+    lengths: Vec<Option<usize>>,
+    /// The nesting depth that elements appear at, once one has.
+    element_depth: Option<usize>,
+}
+
+/// Parses the sub-array at nesting depth `depth`, whose opening brace has been consumed, pushing
+/// its elements to `elems`.
+fn parse_sub_array<'a, T>(
+    buf: &mut LexBuf<'a>,
+    depth: usize,
+    shape: &mut ArrayShape,
+    elems: &mut Vec<T>,
+    make_null: &mut impl FnMut() -> T,
+    gen: &mut impl FnMut(Cow<'a, str>) -> Result<T, String>,
+) -> Result<(), String> {
     let is_special_char = |c| matches!(c, '{' | '}' | ',' | '\\' | '"');
     let is_end_of_literal = |c| matches!(c, ',' | '}');
-
+    let mut length = 0;
     loop {
         buf.take_while(|ch| ch.is_ascii_whitespace());
         match buf.next() {
             Some('}') => break,
-            _ if elems.len() == 0 => {
+            _ if length == 0 => {
                 buf.prev();
             }
             Some(',') => {}
@@ -788,23 +837,39 @@ where
         }
         buf.take_while(|ch| ch.is_ascii_whitespace());
 
-        let elem = match buf.peek() {
-            Some('"') => gen(lex_quoted_element(buf)?)?,
-            Some('{') => bail!("parsing multi-dimensional arrays is not supported"),
-            Some(_) => match lex_unquoted_element(buf, is_special_char, is_end_of_literal)? {
-                Some(elem) => gen(elem)?,
-                None => make_null(),
-            },
-            None => bail!("unexpected end of input"),
-        };
-        elems.push(elem);
+        if buf.peek() == Some('{') {
+            if shape.element_depth.map_or(false, |d| d <= depth) {
+                bail!("{}", MISMATCHED_ARRAY_DIMENSIONS);
+            }
+            buf.next();
+            parse_sub_array(buf, depth + 1, shape, elems, make_null, gen)?;
+        } else {
+            if *shape.element_depth.get_or_insert(depth) != depth || shape.lengths.len() > depth + 1
+            {
+                bail!("{}", MISMATCHED_ARRAY_DIMENSIONS);
+            }
+            let elem = match buf.peek() {
+                Some('"') => gen(lex_quoted_element(buf)?)?,
+                Some(_) => match lex_unquoted_element(buf, is_special_char, is_end_of_literal)? {
+                    Some(elem) => gen(elem)?,
+                    None => make_null(),
+                },
+                None => bail!("unexpected end of input"),
+            };
+            elems.push(elem);
+        }
+        length += 1;
     }
 
-    if buf.next().is_some() {
-        bail!("malformed array literal: junk after closing right brace");
+    if shape.lengths.len() <= depth {
+        shape.lengths.resize(depth + 1, None);
     }
-
-    Ok(elems)
+    match shape.lengths[depth] {
+        Some(expected) if expected != length => bail!("{}", MISMATCHED_ARRAY_DIMENSIONS),
+        Some(_) => {}
+        None => shape.lengths[depth] = Some(length),
+    }
+    Ok(())
 }
 
 pub fn parse_list<'a, T, E>(
@@ -1879,4 +1944,54 @@ mod tests {
             assert_eq!(&buf, expect);
         }
     }
+
+    #[test]
+    fn test_parse_array() {
+        fn parse(s: &str) -> Result<(Vec<usize>, Vec<Option<String>>), String> {
+            let (dims, elems) = parse_array(s, || None, |elem| Ok::<_, String>(Some(elem.into())))
+                .map_err(|e| e.to_string())?;
+            Ok((dims.into_iter().map(|dim| dim.length).collect(), elems))
+        }
+        let elems = |elems: &[Option<&str>]| -> Vec<Option<String>> {
+            elems.iter().map(|elem| elem.map(String::from)).collect()
+        };
+
+        assert_eq!(parse("{}"), Ok((vec![], vec![])));
+        assert_eq!(parse("{{}}"), Ok((vec![], vec![])));
+        assert_eq!(parse("{{},{}}"), Ok((vec![], vec![])));
+        assert_eq!(
+            parse("{a, NULL, \"b,c\"}"),
+            Ok((vec![3], elems(&[Some("a"), None, Some("b,c")])))
+        );
+        assert_eq!(
+            parse("{{a,b,c},{NULL,\"{}\",f}}"),
+            Ok((
+                vec![2, 3],
+                elems(&[Some("a"), Some("b"), Some("c"), None, Some("{}"), Some("f")])
+            ))
+        );
+        assert_eq!(
+            parse("{{{1},{2}},{{3},{4}}}"),
+            Ok((
+                vec![2, 2, 1],
+                elems(&[Some("1"), Some("2"), Some("3"), Some("4")])
+            ))
+        );
+
+        for mismatched in [
+            "{{1,2},{3}}",
+            "{{1},2}",
+            "{1,{2}}",
+            "{{1},{{2}}}",
+            "{{},{1}}",
+            "{{},1}",
+        ] {
+            assert!(
+                parse(mismatched)
+                    .unwrap_err()
+                    .contains("sub-arrays with matching dimensions"),
+                "{mismatched}"
+            );
+        }
+    }
 }
@@ -377,6 +377,7 @@ pub enum SourceIncludeMetadataType {
     Topic,
     Offset,
     Headers,
+    RawValue,
 }
 
 impl AstDisplay for SourceIncludeMetadataType {
@@ -388,6 +389,7 @@ impl AstDisplay for SourceIncludeMetadataType {
             SourceIncludeMetadataType::Topic => f.write_str("TOPIC"),
             SourceIncludeMetadataType::Offset => f.write_str("OFFSET"),
             SourceIncludeMetadataType::Headers => f.write_str("HEADERS"),
+            SourceIncludeMetadataType::RawValue => f.write_str("RAW VALUE"),
         }
     }
 }
@@ -655,6 +657,7 @@ pub enum PostgresConnectionOptionName {
     SslCertificateAuthority,
     SslKey,
     SslMode,
+    SslSni,
     User,
 }
 
@@ -671,6 +674,7 @@ impl AstDisplay for PostgresConnectionOptionName {
             PostgresConnectionOptionName::SslCertificateAuthority => "SSL CERTIFICATE AUTHORITY",
             PostgresConnectionOptionName::SslKey => "SSL KEY",
             PostgresConnectionOptionName::SslMode => "SSL MODE",
+            PostgresConnectionOptionName::SslSni => "SSL SNI",
             PostgresConnectionOptionName::User => "USER",
         })
     }
@@ -956,10 +960,56 @@ pub struct KafkaSourceConnection<T: AstInfo> {
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum PgConfigOptionName {
+    /// The file to which the raw replication stream is copied before decoding
+    CaptureRawWal,
+    /// The least severe messages that upstream sends to and logs for the source's connections
+    ClientMinMessages,
+    /// How long the snapshot of a table may wait for the next chunk of rows
+    CopyReadTimeout,
     /// Hex encoded string of binary serialization of `dataflow_types::PostgresSourceDetails`
     Details,
+    /// Whether to ask the upstream server to stream large in-progress transactions
+    EnableStreamingTransactions,
+    /// The number of buffered changes after which a large transaction is split
+    MaxTransactionRows,
+    /// What to do when an upstream table's schema changes incompatibly
+    OnSchemaChange,
+    /// What to do when an upstream table is truncated
+    OnTruncate,
+    /// What to do with upstream tables that are unlogged
+    OnUnloggedTable,
+    /// How long the replication connection may be idle before its liveness is checked
+    PingInterval,
+    /// Whether to attach the upstream table, transaction and LSN of each change as headers
+    ProvenanceHeaders,
     /// The name of the publication to sync
     Publication,
+    /// The logical decoding output plugin to replicate changes with
+    ReplicationPlugin,
+    /// The existing replication slot to replicate from, e.g. one left behind by Debezium
+    ReplicationSlot,
+    /// The standby to replicate from instead of the server of the connection
+    ReplicationStandby,
+    /// The number of rows to fetch at a time when snapshotting tables through a cursor
+    SnapshotCursorFetchSize,
+    /// The `COPY` format in which tables are snapshotted
+    SnapshotFormat,
+    /// The order in which tables are snapshotted
+    SnapshotOrder,
+    /// The standby that tables are snapshotted from instead
+    SnapshotStandby,
+    /// The statement timeout of the queries that snapshot tables
+    SnapshotStatementTimeout,
+    /// The tables whose deleted rows are kept and flagged as deleted instead of retracted
+    SoftDeleteTables,
+    /// Whether to emit large transactions in several parts to bound memory usage
+    SplitLargeTransactions,
+    /// Whether to pause WAL replay on a standby while snapshotting from it
+    SynchronizeReplicas,
+    /// The OIDs of the only tables of the publication that produce output
+    TableOidAllowlist,
+    /// The names that tables are expected to be renamed to upstream, by OID
+    TableRenames,
     /// Columns whose types you want to unconditionally format as text
     TextColumns,
 }
@@ -967,8 +1017,31 @@ pub enum PgConfigOptionName {
 impl AstDisplay for PgConfigOptionName {
     fn fmt<W: fmt::Write>(&self, f: &mut AstFormatter<W>) {
         f.write_str(match self {
+            PgConfigOptionName::CaptureRawWal => "CAPTURE RAW WAL",
+            PgConfigOptionName::ClientMinMessages => "CLIENT MIN MESSAGES",
+            PgConfigOptionName::CopyReadTimeout => "COPY READ TIMEOUT",
             PgConfigOptionName::Details => "DETAILS",
+            PgConfigOptionName::EnableStreamingTransactions => "ENABLE STREAMING TRANSACTIONS",
+            PgConfigOptionName::MaxTransactionRows => "MAX TRANSACTION ROWS",
+            PgConfigOptionName::OnSchemaChange => "ON SCHEMA CHANGE",
+            PgConfigOptionName::OnTruncate => "ON TRUNCATE",
+            PgConfigOptionName::OnUnloggedTable => "ON UNLOGGED TABLE",
+            PgConfigOptionName::PingInterval => "PING INTERVAL",
+            PgConfigOptionName::ProvenanceHeaders => "PROVENANCE HEADERS",
             PgConfigOptionName::Publication => "PUBLICATION",
+            PgConfigOptionName::ReplicationPlugin => "REPLICATION PLUGIN",
+            PgConfigOptionName::ReplicationSlot => "REPLICATION SLOT",
+            PgConfigOptionName::ReplicationStandby => "REPLICATION STANDBY",
+            PgConfigOptionName::SnapshotCursorFetchSize => "SNAPSHOT CURSOR FETCH SIZE",
+            PgConfigOptionName::SnapshotFormat => "SNAPSHOT FORMAT",
+            PgConfigOptionName::SnapshotOrder => "SNAPSHOT ORDER",
+            PgConfigOptionName::SnapshotStandby => "SNAPSHOT STANDBY",
+            PgConfigOptionName::SnapshotStatementTimeout => "SNAPSHOT STATEMENT TIMEOUT",
+            PgConfigOptionName::SoftDeleteTables => "SOFT DELETE TABLES",
+            PgConfigOptionName::SplitLargeTransactions => "SPLIT LARGE TRANSACTIONS",
+            PgConfigOptionName::SynchronizeReplicas => "SYNCHRONIZE REPLICAS",
+            PgConfigOptionName::TableOidAllowlist => "TABLE OID ALLOWLIST",
+            PgConfigOptionName::TableRenames => "TABLE RENAMES",
             PgConfigOptionName::TextColumns => "TEXT COLUMNS",
         })
     }
@@ -742,6 +742,7 @@ impl_display_t!(CreateSubsourceStatement);
 pub enum CreateSinkOptionName {
     Size,
     Snapshot,
+    IncludeLsnHeader,
 }
 
 impl AstDisplay for CreateSinkOptionName {
@@ -753,6 +754,9 @@ impl AstDisplay for CreateSinkOptionName {
             CreateSinkOptionName::Snapshot => {
                 f.write_str("SNAPSHOT");
             }
+            CreateSinkOptionName::IncludeLsnHeader => {
+                f.write_str("INCLUDE LSN HEADER");
+            }
         }
     }
 }
@@ -1532,6 +1536,7 @@ impl<T: AstInfo> AstDisplay for AlterSinkStatement<T> {
 pub enum AlterSourceAction<T: AstInfo> {
     SetOptions(Vec<CreateSourceOption<T>>),
     ResetOptions(Vec<CreateSourceOptionName>),
+    SetPostgresOptions(Vec<PgConfigOption<T>>),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -1561,6 +1566,11 @@ impl<T: AstInfo> AstDisplay for AlterSourceStatement<T> {
                 f.write_node(&display::comma_separated(options));
                 f.write_str(")");
             }
+            AlterSourceAction::SetPostgresOptions(options) => {
+                f.write_str("SET POSTGRES OPTIONS (");
+                f.write_node(&display::comma_separated(options));
+                f.write_str(")");
+            }
         }
     }
 }
@@ -2314,7 +2314,7 @@ impl<'a> Parser<'a> {
                     value: Some(self.parse_object_option_value()?),
                 });
             }
-            SSL => match self.expect_one_of_keywords(&[CERTIFICATE, MODE, KEY])? {
+            SSL => match self.expect_one_of_keywords(&[CERTIFICATE, MODE, KEY, SNI])? {
                 CERTIFICATE => {
                     if self.parse_keyword(AUTHORITY) {
                         PostgresConnectionOptionName::SslCertificateAuthority
@@ -2324,6 +2324,7 @@ impl<'a> Parser<'a> {
                 }
                 KEY => PostgresConnectionOptionName::SslKey,
                 MODE => PostgresConnectionOptionName::SslMode,
+                SNI => PostgresConnectionOptionName::SslSni,
                 _ => unreachable!(),
             },
             USER | USERNAME => PostgresConnectionOptionName::User,
@@ -2635,7 +2636,11 @@ impl<'a> Parser<'a> {
 
     /// Parse the name of a CREATE SINK optional parameter
     fn parse_create_sink_option_name(&mut self) -> Result<CreateSinkOptionName, ParserError> {
-        let name = match self.expect_one_of_keywords(&[SIZE, SNAPSHOT])? {
+        let name = match self.expect_one_of_keywords(&[INCLUDE, SIZE, SNAPSHOT])? {
+            INCLUDE => {
+                self.expect_keywords(&[LSN, HEADER])?;
+                CreateSinkOptionName::IncludeLsnHeader
+            }
             SIZE => CreateSinkOptionName::Size,
             SNAPSHOT => CreateSinkOptionName::Snapshot,
             _ => unreachable!(),
@@ -2722,9 +2727,126 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_pg_connection_option(&mut self) -> Result<PgConfigOption<Raw>, ParserError> {
-        let name = match self.expect_one_of_keywords(&[DETAILS, PUBLICATION, TEXT])? {
+        let name = match self.expect_one_of_keywords(&[
+            CAPTURE,
+            CLIENT,
+            COPY,
+            DETAILS,
+            ENABLE,
+            MAX,
+            ON,
+            PING,
+            PROVENANCE,
+            PUBLICATION,
+            REPLICATION,
+            SNAPSHOT,
+            SOFT,
+            SPLIT,
+            SYNCHRONIZE,
+            TABLE,
+            TEXT,
+        ])? {
+            CAPTURE => {
+                self.expect_keywords(&[RAW, WAL])?;
+                PgConfigOptionName::CaptureRawWal
+            }
+            CLIENT => {
+                self.expect_keywords(&[MIN, MESSAGES])?;
+                PgConfigOptionName::ClientMinMessages
+            }
+            COPY => {
+                self.expect_keywords(&[READ, TIMEOUT])?;
+                PgConfigOptionName::CopyReadTimeout
+            }
             DETAILS => PgConfigOptionName::Details,
+            ENABLE => {
+                self.expect_keywords(&[STREAMING, TRANSACTIONS])?;
+                PgConfigOptionName::EnableStreamingTransactions
+            }
+            MAX => {
+                self.expect_keywords(&[TRANSACTION, ROWS])?;
+                PgConfigOptionName::MaxTransactionRows
+            }
+            ON => match self.expect_one_of_keywords(&[SCHEMA, TRUNCATE, UNLOGGED])? {
+                SCHEMA => {
+                    self.expect_keyword(CHANGE)?;
+                    PgConfigOptionName::OnSchemaChange
+                }
+                TRUNCATE => PgConfigOptionName::OnTruncate,
+                UNLOGGED => {
+                    self.expect_keyword(TABLE)?;
+                    PgConfigOptionName::OnUnloggedTable
+                }
+                _ => unreachable!(),
+            },
+            PING => {
+                self.expect_keyword(INTERVAL)?;
+                PgConfigOptionName::PingInterval
+            }
+            PROVENANCE => {
+                self.expect_keyword(HEADERS)?;
+                PgConfigOptionName::ProvenanceHeaders
+            }
             PUBLICATION => PgConfigOptionName::Publication,
+            REPLICATION => match self.expect_one_of_keywords(&[PLUGIN, SLOT, STANDBY])? {
+                PLUGIN => PgConfigOptionName::ReplicationPlugin,
+                SLOT => PgConfigOptionName::ReplicationSlot,
+                STANDBY => PgConfigOptionName::ReplicationStandby,
+                _ => unreachable!(),
+            },
+            SNAPSHOT => {
+                match self.expect_one_of_keywords(&[CURSOR, FORMAT, ORDER, STANDBY, STATEMENT])? {
+                    CURSOR => {
+                        self.expect_keywords(&[FETCH, SIZE])?;
+                        PgConfigOptionName::SnapshotCursorFetchSize
+                    }
+                    FORMAT => PgConfigOptionName::SnapshotFormat,
+                    ORDER => PgConfigOptionName::SnapshotOrder,
+                    STANDBY => PgConfigOptionName::SnapshotStandby,
+                    STATEMENT => {
+                        self.expect_keyword(TIMEOUT)?;
+                        PgConfigOptionName::SnapshotStatementTimeout
+                    }
+                    _ => unreachable!(),
+                }
+            }
+            SOFT => {
+                self.expect_keywords(&[DELETE, TABLES])?;
+
+                let _ = self.consume_token(&Token::Eq);
+
+                let value = self
+                    .parse_option_sequence(Parser::parse_object_name)?
+                    .map(|inner| {
+                        WithOptionValue::Sequence(
+                            inner
+                                .into_iter()
+                                .map(WithOptionValue::UnresolvedItemName)
+                                .collect_vec(),
+                        )
+                    });
+
+                return Ok(PgConfigOption {
+                    name: PgConfigOptionName::SoftDeleteTables,
+                    value,
+                });
+            }
+            SPLIT => {
+                self.expect_keywords(&[LARGE, TRANSACTIONS])?;
+                PgConfigOptionName::SplitLargeTransactions
+            }
+            SYNCHRONIZE => {
+                self.expect_keyword(REPLICAS)?;
+                PgConfigOptionName::SynchronizeReplicas
+            }
+            TABLE => match self.expect_one_of_keywords(&[OID, RENAMES])? {
+                OID => {
+                    self.expect_keyword(ALLOWLIST)?;
+                    PgConfigOptionName::TableOidAllowlist
+                }
+                RENAMES => PgConfigOptionName::TableRenames,
+                _ => unreachable!(),
+            },
             TEXT => {
                 self.expect_keyword(COLUMNS)?;
 
@@ -3206,15 +3328,19 @@ impl<'a> Parser<'a> {
     fn parse_source_include_metadata(&mut self) -> Result<Vec<SourceIncludeMetadata>, ParserError> {
         if self.parse_keyword(INCLUDE) {
             self.parse_comma_separated(|parser| {
-                let ty = match parser
-                    .expect_one_of_keywords(&[KEY, TIMESTAMP, PARTITION, TOPIC, OFFSET, HEADERS])?
-                {
+                let ty = match parser.expect_one_of_keywords(&[
+                    KEY, TIMESTAMP, PARTITION, TOPIC, OFFSET, HEADERS, RAW,
+                ])? {
                     KEY => SourceIncludeMetadataType::Key,
                     TIMESTAMP => SourceIncludeMetadataType::Timestamp,
                     PARTITION => SourceIncludeMetadataType::Partition,
                     TOPIC => SourceIncludeMetadataType::Topic,
                     OFFSET => SourceIncludeMetadataType::Offset,
                     HEADERS => SourceIncludeMetadataType::Headers,
+                    RAW => {
+                        parser.expect_keyword(VALUE)?;
+                        SourceIncludeMetadataType::RawValue
+                    }
                     _ => unreachable!("only explicitly allowed items can be parsed"),
                 };
                 let alias = parser
@@ -3726,6 +3852,18 @@ impl<'a> Parser<'a> {
                         action: AlterSourceAction::ResetOptions(reset_options),
                     })
                 }
+                SET if self.parse_keyword(POSTGRES) => {
+                    self.expect_keyword(OPTIONS)?;
+                    self.expect_token(&Token::LParen)?;
+                    let set_options =
+                        self.parse_comma_separated(Parser::parse_pg_connection_option)?;
+                    self.expect_token(&Token::RParen)?;
+                    Statement::AlterSource(AlterSourceStatement {
+                        source_name: name,
+                        if_exists,
+                        action: AlterSourceAction::SetPostgresOptions(set_options),
+                    })
+                }
                 SET => {
                     self.expect_token(&Token::LParen)?;
                     let set_options = self.parse_comma_separated(Parser::parse_source_option)?;
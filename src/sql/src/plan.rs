@@ -741,6 +741,10 @@ pub struct AlterSinkPlan {
 pub struct AlterSourcePlan {
     pub id: GlobalId,
     pub size: AlterOptionParameter,
+    /// The new `TABLE RENAMES` of a PostgreSQL source, if they are changed.
+    pub table_renames: Option<BTreeMap<u32, BTreeSet<String>>>,
+    /// The new `PUBLICATION` of a PostgreSQL source, if it is changed.
+    pub publication: Option<String>,
 }
 
 #[derive(Debug)]
@@ -102,6 +102,9 @@ pub enum PlanError {
     UnrecognizedTypeInPostgresSource {
         cols: Vec<(String, Oid)>,
     },
+    CompositeTypeInPostgresSource {
+        cols: Vec<String>,
+    },
     FetchingCsrSchemaFailed {
         schema_lookup: String,
         cause: Arc<dyn Error + Send + Sync>,
@@ -109,6 +112,9 @@ pub enum PlanError {
     FetchingPostgresPublicationInfoFailed {
         cause: Arc<mz_postgres_util::PostgresError>,
     },
+    PostgresSourceValidationFailed {
+        errors: Vec<mz_postgres_util::validation::ValidationIssue>,
+    },
     InvalidProtobufSchema {
         cause: protobuf_native::OperationFailedError,
     },
@@ -198,6 +204,10 @@ impl PlanError {
                 as text."
                     .into(),
             ),
+            Self::CompositeTypeInPostgresSource { cols: _ } => Some(
+                "Use the TEXT COLUMNS option naming the listed columns to ingest their values as text."
+                    .into(),
+            ),
             Self::FetchingPostgresPublicationInfoFailed { cause } => {
                 if let Some(cause) = cause.source() {
                     if let Some(cause) = cause.downcast_ref::<io::Error>() {
@@ -348,12 +358,29 @@ impl fmt::Display for PlanError {
                     )
                 )
             },
+            Self::CompositeTypeInPostgresSource { cols } => {
+                let mut cols = cols.to_owned();
+                cols.sort();
+
+                write!(
+                    f,
+                    "the following columns contain composite types, which are not supported:\n{}",
+                    itertools::join(cols, "\n")
+                )
+            }
             Self::FetchingCsrSchemaFailed { schema_lookup, .. } => {
                 write!(f, "failed to fetch schema {schema_lookup} from schema registry")
             }
             Self::FetchingPostgresPublicationInfoFailed { .. } => {
                 write!(f, "failed to fetch publication information from PostgreSQL database")
             }
+            Self::PostgresSourceValidationFailed { errors } => {
+                write!(
+                    f,
+                    "the PostgreSQL database is not configured for replication:\n{}",
+                    itertools::join(errors, "\n")
+                )
+            }
             Self::InvalidProtobufSchema { .. } => {
                 write!(f, "invalid protobuf schema")
             }
@@ -42,7 +42,7 @@ mod scl;
 pub(crate) mod show;
 mod tcl;
 
-pub(crate) use ddl::PgConfigOptionExtracted;
+pub(crate) use ddl::{PgConfigOptionExtracted, PG_PROVENANCE_COLUMN, PG_SOFT_DELETE_COLUMN};
 
 /// Describes the output of a SQL statement.
 #[derive(Debug, Clone, Eq, PartialEq)]
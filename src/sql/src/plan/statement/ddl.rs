@@ -55,10 +55,12 @@ use mz_storage_client::types::sources::encoding::{
     ProtobufEncoding, RegexEncoding, SourceDataEncoding, SourceDataEncodingInner,
 };
 use mz_storage_client::types::sources::{
-    GenericSourceConnection, IncludedColumnPos, KafkaSourceConnection, KeyEnvelope, LoadGenerator,
-    LoadGeneratorSourceConnection, PostgresSourceConnection, PostgresSourcePublicationDetails,
-    ProtoPostgresSourcePublicationDetails, SourceConnection, SourceDesc, SourceEnvelope,
-    TestScriptSourceConnection, Timeline, UnplannedSourceEnvelope, UpsertStyle,
+    standby_connection, GenericSourceConnection, IncludedColumnPos, KafkaSourceConnection,
+    KeyEnvelope, LoadGenerator, LoadGeneratorSourceConnection, PgLogLevel,
+    PostgresSourceConnection, PostgresSourcePublicationDetails,
+    ProtoPostgresSourcePublicationDetails, ReplicationPlugin, ReplicationSource, SnapshotFormat,
+    SnapshotOrder, SourceConnection, SourceDesc, SourceEnvelope, TestScriptSourceConnection,
+    Timeline, UnplannedSourceEnvelope, UpsertStyle,
 };
 
 use crate::ast::display::AstDisplay;
@@ -369,11 +371,42 @@ generate_extracted_config!(
 
 generate_extracted_config!(
     PgConfigOption,
+    (CaptureRawWal, String),
+    (ClientMinMessages, String),
+    (CopyReadTimeout, Interval),
     (Details, String),
+    (EnableStreamingTransactions, bool, Default(false)),
+    (MaxTransactionRows, u64),
+    (OnSchemaChange, String),
+    (OnTruncate, String),
+    (OnUnloggedTable, String),
+    (PingInterval, Interval),
+    (ProvenanceHeaders, bool, Default(false)),
     (Publication, String),
+    (ReplicationPlugin, String),
+    (ReplicationSlot, String),
+    (ReplicationStandby, String),
+    (SnapshotCursorFetchSize, u64),
+    (SnapshotFormat, String),
+    (SnapshotOrder, String),
+    (SnapshotStandby, String),
+    (SnapshotStatementTimeout, Interval),
+    (SoftDeleteTables, Vec::<UnresolvedItemName>, Default(vec![])),
+    (SplitLargeTransactions, bool, Default(false)),
+    (SynchronizeReplicas, bool, Default(false)),
+    (TableOidAllowlist, Vec::<u32>),
+    (TableRenames, Vec::<(u32, String)>, Default(vec![])),
     (TextColumns, Vec::<UnresolvedItemName>, Default(vec![]))
 );
 
+/// The name of the column appended to the subsources of the tables referenced in `SOFT DELETE
+/// TABLES`, which is true for the rows that were deleted upstream.
+pub(crate) const PG_SOFT_DELETE_COLUMN: &str = "mz_deleted";
+
+/// The name of the column appended to the subsources of sources with `PROVENANCE HEADERS`, which
+/// maps the names of the headers of each row to their values.
+pub(crate) const PG_PROVENANCE_COLUMN: &str = "mz_provenance";
+
 pub fn plan_create_source(
     scx: &StatementContext,
     stmt: CreateSourceStatement<Aug>,
@@ -497,6 +530,7 @@ pub fn plan_create_source(
                 include_topic: None,
                 include_offset: None,
                 include_headers: None,
+                include_raw_value: None,
             };
 
             let unwrap_name = |alias: Option<Ident>, default, pos| {
@@ -516,6 +550,13 @@ pub fn plan_create_source(
                 // TODO(guswynn): should this be `bail_unsupported!`?
                 sql_bail!("INCLUDE HEADERS requires ENVELOPE UPSERT or no ENVELOPE");
             }
+            if !matches!(envelope, Envelope::Upsert | Envelope::None)
+                && include_metadata
+                    .iter()
+                    .any(|sic| sic.ty == SourceIncludeMetadataType::RawValue)
+            {
+                sql_bail!("INCLUDE RAW VALUE requires ENVELOPE UPSERT or no ENVELOPE");
+            }
 
             for (pos, item) in include_metadata.iter().cloned().enumerate() {
                 match item.ty {
@@ -539,6 +580,10 @@ pub fn plan_create_source(
                     SourceIncludeMetadataType::Headers => {
                         connection.include_headers = unwrap_name(item.alias, "headers", pos);
                     }
+                    SourceIncludeMetadataType::RawValue => {
+                        connection.include_raw_value =
+                            unwrap_name(item.alias, "_mz_kafka_raw_value", pos);
+                    }
                     SourceIncludeMetadataType::Key => {} // handled below
                 }
             }
@@ -557,12 +602,219 @@ pub fn plan_create_source(
                 _ => sql_bail!("{} is not a postgres connection", connection_item.name()),
             };
             let PgConfigOptionExtracted {
+                capture_raw_wal,
+                client_min_messages,
+                copy_read_timeout,
                 details,
+                enable_streaming_transactions,
+                max_transaction_rows,
+                on_schema_change,
+                on_truncate,
+                on_unlogged_table,
+                ping_interval,
+                provenance_headers,
                 publication,
+                replication_plugin,
+                // Determines the slot in `details` during purification.
+                replication_slot: _,
+                replication_standby,
+                snapshot_cursor_fetch_size,
+                snapshot_format,
+                snapshot_order,
+                snapshot_standby,
+                snapshot_statement_timeout,
+                soft_delete_tables,
+                split_large_transactions,
+                synchronize_replicas,
+                table_oid_allowlist,
+                table_renames,
                 text_columns,
                 seen: _,
             } = options.clone().try_into()?;
 
+            let max_transaction_rows = match (split_large_transactions, max_transaction_rows) {
+                (true, Some(0)) => sql_bail!("MAX TRANSACTION ROWS must be greater than 0"),
+                (true, Some(rows)) => Some(usize::cast_from(rows)),
+                (true, None) => {
+                    sql_bail!("SPLIT LARGE TRANSACTIONS requires MAX TRANSACTION ROWS to be set")
+                }
+                (false, Some(_)) => {
+                    sql_bail!("MAX TRANSACTION ROWS requires SPLIT LARGE TRANSACTIONS = true")
+                }
+                (false, None) => None,
+            };
+
+            let ping_interval = match ping_interval {
+                Some(interval) => {
+                    let interval = interval.duration()?;
+                    if interval.is_zero() {
+                        sql_bail!("PING INTERVAL must be greater than 0");
+                    }
+                    Some(interval)
+                }
+                None => None,
+            };
+
+            let snapshot_cursor_fetch_size = match snapshot_cursor_fetch_size {
+                Some(0) => sql_bail!("SNAPSHOT CURSOR FETCH SIZE must be greater than 0"),
+                Some(rows) => Some(usize::cast_from(rows)),
+                None => None,
+            };
+
+            let snapshot_statement_timeout = snapshot_statement_timeout
+                .map(|timeout| timeout.duration())
+                .transpose()?;
+
+            let copy_read_timeout = match copy_read_timeout {
+                Some(timeout) => {
+                    let timeout = timeout.duration()?;
+                    if timeout.is_zero() {
+                        sql_bail!("COPY READ TIMEOUT must be greater than 0");
+                    }
+                    timeout
+                }
+                None => PostgresSourceConnection::DEFAULT_COPY_READ_TIMEOUT,
+            };
+
+            let snapshot_order = match snapshot_order.as_deref() {
+                None => SnapshotOrder::Size,
+                Some(order) if order.eq_ignore_ascii_case("size") => SnapshotOrder::Size,
+                Some(order) if order.eq_ignore_ascii_case("output") => SnapshotOrder::OutputIndex,
+                Some(order) => sql_bail!(
+                    "invalid SNAPSHOT ORDER {}: must be 'size' or 'output'",
+                    order.quoted()
+                ),
+            };
+
+            let snapshot_format = match snapshot_format.as_deref() {
+                None => SnapshotFormat::Text,
+                Some(format) if format.eq_ignore_ascii_case("text") => SnapshotFormat::Text,
+                Some(format) if format.eq_ignore_ascii_case("binary") => SnapshotFormat::Binary,
+                Some(format) => sql_bail!(
+                    "invalid SNAPSHOT FORMAT {}: must be 'text' or 'binary'",
+                    format.quoted()
+                ),
+            };
+            if snapshot_format == SnapshotFormat::Binary && snapshot_cursor_fetch_size.is_some() {
+                sql_bail!(
+                    "SNAPSHOT FORMAT 'binary' cannot be used with SNAPSHOT CURSOR FETCH SIZE"
+                );
+            }
+
+            let client_min_messages = match client_min_messages.as_deref() {
+                None => None,
+                Some(level) => match PgLogLevel::ALL
+                    .into_iter()
+                    .find(|candidate| candidate.name().eq_ignore_ascii_case(level))
+                {
+                    Some(level) => Some(level),
+                    None => sql_bail!(
+                        "invalid CLIENT MIN MESSAGES {}: must be one of {}",
+                        level.quoted(),
+                        PgLogLevel::ALL
+                            .iter()
+                            .map(|level| format!("'{}'", level.name()))
+                            .join(", ")
+                    ),
+                },
+            };
+
+            if let Some(path) = &capture_raw_wal {
+                scx.require_unsafe_mode("CAPTURE RAW WAL")?;
+                if path.is_empty() {
+                    sql_bail!("CAPTURE RAW WAL must not be empty");
+                }
+            }
+
+            // An empty allowlist would leave the source without tables to ingest.
+            if table_oid_allowlist
+                .as_ref()
+                .map_or(false, |oids| oids.is_empty())
+            {
+                sql_bail!("TABLE OID ALLOWLIST must not be empty");
+            }
+
+            let publication_table_rename_map = plan_table_renames(table_renames)?;
+
+            let resnapshot_on_schema_change = match on_schema_change.as_deref() {
+                None => false,
+                Some(policy) if policy.eq_ignore_ascii_case("error") => false,
+                Some(policy) if policy.eq_ignore_ascii_case("resnapshot") => true,
+                Some(policy) => sql_bail!(
+                    "invalid ON SCHEMA CHANGE {}: must be 'error' or 'resnapshot'",
+                    policy.quoted()
+                ),
+            };
+
+            let retract_on_truncate = match on_truncate.as_deref() {
+                None => false,
+                Some(policy) if policy.eq_ignore_ascii_case("error") => false,
+                Some(policy) if policy.eq_ignore_ascii_case("retract") => true,
+                Some(policy) => sql_bail!(
+                    "invalid ON TRUNCATE {}: must be 'error' or 'retract'",
+                    policy.quoted()
+                ),
+            };
+
+            let snapshot_unlogged_tables = match on_unlogged_table.as_deref() {
+                None => false,
+                Some(policy) if policy.eq_ignore_ascii_case("error") => false,
+                Some(policy) if policy.eq_ignore_ascii_case("snapshot") => true,
+                Some(policy) => sql_bail!(
+                    "invalid ON UNLOGGED TABLE {}: must be 'error' or 'snapshot'",
+                    policy.quoted()
+                ),
+            };
+
+            let replication_plugin = match replication_plugin.as_deref() {
+                None => ReplicationPlugin::PgOutput,
+                Some(plugin) if plugin.eq_ignore_ascii_case("pgoutput") => {
+                    ReplicationPlugin::PgOutput
+                }
+                Some(plugin) if plugin.eq_ignore_ascii_case("decoderbufs") => {
+                    ReplicationPlugin::DecoderBufs
+                }
+                Some(plugin) => sql_bail!(
+                    "invalid REPLICATION PLUGIN {}: must be 'pgoutput' or 'decoderbufs'",
+                    plugin.quoted()
+                ),
+            };
+            // decoderbufs neither streams transactions nor announces where a transaction
+            // commits before sending its changes, which splitting requires.
+            if replication_plugin == ReplicationPlugin::DecoderBufs {
+                if enable_streaming_transactions {
+                    sql_bail!(
+                        "ENABLE STREAMING TRANSACTIONS is not supported with \
+                         REPLICATION PLUGIN 'decoderbufs'"
+                    );
+                }
+                if max_transaction_rows.is_some() {
+                    sql_bail!(
+                        "SPLIT LARGE TRANSACTIONS is not supported with \
+                         REPLICATION PLUGIN 'decoderbufs'"
+                    );
+                }
+            }
+
+            let replication_source = match replication_standby {
+                None => ReplicationSource::Primary,
+                Some(connection_string) => ReplicationSource::Standby { connection_string },
+            };
+            // The standby is connected to with the settings of the connection, so its connection
+            // string is validated against them.
+            replication_source
+                .connection(&connection)
+                .map_err(|e| sql_err!("invalid REPLICATION STANDBY: {}", e))?;
+            if let Some(connection_string) = &snapshot_standby {
+                standby_connection(&connection, connection_string)
+                    .map_err(|e| sql_err!("invalid SNAPSHOT STANDBY: {}", e))?;
+                // The snapshot standby pauses its own replay while the snapshot is taken, and
+                // pausing the server we replicate from would not hold it still.
+                if synchronize_replicas {
+                    sql_bail!("SYNCHRONIZE REPLICAS is not supported with SNAPSHOT STANDBY");
+                }
+            }
+
             let details = details
                 .as_ref()
                 .ok_or_else(|| sql_err!("internal error: Postgres source missing details"))?;
@@ -612,6 +864,17 @@ pub fn plan_create_source(
                     .insert(col);
             }
 
+            // Look up the tables referenced in SOFT DELETE TABLES in the publication_catalog.
+            let soft_delete_tables: BTreeSet<u32> = soft_delete_tables
+                .into_iter()
+                .map(|name| {
+                    let (_name, table_desc) = publication_catalog
+                        .resolve(name)
+                        .expect("known to exist from purification");
+                    table_desc.oid
+                })
+                .collect();
+
             // Register the available subsources
             let mut available_subsources = BTreeMap::new();
 
@@ -742,6 +1005,14 @@ pub fn plan_create_source(
 
                     column_casts.push(mir_cast);
                 }
+                if soft_delete_tables.contains(&table.oid) {
+                    // Rows are live unless the source flags them when they are deleted.
+                    column_casts.push(
+                        HirScalarExpr::literal(mz_repr::Datum::False, ScalarType::Bool)
+                            .lower_uncorrelated()
+                            .expect("no correlation"),
+                    );
+                }
                 let r = table_casts.insert(i + 1, column_casts);
                 assert!(r.is_none(), "cannot have table defined multiple times");
 
@@ -766,6 +1037,27 @@ pub fn plan_create_source(
                 table_casts,
                 publication: publication.expect("validated exists during purification"),
                 publication_details,
+                streaming_transactions: enable_streaming_transactions,
+                max_transaction_rows,
+                ping_interval,
+                snapshot_cursor_fetch_size,
+                provenance_headers,
+                snapshot_statement_timeout,
+                resnapshot_on_schema_change,
+                replication_plugin,
+                synchronize_replicas,
+                replication_source,
+                snapshot_order,
+                capture_raw_wal,
+                soft_delete_tables,
+                snapshot_standby,
+                retract_on_truncate,
+                table_oid_allowlist: table_oid_allowlist.map(BTreeSet::from_iter),
+                publication_table_rename_map,
+                client_min_messages,
+                snapshot_unlogged_tables,
+                copy_read_timeout,
+                snapshot_format,
             });
             // The postgres source only outputs data to its subsources. The catalog object
             // representing the source itself is just an empty relation with no columns
@@ -1853,7 +2145,12 @@ pub fn describe_create_sink(
     Ok(StatementDesc::new(None))
 }
 
-generate_extracted_config!(CreateSinkOption, (Size, String), (Snapshot, bool));
+generate_extracted_config!(
+    CreateSinkOption,
+    (Size, String),
+    (Snapshot, bool),
+    (IncludeLsnHeader, bool, Default(false))
+);
 
 pub fn plan_create_sink(
     scx: &StatementContext,
@@ -1871,8 +2168,11 @@ pub fn plan_create_sink(
         with_options,
     } = stmt;
 
-    const SAFE_WITH_OPTIONS: &[CreateSinkOptionName] =
-        &[CreateSinkOptionName::Size, CreateSinkOptionName::Snapshot];
+    const SAFE_WITH_OPTIONS: &[CreateSinkOptionName] = &[
+        CreateSinkOptionName::Size,
+        CreateSinkOptionName::Snapshot,
+        CreateSinkOptionName::IncludeLsnHeader,
+    ];
 
     if with_options
         .iter()
@@ -1972,6 +2272,28 @@ pub fn plan_create_sink(
         return Err(PlanError::UpsertSinkWithoutKey);
     }
 
+    let CreateSinkOptionExtracted {
+        size,
+        snapshot,
+        include_lsn_header,
+        seen: _,
+    } = with_options.try_into()?;
+
+    // The LSNs of Postgres sources are reclocked away before any downstream dataflow sees the
+    // data, so they can only be sunk from the provenance headers the source attached to its rows.
+    let lsn_header_index = if include_lsn_header {
+        match desc.get_by_name(&ColumnName::from(PG_PROVENANCE_COLUMN)) {
+            Some((index, typ)) if matches!(typ.scalar_type, ScalarType::Map { .. }) => Some(index),
+            _ => sql_bail!(
+                "INCLUDE LSN HEADER requires a {} column, which PostgreSQL sources created with \
+                PROVENANCE HEADERS add to their subsources",
+                PG_PROVENANCE_COLUMN.quoted()
+            ),
+        }
+    } else {
+        None
+    };
+
     let connection_builder = match connection {
         CreateSinkConnection::Kafka { connection, .. } => kafka_sink_builder(
             scx,
@@ -1981,15 +2303,10 @@ pub fn plan_create_sink(
             key_desc_and_indices,
             desc.into_owned(),
             envelope,
+            lsn_header_index,
         )?,
     };
 
-    let CreateSinkOptionExtracted {
-        size,
-        snapshot,
-        seen: _,
-    } = with_options.try_into()?;
-
     let cluster_config = source_sink_cluster_config(scx, "sink", in_cluster.as_ref(), size)?;
 
     // WITH SNAPSHOT defaults to true
@@ -2074,6 +2391,7 @@ fn kafka_sink_builder(
     key_desc_and_indices: Option<(RelationDesc, Vec<usize>)>,
     value_desc: RelationDesc,
     envelope: SinkEnvelope,
+    lsn_header_index: Option<usize>,
 ) -> Result<StorageSinkConnectionBuilder, PlanError> {
     let item = scx.get_item_by_resolved_name(&connection)?;
     // Get Kafka connection
@@ -2233,6 +2551,7 @@ fn kafka_sink_builder(
             key_desc_and_indices,
             value_desc,
             retention,
+            lsn_header_index,
         },
     ))
 }
@@ -3091,6 +3410,7 @@ generate_extracted_config!(
     (SslCertificateAuthority, StringOrSecret),
     (SslKey, with_options::Secret),
     (SslMode, String),
+    (SslSni, String),
     (User, StringOrSecret)
 );
 
@@ -3133,6 +3453,7 @@ impl PostgresConnectionOptionExtracted {
             tls_mode,
             tls_root_cert: self.ssl_certificate_authority,
             tls_identity,
+            tls_sni_hostname: self.ssl_sni,
             user: self
                 .user
                 .ok_or_else(|| sql_err!("USER option is required"))?,
@@ -3986,7 +4307,8 @@ pub fn plan_alter_sink(
             let CreateSinkOptionExtracted {
                 size: size_opt,
                 snapshot,
-                seen: _,
+                include_lsn_header: _,
+                seen,
             } = options.try_into()?;
 
             if let Some(value) = size_opt {
@@ -3995,6 +4317,9 @@ pub fn plan_alter_sink(
             if let Some(_) = snapshot {
                 sql_bail!("Cannot modify the SNAPSHOT of a SINK.");
             }
+            if seen.contains(&CreateSinkOptionName::IncludeLsnHeader) {
+                sql_bail!("Cannot modify the INCLUDE LSN HEADER of a SINK.");
+            }
         }
         AlterSinkAction::ResetOptions(reset) => {
             for name in reset {
@@ -4005,6 +4330,9 @@ pub fn plan_alter_sink(
                     CreateSinkOptionName::Snapshot => {
                         sql_bail!("Cannot modify the SNAPSHOT of a SINK.");
                     }
+                    CreateSinkOptionName::IncludeLsnHeader => {
+                        sql_bail!("Cannot modify the INCLUDE LSN HEADER of a SINK.");
+                    }
                 }
             }
         }
@@ -4013,6 +4341,23 @@ pub fn plan_alter_sink(
     Ok(Plan::AlterSink(AlterSinkPlan { id, size }))
 }
 
+/// Returns the map of the table renames of a PostgreSQL source, from the OIDs of its tables to
+/// the names they are expected to be renamed to. A table may be expected to be renamed several
+/// times, e.g. to a name it is about to be renamed to while it still has the name it was
+/// previously renamed to.
+fn plan_table_renames(
+    table_renames: Vec<(u32, String)>,
+) -> Result<BTreeMap<u32, BTreeSet<String>>, PlanError> {
+    let mut rename_map: BTreeMap<_, BTreeSet<_>> = BTreeMap::new();
+    for (oid, name) in table_renames {
+        if name.is_empty() {
+            sql_bail!("TABLE RENAMES must not rename tables to empty names");
+        }
+        rename_map.entry(oid).or_default().insert(name);
+    }
+    Ok(rename_map)
+}
+
 pub fn describe_alter_source(
     _: &StatementContext,
     _: AlterSourceStatement<Aug>,
@@ -4057,6 +4402,8 @@ pub fn plan_alter_source(
     let id = entry.id();
 
     let mut size = AlterOptionParameter::Unchanged;
+    let mut table_renames = None;
+    let mut publication = None;
     match action {
         AlterSourceAction::SetOptions(options) => {
             let CreateSourceOptionExtracted {
@@ -4098,9 +4445,30 @@ pub fn plan_alter_source(
                 }
             }
         }
+        AlterSourceAction::SetPostgresOptions(options) => {
+            let extracted = PgConfigOptionExtracted::try_from(options)?;
+            for name in &extracted.seen {
+                match name {
+                    PgConfigOptionName::TableRenames => {
+                        table_renames = Some(plan_table_renames(extracted.table_renames.clone())?);
+                    }
+                    PgConfigOptionName::Publication => {
+                        publication = extracted.publication.clone();
+                    }
+                    name => {
+                        sql_bail!("Cannot modify the {} of a SOURCE.", name.to_ast_string());
+                    }
+                }
+            }
+        }
     };
 
-    Ok(Plan::AlterSource(AlterSourcePlan { id, size }))
+    Ok(Plan::AlterSource(AlterSourcePlan {
+        id,
+        size,
+        table_renames,
+        publication,
+    }))
 }
 
 pub fn describe_alter_system_set(
@@ -384,6 +384,33 @@ impl<V: ImpliedValue> ImpliedValue for Vec<V> {
     }
 }
 
+impl<A, B> TryFromValue<WithOptionValue<Aug>> for (A, B)
+where
+    A: TryFromValue<WithOptionValue<Aug>>,
+    B: TryFromValue<WithOptionValue<Aug>>,
+{
+    fn try_from_value(v: WithOptionValue<Aug>) -> Result<Self, PlanError> {
+        match v {
+            WithOptionValue::Sequence(a) if a.len() == 2 => {
+                let mut a = a.into_iter();
+                let first = A::try_from_value(a.next().expect("known to exist"))?;
+                let second = B::try_from_value(a.next().expect("known to exist"))?;
+                Ok((first, second))
+            }
+            _ => sql_bail!("cannot use value as pair"),
+        }
+    }
+    fn name() -> String {
+        format!("pair of {} and {}", A::name(), B::name())
+    }
+}
+
+impl<A, B> ImpliedValue for (A, B) {
+    fn implied_value() -> Result<Self, PlanError> {
+        sql_bail!("must provide a pair of values")
+    }
+}
+
 impl<T: AstInfo, V: TryFromValue<WithOptionValue<T>>> TryFromValue<WithOptionValue<T>>
     for Option<V>
 {
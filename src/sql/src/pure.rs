@@ -21,12 +21,15 @@ use mz_repr::adt::system::Oid;
 use prost::Message;
 use protobuf_native::compiler::{SourceTreeDescriptorDatabase, VirtualSourceTree};
 use protobuf_native::MessageLite;
-use tracing::info;
+use tracing::{info, warn};
 use uuid::Uuid;
 
 use mz_ccsr::Schema as CcsrSchema;
 use mz_ccsr::{Client, GetByIdError, GetBySubjectError};
 use mz_ore::str::StrExt;
+use mz_postgres_util::desc::PostgresTableDesc;
+use mz_postgres_util::validation::ValidationIssue;
+use mz_postgres_util::PublicationFilter;
 use mz_proto::RustType;
 use mz_repr::{strconv, GlobalId};
 use mz_sql_parser::ast::display::AstDisplay;
@@ -51,6 +54,7 @@ use crate::names::{Aug, RawDatabaseSpecifier};
 use crate::normalize;
 use crate::plan::error::PlanError;
 use crate::plan::statement::ddl::load_generator_ast_to_generator;
+use crate::plan::statement::{PG_PROVENANCE_COLUMN, PG_SOFT_DELETE_COLUMN};
 use crate::plan::StatementContext;
 
 fn subsource_gen<'a, T>(
@@ -113,6 +117,10 @@ fn subsource_name_gen(
 ///
 /// See the section on [purification](crate#purification) in the crate
 /// documentation for details.
+///
+/// Besides the subsources and the purified statement, returns the estimated
+/// number of bytes the initial snapshot of a Postgres source takes up in
+/// storage, if it could be determined.
 pub async fn purify_create_source(
     catalog: Box<dyn SessionCatalog>,
     now: u64,
@@ -122,6 +130,7 @@ pub async fn purify_create_source(
     (
         Vec<(GlobalId, CreateSubsourceStatement<Aug>)>,
         CreateSourceStatement<Aug>,
+        Option<u64>,
     ),
     PlanError,
 > {
@@ -164,6 +173,7 @@ pub async fn purify_create_source(
     };
 
     let mut subsources = vec![];
+    let mut estimated_storage_bytes = None;
 
     let progress_desc = match &connection {
         CreateSourceConnection::Kafka(_) => &mz_storage_client::types::sources::KAFKA_PROGRESS_DESC,
@@ -282,8 +292,14 @@ pub async fn purify_create_source(
                 }
             };
             let crate::plan::statement::PgConfigOptionExtracted {
+                on_unlogged_table,
                 publication,
+                replication_slot,
                 mut text_columns,
+                mut soft_delete_tables,
+                provenance_headers,
+                table_oid_allowlist,
+                table_renames,
                 ..
             } = options.clone().try_into()?;
             let publication = publication
@@ -294,12 +310,22 @@ pub async fn purify_create_source(
                 .config(&*connection_context.secrets_reader)
                 .await?;
             let publication_tables =
-                mz_postgres_util::publication_info(&config, &publication, None)
+                mz_postgres_util::publication_info(&config, &publication, &PublicationFilter::All)
                     .await
                     .map_err(|cause| PlanError::FetchingPostgresPublicationInfoFailed {
                         cause: Arc::new(cause),
                     })?;
 
+            // The estimate only informs the user, so failing to compute it must not fail the
+            // creation of the source.
+            match mz_postgres_util::estimate_publication_storage_bytes(&config, &publication).await
+            {
+                Ok(bytes) => estimated_storage_bytes = Some(bytes),
+                Err(e) => {
+                    warn!("failed to estimate the storage size of publication {publication}: {e}")
+                }
+            }
+
             // An index from table name -> schema name -> database name -> PostgresTableDesc
             let mut tables_by_name = BTreeMap::new();
             for table in &publication_tables {
@@ -314,6 +340,26 @@ pub async fn purify_create_source(
 
             let publication_catalog = ErsatzCatalog(tables_by_name);
 
+            for oid in table_oid_allowlist.iter().flatten() {
+                if !publication_tables.iter().any(|table| table.oid == *oid) {
+                    return Err(PlanError::InvalidOptionValue {
+                        option_name: PgConfigOptionName::TableOidAllowlist.to_ast_string(),
+                        err: Box::new(sql_err!(
+                            "publication {} contains no table with OID {}",
+                            publication.quoted(),
+                            oid
+                        )),
+                    });
+                }
+            }
+            // The tables that the allowlist excludes produce no output, so they get no
+            // subsources.
+            let allowlisted = |table: &PostgresTableDesc| {
+                table_oid_allowlist
+                    .as_ref()
+                    .map_or(true, |oids| oids.contains(&table.oid))
+            };
+
             let mut targeted_subsources = vec![];
 
             let mut validated_requested_subsources = vec![];
@@ -322,7 +368,7 @@ pub async fn purify_create_source(
                     if publication_tables.is_empty() {
                         sql_bail!("FOR ALL TABLES is only valid for non-empty publications");
                     }
-                    for table in &publication_tables {
+                    for table in publication_tables.iter().filter(|table| allowlisted(table)) {
                         let upstream_name = UnresolvedItemName::qualified(&[
                             &connection.database,
                             &table.namespace,
@@ -344,12 +390,58 @@ pub async fn purify_create_source(
                         &publication_catalog,
                         source_name,
                     )?);
+                    if let Some((upstream_name, _, table)) = validated_requested_subsources
+                        .iter()
+                        .find(|(_, _, table)| !allowlisted(table))
+                    {
+                        return Err(PlanError::InvalidOptionValue {
+                            option_name: PgConfigOptionName::TableOidAllowlist.to_ast_string(),
+                            err: Box::new(sql_err!(
+                                "table {} with OID {} is not in the allowlist, so it can't be ingested",
+                                upstream_name.to_ast_string(),
+                                table.oid
+                            )),
+                        });
+                    }
                 }
                 None => {
                     sql_bail!("multi-output sources require a FOR TABLES (..) or FOR ALL TABLES statement");
                 }
             };
 
+            // Check all other upstream prerequisites at once, so users learn about every problem
+            // rather than fixing them one failed attempt at a time.
+            let requested_tables: Vec<_> = validated_requested_subsources
+                .iter()
+                .map(|(_, _, table)| (*table).clone())
+                .collect();
+            let report = mz_postgres_util::validation::validate_postgres_source(
+                &config,
+                &publication,
+                &requested_tables,
+            )
+            .await
+            .map_err(|cause| PlanError::FetchingPostgresPublicationInfoFailed {
+                cause: Arc::new(cause),
+            })?;
+            // Unsupported types are reported below instead, once the TEXT COLUMNS that override
+            // them are known. Unlogged tables are fine if the source only snapshots them.
+            let snapshot_unlogged_tables = on_unlogged_table
+                .as_deref()
+                .map_or(false, |policy| policy.eq_ignore_ascii_case("snapshot"));
+            let errors: Vec<_> = report
+                .errors()
+                .filter(|issue| match issue {
+                    ValidationIssue::UnsupportedType { .. } => false,
+                    ValidationIssue::UnloggedTable { .. } => !snapshot_unlogged_tables,
+                    _ => true,
+                })
+                .cloned()
+                .collect();
+            if !errors.is_empty() {
+                return Err(PlanError::PostgresSourceValidationFailed { errors });
+            }
+
             let mut text_cols_dict: BTreeMap<u32, BTreeSet<String>> = BTreeMap::new();
 
             for name in text_columns.iter_mut() {
@@ -419,8 +511,78 @@ pub async fn purify_create_source(
                 text_cols_option.value = Some(WithOptionValue::Sequence(seq));
             }
 
-            // Aggregate all unrecognized types.
+            let mut soft_delete_oids = BTreeSet::new();
+
+            for name in soft_delete_tables.iter_mut() {
+                let (fully_qualified_name, desc) = publication_catalog
+                    .resolve(name.clone())
+                    .map_err(|e| PlanError::InvalidOptionValue {
+                        option_name: PgConfigOptionName::SoftDeleteTables.to_ast_string(),
+                        err: Box::new(e),
+                    })?;
+
+                if desc
+                    .columns
+                    .iter()
+                    .any(|column| column.name == PG_SOFT_DELETE_COLUMN)
+                {
+                    return Err(PlanError::InvalidOptionValue {
+                        option_name: PgConfigOptionName::SoftDeleteTables.to_ast_string(),
+                        err: Box::new(sql_err!(
+                            "table {} already has a column named {}",
+                            fully_qualified_name,
+                            PG_SOFT_DELETE_COLUMN.quoted()
+                        )),
+                    });
+                }
+
+                // Rewrite fully qualified name.
+                *name = fully_qualified_name;
+
+                if !soft_delete_oids.insert(desc.oid) {
+                    return Err(PlanError::InvalidOptionValue {
+                        option_name: PgConfigOptionName::SoftDeleteTables.to_ast_string(),
+                        err: Box::new(PlanError::UnexpectedDuplicateReference {
+                            name: name.clone(),
+                        }),
+                    });
+                }
+            }
+
+            // Normalize options to contain full qualified values.
+            if let Some(soft_delete_option) = options
+                .iter_mut()
+                .find(|option| option.name == PgConfigOptionName::SoftDeleteTables)
+            {
+                let seq = soft_delete_tables
+                    .into_iter()
+                    .map(WithOptionValue::UnresolvedItemName)
+                    .collect();
+                soft_delete_option.value = Some(WithOptionValue::Sequence(seq));
+            }
+
+            for (oid, _) in &table_renames {
+                if !publication_tables.iter().any(|table| table.oid == *oid) {
+                    return Err(PlanError::InvalidOptionValue {
+                        option_name: PgConfigOptionName::TableRenames.to_ast_string(),
+                        err: Box::new(sql_err!(
+                            "publication {} contains no table with OID {}",
+                            publication.quoted(),
+                            oid
+                        )),
+                    });
+                }
+            }
+
+            // The headers of each row map their names to their values.
+            let provenance_type = {
+                let data_type = mz_sql_parser::parser::parse_data_type("map[text => text]")?;
+                crate::names::resolve(scx.catalog, data_type)?.0
+            };
+
+            // Aggregate all unrecognized types, and separately those that are composite types.
             let mut unsupported_cols = vec![];
+            let mut composite_cols = vec![];
 
             // Now that we have an explicit list of validated requested subsources we can create them
             for (upstream_name, subsource_name, table) in validated_requested_subsources.into_iter()
@@ -436,10 +598,24 @@ pub async fn purify_create_source(
                             Err(_) => {
                                 let mut full_name = upstream_name.0.clone();
                                 full_name.push(name);
-                                unsupported_cols.push((
-                                    UnresolvedItemName(full_name).to_ast_string(),
-                                    Oid(c.type_oid),
-                                ));
+                                let full_name = UnresolvedItemName(full_name).to_ast_string();
+                                let qualified_table = format!("{}.{}", table.namespace, table.name);
+                                let composite = report.errors().any(|issue| {
+                                    matches!(
+                                        issue,
+                                        ValidationIssue::UnsupportedType {
+                                            table,
+                                            column,
+                                            composite: true,
+                                            ..
+                                        } if *table == qualified_table && *column == c.name
+                                    )
+                                });
+                                if composite {
+                                    composite_cols.push(full_name);
+                                } else {
+                                    unsupported_cols.push((full_name, Oid(c.type_oid)));
+                                }
                                 continue;
                             }
                         },
@@ -463,8 +639,51 @@ pub async fn purify_create_source(
                     });
                 }
 
+                let soft_delete = soft_delete_oids.contains(&table.oid);
+                if soft_delete {
+                    columns.push(ColumnDef {
+                        name: Ident::new(PG_SOFT_DELETE_COLUMN),
+                        data_type: scx.resolve_type(mz_pgrepr::Type::Bool)?,
+                        collation: None,
+                        options: vec![mz_sql_parser::ast::ColumnOptionDef {
+                            name: None,
+                            option: mz_sql_parser::ast::ColumnOption::NotNull,
+                        }],
+                    });
+                }
+
+                if provenance_headers {
+                    if table.columns.iter().any(|c| c.name == PG_PROVENANCE_COLUMN) {
+                        return Err(PlanError::InvalidOptionValue {
+                            option_name: PgConfigOptionName::ProvenanceHeaders.to_ast_string(),
+                            err: Box::new(sql_err!(
+                                "table {}.{} already has a column named {}",
+                                table.namespace,
+                                table.name,
+                                PG_PROVENANCE_COLUMN.quoted()
+                            )),
+                        });
+                    }
+                    columns.push(ColumnDef {
+                        name: Ident::new(PG_PROVENANCE_COLUMN),
+                        data_type: provenance_type.clone(),
+                        collation: None,
+                        options: vec![mz_sql_parser::ast::ColumnOptionDef {
+                            name: None,
+                            option: mz_sql_parser::ast::ColumnOption::NotNull,
+                        }],
+                    });
+                }
+
                 let mut constraints = vec![];
-                for key in table.keys.clone() {
+                // The rows that were deleted upstream are kept alongside the rows that reuse
+                // their keys, so the keys of soft delete tables do not hold in the subsource.
+                let keys = if soft_delete {
+                    vec![]
+                } else {
+                    table.keys.clone()
+                };
+                for key in keys {
                     let mut key_columns = vec![];
 
                     for col_num in key.cols {
@@ -527,6 +746,11 @@ pub async fn purify_create_source(
                 subsources.push((transient_id, subsource));
             }
 
+            if !composite_cols.is_empty() {
+                return Err(PlanError::CompositeTypeInPostgresSource {
+                    cols: composite_cols,
+                });
+            }
             if !unsupported_cols.is_empty() {
                 return Err(PlanError::UnrecognizedTypeInPostgresSource {
                     cols: unsupported_cols,
@@ -537,13 +761,37 @@ pub async fn purify_create_source(
 
             // Remove any old detail references
             options.retain(|PgConfigOption { name, .. }| name != &PgConfigOptionName::Details);
-            let details = PostgresSourcePublicationDetails {
-                tables: publication_tables,
-                slot: format!(
+            // An existing slot, e.g. one that Debezium replicated from, is taken over as is, and
+            // dropped along with the source.
+            let slot = match replication_slot {
+                Some(slot) => {
+                    if slot.is_empty()
+                        || slot.len() > 63
+                        || !slot
+                            .chars()
+                            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+                    {
+                        return Err(PlanError::InvalidOptionValue {
+                            option_name: PgConfigOptionName::ReplicationSlot.to_ast_string(),
+                            err: Box::new(sql_err!(
+                                "replication slot names may only contain lower case letters, \
+                                 numbers and the underscore character, and be at most 63 \
+                                 characters long"
+                            )),
+                        });
+                    }
+                    slot
+                }
+                None => format!(
                     "materialize_{}",
                     Uuid::new_v4().to_string().replace('-', "")
                 ),
             };
+            let details = PostgresSourcePublicationDetails {
+                tables: publication_tables,
+                slot,
+                publication: Some(publication),
+            };
             options.push(PgConfigOption {
                 name: PgConfigOptionName::Details,
                 value: Some(WithOptionValue::Value(Value::String(hex::encode(
@@ -709,7 +957,7 @@ pub async fn purify_create_source(
 
     purify_source_format(&*catalog, format, connection, envelope, &connection_context).await?;
 
-    Ok((subsources, stmt))
+    Ok((subsources, stmt, estimated_storage_bytes))
 }
 
 async fn purify_source_format(
@@ -508,6 +508,93 @@ const ENABLE_MULTI_WORKER_STORAGE_PERSIST_SINK: ServerVar<bool> = ServerVar {
     safe: true,
 };
 
+/// The maximum size in bytes of a single row decoded by a Postgres source.
+const PG_SOURCE_MAX_ROW_SIZE_BYTES: ServerVar<usize> = ServerVar {
+    name: UncasedStr::new("pg_source_max_row_size_bytes"),
+    value: &usize::MAX,
+    description: "The maximum size in bytes of a single row decoded by a Postgres source. \
+                  Rows exceeding this size put the source into an errored state (Materialize).",
+    internal: true,
+    safe: true,
+};
+
+/// The maximum number of changes a Postgres source buffers for a single upstream transaction.
+const PG_SOURCE_MAX_TRANSACTION_CHANGES: ServerVar<usize> = ServerVar {
+    name: UncasedStr::new("pg_source_max_transaction_changes"),
+    value: &usize::MAX,
+    description: "The maximum number of changes a Postgres source buffers for a single \
+                  upstream transaction. Transactions exceeding this size put the source into \
+                  an errored state (Materialize).",
+    internal: true,
+    safe: true,
+};
+
+/// How long the resume LSN of a Postgres source may stay unchanged before the source is reported
+/// as stalled.
+const PG_SOURCE_LSN_STALENESS_THRESHOLD: ServerVar<Duration> = ServerVar {
+    name: UncasedStr::new("pg_source_lsn_staleness_threshold"),
+    value: &Duration::from_secs(300),
+    description: "How long the resume LSN of a Postgres source may stay unchanged before the \
+                  source is reported as stalled (Materialize).",
+    internal: true,
+    safe: true,
+};
+
+/// How often a Postgres source audits the upstream schemas of its ingested tables.
+const PG_SOURCE_SCHEMA_AUDIT_INTERVAL: ServerVar<Duration> = ServerVar {
+    name: UncasedStr::new("pg_source_schema_audit_interval"),
+    value: &Duration::from_secs(60 * 60),
+    description: "How often a Postgres source re-checks the upstream schemas of its ingested \
+                  tables for changes (Materialize).",
+    internal: true,
+    safe: true,
+};
+
+/// How often a Postgres source at most answers the status update requests of its upstream.
+const PG_SOURCE_MIN_FEEDBACK_INTERVAL: ServerVar<Duration> = ServerVar {
+    name: UncasedStr::new("pg_source_min_feedback_interval"),
+    value: &Duration::from_secs(1),
+    description: "The minimum time between the status updates a Postgres source sends in reply \
+                  to the requests of its upstream. Requests that arrive in between are answered \
+                  by a single status update (Materialize).",
+    internal: true,
+    safe: true,
+};
+
+/// The size in bytes of the changes a Postgres source buffers for uncommitted upstream
+/// transactions beyond which it reports itself as degraded.
+const PG_SOURCE_TRANSACTION_BUFFER_DEGRADED_BYTES: ServerVar<usize> = ServerVar {
+    name: UncasedStr::new("pg_source_transaction_buffer_degraded_bytes"),
+    value: &(512 * 1024 * 1024),
+    description: "The size in bytes of the changes a Postgres source buffers for uncommitted \
+                  upstream transactions beyond which it reports itself as degraded (Materialize).",
+    internal: true,
+    safe: true,
+};
+
+/// How many bytes of WAL the changes a Postgres source emitted may be ahead of the ones that were
+/// committed downstream before it stops reading from its replication stream.
+const PG_SOURCE_BACKPRESSURE_LAG_BYTES: ServerVar<usize> = ServerVar {
+    name: UncasedStr::new("pg_source_backpressure_lag_bytes"),
+    value: &usize::MAX,
+    description: "How many bytes of WAL the changes a Postgres source emitted may be ahead of \
+                  the ones committed downstream before it stops reading from its replication \
+                  stream, leaving the WAL upstream until downstream catches up (Materialize).",
+    internal: true,
+    safe: true,
+};
+
+/// The Postgres sources whose replication is paused, e.g. during an upstream maintenance window.
+static DEFAULT_PG_SOURCE_PAUSED_IDS: Lazy<Vec<Ident>> = Lazy::new(Vec::new);
+static PG_SOURCE_PAUSED_IDS: Lazy<ServerVar<Vec<Ident>>> = Lazy::new(|| ServerVar {
+    name: UncasedStr::new("pg_source_paused_ids"),
+    value: &DEFAULT_PG_SOURCE_PAUSED_IDS,
+    description: "The IDs of the Postgres sources whose replication connection is paused, \
+                  keeping their replication slots for when they resume (Materialize).",
+    internal: true,
+    safe: true,
+});
+
 /// Controls the connection timeout to Cockroach.
 ///
 /// Used by persist as [`mz_persist_client::cfg::DynamicConfig::consensus_connect_timeout`].
@@ -1345,6 +1432,14 @@ impl Default for SystemVars {
             .with_var(&MAX_RESULT_SIZE)
             .with_var(&ALLOWED_CLUSTER_REPLICA_SIZES)
             .with_var(&ENABLE_MULTI_WORKER_STORAGE_PERSIST_SINK)
+            .with_var(&PG_SOURCE_MAX_ROW_SIZE_BYTES)
+            .with_var(&PG_SOURCE_MAX_TRANSACTION_CHANGES)
+            .with_var(&PG_SOURCE_LSN_STALENESS_THRESHOLD)
+            .with_var(&PG_SOURCE_PAUSED_IDS)
+            .with_var(&PG_SOURCE_SCHEMA_AUDIT_INTERVAL)
+            .with_var(&PG_SOURCE_MIN_FEEDBACK_INTERVAL)
+            .with_var(&PG_SOURCE_TRANSACTION_BUFFER_DEGRADED_BYTES)
+            .with_var(&PG_SOURCE_BACKPRESSURE_LAG_BYTES)
             .with_var(&PERSIST_BLOB_TARGET_SIZE)
             .with_var(&PERSIST_COMPACTION_MINIMUM_TIMEOUT)
             .with_var(&CRDB_CONNECT_TIMEOUT)
@@ -1574,6 +1669,49 @@ impl SystemVars {
         *self.expect_value(&ENABLE_MULTI_WORKER_STORAGE_PERSIST_SINK)
     }
 
+    /// Returns the `pg_source_max_row_size_bytes` configuration parameter.
+    pub fn pg_source_max_row_size_bytes(&self) -> usize {
+        *self.expect_value(&PG_SOURCE_MAX_ROW_SIZE_BYTES)
+    }
+
+    /// Returns the `pg_source_max_transaction_changes` configuration parameter.
+    pub fn pg_source_max_transaction_changes(&self) -> usize {
+        *self.expect_value(&PG_SOURCE_MAX_TRANSACTION_CHANGES)
+    }
+
+    /// Returns the `pg_source_lsn_staleness_threshold` configuration parameter.
+    pub fn pg_source_lsn_staleness_threshold(&self) -> Duration {
+        *self.expect_value(&PG_SOURCE_LSN_STALENESS_THRESHOLD)
+    }
+
+    /// Returns the `pg_source_schema_audit_interval` configuration parameter.
+    pub fn pg_source_schema_audit_interval(&self) -> Duration {
+        *self.expect_value(&PG_SOURCE_SCHEMA_AUDIT_INTERVAL)
+    }
+
+    /// Returns the `pg_source_min_feedback_interval` configuration parameter.
+    pub fn pg_source_min_feedback_interval(&self) -> Duration {
+        *self.expect_value(&PG_SOURCE_MIN_FEEDBACK_INTERVAL)
+    }
+
+    /// Returns the `pg_source_transaction_buffer_degraded_bytes` configuration parameter.
+    pub fn pg_source_transaction_buffer_degraded_bytes(&self) -> usize {
+        *self.expect_value(&PG_SOURCE_TRANSACTION_BUFFER_DEGRADED_BYTES)
+    }
+
+    /// Returns the `pg_source_backpressure_lag_bytes` configuration parameter.
+    pub fn pg_source_backpressure_lag_bytes(&self) -> usize {
+        *self.expect_value(&PG_SOURCE_BACKPRESSURE_LAG_BYTES)
+    }
+
+    /// Returns the value of the `pg_source_paused_ids` configuration parameter.
+    pub fn pg_source_paused_ids(&self) -> Vec<String> {
+        self.expect_value(&PG_SOURCE_PAUSED_IDS)
+            .into_iter()
+            .map(|s| s.as_str().into())
+            .collect()
+    }
+
     /// Returns the `persist_blob_target_size` configuration parameter.
     pub fn persist_blob_target_size(&self) -> usize {
         *self.expect_value(&PERSIST_BLOB_TARGET_SIZE)
@@ -2608,7 +2746,16 @@ pub fn is_compute_config_var(name: &str) -> bool {
 
 /// Returns whether the named variable is a storage configuration parameter.
 pub fn is_storage_config_var(name: &str) -> bool {
-    name == ENABLE_MULTI_WORKER_STORAGE_PERSIST_SINK.name() || is_persist_config_var(name)
+    name == ENABLE_MULTI_WORKER_STORAGE_PERSIST_SINK.name()
+        || name == PG_SOURCE_MAX_ROW_SIZE_BYTES.name()
+        || name == PG_SOURCE_MAX_TRANSACTION_CHANGES.name()
+        || name == PG_SOURCE_LSN_STALENESS_THRESHOLD.name()
+        || name == PG_SOURCE_PAUSED_IDS.name()
+        || name == PG_SOURCE_SCHEMA_AUDIT_INTERVAL.name()
+        || name == PG_SOURCE_MIN_FEEDBACK_INTERVAL.name()
+        || name == PG_SOURCE_TRANSACTION_BUFFER_DEGRADED_BYTES.name()
+        || name == PG_SOURCE_BACKPRESSURE_LAG_BYTES.name()
+        || is_persist_config_var(name)
 }
 
 /// Returns whether the named variable is a persist configuration parameter.
@@ -37,6 +37,7 @@ use mz_timely_util::progress::any_antichain;
 
 use crate::client::proto_storage_server::ProtoStorage;
 use crate::controller::CollectionMetadata;
+use crate::healthcheck::ReplicationSlotStatus;
 use crate::metrics::RehydratingStorageClientMetrics;
 use crate::types::parameters::StorageParameters;
 use crate::types::sinks::{MetadataFilled, StorageSinkDesc};
@@ -315,6 +316,9 @@ pub struct SourceStatisticsUpdate {
     pub updates_staged: u64,
     pub updates_committed: u64,
     pub bytes_received: u64,
+    /// The state of the replication slot of a Postgres source. Only reported by the worker that
+    /// reports the health of the source, once the source looked the slot up.
+    pub replication_slot: Option<ReplicationSlotUpdate>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
@@ -327,6 +331,17 @@ pub struct SinkStatisticsUpdate {
     pub bytes_committed: u64,
 }
 
+/// The state of a Postgres source's replication slot, for the
+/// `mz_postgres_replication_slot_states` table. Unlike the status history, this only holds the
+/// state the source last looked up.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ReplicationSlotUpdate {
+    pub id: GlobalId,
+    pub status: ReplicationSlotStatus,
+    /// When the source looked the slot up, in milliseconds since the epoch.
+    pub updated_at: u64,
+}
+
 /// A trait that abstracts over user-facing statistics objects, used
 /// by `spawn_statistics_scraper`.
 pub trait PackableStats {
@@ -356,6 +371,20 @@ impl PackableStats for SinkStatisticsUpdate {
         packer.push(Datum::from(self.bytes_committed));
     }
 }
+impl PackableStats for ReplicationSlotUpdate {
+    fn pack(&self, mut packer: mz_repr::RowPacker<'_>) {
+        use mz_repr::Datum;
+        packer.push(Datum::from(self.id.to_string().as_str()));
+        packer.push(Datum::from(self.status.active));
+        packer.push(Datum::from(self.status.confirmed_flush_lsn));
+        packer.push(Datum::from(self.status.wal_status.as_deref()));
+        packer.push(Datum::TimestampTz(
+            mz_ore::now::to_datetime(self.updated_at)
+                .try_into()
+                .expect("must fit"),
+        ));
+    }
+}
 
 /// Responses that the storage nature of a worker/dataflow can provide back to the coordinator.
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
@@ -375,8 +404,8 @@ pub enum StorageResponse<T = mz_repr::Timestamp> {
 impl RustType<ProtoStorageResponse> for StorageResponse<mz_repr::Timestamp> {
     fn into_proto(&self) -> ProtoStorageResponse {
         use proto_storage_response::{
-            Kind::*, ProtoDroppedIds, ProtoSinkStatisticsUpdate, ProtoSourceStatisticsUpdate,
-            ProtoStatisticsUpdates,
+            Kind::*, ProtoDroppedIds, ProtoReplicationSlotUpdate, ProtoSinkStatisticsUpdate,
+            ProtoSourceStatisticsUpdate, ProtoStatisticsUpdates,
         };
         ProtoStorageResponse {
             kind: Some(match self {
@@ -396,6 +425,14 @@ impl RustType<ProtoStorageResponse> for StorageResponse<mz_repr::Timestamp> {
                                 updates_staged: update.updates_staged,
                                 updates_committed: update.updates_committed,
                                 bytes_received: update.bytes_received,
+                                replication_slot: update.replication_slot.as_ref().map(|slot| {
+                                    ProtoReplicationSlotUpdate {
+                                        active: slot.status.active,
+                                        confirmed_flush_lsn: slot.status.confirmed_flush_lsn,
+                                        wal_status: slot.status.wal_status.clone(),
+                                        updated_at: slot.updated_at,
+                                    }
+                                }),
                             })
                             .collect(),
                         sink_updates: sink_stats
@@ -429,16 +466,28 @@ impl RustType<ProtoStorageResponse> for StorageResponse<mz_repr::Timestamp> {
                     .source_updates
                     .into_iter()
                     .map(|update| {
+                        let id = update
+                            .id
+                            .into_rust_if_some("ProtoStorageResponse::stats::source_updates::id")?;
                         Ok(SourceStatisticsUpdate {
-                            id: update.id.into_rust_if_some(
-                                "ProtoStorageResponse::stats::source_updates::id",
-                            )?,
+                            id,
                             worker_id: usize::cast_from(update.worker_id),
                             snapshot_committed: update.snapshot_committed,
                             messages_received: update.messages_received,
                             updates_staged: update.updates_staged,
                             updates_committed: update.updates_committed,
                             bytes_received: update.bytes_received,
+                            replication_slot: update.replication_slot.map(|slot| {
+                                ReplicationSlotUpdate {
+                                    id,
+                                    status: ReplicationSlotStatus {
+                                        active: slot.active,
+                                        confirmed_flush_lsn: slot.confirmed_flush_lsn,
+                                        wal_status: slot.wal_status,
+                                    },
+                                    updated_at: slot.updated_at,
+                                }
+                            }),
                         })
                     })
                     .collect::<Result<Vec<_>, TryFromProtoError>>()?,
@@ -59,7 +59,8 @@ use mz_stash::{self, AppendBatch, StashError, StashFactory, TypedCollection};
 
 use crate::client::{
     CreateSinkCommand, CreateSourceCommand, ProtoStorageCommand, ProtoStorageResponse,
-    SinkStatisticsUpdate, SourceStatisticsUpdate, StorageCommand, StorageResponse, Update,
+    ReplicationSlotUpdate, SinkStatisticsUpdate, SourceStatisticsUpdate, StorageCommand,
+    StorageResponse, Update,
 };
 use crate::controller::rehydration::RehydratingStorageClient;
 use crate::healthcheck;
@@ -70,7 +71,9 @@ use crate::types::parameters::StorageParameters;
 use crate::types::sinks::{
     MetadataUnfilled, ProtoDurableExportMetadata, SinkAsOf, StorageSinkDesc,
 };
-use crate::types::sources::{IngestionDescription, SourceData, SourceEnvelope, SourceExport};
+use crate::types::sources::{
+    GenericSourceConnection, IngestionDescription, SourceData, SourceEnvelope, SourceExport,
+};
 
 mod collection_mgmt;
 mod command_wals;
@@ -122,6 +125,7 @@ pub enum IntrospectionType {
     // once we allow multiplexing multiple sources/sinks on a single cluster.
     StorageSourceStatistics,
     StorageSinkStatistics,
+    PostgresReplicationSlots,
 }
 
 /// Describes how data is written to the collection.
@@ -310,6 +314,14 @@ pub trait StorageController: Debug + Send {
         collections: Vec<(GlobalId, CollectionDescription<Self::Timestamp>)>,
     ) -> Result<(), StorageError>;
 
+    /// Replaces the connection of the ingestion `id`, which is restarted with
+    /// the new connection from its current resumption frontier.
+    async fn alter_collection(
+        &mut self,
+        id: GlobalId,
+        connection: GenericSourceConnection,
+    ) -> Result<(), StorageError>;
+
     /// Acquire an immutable reference to the export state, should it exist.
     fn export(&self, id: GlobalId) -> Result<&ExportState<Self::Timestamp>, StorageError>;
 
@@ -772,6 +784,10 @@ pub struct StorageControllerState<T: Timestamp + Lattice + Codec64 + TimestampMa
     /// and its contents are entirely driven by `StorageResponse::StatisticsUpdates`'s.
     sink_statistics:
         Arc<std::sync::Mutex<BTreeMap<GlobalId, BTreeMap<usize, SinkStatisticsUpdate>>>>,
+    /// The replication slot states that the `source_statistics` last carried, keyed by the
+    /// worker that reported them, so that only the latest state of each source is written.
+    replication_slots:
+        Arc<std::sync::Mutex<BTreeMap<GlobalId, BTreeMap<usize, ReplicationSlotUpdate>>>>,
 
     /// Clients for all known storage instances.
     clients: BTreeMap<StorageInstanceId, RehydratingStorageClient<T>>,
@@ -993,6 +1009,7 @@ impl<T: Timestamp + Lattice + Codec64 + From<EpochMillis> + TimestampManipulatio
             envd_epoch,
             source_statistics: Arc::new(std::sync::Mutex::new(BTreeMap::new())),
             sink_statistics: Arc::new(std::sync::Mutex::new(BTreeMap::new())),
+            replication_slots: Arc::new(std::sync::Mutex::new(BTreeMap::new())),
             clients: BTreeMap::new(),
             initialized: false,
             config: StorageParameters::default(),
@@ -1404,64 +1421,7 @@ where
         for (id, description) in to_create {
             match description.data_source {
                 DataSource::Ingestion(ingestion) => {
-                    // Each ingestion is augmented with the collection metadata.
-                    let mut source_imports = BTreeMap::new();
-                    for (id, _) in ingestion.source_imports {
-                        // This _requires_ that the sub-source collection (with
-                        // `DataSource::Other`) was registered BEFORE we process this, the
-                        // top-level collection.
-                        let metadata = self.collection(id)?.collection_metadata.clone();
-                        source_imports.insert(id, metadata);
-                    }
-
-                    // The ingestion metadata is simply the collection metadata of the collection with
-                    // the associated ingestion
-                    let ingestion_metadata = self.collection(id)?.collection_metadata.clone();
-
-                    let mut source_exports = BTreeMap::new();
-                    for (id, export) in ingestion.source_exports {
-                        // Note that these metadata's have been previously enriched with the
-                        // required `RelationDesc` for each sub-source above!
-                        let storage_metadata = self.collection(id)?.collection_metadata.clone();
-                        source_exports.insert(
-                            id,
-                            SourceExport {
-                                storage_metadata,
-                                output_index: export.output_index,
-                            },
-                        );
-                    }
-
-                    let desc = IngestionDescription {
-                        source_imports,
-                        source_exports,
-                        ingestion_metadata,
-                        // The rest of the fields are identical
-                        desc: ingestion.desc,
-                        instance_id: ingestion.instance_id,
-                        remap_collection_id: ingestion.remap_collection_id,
-                    };
-                    let mut state = desc.initialize_state(&self.persist).await;
-                    let resume_upper = desc.calculate_resumption_frontier(&mut state).await;
-
-                    // Fetch the client for this ingestion's instance.
-                    let client = self
-                        .state
-                        .clients
-                        .get_mut(&ingestion.instance_id)
-                        .with_context(|| {
-                            format!(
-                                "instance {} missing for ingestion {}",
-                                ingestion.instance_id, id
-                            )
-                        })?;
-                    let augmented_ingestion = CreateSourceCommand {
-                        id,
-                        description: desc,
-                        resume_upper,
-                    };
-
-                    client.send(StorageCommand::CreateSources(vec![augmented_ingestion]));
+                    self.run_ingestion(id, ingestion).await?;
                 }
                 DataSource::Introspection(i) => {
                     let prev = self.state.introspection_ids.insert(i, id);
@@ -1506,6 +1466,21 @@ where
                             // dropped, so that the internal task will stop.
                             self.state.introspection_tokens.insert(id, scraper_token);
                         }
+                        IntrospectionType::PostgresReplicationSlots => {
+                            // Set the collection to empty.
+                            self.reconcile_managed_collection(id, vec![]).await;
+
+                            let scraper_token = statistics::spawn_statistics_scraper(
+                                id.clone(),
+                                // These do a shallow copy.
+                                self.state.collection_manager.clone(),
+                                Arc::clone(&self.state.replication_slots),
+                            );
+
+                            // Make sure this is dropped when the controller is
+                            // dropped, so that the internal task will stop.
+                            self.state.introspection_tokens.insert(id, scraper_token);
+                        }
                         IntrospectionType::SourceStatusHistory
                         | IntrospectionType::SinkStatusHistory => {
                             // nothing to do: these collections are append only
@@ -1519,6 +1494,25 @@ where
         Ok(())
     }
 
+    async fn alter_collection(
+        &mut self,
+        id: GlobalId,
+        connection: GenericSourceConnection,
+    ) -> Result<(), StorageError> {
+        let ingestion = match &mut self.collection_mut(id)?.description.data_source {
+            DataSource::Ingestion(ingestion) => {
+                ingestion.desc.connection = connection;
+                ingestion.clone()
+            }
+            _ => {
+                return Err(StorageError::InvalidUsage(format!(
+                    "collection {id} is not an ingestion"
+                )))
+            }
+        };
+        self.run_ingestion(id, ingestion).await
+    }
+
     fn export(&self, id: GlobalId) -> Result<&ExportState<Self::Timestamp>, StorageError> {
         self.state
             .exports
@@ -2058,6 +2052,16 @@ where
             Some(StorageResponse::StatisticsUpdates(source_stats, sink_stats)) => {
                 // Note we only hold the locks while moving some plain-old-data around here.
 
+                let mut shared_slots = self.state.replication_slots.lock().expect("poisoned");
+                for stat in &source_stats {
+                    if let Some(slot) = &stat.replication_slot {
+                        // Replace the state any other worker reported, e.g. before the source
+                        // was rescheduled.
+                        shared_slots
+                            .insert(stat.id, BTreeMap::from([(stat.worker_id, slot.clone())]));
+                    }
+                }
+
                 let mut shared_stats = self.state.source_statistics.lock().expect("poisoned");
                 for stat in source_stats {
                     let shared_stats = shared_stats.entry(stat.id).or_default();
@@ -2105,7 +2109,7 @@ where
         let mut updates = vec![];
         for id in self.state.pending_source_drops.drain(..) {
             let status_row =
-                healthcheck::pack_status_row(id, "dropped", None, (self.state.now)(), None);
+                healthcheck::pack_status_row(id, "dropped", None, (self.state.now)(), None, None);
             updates.push((status_row, 1));
         }
         self.append_to_managed_collection(source_status_history_id, updates)
@@ -2117,7 +2121,7 @@ where
         let mut updates = vec![];
         for id in self.state.pending_sink_drops.drain(..) {
             let status_row =
-                healthcheck::pack_status_row(id, "dropped", None, (self.state.now)(), None);
+                healthcheck::pack_status_row(id, "dropped", None, (self.state.now)(), None, None);
             updates.push((status_row, 1));
         }
         self.append_to_managed_collection(sink_status_history_id, updates)
@@ -2173,6 +2177,74 @@ where
 
     Self: StorageController<Timestamp = T>,
 {
+    /// Sends the ingestion `id` to the instance that runs it, augmented with
+    /// the metadata of the collections it reads and writes.
+    async fn run_ingestion(
+        &mut self,
+        id: GlobalId,
+        ingestion: IngestionDescription,
+    ) -> Result<(), StorageError> {
+        // Each ingestion is augmented with the collection metadata.
+        let mut source_imports = BTreeMap::new();
+        for (id, _) in ingestion.source_imports {
+            // This _requires_ that the sub-source collection (with
+            // `DataSource::Other`) was registered BEFORE we process this, the
+            // top-level collection.
+            let metadata = self.collection(id)?.collection_metadata.clone();
+            source_imports.insert(id, metadata);
+        }
+
+        // The ingestion metadata is simply the collection metadata of the collection with
+        // the associated ingestion
+        let ingestion_metadata = self.collection(id)?.collection_metadata.clone();
+
+        let mut source_exports = BTreeMap::new();
+        for (id, export) in ingestion.source_exports {
+            // Note that these metadata's have been previously enriched with the
+            // required `RelationDesc` for each sub-source above!
+            let storage_metadata = self.collection(id)?.collection_metadata.clone();
+            source_exports.insert(
+                id,
+                SourceExport {
+                    storage_metadata,
+                    output_index: export.output_index,
+                },
+            );
+        }
+
+        let desc = IngestionDescription {
+            source_imports,
+            source_exports,
+            ingestion_metadata,
+            // The rest of the fields are identical
+            desc: ingestion.desc,
+            instance_id: ingestion.instance_id,
+            remap_collection_id: ingestion.remap_collection_id,
+        };
+        let mut state = desc.initialize_state(&self.persist).await;
+        let resume_upper = desc.calculate_resumption_frontier(&mut state).await;
+
+        // Fetch the client for this ingestion's instance.
+        let client = self
+            .state
+            .clients
+            .get_mut(&ingestion.instance_id)
+            .with_context(|| {
+                format!(
+                    "instance {} missing for ingestion {}",
+                    ingestion.instance_id, id
+                )
+            })?;
+        let augmented_ingestion = CreateSourceCommand {
+            id,
+            description: desc,
+            resume_upper,
+        };
+
+        client.send(StorageCommand::CreateSources(vec![augmented_ingestion]));
+        Ok(())
+    }
+
     /// Create a new storage controller from a client it should wrap.
     ///
     /// Note that when creating a new storage controller, you must also
@@ -7,17 +7,105 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use std::collections::BTreeMap;
+
 use chrono::{DateTime, NaiveDateTime, Utc};
+use dec::OrderedDecimal;
 use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
 
+use mz_repr::adt::numeric::Numeric;
 use mz_repr::{Datum, GlobalId, RelationDesc, Row, ScalarType};
 
+/// The replication progress of a source, as reported in the `details` of its status rows.
+///
+/// All positions are offsets in the upstream log, e.g. LSNs for Postgres sources.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ReplicationProgress {
+    /// The latest end of the upstream log the source observed.
+    pub upstream_end: u64,
+    /// The latest position the source committed upstream, i.e. up to which the upstream may
+    /// discard its log.
+    pub committed: u64,
+    /// The latest position the source emitted data up to.
+    pub emitted: u64,
+}
+
+/// The state of a Postgres source's replication slot, as found in the upstream's
+/// `pg_replication_slots`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ReplicationSlotStatus {
+    /// Whether a connection is currently streaming from the slot.
+    pub active: bool,
+    /// The LSN up to which the slot's consumer confirmed receiving data, if any.
+    pub confirmed_flush_lsn: Option<u64>,
+    /// The availability of the WAL the slot needs, e.g. `reserved` or `lost`. Only reported by
+    /// Postgres 13 and later.
+    pub wal_status: Option<String>,
+}
+
+/// A column of a table that a Postgres source snapshotted anew, as found upstream at the time.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ResnapshottedColumn {
+    pub name: String,
+    pub type_oid: u32,
+    pub type_mod: i32,
+}
+
+/// A table that a Postgres source snapshotted anew after its upstream schema changed
+/// incompatibly.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ResnapshottedTable {
+    /// The LSN the new snapshot was emitted at, which the source must have persisted its data
+    /// beyond for the snapshot to be complete.
+    pub lsn: u64,
+    /// The upstream columns the table is ingested from since, in the order they are ingested in.
+    pub columns: Vec<ResnapshottedColumn>,
+}
+
+/// The LSNs that a Postgres source's data can be reconciled against the upstream with.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PostgresStatusDetails {
+    /// The tables that were added to the publication after the source was created, which it
+    /// doesn't ingest, by name.
+    pub added_tables: Vec<String>,
+    /// The LSN the initial snapshot was taken at, if the source took it since it last restarted.
+    /// It is not persisted across restarts, so only the status history rows recorded before a
+    /// restart keep it.
+    pub snapshot_lsn: Option<u64>,
+    /// The LSN replication last resumed from, i.e. since the source last restarted or reconnected.
+    pub replication_start_lsn: Option<u64>,
+    /// The state of the source's replication slot when the source last looked it up.
+    pub slot: Option<ReplicationSlotStatus>,
+    /// The ingested tables that are `UNLOGGED` upstream, whose snapshot never changes, by name.
+    pub snapshot_only_tables: Vec<String>,
+    /// The ingested tables that were snapshotted anew after their upstream schema changed, by
+    /// OID. The source reads them back from its latest status when it restarts, so that it does
+    /// not snapshot the tables anew again, see [`resnapshotted_tables`].
+    pub resnapshotted_tables: BTreeMap<u32, ResnapshottedTable>,
+}
+
+/// Details about the state of a source that are reported in the `details` of its status rows.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SourceStatusDetails {
+    /// The replication progress of the source, if it reports one.
+    pub replication_progress: Option<ReplicationProgress>,
+    /// The upstream tables the source could not ingest if asked to, with the reason, by name.
+    pub non_ingestable_tables: BTreeMap<String, String>,
+    /// The ingested tables whose upstream schema changed in a way the source can still ingest,
+    /// with a description of the change, by name.
+    pub schema_drift: BTreeMap<String, String>,
+    /// Details specific to Postgres sources, reported under the `postgres` key.
+    pub postgres: Option<PostgresStatusDetails>,
+}
+
 pub fn pack_status_row(
     collection_id: GlobalId,
     status_name: &str,
     error: Option<&str>,
     ts: u64,
     hint: Option<&str>,
+    details: Option<&SourceStatusDetails>,
 ) -> Row {
     let timestamp = NaiveDateTime::from_timestamp_opt(
         (ts / 1000)
@@ -42,16 +130,182 @@ pub fn pack_status_row(
     let mut packer = row.packer();
     packer.extend([timestamp, collection_id, status, error]);
 
-    match hint {
-        Some(hint) => {
-            let metadata = vec![("hint", Datum::String(hint))];
-            packer.push_dict(metadata);
+    let progress = details.and_then(|details| details.replication_progress.as_ref());
+    let non_ingestable_tables = details
+        .map(|details| &details.non_ingestable_tables)
+        .filter(|tables| !tables.is_empty());
+    let schema_drift = details
+        .map(|details| &details.schema_drift)
+        .filter(|tables| !tables.is_empty());
+    let postgres = details
+        .and_then(|details| details.postgres.as_ref())
+        .filter(|postgres| **postgres != PostgresStatusDetails::default());
+    if hint.is_none()
+        && progress.is_none()
+        && non_ingestable_tables.is_none()
+        && schema_drift.is_none()
+        && postgres.is_none()
+    {
+        packer.push(Datum::Null);
+        return row;
+    }
+    // Dictionary keys must be pushed in ascending order.
+    packer.push_dict_with(|packer| {
+        if let Some(hint) = hint {
+            packer.push(Datum::String("hint"));
+            packer.push(Datum::String(hint));
         }
-        None => packer.push(Datum::Null),
-    };
+        if let Some(tables) = non_ingestable_tables {
+            packer.push(Datum::String("non_ingestable_tables"));
+            packer.push_dict(
+                tables
+                    .iter()
+                    .map(|(name, reason)| (name.as_str(), Datum::String(reason))),
+            );
+        }
+        let to_numeric = |p: u64| Datum::from(OrderedDecimal(Numeric::from(p)));
+        if let Some(postgres) = postgres {
+            packer.push(Datum::String("postgres"));
+            packer.push_dict_with(|packer| {
+                if !postgres.added_tables.is_empty() {
+                    packer.push(Datum::String("added_tables"));
+                    packer.push_list(postgres.added_tables.iter().map(|name| Datum::String(name)));
+                }
+                if let Some(lsn) = postgres.replication_start_lsn {
+                    packer.push(Datum::String("replication_start_lsn"));
+                    packer.push(to_numeric(lsn));
+                }
+                if !postgres.resnapshotted_tables.is_empty() {
+                    // The OIDs are sorted numerically, but dictionary keys are sorted as strings.
+                    let tables: BTreeMap<_, _> = postgres
+                        .resnapshotted_tables
+                        .iter()
+                        .map(|(oid, table)| (oid.to_string(), table))
+                        .collect();
+                    packer.push(Datum::String("resnapshotted_tables"));
+                    packer.push_dict_with(|packer| {
+                        for (oid, table) in &tables {
+                            packer.push(Datum::String(oid));
+                            packer.push_dict_with(|packer| {
+                                packer.push(Datum::String("columns"));
+                                packer.push_list_with(|packer| {
+                                    for column in &table.columns {
+                                        packer.push_dict([
+                                            ("name", Datum::String(&column.name)),
+                                            (
+                                                "type_mod",
+                                                Datum::from(OrderedDecimal(Numeric::from(
+                                                    column.type_mod,
+                                                ))),
+                                            ),
+                                            ("type_oid", to_numeric(column.type_oid.into())),
+                                        ]);
+                                    }
+                                });
+                                packer.push(Datum::String("lsn"));
+                                packer.push(to_numeric(table.lsn));
+                            });
+                        }
+                    });
+                }
+                if let Some(slot) = &postgres.slot {
+                    packer.push(Datum::String("slot"));
+                    packer.push_dict_with(|packer| {
+                        packer.push(Datum::String("active"));
+                        packer.push(Datum::from(slot.active));
+                        if let Some(lsn) = slot.confirmed_flush_lsn {
+                            packer.push(Datum::String("confirmed_flush_lsn"));
+                            packer.push(to_numeric(lsn));
+                        }
+                        if let Some(wal_status) = &slot.wal_status {
+                            packer.push(Datum::String("wal_status"));
+                            packer.push(Datum::String(wal_status));
+                        }
+                    });
+                }
+                if let Some(lsn) = postgres.snapshot_lsn {
+                    packer.push(Datum::String("snapshot_lsn"));
+                    packer.push(to_numeric(lsn));
+                }
+                if !postgres.snapshot_only_tables.is_empty() {
+                    packer.push(Datum::String("snapshot_only_tables"));
+                    packer.push_list(
+                        postgres
+                            .snapshot_only_tables
+                            .iter()
+                            .map(|name| Datum::String(name)),
+                    );
+                }
+            });
+        }
+        if let Some(progress) = progress {
+            packer.push(Datum::String("replication_progress"));
+            packer.push_dict([
+                ("committed", to_numeric(progress.committed)),
+                ("emitted", to_numeric(progress.emitted)),
+                ("upstream_end", to_numeric(progress.upstream_end)),
+            ]);
+        }
+        if let Some(tables) = schema_drift {
+            packer.push(Datum::String("schema_drift"));
+            packer.push_dict(
+                tables
+                    .iter()
+                    .map(|(name, change)| (name.as_str(), Datum::String(change))),
+            );
+        }
+    });
     row
 }
 
+/// Reads the tables that a Postgres source snapshotted anew back from the `details` of one of its
+/// status rows, as packed by [`pack_status_row`]. Tables whose details are malformed are left out.
+pub fn resnapshotted_tables(details: Datum) -> BTreeMap<u32, ResnapshottedTable> {
+    fn field<'a>(datum: Datum<'a>, name: &str) -> Option<Datum<'a>> {
+        match datum {
+            Datum::Map(map) => map.iter().find(|(key, _)| *key == name).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+    fn number<T: std::str::FromStr>(datum: Option<Datum>) -> Option<T> {
+        match datum? {
+            Datum::Numeric(n) => n.0.to_string().parse().ok(),
+            _ => None,
+        }
+    }
+    fn table(datum: Datum) -> Option<ResnapshottedTable> {
+        let Datum::List(columns) = field(datum, "columns")? else {
+            return None;
+        };
+        let columns = columns
+            .iter()
+            .map(|column| {
+                let Datum::String(name) = field(column, "name")? else {
+                    return None;
+                };
+                Some(ResnapshottedColumn {
+                    name: name.into(),
+                    type_oid: number(field(column, "type_oid"))?,
+                    type_mod: number(field(column, "type_mod"))?,
+                })
+            })
+            .collect::<Option<_>>()?;
+        Some(ResnapshottedTable {
+            lsn: number(field(datum, "lsn"))?,
+            columns,
+        })
+    }
+
+    let tables = field(details, "postgres").and_then(|p| field(p, "resnapshotted_tables"));
+    let Some(Datum::Map(tables)) = tables else {
+        return BTreeMap::new();
+    };
+    tables
+        .iter()
+        .filter_map(|(oid, datum)| Some((oid.parse().ok()?, table(datum)?)))
+        .collect()
+}
+
 pub static MZ_SINK_STATUS_HISTORY_DESC: Lazy<RelationDesc> = Lazy::new(|| {
     RelationDesc::empty()
         .with_column("occurred_at", ScalarType::TimestampTz.nullable(false))
@@ -80,7 +334,7 @@ mod tests {
         let hint = "hint message";
         let id = GlobalId::User(1);
         let status = "dropped";
-        let row = pack_status_row(id, status, Some(error_message), 1000, Some(hint));
+        let row = pack_status_row(id, status, Some(error_message), 1000, Some(hint), None);
 
         for (datum, column_type) in row.iter().zip(MZ_SINK_STATUS_HISTORY_DESC.iter_types()) {
             assert!(datum.is_instance_of(column_type));
@@ -109,7 +363,7 @@ mod tests {
         let error_message = "error message";
         let id = GlobalId::User(1);
         let status = "dropped";
-        let row = pack_status_row(id, status, Some(error_message), 1000, None);
+        let row = pack_status_row(id, status, Some(error_message), 1000, None, None);
 
         for (datum, column_type) in row.iter().zip(MZ_SINK_STATUS_HISTORY_DESC.iter_types()) {
             assert!(datum.is_instance_of(column_type));
@@ -130,7 +384,7 @@ mod tests {
         let id = GlobalId::User(1);
         let status = "dropped";
         let hint = "hint message";
-        let row = pack_status_row(id, status, None, 1000, Some(hint));
+        let row = pack_status_row(id, status, None, 1000, Some(hint), None);
 
         for (datum, column_type) in row.iter().zip(MZ_SINK_STATUS_HISTORY_DESC.iter_types()) {
             assert!(datum.is_instance_of(column_type));
@@ -153,4 +407,311 @@ mod tests {
             vec![("hint", Datum::String(hint))]
         );
     }
+
+    #[test]
+    fn test_row_with_progress() {
+        let id = GlobalId::User(1);
+        let hint = "hint message";
+        let progress = ReplicationProgress {
+            upstream_end: 300,
+            committed: 100,
+            emitted: 200,
+        };
+        let details = SourceStatusDetails {
+            replication_progress: Some(progress),
+            non_ingestable_tables: BTreeMap::new(),
+            schema_drift: BTreeMap::new(),
+            postgres: None,
+        };
+        let row = pack_status_row(id, "running", None, 1000, Some(hint), Some(&details));
+
+        for (datum, column_type) in row.iter().zip(MZ_SOURCE_STATUS_HISTORY_DESC.iter_types()) {
+            assert!(datum.is_instance_of(column_type));
+        }
+
+        let details = row.iter().nth(4).unwrap().unwrap_map();
+        let mut details = details.iter();
+        assert_eq!(details.next(), Some(("hint", Datum::String(hint))));
+        let (key, progress) = details.next().unwrap();
+        assert_eq!(key, "replication_progress");
+        assert_eq!(
+            progress.unwrap_map().iter().collect::<Vec<_>>(),
+            vec![
+                (
+                    "committed",
+                    Datum::from(OrderedDecimal(Numeric::from(100u64)))
+                ),
+                (
+                    "emitted",
+                    Datum::from(OrderedDecimal(Numeric::from(200u64)))
+                ),
+                (
+                    "upstream_end",
+                    Datum::from(OrderedDecimal(Numeric::from(300u64)))
+                ),
+            ]
+        );
+        assert_eq!(details.next(), None);
+    }
+
+    #[test]
+    fn test_row_with_non_ingestable_tables() {
+        let id = GlobalId::User(1);
+        let details = SourceStatusDetails {
+            replication_progress: None,
+            non_ingestable_tables: BTreeMap::from([(
+                "public.t1".into(),
+                "column a has unsupported type with OID 16400".into(),
+            )]),
+            schema_drift: BTreeMap::new(),
+            postgres: None,
+        };
+        let row = pack_status_row(id, "running", None, 1000, None, Some(&details));
+
+        for (datum, column_type) in row.iter().zip(MZ_SOURCE_STATUS_HISTORY_DESC.iter_types()) {
+            assert!(datum.is_instance_of(column_type));
+        }
+
+        let details = row.iter().nth(4).unwrap().unwrap_map();
+        let mut details = details.iter();
+        let (key, tables) = details.next().unwrap();
+        assert_eq!(key, "non_ingestable_tables");
+        assert_eq!(
+            tables.unwrap_map().iter().collect::<Vec<_>>(),
+            vec![(
+                "public.t1",
+                Datum::String("column a has unsupported type with OID 16400")
+            )]
+        );
+        assert_eq!(details.next(), None);
+
+        // Without any details to report, there are no details at all.
+        let row = pack_status_row(id, "running", None, 1000, None, Some(&Default::default()));
+        assert_eq!(row.iter().nth(4).unwrap(), Datum::Null);
+    }
+
+    #[test]
+    fn test_row_with_schema_drift() {
+        let id = GlobalId::User(1);
+        let details = SourceStatusDetails {
+            replication_progress: None,
+            non_ingestable_tables: BTreeMap::new(),
+            schema_drift: BTreeMap::from([("public.t1".into(), "upstream added column b".into())]),
+            postgres: None,
+        };
+        let row = pack_status_row(id, "running", None, 1000, None, Some(&details));
+
+        for (datum, column_type) in row.iter().zip(MZ_SOURCE_STATUS_HISTORY_DESC.iter_types()) {
+            assert!(datum.is_instance_of(column_type));
+        }
+
+        let details = row.iter().nth(4).unwrap().unwrap_map();
+        let mut details = details.iter();
+        let (key, tables) = details.next().unwrap();
+        assert_eq!(key, "schema_drift");
+        assert_eq!(
+            tables.unwrap_map().iter().collect::<Vec<_>>(),
+            vec![("public.t1", Datum::String("upstream added column b"))]
+        );
+        assert_eq!(details.next(), None);
+    }
+
+    #[test]
+    fn test_row_with_postgres_lsns() {
+        let id = GlobalId::User(1);
+        let details = SourceStatusDetails {
+            postgres: Some(PostgresStatusDetails {
+                snapshot_lsn: None,
+                replication_start_lsn: Some(0x1000),
+                slot: None,
+                snapshot_only_tables: vec![],
+            }),
+            ..Default::default()
+        };
+        let row = pack_status_row(id, "running", None, 1000, None, Some(&details));
+
+        for (datum, column_type) in row.iter().zip(MZ_SOURCE_STATUS_HISTORY_DESC.iter_types()) {
+            assert!(datum.is_instance_of(column_type));
+        }
+
+        let details = row.iter().nth(4).unwrap().unwrap_map();
+        let mut details = details.iter();
+        let (key, lsns) = details.next().unwrap();
+        assert_eq!(key, "postgres");
+        // Unknown LSNs are left out.
+        assert_eq!(
+            lsns.unwrap_map().iter().collect::<Vec<_>>(),
+            vec![(
+                "replication_start_lsn",
+                Datum::from(OrderedDecimal(Numeric::from(0x1000u64)))
+            )]
+        );
+        assert_eq!(details.next(), None);
+
+        // Without any known LSN, there are no details.
+        let details = SourceStatusDetails {
+            postgres: Some(PostgresStatusDetails::default()),
+            ..Default::default()
+        };
+        let row = pack_status_row(id, "running", None, 1000, None, Some(&details));
+        assert_eq!(row.iter().nth(4).unwrap(), Datum::Null);
+    }
+
+    #[test]
+    fn test_row_with_slot_status() {
+        let id = GlobalId::User(1);
+        let details = SourceStatusDetails {
+            postgres: Some(PostgresStatusDetails {
+                slot: Some(ReplicationSlotStatus {
+                    active: true,
+                    confirmed_flush_lsn: Some(0x2000),
+                    wal_status: Some("reserved".into()),
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let row = pack_status_row(id, "running", None, 1000, None, Some(&details));
+
+        for (datum, column_type) in row.iter().zip(MZ_SOURCE_STATUS_HISTORY_DESC.iter_types()) {
+            assert!(datum.is_instance_of(column_type));
+        }
+
+        let details = row.iter().nth(4).unwrap().unwrap_map();
+        let (key, postgres) = details.iter().next().unwrap();
+        assert_eq!(key, "postgres");
+        let (key, slot) = postgres.unwrap_map().iter().next().unwrap();
+        assert_eq!(key, "slot");
+        assert_eq!(
+            slot.unwrap_map().iter().collect::<Vec<_>>(),
+            vec![
+                ("active", Datum::True),
+                (
+                    "confirmed_flush_lsn",
+                    Datum::from(OrderedDecimal(Numeric::from(0x2000u64)))
+                ),
+                ("wal_status", Datum::String("reserved")),
+            ]
+        );
+
+        // A slot that never confirmed a position leaves it out.
+        let details = SourceStatusDetails {
+            postgres: Some(PostgresStatusDetails {
+                slot: Some(ReplicationSlotStatus {
+                    active: false,
+                    confirmed_flush_lsn: None,
+                    wal_status: None,
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let row = pack_status_row(id, "running", None, 1000, None, Some(&details));
+        let details = row.iter().nth(4).unwrap().unwrap_map();
+        let (_, postgres) = details.iter().next().unwrap();
+        let (_, slot) = postgres.unwrap_map().iter().next().unwrap();
+        assert_eq!(
+            slot.unwrap_map().iter().collect::<Vec<_>>(),
+            vec![("active", Datum::False)]
+        );
+    }
+
+    #[test]
+    fn test_row_with_snapshot_only_tables() {
+        let id = GlobalId::User(1);
+        let details = SourceStatusDetails {
+            postgres: Some(PostgresStatusDetails {
+                snapshot_lsn: Some(0x1000),
+                snapshot_only_tables: vec!["public.cache".into()],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let row = pack_status_row(id, "running", None, 1000, None, Some(&details));
+
+        for (datum, column_type) in row.iter().zip(MZ_SOURCE_STATUS_HISTORY_DESC.iter_types()) {
+            assert!(datum.is_instance_of(column_type));
+        }
+
+        let details = row.iter().nth(4).unwrap().unwrap_map();
+        let (_, postgres) = details.iter().next().unwrap();
+        let postgres: Vec<_> = postgres.unwrap_map().iter().collect();
+        assert_eq!(postgres.len(), 2);
+        assert_eq!(postgres[0].0, "snapshot_lsn");
+        assert_eq!(postgres[1].0, "snapshot_only_tables");
+        assert_eq!(
+            postgres[1].1.unwrap_list().iter().collect::<Vec<_>>(),
+            vec![Datum::String("public.cache")]
+        );
+    }
+
+    #[test]
+    fn test_row_with_added_tables() {
+        let id = GlobalId::User(1);
+        let details = SourceStatusDetails {
+            postgres: Some(PostgresStatusDetails {
+                added_tables: vec!["public.new".into()],
+                replication_start_lsn: Some(0x1000),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let row = pack_status_row(id, "running", None, 1000, None, Some(&details));
+
+        for (datum, column_type) in row.iter().zip(MZ_SOURCE_STATUS_HISTORY_DESC.iter_types()) {
+            assert!(datum.is_instance_of(column_type));
+        }
+
+        let details = row.iter().nth(4).unwrap().unwrap_map();
+        let (_, postgres) = details.iter().next().unwrap();
+        let postgres: Vec<_> = postgres.unwrap_map().iter().collect();
+        assert_eq!(postgres.len(), 2);
+        assert_eq!(postgres[0].0, "added_tables");
+        assert_eq!(
+            postgres[0].1.unwrap_list().iter().collect::<Vec<_>>(),
+            vec![Datum::String("public.new")]
+        );
+        assert_eq!(postgres[1].0, "replication_start_lsn");
+    }
+
+    #[test]
+    fn test_row_with_resnapshotted_tables() {
+        let id = GlobalId::User(1);
+        let table = |lsn, type_oid| ResnapshottedTable {
+            lsn,
+            columns: vec![
+                ResnapshottedColumn {
+                    name: "f1".into(),
+                    type_oid,
+                    type_mod: -1,
+                },
+                ResnapshottedColumn {
+                    name: "f2".into(),
+                    type_oid: 1043,
+                    type_mod: 14,
+                },
+            ],
+        };
+        // OID 100 sorts after OID 99 numerically, but before it as a string.
+        let tables = BTreeMap::from([(99, table(0x1000, 23)), (100, table(0x2000, 20))]);
+        let details = SourceStatusDetails {
+            postgres: Some(PostgresStatusDetails {
+                resnapshotted_tables: tables.clone(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let row = pack_status_row(id, "running", None, 1000, None, Some(&details));
+
+        for (datum, column_type) in row.iter().zip(MZ_SOURCE_STATUS_HISTORY_DESC.iter_types()) {
+            assert!(datum.is_instance_of(column_type));
+        }
+        assert_eq!(resnapshotted_tables(row.iter().nth(4).unwrap()), tables);
+
+        let row = pack_status_row(id, "running", None, 1000, None, None);
+        assert_eq!(
+            resnapshotted_tables(row.iter().nth(4).unwrap()),
+            BTreeMap::new()
+        );
+    }
 }
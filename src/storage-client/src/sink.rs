@@ -263,5 +263,6 @@ async fn build_kafka(
         published_schema_info,
         progress,
         fuel: builder.fuel,
+        lsn_header_index: builder.lsn_header_index,
     }))
 }
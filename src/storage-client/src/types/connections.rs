@@ -22,7 +22,7 @@ use rdkafka::config::FromClientConfigAndContext;
 use rdkafka::ClientContext;
 use serde::{Deserialize, Serialize};
 use tokio::net;
-use tokio_postgres::config::SslMode;
+use tokio_postgres::config::{SslMode, TargetSessionAttrs};
 use url::Url;
 
 use mz_ccsr::tls::{Certificate, Identity};
@@ -744,6 +744,11 @@ pub struct PostgresConnection {
     /// An optional password for authentication.
     pub password: Option<GlobalId>,
     /// A tunnel through which to route traffic.
+    ///
+    /// Sources route every connection they open through it, i.e. the
+    /// replication connection as well as the connections that copy snapshots
+    /// and query the upstream's state. An SSH tunnel is kept alive by a
+    /// background task that reconnects it when its session becomes unhealthy.
     pub tunnel: Tunnel,
     /// Whether to use TLS for encryption, authentication, or both.
     pub tls_mode: SslMode,
@@ -752,6 +757,9 @@ pub struct PostgresConnection {
     pub tls_root_cert: Option<StringOrSecret>,
     /// An optional TLS client certificate for authentication.
     pub tls_identity: Option<TlsIdentity>,
+    /// An optional hostname to send via SNI and verify the server's
+    /// certificate against, instead of `host`.
+    pub tls_sni_hostname: Option<String>,
 }
 
 impl PostgresConnection {
@@ -760,8 +768,18 @@ impl PostgresConnection {
         secrets_reader: &dyn mz_secrets::SecretsReader,
     ) -> Result<mz_postgres_util::Config, anyhow::Error> {
         let mut config = tokio_postgres::Config::new();
+        // `HOST` may list several servers to try in turn, e.g. the nodes of a
+        // replicated cluster.
+        let hosts: Vec<_> = self.host.split(',').map(str::trim).collect();
+        for host in &hosts {
+            config.host(host);
+        }
+        if hosts.len() > 1 {
+            // Replication slots can only be created on and streamed from the
+            // primary, so only connect to a writable server.
+            config.target_session_attrs(TargetSessionAttrs::ReadWrite);
+        }
         config
-            .host(&self.host)
             .port(self.port)
             .dbname(&self.database)
             .user(&self.user.get_string(secrets_reader).await?)
@@ -804,7 +822,11 @@ impl PostgresConnection {
             }
         };
 
-        Ok(mz_postgres_util::Config::new(config, tunnel)?)
+        let mut config = mz_postgres_util::Config::new(config, tunnel)?;
+        if let Some(tls_sni_hostname) = &self.tls_sni_hostname {
+            config = config.tls_sni_hostname(tls_sni_hostname.clone());
+        }
+        Ok(config)
     }
 }
 
@@ -819,6 +841,7 @@ impl RustType<ProtoPostgresConnection> for PostgresConnection {
             tls_mode: Some(self.tls_mode.into_proto()),
             tls_root_cert: self.tls_root_cert.into_proto(),
             tls_identity: self.tls_identity.into_proto(),
+            tls_sni_hostname: self.tls_sni_hostname.clone(),
             tunnel: Some(self.tunnel.into_proto()),
         }
     }
@@ -840,6 +863,7 @@ impl RustType<ProtoPostgresConnection> for PostgresConnection {
                 .into_rust_if_some("ProtoPostgresConnection::tls_mode")?,
             tls_root_cert: proto.tls_root_cert.into_rust()?,
             tls_identity: proto.tls_identity.into_rust()?,
+            tls_sni_hostname: proto.tls_sni_hostname,
         })
     }
 }
@@ -859,6 +883,7 @@ impl Arbitrary for PostgresConnection {
             any_ssl_mode(),
             any::<Option<StringOrSecret>>(),
             any::<Option<TlsIdentity>>(),
+            any::<Option<String>>(),
         )
             .prop_map(
                 |(
@@ -871,6 +896,7 @@ impl Arbitrary for PostgresConnection {
                     tls_mode,
                     tls_root_cert,
                     tls_identity,
+                    tls_sni_hostname,
                 )| {
                     PostgresConnection {
                         host,
@@ -882,6 +908,7 @@ impl Arbitrary for PostgresConnection {
                         tls_mode,
                         tls_root_cert,
                         tls_identity,
+                        tls_sni_hostname,
                     }
                 },
             )
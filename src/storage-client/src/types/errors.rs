@@ -340,15 +340,68 @@ impl Display for SourceError {
 #[derive(Ord, PartialOrd, Clone, Debug, Eq, PartialEq, Serialize, Deserialize, Hash)]
 pub enum SourceErrorDetails {
     Initialization(String),
+    /// An upstream table that the source ingests was dropped or removed from the set of tables
+    /// the source replicates.
+    TableDropped {
+        table_oid: u32,
+        table_name: String,
+    },
+    /// The replication slot the source reads from can no longer be read from, e.g. because the
+    /// upstream dropped or invalidated it.
+    SlotInvalidated {
+        slot_name: String,
+        reason: String,
+    },
+    /// The upstream is configured in a way that prevents the source from working, which the user
+    /// must fix as described by `hint`.
+    UpstreamMisconfigured {
+        error: String,
+        hint: String,
+    },
     Other(String),
 }
 
+impl SourceErrorDetails {
+    /// Reports a hint for the user about how the error could be fixed.
+    pub fn hint(&self) -> Option<String> {
+        match self {
+            SourceErrorDetails::TableDropped { .. } => {
+                Some("Re-create the source to resume replication from the remaining tables.".into())
+            }
+            SourceErrorDetails::SlotInvalidated { .. } => {
+                Some("Re-create the source to snapshot the upstream tables anew.".into())
+            }
+            SourceErrorDetails::UpstreamMisconfigured { hint, .. } => Some(hint.clone()),
+            SourceErrorDetails::Initialization(_) | SourceErrorDetails::Other(_) => None,
+        }
+    }
+}
+
 impl RustType<ProtoSourceErrorDetails> for SourceErrorDetails {
     fn into_proto(&self) -> ProtoSourceErrorDetails {
         use proto_source_error_details::Kind;
         ProtoSourceErrorDetails {
             kind: Some(match self {
                 SourceErrorDetails::Initialization(s) => Kind::Initialization(s.clone()),
+                SourceErrorDetails::TableDropped {
+                    table_oid,
+                    table_name,
+                } => Kind::TableDropped(ProtoTableDropped {
+                    table_oid: *table_oid,
+                    table_name: table_name.clone(),
+                }),
+                SourceErrorDetails::SlotInvalidated { slot_name, reason } => {
+                    Kind::SlotInvalidated(ProtoSlotInvalidated {
+                        slot_name: slot_name.clone(),
+                        reason: reason.clone(),
+                    })
+                }
+                SourceErrorDetails::UpstreamMisconfigured { error, hint } => {
+                    Kind::UpstreamMisconfigured(ProtoUpstreamMisconfigured {
+                        error: error.clone(),
+                        hint: hint.clone(),
+                    })
+                }
                 SourceErrorDetails::Other(s) => Kind::Other(s.clone()),
             }),
         }
@@ -359,6 +412,19 @@ impl RustType<ProtoSourceErrorDetails> for SourceErrorDetails {
         match proto.kind {
             Some(kind) => match kind {
                 Kind::Initialization(s) => Ok(SourceErrorDetails::Initialization(s)),
+                Kind::TableDropped(ProtoTableDropped {
+                    table_oid,
+                    table_name,
+                }) => Ok(SourceErrorDetails::TableDropped {
+                    table_oid,
+                    table_name,
+                }),
+                Kind::SlotInvalidated(ProtoSlotInvalidated { slot_name, reason }) => {
+                    Ok(SourceErrorDetails::SlotInvalidated { slot_name, reason })
+                }
+                Kind::UpstreamMisconfigured(ProtoUpstreamMisconfigured { error, hint }) => {
+                    Ok(SourceErrorDetails::UpstreamMisconfigured { error, hint })
+                }
                 Kind::DeprecatedFileIo(s) | Kind::DeprecatedPersistence(s) => {
                     warn!("Deprecated source error kind: {s}");
                     Ok(SourceErrorDetails::Other(s))
@@ -382,11 +448,29 @@ impl Display for SourceErrorDetails {
                     e
                 )
             }
+            SourceErrorDetails::TableDropped {
+                table_oid,
+                table_name,
+            } => write!(
+                f,
+                "source table {} with oid {} has been dropped",
+                table_name, table_oid
+            ),
+            SourceErrorDetails::SlotInvalidated { slot_name, reason } => {
+                write!(
+                    f,
+                    "replication slot {} can no longer be read: {}",
+                    slot_name, reason
+                )
+            }
+            SourceErrorDetails::UpstreamMisconfigured { error, .. } => write!(f, "{}", error),
             SourceErrorDetails::Other(e) => write!(f, "{}", e),
         }
     }
 }
 
+impl Error for SourceErrorDetails {}
+
 /// An error that's destined to be presented to the user in a differential dataflow collection.
 /// For example, a divide by zero will be visible in the error collection for a particular row.
 ///
@@ -467,9 +551,11 @@ impl From<EnvelopeError> for DataflowError {
 
 #[cfg(test)]
 mod tests {
+    use mz_proto::protobuf_roundtrip;
+
     use crate::types::errors::DecodeErrorKind;
 
-    use super::DecodeError;
+    use super::{DecodeError, ProtoSourceErrorDetails, SourceErrorDetails};
 
     #[test]
     fn test_decode_error_codec_roundtrip() -> Result<(), String> {
@@ -485,4 +571,45 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_table_dropped_roundtrip() {
+        let original = SourceErrorDetails::TableDropped {
+            table_oid: 16384,
+            table_name: "t1".into(),
+        };
+        let decoded =
+            protobuf_roundtrip::<_, ProtoSourceErrorDetails>(&original).expect("valid proto");
+        assert_eq!(decoded, original);
+        assert_eq!(
+            decoded.to_string(),
+            "source table t1 with oid 16384 has been dropped"
+        );
+        assert!(decoded.hint().is_some());
+    }
+
+    #[test]
+    fn test_slot_invalidated_roundtrip() {
+        let original = SourceErrorDetails::SlotInvalidated {
+            slot_name: "materialize_1".into(),
+            reason: "it was dropped upstream".into(),
+        };
+        let decoded =
+            protobuf_roundtrip::<_, ProtoSourceErrorDetails>(&original).expect("valid proto");
+        assert_eq!(decoded, original);
+        assert_eq!(
+            decoded.to_string(),
+            "replication slot materialize_1 can no longer be read: it was dropped upstream"
+        );
+        assert!(decoded.hint().is_some());
+
+        let original = SourceErrorDetails::UpstreamMisconfigured {
+            error: "logical decoding is disabled".into(),
+            hint: "enable it".into(),
+        };
+        let decoded =
+            protobuf_roundtrip::<_, ProtoSourceErrorDetails>(&original).expect("valid proto");
+        assert_eq!(decoded, original);
+        assert_eq!(decoded.hint().as_deref(), Some("enable it"));
+    }
 }
@@ -9,10 +9,14 @@
 
 //! Configuration parameter types.
 
+use std::collections::BTreeSet;
+use std::time::Duration;
+
 use serde::{Deserialize, Serialize};
 
 use mz_persist_client::cfg::PersistParameters;
-use mz_proto::{IntoRustIfSome, RustType, TryFromProtoError};
+use mz_proto::{IntoRustIfSome, ProtoType, RustType, TryFromProtoError};
+use mz_repr::GlobalId;
 
 include!(concat!(
     env!("OUT_DIR"),
@@ -28,6 +32,28 @@ pub struct StorageParameters {
     /// Controls whether or not to use the new storage `persist_sink` implementation in storage
     /// ingestions.
     pub enable_multi_worker_storage_persist_sink: bool,
+    /// The maximum size in bytes of a single decoded row ingested by a Postgres source.
+    pub pg_source_max_row_size_bytes: Option<usize>,
+    /// The maximum number of changes a Postgres source buffers for a single upstream
+    /// transaction.
+    pub pg_source_max_transaction_changes: Option<usize>,
+    /// How long the resume LSN of a Postgres source may stay unchanged before the source is
+    /// reported as stalled.
+    pub pg_source_lsn_staleness_threshold: Option<Duration>,
+    /// The Postgres sources whose replication is paused. Unlike the other parameters, this is
+    /// always set, as an empty set means that no source is paused.
+    pub pg_source_paused_ids: BTreeSet<GlobalId>,
+    /// How often a Postgres source audits the upstream schemas of its ingested tables.
+    pub pg_source_schema_audit_interval: Option<Duration>,
+    /// The size in bytes of the changes a Postgres source buffers for uncommitted upstream
+    /// transactions beyond which it reports itself as degraded.
+    pub pg_source_transaction_buffer_degraded_bytes: Option<usize>,
+    /// How many bytes of WAL the changes a Postgres source emitted may be ahead of the ones that
+    /// were committed downstream before it stops reading from its replication stream.
+    pub pg_source_backpressure_lag_bytes: Option<usize>,
+    /// The minimum time between the status updates a Postgres source sends in reply to the
+    /// requests of its upstream.
+    pub pg_source_min_feedback_interval: Option<Duration>,
     /// Persist client configuration.
     pub persist: PersistParameters,
 }
@@ -37,6 +63,29 @@ impl StorageParameters {
     pub fn update(&mut self, other: StorageParameters) {
         self.enable_multi_worker_storage_persist_sink =
             other.enable_multi_worker_storage_persist_sink;
+        if other.pg_source_max_row_size_bytes.is_some() {
+            self.pg_source_max_row_size_bytes = other.pg_source_max_row_size_bytes;
+        }
+        if other.pg_source_max_transaction_changes.is_some() {
+            self.pg_source_max_transaction_changes = other.pg_source_max_transaction_changes;
+        }
+        if other.pg_source_lsn_staleness_threshold.is_some() {
+            self.pg_source_lsn_staleness_threshold = other.pg_source_lsn_staleness_threshold;
+        }
+        self.pg_source_paused_ids = other.pg_source_paused_ids;
+        if other.pg_source_schema_audit_interval.is_some() {
+            self.pg_source_schema_audit_interval = other.pg_source_schema_audit_interval;
+        }
+        if other.pg_source_transaction_buffer_degraded_bytes.is_some() {
+            self.pg_source_transaction_buffer_degraded_bytes =
+                other.pg_source_transaction_buffer_degraded_bytes;
+        }
+        if other.pg_source_backpressure_lag_bytes.is_some() {
+            self.pg_source_backpressure_lag_bytes = other.pg_source_backpressure_lag_bytes;
+        }
+        if other.pg_source_min_feedback_interval.is_some() {
+            self.pg_source_min_feedback_interval = other.pg_source_min_feedback_interval;
+        }
         self.persist.update(other.persist);
     }
 }
@@ -45,6 +94,16 @@ impl RustType<ProtoStorageParameters> for StorageParameters {
     fn into_proto(&self) -> ProtoStorageParameters {
         ProtoStorageParameters {
             enable_multi_worker_storage_persist_sink: self.enable_multi_worker_storage_persist_sink,
+            pg_source_max_row_size_bytes: self.pg_source_max_row_size_bytes.into_proto(),
+            pg_source_max_transaction_changes: self.pg_source_max_transaction_changes.into_proto(),
+            pg_source_lsn_staleness_threshold: self.pg_source_lsn_staleness_threshold.into_proto(),
+            pg_source_paused_ids: self.pg_source_paused_ids.into_proto(),
+            pg_source_schema_audit_interval: self.pg_source_schema_audit_interval.into_proto(),
+            pg_source_transaction_buffer_degraded_bytes: self
+                .pg_source_transaction_buffer_degraded_bytes
+                .into_proto(),
+            pg_source_backpressure_lag_bytes: self.pg_source_backpressure_lag_bytes.into_proto(),
+            pg_source_min_feedback_interval: self.pg_source_min_feedback_interval.into_proto(),
             persist: Some(self.persist.into_proto()),
         }
     }
@@ -53,6 +112,20 @@ impl RustType<ProtoStorageParameters> for StorageParameters {
         Ok(Self {
             enable_multi_worker_storage_persist_sink: proto
                 .enable_multi_worker_storage_persist_sink,
+            pg_source_max_row_size_bytes: proto.pg_source_max_row_size_bytes.into_rust()?,
+            pg_source_max_transaction_changes: proto
+                .pg_source_max_transaction_changes
+                .into_rust()?,
+            pg_source_lsn_staleness_threshold: proto
+                .pg_source_lsn_staleness_threshold
+                .into_rust()?,
+            pg_source_paused_ids: proto.pg_source_paused_ids.into_rust()?,
+            pg_source_schema_audit_interval: proto.pg_source_schema_audit_interval.into_rust()?,
+            pg_source_transaction_buffer_degraded_bytes: proto
+                .pg_source_transaction_buffer_degraded_bytes
+                .into_rust()?,
+            pg_source_backpressure_lag_bytes: proto.pg_source_backpressure_lag_bytes.into_rust()?,
+            pg_source_min_feedback_interval: proto.pg_source_min_feedback_interval.into_rust()?,
             persist: proto
                 .persist
                 .into_rust_if_some("ProtoStorageParameters::persist")?,
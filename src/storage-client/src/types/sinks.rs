@@ -289,6 +289,9 @@ pub struct KafkaSinkConnection {
     // Maximum number of records the sink will attempt to send each time it is
     // invoked
     pub fuel: usize,
+    /// The `mz_provenance` column of the sunk relation, whose LSN is attached to each message as
+    /// the `mz-lsn` header, if requested with `INCLUDE LSN HEADER`.
+    pub lsn_header_index: Option<usize>,
 }
 
 proptest::prop_compose! {
@@ -302,6 +305,7 @@ proptest::prop_compose! {
         published_schema_info in any::<Option<PublishedSchemaInfo>>(),
         progress in any::<KafkaSinkProgressConnection>(),
         fuel in any::<usize>(),
+        lsn_header_index in any::<Option<usize>>(),
     ) -> KafkaSinkConnection {
         KafkaSinkConnection {
             connection,
@@ -313,6 +317,7 @@ proptest::prop_compose! {
             published_schema_info,
             progress,
             fuel,
+            lsn_header_index,
         }
     }
 }
@@ -372,6 +377,7 @@ impl RustType<ProtoKafkaSinkConnection> for KafkaSinkConnection {
             published_schema_info: self.published_schema_info.into_proto(),
             progress: Some(self.progress.into_proto()),
             fuel: self.fuel.into_proto(),
+            lsn_header_index: self.lsn_header_index.into_proto(),
         }
     }
 
@@ -394,6 +400,7 @@ impl RustType<ProtoKafkaSinkConnection> for KafkaSinkConnection {
                 .progress
                 .into_rust_if_some("ProtoKafkaSinkConnection::progress")?,
             fuel: proto.fuel.into_rust()?,
+            lsn_header_index: proto.lsn_header_index.into_rust()?,
         })
     }
 }
@@ -465,6 +472,8 @@ pub struct KafkaSinkConnectionBuilder {
     pub replication_factor: i32,
     pub fuel: usize,
     pub retention: KafkaSinkConnectionRetention,
+    /// The column of the sunk relation to take the `mz-lsn` header of each message from.
+    pub lsn_header_index: Option<usize>,
 }
 
 #[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
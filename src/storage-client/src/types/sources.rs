@@ -512,6 +512,11 @@ impl RustType<ProtoMzOffset> for MzOffset {
 }
 
 impl MzOffset {
+    /// The offset that a source starts at, before it has read anything.
+    pub const ZERO: Self = Self { offset: 0 };
+    /// The largest offset, which no source ever reaches.
+    pub const MAX: Self = Self { offset: u64::MAX };
+
     pub fn checked_sub(self, other: Self) -> Option<Self> {
         self.offset
             .checked_sub(other.offset)
@@ -618,6 +623,7 @@ pub enum IncludedColumnSource {
     Timestamp,
     Topic,
     Headers,
+    RawValue,
 }
 
 impl RustType<ProtoIncludedColumnSource> for IncludedColumnSource {
@@ -630,6 +636,7 @@ impl RustType<ProtoIncludedColumnSource> for IncludedColumnSource {
                 IncludedColumnSource::Timestamp => Kind::Timestamp(()),
                 IncludedColumnSource::Topic => Kind::Topic(()),
                 IncludedColumnSource::Headers => Kind::Headers(()),
+                IncludedColumnSource::RawValue => Kind::RawValue(()),
             }),
         }
     }
@@ -645,6 +652,7 @@ impl RustType<ProtoIncludedColumnSource> for IncludedColumnSource {
             Kind::Timestamp(()) => IncludedColumnSource::Timestamp,
             Kind::Topic(()) => IncludedColumnSource::Topic,
             Kind::Headers(()) => IncludedColumnSource::Headers,
+            Kind::RawValue(()) => IncludedColumnSource::RawValue,
         })
     }
 }
@@ -1404,6 +1412,9 @@ pub struct KafkaSourceConnection {
     /// If present, include the offset as an output column of the source with the given name.
     pub include_offset: Option<IncludedColumnPos>,
     pub include_headers: Option<IncludedColumnPos>,
+    /// If present, include the undecoded message value as an output column of the source with
+    /// the given name.
+    pub include_raw_value: Option<IncludedColumnPos>,
 }
 
 pub static KAFKA_PROGRESS_DESC: Lazy<RelationDesc> = Lazy::new(|| {
@@ -1464,16 +1475,21 @@ impl SourceConnection for KafkaSourceConnection {
             custom_id: None,
         };
         let metadata_columns = [
-            (&self.include_offset, ScalarType::UInt64),
-            (&self.include_partition, ScalarType::Int32),
-            (&self.include_timestamp, ScalarType::Timestamp),
-            (&self.include_topic, ScalarType::String),
-            (&self.include_headers, header_typ),
+            (&self.include_offset, ScalarType::UInt64.nullable(false)),
+            (&self.include_partition, ScalarType::Int32.nullable(false)),
+            (
+                &self.include_timestamp,
+                ScalarType::Timestamp.nullable(false),
+            ),
+            (&self.include_topic, ScalarType::String.nullable(false)),
+            (&self.include_headers, header_typ.nullable(false)),
+            // Messages without a value, like tombstones, have no raw value either.
+            (&self.include_raw_value, ScalarType::Bytes.nullable(true)),
         ];
 
         for (include, ty) in metadata_columns {
             if let Some(include) = include {
-                items.insert(include.pos + 1, (&*include.name, ty.nullable(false)));
+                items.insert(include.pos + 1, (&*include.name, ty));
             }
         }
 
@@ -1491,6 +1507,7 @@ impl SourceConnection for KafkaSourceConnection {
             (&self.include_timestamp, IncludedColumnSource::Timestamp),
             (&self.include_topic, IncludedColumnSource::Topic),
             (&self.include_headers, IncludedColumnSource::Headers),
+            (&self.include_raw_value, IncludedColumnSource::RawValue),
         ];
         for (include, ty) in metadata_columns {
             if let Some(include) = include {
@@ -1519,6 +1536,7 @@ impl Arbitrary for KafkaSourceConnection {
             any::<Option<IncludedColumnPos>>(),
             any::<Option<IncludedColumnPos>>(),
             any::<Option<IncludedColumnPos>>(),
+            any::<Option<IncludedColumnPos>>(),
         )
             .prop_map(
                 |(
@@ -1533,6 +1551,7 @@ impl Arbitrary for KafkaSourceConnection {
                     include_topic,
                     include_offset,
                     include_headers,
+                    include_raw_value,
                 )| KafkaSourceConnection {
                     connection,
                     connection_id,
@@ -1545,6 +1564,7 @@ impl Arbitrary for KafkaSourceConnection {
                     include_topic,
                     include_offset,
                     include_headers,
+                    include_raw_value,
                 },
             )
             .boxed()
@@ -1566,6 +1586,7 @@ impl RustType<ProtoKafkaSourceConnection> for KafkaSourceConnection {
             include_topic: self.include_topic.into_proto(),
             include_offset: self.include_offset.into_proto(),
             include_headers: self.include_headers.into_proto(),
+            include_raw_value: self.include_raw_value.into_proto(),
         }
     }
 
@@ -1593,6 +1614,7 @@ impl RustType<ProtoKafkaSourceConnection> for KafkaSourceConnection {
             include_topic: proto.include_topic.into_rust()?,
             include_offset: proto.include_offset.into_rust()?,
             include_headers: proto.include_headers.into_rust()?,
+            include_raw_value: proto.include_raw_value.into_rust()?,
         })
     }
 }
@@ -1874,6 +1896,386 @@ pub struct PostgresSourceConnection {
     pub table_casts: BTreeMap<usize, Vec<MirScalarExpr>>,
     pub publication: String,
     pub publication_details: PostgresSourcePublicationDetails,
+    /// Whether to ask the upstream server to stream large transactions before
+    /// they commit (Postgres 14+).
+    pub streaming_transactions: bool,
+    /// If set, transactions that buffer more than this many changes are
+    /// emitted in several parts instead of atomically.
+    pub max_transaction_rows: Option<usize>,
+    /// If set, the liveness of the replication connection is checked whenever
+    /// no message was received from it for this long.
+    pub ping_interval: Option<Duration>,
+    /// If set, tables are snapshotted by fetching this many rows at a time
+    /// from a cursor instead of copying them in one go.
+    pub snapshot_cursor_fetch_size: Option<usize>,
+    /// Whether each message carries headers identifying the upstream table,
+    /// transaction and LSN it originates from.
+    pub provenance_headers: bool,
+    /// The `statement_timeout` of the queries that snapshot tables. If unset,
+    /// the timeout is disabled for them, regardless of the upstream default.
+    pub snapshot_statement_timeout: Option<Duration>,
+    /// Whether a table whose upstream schema changed incompatibly is
+    /// snapshotted anew instead of erroring, if its columns still cast into
+    /// their existing types.
+    pub resnapshot_on_schema_change: bool,
+    /// The logical decoding output plugin of the replication slot.
+    pub replication_plugin: ReplicationPlugin,
+    /// Whether WAL replay is paused while snapshotting from a standby, so
+    /// that the snapshot does not need to be rewound across the changes the
+    /// standby replays during the copy.
+    pub synchronize_replicas: bool,
+    /// The server to stream changes from.
+    pub replication_source: ReplicationSource,
+    /// The order in which tables are snapshotted.
+    pub snapshot_order: SnapshotOrder,
+    /// If set, the file to which the raw payload of every `XLogData` message
+    /// of the replication stream is appended before it is decoded. Only
+    /// meant for debugging replication issues.
+    pub capture_raw_wal: Option<String>,
+    /// The OIDs of the tables whose deleted rows are kept, flagged as deleted
+    /// by their last column, instead of being retracted.
+    pub soft_delete_tables: BTreeSet<u32>,
+    /// If set, a libpq connection string that identifies a hot standby of the
+    /// server the source replicates from by its `host` and `port`, which the
+    /// initial snapshot copies tables from instead. See
+    /// [`standby_connection`].
+    pub snapshot_standby: Option<String>,
+    /// Whether the rows of a table that is truncated upstream are retracted
+    /// instead of erroring.
+    pub retract_on_truncate: bool,
+    /// If set, the OIDs of the only tables of the publication that produce
+    /// output, for users who can't change which tables the publication
+    /// contains. The other tables are still checked for compatibility.
+    pub table_oid_allowlist: Option<BTreeSet<u32>>,
+    /// The names that tables are expected to be renamed to upstream, by OID.
+    /// A table renamed to one of its expected names is still considered
+    /// compatible, and is known by its new name from then on.
+    pub publication_table_rename_map: BTreeMap<u32, BTreeSet<String>>,
+    /// If set, the `client_min_messages` of the source's connections to the
+    /// server it replicates from. If unset, the upstream default applies.
+    pub client_min_messages: Option<PgLogLevel>,
+    /// Whether tables that are `UNLOGGED` upstream are snapshotted, and then
+    /// never change, instead of erroring.
+    pub snapshot_unlogged_tables: bool,
+    /// How long the snapshot of a table may wait for the next chunk of rows
+    /// from upstream before it is retried.
+    pub copy_read_timeout: Duration,
+    /// The format in which upstream sends the rows of snapshotted tables.
+    pub snapshot_format: SnapshotFormat,
+}
+
+impl PostgresSourceConnection {
+    /// The default of [`PostgresSourceConnection::copy_read_timeout`].
+    pub const DEFAULT_COPY_READ_TIMEOUT: Duration = Duration::from_secs(30);
+}
+
+/// A logical decoding output plugin that a Postgres source can replicate
+/// changes with.
+#[derive(Arbitrary, Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ReplicationPlugin {
+    /// The `pgoutput` plugin built into Postgres.
+    PgOutput,
+    /// The `decoderbufs` plugin used by Debezium, which encodes changes as
+    /// Protocol Buffers.
+    DecoderBufs,
+}
+
+impl ReplicationPlugin {
+    /// The name the plugin is installed under upstream.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ReplicationPlugin::PgOutput => "pgoutput",
+            ReplicationPlugin::DecoderBufs => "decoderbufs",
+        }
+    }
+}
+
+impl RustType<ProtoReplicationPlugin> for ReplicationPlugin {
+    fn into_proto(&self) -> ProtoReplicationPlugin {
+        use proto_replication_plugin::Kind;
+        ProtoReplicationPlugin {
+            kind: Some(match self {
+                ReplicationPlugin::PgOutput => Kind::PgOutput(()),
+                ReplicationPlugin::DecoderBufs => Kind::DecoderBufs(()),
+            }),
+        }
+    }
+
+    fn from_proto(proto: ProtoReplicationPlugin) -> Result<Self, TryFromProtoError> {
+        use proto_replication_plugin::Kind;
+        Ok(match proto.kind {
+            Some(Kind::PgOutput(())) => ReplicationPlugin::PgOutput,
+            Some(Kind::DecoderBufs(())) => ReplicationPlugin::DecoderBufs,
+            None => {
+                return Err(TryFromProtoError::MissingField(
+                    "ProtoReplicationPlugin::kind".into(),
+                ))
+            }
+        })
+    }
+}
+
+/// The server a Postgres source replicates from.
+///
+/// A replication slot only exists on the server it was created on, so the
+/// source creates its slot on, snapshots from and streams changes from the
+/// same server. Only the planning of the source reads from the server of its
+/// connection regardless.
+#[derive(Arbitrary, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ReplicationSource {
+    /// The server of the source's connection.
+    Primary,
+    /// A standby of the server of the source's connection, which decodes
+    /// changes itself (Postgres 16+). Its slot survives the standby's
+    /// promotion, after which the source keeps replicating from it.
+    Standby {
+        /// A libpq connection string that identifies the standby by its `host`
+        /// and `port`. All other settings, e.g. the credentials, are taken from
+        /// the source's connection.
+        connection_string: String,
+    },
+}
+
+impl ReplicationSource {
+    /// Returns the connection to replicate over, given the source's
+    /// `connection`.
+    ///
+    /// # Errors
+    /// - If the connection string of a standby does not parse, contains a
+    ///   password, or does not name exactly one TCP host and at most one port.
+    pub fn connection(
+        &self,
+        connection: &PostgresConnection,
+    ) -> Result<PostgresConnection, anyhow::Error> {
+        match self {
+            ReplicationSource::Primary => Ok(connection.clone()),
+            ReplicationSource::Standby { connection_string } => {
+                standby_connection(connection, connection_string)
+            }
+        }
+    }
+}
+
+/// Returns the connection to the standby of the server of `connection` that
+/// `connection_string` identifies by its `host` and `port`, with all other
+/// settings taken from `connection`. The SSL SNI hostname of `connection`
+/// names its own server, so the standby is sent its host instead.
+///
+/// # Errors
+/// - If `connection_string` does not parse, contains a password, or does not
+///   name exactly one TCP host and at most one port.
+pub fn standby_connection(
+    connection: &PostgresConnection,
+    connection_string: &str,
+) -> Result<PostgresConnection, anyhow::Error> {
+    let config = tokio_postgres::Config::from_str(connection_string)
+        .map_err(|e| anyhow!("invalid connection string: {e}"))?;
+    if config.get_password().is_some() {
+        bail!("connection string must not contain a password");
+    }
+    // Connections to several hosts only accept a writable server.
+    let host = match config.get_hosts() {
+        [tokio_postgres::config::Host::Tcp(host)] => host.clone(),
+        [_] => bail!("connection string must name a TCP host"),
+        _ => bail!("connection string must name exactly one host"),
+    };
+    let port = match config.get_ports() {
+        [] => connection.port,
+        [port] => *port,
+        _ => bail!("connection string must name at most one port"),
+    };
+    Ok(PostgresConnection {
+        host,
+        port,
+        tls_sni_hostname: None,
+        ..connection.clone()
+    })
+}
+
+/// The order in which a Postgres source snapshots its tables.
+#[derive(Arbitrary, Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub enum SnapshotOrder {
+    /// The largest tables first, by their estimated size upstream, so that
+    /// the longest copies start as early as possible.
+    #[default]
+    Size,
+    /// The tables in the order of their outputs, which does not depend on
+    /// the upstream statistics and is hence reproducible.
+    OutputIndex,
+}
+
+impl RustType<ProtoSnapshotOrder> for SnapshotOrder {
+    fn into_proto(&self) -> ProtoSnapshotOrder {
+        use proto_snapshot_order::Kind;
+        ProtoSnapshotOrder {
+            kind: Some(match self {
+                SnapshotOrder::Size => Kind::Size(()),
+                SnapshotOrder::OutputIndex => Kind::OutputIndex(()),
+            }),
+        }
+    }
+
+    fn from_proto(proto: ProtoSnapshotOrder) -> Result<Self, TryFromProtoError> {
+        use proto_snapshot_order::Kind;
+        Ok(match proto.kind {
+            Some(Kind::Size(())) => SnapshotOrder::Size,
+            Some(Kind::OutputIndex(())) => SnapshotOrder::OutputIndex,
+            None => {
+                return Err(TryFromProtoError::MissingField(
+                    "ProtoSnapshotOrder::kind".into(),
+                ))
+            }
+        })
+    }
+}
+
+/// The `COPY` format in which a Postgres source snapshots its tables.
+#[derive(Arbitrary, Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub enum SnapshotFormat {
+    /// The tab-delimited text format, whose values are ingested as sent.
+    #[default]
+    Text,
+    /// The binary format, which is more compact for most types, and whose
+    /// values are decoded and re-encoded as text.
+    Binary,
+}
+
+impl RustType<ProtoSnapshotFormat> for SnapshotFormat {
+    fn into_proto(&self) -> ProtoSnapshotFormat {
+        use proto_snapshot_format::Kind;
+        ProtoSnapshotFormat {
+            kind: Some(match self {
+                SnapshotFormat::Text => Kind::Text(()),
+                SnapshotFormat::Binary => Kind::Binary(()),
+            }),
+        }
+    }
+
+    fn from_proto(proto: ProtoSnapshotFormat) -> Result<Self, TryFromProtoError> {
+        use proto_snapshot_format::Kind;
+        Ok(match proto.kind {
+            Some(Kind::Text(())) => SnapshotFormat::Text,
+            Some(Kind::Binary(())) => SnapshotFormat::Binary,
+            None => {
+                return Err(TryFromProtoError::MissingField(
+                    "ProtoSnapshotFormat::kind".into(),
+                ))
+            }
+        })
+    }
+}
+
+/// The severity levels of the messages that Postgres sends to clients, from
+/// the least to the most severe.
+#[derive(Arbitrary, Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum PgLogLevel {
+    Debug5,
+    Debug4,
+    Debug3,
+    Debug2,
+    Debug1,
+    Log,
+    Notice,
+    Warning,
+    Error,
+}
+
+impl PgLogLevel {
+    /// All levels, from the least to the most severe.
+    pub const ALL: [PgLogLevel; 9] = [
+        PgLogLevel::Debug5,
+        PgLogLevel::Debug4,
+        PgLogLevel::Debug3,
+        PgLogLevel::Debug2,
+        PgLogLevel::Debug1,
+        PgLogLevel::Log,
+        PgLogLevel::Notice,
+        PgLogLevel::Warning,
+        PgLogLevel::Error,
+    ];
+
+    /// The name of the level in Postgres.
+    pub fn name(&self) -> &'static str {
+        match self {
+            PgLogLevel::Debug5 => "debug5",
+            PgLogLevel::Debug4 => "debug4",
+            PgLogLevel::Debug3 => "debug3",
+            PgLogLevel::Debug2 => "debug2",
+            PgLogLevel::Debug1 => "debug1",
+            PgLogLevel::Log => "log",
+            PgLogLevel::Notice => "notice",
+            PgLogLevel::Warning => "warning",
+            PgLogLevel::Error => "error",
+        }
+    }
+}
+
+impl RustType<ProtoPgLogLevel> for PgLogLevel {
+    fn into_proto(&self) -> ProtoPgLogLevel {
+        use proto_pg_log_level::Kind;
+        ProtoPgLogLevel {
+            kind: Some(match self {
+                PgLogLevel::Debug5 => Kind::Debug5(()),
+                PgLogLevel::Debug4 => Kind::Debug4(()),
+                PgLogLevel::Debug3 => Kind::Debug3(()),
+                PgLogLevel::Debug2 => Kind::Debug2(()),
+                PgLogLevel::Debug1 => Kind::Debug1(()),
+                PgLogLevel::Log => Kind::Log(()),
+                PgLogLevel::Notice => Kind::Notice(()),
+                PgLogLevel::Warning => Kind::Warning(()),
+                PgLogLevel::Error => Kind::Error(()),
+            }),
+        }
+    }
+
+    fn from_proto(proto: ProtoPgLogLevel) -> Result<Self, TryFromProtoError> {
+        use proto_pg_log_level::Kind;
+        Ok(match proto.kind {
+            Some(Kind::Debug5(())) => PgLogLevel::Debug5,
+            Some(Kind::Debug4(())) => PgLogLevel::Debug4,
+            Some(Kind::Debug3(())) => PgLogLevel::Debug3,
+            Some(Kind::Debug2(())) => PgLogLevel::Debug2,
+            Some(Kind::Debug1(())) => PgLogLevel::Debug1,
+            Some(Kind::Log(())) => PgLogLevel::Log,
+            Some(Kind::Notice(())) => PgLogLevel::Notice,
+            Some(Kind::Warning(())) => PgLogLevel::Warning,
+            Some(Kind::Error(())) => PgLogLevel::Error,
+            None => {
+                return Err(TryFromProtoError::MissingField(
+                    "ProtoPgLogLevel::kind".into(),
+                ))
+            }
+        })
+    }
+}
+
+impl RustType<ProtoReplicationSource> for ReplicationSource {
+    fn into_proto(&self) -> ProtoReplicationSource {
+        use proto_replication_source::Kind;
+        ProtoReplicationSource {
+            kind: Some(match self {
+                ReplicationSource::Primary => Kind::Primary(()),
+                ReplicationSource::Standby { connection_string } => {
+                    Kind::Standby(connection_string.clone())
+                }
+            }),
+        }
+    }
+
+    fn from_proto(proto: ProtoReplicationSource) -> Result<Self, TryFromProtoError> {
+        use proto_replication_source::Kind;
+        Ok(match proto.kind {
+            Some(Kind::Primary(())) => ReplicationSource::Primary,
+            Some(Kind::Standby(connection_string)) => {
+                ReplicationSource::Standby { connection_string }
+            }
+            None => {
+                return Err(TryFromProtoError::MissingField(
+                    "ProtoReplicationSource::kind".into(),
+                ))
+            }
+        })
+    }
 }
 
 impl Arbitrary for PostgresSourceConnection {
@@ -1891,14 +2293,95 @@ impl Arbitrary for PostgresSourceConnection {
             ),
             any::<String>(),
             any::<PostgresSourcePublicationDetails>(),
+            any::<bool>(),
+            any::<Option<usize>>(),
+            any::<Option<Duration>>(),
+            any::<Option<usize>>(),
+            any::<bool>(),
+            any::<Option<Duration>>(),
+            (
+                any::<bool>(),
+                any::<ReplicationPlugin>(),
+                any::<bool>(),
+                any::<ReplicationSource>(),
+                any::<SnapshotOrder>(),
+                any::<Option<String>>(),
+                proptest::collection::btree_set(any::<u32>(), 0..4),
+                any::<Option<String>>(),
+                any::<bool>(),
+                proptest::option::of(proptest::collection::btree_set(any::<u32>(), 0..4)),
+                proptest::collection::btree_map(
+                    any::<u32>(),
+                    proptest::collection::btree_set(any::<String>(), 1..3),
+                    0..4,
+                ),
+                (
+                    any::<Option<PgLogLevel>>(),
+                    any::<bool>(),
+                    any::<Duration>(),
+                    any::<SnapshotFormat>(),
+                ),
+            ),
         )
             .prop_map(
-                |(connection, connection_id, table_casts, publication, details)| Self {
+                |(
+                    connection,
+                    connection_id,
+                    table_casts,
+                    publication,
+                    details,
+                    streaming_transactions,
+                    max_transaction_rows,
+                    ping_interval,
+                    snapshot_cursor_fetch_size,
+                    provenance_headers,
+                    snapshot_statement_timeout,
+                    (
+                        resnapshot_on_schema_change,
+                        replication_plugin,
+                        synchronize_replicas,
+                        replication_source,
+                        snapshot_order,
+                        capture_raw_wal,
+                        soft_delete_tables,
+                        snapshot_standby,
+                        retract_on_truncate,
+                        table_oid_allowlist,
+                        publication_table_rename_map,
+                        (
+                            client_min_messages,
+                            snapshot_unlogged_tables,
+                            copy_read_timeout,
+                            snapshot_format,
+                        ),
+                    ),
+                )| Self {
                     connection,
                     connection_id,
                     table_casts,
                     publication,
                     publication_details: details,
+                    streaming_transactions,
+                    max_transaction_rows,
+                    ping_interval,
+                    snapshot_cursor_fetch_size,
+                    provenance_headers,
+                    snapshot_statement_timeout,
+                    resnapshot_on_schema_change,
+                    replication_plugin,
+                    synchronize_replicas,
+                    replication_source,
+                    snapshot_order,
+                    capture_raw_wal,
+                    soft_delete_tables,
+                    snapshot_standby,
+                    retract_on_truncate,
+                    table_oid_allowlist,
+                    publication_table_rename_map,
+                    client_min_messages,
+                    snapshot_unlogged_tables,
+                    copy_read_timeout,
+                    snapshot_format,
                 },
             )
             .boxed()
@@ -1961,6 +2444,40 @@ impl RustType<ProtoPostgresSourceConnection> for PostgresSourceConnection {
             details: Some(self.publication_details.into_proto()),
             table_casts,
             table_cast_pos,
+            streaming_transactions: self.streaming_transactions,
+            max_transaction_rows: self.max_transaction_rows.map(mz_ore::cast::usize_to_u64),
+            ping_interval: self.ping_interval.into_proto(),
+            snapshot_cursor_fetch_size: self
+                .snapshot_cursor_fetch_size
+                .map(mz_ore::cast::usize_to_u64),
+            provenance_headers: self.provenance_headers,
+            snapshot_statement_timeout: self.snapshot_statement_timeout.into_proto(),
+            resnapshot_on_schema_change: self.resnapshot_on_schema_change,
+            replication_plugin: Some(self.replication_plugin.into_proto()),
+            synchronize_replicas: self.synchronize_replicas,
+            replication_source: Some(self.replication_source.into_proto()),
+            snapshot_order: Some(self.snapshot_order.into_proto()),
+            capture_raw_wal: self.capture_raw_wal.clone(),
+            soft_delete_tables: self.soft_delete_tables.iter().copied().collect(),
+            snapshot_standby: self.snapshot_standby.clone(),
+            retract_on_truncate: self.retract_on_truncate,
+            table_oid_allowlist: self.table_oid_allowlist.as_ref().map(|oids| {
+                ProtoTableOidAllowlist {
+                    oids: oids.iter().copied().collect(),
+                }
+            }),
+            publication_table_rename_map: self
+                .publication_table_rename_map
+                .iter()
+                .map(|(oid, names)| {
+                    let names = names.iter().cloned().collect();
+                    (*oid, ProtoTableRenames { names })
+                })
+                .collect(),
+            client_min_messages: self.client_min_messages.into_proto(),
+            snapshot_unlogged_tables: self.snapshot_unlogged_tables,
+            copy_read_timeout: Some(self.copy_read_timeout.into_proto()),
+            snapshot_format: Some(self.snapshot_format.into_proto()),
         }
     }
 
@@ -2002,6 +2519,48 @@ impl RustType<ProtoPostgresSourceConnection> for PostgresSourceConnection {
                 .details
                 .into_rust_if_some("ProtoPostgresSourceConnection::details")?,
             table_casts,
+            streaming_transactions: proto.streaming_transactions,
+            max_transaction_rows: proto.max_transaction_rows.map(mz_ore::cast::u64_to_usize),
+            ping_interval: proto.ping_interval.into_rust()?,
+            snapshot_cursor_fetch_size: proto
+                .snapshot_cursor_fetch_size
+                .map(mz_ore::cast::u64_to_usize),
+            provenance_headers: proto.provenance_headers,
+            snapshot_statement_timeout: proto.snapshot_statement_timeout.into_rust()?,
+            resnapshot_on_schema_change: proto.resnapshot_on_schema_change,
+            replication_plugin: proto
+                .replication_plugin
+                .into_rust_if_some("ProtoPostgresSourceConnection::replication_plugin")?,
+            synchronize_replicas: proto.synchronize_replicas,
+            replication_source: proto
+                .replication_source
+                .into_rust_if_some("ProtoPostgresSourceConnection::replication_source")?,
+            snapshot_order: proto
+                .snapshot_order
+                .into_rust_if_some("ProtoPostgresSourceConnection::snapshot_order")?,
+            capture_raw_wal: proto.capture_raw_wal,
+            soft_delete_tables: proto.soft_delete_tables.into_iter().collect(),
+            snapshot_standby: proto.snapshot_standby,
+            retract_on_truncate: proto.retract_on_truncate,
+            table_oid_allowlist: proto
+                .table_oid_allowlist
+                .map(|allowlist| allowlist.oids.into_iter().collect()),
+            publication_table_rename_map: proto
+                .publication_table_rename_map
+                .into_iter()
+                .map(|(oid, renames)| (oid, renames.names.into_iter().collect()))
+                .collect(),
+            client_min_messages: proto.client_min_messages.into_rust()?,
+            snapshot_unlogged_tables: proto.snapshot_unlogged_tables,
+            // Sources created before the timeout was configurable use the
+            // default.
+            copy_read_timeout: proto
+                .copy_read_timeout
+                .into_rust()?
+                .unwrap_or(PostgresSourceConnection::DEFAULT_COPY_READ_TIMEOUT),
+            // Sources created before the format was selectable snapshot as
+            // text.
+            snapshot_format: proto.snapshot_format.into_rust()?.unwrap_or_default(),
         })
     }
 }
@@ -2010,6 +2569,9 @@ impl RustType<ProtoPostgresSourceConnection> for PostgresSourceConnection {
 pub struct PostgresSourcePublicationDetails {
     pub tables: Vec<mz_postgres_util::desc::PostgresTableDesc>,
     pub slot: String,
+    /// The publication `tables` were read from. Unknown for sources created
+    /// before it was recorded.
+    pub publication: Option<String>,
 }
 
 impl RustType<ProtoPostgresSourcePublicationDetails> for PostgresSourcePublicationDetails {
@@ -2017,6 +2579,7 @@ impl RustType<ProtoPostgresSourcePublicationDetails> for PostgresSourcePublicati
         ProtoPostgresSourcePublicationDetails {
             tables: self.tables.iter().map(|t| t.into_proto()).collect(),
             slot: self.slot.clone(),
+            publication: self.publication.clone(),
         }
     }
 
@@ -2028,6 +2591,7 @@ impl RustType<ProtoPostgresSourcePublicationDetails> for PostgresSourcePublicati
                 .map(mz_postgres_util::desc::PostgresTableDesc::from_proto)
                 .collect::<Result<_, _>>()?,
             slot: proto.slot,
+            publication: proto.publication,
         })
     }
 }
@@ -2609,3 +3173,49 @@ fn test_timeline_parsing() {
     assert!("Dance".parse::<Timeline>().is_err());
     assert!("".parse::<Timeline>().is_err());
 }
+
+#[test]
+fn test_standby_connection() {
+    use crate::types::connections::{StringOrSecret, Tunnel};
+
+    let primary = PostgresConnection {
+        host: "primary".into(),
+        port: 5432,
+        database: "postgres".into(),
+        user: StringOrSecret::String("postgres".into()),
+        password: None,
+        tunnel: Tunnel::Direct,
+        tls_mode: tokio_postgres::config::SslMode::Disable,
+        tls_root_cert: None,
+        tls_identity: None,
+        tls_sni_hostname: Some("primary.example.com".into()),
+    };
+    let standby = |connection_string: &str| {
+        ReplicationSource::Standby {
+            connection_string: connection_string.into(),
+        }
+        .connection(&primary)
+    };
+
+    assert_eq!(
+        ReplicationSource::Primary.connection(&primary).unwrap(),
+        primary
+    );
+    let connection = standby("host=standby port=5433").unwrap();
+    assert_eq!(
+        (connection.host.as_str(), connection.port),
+        ("standby", 5433)
+    );
+    assert_eq!(connection.database, primary.database);
+    assert_eq!(connection.tls_sni_hostname, None);
+    let connection = standby("host=standby").unwrap();
+    assert_eq!(
+        (connection.host.as_str(), connection.port),
+        ("standby", 5432)
+    );
+
+    assert!(standby("port=5433").is_err());
+    assert!(standby("host=standby password=secret").is_err());
+    assert!(standby("host=standby1,standby2").is_err());
+    assert!(standby("host=standby port=5433,5434").is_err());
+}
@@ -449,6 +449,7 @@ where
                     None => None,
                 };
 
+                let raw_value = value;
                 let value = match value.as_ref() {
                     Some(buf) => decode_delimited(&mut value_decoder, buf).await.transpose(),
                     None => None,
@@ -472,6 +473,7 @@ where
                         *position,
                         *upstream_time_millis,
                         headers.as_deref(),
+                        raw_value.as_deref(),
                     ),
                 };
                 output_container.push((result, ts.clone(), *diff));
@@ -500,6 +502,7 @@ fn to_metadata_row(
     position: MzOffset,
     upstream_time_millis: Option<i64>,
     headers: Option<&[(String, Option<Vec<u8>>)]>,
+    raw_value: Option<&[u8]>,
 ) -> Row {
     let position = position.offset;
     let mut row = Row::default();
@@ -524,6 +527,10 @@ fn to_metadata_row(
                         packer.push(d)
                     }
                     IncludedColumnSource::Topic => unreachable!("Topic is not implemented yet"),
+                    IncludedColumnSource::RawValue => packer.push(match raw_value {
+                        Some(raw_value) => Datum::Bytes(raw_value),
+                        None => Datum::Null,
+                    }),
                     IncludedColumnSource::Headers => {
                         packer.push_list_with(|r| {
                             // If the source asked for headers, but we didn't get any, we still
@@ -18,6 +18,7 @@ use timely::progress::Antichain;
 
 use mz_persist_client::{PersistClient, ShardId};
 use mz_repr::{GlobalId, RelationDesc, Timestamp};
+use mz_storage_client::healthcheck::SourceStatusDetails;
 use mz_storage_client::types::sources::SourceData;
 
 pub async fn write_to_persist(
@@ -29,6 +30,7 @@ pub async fn write_to_persist(
     status_shard: ShardId,
     relation_desc: &RelationDesc,
     hint: Option<&str>,
+    details: Option<&SourceStatusDetails>,
 ) {
     let now_ms = now();
     let row = mz_storage_client::healthcheck::pack_status_row(
@@ -37,6 +39,7 @@ pub async fn write_to_persist(
         new_error,
         now_ms,
         hint,
+        details,
     );
 
     let mut handle = client
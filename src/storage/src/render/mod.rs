@@ -172,8 +172,8 @@
 //!
 //! At this point we have a timely stream with correctly timestamped data in the mz time domain
 //! (`mz_repr::Timestamp`) which contains multiplexed messages for each of the potential subsources
-//! of this source. Each message selects the output it belongs to by setting the output field in
-//! [`crate::source::types::SourceMessage`]. By convention, the main source output is always output
+//! of this source. Each message selects the output it belongs to by setting the `output_index` field
+//! of [`crate::source::types::SourceMessage`]. By convention, the main source output is always output
 //! zero and subsources get the outputs from one onwards.
 //!
 //! However, regardless of whether the output is the main source or a subsource it is treated
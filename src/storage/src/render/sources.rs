@@ -12,6 +12,7 @@
 //! See [`render_source`] for more details.
 
 use std::any::Any;
+use std::collections::BTreeMap;
 use std::rc::Rc;
 use std::sync::Arc;
 
@@ -45,6 +46,8 @@ pub enum SourceType<G: Scope> {
     /// A source that produces Row's natively, and skips any `render_decode` stream adapters, and
     /// can produce retractions
     Row(Collection<G, SourceOutput<(), Row>, Diff>),
+    /// Like [`SourceType::Row`], but each record also carries the values of its upstream key
+    KeyedRow(Collection<G, SourceOutput<Row, Row>, Diff>),
 }
 
 /// _Renders_ complete _differential_ [`Collection`]s
@@ -109,6 +112,10 @@ where
         shared_remap_upper: Rc::clone(
             &storage_state.source_uppers[&description.remap_collection_id],
         ),
+        pg_source_limits: Arc::clone(&storage_state.pg_source_limits),
+        pg_source_pauses: Arc::clone(&storage_state.pg_source_pauses),
+        pg_snapshot_attempts: Arc::clone(&storage_state.pg_snapshot_attempts),
+        source_exports: description.source_exports.clone(),
     };
 
     // TODO(petrosagg): put the description as-is in the RawSourceCreationConfig instead of cloning
@@ -143,7 +150,7 @@ where
                 resumption_calculator,
                 internal_cmd_tx,
             );
-            let oks = oks.into_iter().map(SourceType::Row).collect();
+            let oks = oks.into_iter().map(SourceType::KeyedRow).collect();
             ((oks, err), cap)
         }
         GenericSourceConnection::LoadGenerator(connection) => {
@@ -289,6 +296,18 @@ where
                     }),
                     None,
                 ),
+                SourceType::KeyedRow(source) => (
+                    source.map(|r| DecodeResult {
+                        key: Some(Ok(r.key)),
+                        value: Some(Ok(r.value)),
+                        position: r.position,
+                        upstream_time_millis: r.upstream_time_millis,
+                        partition: r.partition,
+                        // The headers are appended to the value as its last column.
+                        metadata: r.headers.as_deref().map(headers_row).unwrap_or_default(),
+                    }),
+                    None,
+                ),
             };
             if let Some(tok) = extra_token {
                 needed_tokens.push(Rc::new(tok));
@@ -427,6 +446,24 @@ struct KV {
     val: Option<Result<Row, DecodeError>>,
 }
 
+/// Packs `headers` into a row holding a map from their names to their values as text, which
+/// [`SourceType::KeyedRow`] sources append to the values of their records.
+pub(crate) fn headers_row(headers: &[(String, Option<Vec<u8>>)]) -> Row {
+    // The keys of a map are sorted.
+    let headers: BTreeMap<_, _> = headers
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.as_deref().map(String::from_utf8_lossy)))
+        .collect();
+    let mut row = Row::default();
+    row.packer().push_dict_with(|packer| {
+        for (name, value) in &headers {
+            packer.push(Datum::String(name));
+            packer.push(value.as_deref().map_or(Datum::Null, Datum::String));
+        }
+    });
+    row
+}
+
 fn append_metadata_to_value<G: Scope>(
     results: Collection<G, DecodeResult, Diff>,
 ) -> Collection<G, KV, Diff> {
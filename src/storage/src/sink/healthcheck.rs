@@ -84,6 +84,7 @@ impl Healthchecker {
                 self.status_shard,
                 &*MZ_SINK_STATUS_HISTORY_DESC,
                 status_update.hint(),
+                None,
             )
             .await;
 
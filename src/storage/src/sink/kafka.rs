@@ -37,6 +37,7 @@ use timely::dataflow::{Scope, Stream};
 use timely::progress::{Antichain, Timestamp as _};
 use timely::PartialOrder;
 use tokio::sync::{mpsc, Mutex};
+use tokio_postgres::types::PgLsn;
 use tracing::{debug, error, info, warn};
 
 use mz_interchange::avro::{AvroEncoder, AvroSchemaGenerator};
@@ -48,7 +49,7 @@ use mz_ore::collections::CollectionExt;
 use mz_ore::metrics::{CounterVecExt, DeleteOnDropCounter, DeleteOnDropGauge, GaugeVecExt};
 use mz_ore::retry::{Retry, RetryResult};
 use mz_ore::task;
-use mz_repr::{Diff, GlobalId, Row, Timestamp};
+use mz_repr::{Datum, Diff, GlobalId, Row, Timestamp};
 use mz_storage_client::client::SinkStatisticsUpdate;
 use mz_storage_client::types::connections::ConnectionContext;
 use mz_storage_client::types::errors::DataflowError;
@@ -498,6 +499,9 @@ impl KafkaSinkState {
                     // different settings for this value to see if it makes a
                     // big difference.
                     "queue.buffering.max.ms" => format!("{}", 10),
+                    // The transactional ID must stay the same across restarts, so that
+                    // initializing transactions fences out any previous instance of this
+                    // producer that is still running, and aborts its open transaction.
                     "transactional.id" => format!("mz-producer-{sink_id}-{worker_id}"),
                 },
             )
@@ -922,25 +926,7 @@ impl KafkaSinkState {
         match result {
             Ok(t) => t,
             Err(error) => {
-                let hint: Option<String> =
-                    error
-                        .downcast_ref::<RDKafkaError>()
-                        .and_then(|kafka_error| {
-                            if kafka_error.is_retriable()
-                                && kafka_error.code() == RDKafkaErrorCode::OperationTimedOut
-                            {
-                                Some(
-                                    "If you're running a single Kafka broker, ensure \
-                                    that the configs transaction.state.log.replication.factor, \
-                                    transaction.state.log.min.isr, and \
-                                    offsets.topic.replication.factor are set to 1 on the broker"
-                                        .to_string(),
-                                )
-                            } else {
-                                None
-                            }
-                        });
-
+                let hint = error_hint(&error);
                 self.update_status(SinkStatus::Stalled {
                     error: format!("{:#}", error),
                     hint,
@@ -961,13 +947,45 @@ impl KafkaSinkState {
     }
 }
 
+/// Returns a hint for resolving a transaction `error` of the sink's producer, if we know one.
+fn error_hint(error: &anyhow::Error) -> Option<String> {
+    let kafka_error = match error.downcast_ref::<KafkaError>() {
+        Some(KafkaError::Transaction(e)) => e,
+        _ => error.downcast_ref::<RDKafkaError>()?,
+    };
+    match kafka_error.code() {
+        RDKafkaErrorCode::OperationTimedOut if kafka_error.is_retriable() => Some(
+            "If you're running a single Kafka broker, ensure \
+            that the configs transaction.state.log.replication.factor, \
+            transaction.state.log.min.isr, and \
+            offsets.topic.replication.factor are set to 1 on the broker"
+                .to_string(),
+        ),
+        // A newer instance of the producer initialized transactions with the same transactional
+        // ID, e.g. because two instances of the sink ran while it was restarted.
+        RDKafkaErrorCode::Fenced
+        | RDKafkaErrorCode::ProducerFenced
+        | RDKafkaErrorCode::InvalidProducerEpoch => Some(
+            "Another instance of this sink took over its transactions. The sink restarts and \
+            resumes from the latest record in its progress topic, without writing any data twice."
+                .to_string(),
+        ),
+        _ => None,
+    }
+}
+
 #[derive(Debug)]
 struct EncodedRow {
     key: Option<Vec<u8>>,
     value: Option<Vec<u8>>,
+    /// The upstream LSN of the row, for the `mz-lsn` header.
+    lsn: Option<u64>,
     count: usize,
 }
 
+/// An encoded key and value, along with the upstream LSN of the row for the `mz-lsn` header.
+type EncodedUpdate = (Option<Vec<u8>>, Option<Vec<u8>>, Option<u64>);
+
 // TODO@jldlaughlin: What guarantees does this sink support? #1728
 fn kafka<G>(
     collection: Collection<G, (Option<Row>, Option<Row>), Diff>,
@@ -996,6 +1014,7 @@ where
         .as_ref()
         .map(|(desc, _indices)| desc.clone());
     let value_desc = connection.value_desc.clone();
+    let lsn_header_index = connection.lsn_header_index;
 
     let encoded_stream = match connection.published_schema_info {
         Some(PublishedSchemaInfo {
@@ -1016,6 +1035,8 @@ where
                 as_of.clone(),
                 Rc::clone(&shared_gate_ts),
                 encoder,
+                lsn_header_index,
+                matches!(envelope, Some(SinkEnvelope::Debezium)),
                 &name,
             )
         }
@@ -1030,6 +1051,8 @@ where
                 as_of.clone(),
                 Rc::clone(&shared_gate_ts),
                 encoder,
+                lsn_header_index,
+                matches!(envelope, Some(SinkEnvelope::Debezium)),
                 &name,
             )
         }
@@ -1051,7 +1074,8 @@ where
     )
 }
 
-/// Produces/sends a stream of encoded rows (as `Vec<u8>`) to Kafka.
+/// Produces/sends a stream of encoded rows (as `Vec<u8>`), along with their upstream LSN if
+/// requested, to Kafka.
 ///
 /// This operator exchanges all updates to a single worker by hashing on the given sink `id`.
 ///
@@ -1063,7 +1087,7 @@ where
 /// Updates that are not beyond the given [`SinkAsOf`] and/or the `gate_ts` in
 /// [`KafkaSinkConnection`] will be discarded without producing them.
 pub fn produce_to_kafka<G>(
-    stream: Stream<G, ((Option<Vec<u8>>, Option<Vec<u8>>), Timestamp, Diff)>,
+    stream: Stream<G, (EncodedUpdate, Timestamp, Diff)>,
     id: GlobalId,
     name: String,
     connection: KafkaSinkConnection,
@@ -1162,7 +1186,7 @@ where
                 Event::Data(_, rows) => {
                     // Queue all pending rows waiting to be sent to kafka
                     assert!(is_active_worker);
-                    for ((key, value), time, diff) in rows.drain(..) {
+                    for ((key, value, lsn), time, diff) in rows.drain(..) {
                         let should_emit = if as_of.strict {
                             as_of.frontier.less_than(&time)
                         } else {
@@ -1184,7 +1208,12 @@ where
                             usize::try_from(diff).expect("can't sink negative multiplicities");
 
                         let rows = s.pending_rows.entry(time).or_default();
-                        rows.push(EncodedRow { key, value, count });
+                        rows.push(EncodedRow {
+                            key,
+                            value,
+                            lsn,
+                            count,
+                        });
                         s.metrics.rows_queued.inc();
                     }
                 }
@@ -1233,10 +1262,18 @@ where
                             };
 
                             let ts_bytes = ts.to_string().into_bytes();
-                            let record = record.headers(OwnedHeaders::new().insert(Header {
+                            let mut headers = OwnedHeaders::new().insert(Header {
                                 key: "materialize-timestamp",
                                 value: Some(&ts_bytes),
-                            }));
+                            });
+                            let lsn_bytes = encoded_row.lsn.map(u64::to_be_bytes);
+                            if let Some(lsn_bytes) = &lsn_bytes {
+                                headers = headers.insert(Header {
+                                    key: "mz-lsn",
+                                    value: Some(lsn_bytes.as_slice()),
+                                });
+                            }
+                            let record = record.headers(headers);
 
                             let size_for_stats =
                                 u64::cast_from(record.payload.as_ref().map_or(0, |p| p.len()))
@@ -1352,8 +1389,10 @@ fn encode_stream<G>(
     as_of: SinkAsOf,
     shared_gate_ts: Rc<Cell<Option<Timestamp>>>,
     encoder: impl Encode + 'static,
+    lsn_header_index: Option<usize>,
+    debezium: bool,
     name_prefix: &str,
-) -> Stream<G, ((Option<Vec<u8>>, Option<Vec<u8>>), Timestamp, Diff)>
+) -> Stream<G, (EncodedUpdate, Timestamp, Diff)>
 where
     G: Scope<Timestamp = Timestamp>,
 {
@@ -1373,15 +1412,45 @@ where
                     // Skip stale data for already published timestamps
                     None
                 } else {
+                    let lsn = lsn_header_index.and_then(|index| {
+                        value
+                            .as_ref()
+                            .and_then(|value| provenance_lsn(value, index, debezium))
+                    });
                     let key = key.map(|key| encoder.encode_key_unchecked(key));
                     let value = value.map(|value| encoder.encode_value_unchecked(value));
-                    Some(((key, value), time, diff))
+                    Some(((key, value, lsn), time, diff))
                 }
             })
             .leave()
     })
 }
 
+/// Returns the LSN that the provenance headers in the column at `index` of a sunk `value` record.
+/// Under `ENVELOPE DEBEZIUM`, these are the headers of the new state of the row, or of its old
+/// state if it was deleted.
+fn provenance_lsn(value: &Row, index: usize, debezium: bool) -> Option<u64> {
+    let provenance = if debezium {
+        let mut states = value.iter();
+        let (before, after) = (states.next()?, states.next()?);
+        match if after.is_null() { before } else { after } {
+            Datum::List(state) => state.iter().nth(index)?,
+            _ => return None,
+        }
+    } else {
+        value.iter().nth(index)?
+    };
+    match provenance {
+        Datum::Map(headers) => headers
+            .iter()
+            .find_map(|(name, value)| match (name, value) {
+                ("lsn", Datum::String(lsn)) => lsn.parse::<PgLsn>().ok().map(u64::from),
+                _ => None,
+            }),
+        _ => None,
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 /// This struct is emitted as part of a transactional produce, and captures the information we
 /// need to resume the Kafka sink at the correct place in the sunk collection. (Currently, all
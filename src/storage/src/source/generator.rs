@@ -124,7 +124,7 @@ impl SourceRender for LoadGeneratorSourceConnection {
 
             while let Some((output, typ, value, diff)) = rows.next() {
                 let message = Ok(SourceMessage {
-                    output,
+                    output_index: output,
                     upstream_time_millis: None,
                     key: (),
                     value,
@@ -201,6 +201,7 @@ impl SourceRender for KafkaSourceConnection {
                             hint: None,
                         },
                         should_halt: true,
+                        details: None,
                     };
                     health_output.give(&health_cap, update).await;
                     // IMPORTANT: wedge forever until the `SuspendAndRestart` is processed.
@@ -257,7 +258,7 @@ impl SourceRender for KafkaSourceConnection {
                     }
                 }
             }
-            let future_ts = Partitioned::with_range(max_pid, None, MzOffset::from(0));
+            let future_ts = Partitioned::with_range(max_pid, None, MzOffset::ZERO);
             data_cap.downgrade(&future_ts);
 
             info!(
@@ -412,14 +413,14 @@ impl SourceRender for KafkaSourceConnection {
                         );
                         if is_responsible {
                             reader.ensure_partition(pid);
-                            let part_min_ts = Partitioned::with_partition(pid, MzOffset::from(0));
+                            let part_min_ts = Partitioned::with_partition(pid, MzOffset::ZERO);
                             reader
                                 .partition_capabilities
                                 .entry(pid)
                                 .or_insert_with(|| data_cap.delayed(&part_min_ts));
                         }
                     }
-                    let future_ts = Partitioned::with_range(max_pid, None, MzOffset::from(0));
+                    let future_ts = Partitioned::with_range(max_pid, None, MzOffset::ZERO);
                     data_cap.downgrade(&future_ts);
                 }
 
@@ -818,7 +819,7 @@ fn construct_source_message(
         panic!("got negative offset ({}) from otherwise non-error'd kafka message", msg.offset());
     };
     let msg = SourceMessage {
-        output: 0,
+        output_index: 0,
         upstream_time_millis: msg.timestamp().to_millis(),
         key: msg.key().map(|k| k.to_vec()),
         value: msg.payload().map(|p| p.to_vec()),
@@ -15,7 +15,10 @@
 //! appropriate source.
 
 use mz_ore::metric;
-use mz_ore::metrics::{IntCounter, IntCounterVec, IntGaugeVec, MetricsRegistry, UIntGaugeVec};
+use mz_ore::metrics::{
+    CounterVec, HistogramVec, IntCounter, IntCounterVec, IntGaugeVec, MetricsRegistry, UIntGaugeVec,
+};
+use mz_ore::stats::{histogram_seconds_buckets, HISTOGRAM_BYTE_BUCKETS};
 use prometheus::core::{AtomicI64, GenericCounterVec};
 
 #[derive(Clone, Debug)]
@@ -147,6 +150,37 @@ pub(super) struct PostgresSourceSpecificMetrics {
     pub(super) delete_messages: IntCounterVec,
     pub(super) tables_in_publication: UIntGaugeVec,
     pub(super) wal_lsn: UIntGaugeVec,
+    pub(super) upstream_wal_lsn: UIntGaugeVec,
+    pub(super) fast_forward_mode: UIntGaugeVec,
+    pub(super) row_size_limit_exceeded: IntCounterVec,
+    pub(super) transaction_size_limit_exceeded: IntCounterVec,
+    pub(super) transactions_split: IntCounterVec,
+    pub(super) transaction_changes: HistogramVec,
+    pub(super) transaction_bytes: HistogramVec,
+    pub(super) transaction_buffer_memory_bytes: UIntGaugeVec,
+    pub(super) commit_to_emit_latency: HistogramVec,
+    pub(super) channel_queued_messages: UIntGaugeVec,
+    pub(super) channel_messages: IntCounterVec,
+    pub(super) channel_send_blocked_seconds: CounterVec,
+    pub(super) backpressure_active: UIntGaugeVec,
+    pub(super) backpressure_seconds: CounterVec,
+    pub(super) replication_connections: IntCounterVec,
+    pub(super) replication_connect_duration: HistogramVec,
+    pub(super) replication_session_duration: HistogramVec,
+    pub(super) connection_limit_errors: IntCounterVec,
+    pub(super) insufficient_privilege_errors: IntCounterVec,
+    pub(super) open_connections: UIntGaugeVec,
+    pub(super) wal_fast_forwards: IntCounterVec,
+    pub(super) wal_bytes_skipped: IntCounterVec,
+    pub(super) wal_peeks: IntCounterVec,
+    pub(super) wal_peek_duration: HistogramVec,
+    pub(super) replication_bytes_received: IntCounterVec,
+    pub(super) snapshot_bytes_received: IntCounterVec,
+    pub(super) snapshot_copy_retries: IntCounterVec,
+    pub(super) snapshots_killed: IntCounterVec,
+    pub(super) cast_errors: IntCounterVec,
+    pub(super) last_keepalive_time: UIntGaugeVec,
+    pub(super) last_data_time: UIntGaugeVec,
 }
 
 impl PostgresSourceSpecificMetrics {
@@ -191,7 +225,171 @@ impl PostgresSourceSpecificMetrics {
                 name: "mz_postgres_per_source_wal_lsn",
                 help: "LSN of the latest transaction committed for this source, see Postgres Replication docs for more details on LSN",
                 var_labels: ["source_id"],
-            ))
+            )),
+            upstream_wal_lsn: registry.register(metric!(
+                name: "mz_postgres_per_source_upstream_wal_lsn",
+                help: "The latest end of the upstream WAL this source observed in a keepalive message",
+                var_labels: ["source_id"],
+            )),
+            fast_forward_mode: registry.register(metric!(
+                name: "mz_postgres_per_source_fast_forward_mode",
+                help: "How this source fast-forwards over WAL lag: 0 by peeking into its slot, 1 by a limited peek, 2 not at all",
+                var_labels: ["source_id"],
+            )),
+            row_size_limit_exceeded: registry.register(metric!(
+                name: "mz_postgres_per_source_row_size_limit_exceeded",
+                help: "The number of times an upstream row exceeded the maximum row size for this source",
+                var_labels: ["source_id"],
+            )),
+            transaction_size_limit_exceeded: registry.register(metric!(
+                name: "mz_postgres_per_source_transaction_size_limit_exceeded",
+                help: "The number of times an upstream transaction exceeded the maximum number of buffered changes for this source",
+                var_labels: ["source_id"],
+            )),
+            transactions_split: registry.register(metric!(
+                name: "mz_postgres_per_source_transactions_split_total",
+                help: "The number of times part of a large upstream transaction was emitted before it committed for this source",
+                var_labels: ["source_id"],
+            )),
+            transaction_changes: registry.register(metric!(
+                name: "mz_postgres_per_source_transaction_changes",
+                help: "The number of changes buffered per upstream transaction for this source",
+                var_labels: ["source_id"],
+                buckets: vec![
+                    1.0, 4.0, 16.0, 64.0, 256.0, 1024.0, 4096.0, 16384.0, 65536.0, 262144.0,
+                    1048576.0, 4194304.0, 16777216.0,
+                ],
+            )),
+            transaction_bytes: registry.register(metric!(
+                name: "mz_postgres_per_source_transaction_bytes",
+                help: "The estimated size in bytes of the rows buffered per upstream transaction for this source",
+                var_labels: ["source_id"],
+                buckets: HISTOGRAM_BYTE_BUCKETS.to_vec(),
+            )),
+            transaction_buffer_memory_bytes: registry.register(metric!(
+                name: "mz_postgres_per_source_transaction_buffer_memory_bytes",
+                help: "The estimated size in bytes of the rows currently buffered for uncommitted upstream transactions by this source",
+                var_labels: ["source_id"],
+            )),
+            commit_to_emit_latency: registry.register(metric!(
+                name: "mz_postgres_per_source_commit_to_emit_latency_seconds",
+                help: "The time between an upstream transaction committing and this source emitting it",
+                var_labels: ["source_id"],
+                buckets: histogram_seconds_buckets(0.001, 32.0),
+            )),
+            channel_queued_messages: registry.register(metric!(
+                name: "mz_postgres_per_source_channel_queued_messages",
+                help: "The number of messages queued between the replication task and the source operator",
+                var_labels: ["source_id"],
+            )),
+            channel_messages: registry.register(metric!(
+                name: "mz_postgres_per_source_channel_messages_total",
+                help: "The total number of messages sent from the replication task to the source operator",
+                var_labels: ["source_id"],
+            )),
+            channel_send_blocked_seconds: registry.register(metric!(
+                name: "mz_postgres_per_source_channel_send_blocked_seconds_total",
+                help: "The total time the replication task spent waiting for room in the channel to the source operator",
+                var_labels: ["source_id"],
+            )),
+            backpressure_active: registry.register(metric!(
+                name: "mz_postgres_per_source_backpressure_active",
+                help: "Whether this source stopped reading from its replication stream until the changes it emitted are committed downstream",
+                var_labels: ["source_id"],
+            )),
+            backpressure_seconds: registry.register(metric!(
+                name: "mz_postgres_per_source_backpressure_seconds_total",
+                help: "The total time this source stopped reading from its replication stream until the changes it emitted were committed downstream",
+                var_labels: ["source_id"],
+            )),
+            replication_connections: registry.register(metric!(
+                name: "mz_postgres_per_source_replication_connections_total",
+                help: "The number of times the replication stream was (re)started for this source",
+                var_labels: ["source_id"],
+            )),
+            replication_connect_duration: registry.register(metric!(
+                name: "mz_postgres_per_source_replication_connect_duration_seconds",
+                help: "The time taken by this source to connect to the upstream, either to start a replication stream or to peek into its replication slot",
+                var_labels: ["source_id"],
+                buckets: histogram_seconds_buckets(0.001, 32.0),
+            )),
+            replication_session_duration: registry.register(metric!(
+                name: "mz_postgres_per_source_replication_session_duration_seconds",
+                help: "The time this source streamed from its replication slot before leaving the stream, e.g. to peek into the slot",
+                var_labels: ["source_id"],
+                buckets: histogram_seconds_buckets(0.001, 32.0),
+            )),
+            connection_limit_errors: registry.register(metric!(
+                name: "mz_postgres_per_source_connection_limit_errors_total",
+                help: "The number of times this source failed to connect because the upstream role reached its connection limit",
+                var_labels: ["source_id"],
+            )),
+            insufficient_privilege_errors: registry.register(metric!(
+                name: "mz_postgres_per_source_insufficient_privilege_errors_total",
+                help: "The number of times this source failed because the upstream role lacked a privilege it requires",
+                var_labels: ["source_id"],
+            )),
+            open_connections: registry.register(metric!(
+                name: "mz_postgres_per_source_open_connections",
+                help: "The number of connections this source holds open to the server it replicates from",
+                var_labels: ["source_id"],
+            )),
+            wal_fast_forwards: registry.register(metric!(
+                name: "mz_postgres_per_source_wal_fast_forwards_total",
+                help: "The number of times this source skipped over WAL that contained no relevant changes",
+                var_labels: ["source_id"],
+            )),
+            wal_bytes_skipped: registry.register(metric!(
+                name: "mz_postgres_per_source_wal_bytes_skipped_total",
+                help: "The number of WAL bytes this source skipped over by fast-forwarding",
+                var_labels: ["source_id"],
+            )),
+            wal_peeks: registry.register(metric!(
+                name: "mz_postgres_per_source_wal_peeks_total",
+                help: "The number of queries this source issued to peek into its replication slot",
+                var_labels: ["source_id"],
+            )),
+            wal_peek_duration: registry.register(metric!(
+                name: "mz_postgres_per_source_wal_peek_duration_seconds",
+                help: "The time taken by the queries this source issued to peek into its replication slot",
+                var_labels: ["source_id"],
+                buckets: histogram_seconds_buckets(0.001, 32.0),
+            )),
+            replication_bytes_received: registry.register(metric!(
+                name: "mz_postgres_per_source_replication_bytes_received_total",
+                help: "The estimated number of bytes received from the upstream replication stream of this source",
+                var_labels: ["source_id"],
+            )),
+            snapshot_bytes_received: registry.register(metric!(
+                name: "mz_postgres_per_source_snapshot_bytes_received_total",
+                help: "The number of bytes of COPY output received while snapshotting this source",
+                var_labels: ["source_id"],
+            )),
+            snapshot_copy_retries: registry.register(metric!(
+                name: "mz_postgres_per_source_snapshot_copy_retries_total",
+                help: "The number of times copying a table was retried within a snapshot of this source after a retryable error",
+                var_labels: ["source_id"],
+            )),
+            snapshots_killed: registry.register(metric!(
+                name: "mz_postgres_per_source_snapshots_killed_total",
+                help: "The number of times the upstream killed the snapshot transaction of this source",
+                var_labels: ["source_id"],
+            )),
+            cast_errors: registry.register(metric!(
+                name: "mz_postgres_per_source_cast_errors_total",
+                help: "The number of upstream rows of this source that failed to cast into the types they are ingested as, and were ingested as errors instead",
+                var_labels: ["source_id"],
+            )),
+            last_keepalive_time: registry.register(metric!(
+                name: "mz_postgres_per_source_last_keepalive_time_ms",
+                help: "The unix timestamp in milliseconds at which this source last received a keepalive message from the upstream",
+                var_labels: ["source_id"],
+            )),
+            last_data_time: registry.register(metric!(
+                name: "mz_postgres_per_source_last_data_time_ms",
+                help: "The unix timestamp in milliseconds at which this source last received a data message from the upstream",
+                var_labels: ["source_id"],
+            )),
         }
     }
 }
@@ -46,7 +46,10 @@ pub mod testscript;
 pub mod types;
 
 pub use kafka::KafkaSourceReader;
-pub use postgres::PostgresSourceReader;
+pub use postgres::{
+    dry_run_postgres_publication, dry_run_postgres_source, replay_replication, DryRunReport,
+    DryRunTable, PgSnapshotAttempts, PgSourceLimits, PgSourcePauses, PostgresSourceReader,
+};
 pub use source_reader_pipeline::create_raw_source;
 pub use source_reader_pipeline::RawSourceCreationConfig;
 
@@ -8,52 +8,119 @@
 // by the Apache License, Version 2.0.
 
 use std::any::Any;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::convert::Infallible;
 use std::error::Error;
+use std::fmt;
 use std::future;
+use std::ops::Deref;
+use std::pin::Pin;
 use std::rc::Rc;
-use std::str::FromStr;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::task::Poll;
+use std::thread;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use anyhow::{anyhow, bail};
+use anyhow::{anyhow, bail, Context};
 use differential_dataflow::{AsCollection, Collection};
+use futures::future::Either;
 use futures::StreamExt;
 use once_cell::sync::Lazy;
 use postgres_protocol::message::backend::{
-    LogicalReplicationMessage, ReplicationMessage, TupleData,
+    LogicalReplicationMessage, ReplicationMessage, Tuple, TupleData,
 };
 use timely::dataflow::operators::to_stream::Event;
 use timely::dataflow::operators::Capability;
 use timely::dataflow::{Scope, Stream};
 use timely::progress::Antichain;
 use tokio::sync::mpsc::{Receiver, Sender};
+use tokio::task::LocalSet;
+use tokio::time::MissedTickBehavior;
 use tokio_postgres::error::DbError;
-use tokio_postgres::replication::LogicalReplicationStream;
+use tokio_postgres::replication::ReplicationStream;
 use tokio_postgres::types::PgLsn;
 use tokio_postgres::Client;
-use tokio_postgres::SimpleQueryMessage;
-use tracing::{info, warn};
+use tracing::{error, info, info_span, warn, Instrument, Span};
 
-use mz_expr::MirScalarExpr;
+use mz_expr::{EvalError, MirScalarExpr};
+use mz_ore::cast::{CastFrom, CastLossy};
 use mz_ore::display::DisplayExt;
-use mz_ore::task;
-use mz_postgres_util::desc::PostgresTableDesc;
+use mz_ore::str::StrExt;
+use mz_ore::task::{self, RuntimeExt};
+use mz_persist_client::cache::PersistClientCache;
+use mz_postgres_util::desc::{Compatibility, PostgresColumnDesc, PostgresTableDesc};
+use mz_postgres_util::validation::ValidationIssue;
+use mz_postgres_util::{PublicationFilter, TablePersistence};
 use mz_repr::{Datum, DatumVec, Diff, GlobalId, Row};
-use mz_storage_client::types::connections::ConnectionContext;
+use mz_secrets::SecretsReader;
+use mz_storage_client::controller::CollectionMetadata;
+use mz_storage_client::healthcheck::{
+    PostgresStatusDetails, ReplicationProgress, ReplicationSlotStatus, SourceStatusDetails,
+};
+use mz_storage_client::types::connections::{ConnectionContext, PostgresConnection};
 use mz_storage_client::types::errors::SourceErrorDetails;
-use mz_storage_client::types::sources::{MzOffset, PostgresSourceConnection, SourceTimestamp};
+use mz_storage_client::types::parameters::StorageParameters;
+use mz_storage_client::types::sources::{
+    standby_connection, MzOffset, PostgresSourceConnection, ReplicationPlugin, SnapshotOrder,
+    SourceTimestamp,
+};
 use mz_timely_util::antichain::AntichainExt;
 use mz_timely_util::builder_async::OperatorBuilder as AsyncOperatorBuilder;
 
+use self::connections::{MetadataClient, UpstreamConnections};
+use self::copy::CopyOutDecoder;
+use self::decoderbufs::DecoderBufsStream;
+use self::log_dedup::LogDedup;
+use self::loop_watchdog::{LoopWatchdog, Phase, THRASHING_ITERATIONS};
+use self::lsn::{CommitLsn, LsnFrontier};
 use self::metrics::PgSourceMetrics;
+use self::monitor::{Observation, PostgresReplicationMonitor};
+use self::pause::PauseSignal;
+use self::provenance::{strip_provenance, ProvenanceTracker};
+use self::query::{
+    at_most_one_row, exactly_one_row, parse_column, parse_nullable_column, rows, QueryResultError,
+    ResultRow,
+};
+use self::replication::consume_replication_stream;
+use self::schema_audit::SchemaAudit;
+use self::schema_change::TableSchemaChanged;
+use self::snapshot::{produce_snapshot, snapshot_tables};
+use self::table_stats::TableStats;
+use self::truncate::TruncateRetractions;
+use self::wal_capture::WalCapture;
 
 use crate::source::types::{HealthStatus, HealthStatusUpdate, SourceReaderMetrics, SourceRender};
 use crate::source::{RawSourceCreationConfig, SourceMessage, SourceReaderError};
 
+mod connections;
+mod copy;
+mod decoderbufs;
+mod dry_run;
+mod log_dedup;
+mod loop_watchdog;
+mod lsn;
 mod metrics;
+mod monitor;
+mod pause;
+mod provenance;
+mod query;
+mod replay;
+mod replication;
+mod schema_audit;
+mod schema_change;
+mod snapshot;
+mod snapshot_attempts;
+mod table_stats;
+mod truncate;
+mod wal_capture;
+
+pub use self::dry_run::{
+    dry_run_postgres_publication, dry_run_postgres_source, DryRunReport, DryRunTable,
+};
+pub use self::pause::PgSourcePauses;
+pub use self::replay::replay_replication;
+pub use self::snapshot_attempts::PgSnapshotAttempts;
 
 /// Postgres epoch is 2000-01-01T00:00:00Z
 static PG_EPOCH: Lazy<SystemTime> = Lazy::new(|| UNIX_EPOCH + Duration::from_secs(946_684_800));
@@ -64,6 +131,43 @@ static FEEDBACK_INTERVAL: Duration = Duration::from_secs(30);
 /// The amount of time we should wait after the last received message before worrying about WAL lag
 static WAL_LAG_GRACE_PERIOD: Duration = Duration::from_secs(30);
 
+/// How long to wait before reading the secrets of a connection again after it first failed
+static SECRETS_INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// The longest time to wait before reading the secrets of a connection again
+static SECRETS_MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// How long to wait before retrying after the upstream role reached its connection limit
+static CONNECTION_LIMIT_BACKOFF: Duration = Duration::from_secs(30);
+
+/// How long to wait before retrying after the upstream role lacked a privilege, which usually
+/// takes a human to grant
+static INSUFFICIENT_PRIVILEGE_BACKOFF: Duration = Duration::from_secs(60);
+
+/// How often a source that stopped reading from its replication stream because of backpressure
+/// checks whether downstream caught up
+static BACKPRESSURE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How often the replication progress of a source is reported through its health status, if it
+/// changed. Every report adds a row to the status history of the source.
+static PROGRESS_REPORT_INTERVAL: Duration = Duration::from_secs(600);
+
+/// How long a snapshot waits for its snapshot standby to replay up to the slot of its source
+static STANDBY_REPLAY_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// How many times the copy of a table is retried within a snapshot after a retryable error
+const SNAPSHOT_COPY_RETRIES: usize = 3;
+
+/// How many times a snapshot reconnects after losing its connection once its slot was created
+const SNAPSHOT_RECONNECT_RETRIES: usize = 3;
+
+/// How long to wait before reconnecting a snapshot that lost its connection
+static SNAPSHOT_RECONNECT_BACKOFF: Duration = Duration::from_secs(3);
+
+/// How many snapshot attempts in a row the upstream may kill without them getting any further
+/// before the source errors instead of restarting its snapshot again
+const SNAPSHOT_KILLED_STALLED_ATTEMPTS: usize = 3;
+
 trait ErrorExt {
     fn is_definite(&self) -> bool;
 }
@@ -76,29 +180,87 @@ impl ErrorExt for tokio::time::error::Elapsed {
 
 impl ErrorExt for DbError {
     fn is_definite(&self) -> bool {
-        let class = match self.code().code().get(0..2) {
-            None => return false,
-            Some(class) => class,
-        };
-        // See https://www.postgresql.org/docs/current/errcodes-appendix.html for the class
-        // definitions.
-        match class {
-            // unknown catalog or schema names
-            "3D" | "3F" => true,
-            // syntax error or access rule violation
-            "42" => true,
-            _ => false,
+        is_definite_sqlstate(self.code().code())
+    }
+}
+
+/// Whether an error with the SQLSTATE `code` permanently wedges the source.
+fn is_definite_sqlstate(code: &str) -> bool {
+    let class = match code.get(0..2) {
+        None => return false,
+        Some(class) => class,
+    };
+    // See https://www.postgresql.org/docs/current/errcodes-appendix.html for the class
+    // definitions.
+    match class {
+        // unknown catalog or schema names
+        "3D" | "3F" => true,
+        // insufficient_privilege, which the source recovers from once the privilege is granted
+        "42" if code == "42501" => false,
+        // syntax error or access rule violation
+        "42" => true,
+        _ => false,
+    }
+}
+
+/// Whether an error with the SQLSTATE `code` means that the upstream does not let us run the
+/// statement at all (syntax error or access rule violation), e.g. 42883 (undefined_function) on
+/// providers that block an administrative function.
+fn is_unsupported_statement_sqlstate(code: &str) -> bool {
+    code.starts_with("42")
+}
+
+/// Whether the upstream did not let us run the statement that failed with `err`, see
+/// [`is_unsupported_statement_sqlstate`].
+fn is_unsupported_statement_error(err: &(dyn Error + 'static)) -> bool {
+    find_source::<DbError>(err).map_or(false, |db_err| {
+        is_unsupported_statement_sqlstate(db_err.code().code())
+    })
+}
+
+/// The operations of a source that run statements against the upstream, which their errors are
+/// tagged with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operation {
+    /// Peeking into the replication slot to fast-forward over WAL without relevant changes
+    Peek,
+    /// Creating a replication slot, or looking up an existing one
+    SlotCreation,
+    /// Copying the rows of a table into the initial snapshot
+    SnapshotCopy,
+}
+
+impl Operation {
+    /// Whether an error with the SQLSTATE `code` of a statement of this operation permanently
+    /// wedges the source.
+    ///
+    /// Statements the upstream does not let us run are definite when they concern the upstream
+    /// objects the source ingests. The statements of the other operations are synthesized by us,
+    /// and failing to run them doesn't mean that the source can't make progress.
+    fn is_definite_sqlstate(self, code: &str) -> bool {
+        match self {
+            Operation::SnapshotCopy => is_definite_sqlstate(code),
+            Operation::Peek | Operation::SlotCreation => {
+                is_definite_sqlstate(code) && !is_unsupported_statement_sqlstate(code)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operation::Peek => f.write_str("peeking into the replication slot"),
+            Operation::SlotCreation => f.write_str("creating the replication slot"),
+            Operation::SnapshotCopy => f.write_str("copying the snapshot"),
         }
     }
 }
 
 impl ErrorExt for tokio_postgres::Error {
     fn is_definite(&self) -> bool {
-        match self.source() {
-            Some(err) => match err.downcast_ref::<DbError>() {
-                Some(db_err) => db_err.is_definite(),
-                None => false,
-            },
+        match find_source::<DbError>(self) {
+            Some(db_err) => db_err.is_definite(),
             // We have no information about what happened, it might be a fatal error or
             // it might not. Unexpected errors can happen if the upstream crashes for
             // example in which case we should retry.
@@ -112,25 +274,414 @@ impl ErrorExt for tokio_postgres::Error {
 
 impl ErrorExt for std::io::Error {
     fn is_definite(&self) -> bool {
-        match self.source() {
-            Some(err) => match err.downcast_ref::<tokio_postgres::Error>() {
-                Some(tokio_err) => tokio_err.is_definite(),
-                None => match err.downcast_ref::<DbError>() {
-                    Some(db_err) => db_err.is_definite(),
-                    None => false,
-                },
-            },
-            // We have no information about what happened, it might be a fatal error or
-            // it might not. Unexpected errors can happen if the upstream crashes for
-            // example in which case we should retry.
-            //
-            // Therefore, we adopt a "indefinite unless proven otherwise" policy and
-            // keep retrying in the event of unexpected errors.
+        match find_source::<DbError>(self) {
+            Some(db_err) => db_err.is_definite(),
+            // Same "indefinite unless proven otherwise" policy as for `tokio_postgres::Error`.
             None => false,
         }
     }
 }
 
+/// Failures that managed Postgres offerings like RDS and Aurora commonly run into. They are
+/// recognized by their exact server messages, as they warrant more specific handling than their
+/// SQLSTATE class alone suggests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ProviderError {
+    /// Logical decoding is disabled, which on RDS and Aurora is controlled by the
+    /// `rds.logical_replication` parameter rather than by `wal_level` directly.
+    LogicalReplicationDisabled,
+    /// The role reached its connection limit, which the small instance classes of RDS reach
+    /// quickly.
+    TooManyConnections,
+    /// The replication slot is gone. Aurora does not carry logical replication slots over to the
+    /// new writer when it fails over, so this is how a failover surfaces.
+    SlotMissing,
+    /// The upstream invalidated the replication slot, e.g. because it retained more WAL than
+    /// `max_slot_wal_keep_size` allows.
+    SlotInvalidated,
+}
+
+impl ProviderError {
+    fn from_details(details: &UpstreamErrorDetails) -> Option<Self> {
+        let message = details.message.as_str();
+        match details.code.as_str() {
+            // object_not_in_prerequisite_state
+            "55000"
+                if message.starts_with("logical decoding requires wal_level >= logical")
+                    || message
+                        .starts_with(r#"logical decoding requires "wal_level" >= "logical""#) =>
+            {
+                Some(ProviderError::LogicalReplicationDisabled)
+            }
+            "55000"
+                if message.starts_with("can no longer get changes from replication slot")
+                    || message.starts_with("cannot read from logical replication slot") =>
+            {
+                Some(ProviderError::SlotInvalidated)
+            }
+            // too_many_connections
+            "53300" if message.starts_with("too many connections for role") => {
+                Some(ProviderError::TooManyConnections)
+            }
+            // undefined_object
+            "42704"
+                if message.starts_with("replication slot")
+                    && message.ends_with("does not exist") =>
+            {
+                Some(ProviderError::SlotMissing)
+            }
+            _ => None,
+        }
+    }
+
+    /// Recognizes the upstream error that caused `err`, if any.
+    fn from_error(err: &anyhow::Error) -> Option<Self> {
+        UpstreamErrorDetails::from_error(err).and_then(|details| Self::from_details(&details))
+    }
+
+    /// The definite error to report for this failure, or none if retrying may fix it.
+    fn source_error(&self, slot: &str, error: String) -> Option<SourceErrorDetails> {
+        match self {
+            ProviderError::LogicalReplicationDisabled => {
+                Some(SourceErrorDetails::UpstreamMisconfigured {
+                    error,
+                    hint: "On Amazon RDS and Aurora, set the rds.logical_replication parameter \
+                           to 1 in the DB parameter group and reboot the instance. Elsewhere, set \
+                           wal_level to logical and restart the server."
+                        .into(),
+                })
+            }
+            ProviderError::TooManyConnections => None,
+            ProviderError::SlotMissing => Some(SourceErrorDetails::SlotInvalidated {
+                slot_name: slot.into(),
+                reason: "it no longer exists upstream. Aurora drops logical replication slots \
+                         when it fails over to a new writer, along with the changes they retained"
+                    .into(),
+            }),
+            ProviderError::SlotInvalidated => Some(SourceErrorDetails::SlotInvalidated {
+                slot_name: slot.into(),
+                reason: format!("it was invalidated upstream: {error}"),
+            }),
+        }
+    }
+}
+
+/// Turns errors caused by provider failures that retrying cannot fix into definite errors that
+/// explain them.
+fn classify_provider_error(err: ReplicationError, slot: &str) -> ReplicationError {
+    let e = match &err {
+        ReplicationError::Definite(e) | ReplicationError::Indefinite(e) => e,
+        ReplicationError::Irrecoverable(_) => return err,
+    };
+    match ProviderError::from_error(e).and_then(|p| p.source_error(slot, describe_error(e))) {
+        Some(details) => ReplicationError::Definite(details.into()),
+        None => err,
+    }
+}
+
+/// Whether an error with the SQLSTATE `code` only aborted the statement that caused it, which can
+/// then be retried within the same transaction after rolling back to a savepoint.
+fn is_retryable_sqlstate(code: &str) -> bool {
+    match code {
+        // serialization_failure, which includes conflicts with recovery on a standby
+        "40001" => true,
+        // deadlock_detected
+        "40P01" => true,
+        // query_canceled, e.g. by a `statement_timeout` we don't control
+        "57014" => true,
+        _ => false,
+    }
+}
+
+/// Whether `err` means that the upstream killed our snapshot transaction, see
+/// [`is_snapshot_killed_sqlstate`].
+fn is_snapshot_killed_error(err: &anyhow::Error) -> bool {
+    UpstreamErrorDetails::from_error(err)
+        .map_or(false, |details| is_snapshot_killed_sqlstate(&details.code))
+}
+
+/// Whether the statement that failed with `err` can be retried, see [`is_retryable_sqlstate`].
+fn is_retryable_error(err: &anyhow::Error) -> bool {
+    UpstreamErrorDetails::from_error(err)
+        .map_or(false, |details| is_retryable_sqlstate(&details.code))
+}
+
+/// Whether `err` means that a read of a snapshot copy took longer than its `COPY READ TIMEOUT`.
+fn is_read_timeout(err: &anyhow::Error) -> bool {
+    err.chain()
+        .any(|err| err.is::<tokio::time::error::Elapsed>())
+}
+
+/// Whether `err` means that the connection to the upstream was lost, e.g. because it was reset.
+fn is_connection_error(err: &anyhow::Error) -> bool {
+    err.chain().any(|err| {
+        find_source::<std::io::Error>(err).is_some()
+            || find_source::<tokio_postgres::Error>(err).map_or(false, |err| err.is_closed())
+    })
+}
+
+/// Whether an error with the SQLSTATE `code` means that the upstream killed our snapshot
+/// transaction, e.g. because it was holding back vacuum for too long.
+fn is_snapshot_killed_sqlstate(code: &str) -> bool {
+    match code {
+        // snapshot_too_old, raised once the snapshot is older than `old_snapshot_threshold`
+        "72000" => true,
+        // admin_shutdown, e.g. a DBA terminating the backend with `pg_terminate_backend`
+        "57P01" => true,
+        _ => false,
+    }
+}
+
+/// How far a snapshot had progressed when the upstream killed it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SnapshotKilled {
+    /// The table that was being copied
+    table: String,
+    /// The number of rows of `table` that had been copied
+    rows: u64,
+    /// The number of tables that had been copied completely
+    tables_done: usize,
+    tables_total: usize,
+}
+
+impl SnapshotKilled {
+    /// Whether this snapshot got further than `other`, which was copying the tables in the same
+    /// order.
+    fn got_further_than(&self, other: &SnapshotKilled) -> bool {
+        (self.tables_done, self.rows) > (other.tables_done, other.rows)
+    }
+
+    fn hint(&self) -> String {
+        "The upstream server is terminating the snapshot transaction, likely because it holds \
+         back vacuum for too long (e.g. due to old_snapshot_threshold) or because it was \
+         terminated manually. The whole snapshot runs in a single transaction, so raise \
+         old_snapshot_threshold, snapshot from a standby with SNAPSHOT STANDBY, or create the \
+         source during a maintenance window."
+            .into()
+    }
+}
+
+impl fmt::Display for SnapshotKilled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "snapshot killed upstream after copying {} rows of table {} ({} of {} tables done)",
+            self.rows, self.table, self.tables_done, self.tables_total
+        )
+    }
+}
+
+/// A streamed transaction aborted one of its subtransactions, whose changes can't be told apart
+/// from the other changes of the transaction that we buffered.
+#[derive(Debug)]
+struct SubtransactionAborted {
+    xid: u32,
+    subxid: u32,
+}
+
+impl fmt::Display for SubtransactionAborted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "streamed transaction {} aborted its subtransaction {}",
+            self.xid, self.subxid
+        )
+    }
+}
+
+/// A privilege that the upstream role lacks, without which the source can't make progress.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum MissingPrivilege {
+    /// The `REPLICATION` attribute, which replication connections and slots require
+    Replication,
+    /// `USAGE` on the schema of an ingested table
+    SchemaUsage { schema: String },
+    /// `SELECT` on an ingested table, which copying its snapshot requires
+    Select { schema: String, table: String },
+    /// A privilege we don't know how to grant
+    Other,
+}
+
+impl MissingPrivilege {
+    /// Recognizes the privilege that the upstream role lacked when it raised `details`, given the
+    /// table that was being copied, if any.
+    fn from_details(
+        details: &UpstreamErrorDetails,
+        table: Option<&PostgresTableDesc>,
+    ) -> Option<Self> {
+        // insufficient_privilege
+        if details.code != "42501" {
+            return None;
+        }
+        let message = details.message.as_str();
+        let detail = details.detail.as_deref().unwrap_or("");
+        // Before PostgreSQL 16, e.g. "must be superuser or replication role to use replication
+        // slots". Since, e.g. "permission denied to start WAL sender", with the attribute in the
+        // detail.
+        if message.contains("replication role") || detail.contains("REPLICATION attribute") {
+            return Some(MissingPrivilege::Replication);
+        }
+        if let Some(schema) = message.strip_prefix("permission denied for schema ") {
+            return Some(MissingPrivilege::SchemaUsage {
+                schema: schema.into(),
+            });
+        }
+        // "permission denied for table", or "for relation" before PostgreSQL 11
+        match table {
+            Some(desc) if message.starts_with("permission denied for ") => {
+                Some(MissingPrivilege::Select {
+                    schema: desc.namespace.clone(),
+                    table: desc.name.clone(),
+                })
+            }
+            _ => Some(MissingPrivilege::Other),
+        }
+    }
+
+    /// Recognizes the privilege that the upstream role lacked when it caused `err`, given the
+    /// table that was being copied, if any.
+    fn from_error(err: &anyhow::Error, table: Option<&PostgresTableDesc>) -> Option<Self> {
+        match err.downcast_ref::<MissingPrivilege>() {
+            Some(missing) => Some(missing.clone()),
+            None => UpstreamErrorDetails::from_error(err)
+                .and_then(|details| Self::from_details(&details, table)),
+        }
+    }
+
+    /// The statement that grants the privilege to the upstream role `user`, and how the source
+    /// recovers afterwards.
+    fn hint(&self, user: Option<&str>) -> String {
+        format!(
+            "{} The source retries every {INSUFFICIENT_PRIVILEGE_BACKOFF:?} and recovers once the \
+             privilege is granted.",
+            self.grant(user)
+        )
+    }
+
+    /// The statement that grants the privilege to the upstream role `user`.
+    fn grant(&self, user: Option<&str>) -> String {
+        let role = match user {
+            Some(user) => format!("{user:?}"),
+            None => "<role>".into(),
+        };
+        match self {
+            MissingPrivilege::Replication => format!(
+                "Run ALTER ROLE {role} WITH REPLICATION; upstream (on Amazon RDS and Aurora, \
+                 GRANT rds_replication TO {role}; instead)."
+            ),
+            MissingPrivilege::SchemaUsage { schema } => {
+                format!("Run GRANT USAGE ON SCHEMA {schema:?} TO {role}; upstream.")
+            }
+            MissingPrivilege::Select { schema, table } => format!(
+                "Run GRANT SELECT ON {schema:?}.{table:?} TO {role}; upstream (on PostgreSQL 14 \
+                 and later, GRANT pg_read_all_data TO {role}; grants SELECT on all tables)."
+            ),
+            MissingPrivilege::Other => {
+                format!("Grant the privilege that the error names to {role} upstream.")
+            }
+        }
+    }
+}
+
+impl fmt::Display for MissingPrivilege {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MissingPrivilege::Replication => {
+                f.write_str("the upstream role lacks the REPLICATION attribute")
+            }
+            MissingPrivilege::SchemaUsage { schema } => {
+                write!(f, "the upstream role lacks USAGE on schema {schema}")
+            }
+            MissingPrivilege::Select { schema, table } => {
+                write!(
+                    f,
+                    "the upstream role lacks SELECT on table {schema}.{table}"
+                )
+            }
+            MissingPrivilege::Other => f.write_str("the upstream role lacks a required privilege"),
+        }
+    }
+}
+
+/// Returns the first error of type `T` in the chain of sources starting at `err` (inclusive).
+fn find_source<'a, T: Error + 'static>(err: &'a (dyn Error + 'static)) -> Option<&'a T> {
+    let mut current = Some(err);
+    while let Some(err) = current {
+        if let Some(found) = err.downcast_ref::<T>() {
+            return Some(found);
+        }
+        // `io::Error::source` skips over the error it wraps and returns that error's source, so
+        // we have to look into the wrapped error explicitly.
+        current = match err.downcast_ref::<std::io::Error>() {
+            Some(io_err) => match io_err.get_ref() {
+                Some(inner) => Some(inner),
+                None => None,
+            },
+            None => err.source(),
+        };
+    }
+    None
+}
+
+/// The structured fields of an error reported by the upstream server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct UpstreamErrorDetails {
+    /// The SQLSTATE code of the error
+    code: String,
+    message: String,
+    detail: Option<String>,
+    hint: Option<String>,
+    schema: Option<String>,
+    table: Option<String>,
+}
+
+impl UpstreamErrorDetails {
+    fn from_db_error(err: &DbError) -> Self {
+        UpstreamErrorDetails {
+            code: err.code().code().to_string(),
+            message: err.message().to_string(),
+            detail: err.detail().map(str::to_string),
+            hint: err.hint().map(str::to_string),
+            schema: err.schema().map(str::to_string),
+            table: err.table().map(str::to_string),
+        }
+    }
+
+    /// Extracts the details of the upstream error that caused `err`, if any.
+    fn from_error(err: &anyhow::Error) -> Option<Self> {
+        err.chain()
+            .find_map(find_source::<DbError>)
+            .map(Self::from_db_error)
+    }
+}
+
+impl fmt::Display for UpstreamErrorDetails {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "code={} message={:?}", self.code, self.message)?;
+        let optional = [
+            ("detail", &self.detail),
+            ("hint", &self.hint),
+            ("schema", &self.schema),
+            ("table", &self.table),
+        ];
+        for (key, value) in optional {
+            if let Some(value) = value {
+                write!(f, " {key}={value:?}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Renders `err` for logs and health statuses, including the structured details of the upstream
+/// error that caused it, if any.
+fn describe_error(err: &anyhow::Error) -> String {
+    match UpstreamErrorDetails::from_error(err) {
+        Some(details) => format!("{} ({details})", err.to_string_alt()),
+        None => err.to_string_alt(),
+    }
+}
+
 #[derive(Debug)]
 enum ReplicationError {
     /// This error is definite: this source is permanently wedged.
@@ -143,6 +694,17 @@ enum ReplicationError {
     Irrecoverable(anyhow::Error),
 }
 
+impl ReplicationError {
+    /// Returns the error, for callers that don't handle it like a source does.
+    fn into_inner(self) -> anyhow::Error {
+        match self {
+            ReplicationError::Definite(err)
+            | ReplicationError::Indefinite(err)
+            | ReplicationError::Irrecoverable(err) => err,
+        }
+    }
+}
+
 impl<E: ErrorExt + Into<anyhow::Error>> From<E> for ReplicationError {
     fn from(err: E) -> Self {
         if err.is_definite() {
@@ -159,6 +721,31 @@ trait ResultExt<T, E> {
     fn err_irrecoverable(self) -> Result<T, ReplicationError>;
 }
 
+trait OperationResultExt<T> {
+    /// Tags the error with the `operation` that raised it, which decides whether it is definite.
+    fn err_during(self, operation: Operation) -> Result<T, ReplicationError>;
+}
+
+impl<T, E> OperationResultExt<T> for Result<T, E>
+where
+    E: ErrorExt + Error + Send + Sync + 'static,
+{
+    fn err_during(self, operation: Operation) -> Result<T, ReplicationError> {
+        self.map_err(|err| {
+            let definite = match find_source::<DbError>(&err) {
+                Some(db_err) => operation.is_definite_sqlstate(db_err.code().code()),
+                None => err.is_definite(),
+            };
+            let err = anyhow::Error::new(err).context(operation);
+            if definite {
+                ReplicationError::Definite(err)
+            } else {
+                ReplicationError::Indefinite(err)
+            }
+        })
+    }
+}
+
 impl<T, E: Into<anyhow::Error>> ResultExt<T, E> for Result<T, E> {
     fn err_definite(self) -> Result<T, ReplicationError> {
         match self {
@@ -185,14 +772,26 @@ enum InternalMessage {
     Err(SourceReaderError),
     Status(HealthStatusUpdate),
     Value {
-        output: usize,
-        value: Row,
+        /// The message, whose key and headers are filled in by the reader, or the error of a row
+        /// that failed to cast
+        message: Result<SourceMessage<Row, Row>, SourceReaderError>,
         lsn: PgLsn,
         diff: Diff,
+        /// Whether this is the last message at `lsn`, which closes it
         end: bool,
+        /// The id of the upstream transaction the value belongs to, if known
+        xid: Option<u32>,
     },
 }
 
+/// The upstream transaction a replicated change belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TransactionInfo {
+    xid: u32,
+    /// The commit time of the transaction, in milliseconds since the Unix epoch, if known
+    commit_time_millis: Option<i64>,
+}
+
 /// Information required to sync data from Postgres
 pub struct PostgresSourceReader {
     receiver_stream: Receiver<InternalMessage>,
@@ -213,7 +812,111 @@ pub struct PgOffsetCommitter {
     resume_lsn: Arc<AtomicU64>,
 }
 
+/// Limits that guard Postgres sources against pathologically large upstream rows and
+/// transactions, and against silently making no progress. The limits are shared by all Postgres
+/// sources of a worker and can be adjusted at runtime through [`StorageParameters`].
+#[derive(Debug)]
+pub struct PgSourceLimits {
+    max_row_size_bytes: AtomicUsize,
+    max_transaction_changes: AtomicUsize,
+    lsn_staleness_threshold_millis: AtomicU64,
+    schema_audit_interval_millis: AtomicU64,
+    min_feedback_interval_millis: AtomicU64,
+    transaction_buffer_degraded_bytes: AtomicUsize,
+    backpressure_lag_bytes: AtomicUsize,
+}
+
+impl Default for PgSourceLimits {
+    fn default() -> Self {
+        Self {
+            max_row_size_bytes: AtomicUsize::new(usize::MAX),
+            max_transaction_changes: AtomicUsize::new(usize::MAX),
+            lsn_staleness_threshold_millis: AtomicU64::new(300_000),
+            schema_audit_interval_millis: AtomicU64::new(3_600_000),
+            min_feedback_interval_millis: AtomicU64::new(1_000),
+            transaction_buffer_degraded_bytes: AtomicUsize::new(512 * 1024 * 1024),
+            backpressure_lag_bytes: AtomicUsize::new(usize::MAX),
+        }
+    }
+}
+
+impl PgSourceLimits {
+    /// Applies the set limits in `params`, leaving the unset ones untouched.
+    pub fn apply(&self, params: &StorageParameters) {
+        if let Some(max_row_size_bytes) = params.pg_source_max_row_size_bytes {
+            self.max_row_size_bytes
+                .store(max_row_size_bytes, Ordering::SeqCst);
+        }
+        if let Some(max_transaction_changes) = params.pg_source_max_transaction_changes {
+            self.max_transaction_changes
+                .store(max_transaction_changes, Ordering::SeqCst);
+        }
+        if let Some(threshold) = params.pg_source_lsn_staleness_threshold {
+            let millis = u64::try_from(threshold.as_millis()).unwrap_or(u64::MAX);
+            self.lsn_staleness_threshold_millis
+                .store(millis, Ordering::SeqCst);
+        }
+        if let Some(interval) = params.pg_source_schema_audit_interval {
+            let millis = u64::try_from(interval.as_millis()).unwrap_or(u64::MAX);
+            self.schema_audit_interval_millis
+                .store(millis, Ordering::SeqCst);
+        }
+        if let Some(interval) = params.pg_source_min_feedback_interval {
+            let millis = u64::try_from(interval.as_millis()).unwrap_or(u64::MAX);
+            self.min_feedback_interval_millis
+                .store(millis, Ordering::SeqCst);
+        }
+        if let Some(bytes) = params.pg_source_transaction_buffer_degraded_bytes {
+            self.transaction_buffer_degraded_bytes
+                .store(bytes, Ordering::SeqCst);
+        }
+        if let Some(bytes) = params.pg_source_backpressure_lag_bytes {
+            self.backpressure_lag_bytes.store(bytes, Ordering::SeqCst);
+        }
+    }
+
+    /// The maximum size in bytes of a single decoded row.
+    fn max_row_size_bytes(&self) -> usize {
+        self.max_row_size_bytes.load(Ordering::SeqCst)
+    }
+
+    /// The maximum number of changes buffered for a single upstream transaction.
+    fn max_transaction_changes(&self) -> usize {
+        self.max_transaction_changes.load(Ordering::SeqCst)
+    }
+
+    /// How long the resume LSN may stay unchanged before the source is reported as stalled.
+    fn lsn_staleness_threshold(&self) -> Duration {
+        Duration::from_millis(self.lsn_staleness_threshold_millis.load(Ordering::SeqCst))
+    }
+
+    /// How often the upstream schemas of the ingested tables are audited.
+    fn schema_audit_interval(&self) -> Duration {
+        Duration::from_millis(self.schema_audit_interval_millis.load(Ordering::SeqCst))
+    }
+
+    /// The minimum time between the status updates sent in reply to the requests of the
+    /// upstream.
+    fn min_feedback_interval(&self) -> Duration {
+        Duration::from_millis(self.min_feedback_interval_millis.load(Ordering::SeqCst))
+    }
+
+    /// The size in bytes of the changes buffered for uncommitted transactions beyond which the
+    /// source is reported as degraded.
+    fn transaction_buffer_degraded_bytes(&self) -> usize {
+        self.transaction_buffer_degraded_bytes
+            .load(Ordering::SeqCst)
+    }
+
+    /// How many bytes of WAL the emitted changes may be ahead of the ones committed downstream
+    /// before the replication stream stops being read.
+    fn backpressure_lag_bytes(&self) -> u64 {
+        u64::cast_from(self.backpressure_lag_bytes.load(Ordering::SeqCst))
+    }
+}
+
 /// Information about an ingested upstream table
+#[derive(Clone)]
 struct SourceTable {
     /// The source output index of this table
     output_index: usize,
@@ -222,26 +925,234 @@ struct SourceTable {
     /// The scalar expressions required to cast the text encoded columns received from postgres
     /// into the target relational types
     casts: Vec<MirScalarExpr>,
+    /// The positions of the table's columns among its columns upstream, unless they are the
+    /// leading ones in order, as determined by [`determine_table_compatibility`]
+    projection: Option<Vec<usize>>,
+    /// The values of the table's columns in the tuples upstream wrote before they were added, as
+    /// determined by [`column_defaults`]
+    default_datums: Vec<ColumnDefault>,
+    /// Whether rows deleted upstream are kept and flagged as deleted by their last column,
+    /// which `casts` evaluate to false, instead of being retracted
+    soft_delete: bool,
+    /// The names the table is expected to be renamed to upstream, under which it is still
+    /// compatible, see [`renamed_desc`]
+    expected_names: BTreeSet<String>,
 }
 
 /// An internal struct held by the spawned tokio task
 struct PostgresTaskInfo {
     source_id: GlobalId,
-    connection_config: mz_postgres_util::Config,
-    publication: String,
+    /// The connections to the upstream server
+    connections: UpstreamConnections,
+    /// The publications we replicate from, which don't share any tables
+    publications: Vec<String>,
+    /// The publications to replicate from instead, once they have been validated to contain the
+    /// same tables as the publications the source was created from
+    pending_publications: Option<Vec<String>>,
+    /// The tables of the publications the source was created from
+    publication_tables: Vec<PostgresTableDesc>,
+    /// The publication tables the source could not ingest and the compatible changes to the
+    /// upstream schemas of the ingested ones, which are reported along with its progress
+    status_details: SourceStatusDetails,
     slot: String,
     /// Our cursor into the WAL
     replication_lsn: PgLsn,
-    metrics: PgSourceMetrics,
+    metrics: Arc<PgSourceMetrics>,
     /// A map of the table oid to its information
     source_tables: BTreeMap<u32, SourceTable>,
+    /// The tables that the table OID allowlist excludes, which produce no output but are still
+    /// validated against the publication
+    excluded_tables: Vec<PostgresTableDesc>,
     row_sender: RowSender,
-    sender: Sender<InternalMessage>,
     resume_lsn: Arc<AtomicU64>,
+    limits: Arc<PgSourceLimits>,
+    /// Whether the replication connection should be closed until the source is resumed
+    pause: PauseSignal,
+    /// Whether to request that large in-progress transactions are streamed to us
+    streaming_transactions: bool,
+    /// The number of buffered changes after which a transaction is emitted in parts, if any
+    max_transaction_rows: Option<usize>,
+    /// How long the replication connection may stay idle before its liveness is checked, if ever
+    ping_interval: Option<Duration>,
+    /// The number of rows to fetch at a time when snapshotting through a cursor, if any
+    snapshot_cursor_fetch_size: Option<usize>,
+    /// The statement timeout of the snapshot queries, which is disabled if unset
+    snapshot_statement_timeout: Option<Duration>,
+    /// How long the snapshot of a table may wait for the next chunk of rows before it is retried
+    copy_read_timeout: Duration,
+    /// Whether tables whose upstream schema changed incompatibly are snapshotted anew
+    resnapshot_on_schema_change: bool,
+    /// Whether the rows of tables truncated upstream are retracted instead of erroring
+    retract_on_truncate: bool,
+    /// Whether tables that are unlogged upstream are only snapshotted instead of erroring
+    snapshot_unlogged_tables: bool,
+    /// The logical decoding output plugin of the slot
+    replication_plugin: ReplicationPlugin,
+    /// Whether WAL replay is paused on a standby while snapshotting from it
+    synchronize_replicas: bool,
+    /// The order in which tables are snapshotted
+    snapshot_order: SnapshotOrder,
+    /// The decoder of the data of the `COPY` queries that snapshot tables
+    snapshot_decoder: &'static dyn CopyOutDecoder,
+    /// The standby that the initial snapshot copies tables from, if not the server we replicate
+    /// from
+    snapshot_config: Option<mz_postgres_util::Config>,
+    /// Where the raw replication stream is captured to, if anywhere
+    wal_capture: Option<WalCapture>,
+    /// The collections the outputs are exported to, by output index
+    outputs: BTreeMap<usize, (GlobalId, CollectionMetadata)>,
+    /// Whether the rows of the outputs carry provenance headers as their last column
+    provenance_headers: bool,
+    persist_clients: Arc<PersistClientCache>,
+    /// Suppresses repeated warnings while e.g. the upstream is unreachable
+    log_dedup: LogDedup,
+    /// The replicated operations of each table, which are logged periodically
+    table_stats: TableStats,
+    /// When the upstream schemas of the ingested tables are audited next
+    schema_audit: SchemaAudit,
+    /// How the source fast-forwards over WAL lag, which degrades when the upstream does not let
+    /// it peek into the replication slot
+    fast_forward_mode: FastForwardMode,
+    /// How far the killed snapshots of the source got across its restarts
+    snapshot_attempts: Arc<PgSnapshotAttempts>,
+}
+
+impl PostgresTaskInfo {
+    /// Whether the tables of the source have yet to be snapshotted, i.e. whether replication has
+    /// no LSN to resume from yet.
+    fn needs_snapshot(&self) -> bool {
+        self.replication_lsn == PgLsn::from(0)
+    }
+}
+
+/// Returns the positions of the columns of `desc`'s primary key among its columns, or none if the
+/// table has no primary key.
+fn primary_key_indices(desc: &PostgresTableDesc) -> Vec<usize> {
+    let Some(key) = desc.keys.iter().find(|key| key.is_primary) else {
+        return vec![];
+    };
+    key.cols
+        .iter()
+        .map(|col_num| {
+            desc.columns
+                .iter()
+                .position(|column| column.col_num == Some(*col_num))
+        })
+        .collect::<Option<_>>()
+        .unwrap_or_default()
+}
+
+/// Extracts the values of the columns at `key_indices` from `value`.
+fn extract_key(value: &Row, key_indices: &[usize], datum_vec: &mut DatumVec) -> Row {
+    if key_indices.is_empty() {
+        return Row::default();
+    }
+    let datums = datum_vec.borrow_with(value);
+    Row::pack(key_indices.iter().map(|i| datums[*i]))
+}
+
+/// Returns the tables that `connection` ingests, by OID.
+fn source_tables(connection: &PostgresSourceConnection) -> BTreeMap<u32, SourceTable> {
+    let mut source_tables = BTreeMap::new();
+    let tables_iter = connection.publication_details.tables.iter();
+
+    for (i, desc) in tables_iter.enumerate() {
+        let output_index = i + 1;
+        // We maintain descriptions for all tables in the publication,
+        // but only casts for those we aim to use (and have validated
+        // that their types are ingestable). This also prevents us from
+        // creating snapshots for tables in the publication that are
+        // not referenced in the source.
+        match connection.table_casts.get(&output_index) {
+            Some(casts) => {
+                let source_table = SourceTable {
+                    output_index,
+                    desc: desc.clone(),
+                    casts: casts.clone(),
+                    projection: None,
+                    default_datums: column_defaults(desc),
+                    soft_delete: connection.soft_delete_tables.contains(&desc.oid),
+                    expected_names: connection
+                        .publication_table_rename_map
+                        .get(&desc.oid)
+                        .cloned()
+                        .unwrap_or_default(),
+                };
+                source_tables.insert(desc.oid, source_table);
+            }
+            None => continue,
+        }
+    }
+    source_tables
+}
+
+/// Returns the tables ingested by a source that ingests all of `tables` with every column as text,
+/// whose values are hence left text encoded. Their output indexes are their positions in `tables`.
+fn text_source_tables(tables: &[PostgresTableDesc]) -> BTreeMap<u32, SourceTable> {
+    tables
+        .iter()
+        .enumerate()
+        .map(|(output_index, desc)| {
+            let casts = (0..desc.columns.len()).map(MirScalarExpr::column).collect();
+            let table = SourceTable {
+                output_index,
+                desc: desc.clone(),
+                casts,
+                projection: None,
+                default_datums: column_defaults(desc),
+                soft_delete: false,
+                expected_names: BTreeSet::new(),
+            };
+            (desc.oid, table)
+        })
+        .collect()
+}
+
+/// Returns the publication `tables` that are not in the table OID `allowlist`, if there is an
+/// allowlist, and removes them from `source_tables`.
+///
+/// Sources don't have subsources for the tables their allowlist excludes, but older sources
+/// may, which stay empty.
+fn split_allowlisted(
+    source_tables: &mut BTreeMap<u32, SourceTable>,
+    tables: &[PostgresTableDesc],
+    allowlist: Option<&BTreeSet<u32>>,
+) -> Vec<PostgresTableDesc> {
+    let Some(allowlist) = allowlist else {
+        return vec![];
+    };
+    source_tables.retain(|oid, _| allowlist.contains(oid));
+    tables
+        .iter()
+        .filter(|table| !allowlist.contains(&table.oid))
+        .cloned()
+        .collect()
+}
+
+/// Checks that the publication `tables` still hold compatible versions of the `excluded` tables.
+fn check_excluded_tables(
+    excluded: &[PostgresTableDesc],
+    tables: &[PostgresTableDesc],
+) -> Result<(), anyhow::Error> {
+    for desc in excluded {
+        match tables.iter().find(|table| table.oid == desc.oid) {
+            Some(table) => {
+                desc.determine_compatibility(table)?;
+            }
+            None => {
+                return Err(SourceErrorDetails::TableDropped {
+                    table_oid: desc.oid,
+                    table_name: desc.name.clone(),
+                }
+                .into())
+            }
+        }
+    }
+    Ok(())
 }
 
 impl SourceRender for PostgresSourceConnection {
-    type Key = ();
+    type Key = Row;
     type Value = Row;
     type Time = MzOffset;
 
@@ -252,7 +1163,7 @@ impl SourceRender for PostgresSourceConnection {
         connection_context: ConnectionContext,
         resume_uppers: impl futures::Stream<Item = Antichain<MzOffset>> + 'static,
     ) -> (
-        Collection<G, Result<SourceMessage<(), Row>, SourceReaderError>, Diff>,
+        Collection<G, Result<SourceMessage<Row, Row>, SourceReaderError>, Diff>,
         Option<Stream<G, Infallible>>,
         Stream<G, HealthStatusUpdate>,
         Rc<dyn Any>,
@@ -294,54 +1205,215 @@ impl SourceRender for PostgresSourceConnection {
 
             let resume_lsn = Arc::new(AtomicU64::new(start_offset.offset));
 
-            let connection_config = self
-                .connection
-                .config(&*connection_context.secrets_reader)
-                .await
-                .expect("Postgres connection unexpectedly missing secrets");
-
-            let mut source_tables = BTreeMap::new();
-            let tables_iter = self.publication_details.tables.iter();
-
-            for (i, desc) in tables_iter.enumerate() {
-                let output_index = i + 1;
-                // We maintain descriptions for all tables in the publication,
-                // but only casts for those we aim to use (and have validated
-                // that their types are ingestable). This also prevents us from
-                // creating snapshots for tables in the publication that are
-                // not referenced in the source.
-                match self.table_casts.get(&output_index) {
-                    Some(casts) => {
-                        let source_table = SourceTable {
-                            output_index,
-                            desc: desc.clone(),
-                            casts: casts.to_vec(),
-                        };
-                        source_tables.insert(desc.oid, source_table);
-                    }
-                    None => continue,
-                }
-            }
+            let metrics = Arc::new(PgSourceMetrics::new(&config.base_metrics, config.id));
 
-            let task_info = PostgresTaskInfo {
-                source_id: config.id,
-                connection_config,
-                publication: self.publication,
-                slot: self.publication_details.slot,
-                replication_lsn: start_offset.offset.into(),
-                metrics: PgSourceMetrics::new(&config.base_metrics, config.id),
-                source_tables,
-                row_sender: RowSender::new(dataflow_tx.clone()),
-                sender: dataflow_tx,
-                resume_lsn: Arc::clone(&resume_lsn),
+            let mut source_tables = source_tables(&self);
+
+            // Every message carries the values of its table's primary key, extracted in one place
+            // so that snapshotted, replicated and rewound rows have identical keys.
+            let key_indices: BTreeMap<usize, Vec<usize>> = source_tables
+                .values()
+                .map(|info| (info.output_index, primary_key_indices(&info.desc)))
+                .collect();
+            let mut key_datum_vec = DatumVec::new();
+            // The upstream table of each output, if messages carry provenance headers.
+            let provenance_oids = match self.provenance_headers {
+                true => source_tables
+                    .iter()
+                    .map(|(oid, info)| (info.output_index, *oid))
+                    .collect(),
+                false => BTreeMap::new(),
             };
+            let mut provenance = ProvenanceTracker::new(provenance_oids);
 
-            task::spawn(|| format!("postgres_source:{}", config.id), {
-                postgres_replication_loop(task_info)
+            // A source whose publication has changed since it was created switches to the new
+            // publication only once it has been validated, which keeps the replication slot and
+            // thereby avoids a new snapshot.
+            let current = mz_postgres_util::publication_names(&self.publication);
+            let created_from = self.publication_details.publication.as_deref();
+            let (publications, pending_publications) =
+                match created_from.map(mz_postgres_util::publication_names) {
+                    Some(created_from) if created_from != current => (created_from, Some(current)),
+                    _ => (current, None),
+                };
+
+            let source_id = config.id;
+            let limits = Arc::clone(&config.pg_source_limits);
+            let schema_audit = SchemaAudit::new(limits.schema_audit_interval());
+            let pause = config.pg_source_pauses.signal(config.id);
+            let snapshot_attempts = Arc::clone(&config.pg_snapshot_attempts);
+            let outputs: BTreeMap<_, _> = config
+                .source_exports
+                .iter()
+                .map(|(id, export)| (export.output_index, (*id, export.storage_metadata.clone())))
+                .collect();
+            let persist_clients = Arc::clone(&config.persist_clients);
+            let provenance_headers = self.provenance_headers;
+            let provenance_outputs = provenance_headers.then(|| outputs.clone());
+            let secrets_reader = Arc::clone(&connection_context.secrets_reader);
+            let row_sender = RowSender::new(
+                dataflow_tx,
+                Arc::clone(&metrics),
+                start_offset.offset.into(),
+            );
+            let task_metrics = Arc::clone(&metrics);
+            let task_resume_lsn = Arc::clone(&resume_lsn);
+            // Everything the source reads from upstream is read from the server it replicates
+            // from, as that is where its slot lives.
+            let connection = self
+                .replication_source
+                .connection(&self.connection)
+                .expect("validated during planning");
+            let snapshot_connection = self.snapshot_standby.map(|connection_string| {
+                standby_connection(&connection, &connection_string)
+                    .expect("validated during planning")
             });
+            let publication_tables = self.publication_details.tables;
+            let excluded_tables = split_allowlisted(
+                &mut source_tables,
+                &publication_tables,
+                self.table_oid_allowlist.as_ref(),
+            );
+            // The tables that the allowlist excludes aren't requested, so they needn't be
+            // ingestable.
+            let allowed_tables: Vec<_> = publication_tables
+                .iter()
+                .filter(|table| !excluded_tables.contains(table))
+                .cloned()
+                .collect();
+            let non_ingestable_tables = non_ingestable_tables(&allowed_tables, &source_tables);
+            for (name, reason) in &non_ingestable_tables {
+                warn!(
+                    "table {name} of the publication of source {} cannot be ingested: {reason}",
+                    config.id
+                );
+            }
+            let slot = self.publication_details.slot;
+            let streaming_transactions = self.streaming_transactions;
+            let max_transaction_rows = self.max_transaction_rows;
+            let ping_interval = self.ping_interval;
+            let snapshot_cursor_fetch_size = self.snapshot_cursor_fetch_size;
+            let snapshot_statement_timeout = self.snapshot_statement_timeout;
+            let copy_read_timeout = self.copy_read_timeout;
+            let resnapshot_on_schema_change = self.resnapshot_on_schema_change;
+            let storage_metadata = config.storage_metadata.clone();
+            let retract_on_truncate = self.retract_on_truncate;
+            let snapshot_unlogged_tables = self.snapshot_unlogged_tables;
+            let replication_plugin = self.replication_plugin;
+            let synchronize_replicas = self.synchronize_replicas;
+            let snapshot_order = self.snapshot_order;
+            let snapshot_decoder = copy::decoder(self.snapshot_format);
+            let capture_raw_wal = self.capture_raw_wal;
+            let client_min_messages = self.client_min_messages;
 
-            let source_metrics = SourceReaderMetrics::new(&config.base_metrics, config.id);
-            let offset_commit_metrics = source_metrics.offset_commit_metrics();
+            spawn_replication_thread(config.id, move || async move {
+                // The secrets are resolved by the task, so that the source stalls instead of
+                // taking down the worker while they cannot be read.
+                let connection_config =
+                    resolve_connection_config(&connection, &*secrets_reader, &row_sender).await;
+                let snapshot_config = match &snapshot_connection {
+                    Some(connection) => Some(
+                        resolve_connection_config(connection, &*secrets_reader, &row_sender).await,
+                    ),
+                    None => None,
+                };
+                // The tables snapshotted anew before the source restarted are recorded in its
+                // status history.
+                let resnapshotted_tables = if resnapshot_on_schema_change {
+                    loop {
+                        match schema_change::read_resnapshotted_tables(
+                            &persist_clients,
+                            source_id,
+                            &storage_metadata,
+                        )
+                        .await
+                        {
+                            Ok(tables) => break tables,
+                            Err(err) => {
+                                warn!(
+                                    "failed to read the resnapshotted tables of source \
+                                     {source_id}, retrying: {err:#}"
+                                );
+                                tokio::time::sleep(Duration::from_secs(1)).await;
+                            }
+                        }
+                    }
+                } else {
+                    BTreeMap::new()
+                };
+                let task_info = PostgresTaskInfo {
+                    source_id,
+                    connections: UpstreamConnections::new(
+                        connection_config,
+                        Arc::clone(&task_metrics),
+                        client_min_messages,
+                    ),
+                    publications,
+                    pending_publications,
+                    publication_tables,
+                    status_details: SourceStatusDetails {
+                        non_ingestable_tables,
+                        postgres: (!resnapshotted_tables.is_empty()).then(|| {
+                            PostgresStatusDetails {
+                                resnapshotted_tables,
+                                ..Default::default()
+                            }
+                        }),
+                        ..Default::default()
+                    },
+                    slot,
+                    replication_lsn: start_offset.offset.into(),
+                    metrics: task_metrics,
+                    source_tables,
+                    excluded_tables,
+                    row_sender,
+                    resume_lsn: task_resume_lsn,
+                    limits,
+                    pause,
+                    streaming_transactions,
+                    max_transaction_rows,
+                    ping_interval,
+                    snapshot_cursor_fetch_size,
+                    snapshot_statement_timeout,
+                    copy_read_timeout,
+                    resnapshot_on_schema_change,
+                    retract_on_truncate,
+                    snapshot_unlogged_tables,
+                    replication_plugin,
+                    synchronize_replicas,
+                    snapshot_order,
+                    snapshot_decoder,
+                    snapshot_config,
+                    wal_capture: capture_raw_wal.map(|path| WalCapture::start(source_id, path)),
+                    outputs,
+                    provenance_headers,
+                    persist_clients,
+                    log_dedup: LogDedup::new(log_dedup::DEFAULT_WINDOW),
+                    table_stats: TableStats::new(source_id, table_stats::DEFAULT_INTERVAL),
+                    schema_audit,
+                    fast_forward_mode: FastForwardMode::default(),
+                    snapshot_attempts,
+                };
+                postgres_replication_loop(task_info).await
+            });
+
+            let (monitor_tx, mut monitor_rx) = tokio::sync::mpsc::channel(1);
+            let monitor = PostgresReplicationMonitor::new(
+                Observation::new(&resume_lsn, &metrics),
+                Instant::now(),
+            );
+            task::spawn(|| format!("postgres_source_monitor:{}", config.id), {
+                monitor.run(
+                    Arc::clone(&resume_lsn),
+                    Arc::clone(&metrics),
+                    Arc::clone(&config.pg_source_limits),
+                    config.pg_source_pauses.signal(config.id),
+                    monitor_tx,
+                )
+            });
+
+            let source_metrics = SourceReaderMetrics::new(&config.base_metrics, config.id);
+            let offset_commit_metrics = source_metrics.offset_commit_metrics();
 
             let mut reader = PostgresSourceReader {
                 receiver_stream: dataflow_rx,
@@ -374,49 +1446,85 @@ impl SourceRender for PostgresSourceConnection {
             };
             tokio::pin!(offset_commit_loop);
 
+            // Retractions must carry the headers of the rows they retract, including those that
+            // were ingested before the source restarted.
+            if let Some(outputs) = provenance_outputs {
+                while let Err(err) = provenance.load(&config.persist_clients, &outputs).await {
+                    warn!(
+                        "failed to read the provenance of the rows of source {}, retrying: {err:#}",
+                        config.id
+                    );
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                }
+            }
+
             loop {
                 tokio::select! {
-                    message = reader.receiver_stream.recv() => match message {
-                        Some(InternalMessage::Value {
-                            output,
-                            value,
-                            diff,
-                            lsn,
-                            end,
-                        }) => {
-                            reader.last_lsn = lsn;
-                            let msg = SourceMessage {
-                                output,
-                                upstream_time_millis: None,
-                                key: (),
-                                value,
-                                headers: None,
-                            };
-
-                            let ts = lsn.into();
-                            let cap = reader.data_capability.delayed(&ts);
-                            let next_ts = ts + 1;
-                            reader.upper_capability.downgrade(&next_ts);
-                            if end {
+                    message = reader.receiver_stream.recv() => {
+                        if message.is_some() {
+                            metrics.channel_queued_messages.dec();
+                        }
+                        match message {
+                            Some(InternalMessage::Value {
+                                message,
+                                diff,
+                                lsn,
+                                end,
+                                xid,
+                            }) => {
+                                reader.last_lsn = lsn;
+                                let message = message.map(|mut msg| {
+                                    if let Some(indices) = key_indices.get(&msg.output_index) {
+                                        msg.key =
+                                            extract_key(&msg.value, indices, &mut key_datum_vec);
+                                    }
+                                    msg.headers = provenance.headers(
+                                        msg.output_index,
+                                        &msg.value,
+                                        xid,
+                                        lsn,
+                                        diff,
+                                    );
+                                    msg
+                                });
+
+                                let ts = lsn.into();
+                                let cap = reader.data_capability.delayed(&ts);
+                                // The frontiers only advance past the LSN with its last message,
+                                // so they never pass an LSN whose messages were only partially
+                                // emitted. Rows at lower LSNs, like those that rewind a snapshot,
+                                // are all sent before the message that closes the LSN.
+                                if end {
+                                    let next_ts = CommitLsn::new(lsn).to_frontier().to_offset();
+                                    reader.upper_capability.downgrade(&next_ts);
+                                    reader.data_capability.downgrade(&next_ts);
+                                }
+                                data_output.give(&cap, (message, *cap.time(), diff)).await;
+                            }
+                            Some(InternalMessage::Status(update)) => {
+                                health_output.give(&health_capability, update).await;
+                            }
+                            Some(InternalMessage::Err(err)) => {
+                                // XXX(petrosagg): we are fabricating a timestamp here!!
+                                let non_definite_lsn =
+                                    CommitLsn::new(reader.last_lsn).to_frontier();
+                                let non_definite_ts = non_definite_lsn.to_offset();
+
+                                let cap = reader.data_capability.delayed(&non_definite_ts);
+                                // The error is emitted as if it committed at the fabricated LSN.
+                                let next_ts = CommitLsn::new(non_definite_lsn.into())
+                                    .to_frontier()
+                                    .to_offset();
                                 reader.data_capability.downgrade(&next_ts);
+                                reader.upper_capability.downgrade(&next_ts);
+                                data_output.give(&cap, (Err(err), *cap.time(), 1)).await;
                             }
-                            data_output.give(&cap, (Ok(msg), *cap.time(), diff)).await;
-                        }
-                        Some(InternalMessage::Status(update)) => {
-                            health_output.give(&health_capability, update).await;
+                            None => return,
                         }
-                        Some(InternalMessage::Err(err)) => {
-                            // XXX(petrosagg): we are fabricating a timestamp here!!
-                            let non_definite_ts = MzOffset::from(reader.last_lsn) + 1;
-
-                            let cap = reader.data_capability.delayed(&non_definite_ts);
-                            let next_ts = non_definite_ts + 1;
-                            reader.data_capability.downgrade(&next_ts);
-                            reader.upper_capability.downgrade(&next_ts);
-                            data_output.give(&cap, (Err(err), *cap.time(), 1)).await;
-                        }
-                        None => return,
-                    },
+                    }
+                    Some(update) = monitor_rx.recv() => {
+                        health_output.give(&health_capability, update).await;
+                    }
                     // This future is not cancel safe but we are only passing a reference to it in
                     // the select! loop so the future stays on the stack and never gets cancelled
                     // until the end of the function.
@@ -436,57 +1544,246 @@ impl SourceRender for PostgresSourceConnection {
 
 impl PgOffsetCommitter {
     fn commit_offsets(&self, frontier: Antichain<MzOffset>) -> Result<(), anyhow::Error> {
+        fail::fail_point!("pg_offset_commit_failure", |_| {
+            Err(anyhow!("failpoint pg_offset_commit_failure"))
+        });
         if let Some(offset) = frontier.as_option() {
-            // TODO(petrosagg): this minus one is very suspicious. It is replicating the previous
-            // behaviour where the commit offset was calculated by calling
-            // OffsetAntichain::as_data_offsets, which subtracted one. Investigate if it's truly
-            // needed
-            self.resume_lsn
-                .store(offset.offset.saturating_sub(1), Ordering::SeqCst);
+            // The frontier is the first LSN that isn't durable yet, while upstream is told the
+            // LSN of the last commit that is, and resumes replication after it.
+            let resume_lsn = LsnFrontier::from_offset(*offset).to_start_replication_arg();
+            self.resume_lsn.store(resume_lsn.into(), Ordering::SeqCst);
         }
 
         Ok(())
     }
 }
 
+/// Resolves the configuration of `connection`, retrying with exponential backoff while its secrets
+/// cannot be read, e.g. because a secret was deleted or the secrets store is briefly unavailable
+/// during a restart. The source is reported as stalled until the secrets can be read again.
+async fn resolve_connection_config(
+    connection: &PostgresConnection,
+    secrets_reader: &dyn SecretsReader,
+    row_sender: &RowSender,
+) -> mz_postgres_util::Config {
+    let mut backoff = SECRETS_INITIAL_BACKOFF;
+    let mut stalled = false;
+    loop {
+        match connection.config(secrets_reader).await {
+            Ok(config) => {
+                if stalled {
+                    row_sender
+                        .send(InternalMessage::Status(HealthStatus::Running.into()))
+                        .await;
+                }
+                return config;
+            }
+            Err(err) => {
+                let error = format!(
+                    "failed to read the secrets of the Postgres connection: {}",
+                    err.to_string_alt()
+                );
+                warn!("{error}, retrying in {backoff:?}");
+                row_sender
+                    .send(InternalMessage::Status(HealthStatusUpdate {
+                        update: HealthStatus::StalledWithError {
+                            error,
+                            hint: Some(
+                                "Check that the secrets used by the connection exist.".into(),
+                            ),
+                        },
+                        should_halt: false,
+                        details: None,
+                    }))
+                    .await;
+                stalled = true;
+                tokio::time::sleep(backoff).await;
+                backoff = std::cmp::min(backoff * 2, SECRETS_MAX_BACKOFF);
+            }
+        }
+    }
+}
+
+/// Runs the replication task of source `id`, which `make_task` returns, on a thread of its own,
+/// on a single-threaded runtime of its own. The task doesn't need to be `Send`, so that it can hold
+/// types that aren't, like the replication stream, across await points. The thread ends with the
+/// task.
+fn spawn_replication_thread<F, Fut>(id: GlobalId, make_task: F) -> thread::JoinHandle<()>
+where
+    F: FnOnce() -> Fut + Send + 'static,
+    Fut: future::Future<Output = ()> + 'static,
+{
+    thread::Builder::new()
+        .name(format!("pg-source-{id}"))
+        .spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .expect("failed to build the runtime of the replication thread");
+            let local = LocalSet::new();
+            // The task only ends early if it panicked or was cancelled, which must not leave the
+            // source stalled without a trace. Panics are resumed on the thread, which the panic
+            // handling of the process then deals with like those of any other thread.
+            let result = local.block_on(
+                &runtime,
+                task::spawn_local(|| format!("postgres_source:{id}"), make_task()),
+            );
+            if let Err(err) = result {
+                error!("replication task of source {id} failed: {err}");
+                if err.is_panic() {
+                    std::panic::resume_unwind(err.into_panic());
+                }
+            }
+        })
+        .expect("failed to spawn the replication thread");
+}
+
 /// Defers to `postgres_replication_loop_inner` and sends errors through the channel if they occur
-#[allow(clippy::or_fun_call)]
 async fn postgres_replication_loop(mut task_info: PostgresTaskInfo) {
     loop {
-        match postgres_replication_loop_inner(&mut task_info).await {
+        // Once the source operator is gone, e.g. because the source was dropped, nothing is
+        // received anymore, so replication stops rather than reconnecting forever.
+        if task_info.row_sender.is_closed() {
+            info!(
+                "source {} is gone, stopping its replication",
+                task_info.source_id
+            );
+            return;
+        }
+        if task_info.pause.is_paused() {
+            info!("replication for source {} paused", task_info.source_id);
+            if !task_info
+                .row_sender
+                .send(InternalMessage::Status(HealthStatus::Paused.into()))
+                .await
+            {
+                return;
+            }
+            task_info.pause.wait_for(false).await;
+            info!(
+                "replication for source {} resumed at {}",
+                task_info.source_id, task_info.replication_lsn
+            );
+            if !task_info
+                .row_sender
+                .send(InternalMessage::Status(HealthStatus::Running.into()))
+                .await
+            {
+                return;
+            }
+        }
+        let mut retry_after = Duration::from_secs(3);
+        let result = postgres_replication_loop_inner(&mut task_info)
+            .await
+            .map_err(|err| classify_provider_error(err, &task_info.slot));
+        match result {
             Ok(()) => {}
+            // Without streaming, the upstream sends a transaction at its commit, and leaves out the
+            // changes of its aborted subtransactions. It stays off until the source restarts, as
+            // large transactions are rare enough that spilling them to disk upstream is cheaper
+            // than finding out which of their subtransactions abort.
+            Err(ReplicationError::Indefinite(e))
+                if e.downcast_ref::<SubtransactionAborted>().is_some() =>
+            {
+                info!(
+                    "source {} disables streaming transactions: {}",
+                    task_info.source_id,
+                    describe_error(&e)
+                );
+                task_info.streaming_transactions = false;
+                retry_after = Duration::ZERO;
+            }
             Err(ReplicationError::Indefinite(e)) => {
-                warn!(
-                    "replication for source {} interrupted, retrying: {e}",
-                    task_info.source_id
+                let error = describe_error(&e);
+                task_info.log_dedup.warn(
+                    "interrupted",
+                    format!(
+                        "replication for source {} interrupted, retrying: {error}",
+                        task_info.source_id
+                    ),
                 );
+                let hint = if let Some(ProviderError::TooManyConnections) =
+                    ProviderError::from_error(&e)
+                {
+                    task_info.metrics.connection_limit_errors.inc();
+                    retry_after = CONNECTION_LIMIT_BACKOFF;
+                    Some(format!(
+                        "The upstream role reached its connection limit, retrying in \
+                         {CONNECTION_LIMIT_BACKOFF:?}. Close some of its connections or \
+                         raise its limit, e.g. with ALTER ROLE ... CONNECTION LIMIT."
+                    ))
+                } else if let Some(missing) = MissingPrivilege::from_error(&e, None) {
+                    // Privileges are granted by a human, which is not worth polling for often.
+                    task_info.metrics.insufficient_privilege_errors.inc();
+                    retry_after = INSUFFICIENT_PRIVILEGE_BACKOFF;
+                    Some(missing.hint(task_info.connections.user()))
+                } else {
+                    None
+                };
+                // While the replication connection can't be opened, its backoff keeps growing,
+                // and so does the wait until the next attempt.
+                retry_after =
+                    std::cmp::max(retry_after, task_info.connections.replication_backoff());
                 // If the channel is shutting down, so is the source.
-                let _ = task_info
-                    .sender
+                if !task_info
+                    .row_sender
                     .send(InternalMessage::Status(HealthStatusUpdate {
-                        update: HealthStatus::StalledWithError {
-                            error: e.to_string_alt(),
-                            hint: None,
-                        },
+                        update: HealthStatus::StalledWithError { error, hint },
                         should_halt: false,
+                        details: None,
                     }))
-                    .await;
+                    .await
+                {
+                    return;
+                }
             }
             Err(ReplicationError::Irrecoverable(e)) => {
+                let error = describe_error(&e);
                 warn!(
-                    "irrecoverable error for source {}: {}, cause: {}",
+                    "irrecoverable error for source {}: {error}",
                     &task_info.source_id,
-                    e,
-                    e.source().unwrap_or(anyhow::anyhow!("unknown").as_ref())
                 );
+                // The progress of a killed snapshot is reported in the status, and the source
+                // errors for good once repeated attempts stop getting any further.
+                let hint = match e.downcast_ref::<SnapshotKilled>() {
+                    Some(killed) => {
+                        task_info.metrics.snapshots_killed.inc();
+                        let stalled = task_info
+                            .snapshot_attempts
+                            .record_killed(task_info.source_id, killed);
+                        if stalled >= SNAPSHOT_KILLED_STALLED_ATTEMPTS {
+                            let error = format!(
+                                "{error}, and the last {stalled} attempts got no further. {}",
+                                killed.hint()
+                            );
+                            warn!("source {} gave up its snapshot", task_info.source_id);
+                            task_info
+                                .row_sender
+                                .send(InternalMessage::Err(SourceReaderError {
+                                    inner: SourceErrorDetails::Initialization(error),
+                                    output_index: 0,
+                                }))
+                                .await;
+                            return;
+                        }
+                        Some(killed.hint())
+                    }
+                    // A snapshot that lost a privilege after it sent rows starts over when the
+                    // source restarts, which doesn't wait for the privilege to be granted.
+                    None => MissingPrivilege::from_error(&e, None).map(|missing| {
+                        task_info.metrics.insufficient_privilege_errors.inc();
+                        format!(
+                            "{} The source restarts its snapshot from the beginning, and \
+                             completes it once the privilege is granted.",
+                            missing.grant(task_info.connections.user())
+                        )
+                    }),
+                };
                 // If the channel is shutting down, so is the source.
-                let _ = task_info
-                    .sender
+                let sent = task_info
+                    .row_sender
                     .send(InternalMessage::Status(HealthStatusUpdate {
-                        update: HealthStatus::StalledWithError {
-                            error: e.to_string_alt(),
-                            hint: None,
-                        },
+                        update: HealthStatus::StalledWithError { error, hint },
                         // TODO: In the future we probably want to handle this more gracefully,
                         // but for now halting is the easiest way to dump the data in the pipe.
                         // The restarted clusterd instance will restart the snapshot fresh, which will
@@ -494,32 +1791,37 @@ async fn postgres_replication_loop(mut task_info: PostgresTaskInfo) {
                         // next snapshotting, the remapped timestamp chosen will be the same for
                         // both instances of clusterd.
                         should_halt: true,
+                        details: None,
                     }))
                     .await;
+                if !sent {
+                    return;
+                }
 
                 future::pending().await
             }
             Err(ReplicationError::Definite(e)) => {
+                let error = describe_error(&e);
                 warn!(
-                    "definite error for source {}: {}, cause: {}",
-                    &task_info.source_id,
-                    e,
-                    e.source().unwrap_or(anyhow::anyhow!("unknown").as_ref())
+                    "definite error for source {}: {error}",
+                    &task_info.source_id
                 );
-                // Drop the send error, as we have no way of communicating back to the
+                // The send error is dropped, as we have no way of communicating back to the
                 // source operator if the channel is gone.
-                let _ = task_info
+                task_info
                     .row_sender
-                    .sender
                     .send(InternalMessage::Err(SourceReaderError {
-                        inner: SourceErrorDetails::Initialization(e.to_string()),
+                        inner: match e.downcast::<SourceErrorDetails>() {
+                            Ok(details) => details,
+                            Err(_) => SourceErrorDetails::Initialization(error),
+                        },
+                        output_index: 0,
                     }))
                     .await;
                 return;
             }
         }
-        // TODO(petrosagg): implement exponential back-off
-        tokio::time::sleep(Duration::from_secs(3)).await;
+        tokio::time::sleep(retry_after).await;
     }
 }
 
@@ -527,816 +1829,6094 @@ async fn postgres_replication_loop(mut task_info: PostgresTaskInfo) {
 async fn postgres_replication_loop_inner(
     task_info: &mut PostgresTaskInfo,
 ) -> Result<(), ReplicationError> {
-    if task_info.replication_lsn == PgLsn::from(0) {
-        // Get all the relevant tables for this publication
-        let publication_tables = mz_postgres_util::publication_info(
-            &task_info.connection_config,
-            &task_info.publication,
-            None,
+    if let Some(publications) = task_info.pending_publications.clone() {
+        let tables = task_info
+            .connections
+            .publication_info(&publications, &PublicationFilter::All)
+            .await
+            .err_indefinite()?;
+        validate_publication_switch(
+            &publications,
+            &task_info.publication_tables,
+            &task_info.source_tables,
+            tables,
         )
-        .await
-        .err_indefinite()?;
-
-        // Validate publication tables against the state snapshot
-        determine_table_compatibility(&task_info.source_tables, publication_tables)
-            .err_definite()?;
+        .err_definite()?;
+        info!(
+            "source {} switching from publications {:?} to {publications:?}",
+            task_info.source_id, task_info.publications
+        );
+        task_info.publications = publications;
+        task_info.pending_publications = None;
+    }
 
-        let client = task_info
-            .connection_config
-            .clone()
-            .connect_replication()
+    if task_info.needs_snapshot() {
+        // Get all the relevant tables for this publication
+        let publication_tables = task_info
+            .connections
+            .publication_info(&task_info.publications, &PublicationFilter::All)
             .await
             .err_indefinite()?;
 
-        // Technically there is TOCTOU problem here but it makes the code easier and if we end
-        // up attempting to create a slot and it already exists we will simply retry
-        // Also, we must check if the slot exists before we start a transaction because creating a
-        // slot must be the first statement in a transaction
-        let res = client
-            .simple_query(&format!(
-                r#"SELECT confirmed_flush_lsn FROM pg_replication_slots WHERE slot_name = '{}'"#,
-                task_info.slot
-            ))
-            .await?;
-        let slot_lsn = parse_single_row(&res, "confirmed_flush_lsn");
-        client
-            .simple_query("BEGIN READ ONLY ISOLATION LEVEL REPEATABLE READ;")
-            .await?;
+        // Without any tables to replicate, the frontier of the source would never advance, so we
+        // fail before creating a slot for it.
+        validate_ingests_tables(
+            &task_info.publications,
+            &publication_tables,
+            &task_info.source_tables,
+        )
+        .err_definite()?;
 
-        let (slot_lsn, snapshot_lsn, temp_slot) = match slot_lsn {
-            Ok(slot_lsn) => {
-                // The main slot already exists which means we can't use it for the snapshot. So
-                // we'll create a temporary replication slot in order to both set the transaction's
-                // snapshot to be a consistent point and also to find out the LSN that the snapshot
-                // is going to run at.
-                //
-                // When this happens we'll most likely be snapshotting at a later LSN than the slot
-                // which we will take care below by rewinding.
-                let temp_slot = uuid::Uuid::new_v4().to_string().replace('-', "");
-                let res = client
-                    .simple_query(&format!(
-                        r#"CREATE_REPLICATION_SLOT {:?} TEMPORARY LOGICAL "pgoutput" USE_SNAPSHOT"#,
-                        temp_slot
-                    ))
-                    .await?;
-                let snapshot_lsn = parse_single_row(&res, "consistent_point")?;
-                (slot_lsn, snapshot_lsn, Some(temp_slot))
-            }
-            Err(_) => {
-                let res = client
-                    .simple_query(&format!(
-                        r#"CREATE_REPLICATION_SLOT {:?} LOGICAL "pgoutput" USE_SNAPSHOT"#,
-                        task_info.slot
-                    ))
-                    .await?;
-                let slot_lsn = parse_single_row(&res, "consistent_point")?;
-                (slot_lsn, slot_lsn, None)
-            }
+        // Tables renamed upstream to their expected names are known by their new names from now
+        // on.
+        adopt_expected_names(&mut task_info.source_tables, &publication_tables);
+
+        // Unlogged tables never produce changes, so they can only be snapshotted.
+        let oids: Vec<_> = task_info.source_tables.keys().copied().collect();
+        let persistence = {
+            let client = task_info.connections.metadata().await.err_indefinite()?;
+            mz_postgres_util::table_persistence(&client, &oids)
+                .await
+                .err_indefinite()?
         };
+        let snapshot_only = snapshot_only_tables(
+            &task_info.source_tables,
+            &persistence,
+            task_info.snapshot_unlogged_tables,
+        )
+        .err_definite()?;
+        for name in &snapshot_only {
+            warn!(
+                "table {name} of source {} is unlogged, so it is only snapshotted",
+                task_info.source_id
+            );
+        }
+        task_info
+            .status_details
+            .postgres
+            .get_or_insert_with(Default::default)
+            .snapshot_only_tables = snapshot_only;
 
-        let mut stream = Box::pin(
-            produce_snapshot(&client, &task_info.metrics, &task_info.source_tables).enumerate(),
-        );
+        // Tables that the allowlist excludes produce no output, but the publication must still
+        // hold compatible versions of them.
+        check_excluded_tables(&task_info.excluded_tables, &publication_tables).err_definite()?;
 
-        while let Some((i, event)) = stream.as_mut().next().await {
-            if i > 0 {
-                // Failure scenario after we have produced at least one row, but before a
-                // successful `COMMIT`
-                fail::fail_point!("pg_snapshot_failure", |_| {
-                    Err(ReplicationError::Indefinite(anyhow::anyhow!(
-                        "recoverable errors should crash the process"
-                    )))
-                });
+        // Validate publication tables against the state snapshot
+        let mut projections =
+            determine_table_compatibility(&task_info.source_tables, publication_tables)
+                .err_definite()?;
+        for (oid, info) in task_info.source_tables.iter_mut() {
+            info.projection = projections.remove(oid);
+        }
+
+        // Lets tests alter a table after it was validated, but before it is snapshotted.
+        fail::fail_point!("pg_snapshot_before_slot_creation");
+
+        let mut progress = SnapshotProgress::default();
+        let mut reconnects = 0;
+        loop {
+            let tables: BTreeMap<u32, SourceTable> = task_info
+                .source_tables
+                .iter()
+                .filter(|(oid, _)| !progress.done.contains_key(*oid))
+                .map(|(oid, table)| (*oid, table.clone()))
+                .collect();
+            if progress.slot_lsn.is_some() && tables.is_empty() {
+                break;
             }
-            let (output, row) = match event {
-                Ok(event) => event,
-                Err(err @ ReplicationError::Definite(_)) => return Err(err),
-                Err(ReplicationError::Indefinite(err) | ReplicationError::Irrecoverable(err)) => {
-                    return Err(ReplicationError::Irrecoverable(err))
+            match snapshot_tables(task_info, &tables, &mut progress).await {
+                Ok(()) => break,
+                // Once the slot exists, a lost connection only requires copying the remaining
+                // tables again, and a partially copied table from after the last row it sent.
+                Err(ReplicationError::Indefinite(err) | ReplicationError::Irrecoverable(err))
+                    if progress.slot_lsn.is_some()
+                        && !progress.unresumable
+                        && reconnects < SNAPSHOT_RECONNECT_RETRIES
+                        && is_connection_error(&err) =>
+                {
+                    reconnects += 1;
+                    warn!(
+                        "source {} lost its snapshot connection with {} of {} tables copied, \
+                         reconnecting ({reconnects} of {SNAPSHOT_RECONNECT_RETRIES}): {err}",
+                        task_info.source_id,
+                        progress.done.len(),
+                        task_info.source_tables.len(),
+                    );
+                    tokio::time::sleep(SNAPSHOT_RECONNECT_BACKOFF).await;
                 }
-            };
-            task_info
-                .row_sender
-                .send_row(output, row, slot_lsn, 1)
-                .await;
+                // Starting over would send the rows of the copied tables again.
+                Err(ReplicationError::Indefinite(err)) if progress.sent => {
+                    return Err(ReplicationError::Irrecoverable(err));
+                }
+                Err(err) => return Err(err),
+            }
         }
 
-        if let Some(temp_slot) = temp_slot {
-            let _ = client
-                .simple_query(&format!("DROP_REPLICATION_SLOT {temp_slot:?}"))
+        let slot_lsn = progress.slot_lsn.expect("snapshot created the slot");
+        let rewind = Rewind::new(
+            task_info
+                .source_tables
+                .iter()
+                .map(|(oid, table)| {
+                    let snapshot = TableSnapshot {
+                        key: resume_key(&table.desc).unwrap_or_default(),
+                        splits: progress.partial.remove(oid).unwrap_or_default(),
+                        lsn: progress.done[oid],
+                    };
+                    (table.output_index, snapshot)
+                })
+                .filter(|(_, snapshot)| snapshot.lsn > slot_lsn)
+                .collect(),
+        );
+        if let Some(snapshot_lsn) = rewind.snapshot_lsn() {
+            async {
+                tracing::info!("postgres snapshot was at {snapshot_lsn:?} but we need it at {slot_lsn:?}. Rewinding");
+                // Our snapshot was too far ahead so we must rewind it by reading the replication
+                // stream until the snapshot lsn and emitting any rows that we find with negated diffs
+                let replication_stream = produce_replication(
+                    &task_info.connections,
+                    &task_info.slot,
+                    &task_info.publications,
+                    slot_lsn,
+                    Arc::clone(&task_info.resume_lsn),
+                    &task_info.metrics,
+                    &task_info.limits,
+                    &task_info.source_tables,
+                    task_info.replication_plugin,
+                    task_info.streaming_transactions,
+                    // Splitting a transaction could emit some of its rows at an LSN that is not
+                    // beyond the snapshot LSN even though the transaction committed after it.
+                    None,
+                    task_info.ping_interval,
+                    &mut task_info.log_dedup,
+                    &mut task_info.table_stats,
+                    &mut task_info.fast_forward_mode,
+                    task_info.wal_capture.as_ref(),
+                    None,
+                    // The frontier of the source only advances once the rewind is done, so it
+                    // must not wait for it.
+                    false,
+                    None,
+                )
                 .await;
-        }
-        client.simple_query("COMMIT;").await?;
-
-        // Drop the stream and the client, to ensure that the future `produce_replication` don't
-        // conflict with the above processing.
-        //
-        // Its possible we can avoid dropping the `client` value here, but we do it out of an
-        // abundance of caution, as rust-postgres has had curious bugs around this.
-        drop(stream);
-        drop(client);
-
-        assert!(slot_lsn <= snapshot_lsn);
-        if slot_lsn < snapshot_lsn {
-            tracing::info!("postgres snapshot was at {snapshot_lsn:?} but we need it at {slot_lsn:?}. Rewinding");
-            // Our snapshot was too far ahead so we must rewind it by reading the replication
-            // stream until the snapshot lsn and emitting any rows that we find with negated diffs
-            let replication_stream = produce_replication(
-                task_info.connection_config.clone(),
-                &task_info.slot,
-                &task_info.publication,
-                slot_lsn,
-                Arc::clone(&task_info.resume_lsn),
-                &task_info.metrics,
-                &task_info.source_tables,
-            )
-            .await;
-            tokio::pin!(replication_stream);
+                tokio::pin!(replication_stream);
 
-            while let Some(event) = replication_stream.next().await {
-                match event {
-                    Ok(Event::Message(lsn, (output, row, diff))) => {
-                        // Here we ignore the lsn that this row actually happened at and we
-                        // forcefully emit it at the slot_lsn with a negated diff.
-                        if lsn <= snapshot_lsn {
-                            task_info
-                                .row_sender
-                                .send_row(output, row, slot_lsn, -diff)
-                                .await;
+                while let Some(event) = replication_stream.next().await {
+                    match event {
+                        Ok(Event::Message(lsn, (output, row, diff, _))) => {
+                            // Here we ignore the lsn that this row actually happened at and we
+                            // forcefully emit it at the slot_lsn with a negated diff.
+                            if let Some(diff) = rewind.retraction(output, lsn, &row, diff) {
+                                task_info
+                                    .row_sender
+                                    .send_row(output, row, slot_lsn, diff, None)
+                                    .await;
+                            }
                         }
-                    }
-                    Ok(Event::Progress([lsn])) => {
-                        if lsn > snapshot_lsn {
-                            // We successfully rewinded the snapshot from snapshot_lsn to slot_lsn
-                            task_info.row_sender.close_lsn(slot_lsn).await;
-                            break;
+                        Ok(Event::Progress([lsn])) => {
+                            if rewind.is_done(lsn) {
+                                // We successfully rewinded the snapshot from snapshot_lsn to slot_lsn
+                                task_info.row_sender.close_lsn(slot_lsn).await;
+                                break;
+                            }
                         }
+                        Err(err @ ReplicationError::Definite(_)) => return Err(err),
+                        Err(
+                            ReplicationError::Indefinite(err) | ReplicationError::Irrecoverable(err),
+                        ) => return Err(ReplicationError::Irrecoverable(err)),
                     }
-                    Err(err @ ReplicationError::Definite(_)) => return Err(err),
-                    Err(
-                        ReplicationError::Indefinite(err) | ReplicationError::Irrecoverable(err),
-                    ) => return Err(ReplicationError::Irrecoverable(err)),
                 }
+                Ok(())
             }
+            .instrument(rewind_span(slot_lsn, snapshot_lsn))
+            .await?;
         }
         task_info.metrics.lsn.set(slot_lsn.into());
         task_info.row_sender.close_lsn(slot_lsn).await;
 
         info!(
-            "replication snapshot for source {} succeeded",
+            "replication snapshot for source {} succeeded at {slot_lsn}",
             &task_info.source_id
         );
+        task_info.snapshot_attempts.clear(task_info.source_id);
         task_info.replication_lsn = slot_lsn;
+        // Only reported until the source restarts, as the snapshot is not taken again.
+        task_info
+            .status_details
+            .postgres
+            .get_or_insert_with(Default::default)
+            .snapshot_lsn = Some(slot_lsn.into());
     }
 
-    let replication_stream = produce_replication(
-        task_info.connection_config.clone(),
-        &task_info.slot,
-        &task_info.publication,
-        task_info.replication_lsn,
-        Arc::clone(&task_info.resume_lsn),
+    adopt_resnapshotted_tables(task_info).await?;
+
+    // Reported right away, so that the LSNs the source's data can be reconciled with and the state
+    // of its slot are known without waiting for the first progress report.
+    info!(
+        "source {} resuming replication from {}",
+        task_info.source_id, task_info.replication_lsn
+    );
+    task_info
+        .status_details
+        .postgres
+        .get_or_insert_with(Default::default)
+        .replication_start_lsn = Some(task_info.replication_lsn.into());
+    if let Some(slot) = replication_slot_status(&task_info.connections, &task_info.slot).await {
+        task_info
+            .status_details
+            .postgres
+            .get_or_insert_with(Default::default)
+            .slot = Some(slot);
+    }
+    let mut reported_progress = report_progress(
+        &task_info.row_sender,
         &task_info.metrics,
-        &task_info.source_tables,
+        &task_info.resume_lsn,
+        task_info.replication_lsn,
+        &task_info.status_details,
     )
     .await;
-    tokio::pin!(replication_stream);
 
-    // TODO(petrosagg): The API does not guarantee that we won't see an error after we have already
-    // partially emitted a transaction, but we know it is the case due to the implementation. Find
-    // a way to encode this in the type signature
-    while let Some(event) = replication_stream.next().await.transpose()? {
-        match event {
-            Event::Message(lsn, (output, row, diff)) => {
-                task_info.row_sender.send_row(output, row, lsn, diff).await;
-            }
-            Event::Progress([lsn]) => {
-                // The lsn passed to `START_REPLICATION_SLOT` produces all transactions that
-                // committed at LSNs *strictly after*, but upper frontiers have "greater than
-                // or equal" semantics, so we must subtract one from the upper to make it
-                // compatible with what `START_REPLICATION_SLOT` expects.
-                task_info.replication_lsn = PgLsn::from(u64::from(lsn) - 1);
-                task_info.row_sender.close_lsn(lsn).await;
+    let schema_change: Option<TableSchemaChanged> = {
+        let truncate_retractions = task_info.retract_on_truncate.then(|| {
+            TruncateRetractions::new(
+                &task_info.persist_clients,
+                &task_info.outputs,
+                task_info.provenance_headers,
+            )
+        });
+        let replication_stream = produce_replication(
+            &task_info.connections,
+            &task_info.slot,
+            &task_info.publications,
+            task_info.replication_lsn,
+            Arc::clone(&task_info.resume_lsn),
+            &task_info.metrics,
+            &task_info.limits,
+            &task_info.source_tables,
+            task_info.replication_plugin,
+            task_info.streaming_transactions,
+            task_info.max_transaction_rows,
+            task_info.ping_interval,
+            &mut task_info.log_dedup,
+            &mut task_info.table_stats,
+            &mut task_info.fast_forward_mode,
+            task_info.wal_capture.as_ref(),
+            Some(task_info.row_sender.message_sender()),
+            true,
+            truncate_retractions.as_ref(),
+        )
+        .await;
+        tokio::pin!(replication_stream);
+
+        // Whether we have sent rows of a transaction whose commit we haven't seen yet. This only
+        // happens when large transactions are split.
+        let mut partially_emitted = false;
+        let mut progress_reports = tokio::time::interval_at(
+            tokio::time::Instant::now() + PROGRESS_REPORT_INTERVAL,
+            PROGRESS_REPORT_INTERVAL,
+        );
+        progress_reports.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            let event = tokio::select! {
+                event = replication_stream.next() => match event {
+                    Some(event) => event,
+                    None => break None,
+                },
+                // Pausing closes the replication connection, which must happen at a transaction
+                // boundary so that we can resume from `replication_lsn` without sending any row
+                // twice. The slot stays in place and retains the WAL since the last LSN we
+                // confirmed.
+                _ = task_info.pause.wait_for(true), if !partially_emitted => return Ok(()),
+                // Nothing is received once the source operator is gone, after which the loop
+                // stops replicating.
+                _ = task_info.row_sender.closed() => return Ok(()),
+                // Like a Relation message, an audit that finds an incompatible change fails
+                // replication, or snapshots the table anew, which requires a transaction
+                // boundary.
+                _ = task_info.schema_audit.due(), if !partially_emitted => {
+                    task_info
+                        .schema_audit
+                        .reschedule(task_info.limits.schema_audit_interval());
+                    let audit = audit_schemas(
+                        &task_info.connections,
+                        &task_info.publications,
+                        &task_info.publication_tables,
+                        &task_info.source_tables,
+                    )
+                    .await;
+                    let reported = PublicationAudit {
+                        schema_drift: task_info.status_details.schema_drift.clone(),
+                        added_tables: task_info
+                            .status_details
+                            .postgres
+                            .as_ref()
+                            .map(|postgres| postgres.added_tables.clone())
+                            .unwrap_or_default(),
+                    };
+                    match audit {
+                        Ok(Some(audit)) if audit != reported => {
+                            for (name, change) in &audit.schema_drift {
+                                warn!(
+                                    "upstream schema of table {name} of source {} changed \
+                                     compatibly: {change}",
+                                    task_info.source_id
+                                );
+                            }
+                            for name in &audit.added_tables {
+                                info!(
+                                    "table {name} was added to the publications of source {}, \
+                                     which doesn't ingest it",
+                                    task_info.source_id
+                                );
+                            }
+                            task_info.status_details.schema_drift = audit.schema_drift;
+                            task_info
+                                .status_details
+                                .postgres
+                                .get_or_insert_with(Default::default)
+                                .added_tables = audit.added_tables;
+                            reported_progress = report_progress(
+                                &task_info.row_sender,
+                                &task_info.metrics,
+                                &task_info.resume_lsn,
+                                task_info.replication_lsn,
+                                &task_info.status_details,
+                            )
+                            .await;
+                            continue;
+                        }
+                        Ok(_) => continue,
+                        Err(err) => Err(ReplicationError::Definite(err)),
+                    }
+                }
+                // Progress is reported on a timer rather than with the commits of the stream, so
+                // that a source whose tables see no changes reports how far the upstream moved
+                // on, and only if it changed, so that an idle source doesn't grow its status
+                // history.
+                _ = progress_reports.tick() => {
+                    let progress = replication_progress(
+                        &task_info.metrics,
+                        &task_info.resume_lsn,
+                        task_info.replication_lsn,
+                    );
+                    if progress != reported_progress {
+                        // A slot we can't look up keeps the state we last found it in.
+                        if let Some(slot) =
+                            replication_slot_status(&task_info.connections, &task_info.slot).await
+                        {
+                            task_info
+                                .status_details
+                                .postgres
+                                .get_or_insert_with(Default::default)
+                                .slot = Some(slot);
+                        }
+                        // The replication stream borrows parts of `task_info` for as long as it
+                        // lives, so we can only pass the parts it does not.
+                        reported_progress = report_progress(
+                            &task_info.row_sender,
+                            &task_info.metrics,
+                            &task_info.resume_lsn,
+                            task_info.replication_lsn,
+                            &task_info.status_details,
+                        )
+                        .await;
+                    }
+                    continue;
+                }
+            };
+            let event = match event {
+                Ok(event) => event,
+                // Retrying would send the rows of the partially emitted transaction a second time,
+                // so we must restart from a clean slate instead.
+                Err(ReplicationError::Indefinite(err)) if partially_emitted => {
+                    return Err(ReplicationError::Irrecoverable(err));
+                }
+                // The table can only be snapshotted anew at a transaction boundary.
+                Err(ReplicationError::Definite(err))
+                    if task_info.resnapshot_on_schema_change
+                        && !partially_emitted
+                        && err.is::<TableSchemaChanged>() =>
+                {
+                    break Some(err.downcast().expect("checked above"));
+                }
+                Err(err) => return Err(err),
+            };
+            match event {
+                Event::Message(lsn, (output, row, diff, txn)) => {
+                    partially_emitted = true;
+                    task_info
+                        .row_sender
+                        .send_row(output, row, lsn, diff, Some(txn))
+                        .await;
+                }
+                Event::Progress([lsn]) => {
+                    partially_emitted = false;
+                    task_info.replication_lsn = replication_lsn_at(lsn)?;
+                    task_info.row_sender.close_lsn(lsn).await;
+                    // Failure scenario after progress was emitted, but before the next message
+                    replication_fail_point("pg_replication_after_progress")?;
+                }
             }
         }
+    };
+
+    if let Some(TableSchemaChanged { desc, projection }) = schema_change {
+        resnapshot_table(task_info, desc, projection).await?;
     }
 
     Ok(())
 }
 
-struct RowMessage {
-    output_index: usize,
-    row: Row,
-    lsn: PgLsn,
-    diff: i64,
+/// Reports the replication progress of the source through its health status, along with the
+/// other `details` about its tables, so that they land in the source's status history. Returns
+/// the reported progress.
+async fn report_progress(
+    row_sender: &RowSender,
+    metrics: &PgSourceMetrics,
+    resume_lsn: &AtomicU64,
+    replication_lsn: PgLsn,
+    details: &SourceStatusDetails,
+) -> ReplicationProgress {
+    let progress = replication_progress(metrics, resume_lsn, replication_lsn);
+    let details = SourceStatusDetails {
+        replication_progress: Some(progress),
+        ..details.clone()
+    };
+    row_sender
+        .send(InternalMessage::Status(HealthStatusUpdate {
+            update: HealthStatus::Running,
+            should_halt: false,
+            details: Some(details),
+        }))
+        .await;
+    progress
 }
 
-/// A type that makes it easy to correctly send inserts and deletes.
-///
-/// Note: `RowSender::delete/insert` should be called with the same
-/// lsn until `close_lsn` is called, which should be called and awaited
-/// before dropping the `RowSender` or moving onto a new lsn.
-/// Internally, this type uses asserts to uphold the first requirement.
-struct RowSender {
-    sender: Sender<InternalMessage>,
-    buffered_message: Option<RowMessage>,
+/// Returns the replication progress of a source that emitted its changes up to `replication_lsn`.
+fn replication_progress(
+    metrics: &PgSourceMetrics,
+    resume_lsn: &AtomicU64,
+    replication_lsn: PgLsn,
+) -> ReplicationProgress {
+    // Before the first keepalive, all we know is that the upstream is at least as far as we are.
+    let emitted = u64::from(replication_lsn);
+    ReplicationProgress {
+        upstream_end: std::cmp::max(metrics.upstream_lsn.get(), emitted),
+        committed: resume_lsn.load(Ordering::SeqCst),
+        emitted,
+    }
 }
 
-impl RowSender {
-    /// Create a new `RowSender`.
-    pub fn new(sender: Sender<InternalMessage>) -> Self {
-        Self {
-            sender,
-            buffered_message: None,
+/// Looks up the state of the replication slot `slot` in the upstream's `pg_replication_slots`, so
+/// that it can be reported along with the source's progress.
+///
+/// This is best effort: it returns `None` if the metadata connection is in use by the replication
+/// stream, which is not polled while we wait, or if the lookup fails.
+async fn replication_slot_status(
+    connections: &UpstreamConnections,
+    slot: &str,
+) -> Option<ReplicationSlotStatus> {
+    let client = match connections.try_metadata().await? {
+        Ok(client) => client,
+        Err(err) => {
+            warn!("failed to look up the state of replication slot {slot}: {err}");
+            return None;
+        }
+    };
+    // `wal_status` only exists as of Postgres 13, so it is read through `to_jsonb` to not fail on
+    // older servers.
+    let res = client
+        .simple_query(&format!(
+            "SELECT active::text AS active, confirmed_flush_lsn, \
+                to_jsonb(s) ->> 'wal_status' AS wal_status \
+             FROM pg_replication_slots s WHERE slot_name = '{slot}'"
+        ))
+        .await;
+    let status = match res {
+        Ok(res) => at_most_one_row(rows(&res))
+            .and_then(|row| row.map(slot_status).transpose())
+            .map_err(anyhow::Error::from),
+        Err(err) => Err(err.into()),
+    };
+    match status {
+        Ok(status) => status,
+        Err(err) => {
+            warn!("failed to look up the state of replication slot {slot}: {err}");
+            None
         }
     }
+}
 
-    /// Send a triplet for the specific output
-    pub async fn send_row(&mut self, output_index: usize, row: Row, lsn: PgLsn, diff: Diff) {
-        if let Some(buffered) = self.buffered_message.take() {
-            assert_eq!(buffered.lsn, lsn);
-            self.send_row_inner(
-                buffered.output_index,
-                buffered.row,
-                buffered.lsn,
-                buffered.diff,
-                false,
-            )
-            .await;
-        }
+/// Parses the `row` of a replication slot in `pg_replication_slots`.
+fn slot_status(row: &impl ResultRow) -> Result<ReplicationSlotStatus, QueryResultError> {
+    let confirmed_flush_lsn: Option<PgLsn> = parse_nullable_column(row, "confirmed_flush_lsn")?;
+    Ok(ReplicationSlotStatus {
+        active: parse_column(row, "active")?,
+        confirmed_flush_lsn: confirmed_flush_lsn.map(u64::from),
+        wal_status: row.value("wal_status")?.map(str::to_string),
+    })
+}
 
-        self.buffered_message = Some(RowMessage {
-            output_index,
-            row,
-            lsn,
-            diff,
-        });
+/// Pauses WAL replay on the upstream server if it is a standby, so that its position stays fixed
+/// while a snapshot is copied from it. Returns the pause to resume replay with, if it was paused.
+async fn pause_wal_replay(
+    connections: &UpstreamConnections,
+) -> Result<Option<ReplayPause<MetadataClient>>, ReplicationError> {
+    let client = connections.metadata().await.err_indefinite()?;
+    let res = client
+        .simple_query("SELECT pg_is_in_recovery()::text AS in_recovery")
+        .await?;
+    let in_recovery: bool = exactly_one_row(rows(&res))
+        .and_then(|row| parse_column(row, "in_recovery"))
+        .err_indefinite()?;
+    if !in_recovery {
+        info!("upstream is not a standby, not pausing its WAL replay");
+        return Ok(None);
     }
+    let pause = ReplayPause::pause(client).await?;
+    info!("paused WAL replay upstream for the duration of the snapshot");
+    Ok(Some(pause))
+}
 
-    /// Finalize an lsn, making sure all messages that my be buffered are flushed, and that the
-    /// last message sent is marked as closing the `lsn` (which is the messages `offset` in the
-    /// rest of the source pipeline.
-    pub async fn close_lsn(&mut self, lsn: PgLsn) {
-        if let Some(buffered) = self.buffered_message.take() {
-            assert!(buffered.lsn <= lsn);
-            self.send_row_inner(
-                buffered.output_index,
-                buffered.row,
-                buffered.lsn,
-                buffered.diff,
-                true,
-            )
-            .await;
-        }
+/// Resumes WAL replay paused by [`ReplayPause`].
+async fn resume_wal_replay(client: &Client) {
+    match client.simple_query("SELECT pg_wal_replay_resume()").await {
+        Ok(_) => info!("resumed WAL replay upstream"),
+        Err(err) => warn!("failed to resume WAL replay upstream: {err}"),
     }
+}
 
-    async fn send_row_inner(&self, output: usize, row: Row, lsn: PgLsn, diff: i64, end: bool) {
-        let message = InternalMessage::Value {
-            output,
-            value: row,
-            lsn,
-            diff,
-            end,
-        };
-        // a closed receiver means the source has been shutdown (dropped or the process is dying),
-        // so just continue on without activation
-        let _ = self.sender.send(message).await;
+/// Resumes WAL replay on the server of `client` if it is a standby whose replay is paused, which
+/// a previous attempt to snapshot can have left behind if its process crashed or was shut down.
+/// A paused standby would otherwise never replay up to the LSN that a new snapshot waits for.
+/// Replay paused by anyone else is resumed too, as the two can't be told apart.
+async fn resume_leftover_replay_pause(client: &Client) -> Result<(), ReplicationError> {
+    let res = client
+        .simple_query(
+            "SELECT (CASE WHEN pg_is_in_recovery() THEN pg_is_wal_replay_paused() ELSE false END)
+                ::text AS paused",
+        )
+        .await?;
+    let paused: bool = exactly_one_row(rows(&res))
+        .and_then(|row| parse_column(row, "paused"))
+        .err_indefinite()?;
+    if paused {
+        warn!(
+            "WAL replay upstream is paused, likely by a snapshot that did not finish, resuming it"
+        );
+        resume_wal_replay(client).await;
     }
+    Ok(())
 }
 
-/// Determines if a set of [`SourceTable`]s and a set of [`PostgresTableDesc`]
-/// are compatible with one another in a way that Materialize can handle.
-///
-/// The returned `BTreeMap` represents the order in which ingested data from
-/// tables must be projected to recover the expected ordering (i.e. their
-/// schemas have changed). Tables without an entry in the returned `BTreeMap` do
-/// not require projection (i.e. their schemas have not changed).
+/// WAL replay paused on a standby for as long as this is alive.
 ///
-/// # Errors
-/// - If `source_tables` is not a strict subset of `tables`, based on the
-///   tables' OIDs.
+/// [`ReplayPause::resume`] resumes replay and hands back the client it was paused with. Dropping
+/// the pause instead, e.g. because the snapshot it belongs to was dropped, resumes replay in a
+/// task of its own. A pause that outlives the runtime of its client is found and resumed by
+/// [`resume_leftover_replay_pause`] when the source snapshots again.
+struct ReplayPause<C: Deref<Target = Client> + Send + 'static> {
+    client: Option<C>,
+}
+
+impl<C: Deref<Target = Client> + Send + 'static> ReplayPause<C> {
+    /// Pauses WAL replay on the standby of `client`.
+    async fn pause(client: C) -> Result<Self, ReplicationError> {
+        client.simple_query("SELECT pg_wal_replay_pause()").await?;
+        Ok(ReplayPause {
+            client: Some(client),
+        })
+    }
+
+    /// The client that replay was paused with.
+    fn client(&self) -> &Client {
+        self.client
+            .as_ref()
+            .expect("only taken when the pause ends")
+    }
+
+    /// Resumes WAL replay, returning the client that it was paused with.
+    async fn resume(mut self) -> C {
+        let client = self.client.take().expect("only taken when the pause ends");
+        resume_wal_replay(&client).await;
+        client
+    }
+}
+
+impl<C: Deref<Target = Client> + Send + 'static> Drop for ReplayPause<C> {
+    fn drop(&mut self) {
+        let Some(client) = self.client.take() else {
+            return;
+        };
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn_named(|| "postgres_resume_wal_replay", async move {
+                    resume_wal_replay(&client).await
+                });
+            }
+            Err(_) => warn!("WAL replay upstream stays paused until the source snapshots again"),
+        }
+    }
+}
+
+/// Returns the WAL position the standby `client` has replayed up to.
+async fn wal_replay_lsn(client: &Client) -> Result<PgLsn, ReplicationError> {
+    let res = client
+        .simple_query("SELECT pg_last_wal_replay_lsn()::text AS lsn")
+        .await?;
+    exactly_one_row(rows(&res))
+        .and_then(|row| parse_column(row, "lsn"))
+        .err_indefinite()
+}
+
+/// Begins a snapshot transaction on the standby at `config`, connecting through `connections`,
+/// once it replayed the WAL up to `min_lsn`, returning its client along with the LSN of its
+/// snapshot.
+///
+/// A standby cannot import a snapshot exported by another server, so the snapshot is instead
+/// taken while WAL replay is paused, which makes it include exactly the transactions that
+/// committed up to the position replay is paused at. Replay is resumed as soon as the snapshot
+/// is taken. Determining whether replay is paused requires Postgres 14+.
+async fn begin_standby_snapshot(
+    connections: &UpstreamConnections,
+    config: &mz_postgres_util::Config,
+    statement_timeout: Option<Duration>,
+    min_lsn: PgLsn,
+) -> Result<(Client, PgLsn), ReplicationError> {
+    let client = connections
+        .snapshot_standby(config)
+        .await
+        .err_indefinite()?;
+    mz_postgres_util::set_statement_timeout(&client, statement_timeout)
+        .await
+        .err_indefinite()?;
+    let res = client
+        .simple_query("SELECT pg_is_in_recovery()::text AS in_recovery")
+        .await?;
+    let in_recovery: bool = exactly_one_row(rows(&res))
+        .and_then(|row| parse_column(row, "in_recovery"))
+        .err_indefinite()?;
+    if !in_recovery {
+        return Err(ReplicationError::Indefinite(anyhow!(
+            "the snapshot standby is not in recovery, it may have been promoted"
+        )));
+    }
+    resume_leftover_replay_pause(&client).await?;
+
+    let deadline = Instant::now() + STANDBY_REPLAY_TIMEOUT;
+    let mut replay_lsn = wal_replay_lsn(&client).await?;
+    if replay_lsn < min_lsn {
+        info!("waiting for the snapshot standby to replay up to {min_lsn}, it is at {replay_lsn}");
+    }
+    while replay_lsn < min_lsn {
+        if Instant::now() >= deadline {
+            return Err(ReplicationError::Indefinite(anyhow!(
+                "the snapshot standby did not replay up to {min_lsn} within \
+                 {STANDBY_REPLAY_TIMEOUT:?}, it is at {replay_lsn}"
+            )));
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        replay_lsn = wal_replay_lsn(&client).await?;
+    }
+
+    // Boxed, as the pause takes any pointer to a client.
+    let pause = ReplayPause::pause(Box::new(client)).await?;
+    let snapshot = async {
+        let client = pause.client();
+        // Replay pauses once the record it is applying was applied.
+        loop {
+            let res = client
+                .simple_query("SELECT pg_get_wal_replay_pause_state() AS state")
+                .await?;
+            let state: String = exactly_one_row(rows(&res))
+                .and_then(|row| parse_column(row, "state"))
+                .err_indefinite()?;
+            if state == "paused" {
+                break;
+            }
+            if Instant::now() >= deadline {
+                return Err(ReplicationError::Indefinite(anyhow!(
+                    "WAL replay on the snapshot standby did not pause within \
+                     {STANDBY_REPLAY_TIMEOUT:?}"
+                )));
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        let snapshot_lsn = wal_replay_lsn(client).await?;
+        client
+            .simple_query("BEGIN READ ONLY ISOLATION LEVEL REPEATABLE READ;")
+            .await?;
+        // The snapshot of the transaction is taken by its first query.
+        client.simple_query("SELECT 1").await?;
+        Ok::<_, ReplicationError>(snapshot_lsn)
+    }
+    .await;
+    // Replay must resume even if taking the snapshot failed, as it would stay paused otherwise.
+    let client = pause.resume().await;
+    let snapshot_lsn = snapshot?;
+    Ok((*client, snapshot_lsn))
+}
+
+/// Snapshots the table described by `desc` anew after its upstream schema changed, replacing the
+/// rows ingested from it so far.
+///
+/// All rows of the table's output are retracted and the snapshot is emitted at the lowest LSN we
+/// can still send rows at, which lies right after the last transaction we replicated. Like the
+/// initial snapshot, the snapshot is taken at a later LSN and rewound to `replication_lsn`, which
+/// is where replication resumes from afterwards.
+async fn resnapshot_table(
+    task_info: &mut PostgresTaskInfo,
+    desc: PostgresTableDesc,
+    projection: Option<Vec<usize>>,
+) -> Result<(), ReplicationError> {
+    let oid = desc.oid;
+    let output_index = task_info.source_tables[&oid].output_index;
+    let (output_id, metadata) = &task_info.outputs[&output_index];
+    info!(
+        "source table {} with oid {oid} of source {} has been altered, snapshotting it anew",
+        desc.name, task_info.source_id
+    );
+
+    // The output is only read once everything sent so far has been persisted, so that it yields
+    // every row we need to retract.
+    let lsn = task_info.row_sender.lower();
+    let emitted_lsn = LsnFrontier::new(lsn).to_start_replication_arg();
+    while task_info.resume_lsn.load(Ordering::SeqCst) < u64::from(emitted_lsn) {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+    let mut retractions =
+        schema_change::read_collection(&task_info.persist_clients, *output_id, metadata)
+            .await
+            .err_indefinite()?;
+    if task_info.provenance_headers {
+        retractions = strip_provenance(retractions);
+    }
+
+    let client = task_info.connections.replication().await.err_indefinite()?;
+    mz_postgres_util::set_statement_timeout(&client, task_info.snapshot_statement_timeout)
+        .await
+        .err_indefinite()?;
+    client
+        .simple_query("BEGIN READ ONLY ISOLATION LEVEL REPEATABLE READ;")
+        .await?;
+    let temp_slot = uuid::Uuid::new_v4().to_string().replace('-', "");
+    let res = client
+        .simple_query(&format!(
+            r#"CREATE_REPLICATION_SLOT {:?} TEMPORARY LOGICAL "pgoutput" USE_SNAPSHOT"#,
+            temp_slot
+        ))
+        .await
+        .err_during(Operation::SlotCreation)?;
+    let snapshot_lsn: PgLsn = exactly_one_row(rows(&res))
+        .and_then(|row| parse_column(row, "consistent_point"))
+        .err_indefinite()?;
+
+    // Retrying after sending any row would send it a second time, so from here on we must restart
+    // from a clean slate instead.
+    let irrecoverable = |err: ReplicationError| match err {
+        err @ ReplicationError::Definite(_) => err,
+        ReplicationError::Indefinite(err) | ReplicationError::Irrecoverable(err) => {
+            ReplicationError::Irrecoverable(err)
+        }
+    };
+    let soft_delete = task_info.source_tables[&oid].soft_delete;
+    for (row, diff) in retractions {
+        // Deleted rows are not snapshotted anew, so they are kept as they are.
+        if soft_delete && matches!(&row, Ok(row) if is_soft_deleted(row)) {
+            continue;
+        }
+        task_info
+            .row_sender
+            .send_row(output_index, row, lsn, -diff, None)
+            .await;
+    }
+    let source_table = task_info.source_tables.get_mut(&oid).expect("known table");
+    source_table.default_datums = column_defaults(&desc);
+    source_table.desc = desc;
+    source_table.projection = projection;
+    let table = source_table.clone();
+
+    let mut stream = Box::pin(produce_snapshot(
+        &client,
+        task_info.source_id,
+        &task_info.metrics,
+        &task_info.limits,
+        vec![(&table, None)],
+        task_info.snapshot_decoder,
+        task_info.snapshot_cursor_fetch_size,
+        task_info.copy_read_timeout,
+        &AtomicUsize::new(0),
+    ));
+    while let Some(event) = stream.as_mut().next().await {
+        let (output, row) = event.map_err(irrecoverable)?;
+        task_info
+            .row_sender
+            .send_row(output, row, lsn, 1, None)
+            .await;
+    }
+    drop(stream);
+    let _ = client
+        .simple_query(&format!("DROP_REPLICATION_SLOT {temp_slot:?}"))
+        .await;
+    client.simple_query("COMMIT;").await.err_irrecoverable()?;
+    drop(client);
+
+    // Our snapshot is ahead of the replication stream, so we rewind it by emitting the changes
+    // to the table up to the snapshot LSN with negated diffs.
+    let replication_lsn = task_info.replication_lsn;
+    if replication_lsn < snapshot_lsn {
+        let rewind = Rewind::new(BTreeMap::from([(
+            output_index,
+            TableSnapshot::at(snapshot_lsn),
+        )]));
+        // Only the changes to the resnapshotted table are rewound.
+        let rewind_tables = BTreeMap::from([(oid, table.clone())]);
+        async {
+            let replication_stream = produce_replication(
+                &task_info.connections,
+                &task_info.slot,
+                &task_info.publications,
+                replication_lsn,
+                Arc::clone(&task_info.resume_lsn),
+                &task_info.metrics,
+                &task_info.limits,
+                &rewind_tables,
+                task_info.replication_plugin,
+                task_info.streaming_transactions,
+                None,
+                task_info.ping_interval,
+                &mut task_info.log_dedup,
+                &mut task_info.table_stats,
+                &mut task_info.fast_forward_mode,
+                task_info.wal_capture.as_ref(),
+                None,
+                // Like the rewind of the initial snapshot, this must not wait for the frontier.
+                false,
+                None,
+            )
+            .await;
+            tokio::pin!(replication_stream);
+            while let Some(event) = replication_stream.next().await {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(err) => return Err(irrecoverable(err)),
+                };
+                match event {
+                    Event::Message(change_lsn, (output, row, diff, _)) => {
+                        if let Some(diff) = rewind.retraction(output, change_lsn, &row, diff) {
+                            task_info
+                                .row_sender
+                                .send_row(output, row, lsn, diff, None)
+                                .await;
+                        }
+                    }
+                    Event::Progress([progress_lsn]) => {
+                        if rewind.is_done(progress_lsn) {
+                            break;
+                        }
+                    }
+                }
+            }
+            Ok(())
+        }
+        .instrument(rewind_span(replication_lsn, snapshot_lsn))
+        .await?;
+    }
+
+    // Split transactions can emit rows at the LSN right after the last transaction we replicated,
+    // in which case the LSN must be left open for them.
+    if task_info.max_transaction_rows.is_none() {
+        task_info.row_sender.close_lsn(lsn).await;
+    }
+    info!(
+        "source table {} with oid {oid} of source {} snapshotted anew",
+        task_info.source_tables[&oid].desc.name, task_info.source_id
+    );
+    // Reported along with the progress of the source, so that it does not snapshot the table anew
+    // again when it restarts.
+    let resnapshotted =
+        schema_change::resnapshotted_table(&task_info.source_tables[&oid].desc, u64::from(lsn));
+    task_info
+        .status_details
+        .postgres
+        .get_or_insert_with(Default::default)
+        .resnapshotted_tables
+        .insert(oid, resnapshotted);
+    Ok(())
+}
+
+/// Ingests the tables that were snapshotted anew before the source restarted with the upstream
+/// columns they were snapshotted with, as recorded in the status details of the source.
+///
+/// A table is only adopted if its current upstream schema still yields the same columns, and if
+/// the source resumed after the LSN the new snapshot was emitted at, so that the snapshot was
+/// persisted. Otherwise the record is dropped, and the table is snapshotted anew again once
+/// replication finds it changed.
+async fn adopt_resnapshotted_tables(
+    task_info: &mut PostgresTaskInfo,
+) -> Result<(), ReplicationError> {
+    let Some(postgres) = task_info.status_details.postgres.as_mut() else {
+        return Ok(());
+    };
+    let pending: Vec<_> = postgres
+        .resnapshotted_tables
+        .iter()
+        .filter(|(oid, table)| match task_info.source_tables.get(oid) {
+            Some(info) => schema_change::resnapshotted_table(&info.desc, table.lsn) != **table,
+            None => false,
+        })
+        .map(|(oid, table)| (*oid, table.clone()))
+        .collect();
+    if pending.is_empty() {
+        return Ok(());
+    }
+
+    let tables = task_info
+        .connections
+        .publication_info(&task_info.publications, &PublicationFilter::All)
+        .await
+        .err_indefinite()?;
+    let resume_lsn = u64::from(task_info.replication_lsn);
+    for (oid, table) in pending {
+        let info = task_info
+            .source_tables
+            .get_mut(&oid)
+            .expect("pending table");
+        let adopted = tables
+            .iter()
+            .find(|desc| desc.oid == oid)
+            .and_then(|upstream| {
+                let desc = schema_change::resnapshot_desc(&info.desc, &info.casts, upstream)?;
+                let snapshotted = schema_change::resnapshotted_table(&desc, table.lsn);
+                (resume_lsn > table.lsn && snapshotted == table).then(|| (desc, upstream))
+            });
+        match adopted {
+            Some((desc, upstream)) => {
+                info!(
+                    "source table {} with oid {oid} of source {} was snapshotted anew before the \
+                     source restarted, ingesting it with its new columns",
+                    info.desc.name, task_info.source_id
+                );
+                info.default_datums = column_defaults(&desc);
+                info.projection = column_projection(&desc, upstream);
+                info.desc = desc;
+            }
+            None => {
+                postgres.resnapshotted_tables.remove(&oid);
+            }
+        }
+    }
+    Ok(())
+}
+
+struct RowMessage {
+    output_index: usize,
+    row: TableRow,
+    lsn: PgLsn,
+    diff: i64,
+    txn: Option<TransactionInfo>,
+}
+
+/// Sends messages to the source operator, recording the channel occupancy and the time spent
+/// waiting for room in the channel.
+#[derive(Clone)]
+struct MessageSender {
+    sender: Sender<InternalMessage>,
+    metrics: Arc<PgSourceMetrics>,
+}
+
+impl MessageSender {
+    /// Sends a message to the source operator. Returns `false`, discarding the message, if the
+    /// channel is closed.
+    pub async fn send(&self, message: InternalMessage) -> bool {
+        // The queued messages gauge is incremented before sending so that the receiving end never
+        // observes a message it has not been accounted for.
+        self.metrics.channel_queued_messages.inc();
+        let start = Instant::now();
+        // a closed receiver means the source has been shutdown (dropped or the process is dying)
+        let sent = match self.sender.send(message).await {
+            Ok(()) => {
+                self.metrics.channel_messages.inc();
+                true
+            }
+            Err(_) => {
+                self.metrics.channel_queued_messages.dec();
+                false
+            }
+        };
+        self.metrics
+            .channel_send_blocked_seconds
+            .inc_by(start.elapsed().as_secs_f64());
+        sent
+    }
+
+    /// Whether the channel is closed, i.e. the source operator dropped its receiver.
+    pub fn is_closed(&self) -> bool {
+        self.sender.is_closed()
+    }
+
+    /// Waits until the channel is closed.
+    pub async fn closed(&self) {
+        self.sender.closed().await
+    }
+}
+
+/// A type that makes it easy to correctly send inserts and deletes.
+///
+/// Note: `RowSender::delete/insert` should be called with the same
+/// lsn until `close_lsn` is called, which should be called and awaited
+/// before dropping the `RowSender` or moving onto a new lsn. The only
+/// exception are the rows of a split transaction, which are sent at an lsn
+/// that is left open before moving onto the commit lsn of the transaction.
+/// Internally, this type uses asserts to uphold the first requirement.
+struct RowSender {
+    messages: MessageSender,
+    buffered_message: Option<RowMessage>,
+    /// The lowest lsn rows can still be sent at
+    lower: PgLsn,
+}
+
+impl RowSender {
+    /// Create a new `RowSender` that starts sending rows at `lower`.
+    pub fn new(
+        sender: Sender<InternalMessage>,
+        metrics: Arc<PgSourceMetrics>,
+        lower: PgLsn,
+    ) -> Self {
+        Self {
+            messages: MessageSender { sender, metrics },
+            buffered_message: None,
+            lower,
+        }
+    }
+
+    /// The lowest lsn rows can still be sent at. All rows sent at lower lsns have been closed.
+    pub fn lower(&self) -> PgLsn {
+        self.lower
+    }
+
+    /// Returns a sender of the messages that are not rows, e.g. health status updates, which can
+    /// be used while rows are being sent.
+    pub fn message_sender(&self) -> MessageSender {
+        self.messages.clone()
+    }
+
+    /// Sends a message to the source operator, see [`MessageSender::send`].
+    pub async fn send(&self, message: InternalMessage) -> bool {
+        self.messages.send(message).await
+    }
+
+    /// Whether the channel is closed, see [`MessageSender::is_closed`].
+    pub fn is_closed(&self) -> bool {
+        self.messages.is_closed()
+    }
+
+    /// Waits until the channel is closed, see [`MessageSender::closed`].
+    pub async fn closed(&self) {
+        self.messages.closed().await
+    }
+
+    /// Send a triplet for the specific output, along with the upstream transaction it belongs to
+    /// if known
+    pub async fn send_row(
+        &mut self,
+        output_index: usize,
+        row: TableRow,
+        lsn: PgLsn,
+        diff: Diff,
+        txn: Option<TransactionInfo>,
+    ) {
+        if let Some(buffered) = self.buffered_message.take() {
+            assert!(buffered.lsn <= lsn);
+            self.send_row_inner(buffered, false).await;
+        }
+
+        self.lower = lsn;
+        self.buffered_message = Some(RowMessage {
+            output_index,
+            row,
+            lsn,
+            diff,
+            txn,
+        });
+    }
+
+    /// Finalize an lsn, making sure all messages that my be buffered are flushed, and that the
+    /// last message sent is marked as closing the `lsn` (which is the messages `offset` in the
+    /// rest of the source pipeline.
+    pub async fn close_lsn(&mut self, lsn: PgLsn) {
+        if let Some(buffered) = self.buffered_message.take() {
+            assert!(buffered.lsn <= lsn);
+            self.lower = CommitLsn::new(buffered.lsn).to_frontier().into();
+            self.send_row_inner(buffered, true).await;
+        }
+    }
+
+    async fn send_row_inner(&self, message: RowMessage, end: bool) {
+        let output_index = message.output_index;
+        let message = InternalMessage::Value {
+            message: match message.row {
+                Ok(row) => Ok(SourceMessage {
+                    output_index,
+                    upstream_time_millis: message.txn.and_then(|txn| txn.commit_time_millis),
+                    key: Row::default(),
+                    value: row,
+                    headers: None,
+                }),
+                Err(inner) => Err(SourceReaderError {
+                    inner,
+                    output_index,
+                }),
+            },
+            lsn: message.lsn,
+            diff: message.diff,
+            end,
+            xid: message.txn.map(|txn| txn.xid),
+        };
+        self.send(message).await;
+    }
+}
+
+/// Returns the headers that trace a message back to the upstream change it originates from: the
+/// OID of its table, the id of its transaction and the LSN it was emitted at. Snapshotted rows
+/// belong to no transaction, and are emitted at the LSN of the replication slot.
+fn provenance_headers(
+    table_oid: u32,
+    xid: Option<u32>,
+    lsn: PgLsn,
+) -> Vec<(String, Option<Vec<u8>>)> {
+    let header = |name: &str, value: String| (name.to_string(), Some(value.into_bytes()));
+    let mut headers = vec![header("table_oid", table_oid.to_string())];
+    if let Some(xid) = xid {
+        headers.push(header("xid", xid.to_string()));
+    }
+    headers.push(header("lsn", lsn.to_string()));
+    headers
+}
+
+/// Determines if a set of [`SourceTable`]s and a set of [`PostgresTableDesc`]
+/// are compatible with one another in a way that Materialize can handle.
+///
+/// The returned `BTreeMap` represents the order in which ingested data from
+/// tables must be projected to recover the expected ordering (i.e. their
+/// columns are no longer the leading ones upstream), by OID. Tables without an
+/// entry in the returned `BTreeMap` do not require projection.
+///
+/// # Errors
+/// - If `source_tables` is not a strict subset of `tables`, based on the
+///   tables' OIDs.
 /// - If any object in `tables` is incompatible with its representation in
 ///   `source_tables`, e.g. no longer contains all of the columns identified in
 ///   `source_tables`.
 fn determine_table_compatibility(
     source_tables: &BTreeMap<u32, SourceTable>,
     tables: Vec<PostgresTableDesc>,
-) -> Result<(), anyhow::Error> {
+) -> Result<BTreeMap<u32, Vec<usize>>, anyhow::Error> {
     let pub_tables: BTreeMap<u32, PostgresTableDesc> =
         tables.into_iter().map(|t| (t.oid, t)).collect();
 
-    for (id, info) in source_tables.iter() {
-        match pub_tables.get(id) {
-            Some(pub_schema) => {
-                // Keep this method in sync with the check in response to
-                // Relation messages in the replication stream.
-                info.desc.determine_compatibility(pub_schema)?;
-            }
-            None => {
-                warn!(
-                    "publication missing table: {} with id {}",
-                    info.desc.name, id
-                );
-                bail!(
-                    "source table {} with oid {} has been dropped",
-                    info.desc.name,
-                    info.desc.oid
-                )
+    let mut projections = BTreeMap::new();
+    for (id, info) in source_tables.iter() {
+        match pub_tables.get(id) {
+            Some(pub_schema) => {
+                // Keep this method in sync with the check in response to
+                // Relation messages in the replication stream.
+                let renamed = renamed_desc(info, pub_schema);
+                let desc = renamed.as_ref().unwrap_or(&info.desc);
+                desc.determine_compatibility(pub_schema)?;
+                if let Some(projection) = column_projection(desc, pub_schema) {
+                    projections.insert(*id, projection);
+                }
+            }
+            None => {
+                warn!(
+                    "publication missing table: {} with id {}",
+                    info.desc.name, id
+                );
+                return Err(SourceErrorDetails::TableDropped {
+                    table_oid: info.desc.oid,
+                    table_name: info.desc.name.clone(),
+                }
+                .into());
+            }
+        }
+    }
+
+    Ok(projections)
+}
+
+/// Returns the description of the table `info` under its new name, if upstream renamed it to one
+/// of the names it is expected to be renamed to, as found in its `upstream_desc`.
+fn renamed_desc(
+    info: &SourceTable,
+    upstream_desc: &PostgresTableDesc,
+) -> Option<PostgresTableDesc> {
+    let renamed =
+        info.desc.name != upstream_desc.name && info.expected_names.contains(&upstream_desc.name);
+    renamed.then(|| PostgresTableDesc {
+        name: upstream_desc.name.clone(),
+        ..info.desc.clone()
+    })
+}
+
+/// Renames the `source_tables` that upstream renamed to their expected names, as found among the
+/// publication `tables`.
+fn adopt_expected_names(
+    source_tables: &mut BTreeMap<u32, SourceTable>,
+    tables: &[PostgresTableDesc],
+) {
+    for table in tables {
+        let Some(info) = source_tables.get_mut(&table.oid) else {
+            continue;
+        };
+        if let Some(desc) = renamed_desc(info, table) {
+            info!(
+                "source table {} with oid {} was renamed to {}",
+                info.desc.name, info.desc.oid, desc.name
+            );
+            info.desc = desc;
+        }
+    }
+}
+
+/// Returns the positions of the columns of `desc` among the columns of the compatible upstream
+/// description `upstream_desc`, or none if they are its leading columns in order.
+///
+/// The columns of a table only move once one of them is dropped and added again upstream, which
+/// the table can only be snapshotted anew after, see [`schema_change::resnapshot_desc`].
+fn column_projection(
+    desc: &PostgresTableDesc,
+    upstream_desc: &PostgresTableDesc,
+) -> Option<Vec<usize>> {
+    let positions = desc
+        .column_positions(upstream_desc)
+        .expect("columns of compatible tables are present upstream");
+    if positions
+        .iter()
+        .enumerate()
+        .all(|(i, position)| i == *position)
+    {
+        None
+    } else {
+        Some(positions)
+    }
+}
+
+/// Checks that the columns of the `source_tables` are still where their projections expect them
+/// upstream, given their current `projections`, as determined by
+/// [`determine_table_compatibility`].
+fn check_projections(
+    source_tables: &BTreeMap<u32, SourceTable>,
+    projections: &BTreeMap<u32, Vec<usize>>,
+) -> Result<(), anyhow::Error> {
+    for (oid, info) in source_tables {
+        if info.projection.as_ref() != projections.get(oid) {
+            bail!(
+                "source table {} with oid {} has been altered",
+                info.desc.name,
+                info.desc.oid
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the source ingests at least one of the `tables` of its `publications`.
+///
+/// Publications are validated to contain tables when a source is planned, but their tables may be
+/// removed before the source first starts, and the source may have been left with none of them.
+fn validate_ingests_tables(
+    publications: &[String],
+    tables: &[PostgresTableDesc],
+    source_tables: &BTreeMap<u32, SourceTable>,
+) -> Result<(), anyhow::Error> {
+    let contain = match publications {
+        [_] => "contains",
+        _ => "contain",
+    };
+    if tables.is_empty() {
+        bail!(
+            "{} {contain} no tables",
+            describe_publications(publications)
+        );
+    }
+    if source_tables.is_empty() {
+        bail!(
+            "{} {contain} no ingestable tables",
+            describe_publications(publications)
+        );
+    }
+    Ok(())
+}
+
+/// Describes `publications` in messages, e.g. `publications "a", "b"`.
+fn describe_publications(publications: &[String]) -> String {
+    match publications {
+        [publication] => format!("publication {publication:?}"),
+        publications => {
+            let names: Vec<_> = publications
+                .iter()
+                .map(|name| format!("{name:?}"))
+                .collect();
+            format!("publications {}", names.join(", "))
+        }
+    }
+}
+
+/// Returns the value of the `publication_names` option of `pgoutput` for `publications`, for use
+/// inside a quoted string literal.
+///
+/// `pgoutput` splits the value at commas into identifiers, which it folds to lower case unless they
+/// are quoted, so every name is quoted to be matched exactly.
+fn publication_names_option(publications: &[String]) -> String {
+    publications
+        .iter()
+        .map(|name| format!("\"{}\"", name.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(",")
+        .replace('\'', "''")
+}
+
+/// Checks that the `source_tables` can be ingested given the `persistence` of each upstream,
+/// returning the names of the unlogged ones, which are only snapshotted as their changes are not
+/// replicated.
+///
+/// Temporary tables can never be ingested, and unlogged ones only if `snapshot_unlogged_tables`.
+fn snapshot_only_tables(
+    source_tables: &BTreeMap<u32, SourceTable>,
+    persistence: &BTreeMap<u32, TablePersistence>,
+    snapshot_unlogged_tables: bool,
+) -> Result<Vec<String>, anyhow::Error> {
+    let mut snapshot_only = vec![];
+    for (oid, info) in source_tables {
+        let table = format!("{}.{}", info.desc.namespace, info.desc.name);
+        match persistence.get(oid) {
+            Some(TablePersistence::Temporary) => {
+                bail!("{}", ValidationIssue::TemporaryTable { table })
+            }
+            Some(TablePersistence::Unlogged) if !snapshot_unlogged_tables => {
+                bail!("{}", ValidationIssue::UnloggedTable { table })
+            }
+            Some(TablePersistence::Unlogged) => snapshot_only.push(table),
+            // Tables that no longer exist are reported by the compatibility checks.
+            Some(TablePersistence::Permanent) | None => {}
+        }
+    }
+    Ok(snapshot_only)
+}
+
+/// Determines which of the publication `tables` not ingested by the source could not be ingested
+/// at all, because they have columns of types Materialize cannot represent, so that a request to
+/// ingest them can be answered with the reason instead of a failing cast.
+///
+/// Returns the reason for each such table, by its qualified name. The tables in `source_tables`
+/// were validated when the source was planned, and may ingest such columns as text.
+fn non_ingestable_tables(
+    tables: &[PostgresTableDesc],
+    source_tables: &BTreeMap<u32, SourceTable>,
+) -> BTreeMap<String, String> {
+    let mut non_ingestable = BTreeMap::new();
+    for table in tables {
+        if source_tables.contains_key(&table.oid) {
+            continue;
+        }
+        let unsupported: Vec<_> = table
+            .columns
+            .iter()
+            .filter(|c| mz_pgrepr::Type::from_oid_and_typmod(c.type_oid, c.type_mod).is_err())
+            .map(|c| {
+                format!(
+                    "column {} has unsupported type with OID {}",
+                    c.name, c.type_oid
+                )
+            })
+            .collect();
+        if !unsupported.is_empty() {
+            let name = format!("{}.{}", table.namespace, table.name);
+            non_ingestable.insert(name, unsupported.join(", "));
+        }
+    }
+    non_ingestable
+}
+
+/// What an audit of the publications of a source found, see [`audit_schemas`].
+#[derive(Debug, PartialEq, Eq)]
+struct PublicationAudit {
+    /// The compatible changes found by [`schema_drift`]
+    schema_drift: BTreeMap<String, String>,
+    /// The tables found by [`added_tables`]
+    added_tables: Vec<String>,
+}
+
+/// Audits the upstream schemas of the `source_tables` on the metadata connection, so that a change
+/// to a table that is rarely written to doesn't go unnoticed until its next Relation message. The
+/// tables of the `publications` are compared against the `publication_tables` the source was
+/// created from, as the replication stream doesn't tell us about tables that were removed from
+/// or added to the publications.
+///
+/// Returns what the audit found, or none if the publication could not be queried, which doesn't
+/// affect ingestion and is retried at the next audit.
+///
+/// # Errors
+/// - If an ingested table was removed from the publications, see [`check_published`].
+/// - If an ingested table was dropped or changed incompatibly, see [`schema_drift`].
+async fn audit_schemas(
+    connections: &UpstreamConnections,
+    publications: &[String],
+    publication_tables: &[PostgresTableDesc],
+    source_tables: &BTreeMap<u32, SourceTable>,
+) -> Result<Option<PublicationAudit>, anyhow::Error> {
+    // The replication stream holds the metadata connection while it looks up a table, and is
+    // not polled while the audit runs, so the audit is skipped rather than waiting for it.
+    let Some(client) = connections.try_metadata().await else {
+        info!(
+            "metadata connection is in use, skipping the audit of {}",
+            describe_publications(publications)
+        );
+        return Ok(None);
+    };
+    let info = match client {
+        Ok(mut client) => match client
+            .publication_info(publications, &PublicationFilter::All)
+            .await
+        {
+            // The ingested tables that are no longer published either were dropped, or still
+            // exist and were removed from the publications.
+            Ok(tables) => {
+                let missing: Vec<_> = source_tables
+                    .keys()
+                    .filter(|oid| !tables.iter().any(|table| table.oid == **oid))
+                    .copied()
+                    .collect();
+                mz_postgres_util::table_persistence(&client, &missing)
+                    .await
+                    .map(|existing| (tables, existing))
+            }
+            Err(err) => Err(err),
+        },
+        Err(err) => Err(err),
+    };
+    match info {
+        Ok((tables, existing)) => {
+            let unpublished: Vec<_> = existing.into_keys().collect();
+            check_published(publications, source_tables, &unpublished)?;
+            Ok(Some(PublicationAudit {
+                added_tables: added_tables(publication_tables, &tables),
+                schema_drift: schema_drift(source_tables, tables)?,
+            }))
+        }
+        Err(err) => {
+            warn!(
+                "failed to audit the upstream schemas of {}: {err}",
+                describe_publications(publications)
+            );
+            Ok(None)
+        }
+    }
+}
+
+/// Checks that none of the `source_tables` was removed from the `publications`, given the OIDs
+/// of the ones that are `unpublished` while they still exist upstream.
+///
+/// Upstream stops replicating the changes to a table once it is removed from the publication,
+/// without telling us, so the source would silently stop ingesting them.
+///
+/// # Errors
+/// - If any table is unpublished, which can only be recovered from by creating the source anew.
+fn check_published(
+    publications: &[String],
+    source_tables: &BTreeMap<u32, SourceTable>,
+    unpublished: &[u32],
+) -> Result<(), anyhow::Error> {
+    let Some(info) = unpublished.iter().find_map(|oid| source_tables.get(oid)) else {
+        return Ok(());
+    };
+    let name = format!("{}.{}", info.desc.namespace, info.desc.name);
+    Err(SourceErrorDetails::UpstreamMisconfigured {
+        error: format!(
+            "table {name} was removed from {}",
+            describe_publications(publications)
+        ),
+        hint: format!(
+            "The changes to {name} are no longer replicated. Add the table back to the \
+             publication with ALTER PUBLICATION ... ADD TABLE {name}; upstream and re-create the \
+             source to ingest it anew, or re-create the source without it."
+        ),
+    }
+    .into())
+}
+
+/// Returns the names of the current publication `tables` that are not among the
+/// `publication_tables` the source was created from, i.e. that were added to the publications
+/// since. The source doesn't ingest them, and ignores their changes.
+fn added_tables(
+    publication_tables: &[PostgresTableDesc],
+    tables: &[PostgresTableDesc],
+) -> Vec<String> {
+    let known: BTreeSet<u32> = publication_tables.iter().map(|table| table.oid).collect();
+    tables
+        .iter()
+        .filter(|table| !known.contains(&table.oid))
+        .map(|table| format!("{}.{}", table.namespace, table.name))
+        .collect()
+}
+
+/// Compares the current upstream `tables` against the descriptions the `source_tables` are
+/// ingested with.
+///
+/// Returns the tables whose upstream schema changed in a way the source can keep ingesting, with a
+/// description of the changes, by qualified name.
+///
+/// # Errors
+/// - If a table was dropped or changed incompatibly, like [`determine_table_compatibility`]. If
+///   the table can be snapshotted anew instead, the error is a [`TableSchemaChanged`].
+fn schema_drift(
+    source_tables: &BTreeMap<u32, SourceTable>,
+    tables: Vec<PostgresTableDesc>,
+) -> Result<BTreeMap<String, String>, anyhow::Error> {
+    let pub_tables: BTreeMap<u32, PostgresTableDesc> =
+        tables.into_iter().map(|t| (t.oid, t)).collect();
+
+    let mut drift = BTreeMap::new();
+    for (id, info) in source_tables.iter() {
+        let Some(desc) = pub_tables.get(id) else {
+            return Err(SourceErrorDetails::TableDropped {
+                table_oid: info.desc.oid,
+                table_name: info.desc.name.clone(),
+            }
+            .into());
+        };
+        check_table_compatibility(info, desc)?;
+        if info.desc != *desc {
+            let name = format!("{}.{}", desc.namespace, desc.name);
+            drift.insert(name, describe_drift(&info.desc, desc));
+        }
+    }
+    Ok(drift)
+}
+
+/// Checks that the upstream description `desc` of the table described by `info` is compatible
+/// with the one it is ingested with, and that its columns are still where its projection expects
+/// them.
+///
+/// # Errors
+/// - If the table changed incompatibly, as determined by
+///   [`PostgresTableDesc::determine_compatibility`]. If the table can be snapshotted anew instead,
+///   the error is a [`TableSchemaChanged`].
+fn check_table_compatibility(
+    info: &SourceTable,
+    desc: &PostgresTableDesc,
+) -> Result<(), anyhow::Error> {
+    let renamed = renamed_desc(info, desc);
+    let expected_desc = renamed.as_ref().unwrap_or(&info.desc);
+    let err = match expected_desc.determine_compatibility(desc) {
+        Ok(_) if column_projection(expected_desc, desc) == info.projection => return Ok(()),
+        Ok(_) => anyhow!(
+            "source table {} with oid {} has been altered",
+            info.desc.name,
+            info.desc.oid
+        ),
+        Err(err) => err,
+    };
+    match schema_change::resnapshot_desc(expected_desc, &info.casts, desc) {
+        Some(new_desc) => {
+            let projection = column_projection(&new_desc, desc);
+            Err(TableSchemaChanged {
+                desc: new_desc,
+                projection,
+            }
+            .into())
+        }
+        None => Err(err),
+    }
+}
+
+/// Describes how the upstream schema `new_desc` of a table differs from the compatible `desc` it
+/// is ingested with.
+fn describe_drift(desc: &PostgresTableDesc, new_desc: &PostgresTableDesc) -> String {
+    let mut changes = vec![];
+    // Compatible changes keep the existing columns, which are usually a prefix.
+    let positions = desc.column_positions(new_desc).unwrap_or_default();
+    let added: Vec<_> = new_desc
+        .columns
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !positions.contains(i))
+        .map(|(_, c)| c.name.as_str())
+        .collect();
+    if !added.is_empty() {
+        changes.push(format!("added columns {}", added.join(", ")));
+    }
+    if let Ok(Compatibility::WidenColumn { columns }) = desc.determine_compatibility(new_desc) {
+        changes.push(format!("widened columns {}", columns.join(", ")));
+    }
+    let altered: Vec<_> = desc
+        .columns
+        .iter()
+        .zip(positions.iter().map(|i| &new_desc.columns[*i]))
+        .filter(|(c, new_c)| {
+            c != new_c && (c.type_oid, c.type_mod) == (new_c.type_oid, new_c.type_mod)
+        })
+        .map(|(c, _)| c.name.as_str())
+        .collect();
+    if !altered.is_empty() {
+        changes.push(format!("altered columns {}", altered.join(", ")));
+    }
+    if desc.keys != new_desc.keys {
+        changes.push("changed keys".into());
+    }
+    if desc.replica_identity != new_desc.replica_identity
+        || desc.identity_column_names != new_desc.identity_column_names
+    {
+        changes.push("changed replica identity".into());
+    }
+    changes.join("; ")
+}
+
+/// Checks that a source can switch to replicating from `publication`, whose tables are `tables`,
+/// without any change to the data it ingests.
+///
+/// # Errors
+/// - If `publications` don't contain exactly the tables in `publication_tables`, the tables of the
+///   publications the source was created from, based on the tables' OIDs.
+/// - If any table in `source_tables` is incompatible with its representation in `tables`, as
+///   determined by [`determine_table_compatibility`].
+fn validate_publication_switch(
+    publications: &[String],
+    publication_tables: &[PostgresTableDesc],
+    source_tables: &BTreeMap<u32, SourceTable>,
+    tables: Vec<PostgresTableDesc>,
+) -> Result<(), anyhow::Error> {
+    let describe = |table: &PostgresTableDesc| {
+        format!("{}.{} (OID {})", table.namespace, table.name, table.oid)
+    };
+    let expected: BTreeSet<u32> = publication_tables.iter().map(|table| table.oid).collect();
+    let actual: BTreeSet<u32> = tables.iter().map(|table| table.oid).collect();
+    let missing: Vec<_> = publication_tables
+        .iter()
+        .filter(|table| !actual.contains(&table.oid))
+        .map(describe)
+        .collect();
+    let unexpected: Vec<_> = tables
+        .iter()
+        .filter(|table| !expected.contains(&table.oid))
+        .map(describe)
+        .collect();
+
+    let mut differences = vec![];
+    if !missing.is_empty() {
+        differences.push(format!("missing tables {}", missing.join(", ")));
+    }
+    if !unexpected.is_empty() {
+        differences.push(format!("additional tables {}", unexpected.join(", ")));
+    }
+    if !differences.is_empty() {
+        bail!(
+            "cannot switch to {}, whose tables differ from those the source was created with: {}",
+            describe_publications(publications),
+            differences.join("; ")
+        );
+    }
+
+    let projections = determine_table_compatibility(source_tables, tables)?;
+    check_projections(source_tables, &projections)
+}
+
+/// How a source finds out whether it can fast-forward over WAL lag, from the most to the least
+/// capable mode. Once the upstream does not let it use a mode, the source falls back to the next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FastForwardMode {
+    /// Peeking into all changes of the replication slot up to the WAL end
+    Peek,
+    /// Decoding at most one change of the replication slot, see [`limited_peek`]
+    LimitedPeek,
+    /// Staying in the replication stream without fast-forwarding
+    Disabled,
+}
+
+impl Default for FastForwardMode {
+    fn default() -> Self {
+        FastForwardMode::Peek
+    }
+}
+
+impl FastForwardMode {
+    /// The value of the mode in the `fast_forward_mode` metric.
+    fn metric_value(self) -> u64 {
+        match self {
+            FastForwardMode::Peek => 0,
+            FastForwardMode::LimitedPeek => 1,
+            FastForwardMode::Disabled => 2,
+        }
+    }
+}
+
+/// The outcome of a [`limited_peek`].
+#[derive(Debug)]
+enum LimitedPeek {
+    /// The replication slot has no changes up to the WAL end, which can be skipped.
+    Skippable,
+    /// The replication slot may have changes up to the WAL end.
+    Inconclusive,
+    /// The upstream does not let us decode the replication slot.
+    Unsupported(tokio_postgres::Error),
+}
+
+/// Checks whether the WAL up to `wal_end` can be skipped by decoding at most one change of the
+/// replication slot, which is much cheaper than peeking into all of its changes.
+///
+/// Decoding starts at the position the slot confirmed, so the check is only conclusive once the
+/// slot confirmed everything up to `last_commit_lsn`. That is usually the case when the database
+/// of the source is idle while others generate WAL, which is when fast-forwarding matters most.
+async fn limited_peek(
+    client: &Client,
+    slot: &str,
+    publications: &[String],
+    replication_plugin: ReplicationPlugin,
+    last_commit_lsn: PgLsn,
+    wal_end: PgLsn,
+) -> Result<LimitedPeek, ReplicationError> {
+    // A standby can't tell its current WAL position, only how far it received the WAL of its
+    // primary, which is unknown if it restores the WAL from an archive instead.
+    let res = client
+        .simple_query(&format!(
+            "SELECT confirmed_flush_lsn,
+                 CASE WHEN pg_is_in_recovery() THEN pg_last_wal_receive_lsn()
+                 ELSE pg_current_wal_lsn() END AS current_lsn
+             FROM pg_replication_slots WHERE slot_name = '{slot}'"
+        ))
+        .await
+        .err_during(Operation::Peek)?;
+    let row = exactly_one_row(rows(&res)).err_indefinite()?;
+    let confirmed_lsn: Option<PgLsn> =
+        parse_nullable_column(row, "confirmed_flush_lsn").err_indefinite()?;
+    let current_lsn: Option<PgLsn> = parse_nullable_column(row, "current_lsn").err_indefinite()?;
+    // A slot that never confirmed a position has nothing we could rely on.
+    let confirmed_lsn = match confirmed_lsn {
+        Some(confirmed_lsn) if confirmed_lsn >= last_commit_lsn => confirmed_lsn,
+        _ => return Ok(LimitedPeek::Inconclusive),
+    };
+    // Nothing has been written since the position the slot confirmed.
+    if current_lsn.map_or(false, |current_lsn| confirmed_lsn >= current_lsn) {
+        return Ok(LimitedPeek::Skippable);
+    }
+
+    let query = match replication_plugin {
+        ReplicationPlugin::PgOutput => format!(
+            "SELECT lsn FROM pg_logical_slot_peek_binary_changes(
+                 '{slot}', '{wal_end}', 1,
+                 'proto_version', '1',
+                 'publication_names', '{publication_names}'
+            )",
+            publication_names = publication_names_option(publications),
+        ),
+        ReplicationPlugin::DecoderBufs => {
+            format!("SELECT lsn FROM pg_logical_slot_peek_binary_changes('{slot}', '{wal_end}', 1)")
+        }
+    };
+    match client.simple_query(&query).await {
+        Ok(res) => match count_peeked_changes(rows(&res), last_commit_lsn).err_indefinite()? {
+            0 => Ok(LimitedPeek::Skippable),
+            _ => Ok(LimitedPeek::Inconclusive),
+        },
+        Err(err) if is_unsupported_statement_error(&err) => Ok(LimitedPeek::Unsupported(err)),
+        Err(err) => Err(err).err_during(Operation::Peek),
+    }
+}
+
+/// Counts the changes returned by a peek into the replication slot that may belong to
+/// transactions committed after `last_commit_lsn`.
+fn count_peeked_changes<'a, R: ResultRow + 'a>(
+    rows: impl IntoIterator<Item = &'a R>,
+    last_commit_lsn: PgLsn,
+) -> Result<usize, anyhow::Error> {
+    let mut changes = 0;
+    for row in rows {
+        let change_lsn: PgLsn = parse_column(row, "lsn")
+            .with_context(|| format!("invalid replication slot peek after {last_commit_lsn}"))?;
+        // Keep all the changes that may exist after our last observed transaction commit
+        if change_lsn > last_commit_lsn {
+            changes += 1;
+        }
+    }
+    Ok(changes)
+}
+
+/// The span covering the `COPY` of a single table during the initial snapshot.
+fn snapshot_span(source_id: GlobalId, desc: &PostgresTableDesc) -> Span {
+    info_span!(
+        "pg_snapshot",
+        %source_id,
+        table_oid = desc.oid,
+        table_name = %desc.name,
+    )
+}
+
+/// The rewind of snapshots that were taken beyond the LSN that replication starts from back to
+/// it. The changes that replication emits up to the LSN of an output's snapshot are already part
+/// of the snapshot, so they are emitted again with negated diffs at the LSN replication starts
+/// from.
+#[derive(Debug)]
+struct Rewind {
+    /// The snapshots that the rows of each rewound output were copied in, by output index
+    snapshots: BTreeMap<usize, TableSnapshot>,
+}
+
+impl Rewind {
+    fn new(snapshots: BTreeMap<usize, TableSnapshot>) -> Self {
+        Self { snapshots }
+    }
+
+    /// Returns the LSN of the latest snapshot, up to which the replication stream must be read,
+    /// or none if there is nothing to rewind.
+    fn snapshot_lsn(&self) -> Option<PgLsn> {
+        self.snapshots.values().map(|snapshot| snapshot.lsn).max()
+    }
+
+    /// Returns the diff that rewinds the change by `diff` of `row` in `output` in the transaction
+    /// that committed at `commit_lsn`, if the snapshot that copied the row includes it.
+    ///
+    /// A snapshot includes every transaction that committed at or before its LSN, so a
+    /// transaction that committed exactly at it is rewound too. Every change of a transaction is
+    /// rewound on its own, which retracts repeated changes to a key in the right multiplicity.
+    fn retraction(
+        &self,
+        output: usize,
+        commit_lsn: PgLsn,
+        row: &TableRow,
+        diff: Diff,
+    ) -> Option<Diff> {
+        let snapshot = self.snapshots.get(&output)?;
+        (commit_lsn <= snapshot.lsn_of(row)).then_some(-diff)
+    }
+
+    /// Returns whether the replication stream has progressed to the `frontier` past every
+    /// snapshot, i.e. whether the rewind is done.
+    fn is_done(&self, frontier: PgLsn) -> bool {
+        self.snapshots
+            .values()
+            .all(|snapshot| frontier > snapshot.lsn)
+    }
+}
+
+/// The snapshots that the rows of a table were copied in. A table whose copy lost its connection
+/// after some of its rows were sent resumes after the key of the last of them in a later
+/// snapshot, so the rows up to that key are part of the earlier snapshot, and the others of the
+/// later one.
+#[derive(Debug, Clone)]
+struct TableSnapshot {
+    /// The positions of the columns of the key that the table was copied in order of, see
+    /// [`resume_key`]
+    key: Vec<usize>,
+    /// The key of the last row sent in every snapshot that lost its connection, along with the
+    /// LSN of that snapshot, in order
+    splits: Vec<(Row, PgLsn)>,
+    /// The LSN of the snapshot that copied the remaining rows, which is the latest one
+    lsn: PgLsn,
+}
+
+impl TableSnapshot {
+    /// Returns the snapshot of a table that was copied in a single snapshot at `lsn`.
+    fn at(lsn: PgLsn) -> Self {
+        Self {
+            key: Vec::new(),
+            splits: Vec::new(),
+            lsn,
+        }
+    }
+
+    /// Returns the LSN of the snapshot that copied the rows with the key of `row`. Rows whose key
+    /// is unknown, like errors, are attributed to the latest snapshot.
+    fn lsn_of(&self, row: &TableRow) -> PgLsn {
+        if self.splits.is_empty() {
+            return self.lsn;
+        }
+        let key = match row {
+            Ok(row) => row_key(row, &self.key),
+            Err(_) => None,
+        };
+        key.and_then(|key| {
+            self.splits
+                .iter()
+                .find(|(last, _)| key.iter().cmp(last.iter()).is_le())
+                .map(|(_, lsn)| *lsn)
+        })
+        .unwrap_or(self.lsn)
+    }
+}
+
+/// The span covering the rewind of a snapshot taken at `snapshot_lsn` back to `slot_lsn`.
+fn rewind_span(slot_lsn: PgLsn, snapshot_lsn: PgLsn) -> Span {
+    info_span!("pg_rewind", %slot_lsn, %snapshot_lsn)
+}
+
+/// Returns the LSN to resume replication from once the source has progressed to `frontier`.
+///
+/// The lsn passed to `START_REPLICATION_SLOT` produces all transactions that committed at LSNs
+/// *strictly after*, but upper frontiers have "greater than or equal" semantics. Resuming from LSN
+/// 0/0 is how a source tells that it still needs its initial snapshot, so a frontier that would
+/// resume from there is an error rather than a reason to snapshot the tables a second time.
+fn replication_lsn_at(frontier: PgLsn) -> Result<PgLsn, ReplicationError> {
+    match LsnFrontier::new(frontier).to_commit_lsn() {
+        Some(commit) if commit.to_start_replication_arg() != PgLsn::from(0) => {
+            Ok(commit.to_start_replication_arg())
+        }
+        _ => Err(ReplicationError::Definite(anyhow!(
+            "replication progressed to {frontier}, which can't be resumed from without \
+             snapshotting the source tables anew"
+        ))),
+    }
+}
+
+/// Returns the command that streams the transactions that committed after `commit` from the
+/// replication slot `slot`, decoded with the output plugin `options`.
+fn start_replication_command(slot: &str, commit: CommitLsn, options: &str) -> String {
+    format!(
+        r#"START_REPLICATION SLOT "{slot}" LOGICAL {lsn} {options}"#,
+        lsn = commit.to_start_replication_arg(),
+    )
+}
+
+/// The span covering a single streaming session of the replication slot.
+fn replication_span(slot: &str, publications: &[String], start_lsn: PgLsn) -> Span {
+    let publication = publications.join(",");
+    info_span!("pg_replication", slot, publication = publication.as_str(), %start_lsn)
+}
+
+/// The progress of a snapshot across the attempts to copy its tables.
+#[derive(Debug, Default)]
+struct SnapshotProgress {
+    /// The LSN of the main replication slot, once it was created
+    slot_lsn: Option<PgLsn>,
+    /// The tables that were copied completely, with the LSN of the snapshot that copied their last
+    /// rows
+    done: BTreeMap<u32, PgLsn>,
+    /// The tables whose copy lost its connection after some of their rows were sent, with the
+    /// key of the last of them and the LSN of the snapshot they were copied at, for every lost
+    /// connection in order
+    partial: BTreeMap<u32, Vec<(Row, PgLsn)>>,
+    /// Whether any rows were sent
+    sent: bool,
+    /// Whether some rows of a table that was not copied completely were sent, and its copy can't
+    /// resume after the last of them, see [`resume_key`]
+    unresumable: bool,
+}
+
+/// Returns the position the existing replication slot `slot`, whose row in `pg_replication_slots`
+/// is `row`, resumes from, or `None` if there is no such slot.
+///
+/// A slot that never confirmed a position, e.g. because it was created by a tool other than
+/// Materialize and never consumed, resumes from its `restart_lsn`. A slot without either was
+/// invalidated upstream, or is a physical slot, neither of which can be replicated from. Nor can
+/// a slot that decodes changes with another plugin than `plugin`, like a Debezium slot that uses
+/// `decoderbufs` for a source that replicates through `pgoutput`.
+fn existing_slot_lsn(
+    slot: &str,
+    plugin: ReplicationPlugin,
+    row: Option<&impl ResultRow>,
+) -> Result<Option<PgLsn>, ReplicationError> {
+    let row = match row {
+        Some(row) => row,
+        None => return Ok(None),
+    };
+    match row.value("plugin").err_indefinite()? {
+        Some(slot_plugin) if slot_plugin != plugin.name() => {
+            return Err(ReplicationError::Definite(anyhow!(
+                "replication slot {slot} decodes changes with the {slot_plugin} plugin, \
+                 but the source replicates through {}",
+                plugin.name()
+            )));
+        }
+        _ => {}
+    }
+    let confirmed_lsn: Option<PgLsn> =
+        parse_nullable_column(row, "confirmed_flush_lsn").err_indefinite()?;
+    let restart_lsn: Option<PgLsn> = parse_nullable_column(row, "restart_lsn").err_indefinite()?;
+    match (confirmed_lsn, restart_lsn) {
+        (Some(confirmed_lsn), _) => Ok(Some(confirmed_lsn)),
+        (None, Some(restart_lsn)) => {
+            warn!(
+                "replication slot {slot} has no confirmed_flush_lsn, \
+                 resuming from its restart_lsn {restart_lsn}"
+            );
+            Ok(Some(restart_lsn))
+        }
+        (None, None) => Err(ReplicationError::Definite(anyhow!(
+            "replication slot {slot} has neither a confirmed_flush_lsn nor a restart_lsn, \
+             it was invalidated or is not a logical replication slot"
+        ))),
+    }
+}
+
+/// Ends the snapshot transaction of `client`, dropping the temporary slot its snapshot was taken
+/// with, if any.
+async fn end_snapshot_transaction(
+    client: &Client,
+    temp_slot: Option<String>,
+) -> Result<(), ReplicationError> {
+    if let Some(temp_slot) = temp_slot {
+        let _ = client
+            .simple_query(&format!("DROP_REPLICATION_SLOT {temp_slot:?}"))
+            .await;
+    }
+    client.simple_query("COMMIT;").await?;
+    client.simple_query("RESET statement_timeout;").await?;
+    Ok(())
+}
+
+/// Returns the quoted names of the columns of the table described by `info`, in the order they are
+/// ingested in, if they need to be selected explicitly because they are not the leading columns
+/// upstream.
+fn projected_column_list(info: &SourceTable) -> Option<String> {
+    if info.projection.is_none() {
+        return None;
+    }
+    Some(column_names(&info.desc))
+}
+
+/// Returns the quoted names of the columns of `desc`, in order.
+fn column_names(desc: &PostgresTableDesc) -> String {
+    let columns: Vec<_> = desc
+        .columns
+        .iter()
+        .map(|column| format!("{:?}", column.name))
+        .collect();
+    columns.join(", ")
+}
+
+/// Returns the positions of the columns of the primary key of the table described by `desc`, if
+/// its copy can resume after the last row it sent in a later snapshot.
+///
+/// This requires the table to be copied in the order of its key, and the keys of the rows that
+/// replication emits to be compared like upstream compares them, which is only the case for the
+/// types whose datums sort like their values upstream, regardless of collations.
+fn resume_key(desc: &PostgresTableDesc) -> Option<Vec<usize>> {
+    let key = desc.keys.iter().find(|key| key.is_primary)?;
+    key.cols
+        .iter()
+        .map(|col_num| {
+            let position = desc
+                .columns
+                .iter()
+                .position(|column| column.col_num == Some(*col_num))?;
+            let column = &desc.columns[position];
+            match mz_pgrepr::Type::from_oid_and_typmod(column.type_oid, column.type_mod) {
+                Ok(
+                    mz_pgrepr::Type::Int2
+                    | mz_pgrepr::Type::Int4
+                    | mz_pgrepr::Type::Int8
+                    | mz_pgrepr::Type::Uuid,
+                ) => Some(position),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Returns the key of `row` at the positions `key`, unless any of its values is not of a type
+/// that [`resume_key`] allows, e.g. because the column is ingested as text.
+fn row_key(row: &Row, key: &[usize]) -> Option<Row> {
+    let datums = row.unpack();
+    let mut key_row = Row::default();
+    let mut packer = key_row.packer();
+    for position in key {
+        match datums.get(*position)? {
+            datum @ (Datum::Int16(_) | Datum::Int32(_) | Datum::Int64(_) | Datum::Uuid(_)) => {
+                packer.push(*datum)
+            }
+            _ => return None,
+        }
+    }
+    Some(key_row)
+}
+
+/// Returns the relations to `COPY` the rows of the table described by `desc` out of, which are
+/// the leaf partitions of a partitioned table, and the table itself otherwise.
+///
+/// A partitioned table only appears in a publication that is published via its partition root,
+/// whose changes are replicated as changes to the root, so only its snapshot needs the partitions.
+/// They are looked up within the snapshot transaction, so that they match the copied data.
+///
+/// A foreign table that is a partition can't be copied, and its changes are not replicated, so
+/// it fails the snapshot.
+async fn copy_relations(
+    client: &Client,
+    desc: &PostgresTableDesc,
+) -> Result<Vec<(String, String)>, ReplicationError> {
+    // Replication connections only support the simple query protocol.
+    let res = client
+        .simple_query(&format!(
+            "WITH RECURSIVE tree AS (
+                SELECT oid, relkind FROM pg_catalog.pg_class WHERE oid = {}
+                UNION ALL
+                SELECT c.oid, c.relkind
+                FROM tree
+                JOIN pg_catalog.pg_inherits AS i ON i.inhparent = tree.oid
+                JOIN pg_catalog.pg_class AS c ON c.oid = i.inhrelid
+                WHERE tree.relkind = 'p'
+            )
+            SELECT n.nspname, c.relname, tree.relkind::text
+            FROM tree
+            JOIN pg_catalog.pg_class AS c ON c.oid = tree.oid
+            JOIN pg_catalog.pg_namespace AS n ON n.oid = c.relnamespace
+            WHERE tree.relkind IN ('r', 'f')
+            ORDER BY tree.oid",
+            desc.oid
+        ))
+        .await?;
+    let mut relations = vec![];
+    for row in rows(&res) {
+        let namespace: String = parse_column(row, "nspname").err_indefinite()?;
+        let name: String = parse_column(row, "relname").err_indefinite()?;
+        let relkind: String = parse_column(row, "relkind").err_indefinite()?;
+        if relkind == "f" {
+            return Err(ReplicationError::Definite(anyhow!(
+                "{}",
+                ValidationIssue::ForeignPartition {
+                    table: format!("{}.{}", desc.namespace, desc.name),
+                    partition: format!("{namespace}.{name}"),
+                }
+            )));
+        }
+        relations.push((namespace, name));
+    }
+    Ok(relations)
+}
+
+/// Returns the columns of the `source_tables` by their OID, as seen by the snapshot transaction
+/// `client` is in.
+///
+/// Like in [`mz_postgres_util::publication_info`], a column of a domain type is described by the
+/// domain's base type.
+async fn snapshot_columns(
+    client: &Client,
+    source_tables: &BTreeMap<u32, SourceTable>,
+) -> Result<BTreeMap<u32, Vec<PostgresColumnDesc>>, ReplicationError> {
+    if source_tables.is_empty() {
+        return Ok(BTreeMap::new());
+    }
+    let oids: Vec<_> = source_tables.keys().map(|oid| oid.to_string()).collect();
+    // Replication connections only support the simple query protocol.
+    let res = client
+        .simple_query(&format!(
+            "WITH RECURSIVE columns AS (
+                SELECT attrelid, attname, attnum, attnotnull, atttypid AS typoid,
+                    atttypmod AS typmod
+                FROM pg_catalog.pg_attribute
+                WHERE attrelid IN ({}) AND attnum > 0 AND NOT attisdropped
+                UNION ALL
+                SELECT c.attrelid, c.attname, c.attnum, c.attnotnull, t.typbasetype, t.typtypmod
+                FROM columns AS c
+                JOIN pg_catalog.pg_type AS t ON t.oid = c.typoid
+                WHERE t.typtype = 'd'
+            )
+            SELECT c.attrelid, c.attname, c.attnum, c.attnotnull::text, c.typoid, c.typmod
+            FROM columns AS c
+            JOIN pg_catalog.pg_type AS t ON t.oid = c.typoid
+            WHERE t.typtype <> 'd'
+            ORDER BY c.attrelid, c.attnum",
+            oids.join(", ")
+        ))
+        .await
+        .err_indefinite()?;
+
+    let mut columns: BTreeMap<u32, Vec<PostgresColumnDesc>> = BTreeMap::new();
+    for row in rows(&res) {
+        let oid = parse_column(row, "attrelid").err_indefinite()?;
+        let not_null: bool = parse_column(row, "attnotnull").err_indefinite()?;
+        let column = PostgresColumnDesc {
+            name: parse_column(row, "attname").err_indefinite()?,
+            col_num: Some(parse_column(row, "attnum").err_indefinite()?),
+            type_oid: parse_column(row, "typoid").err_indefinite()?,
+            type_mod: parse_column(row, "typmod").err_indefinite()?,
+            nullable: !not_null,
+            // Domain constraints and defaults are only recorded for reference, and don't affect
+            // compatibility.
+            domain_constraints: vec![],
+            default_expr: None,
+        };
+        columns.entry(oid).or_default().push(column);
+    }
+    Ok(columns)
+}
+
+/// Checks that the upstream role may copy all of `source_tables`, before any of their rows are
+/// sent. A table that fails to copy halfway through the snapshot would require starting it over.
+async fn check_snapshot_privileges(
+    client: &Client,
+    source_tables: &BTreeMap<u32, SourceTable>,
+) -> Result<(), ReplicationError> {
+    if source_tables.is_empty() {
+        return Ok(());
+    }
+    let oids: Vec<_> = source_tables.keys().map(|oid| oid.to_string()).collect();
+    // Replication connections only support the simple query protocol.
+    let res = client
+        .simple_query(&format!(
+            "SELECT oid,
+                has_schema_privilege(relnamespace, 'USAGE')::text AS can_use_schema,
+                has_table_privilege(oid, 'SELECT')::text AS can_select
+            FROM pg_catalog.pg_class
+            WHERE oid IN ({})
+            ORDER BY oid",
+            oids.join(", ")
+        ))
+        .await
+        .err_indefinite()?;
+
+    for row in rows(&res) {
+        let oid: u32 = parse_column(row, "oid").err_indefinite()?;
+        let can_use_schema: bool = parse_column(row, "can_use_schema").err_indefinite()?;
+        let can_select: bool = parse_column(row, "can_select").err_indefinite()?;
+        let Some(info) = source_tables.get(&oid) else {
+            continue;
+        };
+        let missing = if !can_use_schema {
+            MissingPrivilege::SchemaUsage {
+                schema: info.desc.namespace.clone(),
+            }
+        } else if !can_select {
+            MissingPrivilege::Select {
+                schema: info.desc.namespace.clone(),
+                table: info.desc.name.clone(),
+            }
+        } else {
+            continue;
+        };
+        return Err(ReplicationError::Indefinite(anyhow::Error::msg(missing)));
+    }
+    Ok(())
+}
+
+/// The size of a table as estimated by the statistics of the upstream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TableEstimate {
+    /// The estimated number of rows, unknown if the table was never vacuumed or analyzed
+    rows: Option<u64>,
+    /// The size of the table on disk, including its TOAST table, in bytes
+    bytes: u64,
+}
+
+/// Returns the estimated size of each of `source_tables`, by OID, as of the snapshot of the
+/// transaction of `client`.
+async fn table_estimates(
+    client: &Client,
+    source_tables: &BTreeMap<u32, SourceTable>,
+) -> Result<BTreeMap<u32, TableEstimate>, ReplicationError> {
+    if source_tables.is_empty() {
+        return Ok(BTreeMap::new());
+    }
+    let oids: Vec<_> = source_tables.keys().map(|oid| oid.to_string()).collect();
+    let res = client
+        .simple_query(&format!(
+            "SELECT oid, reltuples::bigint AS reltuples, pg_catalog.pg_table_size(oid) AS bytes
+            FROM pg_catalog.pg_class
+            WHERE oid IN ({})",
+            oids.join(", ")
+        ))
+        .await
+        .err_indefinite()?;
+
+    let mut estimates = BTreeMap::new();
+    for row in rows(&res) {
+        let oid = parse_column(row, "oid").err_indefinite()?;
+        // `reltuples` is -1 for tables that were never vacuumed or analyzed (Postgres 14+).
+        let rows: i64 = parse_column(row, "reltuples").err_indefinite()?;
+        let estimate = TableEstimate {
+            rows: u64::try_from(rows).ok(),
+            bytes: parse_column(row, "bytes").err_indefinite()?,
+        };
+        estimates.insert(oid, estimate);
+    }
+    Ok(estimates)
+}
+
+/// Returns `source_tables` in the order they are snapshotted in according to `order`. Tables of
+/// the same estimated size, or without an estimate, are ordered by their output index.
+fn order_tables<'a>(
+    order: SnapshotOrder,
+    source_tables: &'a BTreeMap<u32, SourceTable>,
+    estimates: &BTreeMap<u32, TableEstimate>,
+) -> Vec<(u32, &'a SourceTable)> {
+    let mut tables: Vec<_> = source_tables
+        .iter()
+        .map(|(oid, table)| (*oid, table))
+        .collect();
+    match order {
+        SnapshotOrder::Size => tables.sort_by_key(|(oid, table)| {
+            let bytes = estimates.get(oid).map(|estimate| estimate.bytes);
+            (std::cmp::Reverse(bytes), table.output_index)
+        }),
+        SnapshotOrder::OutputIndex => tables.sort_by_key(|(_, table)| table.output_index),
+    }
+    tables
+}
+
+/// Describes the estimated size of the table `name` for the log line of a snapshot.
+fn describe_estimate(name: &str, estimate: Option<&TableEstimate>) -> String {
+    match estimate {
+        Some(TableEstimate {
+            rows: Some(rows),
+            bytes,
+        }) => format!("{name} (~{rows} rows, {bytes} bytes)"),
+        Some(TableEstimate { rows: None, bytes }) => format!("{name} ({bytes} bytes)"),
+        None => format!("{name} (no estimate)"),
+    }
+}
+
+/// Checks that the `source_tables` are still compatible with their upstream tables, whose
+/// `columns` are as seen by the snapshot transaction.
+///
+/// # Errors
+/// - If any table in `source_tables` is missing from `columns`, or is incompatible with its
+///   `columns`, as determined by [`determine_table_compatibility`].
+fn validate_snapshot_columns(
+    source_tables: &BTreeMap<u32, SourceTable>,
+    mut columns: BTreeMap<u32, Vec<PostgresColumnDesc>>,
+) -> Result<(), anyhow::Error> {
+    let tables = source_tables
+        .values()
+        .filter_map(|info| {
+            let columns = columns.remove(&info.desc.oid)?;
+            Some(PostgresTableDesc {
+                columns,
+                ..info.desc.clone()
+            })
+        })
+        .collect();
+    determine_table_compatibility(source_tables, tables)
+        .and_then(|projections| check_projections(source_tables, &projections))
+        .context("upstream table changed before the snapshot started")
+}
+
+/// Ensures that a row of `size` bytes received while snapshotting the table described by `info`
+/// does not exceed the configured maximum row size.
+fn check_snapshot_row_size(
+    size: usize,
+    info: &SourceTable,
+    limits: &PgSourceLimits,
+    metrics: &PgSourceMetrics,
+) -> Result<(), ReplicationError> {
+    let max_row_size = limits.max_row_size_bytes();
+    if size > max_row_size {
+        metrics.row_size_limit_exceeded.inc();
+        return Err(ReplicationError::Definite(anyhow!(
+            "row of size {} bytes in source table {} with oid {} exceeds the maximum row size of \
+             {} bytes",
+            size,
+            info.desc.name,
+            info.desc.oid,
+            max_row_size
+        )));
+    }
+    Ok(())
+}
+
+/// Ensures that a tuple of table `rel_id` received as part of transaction `xid` does not exceed
+/// the configured maximum row size.
+fn check_row_size(
+    rel_id: u32,
+    xid: u32,
+    tuple_data: &[TupleData],
+    limits: &PgSourceLimits,
+    metrics: &PgSourceMetrics,
+) -> Result<(), ReplicationError> {
+    let size: usize = tuple_data
+        .iter()
+        .map(|val| match val {
+            TupleData::Text(b) => b.len(),
+            TupleData::Null | TupleData::UnchangedToast => 0,
+        })
+        .sum();
+    let max_row_size = limits.max_row_size_bytes();
+    if size > max_row_size {
+        metrics.row_size_limit_exceeded.inc();
+        return Err(ReplicationError::Definite(anyhow!(
+            "row of size {size} bytes from table with OID = {rel_id} in transaction {xid} \
+             exceeds the maximum row size of {max_row_size} bytes"
+        )));
+    }
+    Ok(())
+}
+
+/// Ensures that transaction `xid` has not buffered more changes than the configured limit.
+/// `rel_id` identifies the table whose change was buffered last.
+fn check_transaction_size(
+    rel_id: u32,
+    xid: u32,
+    changes: usize,
+    limits: &PgSourceLimits,
+    metrics: &PgSourceMetrics,
+) -> Result<(), ReplicationError> {
+    let max_changes = limits.max_transaction_changes();
+    if changes > max_changes {
+        metrics.transaction_size_limit_exceeded.inc();
+        return Err(ReplicationError::Definite(anyhow!(
+            "transaction {xid} exceeds the maximum of {max_changes} buffered changes \
+             while processing a change for table with OID = {rel_id}"
+        )));
+    }
+    Ok(())
+}
+
+/// The `(deletes, inserts)` buffered for a streamed transaction until it commits.
+type TransactionChanges = (Vec<(usize, TableRow)>, Vec<(usize, TableRow)>);
+
+/// Returns the `(inserts, deletes)` buffers that changes received right now belong to: those of
+/// the streamed transaction whose stream block is open, or the ones of the regular transaction.
+fn transaction_buffers<'a>(
+    current_stream: Option<u32>,
+    streamed_txns: &'a mut BTreeMap<u32, TransactionChanges>,
+    inserts: &'a mut Vec<(usize, TableRow)>,
+    deletes: &'a mut Vec<(usize, TableRow)>,
+) -> (
+    &'a mut Vec<(usize, TableRow)>,
+    &'a mut Vec<(usize, TableRow)>,
+) {
+    match current_stream {
+        Some(xid) => {
+            let (stream_deletes, stream_inserts) = streamed_txns.entry(xid).or_default();
+            (stream_inserts, stream_deletes)
+        }
+        None => (inserts, deletes),
+    }
+}
+
+/// Records the number of changes and bytes of a committed transaction.
+fn observe_transaction_size(
+    metrics: &PgSourceMetrics,
+    inserts: &[(usize, TableRow)],
+    deletes: &[(usize, TableRow)],
+) -> usize {
+    let changes = inserts.len() + deletes.len();
+    let bytes = changes_size(inserts) + changes_size(deletes);
+    metrics
+        .transaction_changes
+        .observe(f64::cast_lossy(changes));
+    metrics.transaction_bytes.observe(f64::cast_lossy(bytes));
+    bytes
+}
+
+/// The estimated size in bytes of the buffered `changes`.
+fn changes_size(changes: &[(usize, TableRow)]) -> usize {
+    changes.iter().map(|(_, row)| table_row_len(row)).sum()
+}
+
+/// The status to report once the changes buffered for uncommitted transactions grew to `bytes`,
+/// beyond the degraded threshold, or shrank below it again if `bytes` is `None`.
+fn transaction_buffer_status(bytes: Option<usize>) -> HealthStatus {
+    match bytes {
+        Some(bytes) => HealthStatus::Degraded {
+            error: format!(
+                "buffering {bytes} bytes of changes of uncommitted upstream transactions in memory"
+            ),
+            hint: Some(
+                "Large upstream transactions are buffered until they commit. Set MAX TRANSACTION \
+                 ROWS on the source to emit their changes before they commit instead."
+                    .into(),
+            ),
+        },
+        None => HealthStatus::Running,
+    }
+}
+
+/// Converts a timestamp sent by the upstream, expressed in microseconds since the Postgres epoch,
+/// into milliseconds since the Unix epoch.
+fn pg_timestamp_to_unix_millis(timestamp: i64) -> Option<i64> {
+    let epoch_millis = PG_EPOCH.duration_since(UNIX_EPOCH).ok()?.as_millis();
+    timestamp
+        .div_euclid(1_000)
+        .checked_add(i64::try_from(epoch_millis).ok()?)
+}
+
+/// Records the time between the upstream commit at `timestamp` and now.
+fn observe_commit_latency(metrics: &PgSourceMetrics, timestamp: i64) {
+    // The commit timestamp is expressed in microseconds since the Postgres epoch. Clock skew
+    // between us and the upstream can place it in the future, in which case we don't record a
+    // latency.
+    if let Ok(micros) = u64::try_from(timestamp) {
+        let commit_time = *PG_EPOCH + Duration::from_micros(micros);
+        if let Ok(latency) = SystemTime::now().duration_since(commit_time) {
+            metrics
+                .commit_to_emit_latency
+                .observe(latency.as_secs_f64());
+        }
+    }
+}
+
+/// The size of the header of an `XLogData` message: its tag, WAL start, WAL end and send time.
+const XLOG_DATA_HEADER_LEN: u64 = 25;
+/// The size of a `PrimaryKeepAlive` message: its tag, WAL end, send time and reply flag.
+const PRIMARY_KEEPALIVE_LEN: u64 = 18;
+
+/// Estimates the number of bytes `message` occupied on the wire.
+///
+/// The payload of `XLogData` messages is not available once decoded, so its size is
+/// reconstructed for the messages that make up the bulk of the stream (transaction boundaries and
+/// row changes). Any other logical replication message is only accounted for by its tag.
+fn replication_message_len(message: &ReplicationMessage<LogicalReplicationMessage>) -> u64 {
+    use LogicalReplicationMessage::*;
+    fn tuple_len(tuple: &Tuple) -> u64 {
+        // The number of columns followed by a kind byte per column and, for text values, their
+        // length and contents.
+        let columns: usize = tuple
+            .tuple_data()
+            .iter()
+            .map(|data| match data {
+                TupleData::Text(b) => 1 + 4 + b.len(),
+                TupleData::Null | TupleData::UnchangedToast => 1,
+            })
+            .sum();
+        2 + u64::cast_from(columns)
+    }
+    match message {
+        ReplicationMessage::XLogData(xlog_data) => {
+            let payload = match xlog_data.data() {
+                Begin(_) => 1 + 8 + 8 + 4,
+                Commit(_) => 1 + 1 + 8 + 8 + 8,
+                Insert(insert) => 1 + 4 + 1 + tuple_len(insert.tuple()),
+                Update(update) => {
+                    let old = update
+                        .key_tuple()
+                        .or_else(|| update.old_tuple())
+                        .map_or(0, |tuple| 1 + tuple_len(tuple));
+                    1 + 4 + old + 1 + tuple_len(update.new_tuple())
+                }
+                Delete(delete) => {
+                    let old = delete
+                        .key_tuple()
+                        .or_else(|| delete.old_tuple())
+                        .map_or(0, tuple_len);
+                    1 + 4 + 1 + old
+                }
+                _ => 1,
+            };
+            XLOG_DATA_HEADER_LEN + payload
+        }
+        ReplicationMessage::PrimaryKeepAlive(_) => PRIMARY_KEEPALIVE_LEN,
+        _ => 0,
+    }
+}
+
+/// The value of a column in the tuples upstream wrote before the column was added, which is what
+/// its default was when it was added.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ColumnDefault {
+    /// The text representation of a constant default, or `None` if the column has no default or
+    /// a `NULL` one.
+    Constant(Option<String>),
+    /// The default expression, which is not a constant that we can evaluate, e.g. a function call.
+    Expression(String),
+}
+
+/// Determines the [`ColumnDefault`] of each of the columns described by `desc`.
+///
+/// Only the constants that `pg_get_expr` deparses defaults into are evaluated, i.e. `NULL`,
+/// booleans, numbers and string literals, all optionally cast to a type. A default that was
+/// altered after its column was added no longer tells what upstream filled in for the earlier
+/// rows, which are padded with the current default regardless.
+fn column_defaults(desc: &PostgresTableDesc) -> Vec<ColumnDefault> {
+    desc.columns
+        .iter()
+        .map(|column| match &column.default_expr {
+            None => ColumnDefault::Constant(None),
+            Some(expr) => match constant_default(expr) {
+                Some(value) => ColumnDefault::Constant(value),
+                None => ColumnDefault::Expression(expr.clone()),
+            },
+        })
+        .collect()
+}
+
+/// Evaluates the default expression `expr` if it is a constant, returning the text representation
+/// of its value, or `None` if it is `NULL`.
+fn constant_default(expr: &str) -> Option<Option<String>> {
+    let (value, cast) = match expr.strip_prefix('\'') {
+        Some(quoted) => {
+            // Quotes within string literals are doubled.
+            let mut value = String::new();
+            let mut chars = quoted.char_indices();
+            let cast = loop {
+                match chars.next()? {
+                    (i, '\'') if quoted[i + 1..].starts_with('\'') => {
+                        value.push('\'');
+                        chars.next();
+                    }
+                    (i, '\'') => break &quoted[i + 1..],
+                    (_, c) => value.push(c),
+                }
+            };
+            (Some(value), cast)
+        }
+        None => {
+            let (literal, cast) = expr.split_at(expr.find("::").unwrap_or(expr.len()));
+            let is_number = literal.starts_with(|c: char| c.is_ascii_digit() || c == '-')
+                && literal
+                    .chars()
+                    .all(|c| c.is_ascii_digit() || ".-+eE".contains(c));
+            let value = match literal {
+                "NULL" => None,
+                "true" | "false" => Some(literal.to_string()),
+                _ if is_number => Some(literal.to_string()),
+                _ => return None,
+            };
+            (value, cast)
+        }
+    };
+    // Anything but a single cast to a type, e.g. an operator, makes the default an expression.
+    let is_type_name = |name: &str| {
+        !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_alphanumeric() || " _.\"()[],".contains(c))
+    };
+    match cast.strip_prefix("::") {
+        None if cast.is_empty() => Some(value),
+        Some(type_name) if is_type_name(type_name) => Some(value),
+        _ => None,
+    }
+}
+
+/// Casts a text row into the target types, or returns the index of the first cast that fails
+/// along with its error.
+fn cast_row(table_cast: &[MirScalarExpr], datums: &[Datum<'_>]) -> Result<Row, (usize, EvalError)> {
+    let arena = mz_repr::RowArena::new();
+    let mut row = Row::default();
+    let mut packer = row.packer();
+    for (i, column_cast) in table_cast.iter().enumerate() {
+        let datum = column_cast.eval(datums, &arena).map_err(|err| (i, err))?;
+        packer.push(datum);
+    }
+    Ok(row)
+}
+
+/// A row of a source table, or the error that casting it out of its upstream text encoding failed
+/// with, which is emitted into the table's output in place of the row.
+type TableRow = Result<Row, SourceErrorDetails>;
+
+/// The number of characters of an upstream value that the error of a failed cast includes.
+const CAST_ERROR_VALUE_CHARS: usize = 64;
+
+/// Casts the text encoded `datums` of a row of the table `info` into the types it is ingested as.
+///
+/// Upstream values that don't cast, e.g. `infinity` timestamps or dates outside of the range that
+/// Materialize supports, only fail their row instead of the whole source. The row's error names
+/// the table, the column and the offending value, and is retracted again along with the row.
+fn cast_table_row(info: &SourceTable, datums: &[Datum<'_>], metrics: &PgSourceMetrics) -> TableRow {
+    cast_row(&info.casts, datums).map_err(|(i, err)| {
+        metrics.cast_errors.inc();
+        cast_error(info, datums, i, err)
+    })
+}
+
+/// Returns the error of a row of the table `info` whose `i`th column failed to cast with `err`.
+fn cast_error(
+    info: &SourceTable,
+    datums: &[Datum<'_>],
+    i: usize,
+    err: EvalError,
+) -> SourceErrorDetails {
+    // The only cast past the table's columns is the constant deleted flag of soft delete tables.
+    let column = info
+        .desc
+        .columns
+        .get(i)
+        .map_or("mz_deleted", |column| column.name.as_str());
+    let value = match datums.get(i) {
+        Some(Datum::String(value)) if value.chars().count() > CAST_ERROR_VALUE_CHARS => {
+            let prefix: String = value.chars().take(CAST_ERROR_VALUE_CHARS).collect();
+            format!("{}...", prefix.quoted())
+        }
+        Some(Datum::String(value)) => value.quoted().to_string(),
+        _ => "NULL".into(),
+    };
+    SourceErrorDetails::Other(format!(
+        "failed to cast value {value} of column {} of table {}.{}: {err}",
+        column.quoted(),
+        info.desc.namespace,
+        info.desc.name
+    ))
+}
+
+/// Returns the number of bytes that buffering `row` takes, roughly.
+fn table_row_len(row: &TableRow) -> usize {
+    match row {
+        Ok(row) => row.byte_len(),
+        Err(err) => err.to_string().len(),
+    }
+}
+
+/// Checks that the `casts` of `info` produce a value for each of its columns, plus the deleted
+/// flag of soft delete tables, out of the text encoded columns of its description alone, and that
+/// they cast each of the text encoded `rows` of the table, whose `None`s are `NULL`s.
+fn check_table_casts(
+    info: &SourceTable,
+    rows: &[Vec<Option<String>>],
+) -> Result<(), anyhow::Error> {
+    let columns = info.desc.columns.len();
+    let expected = columns + usize::from(info.soft_delete);
+    if info.casts.len() != expected {
+        bail!(
+            "table {} has {} casts for {expected} columns",
+            info.desc.name,
+            info.casts.len()
+        );
+    }
+    for (cast, column) in info.casts.iter().zip(info.desc.columns.iter()) {
+        if let Some(input) = cast.support().into_iter().find(|input| *input >= columns) {
+            bail!(
+                "cast of column {}.{} reads column {input}, but the table has {columns} columns",
+                info.desc.name,
+                column.name
+            );
+        }
+    }
+    for row in rows {
+        let datums: Vec<_> = row
+            .iter()
+            .map(|value| value.as_deref().map_or(Datum::Null, Datum::String))
+            .collect();
+        if let Err((i, err)) = cast_row(&info.casts, &datums) {
+            bail!("{}", cast_error(info, &datums, i, err));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the live `row` of a soft delete table flagged as deleted.
+fn soft_deleted_row(row: &Row) -> Row {
+    let mut datums = row.unpack();
+    *datums
+        .last_mut()
+        .expect("rows of soft delete tables have a deleted flag") = Datum::True;
+    Row::pack(datums)
+}
+
+/// Returns whether `row` of a soft delete table is flagged as deleted.
+fn is_soft_deleted(row: &Row) -> bool {
+    row.iter().last() == Some(Datum::True)
+}
+
+/// Fails with an indefinite error, which interrupts and restarts replication, if the fail point
+/// `name` is configured to `return`.
+fn replication_fail_point(name: &str) -> Result<(), ReplicationError> {
+    match fail::eval(name, |_| ()) {
+        Some(()) => Err(ReplicationError::Indefinite(anyhow!(
+            "failpoint {name} triggered"
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// The item type of a logical replication stream.
+type ReplicationStreamItem =
+    Result<ReplicationMessage<LogicalReplicationMessage>, ReplicationError>;
+
+/// Returns the current time in microseconds since the Postgres epoch, as sent in standby status
+/// updates.
+fn standby_timestamp() -> i64 {
+    PG_EPOCH
+        .elapsed()
+        .expect("system clock set earlier than year 2000!")
+        .as_micros()
+        .try_into()
+        .expect("software more than 200k years old, consider updating")
+}
+
+/// The side effects on the upstream of consuming a replication stream.
+///
+/// This is abstracted, along with the stream itself, so that the decoding logic in
+/// [`consume_replication_stream`] can be driven by synthetic message sequences in tests.
+#[async_trait::async_trait]
+trait ReplicationUpstream: Send {
+    /// Reports to the upstream that all changes up to `lsn` have been durably recorded.
+    async fn send_feedback(&mut self, lsn: PgLsn) -> Result<(), ReplicationError>;
+
+    /// Returns the current upstream description of the table with OID `rel_id`, or `None` if it
+    /// is no longer part of the publication.
+    async fn table_desc(
+        &mut self,
+        rel_id: u32,
+    ) -> Result<Option<PostgresTableDesc>, ReplicationError>;
+
+    /// Returns the current upstream definition of the type with OID `type_oid`, as per
+    /// [`mz_postgres_util::type_definition`], or `None` if it no longer exists.
+    async fn type_definition(&mut self, type_oid: u32) -> Result<Option<String>, ReplicationError>;
+}
+
+/// A replication stream opened against a live Postgres server.
+///
+/// The `pgoutput` messages are decoded here rather than by a `LogicalReplicationStream`, so that
+/// their raw payload can be captured first.
+struct PgReplicationStream<'a> {
+    stream: Pin<Box<ReplicationStream>>,
+    connections: &'a UpstreamConnections,
+    publications: &'a [String],
+    /// Where the raw messages are captured to before they are decoded, if anywhere
+    wal_capture: Option<&'a WalCapture>,
+}
+
+impl futures::Stream for PgReplicationStream<'_> {
+    type Item = ReplicationStreamItem;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let message = match self.stream.as_mut().poll_next(cx) {
+            Poll::Ready(Some(Ok(message))) => message,
+            Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err.into()))),
+            Poll::Ready(None) => return Poll::Ready(None),
+            Poll::Pending => return Poll::Pending,
+        };
+        let message = match message {
+            ReplicationMessage::XLogData(body) => {
+                if let Some(capture) = self.wal_capture {
+                    capture.capture(body.wal_start(), body.wal_end(), body.data());
+                }
+                body.map_data(|data| LogicalReplicationMessage::parse(&data))
+                    .map(ReplicationMessage::XLogData)
+                    .map_err(ReplicationError::from)
+            }
+            ReplicationMessage::PrimaryKeepAlive(body) => {
+                Ok(ReplicationMessage::PrimaryKeepAlive(body))
+            }
+            // Like `LogicalReplicationStream`, treat messages of unknown kinds as transient.
+            _ => Err(ReplicationError::Indefinite(anyhow!(
+                "unexpected replication message"
+            ))),
+        };
+        Poll::Ready(Some(message))
+    }
+}
+
+#[async_trait::async_trait]
+impl ReplicationUpstream for PgReplicationStream<'_> {
+    async fn send_feedback(&mut self, lsn: PgLsn) -> Result<(), ReplicationError> {
+        self.stream
+            .as_mut()
+            .standby_status_update(lsn, lsn, lsn, standby_timestamp(), 0)
+            .await
+            .err_indefinite()
+    }
+
+    async fn table_desc(
+        &mut self,
+        rel_id: u32,
+    ) -> Result<Option<PostgresTableDesc>, ReplicationError> {
+        let tables = self
+            .connections
+            .publication_info(self.publications, &PublicationFilter::ByOid(rel_id))
+            .await
+            .err_indefinite()?;
+        Ok(tables.into_iter().next())
+    }
+
+    async fn type_definition(&mut self, type_oid: u32) -> Result<Option<String>, ReplicationError> {
+        self.connections
+            .type_definition(type_oid)
+            .await
+            .err_indefinite()
+    }
+}
+
+/// A custom type announced by the upstream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct UpstreamType {
+    namespace: String,
+    name: String,
+    /// The definition of the type when it was announced, if it still existed
+    definition: Option<String>,
+}
+
+/// The decoding state of the replication stream, which outlives the individual connections to
+/// the upstream.
+struct ReplicationState {
+    inserts: Vec<(usize, TableRow)>,
+    deletes: Vec<(usize, TableRow)>,
+    /// The id of the transaction currently being received
+    xid: u32,
+    /// The LSN of the commit record of the transaction currently being received
+    final_lsn: PgLsn,
+    /// The commit time of the transaction currently being received, in milliseconds since the
+    /// Unix epoch
+    current_tx_timestamp: Option<i64>,
+    /// Whether some changes of the transaction currently being received have already been
+    /// emitted at `final_lsn`
+    split: bool,
+    last_commit_lsn: PgLsn,
+    observed_wal_end: PgLsn,
+    /// The status updates sent to the upstream
+    feedback: Feedback,
+    /// The custom types announced by the upstream, by OID
+    types: BTreeMap<u32, UpstreamType>,
+    /// Whether the source was reported as degraded because of the size of the changes buffered
+    /// for uncommitted transactions
+    buffer_degraded: bool,
+}
+
+impl ReplicationState {
+    fn new(as_of: PgLsn) -> Self {
+        Self {
+            inserts: vec![],
+            deletes: vec![],
+            xid: 0,
+            final_lsn: as_of,
+            current_tx_timestamp: None,
+            split: false,
+            last_commit_lsn: as_of,
+            observed_wal_end: as_of,
+            feedback: Feedback::new(),
+            types: BTreeMap::new(),
+            buffer_degraded: false,
+        }
+    }
+}
+
+/// The status updates sent to the upstream, which acknowledge the changes committed downstream
+/// and keep the upstream from timing out the connection.
+#[derive(Debug)]
+struct Feedback {
+    /// When the last status update was sent, or when replication started if none was sent yet
+    last_sent: Instant,
+    /// The LSN last reported to the upstream in a status update
+    reported_lsn: Option<PgLsn>,
+    /// Whether the upstream requested a status update that was not sent yet. Requests that
+    /// arrive in the meantime are answered by the same status update.
+    reply_requested: bool,
+}
+
+impl Feedback {
+    fn new() -> Self {
+        Self {
+            last_sent: Instant::now(),
+            reported_lsn: None,
+            reply_requested: false,
+        }
+    }
+
+    /// Returns whether a status update is due.
+    ///
+    /// The upstream periodically requests status updates by setting the keepalive's reply field
+    /// to 1. However, we cannot rely on these messages arriving on time. For example, when the
+    /// upstream is sending a big transaction its keepalive messages are queued and can be delayed
+    /// arbitrarily. Therefore, we also make sure to send a proactive status update every
+    /// [`FEEDBACK_INTERVAL`]. There is an implicit requirement that a new resumption frontier is
+    /// converted into an lsn relatively soon after startup.
+    ///
+    /// A busy upstream on the other hand can request status updates every few messages, so
+    /// requested ones are sent at most once every `min_interval`, as each of them is a round-trip
+    /// interleaved with the data.
+    ///
+    /// See: https://www.postgresql.org/message-id/CAMsr+YE2dSfHVr7iEv1GSPZihitWX-PMkD9QALEGcTYa+sdsgg@mail.gmail.com
+    fn due(&self, min_interval: Duration) -> bool {
+        let elapsed = self.last_sent.elapsed();
+        elapsed > FEEDBACK_INTERVAL
+            || (self.reply_requested && (self.reported_lsn.is_none() || elapsed >= min_interval))
+    }
+
+    /// Returns how long a requested status update has left to wait for `min_interval` to pass,
+    /// if one is pending.
+    fn reply_delay(&self, min_interval: Duration) -> Option<Duration> {
+        self.reply_requested
+            .then(|| min_interval.saturating_sub(self.last_sent.elapsed()))
+    }
+
+    /// Reports to the upstream that all changes up to `lsn` have been durably recorded.
+    async fn send<S: ReplicationUpstream>(
+        &mut self,
+        stream: &mut S,
+        lsn: PgLsn,
+    ) -> Result<(), ReplicationError> {
+        stream.send_feedback(lsn).await?;
+        self.last_sent = Instant::now();
+        self.reported_lsn = Some(lsn);
+        self.reply_requested = false;
+        Ok(())
+    }
+
+    /// Reports the changes committed downstream up to `committed_lsn` to the upstream, if a
+    /// status update is due.
+    async fn send_if_due<S: ReplicationUpstream>(
+        &mut self,
+        stream: &mut S,
+        committed_lsn: &AtomicU64,
+        min_interval: Duration,
+    ) -> Result<(), ReplicationError> {
+        if self.due(min_interval) {
+            let lsn = PgLsn::from(committed_lsn.load(Ordering::SeqCst));
+            self.send(stream, lsn).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Waits while the changes emitted up to `emitted_lsn` are more WAL bytes ahead of
+/// `committed_lsn` than the backpressure limit allows, i.e. while downstream can't keep up, so
+/// that the WAL stays upstream instead of piling up in memory. Status updates keep being sent in
+/// the meantime, so that the upstream doesn't time out the connection.
+///
+/// Returns whether it waited.
+async fn wait_for_downstream<S: ReplicationUpstream>(
+    stream: &mut S,
+    emitted_lsn: PgLsn,
+    committed_lsn: &AtomicU64,
+    feedback: &mut Feedback,
+    limits: &PgSourceLimits,
+    metrics: &PgSourceMetrics,
+) -> Result<bool, ReplicationError> {
+    let lag = || u64::from(emitted_lsn).saturating_sub(committed_lsn.load(Ordering::SeqCst));
+    if lag() <= limits.backpressure_lag_bytes() {
+        return Ok(false);
+    }
+    info!(
+        "emitted changes are {} bytes of WAL ahead of downstream, pausing the replication stream",
+        lag()
+    );
+    metrics.backpressure_active.set(1);
+    let start = Instant::now();
+    let waited = async {
+        while lag() > limits.backpressure_lag_bytes() {
+            feedback
+                .send_if_due(stream, committed_lsn, limits.min_feedback_interval())
+                .await?;
+            tokio::time::sleep(BACKPRESSURE_POLL_INTERVAL).await;
+        }
+        Ok::<_, ReplicationError>(())
+    }
+    .await;
+    metrics.backpressure_active.set(0);
+    metrics
+        .backpressure_seconds
+        .inc_by(start.elapsed().as_secs_f64());
+    info!("downstream caught up, resuming the replication stream");
+    waited.map(|()| true)
+}
+
+/// Waits until downstream has committed the changes emitted up to `emitted_lsn`. Status updates
+/// keep being sent in the meantime, so that the upstream doesn't time out the connection.
+async fn wait_for_committed<S: ReplicationUpstream>(
+    stream: &mut S,
+    emitted_lsn: PgLsn,
+    committed_lsn: &AtomicU64,
+    feedback: &mut Feedback,
+    limits: &PgSourceLimits,
+) -> Result<(), ReplicationError> {
+    while committed_lsn.load(Ordering::SeqCst) < u64::from(emitted_lsn) {
+        feedback
+            .send_if_due(stream, committed_lsn, limits.min_feedback_interval())
+            .await?;
+        tokio::time::sleep(BACKPRESSURE_POLL_INTERVAL).await;
+    }
+    Ok(())
+}
+
+/// Reports the health of the replication loop through `sender`, if any: as stalled with an
+/// `error` summarizing the iterations that made no progress, or as running again once it does.
+async fn report_loop_health(sender: Option<&MessageSender>, error: Option<String>) {
+    let sender = match sender {
+        Some(sender) => sender,
+        None => return,
+    };
+    let update = match error {
+        Some(error) => HealthStatus::StalledWithError {
+            error,
+            hint: Some(
+                "The source keeps leaving its replication stream without receiving any changes, \
+                 e.g. because peeking into its replication slot is slow or reconnecting fails."
+                    .into(),
+            ),
+        },
+        None => HealthStatus::Running,
+    };
+    sender
+        .send(InternalMessage::Status(HealthStatusUpdate {
+            update,
+            should_halt: false,
+            details: None,
+        }))
+        .await;
+}
+
+// TODO(guswynn|petrosagg): fix the underlying bug that prevents client re-use
+// when exiting the CopyBoth mode, so we don't need to re-create clients in every loop
+// in this function.
+async fn produce_replication<'a>(
+    connections: &'a UpstreamConnections,
+    slot: &'a str,
+    publications: &'a [String],
+    as_of: PgLsn,
+    committed_lsn: Arc<AtomicU64>,
+    metrics: &'a PgSourceMetrics,
+    limits: &'a PgSourceLimits,
+    source_tables: &'a BTreeMap<u32, SourceTable>,
+    replication_plugin: ReplicationPlugin,
+    streaming: bool,
+    max_transaction_rows: Option<usize>,
+    ping_interval: Option<Duration>,
+    log_dedup: &'a mut LogDedup,
+    table_stats: &'a mut TableStats,
+    fast_forward_mode: &'a mut FastForwardMode,
+    wal_capture: Option<&'a WalCapture>,
+    status_sender: Option<MessageSender>,
+    backpressure: bool,
+    truncate_retractions: Option<&'a TruncateRetractions<'a>>,
+) -> impl futures::Stream<
+    Item = Result<Event<[PgLsn; 1], (usize, TableRow, Diff, TransactionInfo)>, ReplicationError>,
+> + 'a {
+    async_stream::try_stream!({
+        let mut state = ReplicationState::new(as_of);
+        metrics.transaction_buffer_memory_bytes.set(0);
+        let mut watchdog = LoopWatchdog::new(THRASHING_ITERATIONS);
+        // The outer loop alternates the client between streaming the replication slot and using
+        // normal SQL queries with pg admin functions to fast-foward our cursor in the event of WAL
+        // lag.
+        //
+        // TODO(petrosagg): we need to do the above because a replication slot can be active only
+        // one place which is why we need to do this dance of entering and exiting replication mode
+        // in order to be able to use the administrative functions below. Perhaps it's worth
+        // creating two independent slots so that we can use the secondary to check without
+        // interrupting the stream on the first one
+        metrics
+            .fast_forward_mode
+            .set(fast_forward_mode.metric_value());
+        loop {
+            let span = replication_span(slot, publications, state.last_commit_lsn);
+            // How long the stream may lag behind the upstream before we leave it to peek.
+            let wal_lag_grace_period = match *fast_forward_mode {
+                FastForwardMode::Peek | FastForwardMode::LimitedPeek => WAL_LAG_GRACE_PERIOD,
+                FastForwardMode::Disabled => Duration::MAX,
+            };
+            // Whether this iteration emitted data or advanced the frontier.
+            let mut progressed = false;
+            // Connecting resolves the upstream hostnames anew, so every iteration follows a
+            // failover to whichever server they point at by then.
+            let connect_start = Instant::now();
+            let client = connections
+                .replication()
+                .instrument(span.clone())
+                .await
+                .err_indefinite()?;
+            tracing::trace!(parent: &span, "starting replication slot");
+            let options = match replication_plugin {
+                ReplicationPlugin::PgOutput => {
+                    let version = if streaming {
+                        r#""proto_version" '2', "streaming" 'on'"#
+                    } else {
+                        r#""proto_version" '1'"#
+                    };
+                    format!(
+                        r#"({version}, "publication_names" '{}')"#,
+                        publication_names_option(publications)
+                    )
+                }
+                // decoderbufs takes no options and sends the changes of all tables.
+                ReplicationPlugin::DecoderBufs => String::new(),
+            };
+            let query =
+                start_replication_command(slot, CommitLsn::new(state.last_commit_lsn), &options);
+            let copy_stream = client
+                .copy_both_simple(&query)
+                .instrument(span.clone())
+                .await
+                .err_indefinite()?;
+            metrics.replication_connections.inc();
+            let connect_time = connect_start.elapsed();
+            metrics
+                .replication_connect_duration
+                .observe(connect_time.as_secs_f64());
+            watchdog.record(Phase::Connecting, connect_time);
+
+            // This may not be required, but as mentioned above in
+            // `postgres_replication_loop_inner`, we drop clients aggressively out of caution, so
+            // the stream is dropped as soon as it has been consumed.
+            let events = match replication_plugin {
+                ReplicationPlugin::PgOutput => {
+                    let stream = PgReplicationStream {
+                        stream: Box::pin(ReplicationStream::new(copy_stream)),
+                        connections,
+                        publications,
+                        wal_capture,
+                    };
+                    Either::Left(consume_replication_stream(
+                        stream,
+                        &mut state,
+                        &committed_lsn,
+                        metrics,
+                        limits,
+                        source_tables,
+                        streaming,
+                        max_transaction_rows,
+                        ping_interval,
+                        wal_lag_grace_period,
+                        log_dedup,
+                        table_stats,
+                        status_sender.as_ref(),
+                        backpressure,
+                        truncate_retractions,
+                        &span,
+                    ))
+                }
+                ReplicationPlugin::DecoderBufs => {
+                    let stream = DecoderBufsStream::new(
+                        ReplicationStream::new(copy_stream),
+                        connections,
+                        publications,
+                        source_tables,
+                        wal_capture,
+                    );
+                    Either::Right(consume_replication_stream(
+                        stream,
+                        &mut state,
+                        &committed_lsn,
+                        metrics,
+                        limits,
+                        source_tables,
+                        streaming,
+                        max_transaction_rows,
+                        ping_interval,
+                        wal_lag_grace_period,
+                        log_dedup,
+                        table_stats,
+                        status_sender.as_ref(),
+                        backpressure,
+                        truncate_retractions,
+                        &span,
+                    ))
+                }
+            };
+            let streaming_start = Instant::now();
+            let mut events = Box::pin(events);
+            while let Some(event) = events.next().await {
+                let event = event?;
+                if !progressed {
+                    progressed = true;
+                    if watchdog.progressed() {
+                        report_loop_health(status_sender.as_ref(), None).await;
+                    }
+                }
+                yield event;
+            }
+            drop(events);
+            // The connection that peeks into the slot is only opened once the streaming one is
+            // closed.
+            drop(client);
+            let session_time = streaming_start.elapsed();
+            metrics
+                .replication_session_duration
+                .observe(session_time.as_secs_f64());
+            watchdog.record(Phase::Streaming, session_time);
+
+            let connect_start = Instant::now();
+            let client = connections.replication().await.err_indefinite()?;
+            let connect_time = connect_start.elapsed();
+            metrics
+                .replication_connect_duration
+                .observe(connect_time.as_secs_f64());
+            watchdog.record(Phase::Connecting, connect_time);
+            let peek_start = Instant::now();
+
+            // We reach this place if the consume loop above detected large WAL lag. This
+            // section determines whether or not we can skip over that part of the WAL by
+            // peeking into the replication slot using a normal SQL query and the
+            // `pg_logical_slot_peek_binary_changes` administrative function.
+            //
+            // By doing so we can get a positive statement about existence or absence of
+            // relevant data from the current LSN to the observed WAL end. If there are no
+            // messages then it is safe to fast forward last_commit_lsn to the WAL end LSN and restart
+            // the replication stream from there.
+            let query = match replication_plugin {
+                ReplicationPlugin::PgOutput => format!(
+                    "SELECT lsn FROM pg_logical_slot_peek_binary_changes(
+                         '{name}', NULL, NULL,
+                         'proto_version', '1',
+                         'publication_names', '{publication_names}'
+                    )",
+                    name = &slot,
+                    publication_names = publication_names_option(publications),
+                ),
+                ReplicationPlugin::DecoderBufs => format!(
+                    "SELECT lsn FROM pg_logical_slot_peek_binary_changes('{slot}', NULL, NULL)"
+                ),
+            };
+
+            // Lets tests exercise the limited peeks of upstreams that don't let us peek.
+            if fail::eval("pg_peek_unsupported", |_| ()).is_some() {
+                *fast_forward_mode = FastForwardMode::LimitedPeek;
+            }
+            metrics.peeks.inc();
+            let peek_binary_start_time = Instant::now();
+            // The number of changes up to the WAL end, if we could find out.
+            let mut changes = None;
+            if *fast_forward_mode == FastForwardMode::Peek {
+                let peek_result = client.simple_query(&query).await;
+                metrics
+                    .peek_duration
+                    .observe(peek_binary_start_time.elapsed().as_secs_f64());
+                match peek_result {
+                    Ok(res) => {
+                        let peeked = count_peeked_changes(rows(&res), state.last_commit_lsn)
+                            .err_indefinite()?;
+                        changes = Some(peeked);
+                    }
+                    // Providers may not let us call the function we peek with, which only costs
+                    // us the fast-forward, so we fall back to a cheaper check instead of failing.
+                    Err(err) if is_unsupported_statement_error(&err) => {
+                        warn!(
+                            parent: &span,
+                            slot = ?slot,
+                            "falling back to limited peeks after failing to peek: {err}"
+                        );
+                        *fast_forward_mode = FastForwardMode::LimitedPeek;
+                    }
+                    Err(err) => Err(err).err_during(Operation::Peek)?,
+                }
+            }
+            if *fast_forward_mode == FastForwardMode::LimitedPeek {
+                let limited_peek_start = Instant::now();
+                let peek = limited_peek(
+                    &client,
+                    slot,
+                    publications,
+                    replication_plugin,
+                    state.last_commit_lsn,
+                    state.observed_wal_end,
+                )
+                .await?;
+                metrics
+                    .peek_duration
+                    .observe(limited_peek_start.elapsed().as_secs_f64());
+                match peek {
+                    LimitedPeek::Skippable => changes = Some(0),
+                    LimitedPeek::Inconclusive => {}
+                    LimitedPeek::Unsupported(err) => {
+                        warn!(
+                            parent: &span,
+                            slot = ?slot,
+                            "disabling fast-forwarding over WAL lag after failing to peek: {err}"
+                        );
+                        *fast_forward_mode = FastForwardMode::Disabled;
+                    }
+                }
+            }
+            metrics
+                .fast_forward_mode
+                .set(fast_forward_mode.metric_value());
+            watchdog.record(Phase::Peeking, peek_start.elapsed());
+            // Fast-forwarding over changes we know to be absent advances the frontier below.
+            if progressed || changes == Some(0) {
+                if watchdog.progressed() {
+                    report_loop_health(status_sender.as_ref(), None).await;
+                }
+            } else if let Some(summary) = watchdog.idle_iteration() {
+                warn!(parent: &span, slot = ?slot, "{summary}");
+                report_loop_health(status_sender.as_ref(), Some(summary)).await;
+            }
+            // Without knowing about the changes, we reconnect the stream where we left it.
+            let changes = match changes {
+                Some(changes) => changes,
+                None => continue,
+            };
+
+            // If there are no changes until the end of the WAL it's safe to fast forward
+            if changes == 0 {
+                metrics.fast_forwards.inc();
+                metrics.wal_bytes_skipped.inc_by(
+                    u64::from(state.observed_wal_end)
+                        .saturating_sub(u64::from(state.last_commit_lsn)),
+                );
+                tracing::info!(
+                    parent: &span,
+                    from_lsn = %state.last_commit_lsn,
+                    to_lsn = %state.observed_wal_end,
+                    "fast-forward"
+                );
+                state.last_commit_lsn = state.observed_wal_end;
+                // `Progress` events are _frontiers_, so we add 1, just like when we
+                // handle data in `Commit` above.
+                yield Event::Progress([CommitLsn::new(state.last_commit_lsn).to_frontier().into()]);
+            }
+
+            tracing::info!(
+                parent: &span,
+                slot = ?slot,
+                query_time = ?peek_binary_start_time.elapsed(),
+                current_lsn = ?state.last_commit_lsn,
+                "Found {} changes in the wal.",
+                changes
+            );
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::fmt::Debug;
+    use std::sync::Mutex;
+
+    use bytes::Bytes;
+    use mz_build_info::DUMMY_BUILD_INFO;
+    use mz_expr::UnaryFunc;
+    use mz_ore::metrics::MetricsRegistry;
+    use mz_ore::now::SYSTEM_TIME;
+    use mz_persist_client::cfg::PersistConfig;
+    use mz_postgres_util::desc::{PostgresColumnDesc, PostgresKeyDesc};
+    use mz_repr::adt::array::ArrayDimension;
+    use mz_repr::ScalarType;
+    use mz_storage_client::types::connections::{StringOrSecret, Tunnel};
+    use mz_storage_client::types::sources::SnapshotFormat;
+    use tokio_postgres::config::SslMode;
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id};
+    use tracing::Subscriber;
+    use tracing_subscriber::layer::{Context, SubscriberExt};
+    use tracing_subscriber::Layer;
+
+    use super::replication::datums_from_tuple;
+    use super::snapshot::ordered_snapshot_query;
+    use super::*;
+    use crate::source::metrics::SourceBaseMetrics;
+
+    /// A captured span: its name and the rendered value of each of its fields.
+    type CapturedSpan = (&'static str, Vec<(&'static str, String)>);
+
+    /// A layer that records every span created while it is installed.
+    #[derive(Clone, Default)]
+    struct SpanCapture(Arc<Mutex<Vec<CapturedSpan>>>);
+
+    struct FieldCapture<'a>(&'a mut Vec<(&'static str, String)>);
+
+    impl Visit for FieldCapture<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            self.0.push((field.name(), format!("{value:?}")));
+        }
+    }
+
+    impl<S: Subscriber> Layer<S> for SpanCapture {
+        fn on_new_span(&self, attrs: &Attributes<'_>, _id: &Id, _ctx: Context<'_, S>) {
+            let mut fields = vec![];
+            attrs.record(&mut FieldCapture(&mut fields));
+            self.0
+                .lock()
+                .expect("lock poisoned")
+                .push((attrs.metadata().name(), fields));
+        }
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("leaf error")]
+    struct LeafError;
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("wrapping error")]
+    struct WrappingError(#[source] std::io::Error);
+
+    #[test]
+    fn find_nested_source() {
+        let io_err = std::io::Error::new(std::io::ErrorKind::Other, LeafError);
+        assert!(find_source::<LeafError>(&io_err).is_some());
+
+        let wrapped = WrappingError(io_err);
+        assert!(find_source::<LeafError>(&wrapped).is_some());
+        assert!(find_source::<WrappingError>(&wrapped).is_some());
+        assert!(find_source::<DbError>(&wrapped).is_none());
+
+        let err = anyhow::Error::new(wrapped).context("replication failed");
+        assert!(err.chain().find_map(find_source::<LeafError>).is_some());
+        assert_eq!(UpstreamErrorDetails::from_error(&err), None);
+        assert_eq!(
+            describe_error(&err),
+            "replication failed: wrapping error: leaf error"
+        );
+    }
+
+    #[test]
+    fn connection_errors() {
+        let reset = std::io::Error::new(std::io::ErrorKind::ConnectionReset, LeafError);
+        let err = anyhow::Error::new(WrappingError(reset)).context("snapshot failed");
+        assert!(is_connection_error(&err));
+
+        let err = anyhow::Error::new(LeafError).context("snapshot failed");
+        assert!(!is_connection_error(&err));
+    }
+
+    #[test]
+    fn snapshot_order() {
+        let table = |output_index| source_table(output_index, table_desc());
+        // OIDs are not assigned in output order.
+        let tables = BTreeMap::from([
+            (30, table(1)),
+            (10, table(2)),
+            (20, table(3)),
+            (40, table(4)),
+        ]);
+        let estimate = |bytes| TableEstimate { rows: None, bytes };
+        let estimates = BTreeMap::from([
+            (30, estimate(8192)),
+            (10, estimate(8192)),
+            (20, estimate(1 << 20)),
+        ]);
+        let order = |order| -> Vec<u32> {
+            order_tables(order, &tables, &estimates)
+                .into_iter()
+                .map(|(oid, _)| oid)
+                .collect()
+        };
+        // Tables of the same size keep their output order, and tables without an estimate go last.
+        assert_eq!(order(SnapshotOrder::Size), vec![20, 30, 10, 40]);
+        assert_eq!(order(SnapshotOrder::OutputIndex), vec![30, 10, 20, 40]);
+    }
+
+    #[test]
+    fn upstream_error_details_display() {
+        let mut details = UpstreamErrorDetails {
+            code: "42P01".into(),
+            message: "relation \"t1\" does not exist".into(),
+            detail: None,
+            hint: None,
+            schema: None,
+            table: None,
+        };
+        assert_eq!(
+            details.to_string(),
+            r#"code=42P01 message="relation \"t1\" does not exist""#
+        );
+
+        details.hint = Some("create it".into());
+        details.table = Some("t1".into());
+        assert_eq!(
+            details.to_string(),
+            r#"code=42P01 message="relation \"t1\" does not exist" hint="create it" table="t1""#
+        );
+    }
+
+    #[test]
+    fn retryable_sqlstates() {
+        // canceling statement due to conflict with recovery
+        assert!(is_retryable_sqlstate("40001"));
+        assert!(is_retryable_sqlstate("40P01"));
+        // canceling statement due to statement timeout
+        assert!(is_retryable_sqlstate("57014"));
+        // terminating connection due to administrator command
+        assert!(!is_retryable_sqlstate("57P01"));
+        assert!(!is_retryable_sqlstate("42P01"));
+
+        let err = anyhow!("connection reset");
+        assert!(!is_retryable_error(&err));
+    }
+
+    #[test]
+    fn operation_sqlstates() {
+        // undefined_function, e.g. on a provider that blocks pg_logical_slot_peek_binary_changes
+        assert!(is_unsupported_statement_sqlstate("42883"));
+        assert!(is_definite_sqlstate("42883"));
+        assert!(!Operation::Peek.is_definite_sqlstate("42883"));
+        // insufficient_privilege to create a replication slot
+        assert!(!Operation::SlotCreation.is_definite_sqlstate("42501"));
+        // insufficient_privilege to copy a table the source ingests, which can be granted
+        assert!(!Operation::SnapshotCopy.is_definite_sqlstate("42501"));
+        assert!(!is_definite_sqlstate("42501"));
+        // invalid_schema_name
+        assert!(Operation::SnapshotCopy.is_definite_sqlstate("3F000"));
+        assert!(Operation::Peek.is_definite_sqlstate("3F000"));
+        // connection_failure
+        assert!(!Operation::SnapshotCopy.is_definite_sqlstate("08006"));
+        assert!(!is_unsupported_statement_sqlstate("08006"));
+
+        // Errors are tagged with the operation that raised them.
+        let io_err = std::io::Error::new(std::io::ErrorKind::Other, "connection reset");
+        let err = Err::<(), _>(io_err)
+            .err_during(Operation::Peek)
+            .unwrap_err();
+        match err {
+            ReplicationError::Indefinite(err) => {
+                assert_eq!(err.downcast_ref::<Operation>(), Some(&Operation::Peek));
+                assert!(err
+                    .to_string_alt()
+                    .starts_with("peeking into the replication slot: "));
+            }
+            err => panic!("unexpected error {err:?}"),
+        }
+    }
+
+    #[test]
+    fn fast_forward_modes() {
+        // Sources start out peeking, and report their mode in a metric whose values increase as
+        // the mode degrades.
+        assert_eq!(FastForwardMode::default(), FastForwardMode::Peek);
+        let values: Vec<_> = [
+            FastForwardMode::Peek,
+            FastForwardMode::LimitedPeek,
+            FastForwardMode::Disabled,
+        ]
+        .into_iter()
+        .map(FastForwardMode::metric_value)
+        .collect();
+        assert_eq!(values, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn snapshot_killed() {
+        // canceling statement due to "snapshot too old"
+        assert!(is_snapshot_killed_sqlstate("72000"));
+        // terminating connection due to administrator command
+        assert!(is_snapshot_killed_sqlstate("57P01"));
+        assert!(!is_snapshot_killed_sqlstate("57014"));
+
+        let killed = SnapshotKilled {
+            table: "t1".into(),
+            rows: 1000,
+            tables_done: 2,
+            tables_total: 5,
+        };
+        let err = anyhow!("terminating connection due to administrator command").context(killed);
+        assert_eq!(
+            describe_error(&err),
+            "snapshot killed upstream after copying 1000 rows of table t1 (2 of 5 tables done): \
+             terminating connection due to administrator command"
+        );
+        let killed = err.downcast_ref::<SnapshotKilled>().expect("known context");
+        assert!(killed.hint().contains("SNAPSHOT STANDBY"));
+        assert!(!killed.hint().contains("SNAPSHOT CURSOR FETCH SIZE"));
+    }
+
+    #[test]
+    fn provider_errors() {
+        let classify = |code: &str, message: &str| {
+            ProviderError::from_details(&UpstreamErrorDetails {
+                code: code.into(),
+                message: message.into(),
+                detail: None,
+                hint: None,
+                schema: None,
+                table: None,
+            })
+        };
+        assert_eq!(
+            classify("55000", "logical decoding requires wal_level >= logical"),
+            Some(ProviderError::LogicalReplicationDisabled)
+        );
+        assert_eq!(
+            classify(
+                "55000",
+                r#"logical decoding requires "wal_level" >= "logical""#
+            ),
+            Some(ProviderError::LogicalReplicationDisabled)
+        );
+        assert_eq!(
+            classify("53300", r#"too many connections for role "materialize""#),
+            Some(ProviderError::TooManyConnections)
+        );
+        assert_eq!(
+            classify(
+                "42704",
+                r#"replication slot "materialize_1" does not exist"#
+            ),
+            Some(ProviderError::SlotMissing)
+        );
+        assert_eq!(
+            classify(
+                "55000",
+                r#"can no longer get changes from replication slot "materialize_1""#
+            ),
+            Some(ProviderError::SlotInvalidated)
+        );
+        assert_eq!(
+            classify(
+                "55000",
+                r#"cannot read from logical replication slot "materialize_1""#
+            ),
+            Some(ProviderError::SlotInvalidated)
+        );
+        // The same SQLSTATEs with other messages are left alone.
+        assert_eq!(classify("42704", r#"type "foo" does not exist"#), None);
+        assert_eq!(classify("53300", "sorry, too many clients already"), None);
+        assert_eq!(classify("55000", "cannot perform this operation now"), None);
+
+        // Retrying after a failover won't bring the slot back.
+        let err = ReplicationError::Indefinite(anyhow!("replication slot gone"));
+        assert!(matches!(
+            classify_provider_error(err, "materialize_1"),
+            ReplicationError::Indefinite(_)
+        ));
+        assert_eq!(
+            ProviderError::SlotMissing
+                .source_error("materialize_1", "gone".into())
+                .map(|details| details.to_string().starts_with(
+                    "replication slot materialize_1 can no longer be read: \
+                     it no longer exists upstream"
+                )),
+            Some(true)
+        );
+        assert_eq!(
+            ProviderError::TooManyConnections.source_error("materialize_1", "busy".into()),
+            None
+        );
+        assert!(matches!(
+            ProviderError::LogicalReplicationDisabled.source_error("materialize_1", "off".into()),
+            Some(SourceErrorDetails::UpstreamMisconfigured { hint, .. })
+                if hint.contains("rds.logical_replication")
+        ));
+    }
+
+    #[test]
+    fn missing_privileges() {
+        let classify = |code: &str, message: &str, detail: Option<&str>| {
+            let details = UpstreamErrorDetails {
+                code: code.into(),
+                message: message.into(),
+                detail: detail.map(str::to_string),
+                hint: None,
+                schema: None,
+                table: None,
+            };
+            (
+                MissingPrivilege::from_details(&details, None),
+                MissingPrivilege::from_details(&details, Some(&table_desc())),
+            )
+        };
+        let select = MissingPrivilege::Select {
+            schema: "public".into(),
+            table: "t1".into(),
+        };
+        assert_eq!(
+            classify(
+                "42501",
+                "must be superuser or replication role to use replication slots",
+                None
+            ),
+            (
+                Some(MissingPrivilege::Replication),
+                Some(MissingPrivilege::Replication)
+            )
+        );
+        assert_eq!(
+            classify(
+                "42501",
+                "permission denied to start WAL sender",
+                Some("Only roles with the REPLICATION attribute may start a WAL sender process.")
+            ),
+            (
+                Some(MissingPrivilege::Replication),
+                Some(MissingPrivilege::Replication)
+            )
+        );
+        let usage = MissingPrivilege::SchemaUsage {
+            schema: "private".into(),
+        };
+        assert_eq!(
+            classify("42501", "permission denied for schema private", None),
+            (Some(usage.clone()), Some(usage))
+        );
+        // The table is only known while it is being copied.
+        assert_eq!(
+            classify("42501", "permission denied for table t1", None),
+            (Some(MissingPrivilege::Other), Some(select.clone()))
+        );
+        assert_eq!(
+            classify("42501", "permission denied for relation t1", None),
+            (Some(MissingPrivilege::Other), Some(select.clone()))
+        );
+        assert_eq!(
+            classify("42P01", r#"relation "t1" does not exist"#, None),
+            (None, None)
+        );
+
+        // The privileges found missing before copying are recognized as such.
+        let err = anyhow::Error::msg(select.clone());
+        assert_eq!(
+            MissingPrivilege::from_error(&err, None),
+            Some(select.clone())
+        );
+        assert_eq!(
+            describe_error(&err.context("snapshot failed")),
+            "snapshot failed: the upstream role lacks SELECT on table public.t1"
+        );
+
+        assert_eq!(
+            select.hint(Some("materialize")),
+            r#"Run GRANT SELECT ON "public"."t1" TO "materialize"; upstream (on PostgreSQL 14 and "#
+                .to_owned()
+                + r#"later, GRANT pg_read_all_data TO "materialize"; grants SELECT on all tables). "#
+                + "The source retries every 60s and recovers once the privilege is granted."
+        );
+        assert!(MissingPrivilege::Replication
+            .hint(Some("materialize"))
+            .starts_with(r#"Run ALTER ROLE "materialize" WITH REPLICATION; upstream"#));
+        assert!(MissingPrivilege::Other.hint(None).contains("<role>"));
+        assert!(!select.grant(None).contains("retries"));
+    }
+
+    #[test]
+    fn peeked_changes() {
+        let row = |lsn| BTreeMap::from([("lsn", lsn)]);
+        let last_commit_lsn = PgLsn::from(0x20);
+
+        assert_eq!(
+            count_peeked_changes(
+                &[row(Some("0/10")), row(Some("0/20")), row(Some("0/30"))],
+                last_commit_lsn
+            )
+            .unwrap(),
+            1
+        );
+        let empty: [BTreeMap<&str, Option<&str>>; 0] = [];
+        assert_eq!(count_peeked_changes(&empty, last_commit_lsn).unwrap(), 0);
+
+        let err = count_peeked_changes(&[row(Some("0/30")), row(Some("bogus"))], last_commit_lsn)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string_alt(),
+            r#"invalid replication slot peek after 0/20: invalid value "bogus" in column lsn: invalid LSN"#
+        );
+        let err = count_peeked_changes(&[row(None)], last_commit_lsn).unwrap_err();
+        assert_eq!(
+            err.to_string_alt(),
+            "invalid replication slot peek after 0/20: unexpected NULL in column lsn"
+        );
+        let err = count_peeked_changes(
+            &[BTreeMap::from([("location", Some("0/30"))])],
+            last_commit_lsn,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string_alt(),
+            "invalid replication slot peek after 0/20: missing expected column: lsn"
+        );
+    }
+
+    #[test]
+    fn upstream_commit_time() {
+        // 2000-01-01T00:00:00Z
+        assert_eq!(pg_timestamp_to_unix_millis(0), Some(946_684_800_000));
+        assert_eq!(pg_timestamp_to_unix_millis(1_999), Some(946_684_800_001));
+        assert_eq!(pg_timestamp_to_unix_millis(-1), Some(946_684_799_999));
+    }
+
+    #[test]
+    fn primary_keys() {
+        let key = |cols: Vec<u16>, is_primary| PostgresKeyDesc {
+            oid: 1,
+            name: "k".into(),
+            cols,
+            is_primary,
+            nulls_not_distinct: false,
+        };
+        let mut desc = table_desc();
+        for (i, column) in desc.columns.iter_mut().enumerate() {
+            column.col_num = Some(u16::try_from(i + 1).unwrap());
+        }
+        let value = text_row(&[Some("a"), Some("b")]);
+        let mut datum_vec = DatumVec::new();
+
+        // Without a primary key every row has the empty key.
+        desc.keys.insert(key(vec![1], false));
+        assert_eq!(primary_key_indices(&desc), Vec::<usize>::new());
+        assert_eq!(extract_key(&value, &[], &mut datum_vec), Row::default());
+
+        // Key columns are extracted in the order of the key, not of the table.
+        desc.keys.insert(key(vec![2, 1], true));
+        let indices = primary_key_indices(&desc);
+        assert_eq!(indices, vec![1, 0]);
+        assert_eq!(
+            extract_key(&value, &indices, &mut datum_vec),
+            text_row(&[Some("b"), Some("a")])
+        );
+
+        // Keys over columns whose position is unknown are not extracted.
+        desc.columns[0].col_num = None;
+        assert_eq!(primary_key_indices(&desc), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn lsns_are_closed_by_their_last_message() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let (tx, mut rx) = tokio::sync::mpsc::channel(10);
+        let mut sender = RowSender::new(tx, Arc::new(test_metrics()), PgLsn::from(0x8));
+        runtime.block_on(async {
+            for row in ["a", "b", "c"] {
+                sender
+                    .send_row(1, Ok(text_row(&[Some(row)])), PgLsn::from(0x10), 1, None)
+                    .await;
+            }
+            sender.close_lsn(PgLsn::from(0x10)).await;
+            sender
+                .send_row(1, Ok(text_row(&[Some("d")])), PgLsn::from(0x20), 1, None)
+                .await;
+            sender.close_lsn(PgLsn::from(0x20)).await;
+            // Closing an LSN without messages sends nothing.
+            sender.close_lsn(PgLsn::from(0x30)).await;
+        });
+
+        let mut closed = vec![];
+        while let Ok(message) = rx.try_recv() {
+            let InternalMessage::Value { lsn, end, .. } = message else {
+                panic!("unexpected message");
+            };
+            closed.push((lsn, end));
+        }
+        assert_eq!(
+            closed,
+            vec![
+                (PgLsn::from(0x10), false),
+                (PgLsn::from(0x10), false),
+                (PgLsn::from(0x10), true),
+                (PgLsn::from(0x20), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn replication_stops_once_the_source_is_gone() {
+        let source_id = GlobalId::User(1);
+        let (tx, mut rx) = tokio::sync::mpsc::channel(10);
+        let pauses = PgSourcePauses::default();
+        let pause = pauses.signal(source_id);
+        let thread = spawn_replication_thread(source_id, move || async move {
+            // Nothing listens on the port, so every attempt to replicate fails and is retried.
+            let config = mz_postgres_util::Config::new(
+                "host=127.0.0.1 port=1 user=postgres".parse().unwrap(),
+                mz_postgres_util::TunnelConfig::Direct,
+            )
+            .unwrap();
+            let metrics = Arc::new(test_metrics());
+            let limits = Arc::new(PgSourceLimits::default());
+            let task_info = PostgresTaskInfo {
+                source_id,
+                connections: UpstreamConnections::new(config, Arc::clone(&metrics), None),
+                publications: vec!["mz_source".into()],
+                pending_publications: None,
+                publication_tables: vec![],
+                status_details: SourceStatusDetails::default(),
+                slot: "mz_slot".into(),
+                replication_lsn: PgLsn::from(0),
+                metrics: Arc::clone(&metrics),
+                source_tables: BTreeMap::new(),
+                excluded_tables: vec![],
+                row_sender: RowSender::new(tx, metrics, PgLsn::from(0)),
+                resume_lsn: Arc::new(AtomicU64::new(0)),
+                limits: Arc::clone(&limits),
+                pause,
+                streaming_transactions: false,
+                max_transaction_rows: None,
+                ping_interval: None,
+                snapshot_cursor_fetch_size: None,
+                snapshot_statement_timeout: None,
+                copy_read_timeout: PostgresSourceConnection::DEFAULT_COPY_READ_TIMEOUT,
+                resnapshot_on_schema_change: false,
+                retract_on_truncate: false,
+                snapshot_unlogged_tables: false,
+                replication_plugin: ReplicationPlugin::PgOutput,
+                synchronize_replicas: false,
+                snapshot_order: SnapshotOrder::default(),
+                snapshot_decoder: copy::decoder(SnapshotFormat::Text),
+                snapshot_config: None,
+                wal_capture: None,
+                outputs: BTreeMap::new(),
+                provenance_headers: false,
+                persist_clients: Arc::new(PersistClientCache::new(
+                    PersistConfig::new(&DUMMY_BUILD_INFO, SYSTEM_TIME.clone()),
+                    &MetricsRegistry::new(),
+                )),
+                log_dedup: LogDedup::new(log_dedup::DEFAULT_WINDOW),
+                table_stats: TableStats::new(source_id, table_stats::DEFAULT_INTERVAL),
+                schema_audit: SchemaAudit::new(limits.schema_audit_interval()),
+                fast_forward_mode: FastForwardMode::default(),
+                snapshot_attempts: Arc::new(PgSnapshotAttempts::default()),
+            };
+            postgres_replication_loop(task_info).await
+        });
+
+        // The source stalls while it can't connect.
+        loop {
+            match rx.blocking_recv() {
+                Some(InternalMessage::Status(HealthStatusUpdate {
+                    update: HealthStatus::StalledWithError { .. },
+                    ..
+                })) => break,
+                Some(_) => {}
+                None => panic!("replication ended while the source was still there"),
+            }
+        }
+
+        // Dropping the source closes the channel, which ends replication before its next attempt,
+        // and with it the thread.
+        drop(rx);
+        thread.join().unwrap();
+        drop(pauses);
+    }
+
+    #[test]
+    fn provenance() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let (tx, mut rx) = tokio::sync::mpsc::channel(10);
+        let mut sender = RowSender::new(tx, Arc::new(test_metrics()), PgLsn::from(0x8));
+        let txn = TransactionInfo {
+            xid: 7,
+            commit_time_millis: Some(946_684_800_000),
+        };
+        runtime.block_on(async {
+            sender
+                .send_row(1, Ok(text_row(&[Some("a")])), PgLsn::from(0x10), 1, None)
+                .await;
+            sender.close_lsn(PgLsn::from(0x10)).await;
+            sender
+                .send_row(
+                    1,
+                    Ok(text_row(&[Some("b")])),
+                    PgLsn::from(0x20),
+                    1,
+                    Some(txn),
+                )
+                .await;
+            // Rows can still be sent at the lsn of the last row until it is closed.
+            assert_eq!(sender.lower(), PgLsn::from(0x20));
+            sender.close_lsn(PgLsn::from(0x20)).await;
+            assert_eq!(sender.lower(), PgLsn::from(0x21));
+        });
+
+        let mut headers = vec![];
+        while let Ok(message) = rx.try_recv() {
+            let InternalMessage::Value {
+                message, lsn, xid, ..
+            } = message
+            else {
+                panic!("unexpected message");
+            };
+            let message = message.unwrap();
+            assert_eq!(message.upstream_time_millis, xid.map(|_| 946_684_800_000));
+            headers.push(provenance_headers(TABLE_OID, xid, lsn));
+        }
+
+        let header = |name: &str, value: &str| (name.to_string(), Some(value.as_bytes().to_vec()));
+        assert_eq!(
+            headers,
+            vec![
+                // Snapshotted rows belong to no transaction.
+                vec![header("table_oid", "16384"), header("lsn", "0/10")],
+                vec![
+                    header("table_oid", "16384"),
+                    header("xid", "7"),
+                    header("lsn", "0/20"),
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn publication_switch() {
+        let other_table = PostgresTableDesc {
+            oid: TABLE_OID + 1,
+            name: "t2".into(),
+            ..table_desc()
+        };
+        let source_tables = BTreeMap::from([(TABLE_OID, source_table(1, table_desc()))]);
+        let publication_tables = vec![table_desc(), other_table.clone()];
+        let validate = |tables| {
+            validate_publication_switch(&["p2".into()], &publication_tables, &source_tables, tables)
+        };
+
+        validate(vec![other_table.clone(), table_desc()]).unwrap();
+
+        let err = validate(vec![table_desc()]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cannot switch to publication \"p2\", whose tables differ from those the source was \
+             created with: missing tables public.t2 (OID 16385)"
+        );
+
+        let third_table = PostgresTableDesc {
+            oid: TABLE_OID + 2,
+            name: "t3".into(),
+            ..table_desc()
+        };
+        let err = validate(vec![table_desc(), third_table]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cannot switch to publication \"p2\", whose tables differ from those the source was \
+             created with: missing tables public.t2 (OID 16385); additional tables public.t3 \
+             (OID 16386)"
+        );
+
+        // The tables ingested by the source must still be compatible.
+        let mut altered = table_desc();
+        altered.columns.truncate(1);
+        assert!(validate(vec![altered, other_table]).is_err());
+    }
+
+    #[test]
+    fn non_ingestable_publication_tables() {
+        let unsupported = |mut table: PostgresTableDesc| {
+            table.columns[1].type_oid = 16400;
+            table
+        };
+        // Ingested tables were validated when the source was planned.
+        let ingested = unsupported(table_desc());
+        let other_table = PostgresTableDesc {
+            oid: TABLE_OID + 1,
+            name: "t2".into(),
+            ..table_desc()
+        };
+        let bad_table = PostgresTableDesc {
+            oid: TABLE_OID + 2,
+            name: "t3".into(),
+            ..unsupported(table_desc())
+        };
+        let source_tables = BTreeMap::from([(TABLE_OID, source_table(1, ingested.clone()))]);
+
+        assert_eq!(
+            non_ingestable_tables(&[ingested, other_table, bad_table], &source_tables),
+            BTreeMap::from([(
+                "public.t3".into(),
+                "column b has unsupported type with OID 16400".into()
+            )])
+        );
+    }
+
+    #[test]
+    fn no_ingested_tables() {
+        let source_tables = BTreeMap::from([(TABLE_OID, source_table(1, table_desc()))]);
+        let publications = ["mz_source".to_string()];
+        validate_ingests_tables(&publications, &[table_desc()], &source_tables).unwrap();
+
+        // The publication matches no tables, e.g. FOR TABLES IN SCHEMA of an empty schema.
+        let err = validate_ingests_tables(&publications, &[], &source_tables).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"publication "mz_source" contains no tables"#
+        );
+
+        // None of the tables of the publication are ingested.
+        let err =
+            validate_ingests_tables(&publications, &[table_desc()], &BTreeMap::new()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"publication "mz_source" contains no ingestable tables"#
+        );
+
+        // The tables of several publications are validated together.
+        let publications = ["team_a".to_string(), "team_b".to_string()];
+        validate_ingests_tables(&publications, &[table_desc()], &source_tables).unwrap();
+        let err = validate_ingests_tables(&publications, &[], &source_tables).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"publications "team_a", "team_b" contain no tables"#
+        );
+    }
+
+    #[test]
+    fn publication_names_options() {
+        let names = |publication| {
+            publication_names_option(&mz_postgres_util::publication_names(publication))
+        };
+        assert_eq!(names("mz_source"), r#""mz_source""#);
+        assert_eq!(names("team_a, Team_B,"), r#""team_a","Team_B""#);
+        assert_eq!(names(r#"it's "quoted""#), r#""it''s ""quoted""""#);
+    }
+
+    #[test]
+    fn unlogged_tables() {
+        let table = |oid, output_index, name: &str| {
+            let desc = PostgresTableDesc {
+                oid,
+                name: name.into(),
+                ..table_desc()
+            };
+            source_table(output_index, desc)
+        };
+        let source_tables = BTreeMap::from([
+            (TABLE_OID, table(TABLE_OID, 1, "t1")),
+            (TABLE_OID + 1, table(TABLE_OID + 1, 2, "cache")),
+        ]);
+        let mut persistence = BTreeMap::from([(TABLE_OID, TablePersistence::Permanent)]);
+        assert_eq!(
+            snapshot_only_tables(&source_tables, &persistence, false).unwrap(),
+            Vec::<String>::new()
+        );
+
+        persistence.insert(TABLE_OID + 1, TablePersistence::Unlogged);
+        let err = snapshot_only_tables(&source_tables, &persistence, false).unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("table public.cache is UNLOGGED, so its changes are not replicated"),
+            "unexpected error: {err}"
+        );
+        assert_eq!(
+            snapshot_only_tables(&source_tables, &persistence, true).unwrap(),
+            vec!["public.cache".to_string()]
+        );
+
+        // Temporary tables are never ingested.
+        persistence.insert(TABLE_OID + 1, TablePersistence::Temporary);
+        let err = snapshot_only_tables(&source_tables, &persistence, true).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "table public.cache is temporary, so it cannot be replicated"
+        );
+    }
+
+    #[test]
+    fn allowlisted_tables() {
+        let table = |oid, output_index| {
+            source_table(
+                output_index,
+                PostgresTableDesc {
+                    oid,
+                    ..table_desc()
+                },
+            )
+        };
+        let tables = BTreeMap::from([
+            (TABLE_OID, table(TABLE_OID, 1)),
+            (TABLE_OID + 1, table(TABLE_OID + 1, 2)),
+        ]);
+        let other_table = PostgresTableDesc {
+            oid: TABLE_OID + 1,
+            ..table_desc()
+        };
+        let publication_tables = vec![table_desc(), other_table.clone()];
+
+        // Without an allowlist, all tables produce output.
+        let mut source_tables = tables.clone();
+        assert!(split_allowlisted(&mut source_tables, &publication_tables, None).is_empty());
+        assert_eq!(source_tables.len(), 2);
+
+        // Excluded tables are found among the publication tables, whether or not the source
+        // has a subsource for them.
+        let allowlist = BTreeSet::from([TABLE_OID]);
+        let mut source_tables = tables;
+        let excluded = split_allowlisted(&mut source_tables, &publication_tables, Some(&allowlist));
+        assert_eq!(source_tables.keys().collect::<Vec<_>>(), vec![&TABLE_OID]);
+        assert_eq!(excluded, vec![other_table.clone()]);
+        let mut source_tables = BTreeMap::from([(TABLE_OID, source_table(TABLE_OID, 1))]);
+        let excluded = split_allowlisted(&mut source_tables, &publication_tables, Some(&allowlist));
+        assert_eq!(excluded, vec![other_table.clone()]);
+
+        // Excluded tables must still be compatible with the publication.
+        check_excluded_tables(&excluded, &publication_tables).unwrap();
+        let dropped = check_excluded_tables(&excluded, &[table_desc()]).unwrap_err();
+        assert_eq!(
+            dropped.to_string(),
+            format!(
+                "source table t1 with oid {} has been dropped",
+                TABLE_OID + 1
+            )
+        );
+        let altered = PostgresTableDesc {
+            columns: vec![],
+            ..other_table
+        };
+        check_excluded_tables(&excluded, &[table_desc(), altered]).unwrap_err();
+    }
+
+    #[test]
+    fn expected_renames() {
+        let info = SourceTable {
+            expected_names: BTreeSet::from(["t1_v2".into(), "t1_v3".into()]),
+            ..source_table(1, table_desc())
+        };
+        let renamed = |name: &str| PostgresTableDesc {
+            name: name.into(),
+            ..table_desc()
+        };
+        let mut source_tables = BTreeMap::from([(TABLE_OID, info.clone())]);
+
+        // Tables renamed to their expected names stay compatible, others don't.
+        determine_table_compatibility(&source_tables, vec![renamed("t1_v2")]).unwrap();
+        check_table_compatibility(&info, &renamed("t1_v2")).unwrap();
+        check_table_compatibility(&info, &renamed("t1_v3")).unwrap();
+        let err =
+            determine_table_compatibility(&source_tables, vec![renamed("t1_v4")]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("source table t1 with oid {TABLE_OID} has been altered")
+        );
+        check_table_compatibility(&info, &renamed("t1_v4")).unwrap_err();
+
+        // Once adopted, the new name is the one the table is known by, and it can still be
+        // renamed to its other expected names.
+        adopt_expected_names(&mut source_tables, &[renamed("t1_v2")]);
+        assert_eq!(source_tables[&TABLE_OID].desc, renamed("t1_v2"));
+        determine_table_compatibility(&source_tables, vec![renamed("t1_v2")]).unwrap();
+        check_table_compatibility(&source_tables[&TABLE_OID], &renamed("t1_v3")).unwrap();
+
+        // Tables that weren't renamed are left as they are.
+        let mut source_tables = BTreeMap::from([(TABLE_OID, info)]);
+        adopt_expected_names(&mut source_tables, &[table_desc()]);
+        assert_eq!(source_tables[&TABLE_OID].desc, table_desc());
+    }
+
+    #[test]
+    fn audited_schema_drift() {
+        let source_tables = BTreeMap::from([(TABLE_OID, source_table(1, table_desc()))]);
+
+        // An unchanged table hasn't drifted.
+        assert_eq!(
+            schema_drift(&source_tables, vec![table_desc()]).unwrap(),
+            BTreeMap::new()
+        );
+
+        // Adding a column and a NOT NULL constraint keeps the table compatible.
+        let mut drifted = table_desc();
+        drifted.columns[1].nullable = false;
+        let added = PostgresColumnDesc {
+            name: "c".into(),
+            ..drifted.columns[0].clone()
+        };
+        drifted.columns.push(added);
+        assert_eq!(
+            schema_drift(&source_tables, vec![drifted]).unwrap(),
+            BTreeMap::from([(
+                "public.t1".into(),
+                "added columns c; altered columns b".into()
+            )])
+        );
+
+        // Changing the type of a column ingested as text can be handled by snapshotting anew.
+        let mut retyped = table_desc();
+        retyped.columns[0].type_oid = 1043;
+        let err = schema_drift(&source_tables, vec![retyped]).unwrap_err();
+        assert!(err.is::<TableSchemaChanged>());
+
+        // Dropping a column breaks ingestion.
+        let mut dropped = table_desc();
+        dropped.columns.pop();
+        let err = schema_drift(&source_tables, vec![dropped]).unwrap_err();
+        assert!(!err.is::<TableSchemaChanged>());
+
+        let err = schema_drift(&source_tables, vec![]).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref(),
+            Some(SourceErrorDetails::TableDropped { .. })
+        ));
+    }
+
+    #[test]
+    fn audited_publication_changes() {
+        let source_tables = BTreeMap::from([(TABLE_OID, source_table(1, table_desc()))]);
+        let publications = ["mz_source".to_string()];
+
+        check_published(&publications, &source_tables, &[]).unwrap();
+        // Tables that the source doesn't ingest may be removed.
+        check_published(&publications, &source_tables, &[TABLE_OID + 1]).unwrap();
+        let err = check_published(&publications, &source_tables, &[TABLE_OID]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"table public.t1 was removed from publication "mz_source""#
+        );
+        assert!(matches!(
+            err.downcast_ref::<SourceErrorDetails>().and_then(|err| err.hint()),
+            Some(hint) if hint.contains("ALTER PUBLICATION ... ADD TABLE public.t1;")
+        ));
+
+        let added = PostgresTableDesc {
+            oid: TABLE_OID + 1,
+            name: "t2".into(),
+            ..table_desc()
+        };
+        assert_eq!(
+            added_tables(&[table_desc()], &[table_desc()]),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            added_tables(&[table_desc()], &[table_desc(), added]),
+            vec!["public.t2".to_string()]
+        );
+        // Tables removed from the publication are not reported as added.
+        assert_eq!(added_tables(&[table_desc()], &[]), Vec::<String>::new());
+    }
+
+    #[test]
+    fn existing_slot_lsns() {
+        let slot_lsn = |confirmed_lsn: Option<&str>, restart_lsn: Option<&str>| {
+            let row = BTreeMap::from([
+                ("confirmed_flush_lsn", confirmed_lsn),
+                ("restart_lsn", restart_lsn),
+                ("plugin", Some("pgoutput")),
+            ]);
+            existing_slot_lsn("slot", ReplicationPlugin::PgOutput, Some(&row))
+        };
+
+        // There is no slot yet.
+        let no_row: Option<&BTreeMap<&str, Option<&str>>> = None;
+        assert_eq!(
+            existing_slot_lsn("slot", ReplicationPlugin::PgOutput, no_row).unwrap(),
+            None
+        );
+
+        let lsn = PgLsn::from(0x16B3748);
+        assert_eq!(
+            slot_lsn(Some("0/16B3748"), Some("0/16B3700")).unwrap(),
+            Some(lsn)
+        );
+        // The slot never confirmed a position.
+        assert_eq!(slot_lsn(None, Some("0/16B3748")).unwrap(), Some(lsn));
+        // The slot was invalidated.
+        assert!(matches!(
+            slot_lsn(None, None),
+            Err(ReplicationError::Definite(err))
+                if err.to_string().contains("neither a confirmed_flush_lsn nor a restart_lsn")
+        ));
+        // Upstream returned something unexpected.
+        assert!(matches!(
+            slot_lsn(Some("16B3748"), None),
+            Err(ReplicationError::Indefinite(_))
+        ));
+        let row = BTreeMap::from([
+            ("confirmed_flush_lsn", Some("0/16B3748")),
+            ("plugin", Some("pgoutput")),
+        ]);
+        assert!(matches!(
+            existing_slot_lsn("slot", ReplicationPlugin::PgOutput, Some(&row)),
+            Err(ReplicationError::Indefinite(err))
+                if err.to_string() == "missing expected column: restart_lsn"
+        ));
+
+        // A slot left behind by Debezium is replicated from, as long as the source decodes its
+        // changes with the same plugin.
+        let row = BTreeMap::from([
+            ("confirmed_flush_lsn", Some("0/16B3748")),
+            ("restart_lsn", Some("0/16B3700")),
+            ("plugin", Some("decoderbufs")),
+        ]);
+        assert_eq!(
+            existing_slot_lsn("debezium", ReplicationPlugin::DecoderBufs, Some(&row)).unwrap(),
+            Some(lsn)
+        );
+        assert!(matches!(
+            existing_slot_lsn("debezium", ReplicationPlugin::PgOutput, Some(&row)),
+            Err(ReplicationError::Definite(err))
+                if err.to_string().contains("decodes changes with the decoderbufs plugin")
+        ));
+    }
+
+    #[test]
+    fn replication_lsns_at_frontiers() {
+        assert_eq!(
+            replication_lsn_at(PgLsn::from(0x16B3749)).unwrap(),
+            PgLsn::from(0x16B3748)
+        );
+        assert_eq!(replication_lsn_at(PgLsn::from(2)).unwrap(), PgLsn::from(1));
+        // Resuming from 0/0 would snapshot the tables a second time.
+        for frontier in [0, 1] {
+            assert!(matches!(
+                replication_lsn_at(PgLsn::from(frontier)),
+                Err(ReplicationError::Definite(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn start_replication_commands() {
+        let options = r#"("proto_version" '1', "publication_names" 'mz_source')"#;
+        assert_eq!(
+            start_replication_command("slot", CommitLsn::new(PgLsn::from(0x16B3748)), options),
+            r#"START_REPLICATION SLOT "slot" LOGICAL 0/16B3748 ("proto_version" '1', "publication_names" 'mz_source')"#
+        );
+        assert_eq!(
+            start_replication_command("slot", CommitLsn::new(PgLsn::from(0)), ""),
+            r#"START_REPLICATION SLOT "slot" LOGICAL 0/0 "#
+        );
+    }
+
+    #[test]
+    fn committed_offsets() {
+        let committer = PgOffsetCommitter {
+            resume_lsn: Arc::new(AtomicU64::new(0)),
+        };
+        let commit = |frontier: Antichain<MzOffset>| {
+            committer.commit_offsets(frontier).unwrap();
+            committer.resume_lsn.load(Ordering::SeqCst)
+        };
+        assert_eq!(
+            commit(Antichain::from_elem(MzOffset::from(0x16B3749))),
+            0x16B3748
+        );
+        // Nothing can have committed before the start of the WAL.
+        assert_eq!(commit(Antichain::from_elem(MzOffset::ZERO)), 0);
+        assert_eq!(commit(Antichain::from_elem(MzOffset::from(1))), 0);
+        // A closed frontier leaves the last committed LSN in place.
+        committer.resume_lsn.store(0x16B3748, Ordering::SeqCst);
+        assert_eq!(commit(Antichain::new()), 0x16B3748);
+    }
+
+    #[test]
+    fn replication_slot_statuses() {
+        let row = BTreeMap::from([
+            ("active", Some("true")),
+            ("confirmed_flush_lsn", Some("0/16B3748")),
+            ("wal_status", Some("reserved")),
+        ]);
+        assert_eq!(
+            slot_status(&row).unwrap(),
+            ReplicationSlotStatus {
+                active: true,
+                confirmed_flush_lsn: Some(0x16B3748),
+                wal_status: Some("reserved".into()),
+            }
+        );
+        // A slot that never confirmed a position, on a server that doesn't report `wal_status`.
+        let row = BTreeMap::from([
+            ("active", Some("false")),
+            ("confirmed_flush_lsn", None),
+            ("wal_status", None),
+        ]);
+        assert_eq!(
+            slot_status(&row).unwrap(),
+            ReplicationSlotStatus {
+                active: false,
+                confirmed_flush_lsn: None,
+                wal_status: None,
+            }
+        );
+        let row = BTreeMap::from([
+            ("active", None),
+            ("confirmed_flush_lsn", None),
+            ("wal_status", None),
+        ]);
+        assert_eq!(
+            slot_status(&row).unwrap_err().to_string(),
+            "unexpected NULL in column active"
+        );
+    }
+
+    #[test]
+    fn snapshot_schema_fence() {
+        let source_tables = BTreeMap::from([(TABLE_OID, source_table(1, table_desc()))]);
+        let columns = |columns: Vec<PostgresColumnDesc>| BTreeMap::from([(TABLE_OID, columns)]);
+
+        // The snapshot sees the schema the tables were validated with.
+        validate_snapshot_columns(&source_tables, columns(table_desc().columns)).unwrap();
+
+        // A column dropped and added again in the meantime no longer lines up.
+        let mut readded = table_desc().columns;
+        readded[0].col_num = Some(3);
+        let err = validate_snapshot_columns(&source_tables, columns(readded)).unwrap_err();
+        assert!(err.to_string().contains("before the snapshot started"));
+
+        // Nor does a table dropped in the meantime.
+        let err = validate_snapshot_columns(&source_tables, BTreeMap::new()).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref(),
+            Some(SourceErrorDetails::TableDropped { .. })
+        ));
+    }
+
+    /// Returns the description of a column of table [`TABLE_OID`] with the given type, which is
+    /// the `col_num`th column created in it.
+    fn typed_column(name: &str, col_num: u16, type_oid: u32, nullable: bool) -> PostgresColumnDesc {
+        PostgresColumnDesc {
+            name: name.into(),
+            col_num: Some(col_num),
+            type_oid,
+            type_mod: -1,
+            nullable,
+            domain_constraints: vec![],
+            default_expr: None,
+        }
+    }
+
+    /// Returns the tables ingested by a source that ingests the table `columns` describe.
+    fn ingested(columns: Vec<PostgresColumnDesc>) -> BTreeMap<u32, SourceTable> {
+        let info = source_table(
+            1,
+            PostgresTableDesc {
+                columns,
+                ..table_desc()
+            },
+        );
+        BTreeMap::from([(TABLE_OID, info)])
+    }
+
+    #[test]
+    fn table_casts() {
+        let int4 = |name, col_num| typed_column(name, col_num, 23, true);
+        let mut source_tables = ingested(vec![int4("a", 1), int4("b", 2)]);
+        let info = source_tables.get_mut(&TABLE_OID).unwrap();
+        check_table_casts(info, &[]).unwrap();
+
+        // Soft delete tables have an extra cast for the deleted flag.
+        info.soft_delete = true;
+        let err = check_table_casts(info, &[]).unwrap_err();
+        assert_eq!(err.to_string(), "table t1 has 2 casts for 3 columns");
+        info.casts
+            .push(MirScalarExpr::literal_ok(Datum::False, ScalarType::Bool));
+        check_table_casts(info, &[]).unwrap();
+
+        // The casts must cast the rows of the table.
+        info.casts[1] = MirScalarExpr::column(1).call_unary(UnaryFunc::CastStringToInt32(
+            mz_expr::func::CastStringToInt32,
+        ));
+        let rows = vec![
+            vec![Some("a".to_string()), Some("1".to_string())],
+            vec![Some("b".to_string()), None],
+        ];
+        check_table_casts(info, &rows).unwrap();
+        let mut rows = rows;
+        rows.push(vec![Some("c".to_string()), Some("x".to_string())]);
+        let err = check_table_casts(info, &rows).unwrap_err().to_string();
+        assert!(
+            err.starts_with(r#"failed to cast value "x" of column "b" of table public.t1: "#),
+            "{err}"
+        );
+
+        // Casts can only read the columns of the table.
+        info.casts[1] = MirScalarExpr::Column(2);
+        let err = check_table_casts(info, &[]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cast of column t1.b reads column 2, but the table has 2 columns"
+        );
+    }
+
+    #[test]
+    fn compatible_schema_changes() {
+        let int4 = |name, col_num| typed_column(name, col_num, 23, true);
+        let source_tables = ingested(vec![int4("a", 1), int4("b", 2)]);
+        let upstream = |columns| {
+            vec![PostgresTableDesc {
+                columns,
+                ..table_desc()
+            }]
+        };
+        let compatible = |columns| determine_table_compatibility(&source_tables, upstream(columns));
+
+        // Unchanged.
+        assert_eq!(
+            compatible(vec![int4("a", 1), int4("b", 2)]).unwrap(),
+            BTreeMap::new()
+        );
+
+        // Added columns are not ingested, whether they are nullable or not.
+        let added = vec![int4("a", 1), int4("b", 2), int4("c", 3)];
+        assert_eq!(compatible(added).unwrap(), BTreeMap::new());
+        let added = vec![int4("a", 1), int4("b", 2), typed_column("c", 3, 23, false)];
+        assert_eq!(compatible(added).unwrap(), BTreeMap::new());
+
+        // Widened types still cast into the ingested ones.
+        let widened = vec![int4("a", 1), typed_column("b", 2, 20, true)];
+        assert_eq!(compatible(widened).unwrap(), BTreeMap::new());
+
+        // Columns that moved are projected into the order they are ingested in.
+        let reordered = vec![int4("b", 2), int4("c", 3), int4("a", 1)];
+        assert_eq!(
+            compatible(reordered).unwrap(),
+            BTreeMap::from([(TABLE_OID, vec![2, 0])])
+        );
+
+        // A source that ingests no tables is compatible with any publication.
+        let no_tables = determine_table_compatibility(&BTreeMap::new(), vec![]).unwrap();
+        assert_eq!(no_tables, BTreeMap::new());
+    }
+
+    #[test]
+    fn incompatible_schema_changes() {
+        let int8 = |name, col_num| typed_column(name, col_num, 20, true);
+        let source_tables = ingested(vec![int8("a", 1), int8("b", 2)]);
+        let upstream = |columns| {
+            vec![PostgresTableDesc {
+                columns,
+                ..table_desc()
+            }]
+        };
+        let altered = |columns| {
+            let err = determine_table_compatibility(&source_tables, upstream(columns)).unwrap_err();
+            assert!(err.downcast_ref::<SourceErrorDetails>().is_none(), "{err}");
+            assert_eq!(
+                err.to_string(),
+                format!("source table t1 with oid {TABLE_OID} has been altered")
+            );
+        };
+
+        // Removed.
+        altered(vec![int8("a", 1)]);
+        // Renamed.
+        altered(vec![int8("a", 1), int8("c", 2)]);
+        // Narrowed.
+        altered(vec![int8("a", 1), typed_column("b", 2, 23, true)]);
+        // Dropped and added again, which lost its values.
+        altered(vec![int8("a", 1), int8("b", 3)]);
+        // Made nullable, while it is ingested as non-nullable.
+        let source_tables = ingested(vec![int8("a", 1), typed_column("b", 2, 20, false)]);
+        determine_table_compatibility(&source_tables, upstream(vec![int8("a", 1), int8("b", 2)]))
+            .unwrap_err();
+    }
+
+    #[test]
+    fn dropped_tables() {
+        let source_tables = ingested(table_desc().columns);
+        let dropped = |tables| {
+            let err = determine_table_compatibility(&source_tables, tables).unwrap_err();
+            assert!(
+                matches!(
+                    err.downcast_ref(),
+                    Some(SourceErrorDetails::TableDropped {
+                        table_oid: TABLE_OID,
+                        ..
+                    })
+                ),
+                "{err}"
+            );
+        };
+
+        // A table with a different OID is a different table, even with the same name.
+        dropped(vec![PostgresTableDesc {
+            oid: TABLE_OID + 1,
+            ..table_desc()
+        }]);
+        // Missing from the publication.
+        dropped(vec![PostgresTableDesc {
+            oid: TABLE_OID + 1,
+            name: "t2".into(),
+            ..table_desc()
+        }]);
+        // The publication is empty.
+        dropped(vec![]);
+    }
+
+    #[test]
+    fn constant_defaults() {
+        let constant = |value: &str| Some(Some(value.to_string()));
+        assert_eq!(constant_default("NULL"), Some(None));
+        assert_eq!(constant_default("NULL::text"), Some(None));
+        assert_eq!(constant_default("true"), constant("true"));
+        assert_eq!(constant_default("42"), constant("42"));
+        assert_eq!(constant_default("1.5e-3"), constant("1.5e-3"));
+        assert_eq!(constant_default("'-1'::integer"), constant("-1"));
+        assert_eq!(constant_default("'it''s'::text"), constant("it's"));
+        assert_eq!(
+            constant_default("'a::b'::character varying(10)"),
+            constant("a::b")
+        );
+        assert_eq!(constant_default("'{1,2}'::integer[]"), constant("{1,2}"));
+
+        assert_eq!(constant_default("now()"), None);
+        assert_eq!(constant_default("nextval('t_a_seq'::regclass)"), None);
+        assert_eq!(constant_default("('a'::text || 'b'::text)"), None);
+        assert_eq!(constant_default("'a'::text || 'b'::text"), None);
+        assert_eq!(constant_default("'unterminated"), None);
+        assert_eq!(constant_default("NaN"), None);
+    }
+
+    #[test]
+    fn short_tuples() {
+        let column = |name: &str, default_expr: Option<&str>| PostgresColumnDesc {
+            name: name.into(),
+            col_num: None,
+            type_oid: 25,
+            type_mod: -1,
+            nullable: true,
+            domain_constraints: vec![],
+            default_expr: default_expr.map(Into::into),
+        };
+        let desc = PostgresTableDesc {
+            columns: vec![
+                column("a", None),
+                column("b", Some("'x'::text")),
+                column("c", None),
+            ],
+            ..table_desc()
+        };
+        let mut info = SourceTable {
+            default_datums: column_defaults(&desc),
+            ..source_table(1, desc)
+        };
+        let tuple = [TupleData::Text(Bytes::from_static(b"1"))];
+
+        // Upstream wrote the tuple before columns b and c were added.
+        let mut datums = vec![];
+        datums_from_tuple(&info, &tuple, &mut datums).unwrap();
+        assert_eq!(
+            datums,
+            vec![Datum::String("1"), Datum::String("x"), Datum::Null]
+        );
+
+        // Columns whose default we cannot evaluate cannot be filled in.
+        info.desc.columns[2].default_expr = Some("now()".into());
+        info.default_datums = column_defaults(&info.desc);
+        let err = datums_from_tuple(&info, &tuple, &mut vec![]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "tuple of table with OID = {TABLE_OID} lacks column c, whose default now() \
+                cannot be evaluated"
+            )
+        );
+
+        // Tuples with more columns than ingested are truncated.
+        let tuple = [
+            TupleData::Text(Bytes::from_static(b"1")),
+            TupleData::Null,
+            TupleData::Text(Bytes::from_static(b"3")),
+            TupleData::Text(Bytes::from_static(b"4")),
+        ];
+        let mut datums = vec![];
+        datums_from_tuple(&info, &tuple, &mut datums).unwrap();
+        assert_eq!(
+            datums,
+            vec![Datum::String("1"), Datum::Null, Datum::String("3")]
+        );
+    }
+
+    #[test]
+    fn soft_deleted_rows() {
+        let casts = vec![
+            MirScalarExpr::Column(0),
+            MirScalarExpr::literal_ok(Datum::False, ScalarType::Bool),
+        ];
+        let row = cast_row(&casts, &[Datum::String("1")]).unwrap();
+        assert_eq!(row, Row::pack([Datum::String("1"), Datum::False]));
+        assert!(!is_soft_deleted(&row));
+
+        let deleted = soft_deleted_row(&row);
+        assert_eq!(deleted, Row::pack([Datum::String("1"), Datum::True]));
+        assert!(is_soft_deleted(&deleted));
+    }
+
+    #[test]
+    fn array_and_range_columns() {
+        let int4 = MirScalarExpr::column(0).call_unary(UnaryFunc::CastStringToInt32(
+            mz_expr::func::CastStringToInt32,
+        ));
+        let casts = vec![
+            // An int4[] column.
+            MirScalarExpr::column(0).call_unary(UnaryFunc::CastStringToArray(
+                mz_expr::func::CastStringToArray {
+                    return_ty: ScalarType::Array(Box::new(ScalarType::Int32)),
+                    cast_expr: Box::new(int4.clone()),
+                },
+            )),
+            // A text[] column.
+            MirScalarExpr::column(1).call_unary(UnaryFunc::CastStringToArray(
+                mz_expr::func::CastStringToArray {
+                    return_ty: ScalarType::Array(Box::new(ScalarType::String)),
+                    cast_expr: Box::new(MirScalarExpr::column(0)),
+                },
+            )),
+            // An int4range column.
+            MirScalarExpr::column(2).call_unary(UnaryFunc::CastStringToRange(
+                mz_expr::func::CastStringToRange {
+                    return_ty: ScalarType::Range {
+                        element_type: Box::new(ScalarType::Int32),
+                    },
+                    cast_expr: Box::new(int4),
+                },
+            )),
+        ];
+
+        // Upstream sends multi-dimensional arrays, NULL and quoted elements, and ranges in their
+        // text encoding, both in snapshots and in the replication stream.
+        let row = cast_row(
+            &casts,
+            &[
+                Datum::String("{{1,NULL},{3,4}}"),
+                Datum::String(r#"{"a,b","{c}",NULL,"NULL","d\"e"}"#),
+                Datum::String("[1,4]"),
+            ],
+        )
+        .unwrap();
+        let canonical_range = cast_row(
+            &casts[2..],
+            &[Datum::Null, Datum::Null, Datum::String("[1,5)")],
+        )
+        .unwrap();
+        let dim = |length| ArrayDimension {
+            lower_bound: 1,
+            length,
+        };
+        let mut expected = Row::default();
+        let mut packer = expected.packer();
+        packer
+            .push_array(
+                &[dim(2), dim(2)],
+                [
+                    Datum::Int32(1),
+                    Datum::Null,
+                    Datum::Int32(3),
+                    Datum::Int32(4),
+                ],
+            )
+            .unwrap();
+        packer
+            .push_array(
+                &[dim(5)],
+                [
+                    Datum::String("a,b"),
+                    Datum::String("{c}"),
+                    Datum::Null,
+                    Datum::String("NULL"),
+                    Datum::String("d\"e"),
+                ],
+            )
+            .unwrap();
+        packer.push(canonical_range.unpack_first());
+        assert_eq!(row, expected);
+
+        // Elements that don't cast, and arrays that aren't rectangular, fail the row.
+        for array in ["{{1},{x}}", "{{1},{2,3}}"] {
+            let datums = [Datum::String(array), Datum::Null, Datum::Null];
+            assert!(cast_row(&casts, &datums).is_err(), "{array}");
+        }
+    }
+
+    #[test]
+    fn cast_errors() {
+        let metrics = test_metrics();
+        let info = SourceTable {
+            casts: vec![
+                MirScalarExpr::Column(0),
+                MirScalarExpr::column(1).call_unary(UnaryFunc::CastStringToInt32(
+                    mz_expr::func::CastStringToInt32,
+                )),
+            ],
+            ..source_table(1, table_desc())
+        };
+        let row = cast_table_row(&info, &[Datum::String("a"), Datum::String("1")], &metrics);
+        assert_eq!(row, Ok(Row::pack([Datum::String("a"), Datum::Int32(1)])));
+
+        // The error of a row that fails to cast names the table, the column and the value.
+        let err = cast_table_row(&info, &[Datum::String("a"), Datum::String("x")], &metrics)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.starts_with(r#"failed to cast value "x" of column "b" of table public.t1: "#),
+            "{err}"
+        );
+
+        // Long values are truncated.
+        let long = format!("{}x", "1".repeat(100));
+        let err = cast_table_row(&info, &[Datum::String("a"), Datum::String(&long)], &metrics)
+            .unwrap_err()
+            .to_string();
+        let prefix = format!(
+            r#"failed to cast value "{}"... of column "b""#,
+            "1".repeat(64)
+        );
+        assert!(err.starts_with(&prefix), "{err}");
+        assert_eq!(metrics.cast_errors.get(), 2);
+    }
+
+    #[test]
+    fn reordered_columns() {
+        let column = |name: &str, col_num| PostgresColumnDesc {
+            name: name.into(),
+            col_num: Some(col_num),
+            type_oid: 25,
+            type_mod: -1,
+            nullable: true,
+            domain_constraints: vec![],
+            default_expr: None,
+        };
+        let desc = |columns| PostgresTableDesc {
+            columns,
+            ..table_desc()
+        };
+        let info = source_table(
+            1,
+            desc(vec![column("a", 1), column("b", 2), column("c", 3)]),
+        );
+
+        // Dropping and adding a column again moves it to the end of the table. Its values are
+        // gone upstream, so the table can only be snapshotted anew.
+        let reordered = desc(vec![column("a", 1), column("c", 3), column("b", 4)]);
+        let err = check_table_compatibility(&info, &reordered).unwrap_err();
+        let TableSchemaChanged {
+            desc: new_desc,
+            projection,
+        } = err.downcast::<TableSchemaChanged>().unwrap();
+        assert_eq!(
+            new_desc.columns,
+            vec![column("a", 1), column("b", 4), column("c", 3)]
+        );
+        assert_eq!(projection, Some(vec![0, 2, 1]));
+
+        // Once it is, its columns are projected into the order they are ingested in.
+        let info = SourceTable {
+            desc: new_desc,
+            projection,
+            ..info
+        };
+        let source_tables = BTreeMap::from([(TABLE_OID, info.clone())]);
+        let projections =
+            determine_table_compatibility(&source_tables, vec![reordered.clone()]).unwrap();
+        assert_eq!(projections, BTreeMap::from([(TABLE_OID, vec![0, 2, 1])]));
+        check_projections(&source_tables, &projections).unwrap();
+        check_table_compatibility(&info, &reordered).unwrap();
+        assert_eq!(
+            projected_column_list(&info).as_deref(),
+            Some(r#""a", "b", "c""#)
+        );
+
+        let tuple = [
+            TupleData::Text(Bytes::from_static(b"1")),
+            TupleData::Text(Bytes::from_static(b"3")),
+            TupleData::Null,
+        ];
+        let mut datums = vec![];
+        datums_from_tuple(&info, &tuple, &mut datums).unwrap();
+        assert_eq!(
+            datums,
+            vec![Datum::String("1"), Datum::Null, Datum::String("3")]
+        );
+
+        // Columns added afterwards don't move the ingested ones.
+        let mut added = reordered.clone();
+        added.columns.push(column("d", 5));
+        check_table_compatibility(&info, &added).unwrap();
+        assert_eq!(describe_drift(&info.desc, &added), "added columns d");
+
+        // A projection that no longer matches the upstream columns is an alteration.
+        let err = check_projections(&source_tables, &BTreeMap::new()).unwrap_err();
+        assert!(err.to_string().contains("has been altered"));
+    }
+
+    #[test]
+    fn phase_spans() {
+        let capture = SpanCapture::default();
+        let subscriber = tracing_subscriber::registry().with(capture.clone());
+        tracing::subscriber::with_default(subscriber, || {
+            let desc = PostgresTableDesc {
+                oid: 16384,
+                namespace: "public".into(),
+                name: "t1".into(),
+                columns: vec![],
+                keys: Default::default(),
+                replica_identity: Default::default(),
+                identity_column_names: vec![],
+            };
+            let _snapshot = snapshot_span(GlobalId::User(1), &desc);
+            let _rewind = rewind_span(PgLsn::from(16), PgLsn::from(32));
+            let _replication = replication_span("slot", &["publication".into()], PgLsn::from(16));
+        });
+
+        let spans = capture.0.lock().expect("lock poisoned");
+        let owned = |fields: &[(&'static str, &str)]| {
+            fields
+                .iter()
+                .map(|(name, value)| (*name, value.to_string()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            *spans,
+            vec![
+                (
+                    "pg_snapshot",
+                    owned(&[
+                        ("source_id", "u1"),
+                        ("table_oid", "16384"),
+                        ("table_name", "t1"),
+                    ])
+                ),
+                (
+                    "pg_rewind",
+                    owned(&[("slot_lsn", "0/10"), ("snapshot_lsn", "0/20")])
+                ),
+                (
+                    "pg_replication",
+                    owned(&[
+                        ("slot", "\"slot\""),
+                        ("publication", "\"publication\""),
+                        ("start_lsn", "0/10"),
+                    ])
+                ),
+            ]
+        );
+    }
+
+    /// A replication stream that replays a fixed sequence of messages.
+    struct TestStream {
+        messages: VecDeque<ReplicationStreamItem>,
+        /// The current upstream description of each table, by OID
+        tables: BTreeMap<u32, PostgresTableDesc>,
+        /// The current upstream definition of each custom type, by OID
+        types: BTreeMap<u32, String>,
+        /// The LSNs reported through standby status updates
+        feedback: Vec<PgLsn>,
+        /// Whether the stream stays idle instead of ending once all messages were consumed
+        stall: bool,
+        /// Whether writing to the upstream fails
+        broken: bool,
+    }
+
+    impl futures::Stream for TestStream {
+        type Item = ReplicationStreamItem;
+
+        fn poll_next(
+            mut self: Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+        ) -> Poll<Option<Self::Item>> {
+            match self.messages.pop_front() {
+                None if self.stall => Poll::Pending,
+                message => Poll::Ready(message),
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl<'a> ReplicationUpstream for &'a mut TestStream {
+        async fn send_feedback(&mut self, lsn: PgLsn) -> Result<(), ReplicationError> {
+            if self.broken {
+                return Err(ReplicationError::Indefinite(anyhow!("connection closed")));
+            }
+            self.feedback.push(lsn);
+            Ok(())
+        }
+
+        async fn table_desc(
+            &mut self,
+            rel_id: u32,
+        ) -> Result<Option<PostgresTableDesc>, ReplicationError> {
+            Ok(self.tables.get(&rel_id).cloned())
+        }
+
+        async fn type_definition(
+            &mut self,
+            type_oid: u32,
+        ) -> Result<Option<String>, ReplicationError> {
+            Ok(self.types.get(&type_oid).cloned())
+        }
+    }
+
+    /// A column value of a tuple in a pgoutput message.
+    enum Value {
+        Text(&'static str),
+        Null,
+        UnchangedToast,
+    }
+
+    fn encode_tuple(buf: &mut Vec<u8>, tuple: &[Value]) {
+        buf.extend_from_slice(&i16::try_from(tuple.len()).unwrap().to_be_bytes());
+        for value in tuple {
+            match value {
+                Value::Text(text) => {
+                    buf.push(b't');
+                    buf.extend_from_slice(&i32::try_from(text.len()).unwrap().to_be_bytes());
+                    buf.extend_from_slice(text.as_bytes());
+                }
+                Value::Null => buf.push(b'n'),
+                Value::UnchangedToast => buf.push(b'u'),
+            }
+        }
+    }
+
+    /// Wraps a pgoutput message in an XLogData message and decodes it.
+    fn xlog_data(payload: Vec<u8>) -> ReplicationStreamItem {
+        let mut buf = vec![b'w'];
+        buf.extend_from_slice(&0u64.to_be_bytes());
+        buf.extend_from_slice(&0u64.to_be_bytes());
+        buf.extend_from_slice(&0i64.to_be_bytes());
+        buf.extend_from_slice(&payload);
+        match ReplicationMessage::parse(&Bytes::from(buf)).unwrap() {
+            ReplicationMessage::XLogData(body) => Ok(ReplicationMessage::XLogData(
+                body.map_data(|data| LogicalReplicationMessage::parse(&data))
+                    .unwrap(),
+            )),
+            _ => unreachable!(),
+        }
+    }
+
+    fn begin(final_lsn: u64, xid: u32) -> ReplicationStreamItem {
+        let mut buf = vec![b'B'];
+        buf.extend_from_slice(&final_lsn.to_be_bytes());
+        buf.extend_from_slice(&0i64.to_be_bytes());
+        buf.extend_from_slice(&xid.to_be_bytes());
+        xlog_data(buf)
+    }
+
+    fn insert(rel_id: u32, new: &[Value]) -> ReplicationStreamItem {
+        let mut buf = vec![b'I'];
+        buf.extend_from_slice(&rel_id.to_be_bytes());
+        buf.push(b'N');
+        encode_tuple(&mut buf, new);
+        xlog_data(buf)
+    }
+
+    fn update(rel_id: u32, old: &[Value], new: &[Value]) -> ReplicationStreamItem {
+        let mut buf = vec![b'U'];
+        buf.extend_from_slice(&rel_id.to_be_bytes());
+        buf.push(b'O');
+        encode_tuple(&mut buf, old);
+        buf.push(b'N');
+        encode_tuple(&mut buf, new);
+        xlog_data(buf)
+    }
+
+    fn delete(rel_id: u32, old: &[Value]) -> ReplicationStreamItem {
+        let mut buf = vec![b'D'];
+        buf.extend_from_slice(&rel_id.to_be_bytes());
+        buf.push(b'O');
+        encode_tuple(&mut buf, old);
+        xlog_data(buf)
+    }
+
+    fn commit(commit_lsn: u64, end_lsn: u64) -> ReplicationStreamItem {
+        let mut buf = vec![b'C', 0];
+        buf.extend_from_slice(&commit_lsn.to_be_bytes());
+        buf.extend_from_slice(&end_lsn.to_be_bytes());
+        buf.extend_from_slice(&0i64.to_be_bytes());
+        xlog_data(buf)
+    }
+
+    fn relation(rel_id: u32) -> ReplicationStreamItem {
+        let mut buf = vec![b'R'];
+        buf.extend_from_slice(&rel_id.to_be_bytes());
+        buf.extend_from_slice(b"public\0t1\0");
+        // REPLICA IDENTITY FULL, no columns
+        buf.push(b'f');
+        buf.extend_from_slice(&0i16.to_be_bytes());
+        xlog_data(buf)
+    }
+
+    fn type_(type_oid: u32, name: &str) -> ReplicationStreamItem {
+        let mut buf = vec![b'Y'];
+        buf.extend_from_slice(&type_oid.to_be_bytes());
+        buf.extend_from_slice(b"public\0");
+        buf.extend_from_slice(name.as_bytes());
+        buf.push(0);
+        xlog_data(buf)
+    }
+
+    fn keepalive(wal_end: u64, reply: u8) -> ReplicationStreamItem {
+        let mut buf = vec![b'k'];
+        buf.extend_from_slice(&wal_end.to_be_bytes());
+        buf.extend_from_slice(&0i64.to_be_bytes());
+        buf.push(reply);
+        match ReplicationMessage::parse(&Bytes::from(buf)).unwrap() {
+            ReplicationMessage::PrimaryKeepAlive(body) => {
+                Ok(ReplicationMessage::PrimaryKeepAlive(body))
             }
+            _ => unreachable!(),
         }
     }
 
-    Ok(())
-}
+    const TABLE_OID: u32 = 16384;
 
-/// Parses SQL results that are expected to be a single row into a Rust type
-fn parse_single_row<T: FromStr>(
-    result: &[SimpleQueryMessage],
-    column: &str,
-) -> Result<T, ReplicationError> {
-    let mut rows = result.into_iter().filter_map(|msg| match msg {
-        SimpleQueryMessage::Row(row) => Some(row),
-        _ => None,
-    });
-    match (rows.next(), rows.next()) {
-        (Some(row), None) => row
-            .get(column)
-            .ok_or_else(|| anyhow!("missing expected column: {column}"))
-            .and_then(|col| col.parse().or_else(|_| Err(anyhow!("invalid data"))))
-            .err_indefinite(),
-        (None, None) => Err(anyhow!("empty result")).err_indefinite(),
-        _ => Err(anyhow!("ambiguous result, more than one row")).err_indefinite(),
+    fn table_desc() -> PostgresTableDesc {
+        let column = |name: &str| PostgresColumnDesc {
+            name: name.into(),
+            col_num: None,
+            type_oid: 25,
+            type_mod: -1,
+            nullable: true,
+            domain_constraints: vec![],
+            default_expr: None,
+        };
+        PostgresTableDesc {
+            oid: TABLE_OID,
+            namespace: "public".into(),
+            name: "t1".into(),
+            columns: vec![column("a"), column("b")],
+            keys: Default::default(),
+            replica_identity: Default::default(),
+            identity_column_names: vec![],
+        }
     }
-}
 
-/// Produces the initial snapshot of the data by performing a `COPY` query for each of the provided
-/// `source_tables`.
-///
-/// The return stream of data returned is not annotated with LSN numbers. It is up to the caller to
-/// provide a client that is in a known LSN context in which the snapshot will be taken. For
-/// example by calling this method while being in a transaction for which the LSN is known.
-fn produce_snapshot<'a>(
-    client: &'a Client,
-    metrics: &'a PgSourceMetrics,
-    source_tables: &'a BTreeMap<u32, SourceTable>,
-) -> impl futures::Stream<Item = Result<(usize, Row), ReplicationError>> + 'a {
-    async_stream::try_stream! {
-        // Scratch space to use while evaluating casts
-        let mut datum_vec = DatumVec::new();
+    /// Returns the table ingested into output `output_index` as described by `desc`, with its
+    /// text columns cast to themselves.
+    fn source_table(output_index: usize, desc: PostgresTableDesc) -> SourceTable {
+        SourceTable {
+            output_index,
+            casts: (0..desc.columns.len()).map(MirScalarExpr::Column).collect(),
+            projection: None,
+            default_datums: vec![],
+            soft_delete: false,
+            expected_names: BTreeSet::new(),
+            desc,
+        }
+    }
 
-        for info in source_tables.values() {
-            let reader = client
-                .copy_out_simple(
-                    format!(
-                        "COPY {:?}.{:?} TO STDOUT (FORMAT TEXT, DELIMITER '\t')",
-                        info.desc.namespace, info.desc.name
-                    )
-                    .as_str(),
-                )
-                .await?;
+    fn text_row(values: &[Option<&str>]) -> Row {
+        Row::pack(values.iter().map(|value| match value {
+            Some(text) => Datum::String(text),
+            None => Datum::Null,
+        }))
+    }
 
-            tokio::pin!(reader);
-            let mut text_row = Row::default();
-            // TODO: once tokio-stream is released with https://github.com/tokio-rs/tokio/pull/4502
-            //    we can convert this into a single `timeout(...)` call on the reader CopyOutStream
-            while let Some(b) = tokio::time::timeout(Duration::from_secs(30), reader.next())
-                .await?
-                .transpose()?
-            {
-                let mut packer = text_row.packer();
-                // Convert raw rows from COPY into repr:Row. Each Row is a relation_id
-                // and list of string-encoded values, e.g. Row{ 16391 , ["1", "2"] }
-                let parser = mz_pgcopy::CopyTextFormatParser::new(b.as_ref(), "\t", "\\N");
-
-                let mut raw_values = parser.iter_raw_truncating(info.desc.columns.len());
-                while let Some(raw_value) = raw_values.next() {
-                    match raw_value.err_definite()? {
-                        Some(value) => {
-                            packer.push(Datum::String(std::str::from_utf8(value).err_definite()?))
-                        }
-                        None => packer.push(Datum::Null),
+    /// A decoded event: the LSN and, unless it is a progress event, the `(output, row, diff)`
+    /// update.
+    type TestEvent = (u64, Option<(usize, Row, Diff)>);
+
+    /// Consumes the messages of `stream` on top of `state`, returning the events produced until
+    /// the stream ended or failed, along with the failure. Time is simulated, and consumption
+    /// gives up after a minute of it.
+    fn consume(
+        stream: &mut TestStream,
+        state: &mut ReplicationState,
+        max_transaction_rows: Option<usize>,
+        ping_interval: Option<Duration>,
+        wal_lag_grace_period: Duration,
+        metrics: &PgSourceMetrics,
+    ) -> (Vec<TestEvent>, Option<ReplicationError>) {
+        let committed_lsn = AtomicU64::new(0x8);
+        let limits = PgSourceLimits::default();
+        let source_tables = BTreeMap::from([(TABLE_OID, source_table(1, table_desc()))]);
+        let mut log_dedup = LogDedup::new(log_dedup::DEFAULT_WINDOW);
+        let mut table_stats = TableStats::new(GlobalId::User(1), table_stats::DEFAULT_INTERVAL);
+        let span = Span::none();
+        let events = consume_replication_stream(
+            stream,
+            state,
+            &committed_lsn,
+            metrics,
+            &limits,
+            &source_tables,
+            false,
+            max_transaction_rows,
+            ping_interval,
+            wal_lag_grace_period,
+            &mut log_dedup,
+            &mut table_stats,
+            None,
+            false,
+            None,
+            &span,
+        );
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .start_paused(true)
+            .build()
+            .unwrap();
+        let mut decoded = vec![];
+        let consumed = runtime.block_on(tokio::time::timeout(Duration::from_secs(60), async {
+            let mut events = Box::pin(events);
+            while let Some(event) = events.next().await {
+                match event {
+                    Ok(Event::Message(lsn, (output, row, diff, _))) => {
+                        let row = row.expect("text columns always cast");
+                        decoded.push((u64::from(lsn), Some((output, row, diff))))
                     }
+                    Ok(Event::Progress([lsn])) => decoded.push((u64::from(lsn), None)),
+                    Err(err) => return Some(err),
                 }
+            }
+            None
+        }));
+        (decoded, consumed.unwrap_or(None))
+    }
 
-                let mut datums = datum_vec.borrow();
-                datums.extend(text_row.iter());
+    fn test_stream(messages: Vec<ReplicationStreamItem>) -> TestStream {
+        TestStream {
+            messages: messages.into(),
+            tables: BTreeMap::from([(TABLE_OID, table_desc())]),
+            types: BTreeMap::new(),
+            feedback: vec![],
+            stall: false,
+            broken: false,
+        }
+    }
 
-                let row = cast_row(&info.casts, &datums).err_definite()?;
+    fn test_metrics() -> PgSourceMetrics {
+        let base_metrics = SourceBaseMetrics::register_with(&MetricsRegistry::new());
+        PgSourceMetrics::new(&base_metrics, GlobalId::User(1))
+    }
 
-                yield (info.output_index, row);
-            }
+    #[test]
+    fn replication_decoding() {
+        let mut stream = test_stream(vec![
+            begin(0x10, 1),
+            insert(TABLE_OID, &[Value::Text("a"), Value::Null]),
+            // Changes to tables that are not part of the source are ignored
+            insert(TABLE_OID + 1, &[Value::Text("x")]),
+            update(
+                TABLE_OID,
+                &[Value::Text("a"), Value::Text("b")],
+                &[Value::Text("c"), Value::UnchangedToast],
+            ),
+            relation(TABLE_OID),
+            commit(0x10, 0x18),
+        ]);
+        let mut state = ReplicationState::new(PgLsn::from(0x8));
+        let metrics = test_metrics();
+        let (events, err) = consume(
+            &mut stream,
+            &mut state,
+            None,
+            None,
+            WAL_LAG_GRACE_PERIOD,
+            &metrics,
+        );
+
+        assert!(err.is_none(), "unexpected error: {err:?}");
+        assert_eq!(
+            events,
+            vec![
+                (0x18, Some((1, text_row(&[Some("a"), Some("b")]), -1))),
+                (0x18, Some((1, text_row(&[Some("a"), None]), 1))),
+                (0x18, Some((1, text_row(&[Some("c"), Some("b")]), 1))),
+                (0x19, None),
+            ]
+        );
+        assert_eq!(state.last_commit_lsn, PgLsn::from(0x18));
+        assert_eq!(state.current_tx_timestamp, Some(946_684_800_000));
+        assert_eq!(metrics.ignored.get(), 1);
+    }
 
-            metrics.tables.inc();
+    /// Rewinds the snapshot `snapshot` of output 1 taken at `snapshot_lsn` with the `events` of
+    /// the replication stream, like the source does, returning the consolidated contents of the
+    /// output at the LSN replication starts from.
+    fn rewound(
+        snapshot: &[Row],
+        snapshot_lsn: u64,
+        events: &[TestEvent],
+    ) -> BTreeMap<(usize, Row), Diff> {
+        let rewind = Rewind::new(BTreeMap::from([(
+            1,
+            TableSnapshot::at(PgLsn::from(snapshot_lsn)),
+        )]));
+        let mut contents = BTreeMap::new();
+        for row in snapshot {
+            *contents.entry((1, row.clone())).or_default() += 1;
+        }
+        for (lsn, update) in events {
+            match update {
+                Some((output, row, diff)) => {
+                    let retraction =
+                        rewind.retraction(*output, PgLsn::from(*lsn), &Ok(row.clone()), *diff);
+                    if let Some(diff) = retraction {
+                        *contents.entry((*output, row.clone())).or_default() += diff;
+                    }
+                }
+                None if rewind.is_done(PgLsn::from(*lsn)) => break,
+                None => {}
+            }
         }
+        contents.retain(|_, diff| *diff != 0);
+        contents
     }
-}
 
-/// Packs a Tuple received in the replication stream into a Row packer.
-fn datums_from_tuple<'a, T>(
-    rel_id: u32,
-    len: usize,
-    tuple_data: T,
-    datums: &mut Vec<Datum<'a>>,
-) -> Result<(), anyhow::Error>
-where
-    T: IntoIterator<Item = &'a TupleData>,
-{
-    for val in tuple_data.into_iter().take(len) {
-        let datum = match val {
-            TupleData::Null => Datum::Null,
-            TupleData::UnchangedToast => bail!(
-                "Missing TOASTed value from table with OID = {}. \
-                Did you forget to set REPLICA IDENTITY to FULL for your table?",
-                rel_id
+    #[test]
+    fn snapshot_rewinds() {
+        let row = |key, value| text_row(&[Some(key), Some(value)]);
+        let mut stream = test_stream(vec![
+            begin(0x10, 1),
+            insert(TABLE_OID, &[Value::Text("k1"), Value::Text("v1")]),
+            // Inserted and deleted again within the rewind
+            insert(TABLE_OID, &[Value::Text("k2"), Value::Text("v1")]),
+            commit(0x10, 0x18),
+            // A chain of updates to the same key within one transaction
+            begin(0x20, 2),
+            update(
+                TABLE_OID,
+                &[Value::Text("k1"), Value::Text("v1")],
+                &[Value::Text("k1"), Value::Text("v2")],
             ),
-            TupleData::Text(b) => std::str::from_utf8(b)?.into(),
-        };
-        datums.push(datum);
-    }
-    Ok(())
-}
+            update(
+                TABLE_OID,
+                &[Value::Text("k1"), Value::Text("v2")],
+                &[Value::Text("k1"), Value::Text("v3")],
+            ),
+            delete(TABLE_OID, &[Value::Text("k2"), Value::Text("v1")]),
+            commit(0x20, 0x28),
+            // Updates back to an earlier value, and a key that is inserted and deleted within
+            // one transaction
+            begin(0x30, 3),
+            update(
+                TABLE_OID,
+                &[Value::Text("k1"), Value::Text("v3")],
+                &[Value::Text("k1"), Value::Text("v1")],
+            ),
+            insert(TABLE_OID, &[Value::Text("k3"), Value::Text("v1")]),
+            delete(TABLE_OID, &[Value::Text("k3"), Value::Text("v1")]),
+            insert(TABLE_OID, &[Value::Text("k2"), Value::Text("v2")]),
+            commit(0x30, 0x38),
+            begin(0x40, 4),
+            update(
+                TABLE_OID,
+                &[Value::Text("k1"), Value::Text("v1")],
+                &[Value::Text("k1"), Value::Text("v4")],
+            ),
+            commit(0x40, 0x48),
+        ]);
+        let mut state = ReplicationState::new(PgLsn::from(0x8));
+        let metrics = test_metrics();
+        let (events, err) = consume(
+            &mut stream,
+            &mut state,
+            None,
+            None,
+            WAL_LAG_GRACE_PERIOD,
+            &metrics,
+        );
+        assert!(err.is_none(), "unexpected error: {err:?}");
 
-/// Casts a text row into the target types
-fn cast_row(table_cast: &[MirScalarExpr], datums: &[Datum<'_>]) -> Result<Row, anyhow::Error> {
-    let arena = mz_repr::RowArena::new();
-    let mut row = Row::default();
-    let mut packer = row.packer();
-    for column_cast in table_cast {
-        let datum = column_cast.eval(datums, &arena)?;
-        packer.push(datum);
+        // The table was empty at the slot LSN, so every snapshot must be rewound to nothing,
+        // whichever transactions it includes. A transaction that committed exactly at the
+        // snapshot LSN is part of the snapshot, and must be rewound along with it.
+        let snapshots = [
+            (0x17, vec![]),
+            (0x18, vec![row("k1", "v1"), row("k2", "v1")]),
+            (0x27, vec![row("k1", "v1"), row("k2", "v1")]),
+            (0x28, vec![row("k1", "v3")]),
+            (0x38, vec![row("k1", "v1"), row("k2", "v2")]),
+            (0x48, vec![row("k1", "v4"), row("k2", "v2")]),
+        ];
+        for (snapshot_lsn, snapshot) in snapshots {
+            assert_eq!(
+                rewound(&snapshot, snapshot_lsn, &events),
+                BTreeMap::new(),
+                "snapshot at {snapshot_lsn:#x}"
+            );
+        }
+
+        // Rewinding a snapshot that excludes the transaction at the boundary would retract it
+        // without the snapshot ever having inserted it.
+        assert_eq!(
+            rewound(&[row("k1", "v3")], 0x38, &events),
+            BTreeMap::from([
+                ((1, row("k1", "v3")), 1),
+                ((1, row("k1", "v1")), -1),
+                ((1, row("k2", "v2")), -1),
+            ])
+        );
+
+        // Outputs without a snapshot to rewind are left alone, and the rewind only waits for the
+        // outputs that have one.
+        let rewind = Rewind::new(BTreeMap::from([
+            (1, TableSnapshot::at(PgLsn::from(0x28))),
+            (2, TableSnapshot::at(PgLsn::from(0x38))),
+        ]));
+        let a = Ok(row("k1", "v1"));
+        assert_eq!(rewind.snapshot_lsn(), Some(PgLsn::from(0x38)));
+        assert_eq!(rewind.retraction(3, PgLsn::from(0x18), &a, 1), None);
+        assert_eq!(rewind.retraction(1, PgLsn::from(0x38), &a, 1), None);
+        assert_eq!(rewind.retraction(2, PgLsn::from(0x38), &a, 1), Some(-1));
+        assert!(!rewind.is_done(PgLsn::from(0x38)));
+        assert!(rewind.is_done(PgLsn::from(0x39)));
+        assert_eq!(Rewind::new(BTreeMap::new()).snapshot_lsn(), None);
     }
-    Ok(row)
-}
 
-// TODO(guswynn|petrosagg): fix the underlying bug that prevents client re-use
-// when exiting the CopyBoth mode, so we don't need to re-create clients in every loop
-// in this function.
-async fn produce_replication<'a>(
-    client_config: mz_postgres_util::Config,
-    slot: &'a str,
-    publication: &'a str,
-    as_of: PgLsn,
-    committed_lsn: Arc<AtomicU64>,
-    metrics: &'a PgSourceMetrics,
-    source_tables: &'a BTreeMap<u32, SourceTable>,
-) -> impl futures::Stream<Item = Result<Event<[PgLsn; 1], (usize, Row, Diff)>, ReplicationError>> + 'a
-{
-    use ReplicationError::*;
-    use ReplicationMessage::*;
-    async_stream::try_stream!({
-        //let mut last_data_message = Instant::now();
-        let mut inserts = vec![];
-        let mut deletes = vec![];
+    #[test]
+    fn resumed_snapshots() {
+        let column = |name: &str, col_num, type_oid| PostgresColumnDesc {
+            name: name.into(),
+            col_num: Some(col_num),
+            type_oid,
+            type_mod: -1,
+            nullable: false,
+            domain_constraints: vec![],
+            default_expr: None,
+        };
+        let primary_key = |cols| PostgresKeyDesc {
+            oid: 1,
+            name: "t1_pkey".into(),
+            cols,
+            is_primary: true,
+            nulls_not_distinct: false,
+        };
+        let desc = PostgresTableDesc {
+            columns: vec![column("v", 1, 25), column("id", 2, 23)],
+            keys: BTreeSet::from([primary_key(vec![2])]),
+            ..table_desc()
+        };
+        assert_eq!(resume_key(&desc), Some(vec![1]));
+        // Text is compared by its collation upstream, and tables without a primary key have no
+        // order to resume in.
+        let text_key = PostgresTableDesc {
+            keys: BTreeSet::from([primary_key(vec![1])]),
+            ..desc.clone()
+        };
+        assert_eq!(resume_key(&text_key), None);
+        assert_eq!(resume_key(&table_desc()), None);
 
-        let mut last_feedback = Instant::now();
+        let info = source_table(1, desc);
+        assert_eq!(
+            ordered_snapshot_query(&info, &[1], None),
+            r#"SELECT "v", "id" FROM "public"."t1" ORDER BY "id""#
+        );
+        let key = |id| Row::pack([Datum::Int32(id)]);
+        assert_eq!(
+            ordered_snapshot_query(&info, &[1], Some(&key(10))),
+            r#"SELECT "v", "id" FROM "public"."t1" WHERE ("id") > (10) ORDER BY "id""#
+        );
 
-        // Scratch space to use while evaluating casts
-        let mut datum_vec = DatumVec::new();
+        let row = |v, id| Row::pack([Datum::String(v), Datum::Int32(id)]);
+        assert_eq!(row_key(&row("a", 10), &[1]), Some(key(10)));
+        // A key that is ingested as text can't be compared like upstream compares it.
+        assert_eq!(row_key(&text_row(&[Some("a"), Some("10")]), &[1]), None);
 
-        let mut last_commit_lsn = as_of;
-        let mut observed_wal_end = as_of;
-        // The outer loop alternates the client between streaming the replication slot and using
-        // normal SQL queries with pg admin functions to fast-foward our cursor in the event of WAL
-        // lag.
-        //
-        // TODO(petrosagg): we need to do the above because a replication slot can be active only
-        // one place which is why we need to do this dance of entering and exiting replication mode
-        // in order to be able to use the administrative functions below. Perhaps it's worth
-        // creating two independent slots so that we can use the secondary to check without
-        // interrupting the stream on the first one
-        loop {
-            let client = client_config
-                .clone()
-                .connect_replication()
-                .await
-                .err_indefinite()?;
-            tracing::trace!("starting replication slot");
-            let query = format!(
-                r#"START_REPLICATION SLOT "{name}" LOGICAL {lsn}
-                  ("proto_version" '1', "publication_names" '{publication}')"#,
-                name = &slot,
-                lsn = last_commit_lsn,
-                publication = publication
+        // The table held the rows 1 to 20 at the slot LSN. The rows up to 10 were sent in a
+        // snapshot at 0x18 before its connection was lost, and the others in one at 0x28.
+        let snapshot = TableSnapshot {
+            key: vec![1],
+            splits: vec![(key(10), PgLsn::from(0x18))],
+            lsn: PgLsn::from(0x28),
+        };
+        let rewind = Rewind::new(BTreeMap::from([(1, snapshot)]));
+        let mut contents = BTreeMap::new();
+        for id in 1..=10 {
+            *contents.entry(row("a", id)).or_default() += 1;
+        }
+        // Changes committed before the first snapshot are part of both snapshots, and those
+        // committed between them only of the second one, i.e. of the rows after 10.
+        let changes = [
+            (0x10, row("a", 1), -1),
+            (0x10, row("a", 1), 1),
+            (0x20, row("a", 5), -1),
+            (0x20, row("b", 5), 1),
+            (0x20, row("a", 12), -1),
+            (0x20, row("a", 21), 1),
+            (0x20, row("a", 15), -1),
+        ];
+        let mut second = (11..=20).map(|id| row("a", id)).collect::<Vec<_>>();
+        second.retain(|r| *r != row("a", 12) && *r != row("a", 15));
+        second.push(row("a", 21));
+        for r in second {
+            *contents.entry(r).or_default() += 1;
+        }
+        for (lsn, r, diff) in changes {
+            if let Some(diff) = rewind.retraction(1, PgLsn::from(lsn), &Ok(r.clone()), diff) {
+                *contents.entry(r).or_default() += diff;
+            }
+        }
+        contents.retain(|_, diff: &mut Diff| *diff != 0);
+        let expected: BTreeMap<_, Diff> = (1..=20).map(|id| (row("a", id), 1)).collect();
+        assert_eq!(contents, expected);
+
+        // Errors have no key, and are attributed to the latest snapshot.
+        let err = Err(SourceErrorDetails::Other("boom".into()));
+        assert_eq!(rewind.retraction(1, PgLsn::from(0x20), &err, 1), Some(-1));
+        assert!(!rewind.is_done(PgLsn::from(0x28)));
+        assert!(rewind.is_done(PgLsn::from(0x29)));
+    }
+
+    #[test]
+    fn type_changes() {
+        let metrics = test_metrics();
+        let mut altered = table_desc();
+        altered.columns.pop();
+        let consume_all = |stream: &mut TestStream, state: &mut ReplicationState| {
+            consume(stream, state, None, None, WAL_LAG_GRACE_PERIOD, &metrics).1
+        };
+        let mood = |definition: &str| UpstreamType {
+            namespace: "public".into(),
+            name: "mood".into(),
+            definition: Some(definition.into()),
+        };
+
+        // The first announcement of a type is only recorded, and announcing it unchanged does
+        // not validate the tables using it.
+        let mut stream = test_stream(vec![type_(25, "mood"), type_(25, "mood")]);
+        stream.tables.insert(TABLE_OID, altered.clone());
+        stream.types.insert(25, "e {sad,happy}".into());
+        let mut state = ReplicationState::new(PgLsn::from(0x8));
+        let err = consume_all(&mut stream, &mut state);
+        assert!(err.is_none(), "unexpected error: {err:?}");
+        assert_eq!(state.types, BTreeMap::from([(25, mood("e {sad,happy}"))]));
+
+        // Types outlive connections, so a change is detected on a later one, be it to the
+        // definition of the type, like an enum that gained a value, or to its name.
+        for (name, definition) in [("mood", "e {sad,ok,happy}"), ("feeling", "e {sad,happy}")] {
+            let mut state = ReplicationState::new(PgLsn::from(0x8));
+            state.types.insert(25, mood("e {sad,happy}"));
+            let mut stream = test_stream(vec![type_(25, name)]);
+            stream.tables.insert(TABLE_OID, altered.clone());
+            stream.types.insert(25, definition.into());
+            let err = consume_all(&mut stream, &mut state);
+            assert!(
+                matches!(err, Some(ReplicationError::Definite(_))),
+                "{name} {definition}: {err:?}"
             );
-            let copy_stream = client.copy_both_simple(&query).await.err_indefinite()?;
-            let mut stream = Box::pin(LogicalReplicationStream::new(copy_stream));
+        }
 
-            let mut last_data_message = Instant::now();
+        // A type that was dropped and created anew under the same name has another OID, so the
+        // tables using the old one are validated.
+        let mut state = ReplicationState::new(PgLsn::from(0x8));
+        state.types.insert(25, mood("e {sad,happy}"));
+        let mut stream = test_stream(vec![type_(16500, "mood")]);
+        stream.tables.insert(TABLE_OID, altered);
+        stream.types.insert(16500, "e {sad,happy}".into());
+        let err = consume_all(&mut stream, &mut state);
+        assert!(
+            matches!(err, Some(ReplicationError::Definite(_))),
+            "{err:?}"
+        );
 
-            // The inner loop
-            loop {
-                // The upstream will periodically request status updates by setting the keepalive's
-                // reply field to 1. However, we cannot rely on these messages arriving on time. For
-                // example, when the upstream is sending a big transaction its keepalive messages are
-                // queued and can be delayed arbitrarily. Therefore, we also make sure to
-                // send a proactive status update every 30 seconds There is an implicit requirement
-                // that a new resumption frontier is converted into an lsn relatively soon after
-                // startup.
-                //
-                // See: https://www.postgresql.org/message-id/CAMsr+YE2dSfHVr7iEv1GSPZihitWX-PMkD9QALEGcTYa+sdsgg@mail.gmail.com
-                let mut needs_status_update = last_feedback.elapsed() > FEEDBACK_INTERVAL;
-
-                metrics.total.inc();
-                use LogicalReplicationMessage::*;
-                match stream.as_mut().next().await {
-                    Some(Ok(XLogData(xlog_data))) => match xlog_data.data() {
-                        Begin(_) => {
-                            last_data_message = Instant::now();
-                            if !inserts.is_empty() || !deletes.is_empty() {
-                                return Err(Definite(anyhow!(
-                                    "got BEGIN statement after uncommitted data"
-                                )))?;
-                            }
-                        }
-                        Insert(insert) if source_tables.contains_key(&insert.rel_id()) => {
-                            last_data_message = Instant::now();
-                            metrics.inserts.inc();
-                            let rel_id = insert.rel_id();
-                            let info = source_tables.get(&rel_id).unwrap();
-                            let new_tuple = insert.tuple().tuple_data();
-                            let mut datums = datum_vec.borrow();
-
-                            datums_from_tuple(
-                                rel_id,
-                                info.desc.columns.len(),
-                                new_tuple,
-                                &mut *datums,
-                            )
-                            .err_definite()?;
+        // Tables that do not use the type are left alone.
+        let mut state = ReplicationState::new(PgLsn::from(0x8));
+        let mut stream = test_stream(vec![type_(16600, "mood"), type_(16600, "feeling")]);
+        stream.tables.clear();
+        let err = consume_all(&mut stream, &mut state);
+        assert!(err.is_none(), "unexpected error: {err:?}");
+    }
 
-                            let row = cast_row(&info.casts, &datums).err_definite()?;
-                            inserts.push((info.output_index, row));
-                        }
-                        Update(update) if source_tables.contains_key(&update.rel_id()) => {
-                            last_data_message = Instant::now();
-                            metrics.updates.inc();
-                            let rel_id = update.rel_id();
-                            let info = source_tables.get(&rel_id).unwrap();
-                            let err = || {
-                                anyhow!(
-                                    "Old row missing from replication stream for table with OID = {}.
-                                     Did you forget to set REPLICA IDENTITY to FULL for your table?",
-                                    rel_id
-                                )
-                            };
-                            let old_tuple = update
-                                .old_tuple()
-                                .ok_or_else(err)
-                                .err_definite()?
-                                .tuple_data();
-
-                            let mut old_datums = datum_vec.borrow();
-
-                            datums_from_tuple(
-                                rel_id,
-                                info.desc.columns.len(),
-                                old_tuple,
-                                &mut *old_datums,
-                            )
-                            .err_definite()?;
-
-                            let old_row = cast_row(&info.casts, &old_datums).err_definite()?;
-                            deletes.push((info.output_index, old_row));
-                            drop(old_datums);
-
-                            // If the new tuple contains unchanged toast values, reuse the ones
-                            // from the old tuple
-                            let new_tuple = update
-                                .new_tuple()
-                                .tuple_data()
-                                .iter()
-                                .zip(old_tuple.iter())
-                                .map(|(new, old)| match new {
-                                    TupleData::UnchangedToast => old,
-                                    _ => new,
-                                });
-                            let mut new_datums = datum_vec.borrow();
+    #[test]
+    fn replication_decoding_errors() {
+        let metrics = test_metrics();
+        let mut stream = test_stream(vec![
+            begin(0x10, 1),
+            insert(TABLE_OID, &[Value::Text("a"), Value::Null]),
+            begin(0x20, 2),
+        ]);
+        let mut state = ReplicationState::new(PgLsn::from(0x8));
+        let (events, err) = consume(
+            &mut stream,
+            &mut state,
+            None,
+            None,
+            WAL_LAG_GRACE_PERIOD,
+            &metrics,
+        );
+        assert_eq!(events, vec![]);
+        match err {
+            Some(ReplicationError::Definite(err)) => assert_eq!(
+                err.to_string(),
+                "got BEGIN statement after uncommitted data"
+            ),
+            err => panic!("unexpected result: {err:?}"),
+        }
 
-                            datums_from_tuple(
-                                rel_id,
-                                info.desc.columns.len(),
-                                new_tuple,
-                                &mut *new_datums,
-                            )
-                            .err_definite()?;
+        let mut stream = test_stream(vec![begin(0x10, 1), relation(TABLE_OID)]);
+        stream.tables.clear();
+        let mut state = ReplicationState::new(PgLsn::from(0x8));
+        let (_, err) = consume(
+            &mut stream,
+            &mut state,
+            None,
+            None,
+            WAL_LAG_GRACE_PERIOD,
+            &metrics,
+        );
+        match err {
+            Some(ReplicationError::Definite(err)) => assert!(matches!(
+                err.downcast_ref::<SourceErrorDetails>(),
+                Some(SourceErrorDetails::TableDropped {
+                    table_oid: TABLE_OID,
+                    ..
+                })
+            )),
+            err => panic!("unexpected result: {err:?}"),
+        }
+    }
 
-                            let new_row = cast_row(&info.casts, &new_datums).err_definite()?;
-                            inserts.push((info.output_index, new_row));
-                        }
-                        Delete(delete) if source_tables.contains_key(&delete.rel_id()) => {
-                            last_data_message = Instant::now();
-                            metrics.deletes.inc();
-                            let rel_id = delete.rel_id();
-                            let info = source_tables.get(&rel_id).unwrap();
-                            let err = || {
-                                anyhow!(
-                                    "Old row missing from replication stream for table with OID = {}.
-                                     Did you forget to set REPLICA IDENTITY to FULL for your table?",
-                                    rel_id
-                                )
-                            };
-                            let old_tuple = delete
-                                .old_tuple()
-                                .ok_or_else(err)
-                                .err_definite()?
-                                .tuple_data();
-                            let mut datums = datum_vec.borrow();
-
-                            datums_from_tuple(
-                                rel_id,
-                                info.desc.columns.len(),
-                                old_tuple,
-                                &mut *datums,
-                            )
-                            .err_definite()?;
+    #[test]
+    fn replication_split_transaction() {
+        let metrics = test_metrics();
+        let transaction = || {
+            vec![
+                begin(0x10, 1),
+                insert(TABLE_OID, &[Value::Text("a"), Value::Null]),
+                insert(TABLE_OID, &[Value::Text("b"), Value::Null]),
+                insert(TABLE_OID, &[Value::Text("c"), Value::Null]),
+            ]
+        };
+        let row = |text| text_row(&[Some(text), None]);
 
-                            let row = cast_row(&info.casts, &datums).err_definite()?;
-                            deletes.push((info.output_index, row));
-                        }
-                        Commit(commit) => {
-                            last_data_message = Instant::now();
-                            metrics.transactions.inc();
-                            last_commit_lsn = PgLsn::from(commit.end_lsn());
+        let mut messages = transaction();
+        messages.push(commit(0x10, 0x18));
+        let mut stream = test_stream(messages);
+        let mut state = ReplicationState::new(PgLsn::from(0x8));
+        let (events, err) = consume(
+            &mut stream,
+            &mut state,
+            Some(1),
+            None,
+            WAL_LAG_GRACE_PERIOD,
+            &metrics,
+        );
+        assert!(err.is_none(), "unexpected error: {err:?}");
+        // Everything but the last change is emitted at the commit record's LSN ahead of time.
+        assert_eq!(
+            events,
+            vec![
+                (0x10, Some((1, row("a"), 1))),
+                (0x10, Some((1, row("b"), 1))),
+                (0x18, Some((1, row("c"), 1))),
+                (0x19, None),
+            ]
+        );
+        assert_eq!(metrics.transactions_split.get(), 2);
 
-                            for (output, row) in deletes.drain(..) {
-                                yield Event::Message(last_commit_lsn, (output, row, -1));
-                            }
-                            for (output, row) in inserts.drain(..) {
-                                yield Event::Message(last_commit_lsn, (output, row, 1));
-                            }
-                            yield Event::Progress([PgLsn::from(u64::from(last_commit_lsn) + 1)]);
-                            metrics.lsn.set(last_commit_lsn.into());
-                        }
-                        Relation(relation) => {
-                            last_data_message = Instant::now();
-                            let rel_id = relation.rel_id();
-                            if let Some(info) = source_tables.get(&rel_id) {
-                                // Because the replication stream doesn't include columns'
-                                // attnums, we need to check the current local schema against
-                                // the current remote schema to ensure e.g. we haven't received
-                                // a schema update with the same terminal column name which is
-                                // actually a different column.
-                                let current_publication_info = mz_postgres_util::publication_info(
-                                    &client_config,
-                                    publication,
-                                    Some(rel_id),
-                                )
-                                .await
-                                .err_indefinite()?;
-
-                                match current_publication_info.get(0) {
-                                    Some(desc) => {
-                                        // Keep this method in sync with the check in
-                                        // validate_tables.
-                                        info.desc
-                                            .determine_compatibility(desc)
-                                            .map_err(Definite)?;
-                                    }
-                                    None => {
-                                        warn!(
-                                            "alter table error, table removed from upstream source: name {}, oid {}, old_schema {:?}",
-                                            info.desc.name,
-                                            info.desc.oid,
-                                            info.desc.columns,
-                                        );
-                                        return Err(Definite(anyhow!(
-                                            "source table {} with oid {} has been dropped",
-                                            info.desc.name,
-                                            info.desc.oid
-                                        )))?;
-                                    }
-                                }
-                            }
-                        }
-                        Insert(_) | Update(_) | Delete(_) | Origin(_) | Type(_) => {
-                            last_data_message = Instant::now();
-                            metrics.ignored.inc();
-                        }
-                        Truncate(truncate) => {
-                            let tables = truncate
-                                .rel_ids()
-                                .iter()
-                                // Filter here makes option handling in map "safe"
-                                .filter_map(|id| source_tables.get(id))
-                                .map(|info| {
-                                    format!("name: {} id: {}", info.desc.name, info.desc.oid)
-                                })
-                                .collect::<Vec<String>>();
-                            return Err(Definite(anyhow!(
-                                "source table(s) {} got truncated",
-                                tables.join(", ")
-                            )))?;
-                        }
-                        // The enum is marked as non_exhaustive. Better to be conservative here in
-                        // case a new message is relevant to the semantics of our source
-                        _ => {
-                            return Err(Definite(anyhow!(
-                                "unexpected logical replication message"
-                            )))?;
-                        }
-                    },
-                    Some(Ok(PrimaryKeepAlive(keepalive))) => {
-                        needs_status_update = needs_status_update || keepalive.reply() == 1;
-                        observed_wal_end = PgLsn::from(keepalive.wal_end());
+        // Losing the connection in the middle of a split transaction cannot be recovered from
+        // by replaying it.
+        let mut stream = test_stream(transaction());
+        let mut state = ReplicationState::new(PgLsn::from(0x8));
+        let (events, err) = consume(
+            &mut stream,
+            &mut state,
+            Some(1),
+            None,
+            WAL_LAG_GRACE_PERIOD,
+            &metrics,
+        );
+        assert_eq!(events.len(), 2);
+        assert!(matches!(err, Some(ReplicationError::Indefinite(_))));
+    }
 
-                        if last_data_message.elapsed() > WAL_LAG_GRACE_PERIOD {
-                            break;
-                        }
-                    }
-                    Some(Err(err)) => {
-                        return Err(ReplicationError::from(err))?;
-                    }
-                    None => {
-                        break;
-                    }
-                    // The enum is marked non_exhaustive, better be conservative
-                    _ => {
-                        return Err(Definite(anyhow!("Unexpected replication message")))?;
-                    }
-                }
-                if needs_status_update {
-                    let ts: i64 = PG_EPOCH
-                        .elapsed()
-                        .expect("system clock set earlier than year 2000!")
-                        .as_micros()
-                        .try_into()
-                        .expect("software more than 200k years old, consider updating");
-
-                    let committed_lsn = PgLsn::from(committed_lsn.load(Ordering::SeqCst));
-                    let standby_res = stream
-                        .as_mut()
-                        .standby_status_update(committed_lsn, committed_lsn, committed_lsn, ts, 0)
-                        .await;
-                    if let Err(err) = standby_res {
-                        return Err(Indefinite(err.into()))?;
-                    }
-                    last_feedback = Instant::now();
-                }
-            }
-            // This may not be required, but as mentioned above in
-            // `postgres_replication_loop_inner`, we drop clients aggressively out of caution.
-            drop(stream);
+    #[test]
+    fn transaction_buffer_memory() {
+        let metrics = test_metrics();
+        let row = text_row(&[Some("a"), None]);
 
-            let client = client_config
-                .clone()
-                .connect_replication()
-                .await
-                .err_indefinite()?;
+        // The changes of a transaction are accounted for until it commits, even across
+        // connections.
+        let mut stream = test_stream(vec![
+            begin(0x10, 1),
+            insert(TABLE_OID, &[Value::Text("a"), Value::Null]),
+        ]);
+        let mut state = ReplicationState::new(PgLsn::from(0x8));
+        let (_, err) = consume(
+            &mut stream,
+            &mut state,
+            None,
+            None,
+            WAL_LAG_GRACE_PERIOD,
+            &metrics,
+        );
+        assert!(err.is_none(), "unexpected error: {err:?}");
+        assert_eq!(
+            metrics.transaction_buffer_memory_bytes.get(),
+            u64::cast_from(row.byte_len())
+        );
 
-            // We reach this place if the consume loop above detected large WAL lag. This
-            // section determines whether or not we can skip over that part of the WAL by
-            // peeking into the replication slot using a normal SQL query and the
-            // `pg_logical_slot_peek_binary_changes` administrative function.
-            //
-            // By doing so we can get a positive statement about existence or absence of
-            // relevant data from the current LSN to the observed WAL end. If there are no
-            // messages then it is safe to fast forward last_commit_lsn to the WAL end LSN and restart
-            // the replication stream from there.
-            let query = format!(
-                "SELECT lsn FROM pg_logical_slot_peek_binary_changes(
-                     '{name}', NULL, NULL,
-                     'proto_version', '1',
-                     'publication_names', '{publication}'
-                )",
-                name = &slot,
-                publication = publication
+        let mut stream = test_stream(vec![commit(0x10, 0x18)]);
+        let (events, err) = consume(
+            &mut stream,
+            &mut state,
+            None,
+            None,
+            WAL_LAG_GRACE_PERIOD,
+            &metrics,
+        );
+        assert!(err.is_none(), "unexpected error: {err:?}");
+        assert_eq!(events, vec![(0x18, Some((1, row, 1))), (0x19, None)]);
+        assert_eq!(metrics.transaction_buffer_memory_bytes.get(), 0);
+
+        assert_eq!(transaction_buffer_status(None), HealthStatus::Running);
+        let degraded = transaction_buffer_status(Some(1024));
+        assert_eq!(degraded.name(), "degraded");
+        assert!(degraded.hint().unwrap().contains("MAX TRANSACTION ROWS"));
+    }
+
+    #[test]
+    fn backpressure() {
+        let metrics = test_metrics();
+        let limits = PgSourceLimits::default();
+        limits.backpressure_lag_bytes.store(0x100, Ordering::SeqCst);
+        let committed_lsn = AtomicU64::new(0x8);
+        let mut stream = test_stream(vec![]);
+        let mut stream = &mut stream;
+        let mut feedback = Feedback::new();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .start_paused(true)
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            // Emitted changes within the limit don't wait.
+            let waited = wait_for_downstream(
+                &mut stream,
+                PgLsn::from(0x108),
+                &committed_lsn,
+                &mut feedback,
+                &limits,
+                &metrics,
+            )
+            .await;
+            assert!(!waited.unwrap());
+
+            // Beyond it, the stream is not read from until downstream catches up.
+            let wait = wait_for_downstream(
+                &mut stream,
+                PgLsn::from(0x200),
+                &committed_lsn,
+                &mut feedback,
+                &limits,
+                &metrics,
             );
+            let catch_up = async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                assert_eq!(metrics.backpressure_active.get(), 1);
+                committed_lsn.store(0x100, Ordering::SeqCst);
+            };
+            let (waited, ()) = futures::join!(wait, catch_up);
+            assert!(waited.unwrap());
+        });
+        assert_eq!(metrics.backpressure_active.get(), 0);
+    }
 
-            let peek_binary_start_time = Instant::now();
-            let rows = client.simple_query(&query).await.err_indefinite()?;
+    #[test]
+    fn replication_wal_lag() {
+        let metrics = test_metrics();
 
-            let changes = rows
-                .into_iter()
-                .filter(|row| match row {
-                    SimpleQueryMessage::Row(row) => {
-                        let change_lsn: PgLsn = row
-                            .get("lsn")
-                            .expect("missing expected column: `lsn`")
-                            .parse()
-                            .expect("invalid lsn");
-                        // Keep all the changes that may exist after our last observed transaction
-                        // commit
-                        change_lsn > last_commit_lsn
-                    }
-                    SimpleQueryMessage::CommandComplete(_) => false,
-                    _ => panic!("unexpected enum variant"),
-                })
-                .count();
+        // Keepalives requesting a reply get the committed LSN reported back.
+        let mut stream = test_stream(vec![keepalive(0x100, 1)]);
+        let mut state = ReplicationState::new(PgLsn::from(0x8));
+        let (events, err) = consume(
+            &mut stream,
+            &mut state,
+            None,
+            None,
+            WAL_LAG_GRACE_PERIOD,
+            &metrics,
+        );
+        assert_eq!(events, vec![]);
+        assert!(err.is_none(), "unexpected error: {err:?}");
+        assert_eq!(stream.feedback, vec![PgLsn::from(0x8)]);
+        assert_eq!(state.observed_wal_end, PgLsn::from(0x100));
 
-            // If there are no changes until the end of the WAL it's safe to fast forward
-            if changes == 0 {
-                last_commit_lsn = observed_wal_end;
-                // `Progress` events are _frontiers_, so we add 1, just like when we
-                // handle data in `Commit` above.
-                yield Event::Progress([PgLsn::from(u64::from(last_commit_lsn) + 1)]);
+        // Without data within the grace period, the stream is abandoned so that the WAL in
+        // between can be peeked into.
+        let mut stream = test_stream(vec![
+            keepalive(0x200, 0),
+            begin(0x210, 1),
+            insert(TABLE_OID, &[Value::Text("a"), Value::Null]),
+            commit(0x210, 0x218),
+        ]);
+        let (events, err) = consume(
+            &mut stream,
+            &mut state,
+            None,
+            None,
+            Duration::ZERO,
+            &metrics,
+        );
+        assert_eq!(events, vec![]);
+        assert!(err.is_none(), "unexpected error: {err:?}");
+        assert_eq!(stream.messages.len(), 3);
+        assert_eq!(state.observed_wal_end, PgLsn::from(0x200));
+        assert_eq!(state.last_commit_lsn, PgLsn::from(0x8));
+    }
+
+    #[test]
+    fn replication_feedback_coalescing() {
+        let metrics = test_metrics();
+        let mut state = ReplicationState::new(PgLsn::from(0x8));
+
+        // The first request is answered right away, and the ones that arrive within the minimum
+        // interval after it by a single status update once it passed.
+        let mut stream = test_stream(vec![
+            keepalive(0x100, 1),
+            keepalive(0x110, 1),
+            begin(0x120, 1),
+            insert(TABLE_OID, &[Value::Text("a"), Value::Null]),
+            keepalive(0x120, 1),
+            commit(0x120, 0x128),
+            keepalive(0x130, 1),
+        ]);
+        stream.stall = true;
+        let (events, err) = consume(
+            &mut stream,
+            &mut state,
+            None,
+            None,
+            WAL_LAG_GRACE_PERIOD,
+            &metrics,
+        );
+        let row = text_row(&[Some("a"), None]);
+        assert_eq!(events, vec![(0x128, Some((1, row, 1))), (0x129, None)]);
+        assert!(err.is_none(), "unexpected error: {err:?}");
+        assert_eq!(stream.feedback, vec![PgLsn::from(0x8); 2]);
+        assert!(!state.feedback.reply_requested);
+    }
+
+    #[test]
+    fn replication_ping() {
+        let metrics = test_metrics();
+        let mut state = ReplicationState::new(PgLsn::from(0x8));
+
+        // Idle connections are checked every ping interval, without acknowledging anything new.
+        let mut stream = test_stream(vec![]);
+        stream.stall = true;
+        let (events, err) = consume(
+            &mut stream,
+            &mut state,
+            None,
+            Some(Duration::from_secs(7)),
+            WAL_LAG_GRACE_PERIOD,
+            &metrics,
+        );
+        assert_eq!(events, vec![]);
+        assert!(err.is_none(), "unexpected error: {err:?}");
+        assert_eq!(stream.feedback, vec![PgLsn::from(0x8); 8]);
+
+        // A broken connection is reported as soon as it is checked.
+        let mut stream = test_stream(vec![]);
+        stream.stall = true;
+        stream.broken = true;
+        let (events, err) = consume(
+            &mut stream,
+            &mut state,
+            None,
+            Some(Duration::from_secs(7)),
+            WAL_LAG_GRACE_PERIOD,
+            &metrics,
+        );
+        assert_eq!(events, vec![]);
+        assert!(matches!(err, Some(ReplicationError::Indefinite(_))));
+    }
+
+    /// Fails to read any secret until `failures` reads have been attempted.
+    #[derive(Debug)]
+    struct FlakySecretsReader {
+        failures: Mutex<usize>,
+    }
+
+    #[async_trait::async_trait]
+    impl SecretsReader for FlakySecretsReader {
+        async fn read(&self, id: GlobalId) -> Result<Vec<u8>, anyhow::Error> {
+            let mut failures = self.failures.lock().unwrap();
+            if *failures > 0 {
+                *failures -= 1;
+                anyhow::bail!("secret {id} does not exist");
             }
+            Ok(b"postgres".to_vec())
+        }
+    }
 
-            tracing::info!(
-                slot = ?slot,
-                query_time = ?peek_binary_start_time.elapsed(),
-                current_lsn = ?last_commit_lsn,
-                "Found {} changes in the wal.",
-                changes
+    #[tokio::test(start_paused = true)]
+    async fn missing_secrets_stall_the_source() {
+        let connection = PostgresConnection {
+            host: "postgres".into(),
+            port: 5432,
+            database: "postgres".into(),
+            user: StringOrSecret::String("postgres".into()),
+            password: Some(GlobalId::User(2)),
+            tunnel: Tunnel::Direct,
+            tls_mode: SslMode::Disable,
+            tls_root_cert: None,
+            tls_identity: None,
+            tls_sni_hostname: None,
+        };
+        let secrets_reader = FlakySecretsReader {
+            failures: Mutex::new(2),
+        };
+        let (tx, mut rx) = tokio::sync::mpsc::channel(10);
+        let row_sender = RowSender::new(tx, Arc::new(test_metrics()), PgLsn::from(0));
+
+        let start = tokio::time::Instant::now();
+        resolve_connection_config(&connection, &secrets_reader, &row_sender).await;
+        // The second attempt backs off twice as long as the first one.
+        assert!(start.elapsed() >= SECRETS_INITIAL_BACKOFF * 3);
+
+        let mut statuses = vec![];
+        while let Ok(message) = rx.try_recv() {
+            match message {
+                InternalMessage::Status(status) => statuses.push(status),
+                _ => panic!("unexpected message"),
+            }
+        }
+        assert_eq!(statuses.len(), 3);
+        for status in &statuses[..2] {
+            assert_eq!(
+                status.update.error(),
+                Some(
+                    "failed to read the secrets of the Postgres connection: \
+                     secret u2 does not exist"
+                )
+            );
+            assert_eq!(
+                status.update.hint(),
+                Some("Check that the secrets used by the connection exist.")
             );
+            assert!(!status.should_halt);
         }
-    })
+        assert_eq!(statuses[2].update, HealthStatus::Running);
+    }
 }
@@ -0,0 +1,300 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! The connections of a Postgres source to the server it replicates from.
+//!
+//! A source holds at most two connections to that server at a time, so that many sources don't
+//! exhaust its `max_connections`:
+//!
+//! - The replication connection, which streams changes from the slot, peeks into it, or copies
+//!   the snapshot. It is opened anew for every use, and the next one is only opened once the
+//!   previous one was dropped.
+//! - The metadata connection, which is shared by the queries that look up the schemas of tables
+//!   and administer the server. It is opened the first time it is needed and kept open, and is
+//!   opened anew if it breaks.
+//!
+//! Connections to a standby that the snapshot is copied from are not counted against the budget,
+//! as they go to a different server.
+
+use std::ops::Deref;
+use std::sync::Arc;
+use std::time::Duration;
+
+use mz_postgres_util::desc::PostgresTableDesc;
+use mz_postgres_util::{PostgresError, PublicationFilter};
+use mz_storage_client::types::sources::PgLogLevel;
+use tokio::sync::{Mutex, OwnedMutexGuard, OwnedSemaphorePermit, Semaphore};
+use tokio_postgres::Client;
+
+use super::metrics::PgSourceMetrics;
+
+/// How many times opening the replication connection is attempted before its error is returned
+const REPLICATION_CONNECT_ATTEMPTS: usize = 4;
+
+/// How long to wait before attempting to open the replication connection again after it first
+/// failed, which doubles with every further attempt until one succeeds
+const REPLICATION_CONNECT_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Hands out the connections of a source to the server it replicates from.
+pub(super) struct UpstreamConnections {
+    config: mz_postgres_util::Config,
+    metrics: Arc<PgSourceMetrics>,
+    /// The permit to hold the replication connection
+    replication: Arc<Semaphore>,
+    /// How long to wait after the next failed attempt to open the replication connection
+    replication_backoff: std::sync::Mutex<Duration>,
+    /// The metadata connection, if it is open
+    metadata: Arc<Mutex<Option<Client>>>,
+    /// The `client_min_messages` that every connection is opened with, if not the upstream
+    /// default
+    client_min_messages: Option<PgLogLevel>,
+    /// The slot that every replication connection copies into a temporary slot of its own once
+    /// opened, as `(slot, copy)`, if any
+    temporary_slot_copy: Option<(String, String)>,
+}
+
+impl UpstreamConnections {
+    pub(super) fn new(
+        config: mz_postgres_util::Config,
+        metrics: Arc<PgSourceMetrics>,
+        client_min_messages: Option<PgLogLevel>,
+    ) -> Self {
+        Self {
+            config,
+            metrics,
+            replication: Arc::new(Semaphore::new(1)),
+            replication_backoff: std::sync::Mutex::new(REPLICATION_CONNECT_BACKOFF),
+            metadata: Arc::new(Mutex::new(None)),
+            client_min_messages,
+            temporary_slot_copy: None,
+        }
+    }
+
+    /// Makes every replication connection copy `slot` into the temporary slot `copy` once opened.
+    ///
+    /// A temporary slot can only be used by the session that created it and is dropped when that
+    /// session ends, so the copy is never left behind, even if the process is killed. The copy
+    /// starts out at the confirmed flush LSN of `slot` at the time the connection is opened.
+    pub(super) fn with_temporary_slot_copy(mut self, slot: String, copy: String) -> Self {
+        self.temporary_slot_copy = Some((slot, copy));
+        self
+    }
+
+    /// Returns the upstream role that the connections are opened as, if configured.
+    pub(super) fn user(&self) -> Option<&str> {
+        self.config.user()
+    }
+
+    /// Opens the replication connection, once the previous one was dropped. Failed attempts are
+    /// retried with a jittered exponential backoff, so that the sources that lose their
+    /// connections to a server at the same time don't all reconnect at once. The backoff carries
+    /// over to the next call if no attempt succeeds.
+    pub(super) async fn replication(&self) -> Result<ReplicationClient, PostgresError> {
+        let permit = Arc::clone(&self.replication)
+            .acquire_owned()
+            .await
+            .expect("semaphore is never closed");
+        let mut backoff = self.replication_backoff();
+        let connected = self
+            .config
+            .connect_replication_with_retry(REPLICATION_CONNECT_ATTEMPTS, &mut backoff)
+            .await;
+        *self.replication_backoff.lock().expect("lock poisoned") = match connected {
+            Ok(_) => REPLICATION_CONNECT_BACKOFF,
+            Err(_) => backoff,
+        };
+        let client = connected?;
+        self.set_client_min_messages(&client).await?;
+        if let Some((slot, copy)) = &self.temporary_slot_copy {
+            client
+                .simple_query(&format!(
+                    "SELECT pg_copy_logical_replication_slot('{}', '{}', true)",
+                    slot.replace('\'', "''"),
+                    copy.replace('\'', "''"),
+                ))
+                .await?;
+        }
+        self.metrics.open_connections.inc();
+        Ok(ReplicationClient {
+            client,
+            _permit: permit,
+            metrics: Arc::clone(&self.metrics),
+        })
+    }
+
+    /// Returns how long to wait before attempting to open the replication connection again, which
+    /// grows while attempts keep failing.
+    pub(super) fn replication_backoff(&self) -> Duration {
+        *self.replication_backoff.lock().expect("lock poisoned")
+    }
+
+    /// Opens a connection to the standby at `config` that the snapshot is copied from, with the
+    /// same settings as the connections to the server we replicate from.
+    pub(super) async fn snapshot_standby(
+        &self,
+        config: &mz_postgres_util::Config,
+    ) -> Result<Client, PostgresError> {
+        let client = config.connect("postgres_snapshot_standby").await?;
+        self.set_client_min_messages(&client).await?;
+        Ok(client)
+    }
+
+    /// Returns the metadata connection, opening it if it is not open. It can't be used by anyone
+    /// else until the returned handle is dropped.
+    pub(super) async fn metadata(&self) -> Result<MetadataClient, PostgresError> {
+        let client = Arc::clone(&self.metadata).lock_owned().await;
+        self.open_metadata(client).await
+    }
+
+    /// Like [`Self::metadata`], but returns `None` rather than waiting while the metadata
+    /// connection is in use, e.g. by a replication stream that is not being polled.
+    pub(super) async fn try_metadata(&self) -> Option<Result<MetadataClient, PostgresError>> {
+        let client = Arc::clone(&self.metadata).try_lock_owned().ok()?;
+        Some(self.open_metadata(client).await)
+    }
+
+    /// Fetches the tables of `publications` over the metadata connection, like
+    /// [`mz_postgres_util::publication_info`].
+    pub(super) async fn publication_info(
+        &self,
+        publications: &[String],
+        filter: &PublicationFilter,
+    ) -> Result<Vec<PostgresTableDesc>, PostgresError> {
+        self.metadata()
+            .await?
+            .publication_info(publications, filter)
+            .await
+    }
+
+    /// Fetches the definition of the type with the given `oid` over the metadata connection, like
+    /// [`mz_postgres_util::type_definition`].
+    pub(super) async fn type_definition(&self, oid: u32) -> Result<Option<String>, PostgresError> {
+        let mut client = self.metadata().await?;
+        let definition = mz_postgres_util::type_definition(&client, oid).await;
+        // Like after fetching tables, a connection whose query failed is opened anew.
+        if definition.is_err() {
+            client.close();
+        }
+        definition
+    }
+
+    async fn open_metadata(
+        &self,
+        client: OwnedMutexGuard<Option<Client>>,
+    ) -> Result<MetadataClient, PostgresError> {
+        let mut client = MetadataClient {
+            client,
+            metrics: Arc::clone(&self.metrics),
+        };
+        if client
+            .client
+            .as_ref()
+            .map_or(false, |client| client.is_closed())
+        {
+            client.close();
+        }
+        if client.client.is_none() {
+            let connection = self.config.connect("postgres_source_metadata").await?;
+            self.set_client_min_messages(&connection).await?;
+            *client.client = Some(connection);
+            self.metrics.open_connections.inc();
+        }
+        Ok(client)
+    }
+
+    /// Sets the `client_min_messages` of the session of the newly opened `client`, if the source
+    /// configures it, so that upstream doesn't send the less severe notices about the session's
+    /// commands, which would otherwise end up in our logs.
+    async fn set_client_min_messages(&self, client: &Client) -> Result<(), PostgresError> {
+        if let Some(level) = self.client_min_messages {
+            // Replication connections only support the simple query protocol.
+            client
+                .simple_query(&format!("SET client_min_messages = {}", level.name()))
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+/// The replication connection of a source, which is closed when dropped.
+pub(super) struct ReplicationClient {
+    client: Client,
+    _permit: OwnedSemaphorePermit,
+    metrics: Arc<PgSourceMetrics>,
+}
+
+impl Deref for ReplicationClient {
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        &self.client
+    }
+}
+
+impl Drop for ReplicationClient {
+    fn drop(&mut self) {
+        self.metrics.open_connections.dec();
+    }
+}
+
+/// The connection a snapshot is copied over.
+pub(super) enum SnapshotClient {
+    /// The replication connection to the server we replicate from
+    Replication(ReplicationClient),
+    /// A connection to the snapshot standby, which is not counted against the budget
+    Standby(Client),
+}
+
+impl Deref for SnapshotClient {
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        match self {
+            SnapshotClient::Replication(client) => client,
+            SnapshotClient::Standby(client) => client,
+        }
+    }
+}
+
+/// Exclusive access to the metadata connection of a source.
+pub(super) struct MetadataClient {
+    client: OwnedMutexGuard<Option<Client>>,
+    metrics: Arc<PgSourceMetrics>,
+}
+
+impl MetadataClient {
+    /// Fetches the tables of `publications`, like [`mz_postgres_util::publication_info`].
+    pub(super) async fn publication_info(
+        &mut self,
+        publications: &[String],
+        filter: &PublicationFilter,
+    ) -> Result<Vec<PostgresTableDesc>, PostgresError> {
+        let info = mz_postgres_util::client_publication_info(self, publications, filter).await;
+        // A query that timed out may still be running, so the connection is opened anew rather
+        // than queueing the next query behind it.
+        if info.is_err() {
+            self.close();
+        }
+        info
+    }
+
+    fn close(&mut self) {
+        if self.client.take().is_some() {
+            self.metrics.open_connections.dec();
+        }
+    }
+}
+
+impl Deref for MetadataClient {
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        self.client.as_ref().expect("metadata connection is open")
+    }
+}
@@ -0,0 +1,349 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Decoding of the data sent by a Postgres source's `COPY ... TO STDOUT` snapshot queries.
+
+use anyhow::anyhow;
+use bytes::BytesMut;
+
+use mz_postgres_util::desc::PostgresColumnDesc;
+use mz_repr::{Datum, Row};
+use mz_storage_client::types::sources::SnapshotFormat;
+
+use super::{ReplicationError, ResultExt};
+
+/// Decodes the rows of a `COPY ... TO STDOUT` query in some format.
+///
+/// Upstream sends each row in its own chunk of `COPY` data. Decoders produce the text
+/// representation of each value, which is what the casts of a source table expect regardless of
+/// the format on the wire.
+pub(super) trait CopyOutDecoder: Send + Sync {
+    /// The options of the `COPY` query that make upstream send data this decoder understands.
+    fn copy_options(&self) -> &'static str;
+
+    /// Decodes the first `columns.len()` values of the row in the chunk `bytes` into `row`.
+    ///
+    /// Values beyond those described by `columns` belong to columns added upstream after the
+    /// source was created, and are ignored. Returns `false`, leaving `row` untouched, if the chunk
+    /// carries no row.
+    fn decode(
+        &self,
+        bytes: &[u8],
+        columns: &[PostgresColumnDesc],
+        row: &mut Row,
+    ) -> Result<bool, ReplicationError>;
+}
+
+/// Returns the decoder of `COPY` data in `format`.
+pub(super) fn decoder(format: SnapshotFormat) -> &'static dyn CopyOutDecoder {
+    match format {
+        SnapshotFormat::Text => &CopyTextDecoder,
+        SnapshotFormat::Binary => &CopyBinaryDecoder,
+    }
+}
+
+/// Decodes `COPY` data in the tab-delimited text format.
+pub(super) struct CopyTextDecoder;
+
+impl CopyOutDecoder for CopyTextDecoder {
+    fn copy_options(&self) -> &'static str {
+        "FORMAT TEXT, DELIMITER '\t'"
+    }
+
+    fn decode(
+        &self,
+        bytes: &[u8],
+        columns: &[PostgresColumnDesc],
+        row: &mut Row,
+    ) -> Result<bool, ReplicationError> {
+        // Delimiters within values are escaped, so every delimiter separates two values.
+        let fields = bytes.iter().filter(|byte| **byte == b'\t').count() + 1;
+        if fields < columns.len() {
+            return Err(ReplicationError::Definite(anyhow!(
+                "COPY row has {fields} fields, expected at least {}",
+                columns.len()
+            )));
+        }
+
+        let mut packer = row.packer();
+        let parser = mz_pgcopy::CopyTextFormatParser::new(bytes, "\t", "\\N");
+        let mut raw_values = parser.iter_raw_with_nulls(columns.len());
+        while let Some(raw_value) = raw_values.next() {
+            match raw_value.err_definite()? {
+                Some(value) => {
+                    let value = std::str::from_utf8(value).err_definite()?;
+                    packer.push(Datum::String(value))
+                }
+                None => packer.push(Datum::Null),
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// Decodes `COPY` data in the binary format.
+///
+/// Values of types Materialize understands are decoded from their binary representation and
+/// re-encoded as text. Values of any other type, like enums ingested with `TEXT COLUMNS`, are passed
+/// through as text, which is how the binary format encodes enums.
+pub(super) struct CopyBinaryDecoder;
+
+/// The signature that starts the header of the binary `COPY` format.
+const BINARY_SIGNATURE: &[u8] = b"PGCOPY\n\xff\r\n\0";
+
+impl CopyOutDecoder for CopyBinaryDecoder {
+    fn copy_options(&self) -> &'static str {
+        "FORMAT BINARY"
+    }
+
+    fn decode(
+        &self,
+        bytes: &[u8],
+        columns: &[PostgresColumnDesc],
+        row: &mut Row,
+    ) -> Result<bool, ReplicationError> {
+        let mut buf = bytes;
+        // The file header is sent along with the first row.
+        if let Some(rest) = buf.strip_prefix(BINARY_SIGNATURE) {
+            buf = rest;
+            let _flags = read_i32(&mut buf)?;
+            let extension_len = usize::try_from(read_i32(&mut buf)?).err_definite()?;
+            take(&mut buf, extension_len)?;
+            if buf.is_empty() {
+                return Ok(false);
+            }
+        }
+        let fields = read_i16(&mut buf)?;
+        // The file trailer is a field count of -1.
+        if fields == -1 {
+            return Ok(false);
+        }
+        let fields = usize::try_from(fields).err_definite()?;
+        if fields < columns.len() {
+            return Err(ReplicationError::Definite(anyhow!(
+                "COPY row has {fields} fields, expected at least {}",
+                columns.len()
+            )));
+        }
+
+        let mut packer = row.packer();
+        let mut text = BytesMut::new();
+        for column in columns {
+            let len = read_i32(&mut buf)?;
+            // A length of -1 denotes NULL.
+            if len == -1 {
+                packer.push(Datum::Null);
+                continue;
+            }
+            let raw = take(&mut buf, usize::try_from(len).err_definite()?)?;
+            match mz_pgrepr::Type::from_oid_and_typmod(column.type_oid, column.type_mod) {
+                Ok(ty) => {
+                    let value = mz_pgrepr::Value::decode_binary(&ty, raw)
+                        .map_err(|e| ReplicationError::Definite(anyhow!(e)))?;
+                    text.clear();
+                    value.encode_text(&mut text);
+                    packer.push(Datum::String(std::str::from_utf8(&text).err_definite()?));
+                }
+                Err(_) => packer.push(Datum::String(std::str::from_utf8(raw).err_definite()?)),
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// Removes and returns the first `len` bytes of `buf`.
+fn take<'a>(buf: &mut &'a [u8], len: usize) -> Result<&'a [u8], ReplicationError> {
+    if buf.len() < len {
+        return Err(ReplicationError::Definite(anyhow!(
+            "unexpected end of COPY data"
+        )));
+    }
+    let (head, tail) = buf.split_at(len);
+    *buf = tail;
+    Ok(head)
+}
+
+fn read_i16(buf: &mut &[u8]) -> Result<i16, ReplicationError> {
+    let bytes = take(buf, 2)?;
+    Ok(i16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_i32(buf: &mut &[u8]) -> Result<i32, ReplicationError> {
+    let bytes = take(buf, 4)?;
+    Ok(i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(name: &str, type_oid: u32) -> PostgresColumnDesc {
+        PostgresColumnDesc {
+            name: name.into(),
+            col_num: None,
+            type_oid,
+            type_mod: -1,
+            nullable: true,
+            domain_constraints: vec![],
+            default_expr: None,
+        }
+    }
+
+    fn columns() -> Vec<PostgresColumnDesc> {
+        // An int4, a text, and an enum column, whose type is unknown to Materialize.
+        vec![column("a", 23), column("b", 25), column("c", 16390)]
+    }
+
+    fn text_row(values: &[Option<&str>]) -> Row {
+        Row::pack(values.iter().map(|value| match value {
+            Some(text) => Datum::String(text),
+            None => Datum::Null,
+        }))
+    }
+
+    fn decode(
+        decoder: &dyn CopyOutDecoder,
+        bytes: &[u8],
+        columns: &[PostgresColumnDesc],
+    ) -> Result<Option<Row>, ReplicationError> {
+        let mut row = Row::default();
+        Ok(decoder.decode(bytes, columns, &mut row)?.then_some(row))
+    }
+
+    /// Returns the message of the definite error in `result`.
+    fn definite(result: Result<Option<Row>, ReplicationError>) -> String {
+        match result {
+            Err(ReplicationError::Definite(err)) => err.to_string(),
+            result => panic!("expected a definite error, got {result:?}"),
+        }
+    }
+
+    fn binary_row(fields: &[Option<&[u8]>]) -> Vec<u8> {
+        let mut buf = i16::try_from(fields.len()).unwrap().to_be_bytes().to_vec();
+        for field in fields {
+            match field {
+                Some(value) => {
+                    buf.extend(i32::try_from(value.len()).unwrap().to_be_bytes());
+                    buf.extend(*value);
+                }
+                None => buf.extend((-1i32).to_be_bytes()),
+            }
+        }
+        buf
+    }
+
+    #[test]
+    fn text_format() {
+        let columns = columns();
+        let row = decode(&CopyTextDecoder, b"1\thello\\tworld\t\\N\n", &columns).unwrap();
+        assert_eq!(
+            row,
+            Some(text_row(&[Some("1"), Some("hello\tworld"), None]))
+        );
+
+        // Columns added upstream are ignored.
+        let row = decode(&CopyTextDecoder, b"1\thello\tsad\textra\n", &columns[..2]).unwrap();
+        assert_eq!(row, Some(text_row(&[Some("1"), Some("hello")])));
+
+        assert!(matches!(
+            decode(&CopyTextDecoder, b"1\t\xff\t\\N\n", &columns),
+            Err(ReplicationError::Definite(_))
+        ));
+
+        // Escaped delimiters do not separate values.
+        assert_eq!(
+            definite(decode(&CopyTextDecoder, b"1\thello\\tworld\n", &columns)),
+            "COPY row has 2 fields, expected at least 3"
+        );
+    }
+
+    #[test]
+    fn binary_format() {
+        let columns = columns();
+        let first = binary_row(&[
+            Some(&42i32.to_be_bytes()[..]),
+            Some(&b"hello\tworld"[..]),
+            Some(&b"happy"[..]),
+        ]);
+        let mut header = BINARY_SIGNATURE.to_vec();
+        header.extend(0i32.to_be_bytes());
+        header.extend(0i32.to_be_bytes());
+
+        // The header is only present in the first chunk.
+        let row = decode(
+            &CopyBinaryDecoder,
+            &[header.clone(), first].concat(),
+            &columns,
+        )
+        .unwrap();
+        assert_eq!(
+            row,
+            Some(text_row(&[Some("42"), Some("hello\tworld"), Some("happy")]))
+        );
+        assert_eq!(decode(&CopyBinaryDecoder, &header, &columns).unwrap(), None);
+
+        let second = binary_row(&[
+            Some(&(-7i32).to_be_bytes()[..]),
+            None,
+            None,
+            Some(&b"extra"[..]),
+        ]);
+        let row = decode(&CopyBinaryDecoder, &second, &columns).unwrap();
+        assert_eq!(row, Some(text_row(&[Some("-7"), None, None])));
+
+        let trailer = (-1i16).to_be_bytes();
+        assert_eq!(
+            decode(&CopyBinaryDecoder, &trailer, &columns).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn decoder_of_format() {
+        let columns = columns();
+        let text = decoder(SnapshotFormat::Text);
+        assert_eq!(text.copy_options(), "FORMAT TEXT, DELIMITER '\t'");
+        assert_eq!(
+            decode(text, b"1\thello\t\\N\n", &columns).unwrap(),
+            Some(text_row(&[Some("1"), Some("hello"), None]))
+        );
+
+        let binary = decoder(SnapshotFormat::Binary);
+        assert_eq!(binary.copy_options(), "FORMAT BINARY");
+        let row = binary_row(&[Some(&1i32.to_be_bytes()[..]), Some(&b"hello"[..]), None]);
+        assert_eq!(
+            decode(binary, &row, &columns).unwrap(),
+            Some(text_row(&[Some("1"), Some("hello"), None]))
+        );
+    }
+
+    #[test]
+    fn binary_format_errors() {
+        let columns = columns();
+        let short = binary_row(&[Some(&42i32.to_be_bytes()[..])]);
+        assert_eq!(
+            definite(decode(&CopyBinaryDecoder, &short, &columns)),
+            "COPY row has 1 fields, expected at least 3"
+        );
+
+        let mut truncated = binary_row(&[Some(&42i32.to_be_bytes()[..]), Some(&b"hi"[..]), None]);
+        truncated.truncate(10);
+        assert_eq!(
+            definite(decode(&CopyBinaryDecoder, &truncated, &columns)),
+            "unexpected end of COPY data"
+        );
+
+        // An int4 must be 4 bytes long.
+        let invalid = binary_row(&[Some(&b"42"[..]), None, None]);
+        assert!(matches!(
+            decode(&CopyBinaryDecoder, &invalid, &columns),
+            Err(ReplicationError::Definite(_))
+        ));
+    }
+}
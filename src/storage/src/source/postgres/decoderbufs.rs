@@ -0,0 +1,579 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Replication through the `decoderbufs` logical decoding plugin used by Debezium.
+//!
+//! `decoderbufs` encodes each change as a Protocol Buffers `RowMessage`. Rather than duplicating
+//! the decoding of the replication stream, the messages are transcoded into the `pgoutput`
+//! messages they correspond to, which `consume_replication_stream` then handles like any other.
+//!
+//! `decoderbufs` identifies tables by name and sends no `Relation` messages, so changes to the
+//! schema of a table go unnoticed. It also neither streams in-progress transactions nor tells
+//! where a transaction commits when it begins.
+
+use std::collections::BTreeMap;
+use std::pin::Pin;
+use std::task::Poll;
+
+use anyhow::anyhow;
+use bytes::Bytes;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use postgres_protocol::message::backend::{LogicalReplicationMessage, ReplicationMessage};
+use prost::Message;
+use tokio_postgres::replication::ReplicationStream;
+use tokio_postgres::types::PgLsn;
+
+use mz_postgres_util::desc::PostgresTableDesc;
+use mz_postgres_util::PublicationFilter;
+
+use super::connections::UpstreamConnections;
+use super::wal_capture::WalCapture;
+use super::{
+    standby_timestamp, ReplicationError, ReplicationStreamItem, ReplicationUpstream, ResultExt,
+    SourceTable,
+};
+
+/// The microseconds between the Unix epoch and the Postgres epoch.
+const PG_EPOCH_OFFSET_MICROS: i64 = 946_684_800_000_000;
+
+// The type OIDs whose values `decoderbufs` sends in a representation other than their text one.
+const DATE_OID: i64 = 1082;
+const TIME_OID: i64 = 1083;
+const TIMESTAMP_OID: i64 = 1114;
+const TIMESTAMPTZ_OID: i64 = 1184;
+const NUMERIC_OID: i64 = 1700;
+
+/// A change to a row, or the beginning or end of a transaction. Mirrors `RowMessage` of
+/// `pg_logicaldec.proto`.
+#[derive(Clone, PartialEq, Message)]
+struct RowMessage {
+    #[prost(uint32, optional, tag = "1")]
+    transaction_id: Option<u32>,
+    /// Microseconds since the Unix epoch
+    #[prost(uint64, optional, tag = "2")]
+    commit_time: Option<u64>,
+    /// The schema qualified and quoted name of the table
+    #[prost(string, optional, tag = "3")]
+    table: Option<String>,
+    #[prost(enumeration = "Op", optional, tag = "4")]
+    op: Option<i32>,
+    #[prost(message, repeated, tag = "5")]
+    new_tuple: Vec<DatumMessage>,
+    #[prost(message, repeated, tag = "6")]
+    old_tuple: Vec<DatumMessage>,
+}
+
+/// Mirrors `Op` of `pg_logicaldec.proto`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+enum Op {
+    Unknown = -1,
+    Insert = 0,
+    Update = 1,
+    Delete = 2,
+    Begin = 3,
+    Commit = 4,
+}
+
+/// The value of a column. Mirrors `DatumMessage` of `pg_logicaldec.proto`.
+#[derive(Clone, PartialEq, Message)]
+struct DatumMessage {
+    #[prost(string, optional, tag = "1")]
+    column_name: Option<String>,
+    #[prost(int64, optional, tag = "2")]
+    column_type: Option<i64>,
+    #[prost(oneof = "Value", tags = "3, 4, 5, 6, 7, 8, 9, 10, 11")]
+    value: Option<Value>,
+}
+
+#[derive(Clone, PartialEq, prost::Oneof)]
+enum Value {
+    #[prost(int32, tag = "3")]
+    Int32(i32),
+    #[prost(int64, tag = "4")]
+    Int64(i64),
+    #[prost(float, tag = "5")]
+    Float(f32),
+    #[prost(double, tag = "6")]
+    Double(f64),
+    #[prost(bool, tag = "7")]
+    Bool(bool),
+    #[prost(string, tag = "8")]
+    String(String),
+    #[prost(bytes, tag = "9")]
+    Bytes(Vec<u8>),
+    #[prost(message, tag = "10")]
+    Point(Point),
+    /// An unchanged TOAST value, which is not sent.
+    #[prost(bool, tag = "11")]
+    Missing(bool),
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct Point {
+    #[prost(double, required, tag = "1")]
+    x: f64,
+    #[prost(double, required, tag = "2")]
+    y: f64,
+}
+
+/// A replication stream of a slot that uses the `decoderbufs` plugin, whose messages are
+/// transcoded into `pgoutput` ones.
+pub(super) struct DecoderBufsStream<'a> {
+    stream: Pin<Box<ReplicationStream>>,
+    connections: &'a UpstreamConnections,
+    publications: &'a [String],
+    /// The OIDs of the source tables, by namespace and name
+    rel_ids: BTreeMap<(String, String), u32>,
+    /// Where the raw messages are captured to before they are transcoded, if anywhere
+    wal_capture: Option<&'a WalCapture>,
+}
+
+impl<'a> DecoderBufsStream<'a> {
+    pub(super) fn new(
+        stream: ReplicationStream,
+        connections: &'a UpstreamConnections,
+        publications: &'a [String],
+        source_tables: &BTreeMap<u32, SourceTable>,
+        wal_capture: Option<&'a WalCapture>,
+    ) -> Self {
+        let rel_ids = source_tables
+            .iter()
+            .map(|(rel_id, table)| {
+                let name = (table.desc.namespace.clone(), table.desc.name.clone());
+                (name, *rel_id)
+            })
+            .collect();
+        Self {
+            stream: Box::pin(stream),
+            connections,
+            publications,
+            rel_ids,
+            wal_capture,
+        }
+    }
+}
+
+impl futures::Stream for DecoderBufsStream<'_> {
+    type Item = ReplicationStreamItem;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let message = match self.stream.as_mut().poll_next(cx) {
+            Poll::Ready(Some(Ok(message))) => message,
+            Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err.into()))),
+            Poll::Ready(None) => return Poll::Ready(None),
+            Poll::Pending => return Poll::Pending,
+        };
+        let message = match message {
+            ReplicationMessage::XLogData(body) => {
+                if let Some(capture) = self.wal_capture {
+                    capture.capture(body.wal_start(), body.wal_end(), body.data());
+                }
+                let lsn = PgLsn::from(body.wal_start());
+                body.map_data(|data| {
+                    let message = RowMessage::decode(data).err_definite()?;
+                    let payload = transcode(&message, lsn, &self.rel_ids)?;
+                    LogicalReplicationMessage::parse(&payload).err_definite()
+                })
+                .map(ReplicationMessage::XLogData)
+            }
+            ReplicationMessage::PrimaryKeepAlive(body) => {
+                Ok(ReplicationMessage::PrimaryKeepAlive(body))
+            }
+            // The enum is marked non_exhaustive, better be conservative
+            _ => Err(ReplicationError::Definite(anyhow!(
+                "Unexpected replication message"
+            ))),
+        };
+        Poll::Ready(Some(message))
+    }
+}
+
+#[async_trait::async_trait]
+impl ReplicationUpstream for DecoderBufsStream<'_> {
+    async fn send_feedback(&mut self, lsn: PgLsn) -> Result<(), ReplicationError> {
+        self.stream
+            .as_mut()
+            .standby_status_update(lsn, lsn, lsn, standby_timestamp(), 0)
+            .await
+            .err_indefinite()
+    }
+
+    async fn table_desc(
+        &mut self,
+        rel_id: u32,
+    ) -> Result<Option<PostgresTableDesc>, ReplicationError> {
+        let tables = self
+            .connections
+            .publication_info(self.publications, &PublicationFilter::ByOid(rel_id))
+            .await
+            .err_indefinite()?;
+        Ok(tables.into_iter().next())
+    }
+
+    async fn type_definition(&mut self, type_oid: u32) -> Result<Option<String>, ReplicationError> {
+        self.connections
+            .type_definition(type_oid)
+            .await
+            .err_indefinite()
+    }
+}
+
+/// Encodes `message`, which was sent at `lsn`, as the equivalent `pgoutput` message.
+///
+/// Changes to tables that are not in `rel_ids` are attributed to the invalid OID 0, so that they
+/// are ignored.
+fn transcode(
+    message: &RowMessage,
+    lsn: PgLsn,
+    rel_ids: &BTreeMap<(String, String), u32>,
+) -> Result<Bytes, ReplicationError> {
+    let xid = message.transaction_id.unwrap_or_default();
+    let timestamp = message
+        .commit_time
+        .and_then(|micros| i64::try_from(micros).ok())
+        .map_or(0, |micros| micros - PG_EPOCH_OFFSET_MICROS);
+    let rel_id = || {
+        message
+            .table
+            .as_deref()
+            .and_then(parse_qualified_name)
+            .and_then(|name| rel_ids.get(&name).copied())
+            .unwrap_or(0)
+    };
+
+    let mut buf = vec![];
+    match message.op.and_then(Op::from_i32) {
+        Some(Op::Begin) => {
+            buf.push(b'B');
+            // The LSN of the commit is not known yet. Zero never lies beyond the last commit,
+            // which keeps the transaction from being split.
+            buf.extend_from_slice(&0u64.to_be_bytes());
+            buf.extend_from_slice(&timestamp.to_be_bytes());
+            buf.extend_from_slice(&xid.to_be_bytes());
+        }
+        Some(Op::Commit) => {
+            // Commit messages are sent at the end LSN of the transaction.
+            buf.extend_from_slice(&[b'C', 0]);
+            buf.extend_from_slice(&u64::from(lsn).to_be_bytes());
+            buf.extend_from_slice(&u64::from(lsn).to_be_bytes());
+            buf.extend_from_slice(&timestamp.to_be_bytes());
+        }
+        Some(Op::Insert) => {
+            buf.push(b'I');
+            buf.extend_from_slice(&rel_id().to_be_bytes());
+            buf.push(b'N');
+            encode_tuple(&mut buf, &message.new_tuple)?;
+        }
+        Some(Op::Update) => {
+            buf.push(b'U');
+            buf.extend_from_slice(&rel_id().to_be_bytes());
+            // The old tuple is only sent for tables with REPLICA IDENTITY FULL.
+            if !message.old_tuple.is_empty() {
+                buf.push(b'O');
+                encode_tuple(&mut buf, &message.old_tuple)?;
+            }
+            buf.push(b'N');
+            encode_tuple(&mut buf, &message.new_tuple)?;
+        }
+        Some(Op::Delete) => {
+            buf.push(b'D');
+            buf.extend_from_slice(&rel_id().to_be_bytes());
+            if message.old_tuple.is_empty() {
+                buf.push(b'K');
+                encode_tuple(&mut buf, &[])?;
+            } else {
+                buf.push(b'O');
+                encode_tuple(&mut buf, &message.old_tuple)?;
+            }
+        }
+        Some(Op::Unknown) | None => {
+            return Err(ReplicationError::Definite(anyhow!(
+                "unexpected decoderbufs operation {:?}",
+                message.op
+            )))
+        }
+    }
+    Ok(Bytes::from(buf))
+}
+
+/// Encodes the values of `tuple` as a `pgoutput` tuple of text values.
+fn encode_tuple(buf: &mut Vec<u8>, tuple: &[DatumMessage]) -> Result<(), ReplicationError> {
+    buf.extend_from_slice(&i16::try_from(tuple.len()).err_definite()?.to_be_bytes());
+    for datum in tuple {
+        match &datum.value {
+            None => buf.push(b'n'),
+            Some(Value::Missing(_)) => buf.push(b'u'),
+            Some(value) => {
+                let text = value_text(value, datum.column_type.unwrap_or_default())?;
+                buf.push(b't');
+                buf.extend_from_slice(&i32::try_from(text.len()).err_definite()?.to_be_bytes());
+                buf.extend_from_slice(text.as_bytes());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns the text representation of `value`, which is of the type with OID `type_oid`.
+fn value_text(value: &Value, type_oid: i64) -> Result<String, ReplicationError> {
+    let invalid = || ReplicationError::Definite(anyhow!("invalid value of type {type_oid}"));
+    let is_timestamp = type_oid == TIMESTAMP_OID || type_oid == TIMESTAMPTZ_OID;
+    let text = match value {
+        // Infinite dates and timestamps are sent as is, beyond the range of finite ones. They are
+        // passed on like `pgoutput` sends them, rather than failing the whole source.
+        Value::Int32(i32::MAX) if type_oid == DATE_OID => "infinity".into(),
+        Value::Int32(i32::MIN) if type_oid == DATE_OID => "-infinity".into(),
+        Value::Int64(i64::MAX) if is_timestamp => "infinity".into(),
+        Value::Int64(i64::MIN) if is_timestamp => "-infinity".into(),
+        // Dates are sent as days since the Unix epoch.
+        Value::Int32(days) if type_oid == DATE_OID => NaiveDate::from_ymd_opt(1970, 1, 1)
+            .and_then(|epoch| epoch.checked_add_signed(chrono::Duration::days((*days).into())))
+            .ok_or_else(invalid)?
+            .to_string(),
+        // Timestamps are sent as microseconds since the Unix epoch.
+        Value::Int64(micros) if is_timestamp => {
+            let nanos = u32::try_from(micros.rem_euclid(1_000_000) * 1_000).err_definite()?;
+            let timestamp = NaiveDateTime::from_timestamp_opt(micros.div_euclid(1_000_000), nanos)
+                .ok_or_else(invalid)?;
+            if type_oid == TIMESTAMPTZ_OID {
+                format!("{timestamp}+00")
+            } else {
+                timestamp.to_string()
+            }
+        }
+        // Times are sent as microseconds since midnight.
+        Value::Int64(micros) if type_oid == TIME_OID => {
+            let secs = u32::try_from(micros.div_euclid(1_000_000)).err_definite()?;
+            let nanos = u32::try_from(micros.rem_euclid(1_000_000) * 1_000).err_definite()?;
+            NaiveTime::from_num_seconds_from_midnight_opt(secs, nanos)
+                .ok_or_else(invalid)?
+                .to_string()
+        }
+        // Recent versions send numbers in their text representation, which is exact. Older ones
+        // send the closest floating point number, whose digits beyond its precision are lost.
+        Value::Double(_) if type_oid == NUMERIC_OID => {
+            return Err(ReplicationError::Definite(anyhow!(
+                "decoderbufs sent a numeric value as a floating point number, which loses \
+                 precision; upgrade decoderbufs to a version that sends numeric values as text"
+            )))
+        }
+        Value::Int32(value) => value.to_string(),
+        Value::Int64(value) => value.to_string(),
+        Value::Float(value) => float_text(f64::from(*value), value.to_string()),
+        Value::Double(value) => float_text(*value, value.to_string()),
+        Value::Bool(value) => if *value { "t" } else { "f" }.to_string(),
+        Value::String(value) => value.clone(),
+        Value::Bytes(value) => format!("\\x{}", hex::encode(value)),
+        Value::Point(Point { x, y }) => format!("({x},{y})"),
+        Value::Missing(_) => unreachable!("unchanged TOAST values have no text representation"),
+    };
+    Ok(text)
+}
+
+/// Returns the text representation of a floating point number, which is `finite` unless it is
+/// infinite or not a number.
+fn float_text(value: f64, finite: String) -> String {
+    if value.is_nan() {
+        "NaN".into()
+    } else if value == f64::INFINITY {
+        "Infinity".into()
+    } else if value == f64::NEG_INFINITY {
+        "-Infinity".into()
+    } else {
+        finite
+    }
+}
+
+/// Splits a schema qualified name, whose parts are quoted where Postgres requires it, into its
+/// namespace and name.
+fn parse_qualified_name(qualified: &str) -> Option<(String, String)> {
+    let mut parts = vec![];
+    let mut chars = qualified.chars().peekable();
+    loop {
+        let mut part = String::new();
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            loop {
+                match chars.next()? {
+                    '"' if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        part.push('"');
+                    }
+                    '"' => break,
+                    c => part.push(c),
+                }
+            }
+        } else {
+            while let Some(c) = chars.next_if(|c| *c != '.') {
+                part.push(c);
+            }
+        }
+        parts.push(part);
+        match chars.next() {
+            Some('.') => continue,
+            Some(_) => return None,
+            None => break,
+        }
+    }
+    match <[String; 2]>::try_from(parts) {
+        Ok([namespace, name]) => Some((namespace, name)),
+        Err(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn datum(column_type: i64, value: Option<Value>) -> DatumMessage {
+        DatumMessage {
+            column_name: None,
+            column_type: Some(column_type),
+            value,
+        }
+    }
+
+    /// Transcodes `message`, encoded as sent by `decoderbufs`, into a `pgoutput` message.
+    fn parse(message: RowMessage, lsn: u64) -> LogicalReplicationMessage {
+        let rel_ids = BTreeMap::from([(("public".into(), "Weird.Name".into()), 16384)]);
+        let message = RowMessage::decode(message.encode_to_vec().as_slice()).unwrap();
+        let payload = transcode(&message, PgLsn::from(lsn), &rel_ids).unwrap();
+        LogicalReplicationMessage::parse(&payload).unwrap()
+    }
+
+    fn row_message(op: Op) -> RowMessage {
+        RowMessage {
+            transaction_id: Some(7),
+            commit_time: Some(946_684_800_000_042),
+            table: Some(r#"public."Weird.Name""#.into()),
+            op: Some(op.into()),
+            new_tuple: vec![],
+            old_tuple: vec![],
+        }
+    }
+
+    #[test]
+    fn transcoding() {
+        match parse(row_message(Op::Begin), 0x10) {
+            LogicalReplicationMessage::Begin(begin) => {
+                assert_eq!(begin.xid(), 7);
+                assert_eq!(begin.final_lsn(), 0);
+                assert_eq!(begin.timestamp(), 42);
+            }
+            _ => panic!("expected a begin message"),
+        }
+        match parse(row_message(Op::Commit), 0x18) {
+            LogicalReplicationMessage::Commit(commit) => {
+                assert_eq!(commit.end_lsn(), 0x18);
+                assert_eq!(commit.timestamp(), 42);
+            }
+            _ => panic!("expected a commit message"),
+        }
+
+        let mut update = row_message(Op::Update);
+        update.old_tuple = vec![
+            datum(23, Some(Value::Int32(1))),
+            datum(1114, Some(Value::Int64(1_500_000))),
+            datum(25, Some(Value::String("a".into()))),
+        ];
+        update.new_tuple = vec![
+            datum(1082, Some(Value::Int32(-1))),
+            datum(701, Some(Value::Double(f64::NEG_INFINITY))),
+            datum(25, Some(Value::Missing(true))),
+        ];
+        let texts = |tuple: &postgres_protocol::message::backend::Tuple| {
+            tuple
+                .tuple_data()
+                .iter()
+                .map(|data| match data {
+                    postgres_protocol::message::backend::TupleData::Text(text) => {
+                        Some(std::str::from_utf8(text).unwrap().to_string())
+                    }
+                    postgres_protocol::message::backend::TupleData::Null => None,
+                    _ => Some("<toast>".into()),
+                })
+                .collect::<Vec<_>>()
+        };
+        match parse(update, 0x18) {
+            LogicalReplicationMessage::Update(update) => {
+                assert_eq!(update.rel_id(), 16384);
+                assert_eq!(
+                    texts(update.old_tuple().unwrap()),
+                    vec![
+                        Some("1".into()),
+                        Some("1970-01-01 00:00:01.500".into()),
+                        Some("a".into())
+                    ]
+                );
+                assert_eq!(
+                    texts(update.new_tuple()),
+                    vec![
+                        Some("1969-12-31".into()),
+                        Some("-Infinity".into()),
+                        Some("<toast>".into())
+                    ]
+                );
+            }
+            _ => panic!("expected an update message"),
+        }
+
+        // Deletes without the old row are reported like pgoutput does for tables without
+        // REPLICA IDENTITY FULL, and changes to other tables are attributed to OID 0.
+        let mut delete = row_message(Op::Delete);
+        delete.table = Some("public.other".into());
+        match parse(delete, 0x18) {
+            LogicalReplicationMessage::Delete(delete) => {
+                assert_eq!(delete.rel_id(), 0);
+                assert!(delete.old_tuple().is_none());
+            }
+            _ => panic!("expected a delete message"),
+        }
+    }
+
+    #[test]
+    fn values() {
+        let text = |type_oid, value| value_text(&value, type_oid).unwrap();
+
+        // Numbers sent as text are passed on exactly, while floating point ones are rejected.
+        assert_eq!(
+            text(1700, Value::String("12345678901234567890.123".into())),
+            "12345678901234567890.123"
+        );
+        assert!(matches!(
+            value_text(&Value::Double(0.1), 1700),
+            Err(ReplicationError::Definite(_))
+        ));
+        assert_eq!(text(701, Value::Double(0.1)), "0.1");
+
+        // Infinite dates and timestamps don't fail the source.
+        assert_eq!(text(1082, Value::Int32(i32::MAX)), "infinity");
+        assert_eq!(text(1082, Value::Int32(i32::MIN)), "-infinity");
+        assert_eq!(text(1114, Value::Int64(i64::MAX)), "infinity");
+        assert_eq!(text(1184, Value::Int64(i64::MIN)), "-infinity");
+        assert_eq!(text(1184, Value::Int64(0)), "1970-01-01 00:00:00+00");
+        assert_eq!(text(20, Value::Int64(i64::MAX)), i64::MAX.to_string());
+    }
+
+    #[test]
+    fn qualified_names() {
+        let name = |namespace: &str, name: &str| Some((namespace.into(), name.into()));
+        assert_eq!(parse_qualified_name("public.t"), name("public", "t"));
+        assert_eq!(
+            parse_qualified_name(r#""My ""Schema""."a.b""#),
+            name(r#"My "Schema""#, "a.b")
+        );
+        assert_eq!(parse_qualified_name("t"), None);
+        assert_eq!(parse_qualified_name(r#""unterminated.t"#), None);
+        assert_eq!(parse_qualified_name("a.b.c"), None);
+    }
+}
@@ -0,0 +1,202 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Dry runs of Postgres sources, which check whether a source would work and estimate the size of
+//! its snapshot without creating it.
+
+use std::collections::BTreeMap;
+
+use mz_postgres_util::desc::PostgresTableDesc;
+use mz_postgres_util::validation::{validate_postgres_source, ValidationIssue, ValidationReport};
+use mz_postgres_util::PublicationFilter;
+use mz_storage_client::types::sources::PostgresSourceConnection;
+use tokio_postgres::Client;
+
+use super::query::rows;
+use super::{
+    check_excluded_tables, check_table_casts, column_names, determine_table_compatibility,
+    source_tables, split_allowlisted, table_estimates, text_source_tables, validate_ingests_tables,
+    SourceTable,
+};
+
+/// How many rows of each table a dry run checks the casts of.
+const SAMPLE_ROWS: usize = 1000;
+
+/// The result of [`dry_run_postgres_source`].
+#[derive(Debug, Clone, Default)]
+pub struct DryRunReport {
+    /// The upstream prerequisites of the source that are not met.
+    pub validation: ValidationReport,
+    /// Why the source would fail to ingest its tables from the publication as it is now, if it
+    /// would.
+    pub ingestion_error: Option<String>,
+    /// The tables the source ingests, by qualified name.
+    pub tables: BTreeMap<String, DryRunTable>,
+}
+
+impl DryRunReport {
+    /// Reports whether the source would fail, as far as upstream can tell without creating it.
+    pub fn has_errors(&self) -> bool {
+        self.validation.has_errors()
+            || self.ingestion_error.is_some()
+            || self.tables.values().any(|table| table.cast_error.is_some())
+    }
+
+    /// Returns the estimated size of the snapshot of all tables, in bytes.
+    pub fn estimated_bytes(&self) -> u64 {
+        self.tables
+            .values()
+            .filter_map(|table| table.estimated_bytes)
+            .sum()
+    }
+}
+
+/// What a dry run found out about a table that a source ingests.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DryRunTable {
+    /// The estimated number of rows in the table, if upstream has estimated it.
+    pub estimated_rows: Option<u64>,
+    /// The size of the table upstream, in bytes, if the table still exists.
+    pub estimated_bytes: Option<u64>,
+    /// Why the table's columns can't be cast to the types they are ingested as, if they can't,
+    /// as far as a sample of its rows tells.
+    pub cast_error: Option<String>,
+}
+
+/// Checks whether `connection` could replicate from the server that `config` connects to, like
+/// the source does when it starts, and estimates the size of the snapshot of its tables. The casts
+/// of each table are checked against the first rows of the table.
+///
+/// This only reads from upstream: it doesn't create a replication slot, nor leave any other
+/// object behind.
+///
+/// # Errors
+///
+/// Only if connecting or querying upstream fails; problems with the upstream or with the tables
+/// are reported in the returned [`DryRunReport`].
+pub async fn dry_run_postgres_source(
+    config: &mz_postgres_util::Config,
+    connection: &PostgresSourceConnection,
+) -> Result<DryRunReport, anyhow::Error> {
+    let mut source_tables = source_tables(connection);
+    let excluded_tables = split_allowlisted(
+        &mut source_tables,
+        &connection.publication_details.tables,
+        connection.table_oid_allowlist.as_ref(),
+    );
+    dry_run(
+        config,
+        &connection.publication,
+        &source_tables,
+        &excluded_tables,
+        connection.snapshot_unlogged_tables,
+    )
+    .await
+}
+
+/// Like [`dry_run_postgres_source`], but for a source that would ingest every table of
+/// `publication` with all of its columns as text, like `TEXT COLUMNS` does.
+///
+/// Values can always be cast to text, so this only tells whether the tables could be ingested at
+/// all, and how big their snapshot is.
+pub async fn dry_run_postgres_publication(
+    config: &mz_postgres_util::Config,
+    publication: &str,
+) -> Result<DryRunReport, anyhow::Error> {
+    let tables =
+        mz_postgres_util::publication_info(config, publication, &PublicationFilter::All).await?;
+    let source_tables = text_source_tables(&tables);
+    dry_run(config, publication, &source_tables, &[], false).await
+}
+
+/// Dry runs a source that ingests the `source_tables` from `publication`, and excludes the
+/// `excluded_tables` of it with its table OID allowlist.
+async fn dry_run(
+    config: &mz_postgres_util::Config,
+    publication: &str,
+    source_tables: &BTreeMap<u32, SourceTable>,
+    excluded_tables: &[PostgresTableDesc],
+    snapshot_unlogged_tables: bool,
+) -> Result<DryRunReport, anyhow::Error> {
+    let tables =
+        mz_postgres_util::publication_info(config, publication, &PublicationFilter::All).await?;
+
+    let ingested: Vec<_> = tables
+        .iter()
+        .filter(|table| source_tables.contains_key(&table.oid))
+        .cloned()
+        .collect();
+    let mut validation = validate_postgres_source(config, publication, &ingested).await?;
+    // Unlogged tables are fine if the source only snapshots them.
+    if snapshot_unlogged_tables {
+        validation
+            .issues
+            .retain(|issue| !matches!(issue, ValidationIssue::UnloggedTable { .. }));
+    }
+
+    // The same checks the source runs before it creates its slot.
+    let publications = mz_postgres_util::publication_names(publication);
+    let ingestion_error = validate_ingests_tables(&publications, &tables, source_tables)
+        .and_then(|()| check_excluded_tables(excluded_tables, &tables))
+        .and_then(|_| determine_table_compatibility(source_tables, tables))
+        .err()
+        .map(|err| err.to_string());
+
+    let client = config.connect("postgres_dry_run").await?;
+    let mut estimates = table_estimates(&client, source_tables)
+        .await
+        .map_err(|err| err.into_inner())?;
+
+    let mut tables = BTreeMap::new();
+    for (oid, info) in source_tables {
+        let estimate = estimates.remove(oid);
+        // Tables that no longer match their description, or no longer exist, already fail the
+        // ingestion, and their rows wouldn't match the casts.
+        let sample = match ingestion_error {
+            None => sample_rows(&client, info).await?,
+            Some(_) => vec![],
+        };
+        let table = DryRunTable {
+            estimated_rows: estimate.as_ref().and_then(|estimate| estimate.rows),
+            estimated_bytes: estimate.map(|estimate| estimate.bytes),
+            cast_error: check_table_casts(info, &sample)
+                .err()
+                .map(|err| err.to_string()),
+        };
+        tables.insert(format!("{}.{}", info.desc.namespace, info.desc.name), table);
+    }
+
+    Ok(DryRunReport {
+        validation,
+        ingestion_error,
+        tables,
+    })
+}
+
+/// Returns the first rows of the table `info`, text encoded like in a snapshot.
+async fn sample_rows(
+    client: &Client,
+    info: &SourceTable,
+) -> Result<Vec<Vec<Option<String>>>, anyhow::Error> {
+    let query = format!(
+        "SELECT {} FROM {:?}.{:?} LIMIT {SAMPLE_ROWS}",
+        column_names(&info.desc),
+        info.desc.namespace,
+        info.desc.name
+    );
+    let res = client.simple_query(&query).await?;
+    let sample = rows(&res)
+        .map(|row| {
+            (0..row.len())
+                .map(|i| row.get(i).map(String::from))
+                .collect()
+        })
+        .collect();
+    Ok(sample)
+}
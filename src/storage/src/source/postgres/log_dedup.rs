@@ -0,0 +1,133 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Deduplication of the warnings logged by a Postgres source.
+
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use tracing::warn;
+
+/// The default window during which identical warnings are suppressed.
+pub(super) const DEFAULT_WINDOW: Duration = Duration::from_secs(60);
+
+/// Suppresses identical warnings of the same class that repeat within a window.
+///
+/// The first occurrence of a warning is always logged. Repetitions with exactly the same content
+/// are counted instead of logged until the window closes, at which point the next repetition is
+/// logged along with the number of suppressed ones. A warning whose content differs from the last
+/// one of its class (e.g. because it carries a different LSN or SQLSTATE) is never suppressed.
+#[derive(Debug)]
+pub(super) struct LogDedup {
+    window: Duration,
+    entries: BTreeMap<&'static str, Entry>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    message: String,
+    window_start: Instant,
+    suppressed: u64,
+}
+
+impl LogDedup {
+    pub(super) fn new(window: Duration) -> Self {
+        Self {
+            window,
+            entries: BTreeMap::new(),
+        }
+    }
+
+    /// Registers an occurrence of `message` of the given `class` at time `now`, returning the
+    /// text to log or `None` if it should be suppressed.
+    pub(super) fn check(
+        &mut self,
+        class: &'static str,
+        message: String,
+        now: Instant,
+    ) -> Option<String> {
+        let entry = match self.entries.get_mut(class) {
+            Some(entry) if entry.message == message => entry,
+            _ => {
+                self.entries.insert(
+                    class,
+                    Entry {
+                        message: message.clone(),
+                        window_start: now,
+                        suppressed: 0,
+                    },
+                );
+                return Some(message);
+            }
+        };
+        if now.saturating_duration_since(entry.window_start) < self.window {
+            entry.suppressed += 1;
+            return None;
+        }
+        let suppressed = std::mem::take(&mut entry.suppressed);
+        entry.window_start = now;
+        if suppressed > 0 {
+            Some(format!(
+                "{message} (suppressed {suppressed} identical warnings in the last {:?})",
+                self.window
+            ))
+        } else {
+            Some(message)
+        }
+    }
+
+    /// Logs `message` as a warning of the given `class`, unless it is suppressed.
+    pub(super) fn warn(&mut self, class: &'static str, message: String) {
+        if let Some(message) = self.check(class, message, Instant::now()) {
+            warn!("{message}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suppresses_identical_warnings_within_window() {
+        let start = Instant::now();
+        let mut dedup = LogDedup::new(Duration::from_secs(10));
+
+        assert_eq!(
+            dedup.check("retry", "boom".into(), start),
+            Some("boom".into())
+        );
+        assert_eq!(dedup.check("retry", "boom".into(), start), None);
+        assert_eq!(
+            dedup.check("retry", "boom".into(), start + Duration::from_secs(5)),
+            None
+        );
+        assert_eq!(
+            dedup.check("retry", "boom".into(), start + Duration::from_secs(10)),
+            Some("boom (suppressed 2 identical warnings in the last 10s)".into())
+        );
+        assert_eq!(
+            dedup.check("retry", "boom".into(), start + Duration::from_secs(20)),
+            Some("boom".into())
+        );
+    }
+
+    #[test]
+    fn never_suppresses_changed_warnings() {
+        let start = Instant::now();
+        let mut dedup = LogDedup::new(Duration::from_secs(10));
+
+        assert!(dedup.check("retry", "lsn 1".into(), start).is_some());
+        assert!(dedup.check("retry", "lsn 2".into(), start).is_some());
+        assert!(dedup.check("retry", "lsn 2".into(), start).is_none());
+        assert!(dedup.check("retry", "lsn 1".into(), start).is_some());
+        // Classes are tracked independently.
+        assert!(dedup.check("relation", "lsn 1".into(), start).is_some());
+    }
+}
@@ -0,0 +1,126 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Detection of a replication loop that keeps cycling through its phases without making progress.
+
+use std::time::Duration;
+
+/// The number of consecutive iterations of the replication loop without progress after which the
+/// source is reported as thrashing.
+pub(super) const THRASHING_ITERATIONS: u64 = 10;
+
+/// A phase of an iteration of the replication loop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Phase {
+    /// Connecting to the upstream, to stream from the replication slot or to peek into it.
+    Connecting,
+    /// Streaming from the replication slot.
+    Streaming,
+    /// Peeking into the replication slot to decide whether the WAL lag can be skipped.
+    Peeking,
+}
+
+/// Keeps track of the time the replication loop spends in each [`Phase`] since it last made
+/// progress, i.e. emitted data or advanced its frontier.
+#[derive(Debug)]
+pub(super) struct LoopWatchdog {
+    threshold: u64,
+    idle_iterations: u64,
+    connecting: Duration,
+    streaming: Duration,
+    peeking: Duration,
+    /// Whether the loop was reported as thrashing since it last made progress.
+    reported: bool,
+}
+
+impl LoopWatchdog {
+    /// Returns a watchdog that reports the loop after every `threshold` consecutive iterations
+    /// without progress.
+    pub(super) fn new(threshold: u64) -> Self {
+        Self {
+            threshold,
+            idle_iterations: 0,
+            connecting: Duration::ZERO,
+            streaming: Duration::ZERO,
+            peeking: Duration::ZERO,
+            reported: false,
+        }
+    }
+
+    /// Records that the loop spent `elapsed` in `phase`.
+    pub(super) fn record(&mut self, phase: Phase, elapsed: Duration) {
+        let total = match phase {
+            Phase::Connecting => &mut self.connecting,
+            Phase::Streaming => &mut self.streaming,
+            Phase::Peeking => &mut self.peeking,
+        };
+        *total += elapsed;
+    }
+
+    /// Records that the loop made progress, returning whether it had been reported as thrashing.
+    pub(super) fn progressed(&mut self) -> bool {
+        let reported = self.reported;
+        *self = Self::new(self.threshold);
+        reported
+    }
+
+    /// Records the end of an iteration of the loop that made no progress, returning a summary of
+    /// the iterations since the last progress if the loop should be reported as thrashing.
+    pub(super) fn idle_iteration(&mut self) -> Option<String> {
+        self.idle_iterations += 1;
+        if self.idle_iterations % self.threshold != 0 {
+            return None;
+        }
+        self.reported = true;
+        Some(format!(
+            "replication made no progress in {} consecutive attempts, which spent {:?} \
+             connecting, {:?} streaming and {:?} peeking into the replication slot",
+            self.idle_iterations, self.connecting, self.streaming, self.peeking
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_thrashing() {
+        let mut watchdog = LoopWatchdog::new(2);
+        let iterate = |watchdog: &mut LoopWatchdog| {
+            watchdog.record(Phase::Connecting, Duration::from_millis(10));
+            watchdog.record(Phase::Streaming, Duration::from_secs(1));
+            watchdog.record(Phase::Connecting, Duration::from_millis(10));
+            watchdog.record(Phase::Peeking, Duration::from_millis(500));
+            watchdog.idle_iteration()
+        };
+
+        assert_eq!(iterate(&mut watchdog), None);
+        assert_eq!(
+            iterate(&mut watchdog).as_deref(),
+            Some(
+                "replication made no progress in 2 consecutive attempts, which spent 40ms \
+                 connecting, 2s streaming and 1s peeking into the replication slot"
+            )
+        );
+        // The summary is refreshed every `threshold` iterations until the loop makes progress.
+        assert_eq!(iterate(&mut watchdog), None);
+        assert!(iterate(&mut watchdog)
+            .unwrap()
+            .contains("in 4 consecutive attempts"));
+        assert!(watchdog.progressed());
+
+        assert_eq!(iterate(&mut watchdog), None);
+        assert!(!watchdog.progressed());
+        assert_eq!(iterate(&mut watchdog), None);
+        assert!(iterate(&mut watchdog)
+            .unwrap()
+            .contains("which spent 40ms connecting"));
+    }
+}
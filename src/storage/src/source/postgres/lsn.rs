@@ -0,0 +1,134 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Conversions between the inclusive LSNs that upstream talks about and the exclusive frontiers
+//! that the source reports its progress in.
+//!
+//! Upstream commits transactions at LSNs, and `START_REPLICATION` sends the transactions that
+//! committed *strictly after* the LSN it is given. Frontiers have "greater than or equal"
+//! semantics instead: a frontier of `lsn` means that everything before `lsn` has been seen. The
+//! frontier right after a commit is thus one past its LSN, and the argument to resume replication
+//! from a frontier is one before it.
+
+use mz_storage_client::types::sources::MzOffset;
+use tokio_postgres::types::PgLsn;
+
+/// The LSN that a transaction committed at, which is the last LSN whose changes are included
+/// when the transaction has been seen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(super) struct CommitLsn(PgLsn);
+
+impl CommitLsn {
+    pub(super) fn new(lsn: PgLsn) -> Self {
+        Self(lsn)
+    }
+
+    /// Returns the frontier right after the commit, which includes it.
+    ///
+    /// Upstream never commits at the largest LSN, which is saturated at rather than overflowed.
+    pub(super) fn to_frontier(self) -> LsnFrontier {
+        LsnFrontier(PgLsn::from(u64::from(self.0).saturating_add(1)))
+    }
+
+    /// Returns the LSN to pass to `START_REPLICATION` to receive the transactions that committed
+    /// after this one.
+    pub(super) fn to_start_replication_arg(self) -> PgLsn {
+        self.0
+    }
+}
+
+impl From<CommitLsn> for PgLsn {
+    fn from(lsn: CommitLsn) -> Self {
+        lsn.0
+    }
+}
+
+/// A frontier over LSNs: everything that committed before its LSN has been seen, and nothing at
+/// or after it has.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(super) struct LsnFrontier(PgLsn);
+
+impl LsnFrontier {
+    pub(super) fn new(lsn: PgLsn) -> Self {
+        Self(lsn)
+    }
+
+    /// Returns the frontier that the source timestamp `offset` denotes.
+    pub(super) fn from_offset(offset: MzOffset) -> Self {
+        Self(PgLsn::from(offset.offset))
+    }
+
+    /// Returns the source timestamp of the frontier.
+    pub(super) fn to_offset(self) -> MzOffset {
+        MzOffset::from(self.0)
+    }
+
+    /// Returns the LSN of the last commit before the frontier, or none if nothing can have
+    /// committed before it, i.e. if it is at LSN 0.
+    pub(super) fn to_commit_lsn(self) -> Option<CommitLsn> {
+        u64::from(self.0)
+            .checked_sub(1)
+            .map(|lsn| CommitLsn(PgLsn::from(lsn)))
+    }
+
+    /// Returns the LSN to pass to `START_REPLICATION` to receive the transactions that committed
+    /// at or after the frontier.
+    ///
+    /// `START_REPLICATION` can't start before LSN 0, which therefore also starts the frontier at
+    /// LSN 0. Upstream never commits at LSN 0, so no transaction is skipped.
+    pub(super) fn to_start_replication_arg(self) -> PgLsn {
+        self.to_commit_lsn()
+            .map_or(PgLsn::from(0), CommitLsn::to_start_replication_arg)
+    }
+}
+
+impl From<LsnFrontier> for PgLsn {
+    fn from(frontier: LsnFrontier) -> Self {
+        frontier.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commit_lsns() {
+        let commit = CommitLsn::new(PgLsn::from(0x16B3748));
+        assert_eq!(
+            commit.to_frontier(),
+            LsnFrontier::new(PgLsn::from(0x16B3749))
+        );
+        assert_eq!(commit.to_start_replication_arg(), PgLsn::from(0x16B3748));
+        assert_eq!(commit.to_frontier().to_commit_lsn(), Some(commit));
+
+        let last = CommitLsn::new(PgLsn::from(u64::MAX));
+        assert_eq!(last.to_frontier(), LsnFrontier::new(PgLsn::from(u64::MAX)));
+    }
+
+    #[test]
+    fn lsn_frontiers() {
+        let frontier = LsnFrontier::new(PgLsn::from(0x16B3749));
+        assert_eq!(
+            frontier.to_commit_lsn(),
+            Some(CommitLsn::new(PgLsn::from(0x16B3748)))
+        );
+        assert_eq!(frontier.to_start_replication_arg(), PgLsn::from(0x16B3748));
+        assert_eq!(frontier.to_offset(), MzOffset::from(0x16B3749));
+        assert_eq!(LsnFrontier::from_offset(frontier.to_offset()), frontier);
+
+        let start = LsnFrontier::new(PgLsn::from(0));
+        assert_eq!(start.to_commit_lsn(), None);
+        assert_eq!(start.to_start_replication_arg(), PgLsn::from(0));
+
+        let first = LsnFrontier::new(PgLsn::from(1));
+        assert_eq!(first.to_commit_lsn(), Some(CommitLsn::new(PgLsn::from(0))));
+        assert_eq!(first.to_start_replication_arg(), PgLsn::from(0));
+    }
+}
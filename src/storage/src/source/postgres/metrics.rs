@@ -7,9 +7,12 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use prometheus::core::AtomicU64;
+use prometheus::core::{AtomicF64, AtomicU64};
 
-use mz_ore::metrics::{CounterVecExt, DeleteOnDropCounter, DeleteOnDropGauge, GaugeVecExt};
+use mz_ore::metrics::{
+    CounterVecExt, DeleteOnDropCounter, DeleteOnDropGauge, DeleteOnDropHistogram, GaugeVecExt,
+    HistogramVecExt,
+};
 use mz_repr::GlobalId;
 
 use crate::source::metrics::SourceBaseMetrics;
@@ -23,6 +26,37 @@ pub(super) struct PgSourceMetrics {
     pub transactions: DeleteOnDropCounter<'static, AtomicU64, Vec<String>>,
     pub tables: DeleteOnDropGauge<'static, AtomicU64, Vec<String>>,
     pub lsn: DeleteOnDropGauge<'static, AtomicU64, Vec<String>>,
+    pub upstream_lsn: DeleteOnDropGauge<'static, AtomicU64, Vec<String>>,
+    pub fast_forward_mode: DeleteOnDropGauge<'static, AtomicU64, Vec<String>>,
+    pub row_size_limit_exceeded: DeleteOnDropCounter<'static, AtomicU64, Vec<String>>,
+    pub transaction_size_limit_exceeded: DeleteOnDropCounter<'static, AtomicU64, Vec<String>>,
+    pub transactions_split: DeleteOnDropCounter<'static, AtomicU64, Vec<String>>,
+    pub transaction_changes: DeleteOnDropHistogram<'static, Vec<String>>,
+    pub transaction_bytes: DeleteOnDropHistogram<'static, Vec<String>>,
+    pub transaction_buffer_memory_bytes: DeleteOnDropGauge<'static, AtomicU64, Vec<String>>,
+    pub commit_to_emit_latency: DeleteOnDropHistogram<'static, Vec<String>>,
+    pub channel_queued_messages: DeleteOnDropGauge<'static, AtomicU64, Vec<String>>,
+    pub channel_messages: DeleteOnDropCounter<'static, AtomicU64, Vec<String>>,
+    pub channel_send_blocked_seconds: DeleteOnDropCounter<'static, AtomicF64, Vec<String>>,
+    pub backpressure_active: DeleteOnDropGauge<'static, AtomicU64, Vec<String>>,
+    pub backpressure_seconds: DeleteOnDropCounter<'static, AtomicF64, Vec<String>>,
+    pub replication_connections: DeleteOnDropCounter<'static, AtomicU64, Vec<String>>,
+    pub replication_connect_duration: DeleteOnDropHistogram<'static, Vec<String>>,
+    pub replication_session_duration: DeleteOnDropHistogram<'static, Vec<String>>,
+    pub connection_limit_errors: DeleteOnDropCounter<'static, AtomicU64, Vec<String>>,
+    pub insufficient_privilege_errors: DeleteOnDropCounter<'static, AtomicU64, Vec<String>>,
+    pub open_connections: DeleteOnDropGauge<'static, AtomicU64, Vec<String>>,
+    pub fast_forwards: DeleteOnDropCounter<'static, AtomicU64, Vec<String>>,
+    pub wal_bytes_skipped: DeleteOnDropCounter<'static, AtomicU64, Vec<String>>,
+    pub peeks: DeleteOnDropCounter<'static, AtomicU64, Vec<String>>,
+    pub peek_duration: DeleteOnDropHistogram<'static, Vec<String>>,
+    pub replication_bytes_received: DeleteOnDropCounter<'static, AtomicU64, Vec<String>>,
+    pub snapshot_bytes_received: DeleteOnDropCounter<'static, AtomicU64, Vec<String>>,
+    pub snapshot_copy_retries: DeleteOnDropCounter<'static, AtomicU64, Vec<String>>,
+    pub snapshots_killed: DeleteOnDropCounter<'static, AtomicU64, Vec<String>>,
+    pub cast_errors: DeleteOnDropCounter<'static, AtomicU64, Vec<String>>,
+    pub last_keepalive_time: DeleteOnDropGauge<'static, AtomicU64, Vec<String>>,
+    pub last_data_time: DeleteOnDropGauge<'static, AtomicU64, Vec<String>>,
 }
 
 impl PgSourceMetrics {
@@ -52,6 +86,168 @@ impl PgSourceMetrics {
                 .tables_in_publication
                 .get_delete_on_drop_gauge(labels.to_vec()),
             lsn: pg_metrics.wal_lsn.get_delete_on_drop_gauge(labels.to_vec()),
+            upstream_lsn: pg_metrics
+                .upstream_wal_lsn
+                .get_delete_on_drop_gauge(labels.to_vec()),
+            fast_forward_mode: pg_metrics
+                .fast_forward_mode
+                .get_delete_on_drop_gauge(labels.to_vec()),
+            row_size_limit_exceeded: pg_metrics
+                .row_size_limit_exceeded
+                .get_delete_on_drop_counter(labels.to_vec()),
+            transaction_size_limit_exceeded: pg_metrics
+                .transaction_size_limit_exceeded
+                .get_delete_on_drop_counter(labels.to_vec()),
+            transactions_split: pg_metrics
+                .transactions_split
+                .get_delete_on_drop_counter(labels.to_vec()),
+            transaction_changes: pg_metrics
+                .transaction_changes
+                .get_delete_on_drop_histogram(labels.to_vec()),
+            transaction_bytes: pg_metrics
+                .transaction_bytes
+                .get_delete_on_drop_histogram(labels.to_vec()),
+            transaction_buffer_memory_bytes: pg_metrics
+                .transaction_buffer_memory_bytes
+                .get_delete_on_drop_gauge(labels.to_vec()),
+            commit_to_emit_latency: pg_metrics
+                .commit_to_emit_latency
+                .get_delete_on_drop_histogram(labels.to_vec()),
+            channel_queued_messages: pg_metrics
+                .channel_queued_messages
+                .get_delete_on_drop_gauge(labels.to_vec()),
+            channel_messages: pg_metrics
+                .channel_messages
+                .get_delete_on_drop_counter(labels.to_vec()),
+            channel_send_blocked_seconds: pg_metrics
+                .channel_send_blocked_seconds
+                .get_delete_on_drop_counter(labels.to_vec()),
+            backpressure_active: pg_metrics
+                .backpressure_active
+                .get_delete_on_drop_gauge(labels.to_vec()),
+            backpressure_seconds: pg_metrics
+                .backpressure_seconds
+                .get_delete_on_drop_counter(labels.to_vec()),
+            replication_connections: pg_metrics
+                .replication_connections
+                .get_delete_on_drop_counter(labels.to_vec()),
+            replication_connect_duration: pg_metrics
+                .replication_connect_duration
+                .get_delete_on_drop_histogram(labels.to_vec()),
+            replication_session_duration: pg_metrics
+                .replication_session_duration
+                .get_delete_on_drop_histogram(labels.to_vec()),
+            connection_limit_errors: pg_metrics
+                .connection_limit_errors
+                .get_delete_on_drop_counter(labels.to_vec()),
+            insufficient_privilege_errors: pg_metrics
+                .insufficient_privilege_errors
+                .get_delete_on_drop_counter(labels.to_vec()),
+            open_connections: pg_metrics
+                .open_connections
+                .get_delete_on_drop_gauge(labels.to_vec()),
+            fast_forwards: pg_metrics
+                .wal_fast_forwards
+                .get_delete_on_drop_counter(labels.to_vec()),
+            wal_bytes_skipped: pg_metrics
+                .wal_bytes_skipped
+                .get_delete_on_drop_counter(labels.to_vec()),
+            peeks: pg_metrics
+                .wal_peeks
+                .get_delete_on_drop_counter(labels.to_vec()),
+            peek_duration: pg_metrics
+                .wal_peek_duration
+                .get_delete_on_drop_histogram(labels.to_vec()),
+            replication_bytes_received: pg_metrics
+                .replication_bytes_received
+                .get_delete_on_drop_counter(labels.to_vec()),
+            snapshot_bytes_received: pg_metrics
+                .snapshot_bytes_received
+                .get_delete_on_drop_counter(labels.to_vec()),
+            snapshot_copy_retries: pg_metrics
+                .snapshot_copy_retries
+                .get_delete_on_drop_counter(labels.to_vec()),
+            snapshots_killed: pg_metrics
+                .snapshots_killed
+                .get_delete_on_drop_counter(labels.to_vec()),
+            cast_errors: pg_metrics
+                .cast_errors
+                .get_delete_on_drop_counter(labels.to_vec()),
+            last_keepalive_time: pg_metrics
+                .last_keepalive_time
+                .get_delete_on_drop_gauge(labels.to_vec()),
+            last_data_time: pg_metrics
+                .last_data_time
+                .get_delete_on_drop_gauge(labels.to_vec()),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use mz_ore::metrics::MetricsRegistry;
+
+    use super::*;
+
+    #[test]
+    fn replication_loop_metrics() {
+        let registry = MetricsRegistry::new();
+        let base_metrics = SourceBaseMetrics::register_with(&registry);
+        let metrics = PgSourceMetrics::new(&base_metrics, GlobalId::User(1));
+
+        metrics.replication_connections.inc();
+        metrics.replication_connections.inc();
+        metrics.fast_forwards.inc();
+        metrics.wal_bytes_skipped.inc_by(4096);
+        metrics.peeks.inc();
+        metrics.peek_duration.observe(0.5);
+        metrics.replication_connect_duration.observe(0.1);
+        metrics.replication_session_duration.observe(60.0);
+        metrics.open_connections.inc();
+        metrics.open_connections.inc();
+        metrics.open_connections.dec();
+
+        let families = registry.gather();
+        let find = |name: &str| {
+            let family = families
+                .iter()
+                .find(|family| family.get_name() == name)
+                .unwrap_or_else(|| panic!("metric {name} not reported"));
+            let dims = family.get_metric();
+            assert_eq!(dims.len(), 1);
+            assert_eq!(dims[0].get_label()[0].get_value(), "u1");
+            dims[0].clone()
+        };
+        let counter = |name: &str| find(name).get_counter().get_value();
+
+        assert_eq!(
+            counter("mz_postgres_per_source_replication_connections_total"),
+            2.0
+        );
+        assert_eq!(
+            counter("mz_postgres_per_source_wal_fast_forwards_total"),
+            1.0
+        );
+        assert_eq!(
+            counter("mz_postgres_per_source_wal_bytes_skipped_total"),
+            4096.0
+        );
+        assert_eq!(counter("mz_postgres_per_source_wal_peeks_total"), 1.0);
+        let peek_duration = find("mz_postgres_per_source_wal_peek_duration_seconds");
+        assert_eq!(peek_duration.get_histogram().get_sample_count(), 1);
+        for name in [
+            "mz_postgres_per_source_replication_connect_duration_seconds",
+            "mz_postgres_per_source_replication_session_duration_seconds",
+        ] {
+            assert_eq!(find(name).get_histogram().get_sample_count(), 1);
+        }
+        let open_connections = find("mz_postgres_per_source_open_connections");
+        assert_eq!(open_connections.get_gauge().get_value(), 1.0);
+
+        drop(metrics);
+        let families = registry.gather();
+        assert!(families
+            .iter()
+            .all(|family| !family.get_name().starts_with("mz_postgres_per_source_wal_")));
+    }
+}
@@ -0,0 +1,240 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Detection of Postgres sources whose resume LSN silently stops advancing.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tokio::sync::mpsc::Sender;
+use tokio::time::MissedTickBehavior;
+use tokio_postgres::types::PgLsn;
+
+use crate::source::types::{HealthStatus, HealthStatusUpdate};
+
+use super::metrics::PgSourceMetrics;
+use super::pause::PauseSignal;
+use super::PgSourceLimits;
+
+/// How often the resume LSN is sampled.
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// What the monitor observes about a source at a point in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct Observation {
+    /// The resume LSN of the source
+    pub(super) resume_lsn: u64,
+    /// The WAL end that upstream last reported in a keepalive
+    pub(super) wal_end: u64,
+    /// The number of bytes received from upstream so far, by both the replication stream and
+    /// snapshots, which includes keepalives
+    pub(super) upstream_bytes: u64,
+    /// Whether the replication stream is paused because downstream can't keep up
+    pub(super) backpressure: bool,
+}
+
+impl Observation {
+    pub(super) fn new(resume_lsn: &AtomicU64, metrics: &PgSourceMetrics) -> Self {
+        Self {
+            resume_lsn: resume_lsn.load(Ordering::SeqCst),
+            wal_end: metrics.upstream_lsn.get(),
+            upstream_bytes: metrics.replication_bytes_received.get()
+                + metrics.snapshot_bytes_received.get(),
+            backpressure: metrics.backpressure_active.get() != 0,
+        }
+    }
+}
+
+/// Watches the resume LSN of a source, which only advances once the data up to it has been
+/// durably ingested, and reports the source as stalled while it makes no progress for longer than
+/// the configured staleness threshold.
+///
+/// The resume LSN of a healthy source stands still while upstream has nothing for it, so the
+/// source also counts as making progress while it has caught up with the WAL end of the last
+/// keepalive, or while it keeps receiving messages from upstream, keepalives included. Upstream
+/// sends those at least every half of its `wal_sender_timeout` to an idle source. Messages don't
+/// count while backpressure pauses the stream, as it only waits for downstream then.
+///
+/// This detects sources that look healthy, without any error, while making no progress, e.g.
+/// because the source operator is not being scheduled.
+pub(super) struct PostgresReplicationMonitor {
+    last: Observation,
+    last_change: Instant,
+    stalled: bool,
+}
+
+impl PostgresReplicationMonitor {
+    pub(super) fn new(observation: Observation, now: Instant) -> Self {
+        Self {
+            last: observation,
+            last_change: now,
+            stalled: false,
+        }
+    }
+
+    /// Records the `observation` at time `now`, returning the status to report if it changed.
+    pub(super) fn sample(
+        &mut self,
+        observation: Observation,
+        now: Instant,
+        threshold: Duration,
+    ) -> Option<HealthStatus> {
+        let progressed = observation.resume_lsn != self.last.resume_lsn
+            || observation.resume_lsn >= observation.wal_end
+            || (observation.upstream_bytes != self.last.upstream_bytes
+                && !observation.backpressure);
+        self.last = observation;
+        if progressed {
+            self.last_change = now;
+            if self.stalled {
+                self.stalled = false;
+                return Some(HealthStatus::Running);
+            }
+            return None;
+        }
+        let elapsed = now.saturating_duration_since(self.last_change);
+        if self.stalled || elapsed <= threshold {
+            return None;
+        }
+        self.stalled = true;
+        Some(HealthStatus::StalledWithError {
+            error: format!(
+                "resume LSN {} has not advanced towards upstream WAL end {} in {}s",
+                PgLsn::from(observation.resume_lsn),
+                PgLsn::from(observation.wal_end),
+                elapsed.as_secs()
+            ),
+            hint: Some(
+                "Source LSN has not advanced; consider checking downstream query latency.".into(),
+            ),
+        })
+    }
+
+    /// Samples the progress of the source every [`SAMPLE_INTERVAL`] and sends the resulting
+    /// status changes through `health_tx`, until its receiver is dropped.
+    ///
+    /// The resume LSN is expected to stand still while the source is paused, so the time spent
+    /// paused does not count towards the staleness threshold.
+    pub(super) async fn run(
+        mut self,
+        resume_lsn: Arc<AtomicU64>,
+        metrics: Arc<PgSourceMetrics>,
+        limits: Arc<PgSourceLimits>,
+        pause: PauseSignal,
+        health_tx: Sender<HealthStatusUpdate>,
+    ) {
+        let mut interval = tokio::time::interval(SAMPLE_INTERVAL);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        while !health_tx.is_closed() {
+            interval.tick().await;
+            let observation = Observation::new(&resume_lsn, &metrics);
+            if pause.is_paused() {
+                // The replication loop reports the source as paused, superseding any stall.
+                self = Self::new(observation, Instant::now());
+                continue;
+            }
+            let threshold = limits.lsn_staleness_threshold();
+            if let Some(status) = self.sample(observation, Instant::now(), threshold) {
+                if health_tx.send(status.into()).await.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_stale_lsn() {
+        let threshold = Duration::from_secs(300);
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let observe = |resume_lsn, upstream_bytes| Observation {
+            resume_lsn,
+            wal_end: 64,
+            upstream_bytes,
+            backpressure: false,
+        };
+        let mut monitor = PostgresReplicationMonitor::new(observe(16, 0), start);
+
+        // Advancing LSNs are healthy.
+        assert_eq!(monitor.sample(observe(32, 0), at(10), threshold), None);
+        // An unchanged LSN behind the WAL end only stalls once the threshold has passed since
+        // the source last made progress.
+        assert_eq!(monitor.sample(observe(32, 0), at(310), threshold), None);
+        let stalled = monitor.sample(observe(32, 0), at(320), threshold).unwrap();
+        assert_eq!(
+            stalled.error(),
+            Some("resume LSN 0/20 has not advanced towards upstream WAL end 0/40 in 310s")
+        );
+        assert_eq!(
+            stalled.hint(),
+            Some("Source LSN has not advanced; consider checking downstream query latency.")
+        );
+        // The stall is reported once.
+        assert_eq!(monitor.sample(observe(32, 0), at(330), threshold), None);
+
+        // The source recovers as soon as the LSN advances.
+        assert_eq!(
+            monitor.sample(observe(48, 0), at(340), threshold),
+            Some(HealthStatus::Running)
+        );
+        assert_eq!(monitor.sample(observe(48, 0), at(350), threshold), None);
+    }
+
+    #[test]
+    fn idle_upstream_is_healthy() {
+        let threshold = Duration::from_secs(300);
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        // A source that caught up with the WAL end has nothing to advance towards.
+        let caught_up = Observation {
+            resume_lsn: 64,
+            wal_end: 64,
+            upstream_bytes: 0,
+            backpressure: false,
+        };
+        let mut monitor = PostgresReplicationMonitor::new(caught_up, start);
+        for secs in (10..3600).step_by(10) {
+            assert_eq!(monitor.sample(caught_up, at(secs), threshold), None);
+        }
+
+        // Upstream WAL that the source has nothing to ingest from, e.g. of other databases,
+        // moves the WAL end, while keepalives keep arriving.
+        let keepalive = |upstream_bytes| Observation {
+            resume_lsn: 64,
+            wal_end: 128,
+            upstream_bytes,
+            backpressure: false,
+        };
+        let mut monitor = PostgresReplicationMonitor::new(keepalive(0), start);
+        for secs in (10..3600).step_by(10) {
+            assert_eq!(
+                monitor.sample(keepalive(secs / 30), at(secs), threshold),
+                None
+            );
+        }
+
+        // Messages received while waiting for downstream are no progress.
+        let backpressure = |upstream_bytes| Observation {
+            backpressure: true,
+            ..keepalive(upstream_bytes)
+        };
+        let mut monitor = PostgresReplicationMonitor::new(backpressure(0), start);
+        assert_eq!(monitor.sample(backpressure(1), at(300), threshold), None);
+        assert!(monitor
+            .sample(backpressure(2), at(310), threshold)
+            .is_some());
+    }
+}
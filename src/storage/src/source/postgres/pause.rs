@@ -0,0 +1,122 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Pausing the replication of Postgres sources, e.g. during an upstream maintenance window.
+
+use std::collections::BTreeSet;
+use std::future;
+
+use tokio::sync::watch;
+
+use mz_repr::GlobalId;
+use mz_storage_client::types::parameters::StorageParameters;
+
+/// The Postgres sources whose replication is paused. A paused source closes its replication
+/// connection but keeps its replication slot, so that it can resume from where it left off. The
+/// pauses are shared by all Postgres sources of a worker and can be adjusted at runtime through
+/// [`StorageParameters`].
+#[derive(Debug)]
+pub struct PgSourcePauses {
+    paused_ids: watch::Sender<BTreeSet<GlobalId>>,
+}
+
+impl Default for PgSourcePauses {
+    fn default() -> Self {
+        let (paused_ids, _) = watch::channel(BTreeSet::new());
+        Self { paused_ids }
+    }
+}
+
+impl PgSourcePauses {
+    /// Pauses exactly the sources in `params`, resuming all others.
+    pub fn apply(&self, params: &StorageParameters) {
+        self.paused_ids.send_if_modified(|paused_ids| {
+            if *paused_ids == params.pg_source_paused_ids {
+                return false;
+            }
+            paused_ids.clone_from(&params.pg_source_paused_ids);
+            true
+        });
+    }
+
+    /// Returns a signal that tracks whether the source `id` is paused.
+    pub(super) fn signal(&self, id: GlobalId) -> PauseSignal {
+        PauseSignal {
+            id,
+            paused_ids: self.paused_ids.subscribe(),
+        }
+    }
+}
+
+/// Tracks whether a single source is paused.
+pub(super) struct PauseSignal {
+    id: GlobalId,
+    paused_ids: watch::Receiver<BTreeSet<GlobalId>>,
+}
+
+impl PauseSignal {
+    /// Reports whether the source is currently paused.
+    pub(super) fn is_paused(&self) -> bool {
+        self.paused_ids.borrow().contains(&self.id)
+    }
+
+    /// Waits until the source is paused, if `paused` is true, or until it is resumed otherwise.
+    ///
+    /// This method is cancel safe. It never returns if the pauses are dropped in the meantime,
+    /// which only happens when the worker shuts down.
+    pub(super) async fn wait_for(&mut self, paused: bool) {
+        loop {
+            if self.paused_ids.borrow_and_update().contains(&self.id) == paused {
+                return;
+            }
+            if self.paused_ids.changed().await.is_err() {
+                future::pending().await
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn params(paused_ids: &[GlobalId]) -> StorageParameters {
+        StorageParameters {
+            pg_source_paused_ids: paused_ids.iter().copied().collect(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn pause_and_resume() {
+        let pauses = PgSourcePauses::default();
+        let mut signal = pauses.signal(GlobalId::User(1));
+        assert!(!signal.is_paused());
+        signal.wait_for(false).await;
+
+        // Pausing other sources does not pause this one.
+        pauses.apply(&params(&[GlobalId::User(2)]));
+        assert!(!signal.is_paused());
+        let waiting = tokio::time::timeout(Duration::from_millis(10), signal.wait_for(true));
+        assert!(waiting.await.is_err());
+
+        pauses.apply(&params(&[GlobalId::User(1), GlobalId::User(2)]));
+        assert!(signal.is_paused());
+        signal.wait_for(true).await;
+
+        // Signals created while paused start out paused.
+        assert!(pauses.signal(GlobalId::User(1)).is_paused());
+
+        pauses.apply(&params(&[]));
+        assert!(!signal.is_paused());
+        signal.wait_for(false).await;
+    }
+}
@@ -0,0 +1,236 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Keeping track of the provenance of the rows a source ingested, for sources that attach
+//! provenance headers to their messages.
+
+use std::collections::BTreeMap;
+
+use tokio_postgres::types::PgLsn;
+
+use mz_persist_client::cache::PersistClientCache;
+use mz_repr::{Datum, Diff, GlobalId, Row};
+use mz_storage_client::controller::CollectionMetadata;
+
+use super::{provenance_headers, schema_change, TableRow};
+
+/// Where a row originates from upstream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Provenance {
+    /// The transaction that wrote the row, unless it was snapshotted
+    xid: Option<u32>,
+    /// The LSN the row was emitted at
+    lsn: PgLsn,
+}
+
+/// Remembers the provenance of the rows that each output holds.
+///
+/// The headers of a row are appended to it as its last column, so a retraction only cancels the row
+/// it retracts if it carries the headers of the change that inserted the row, rather than those of
+/// the change that deletes it. This keeps the provenance of every row in memory.
+pub(super) struct ProvenanceTracker {
+    /// The upstream table of each output
+    oids: BTreeMap<usize, u32>,
+    /// The provenance of each copy of the rows of each output
+    rows: BTreeMap<(usize, Row), Vec<Provenance>>,
+}
+
+impl ProvenanceTracker {
+    pub(super) fn new(oids: BTreeMap<usize, u32>) -> Self {
+        Self {
+            oids,
+            rows: BTreeMap::new(),
+        }
+    }
+
+    /// Reads the provenance of the rows that the `outputs` hold from their last column. Only the
+    /// rows that have been persisted are read, so this must happen before the source emits any.
+    pub(super) async fn load(
+        &mut self,
+        persist_clients: &PersistClientCache,
+        outputs: &BTreeMap<usize, (GlobalId, CollectionMetadata)>,
+    ) -> Result<(), anyhow::Error> {
+        for (output, (id, metadata)) in outputs {
+            if !self.oids.contains_key(output) {
+                continue;
+            }
+            let rows = schema_change::read_collection(persist_clients, *id, metadata).await?;
+            for (row, diff) in rows {
+                if let Ok(row) = row {
+                    let (row, provenance) = split_provenance(&row);
+                    self.insert(*output, row, provenance, diff);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the headers to attach to a change of `diff` copies of `row` in `output`, which
+    /// originates from the transaction `xid` and is emitted at `lsn`, unless the output has none.
+    ///
+    /// Insertions are attached the headers of their own change, and retractions those of the
+    /// change that inserted the retracted row. Rows whose insertion is unknown are retracted with
+    /// the headers of their own change.
+    pub(super) fn headers(
+        &mut self,
+        output: usize,
+        row: &Row,
+        xid: Option<u32>,
+        lsn: PgLsn,
+        diff: Diff,
+    ) -> Option<Vec<(String, Option<Vec<u8>>)>> {
+        let oid = *self.oids.get(&output)?;
+        let change = Provenance { xid, lsn };
+        let provenance = if diff > 0 {
+            self.insert(output, row.clone(), change, diff);
+            change
+        } else {
+            self.remove(output, row, diff).unwrap_or(change)
+        };
+        Some(provenance_headers(oid, provenance.xid, provenance.lsn))
+    }
+
+    fn insert(&mut self, output: usize, row: Row, provenance: Provenance, diff: Diff) {
+        let copies = self.rows.entry((output, row)).or_default();
+        for _ in 0..diff {
+            copies.push(provenance);
+        }
+    }
+
+    /// Forgets the provenance of `diff` copies of `row`, returning that of the most recently
+    /// inserted one.
+    fn remove(&mut self, output: usize, row: &Row, diff: Diff) -> Option<Provenance> {
+        let key = (output, row.clone());
+        let copies = self.rows.get_mut(&key)?;
+        let mut removed = None;
+        for _ in 0..diff.unsigned_abs() {
+            removed = copies.pop().or(removed);
+        }
+        if copies.is_empty() {
+            self.rows.remove(&key);
+        }
+        removed
+    }
+}
+
+/// Removes the headers from persisted `rows`, so that they can be retracted like any other row.
+pub(super) fn strip_provenance(rows: Vec<(TableRow, Diff)>) -> Vec<(TableRow, Diff)> {
+    rows.into_iter()
+        .map(|(row, diff)| (row.map(|row| split_provenance(&row).0), diff))
+        .collect()
+}
+
+/// Splits a persisted row into the row the source emitted and the provenance its headers record.
+fn split_provenance(row: &Row) -> (Row, Provenance) {
+    let mut datums: Vec<_> = row.iter().collect();
+    let mut provenance = Provenance {
+        xid: None,
+        lsn: PgLsn::from(0),
+    };
+    if let Some(Datum::Map(headers)) = datums.pop() {
+        for (name, value) in headers.iter() {
+            match (name, value) {
+                ("xid", Datum::String(xid)) => provenance.xid = xid.parse().ok(),
+                ("lsn", Datum::String(lsn)) => {
+                    provenance.lsn = lsn.parse().unwrap_or(provenance.lsn)
+                }
+                _ => {}
+            }
+        }
+    }
+    (Row::pack(datums), provenance)
+}
+
+#[cfg(test)]
+mod tests {
+    use mz_repr::RowPacker;
+
+    use crate::render::sources::headers_row;
+
+    use super::*;
+
+    fn persisted(row: &Row, headers: &[(String, Option<Vec<u8>>)]) -> Row {
+        let mut row = row.clone();
+        RowPacker::for_existing_row(&mut row).extend_by_row(&headers_row(headers));
+        row
+    }
+
+    #[test]
+    fn retractions_carry_inserted_headers() {
+        let a = Row::pack([Datum::Int32(1)]);
+        let b = Row::pack([Datum::Int32(2)]);
+        let mut tracker = ProvenanceTracker::new(BTreeMap::from([(1, 16384)]));
+
+        let snapshotted = tracker.headers(1, &a, None, PgLsn::from(0x10), 1);
+        let inserted = tracker.headers(1, &b, Some(7), PgLsn::from(0x20), 1);
+        assert_eq!(
+            inserted,
+            Some(provenance_headers(16384, Some(7), PgLsn::from(0x20)))
+        );
+        // Outputs without headers are left alone.
+        assert_eq!(tracker.headers(2, &a, Some(7), PgLsn::from(0x20), 1), None);
+
+        // An update retracts the old row with the headers it was inserted with.
+        assert_eq!(
+            tracker.headers(1, &a, Some(8), PgLsn::from(0x30), -1),
+            snapshotted
+        );
+        let updated = tracker.headers(1, &a, Some(8), PgLsn::from(0x30), 1);
+        assert_eq!(
+            updated,
+            Some(provenance_headers(16384, Some(8), PgLsn::from(0x30)))
+        );
+        assert_eq!(
+            tracker.headers(1, &a, Some(9), PgLsn::from(0x40), -1),
+            updated
+        );
+        assert_eq!(
+            tracker.headers(1, &b, Some(9), PgLsn::from(0x40), -1),
+            inserted
+        );
+        assert!(tracker.rows.is_empty());
+
+        // Rows that were never inserted are retracted with the headers of their own change.
+        assert_eq!(
+            tracker.headers(1, &a, Some(10), PgLsn::from(0x50), -1),
+            Some(provenance_headers(16384, Some(10), PgLsn::from(0x50)))
+        );
+    }
+
+    #[test]
+    fn persisted_provenance() {
+        let a = Row::pack([Datum::Int32(1), Datum::String("a")]);
+        let headers = provenance_headers(16384, Some(7), PgLsn::from(0x20));
+        let row = persisted(&a, &headers);
+
+        let (split, provenance) = split_provenance(&row);
+        assert_eq!(split, a);
+        assert_eq!(
+            provenance,
+            Provenance {
+                xid: Some(7),
+                lsn: PgLsn::from(0x20),
+            }
+        );
+        assert_eq!(
+            strip_provenance(vec![(Ok(row), 1)]),
+            vec![(Ok(a.clone()), 1)]
+        );
+
+        // Snapshotted rows belong to no transaction.
+        let snapshotted = persisted(&a, &provenance_headers(16384, None, PgLsn::from(0x10)));
+        let mut tracker = ProvenanceTracker::new(BTreeMap::from([(1, 16384)]));
+        let (split, provenance) = split_provenance(&snapshotted);
+        tracker.insert(1, split, provenance, 1);
+        assert_eq!(
+            tracker.headers(1, &a, Some(8), PgLsn::from(0x30), -1),
+            Some(provenance_headers(16384, None, PgLsn::from(0x10)))
+        );
+    }
+}
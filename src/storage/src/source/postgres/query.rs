@@ -0,0 +1,192 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Typed access to the results of the administrative queries issued by a Postgres source.
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use tokio_postgres::{SimpleQueryMessage, SimpleQueryRow};
+
+/// An error extracting values from the result of a simple query.
+#[derive(Debug, thiserror::Error)]
+pub(super) enum QueryResultError {
+    #[error("empty result")]
+    NoRows,
+    #[error("ambiguous result, more than one row")]
+    MultipleRows,
+    #[error("missing expected column: {0}")]
+    MissingColumn(String),
+    #[error("unexpected NULL in column {0}")]
+    Null(String),
+    #[error("invalid value {value:?} in column {column}: {reason}")]
+    InvalidValue {
+        column: String,
+        value: String,
+        reason: String,
+    },
+}
+
+/// A row of a query result whose values are accessed by column name.
+pub(super) trait ResultRow {
+    /// Returns the text representation of the value of `column`, or `None` if it is NULL.
+    fn value(&self, column: &str) -> Result<Option<&str>, QueryResultError>;
+}
+
+impl ResultRow for SimpleQueryRow {
+    fn value(&self, column: &str) -> Result<Option<&str>, QueryResultError> {
+        self.try_get(column)
+            .map_err(|_| QueryResultError::MissingColumn(column.to_string()))
+    }
+}
+
+/// Synthesized rows for tests, since `SimpleQueryRow`s can only be constructed by `tokio_postgres`
+/// itself.
+#[cfg(test)]
+impl ResultRow for std::collections::BTreeMap<&str, Option<&str>> {
+    fn value(&self, column: &str) -> Result<Option<&str>, QueryResultError> {
+        self.get(column)
+            .copied()
+            .ok_or_else(|| QueryResultError::MissingColumn(column.to_string()))
+    }
+}
+
+/// Returns the rows of the result of a simple query, skipping any other message.
+pub(super) fn rows(result: &[SimpleQueryMessage]) -> impl Iterator<Item = &SimpleQueryRow> {
+    result.iter().filter_map(|msg| match msg {
+        SimpleQueryMessage::Row(row) => Some(row),
+        _ => None,
+    })
+}
+
+/// Returns the only row in `rows`, failing if there are none or more than one.
+pub(super) fn exactly_one_row<R>(rows: impl IntoIterator<Item = R>) -> Result<R, QueryResultError> {
+    at_most_one_row(rows)?.ok_or(QueryResultError::NoRows)
+}
+
+/// Returns the only row in `rows` if there is one, failing if there are more than one.
+pub(super) fn at_most_one_row<R>(
+    rows: impl IntoIterator<Item = R>,
+) -> Result<Option<R>, QueryResultError> {
+    let mut rows = rows.into_iter();
+    match (rows.next(), rows.next()) {
+        (row, None) => Ok(row),
+        (_, Some(_)) => Err(QueryResultError::MultipleRows),
+    }
+}
+
+/// Parses the value of `column` in `row`, which must not be NULL.
+pub(super) fn parse_column<T>(row: &impl ResultRow, column: &str) -> Result<T, QueryResultError>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    parse_nullable_column(row, column)?.ok_or_else(|| QueryResultError::Null(column.to_string()))
+}
+
+/// Parses the value of `column` in `row`, returning `None` if it is NULL.
+pub(super) fn parse_nullable_column<T>(
+    row: &impl ResultRow,
+    column: &str,
+) -> Result<Option<T>, QueryResultError>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    row.value(column)?
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|err: T::Err| QueryResultError::InvalidValue {
+                    column: column.to_string(),
+                    value: value.to_string(),
+                    reason: err.to_string(),
+                })
+        })
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use tokio_postgres::types::PgLsn;
+
+    use super::*;
+
+    type TestRow = BTreeMap<&'static str, Option<&'static str>>;
+
+    fn row(values: &[(&'static str, Option<&'static str>)]) -> TestRow {
+        values.iter().copied().collect()
+    }
+
+    #[test]
+    fn row_count() {
+        assert!(matches!(
+            exactly_one_row(Vec::<TestRow>::new()),
+            Err(QueryResultError::NoRows)
+        ));
+        assert!(matches!(at_most_one_row(Vec::<TestRow>::new()), Ok(None)));
+        assert!(exactly_one_row(vec![row(&[])]).is_ok());
+        assert!(matches!(at_most_one_row(vec![row(&[])]), Ok(Some(_))));
+        assert!(matches!(
+            exactly_one_row(vec![row(&[]), row(&[])]),
+            Err(QueryResultError::MultipleRows)
+        ));
+        assert!(matches!(
+            at_most_one_row(vec![row(&[]), row(&[])]),
+            Err(QueryResultError::MultipleRows)
+        ));
+    }
+
+    #[test]
+    fn typed_columns() {
+        let row = row(&[
+            ("confirmed_flush_lsn", Some("0/16B3748")),
+            ("count", Some("3")),
+            ("active_pid", None),
+        ]);
+
+        let lsn: PgLsn = parse_column(&row, "confirmed_flush_lsn").unwrap();
+        assert_eq!(u64::from(lsn), 0x16B3748);
+        let count: u64 = parse_column(&row, "count").unwrap();
+        assert_eq!(count, 3);
+        let pid: Option<i32> = parse_nullable_column(&row, "active_pid").unwrap();
+        assert_eq!(pid, None);
+        let count: Option<u64> = parse_nullable_column(&row, "count").unwrap();
+        assert_eq!(count, Some(3));
+    }
+
+    #[test]
+    fn column_errors() {
+        let row = row(&[("count", Some("three")), ("active_pid", None)]);
+
+        let err = parse_column::<u64>(&row, "lsn").unwrap_err();
+        assert_eq!(err.to_string(), "missing expected column: lsn");
+        let err = parse_nullable_column::<u64>(&row, "lsn").unwrap_err();
+        assert_eq!(err.to_string(), "missing expected column: lsn");
+        let err = parse_column::<i32>(&row, "active_pid").unwrap_err();
+        assert_eq!(err.to_string(), "unexpected NULL in column active_pid");
+        let err = parse_column::<u64>(&row, "count").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"invalid value "three" in column count: invalid digit found in string"#
+        );
+    }
+
+    #[test]
+    fn skips_non_row_messages() {
+        let result = vec![SimpleQueryMessage::CommandComplete(0)];
+        assert_eq!(rows(&result).count(), 0);
+        assert!(matches!(
+            exactly_one_row(rows(&result)),
+            Err(QueryResultError::NoRows)
+        ));
+    }
+}
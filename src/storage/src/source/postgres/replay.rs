@@ -0,0 +1,118 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Replaying a range of a Postgres source's replication stream, for debugging.
+
+use std::sync::atomic::AtomicU64;
+use std::sync::Arc;
+
+use futures::StreamExt;
+use timely::dataflow::operators::to_stream::Event;
+use tokio_postgres::types::PgLsn;
+
+use mz_ore::metrics::MetricsRegistry;
+use mz_postgres_util::desc::PostgresTableDesc;
+use mz_postgres_util::PublicationFilter;
+use mz_repr::{Diff, GlobalId, Row};
+use mz_storage_client::types::sources::ReplicationPlugin;
+
+use super::connections::UpstreamConnections;
+use super::log_dedup::{self, LogDedup};
+use super::metrics::PgSourceMetrics;
+use super::table_stats::{self, TableStats};
+use super::{produce_replication, text_source_tables, FastForwardMode, PgSourceLimits};
+use crate::source::metrics::SourceBaseMetrics;
+
+/// Replays the changes to the tables of `publication` that were committed between `from_lsn` and
+/// `to_lsn`, inclusive, calling `on_change` with each of them. If `table` is set, only the changes
+/// to the tables of that name, in any schema, are replayed.
+///
+/// Changes are decoded exactly like a source decodes them, except that their values are left text
+/// encoded. To leave `slot` untouched, the changes are read from a temporary copy of it, which is
+/// dropped with the connection that reads it. Changes committed before the LSN that `slot` has
+/// confirmed can no longer be decoded, and changes are awaited until upstream's WAL reaches
+/// `to_lsn`. If the connection breaks, it is reopened with a fresh copy, so the changes that `slot`
+/// confirmed in the meantime are missing from the replay.
+pub async fn replay_replication(
+    config: mz_postgres_util::Config,
+    slot: &str,
+    publication: &str,
+    table: Option<&str>,
+    from_lsn: PgLsn,
+    to_lsn: PgLsn,
+    mut on_change: impl FnMut(PgLsn, &PostgresTableDesc, Row, Diff),
+) -> Result<(), anyhow::Error> {
+    let filter = match table {
+        Some(name) => PublicationFilter::ByName(name.to_string()),
+        None => PublicationFilter::All,
+    };
+    let descs = mz_postgres_util::publication_info(&config, publication, &filter).await?;
+    let source_tables = text_source_tables(&descs);
+
+    // Slot names are global, so the copy is named after this process to not collide with the
+    // copies of concurrent replays.
+    let replay_slot = format!("{slot}_replay_{}", std::process::id());
+
+    let base_metrics = SourceBaseMetrics::register_with(&MetricsRegistry::new());
+    let metrics = Arc::new(PgSourceMetrics::new(&base_metrics, GlobalId::Transient(0)));
+    let connections = UpstreamConnections::new(config, Arc::clone(&metrics), None)
+        .with_temporary_slot_copy(slot.to_string(), replay_slot.clone());
+    let limits = PgSourceLimits::default();
+    let mut log_dedup = LogDedup::new(log_dedup::DEFAULT_WINDOW);
+    let mut table_stats = TableStats::new(GlobalId::Transient(0), table_stats::DEFAULT_INTERVAL);
+    // Peeking would copy the slot anew, and only the stream is needed to replay a range.
+    let mut fast_forward_mode = FastForwardMode::Disabled;
+    let publications = mz_postgres_util::publication_names(publication);
+    let replication = produce_replication(
+        &connections,
+        &replay_slot,
+        &publications,
+        from_lsn,
+        Arc::new(AtomicU64::new(from_lsn.into())),
+        &metrics,
+        &limits,
+        &source_tables,
+        ReplicationPlugin::PgOutput,
+        false,
+        None,
+        None,
+        &mut log_dedup,
+        &mut table_stats,
+        &mut fast_forward_mode,
+        None,
+        None,
+        false,
+        None,
+    )
+    .await;
+
+    let mut replication = Box::pin(replication);
+    while let Some(event) = replication.next().await {
+        let event = event.map_err(|err| err.into_inner())?;
+        match event {
+            Event::Message(lsn, (output, row, diff, _)) => {
+                if lsn > to_lsn {
+                    break;
+                }
+                if lsn >= from_lsn {
+                    let row = row.map_err(|err| anyhow::anyhow!("{err}"))?;
+                    on_change(lsn, &descs[output], row, diff);
+                }
+            }
+            // Progress events are frontiers, so all changes up to `to_lsn` have been
+            // replayed once one goes past it.
+            Event::Progress([lsn]) => {
+                if lsn > to_lsn {
+                    break;
+                }
+            }
+        }
+    }
+    Ok(())
+}
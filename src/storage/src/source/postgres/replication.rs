@@ -0,0 +1,764 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Decoding the replication stream of a Postgres source into row and progress events.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, bail};
+use futures::StreamExt;
+use postgres_protocol::message::backend::{
+    LogicalReplicationMessage, ReplicationMessage, TupleData,
+};
+use timely::dataflow::operators::to_stream::Event;
+use tokio_postgres::types::PgLsn;
+use tracing::{warn, Span};
+
+use mz_repr::{Datum, DatumVec, Diff};
+use mz_storage_client::types::errors::SourceErrorDetails;
+
+use super::log_dedup::LogDedup;
+use super::lsn::CommitLsn;
+use super::metrics::PgSourceMetrics;
+use super::table_stats::TableStats;
+use super::truncate::{retract_truncated, TruncateRetractions};
+use super::{
+    cast_table_row, changes_size, check_projections, check_row_size, check_table_compatibility,
+    check_transaction_size, determine_table_compatibility, observe_commit_latency,
+    observe_transaction_size, pg_timestamp_to_unix_millis, replication_fail_point,
+    replication_message_len, soft_deleted_row, table_row_len, transaction_buffer_status,
+    transaction_buffers, wait_for_committed, wait_for_downstream, ColumnDefault, InternalMessage,
+    MessageSender, PgSourceLimits, ReplicationError, ReplicationState, ReplicationStreamItem,
+    ReplicationUpstream, ResultExt, SourceTable, SubtransactionAborted, TableRow,
+    TransactionChanges, TransactionInfo, UpstreamType,
+};
+
+/// Packs a Tuple received in the replication stream for the table described by `info` into a Row
+/// packer, in the order of the table's columns.
+///
+/// Tuples that upstream wrote before some of the table's columns were added lack them, which are
+/// filled in with their defaults, as determined by [`column_defaults`](super::column_defaults).
+pub(super) fn datums_from_tuple<'a>(
+    info: &'a SourceTable,
+    tuple_data: &'a [TupleData],
+    datums: &mut Vec<Datum<'a>>,
+) -> Result<(), anyhow::Error> {
+    let datum = |val: &'a TupleData| -> Result<Datum<'a>, anyhow::Error> {
+        Ok(match val {
+            TupleData::Null => Datum::Null,
+            TupleData::UnchangedToast => bail!(
+                "Missing TOASTed value from table with OID = {}. \
+                Did you forget to set REPLICA IDENTITY to FULL for your table?",
+                info.desc.oid
+            ),
+            TupleData::Text(b) => std::str::from_utf8(b)?.into(),
+        })
+    };
+    let default = |i: usize| -> Result<Datum<'a>, anyhow::Error> {
+        match info.default_datums.get(i) {
+            Some(ColumnDefault::Constant(Some(value))) => Ok(Datum::String(value)),
+            Some(ColumnDefault::Constant(None)) => Ok(Datum::Null),
+            Some(ColumnDefault::Expression(expr)) => bail!(
+                "tuple of table with OID = {} lacks column {}, whose default {} cannot be \
+                evaluated",
+                info.desc.oid,
+                info.desc.columns[i].name,
+                expr
+            ),
+            None => bail!(
+                "tuple of table with OID = {} has {} columns, expected {}",
+                info.desc.oid,
+                tuple_data.len(),
+                info.desc.columns.len()
+            ),
+        }
+    };
+    for i in 0..info.desc.columns.len() {
+        let position = match &info.projection {
+            Some(projection) => projection[i],
+            None => i,
+        };
+        match tuple_data.get(position) {
+            Some(val) => datums.push(datum(val)?),
+            None => datums.push(default(i)?),
+        }
+    }
+    Ok(())
+}
+
+/// Decodes the messages of a single replication connection into row and progress events.
+///
+/// The returned stream ends when `stream` does, or when no data has been received for
+/// `wal_lag_grace_period` while the upstream keeps reporting a WAL end beyond our position, in
+/// which case `state.observed_wal_end` can be used to attempt a fast-forward.
+pub(super) fn consume_replication_stream<'a, S>(
+    mut stream: S,
+    state: &'a mut ReplicationState,
+    committed_lsn: &'a AtomicU64,
+    metrics: &'a PgSourceMetrics,
+    limits: &'a PgSourceLimits,
+    source_tables: &'a BTreeMap<u32, SourceTable>,
+    streaming: bool,
+    max_transaction_rows: Option<usize>,
+    ping_interval: Option<Duration>,
+    wal_lag_grace_period: Duration,
+    log_dedup: &'a mut LogDedup,
+    table_stats: &'a mut TableStats,
+    status_sender: Option<&'a MessageSender>,
+    backpressure: bool,
+    truncate_retractions: Option<&'a TruncateRetractions<'a>>,
+    span: &'a Span,
+) -> impl futures::Stream<
+    Item = Result<Event<[PgLsn; 1], (usize, TableRow, Diff, TransactionInfo)>, ReplicationError>,
+> + 'a
+where
+    S: futures::Stream<Item = ReplicationStreamItem> + ReplicationUpstream + Unpin + 'a,
+{
+    use ReplicationError::*;
+    use ReplicationMessage::*;
+    async_stream::try_stream!({
+        let ReplicationState {
+            inserts,
+            deletes,
+            xid,
+            final_lsn,
+            current_tx_timestamp,
+            split,
+            last_commit_lsn,
+            observed_wal_end,
+            feedback,
+            types,
+            buffer_degraded,
+        } = state;
+
+        // Scratch space to use while evaluating casts
+        let mut datum_vec = DatumVec::new();
+
+        // Streamed transactions are re-sent from the beginning after a reconnection, so
+        // anything buffered from a previous connection must be discarded.
+        let mut streamed_txns: BTreeMap<u32, TransactionChanges> = BTreeMap::new();
+        // The id of the streamed transaction whose stream block is currently open, if any
+        let mut current_stream: Option<u32> = None;
+
+        let mut last_data_message = Instant::now();
+        // The estimated size of the changes buffered for uncommitted transactions, including
+        // the ones left over by the previous connection
+        let mut buffered_bytes = changes_size(inserts) + changes_size(deletes);
+        // The requests of the upstream of a previous connection were answered by reconnecting.
+        feedback.reply_requested = false;
+
+        loop {
+            if backpressure
+                && wait_for_downstream(
+                    &mut stream,
+                    *last_commit_lsn,
+                    committed_lsn,
+                    feedback,
+                    limits,
+                    metrics,
+                )
+                .await?
+            {
+                // The upstream was not read from while waiting, which says nothing about how far
+                // it is ahead of us.
+                last_data_message = Instant::now();
+            }
+
+            let min_feedback_interval = limits.min_feedback_interval();
+            // A requested status update that had to wait for the minimum interval is sent once
+            // it passed, rather than only once the next message arrives.
+            let reply_delay = feedback.reply_delay(min_feedback_interval);
+            let timeout = ping_interval.into_iter().chain(reply_delay).min();
+
+            let message = match timeout {
+                Some(timeout) => {
+                    match tokio::time::timeout(timeout, stream.next()).await {
+                        Ok(message) => message,
+                        Err(_) if reply_delay.is_some() => {
+                            let lsn = PgLsn::from(committed_lsn.load(Ordering::SeqCst));
+                            feedback.send(&mut stream, lsn).await?;
+                            continue;
+                        }
+                        Err(_) => {
+                            // An idle connection is indistinguishable from one that was silently
+                            // severed until we write to it, which surfaces the latter long before
+                            // TCP keepalives would. The status update repeats the last reported
+                            // LSN so that nothing new is acknowledged.
+                            let lsn = match feedback.reported_lsn {
+                                Some(lsn) => lsn,
+                                None => PgLsn::from(committed_lsn.load(Ordering::SeqCst)),
+                            };
+                            feedback.send(&mut stream, lsn).await?;
+                            continue;
+                        }
+                    }
+                }
+                None => stream.next().await,
+            };
+            metrics.total.inc();
+            if let Some(Ok(message)) = &message {
+                metrics
+                    .replication_bytes_received
+                    .inc_by(replication_message_len(message));
+                // Recorded as wall-clock readings so that they can be compared against
+                // the WAL end gauge and the current time when debugging a stalled source.
+                let now = u64::try_from(
+                    SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_millis(),
+                )
+                .unwrap_or(u64::MAX);
+                match message {
+                    XLogData(_) => metrics.last_data_time.set(now),
+                    PrimaryKeepAlive(_) => metrics.last_keepalive_time.set(now),
+                    _ => {}
+                }
+            }
+            use LogicalReplicationMessage::*;
+            match message {
+                Some(Ok(XLogData(xlog_data))) => match xlog_data.data() {
+                    Begin(begin) => {
+                        last_data_message = Instant::now();
+                        *xid = begin.xid();
+                        *final_lsn = PgLsn::from(begin.final_lsn());
+                        *current_tx_timestamp = pg_timestamp_to_unix_millis(begin.timestamp());
+                        if !inserts.is_empty() || !deletes.is_empty() {
+                            return Err(Definite(anyhow!(
+                                "got BEGIN statement after uncommitted data"
+                            )))?;
+                        }
+                    }
+                    Insert(insert) if source_tables.contains_key(&insert.rel_id()) => {
+                        last_data_message = Instant::now();
+                        let (inserts, deletes) = transaction_buffers(
+                            current_stream,
+                            &mut streamed_txns,
+                            inserts,
+                            deletes,
+                        );
+                        metrics.inserts.inc();
+                        let rel_id = insert.rel_id();
+                        table_stats.insert(rel_id);
+                        let info = source_tables.get(&rel_id).unwrap();
+                        let new_tuple = insert.tuple().tuple_data();
+                        check_row_size(rel_id, *xid, new_tuple, limits, metrics)?;
+                        let mut datums = datum_vec.borrow();
+
+                        datums_from_tuple(info, new_tuple, &mut *datums).err_definite()?;
+
+                        let row = cast_table_row(info, &datums, metrics);
+                        buffered_bytes += table_row_len(&row);
+                        inserts.push((info.output_index, row));
+                        check_transaction_size(
+                            rel_id,
+                            *xid,
+                            inserts.len() + deletes.len(),
+                            limits,
+                            metrics,
+                        )?;
+                    }
+                    Update(update) if source_tables.contains_key(&update.rel_id()) => {
+                        last_data_message = Instant::now();
+                        let (inserts, deletes) = transaction_buffers(
+                            current_stream,
+                            &mut streamed_txns,
+                            inserts,
+                            deletes,
+                        );
+                        metrics.updates.inc();
+                        let rel_id = update.rel_id();
+                        table_stats.update(rel_id);
+                        let info = source_tables.get(&rel_id).unwrap();
+                        let err = || {
+                            anyhow!(
+                                "Old row missing from replication stream for table with OID = {}.
+                                 Did you forget to set REPLICA IDENTITY to FULL for your table?",
+                                rel_id
+                            )
+                        };
+                        let old_tuple = update
+                            .old_tuple()
+                            .ok_or_else(err)
+                            .err_definite()?
+                            .tuple_data();
+                        check_row_size(rel_id, *xid, old_tuple, limits, metrics)?;
+                        check_row_size(
+                            rel_id,
+                            *xid,
+                            update.new_tuple().tuple_data(),
+                            limits,
+                            metrics,
+                        )?;
+
+                        let mut old_datums = datum_vec.borrow();
+
+                        datums_from_tuple(info, old_tuple, &mut *old_datums).err_definite()?;
+
+                        let old_row = cast_table_row(info, &old_datums, metrics);
+                        buffered_bytes += table_row_len(&old_row);
+                        deletes.push((info.output_index, old_row));
+                        drop(old_datums);
+
+                        // If the new tuple contains unchanged toast values, reuse the ones
+                        // from the old tuple
+                        let new_tuple = update
+                            .new_tuple()
+                            .tuple_data()
+                            .iter()
+                            .zip(old_tuple.iter())
+                            .map(|(new, old)| match new {
+                                TupleData::UnchangedToast => old,
+                                _ => new,
+                            });
+                        let mut new_datums = datum_vec.borrow();
+
+                        datums_from_tuple(info, new_tuple, &mut *new_datums).err_definite()?;
+
+                        let new_row = cast_table_row(info, &new_datums, metrics);
+                        buffered_bytes += table_row_len(&new_row);
+                        inserts.push((info.output_index, new_row));
+                        check_transaction_size(
+                            rel_id,
+                            *xid,
+                            inserts.len() + deletes.len(),
+                            limits,
+                            metrics,
+                        )?;
+                    }
+                    Delete(delete) if source_tables.contains_key(&delete.rel_id()) => {
+                        last_data_message = Instant::now();
+                        let (inserts, deletes) = transaction_buffers(
+                            current_stream,
+                            &mut streamed_txns,
+                            inserts,
+                            deletes,
+                        );
+                        metrics.deletes.inc();
+                        let rel_id = delete.rel_id();
+                        table_stats.delete(rel_id);
+                        let info = source_tables.get(&rel_id).unwrap();
+                        let err = || {
+                            anyhow!(
+                                "Old row missing from replication stream for table with OID = {}.
+                                 Did you forget to set REPLICA IDENTITY to FULL for your table?",
+                                rel_id
+                            )
+                        };
+                        let old_tuple = delete
+                            .old_tuple()
+                            .ok_or_else(err)
+                            .err_definite()?
+                            .tuple_data();
+                        check_row_size(rel_id, *xid, old_tuple, limits, metrics)?;
+                        let mut datums = datum_vec.borrow();
+
+                        datums_from_tuple(info, old_tuple, &mut *datums).err_definite()?;
+
+                        let row = cast_table_row(info, &datums, metrics);
+                        // The error of a row that failed to cast is retracted along with it, as
+                        // there is no row to flag as deleted.
+                        if let (true, Ok(row)) = (info.soft_delete, &row) {
+                            let deleted = soft_deleted_row(row);
+                            buffered_bytes += deleted.byte_len();
+                            inserts.push((info.output_index, Ok(deleted)));
+                        }
+                        buffered_bytes += table_row_len(&row);
+                        deletes.push((info.output_index, row));
+                        check_transaction_size(
+                            rel_id,
+                            *xid,
+                            inserts.len() + deletes.len(),
+                            limits,
+                            metrics,
+                        )?;
+                    }
+                    Commit(commit) => {
+                        last_data_message = Instant::now();
+                        // Failure scenario after a transaction was buffered, but before it was
+                        // emitted
+                        replication_fail_point("pg_replication_before_commit")?;
+                        metrics.transactions.inc();
+                        *last_commit_lsn = PgLsn::from(commit.end_lsn());
+                        *split = false;
+
+                        let bytes = observe_transaction_size(metrics, inserts, deletes);
+                        buffered_bytes = buffered_bytes.saturating_sub(bytes);
+
+                        let txn = TransactionInfo {
+                            xid: *xid,
+                            commit_time_millis: *current_tx_timestamp,
+                        };
+                        // Emitting a large transaction can take long enough for the upstream to
+                        // time out the connection, as it is not read from in the meantime.
+                        for (output, row) in deletes.drain(..) {
+                            feedback
+                                .send_if_due(&mut stream, committed_lsn, min_feedback_interval)
+                                .await?;
+                            yield Event::Message(*last_commit_lsn, (output, row, -1, txn));
+                        }
+                        for (output, row) in inserts.drain(..) {
+                            feedback
+                                .send_if_due(&mut stream, committed_lsn, min_feedback_interval)
+                                .await?;
+                            yield Event::Message(*last_commit_lsn, (output, row, 1, txn));
+                        }
+                        let frontier = CommitLsn::new(*last_commit_lsn).to_frontier();
+                        yield Event::Progress([frontier.into()]);
+                        metrics.lsn.set((*last_commit_lsn).into());
+                        observe_commit_latency(metrics, commit.timestamp());
+                        tracing::trace!(parent: span, commit_lsn = %last_commit_lsn, "commit");
+                    }
+                    StreamStart(start) if streaming => {
+                        last_data_message = Instant::now();
+                        if let Some(open_xid) = current_stream {
+                            return Err(Definite(anyhow!(
+                                "got STREAM START for transaction {} while the stream block \
+                                 of transaction {open_xid} is still open",
+                                start.xid()
+                            )))?;
+                        }
+                        // Stream blocks of different transactions can be interleaved, but
+                        // every change until the matching STREAM STOP belongs to this one.
+                        *xid = start.xid();
+                        current_stream = Some(*xid);
+                    }
+                    StreamStop(_) if streaming => {
+                        last_data_message = Instant::now();
+                        if current_stream.take().is_none() {
+                            return Err(Definite(anyhow!(
+                                "got STREAM STOP outside of a stream block"
+                            )))?;
+                        }
+                    }
+                    StreamCommit(commit) if streaming => {
+                        last_data_message = Instant::now();
+                        if let Some(open_xid) = current_stream {
+                            return Err(Definite(anyhow!(
+                                "got STREAM COMMIT for transaction {} while the stream block \
+                                 of transaction {open_xid} is still open",
+                                commit.xid()
+                            )))?;
+                        }
+                        replication_fail_point("pg_replication_before_commit")?;
+                        metrics.transactions.inc();
+                        *last_commit_lsn = PgLsn::from(commit.end_lsn());
+
+                        let (deletes, inserts) =
+                            streamed_txns.remove(&commit.xid()).unwrap_or_default();
+                        let bytes = observe_transaction_size(metrics, &inserts, &deletes);
+                        buffered_bytes = buffered_bytes.saturating_sub(bytes);
+
+                        let txn = TransactionInfo {
+                            xid: commit.xid(),
+                            commit_time_millis: pg_timestamp_to_unix_millis(commit.timestamp()),
+                        };
+                        for (output, row) in deletes {
+                            feedback
+                                .send_if_due(&mut stream, committed_lsn, min_feedback_interval)
+                                .await?;
+                            yield Event::Message(*last_commit_lsn, (output, row, -1, txn));
+                        }
+                        for (output, row) in inserts {
+                            feedback
+                                .send_if_due(&mut stream, committed_lsn, min_feedback_interval)
+                                .await?;
+                            yield Event::Message(*last_commit_lsn, (output, row, 1, txn));
+                        }
+                        let frontier = CommitLsn::new(*last_commit_lsn).to_frontier();
+                        yield Event::Progress([frontier.into()]);
+                        metrics.lsn.set((*last_commit_lsn).into());
+                        observe_commit_latency(metrics, commit.timestamp());
+                        tracing::trace!(parent: span, commit_lsn = %last_commit_lsn, "commit");
+                    }
+                    StreamAbort(abort) if streaming => {
+                        last_data_message = Instant::now();
+                        if abort.subxid() == abort.xid() {
+                            if let Some((deletes, inserts)) = streamed_txns.remove(&abort.xid()) {
+                                let bytes = changes_size(&inserts) + changes_size(&deletes);
+                                buffered_bytes = buffered_bytes.saturating_sub(bytes);
+                            }
+                        } else {
+                            // Changes are buffered per top-level transaction, so we cannot
+                            // tell which of them belong to the aborted subtransaction. The
+                            // transaction is received again in full at its commit once we
+                            // reconnect without streaming.
+                            if let Some((deletes, inserts)) = streamed_txns.remove(&abort.xid()) {
+                                let bytes = changes_size(&inserts) + changes_size(&deletes);
+                                buffered_bytes = buffered_bytes.saturating_sub(bytes);
+                            }
+                            return Err(Indefinite(anyhow!(SubtransactionAborted {
+                                xid: abort.xid(),
+                                subxid: abort.subxid(),
+                            })))?;
+                        }
+                    }
+                    Relation(relation) => {
+                        last_data_message = Instant::now();
+                        let rel_id = relation.rel_id();
+                        if let Some(info) = source_tables.get(&rel_id) {
+                            // Because the replication stream doesn't include columns'
+                            // attnums, we need to check the current local schema against
+                            // the current remote schema to ensure e.g. we haven't received
+                            // a schema update with the same terminal column name which is
+                            // actually a different column.
+                            match stream.table_desc(rel_id).await? {
+                                Some(desc) => {
+                                    // Keep this method in sync with the check in
+                                    // validate_tables. Whether the table is snapshotted anew
+                                    // instead is up to the replication loop.
+                                    if let Err(err) = check_table_compatibility(info, &desc) {
+                                        return Err(Definite(err))?;
+                                    }
+                                }
+                                None => {
+                                    log_dedup.warn(
+                                        "table_removed",
+                                        format!(
+                                            "alter table error, table removed from upstream source: name {}, oid {}, old_schema {:?}",
+                                            info.desc.name,
+                                            info.desc.oid,
+                                            info.desc.columns,
+                                        ),
+                                    );
+                                    return Err(Definite(
+                                        SourceErrorDetails::TableDropped {
+                                            table_oid: info.desc.oid,
+                                            table_name: info.desc.name.clone(),
+                                        }
+                                        .into(),
+                                    ))?;
+                                }
+                            }
+                        }
+                    }
+                    // The upstream announces a custom type before the first relation that uses
+                    // it on every connection. Casts of columns of a type that changed since it
+                    // was last announced would fail silently, so we validate the tables using
+                    // it anew. A type changed if it was renamed or altered, or if it was dropped
+                    // and created anew under the same name, and thus with another OID. Its
+                    // definition is looked up as of now rather than as of the message, which
+                    // at worst validates the tables early.
+                    Type(ty) => {
+                        last_data_message = Instant::now();
+                        let announced = UpstreamType {
+                            namespace: ty.namespace().err_definite()?.to_owned(),
+                            name: ty.name().err_definite()?.to_owned(),
+                            definition: stream.type_definition(ty.id()).await?,
+                        };
+                        let mut changed_oids: BTreeSet<u32> = types
+                            .iter()
+                            .filter(|(oid, known)| {
+                                **oid != ty.id()
+                                    && known.namespace == announced.namespace
+                                    && known.name == announced.name
+                            })
+                            .map(|(oid, _)| *oid)
+                            .collect();
+                        types.retain(|oid, _| !changed_oids.contains(oid));
+                        if matches!(
+                            types.insert(ty.id(), announced.clone()),
+                            Some(known) if known != announced
+                        ) {
+                            changed_oids.insert(ty.id());
+                        }
+                        if !changed_oids.is_empty() {
+                            info!(
+                                "upstream type {} with oid {} changed, validating the tables \
+                                 using it",
+                                announced.name,
+                                ty.id()
+                            );
+                            let affected: BTreeMap<u32, SourceTable> = source_tables
+                                .iter()
+                                .filter(|(_, info)| {
+                                    info.desc
+                                        .columns
+                                        .iter()
+                                        .any(|c| changed_oids.contains(&c.type_oid))
+                                })
+                                .map(|(oid, info)| (*oid, info.clone()))
+                                .collect();
+                            let mut descs = vec![];
+                            for oid in affected.keys() {
+                                if let Some(desc) = stream.table_desc(*oid).await? {
+                                    descs.push(desc);
+                                }
+                            }
+                            if let Err(err) = determine_table_compatibility(&affected, descs)
+                                .and_then(|projections| check_projections(&affected, &projections))
+                            {
+                                return Err(Definite(err))?;
+                            }
+                        }
+                    }
+                    Insert(_) | Update(_) | Delete(_) | Origin(_) => {
+                        last_data_message = Instant::now();
+                        metrics.ignored.inc();
+                    }
+                    Truncate(truncate) => {
+                        last_data_message = Instant::now();
+                        let truncated: Vec<&SourceTable> = truncate
+                            .rel_ids()
+                            .iter()
+                            .filter_map(|id| source_tables.get(id))
+                            .collect();
+                        // Rows of the truncated tables that were emitted as part of the
+                        // transaction can't be told apart from the persisted ones.
+                        let retractions = truncate_retractions.filter(|_| !*split);
+                        let Some(retractions) = retractions else {
+                            let tables = truncated
+                                .iter()
+                                .map(|info| {
+                                    format!("name: {} id: {}", info.desc.name, info.desc.oid)
+                                })
+                                .collect::<Vec<String>>();
+                            return Err(Definite(anyhow!(
+                                "source table(s) {} got truncated",
+                                tables.join(", ")
+                            )))?;
+                        };
+                        // The outputs are read once everything emitted so far has been
+                        // persisted, so that they yield every row we need to retract.
+                        wait_for_committed(
+                            &mut stream,
+                            *last_commit_lsn,
+                            committed_lsn,
+                            feedback,
+                            limits,
+                        )
+                        .await?;
+                        let (inserts, deletes) = transaction_buffers(
+                            current_stream,
+                            &mut streamed_txns,
+                            inserts,
+                            deletes,
+                        );
+                        for info in truncated {
+                            info!(
+                                "source table {} with oid {} got truncated, retracting its rows",
+                                info.desc.name, info.desc.oid
+                            );
+                            let rows =
+                                retractions.read(info.output_index).await.err_indefinite()?;
+                            let before = changes_size(inserts) + changes_size(deletes);
+                            retract_truncated(info, rows, inserts, deletes);
+                            let after = changes_size(inserts) + changes_size(deletes);
+                            buffered_bytes = (buffered_bytes + after).saturating_sub(before);
+                        }
+                    }
+                    // The enum is marked as non_exhaustive. Better to be conservative here in
+                    // case a new message is relevant to the semantics of our source
+                    _ => {
+                        return Err(Definite(anyhow!("unexpected logical replication message")))?;
+                    }
+                },
+                Some(Ok(PrimaryKeepAlive(keepalive))) => {
+                    feedback.reply_requested |= keepalive.reply() == 1;
+                    *observed_wal_end = PgLsn::from(keepalive.wal_end());
+                    metrics.upstream_lsn.set(keepalive.wal_end());
+
+                    // Reconnecting would replay the split transaction from its beginning.
+                    if last_data_message.elapsed() > wal_lag_grace_period && !*split {
+                        break;
+                    }
+                }
+                Some(Err(err)) => {
+                    return Err(err)?;
+                }
+                None => {
+                    break;
+                }
+                // The enum is marked non_exhaustive, better be conservative
+                _ => {
+                    return Err(Definite(anyhow!("Unexpected replication message")))?;
+                }
+            }
+            // Emit the changes buffered so far if the transaction grew too large, unless they
+            // belong to a streamed transaction that might still abort. They are emitted at
+            // the LSN of the commit record, which lies between the end of the previous
+            // transaction and the end of this one. If there is no room for it we keep
+            // buffering.
+            if let Some(max_rows) = max_transaction_rows {
+                if current_stream.is_none()
+                    && inserts.len() + deletes.len() > max_rows
+                    && *final_lsn > *last_commit_lsn
+                {
+                    if !*split {
+                        warn!(
+                            "splitting transaction {xid} of more than {max_rows} changes; \
+                             it will not be applied atomically"
+                        );
+                    }
+                    *split = true;
+                    metrics.transactions_split.inc();
+                    // Hold back one change so that the commit always emits a row at its end
+                    // LSN, which is what closes the transaction downstream.
+                    let held_insert = inserts.pop();
+                    let held_delete = match held_insert {
+                        Some(_) => None,
+                        None => deletes.pop(),
+                    };
+                    let txn = TransactionInfo {
+                        xid: *xid,
+                        commit_time_millis: *current_tx_timestamp,
+                    };
+                    let bytes = changes_size(inserts) + changes_size(deletes);
+                    buffered_bytes = buffered_bytes.saturating_sub(bytes);
+                    for (output, row) in deletes.drain(..) {
+                        feedback
+                            .send_if_due(&mut stream, committed_lsn, min_feedback_interval)
+                            .await?;
+                        yield Event::Message(*final_lsn, (output, row, -1, txn));
+                    }
+                    for (output, row) in inserts.drain(..) {
+                        feedback
+                            .send_if_due(&mut stream, committed_lsn, min_feedback_interval)
+                            .await?;
+                        yield Event::Message(*final_lsn, (output, row, 1, txn));
+                    }
+                    inserts.extend(held_insert);
+                    deletes.extend(held_delete);
+                }
+            }
+            metrics
+                .transaction_buffer_memory_bytes
+                .set(u64::cast_from(buffered_bytes));
+            let degraded = buffered_bytes > limits.transaction_buffer_degraded_bytes();
+            if degraded != *buffer_degraded {
+                *buffer_degraded = degraded;
+                if degraded {
+                    warn!(
+                        "buffering {buffered_bytes} bytes of changes of uncommitted transactions; \
+                         consider setting MAX TRANSACTION ROWS"
+                    );
+                }
+                if let Some(sender) = status_sender {
+                    let status = transaction_buffer_status(degraded.then_some(buffered_bytes));
+                    sender.send(InternalMessage::Status(status.into())).await;
+                }
+            }
+            feedback
+                .send_if_due(&mut stream, committed_lsn, min_feedback_interval)
+                .await?;
+        }
+        // Streamed transactions are discarded along with the connection.
+        let bytes = changes_size(inserts) + changes_size(deletes);
+        metrics
+            .transaction_buffer_memory_bytes
+            .set(u64::cast_from(bytes));
+        if *split {
+            return Err(Indefinite(anyhow!(
+                "replication stream ended in the middle of split transaction {xid}"
+            )))?;
+        }
+    })
+}
@@ -0,0 +1,77 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Scheduling of the periodic audits of the upstream schemas of a Postgres source's tables.
+
+use std::time::Duration;
+
+use rand::Rng;
+use tokio::time::Instant;
+
+/// The fraction of the interval by which an audit may run early or late, so that the sources
+/// ingesting from the same upstream don't all query it at once.
+const JITTER: f64 = 0.25;
+
+/// When the next audit of a source is due.
+///
+/// Relation messages only tell us about a schema change once the table is next written to, so a
+/// change to a rarely written table could otherwise go unnoticed for a long time.
+#[derive(Debug)]
+pub(super) struct SchemaAudit {
+    next: Instant,
+}
+
+impl SchemaAudit {
+    /// Schedules the first audit about `interval` from now.
+    pub(super) fn new(interval: Duration) -> Self {
+        Self {
+            next: next_audit(interval),
+        }
+    }
+
+    /// Completes once the next audit is due.
+    pub(super) async fn due(&self) {
+        tokio::time::sleep_until(self.next).await
+    }
+
+    /// Schedules the next audit about `interval` from now.
+    pub(super) fn reschedule(&mut self, interval: Duration) {
+        self.next = next_audit(interval);
+    }
+}
+
+/// Returns the time about `interval` from now, give or take the [`JITTER`].
+fn next_audit(interval: Duration) -> Instant {
+    let factor = rand::thread_rng().gen_range(1.0 - JITTER..=1.0 + JITTER);
+    let now = Instant::now();
+    // An absurdly large interval effectively disables the audit.
+    now.checked_add(interval.mul_f64(factor))
+        .unwrap_or_else(|| now + Duration::from_secs(60 * 60 * 24 * 365))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn jittered_schedule() {
+        let interval = Duration::from_secs(3600);
+        for _ in 0..100 {
+            let now = Instant::now();
+            let next = next_audit(interval);
+            assert!(next >= now + interval.mul_f64(1.0 - JITTER));
+            assert!(next <= Instant::now() + interval.mul_f64(1.0 + JITTER));
+        }
+
+        let audit = SchemaAudit::new(Duration::ZERO);
+        tokio::time::timeout(Duration::from_secs(1), audit.due())
+            .await
+            .expect("audit with a zero interval is due immediately");
+    }
+}
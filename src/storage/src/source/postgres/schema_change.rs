@@ -0,0 +1,353 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Snapshotting tables anew when their upstream schema changes incompatibly.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context};
+use differential_dataflow::consolidation::consolidate;
+use timely::progress::Antichain;
+
+use mz_expr::MirScalarExpr;
+use mz_persist_client::cache::PersistClientCache;
+use mz_persist_client::ShardId;
+use mz_persist_types::codec_impls::UnitSchema;
+use mz_postgres_util::desc::PostgresTableDesc;
+use mz_repr::{Datum, Diff, GlobalId, RelationDesc, Row, ScalarType, Timestamp};
+use mz_storage_client::controller::CollectionMetadata;
+use mz_storage_client::healthcheck::{
+    resnapshotted_tables, ResnapshottedColumn, ResnapshottedTable, MZ_SOURCE_STATUS_HISTORY_DESC,
+};
+use mz_storage_client::types::errors::DataflowError;
+use mz_storage_client::types::sources::SourceData;
+
+use super::TableRow;
+
+/// The upstream schema of a source table changed in a way that requires snapshotting the table
+/// anew, but whose columns still cast into the types the source ingests them as.
+#[derive(Debug, thiserror::Error)]
+#[error("source table {} with oid {} has been altered", .desc.name, .desc.oid)]
+pub(super) struct TableSchemaChanged {
+    /// The description to ingest the table with from now on
+    pub(super) desc: PostgresTableDesc,
+    /// The positions of the columns of `desc` among the table's columns upstream, unless they are
+    /// the leading ones in order
+    pub(super) projection: Option<Vec<usize>>,
+}
+
+/// Returns the description to ingest a table with once its upstream schema has changed from `desc`
+/// to `new_desc`, given that its columns are cast by `casts`, or none if the columns of `new_desc`
+/// no longer cast into the existing types.
+///
+/// Columns must keep their names, and must not become nullable. They are found by their names, so
+/// columns that were dropped and added again are found even though they moved to the end of the
+/// table, and the returned description lists them in the order they are ingested in. Their types
+/// may change, as long as they map onto the same Materialize type or the column is ingested as
+/// text. Like when determining compatibility, columns added upstream are ignored.
+pub(super) fn resnapshot_desc(
+    desc: &PostgresTableDesc,
+    casts: &[MirScalarExpr],
+    new_desc: &PostgresTableDesc,
+) -> Option<PostgresTableDesc> {
+    if desc.oid != new_desc.oid
+        || desc.namespace != new_desc.namespace
+        || desc.name != new_desc.name
+        || desc.columns.len() > new_desc.columns.len()
+        || desc.keys.difference(&new_desc.keys).next().is_some()
+    {
+        return None;
+    }
+
+    // The casts are evaluated on rows whose values are all text encoded.
+    let text_types: Vec<_> = desc
+        .columns
+        .iter()
+        .map(|column| ScalarType::String.nullable(column.nullable))
+        .collect();
+    let mut columns = Vec::with_capacity(desc.columns.len());
+    for (i, column) in desc.columns.iter().enumerate() {
+        let new_column = new_desc.columns.iter().find(|c| c.name == column.name)?;
+        if !column.nullable && new_column.nullable {
+            return None;
+        }
+        columns.push(new_column.clone());
+        let Some(cast) = casts.get(i) else {
+            continue;
+        };
+        let target = cast.typ(&text_types).scalar_type;
+        if target == ScalarType::String {
+            continue;
+        }
+        let ty =
+            mz_pgrepr::Type::from_oid_and_typmod(new_column.type_oid, new_column.type_mod).ok()?;
+        if ScalarType::try_from(&ty).ok()? != target {
+            return None;
+        }
+    }
+
+    Some(PostgresTableDesc {
+        columns,
+        ..new_desc.clone()
+    })
+}
+
+/// Records that a table was snapshotted anew with the columns of `desc` at `lsn`.
+pub(super) fn resnapshotted_table(desc: &PostgresTableDesc, lsn: u64) -> ResnapshottedTable {
+    ResnapshottedTable {
+        lsn,
+        columns: desc
+            .columns
+            .iter()
+            .map(|column| ResnapshottedColumn {
+                name: column.name.clone(),
+                type_oid: column.type_oid,
+                type_mod: column.type_mod,
+            })
+            .collect(),
+    }
+}
+
+/// Reads the rows of the collection `id`, described by `metadata`, as of the latest time they
+/// are readable at, along with the errors of the rows that failed to cast. Other errors in the
+/// collection are skipped, as they cannot be retracted.
+pub(super) async fn read_collection(
+    persist_clients: &PersistClientCache,
+    id: GlobalId,
+    metadata: &CollectionMetadata,
+) -> Result<Vec<(TableRow, Diff)>, anyhow::Error> {
+    let contents = read_shard(
+        persist_clients,
+        metadata,
+        metadata.data_shard,
+        &metadata.relation_desc,
+        &format!("pg resnapshot {id}"),
+    )
+    .await?;
+    let mut rows = vec![];
+    for (data, diff) in contents {
+        match data {
+            Ok(row) => rows.push((Ok(row), diff)),
+            // The source only attributes the errors of rows that failed to cast to the outputs
+            // of tables.
+            Err(DataflowError::SourceError(err)) => rows.push((Err(err.error), diff)),
+            Err(_) => {}
+        }
+    }
+    consolidate(&mut rows);
+    Ok(rows)
+}
+
+/// Reads the tables that the source `id` snapshotted anew from its latest status that carries
+/// details, which its health operator wrote to the status shard of its `metadata`.
+pub(super) async fn read_resnapshotted_tables(
+    persist_clients: &PersistClientCache,
+    id: GlobalId,
+    metadata: &CollectionMetadata,
+) -> Result<BTreeMap<u32, ResnapshottedTable>, anyhow::Error> {
+    let Some(status_shard) = metadata.status_shard else {
+        return Ok(BTreeMap::new());
+    };
+    let contents = read_shard(
+        persist_clients,
+        metadata,
+        status_shard,
+        &MZ_SOURCE_STATUS_HISTORY_DESC,
+        &format!("pg resnapshotted tables {id}"),
+    )
+    .await?;
+    let id = id.to_string();
+    // The columns are the time the status occurred at, the source, and later its details.
+    let latest = contents
+        .iter()
+        .filter_map(|(data, diff)| match data {
+            Ok(row) if *diff > 0 => Some(row.iter().collect::<Vec<_>>()),
+            _ => None,
+        })
+        .filter(|datums| datums[1] == Datum::String(&id) && datums[4] != Datum::Null)
+        .max_by_key(|datums| datums[0]);
+    Ok(latest.map_or_else(BTreeMap::new, |datums| resnapshotted_tables(datums[4])))
+}
+
+/// Reads the `shard` of the persist location of `metadata`, whose rows are described by `desc`,
+/// as of the latest time it is readable at.
+async fn read_shard(
+    persist_clients: &PersistClientCache,
+    metadata: &CollectionMetadata,
+    shard: ShardId,
+    desc: &RelationDesc,
+    purpose: &str,
+) -> Result<Vec<(Result<Row, DataflowError>, Diff)>, anyhow::Error> {
+    let client = persist_clients
+        .open(metadata.persist_location.clone())
+        .await
+        .context("error opening persist client")?;
+    let (mut write_handle, mut read_handle) = client
+        .open::<SourceData, (), Timestamp, Diff>(
+            shard,
+            purpose,
+            Arc::new(desc.clone()),
+            Arc::new(UnitSchema),
+        )
+        .await
+        .context("error opening persist shard")?;
+    let upper = write_handle.fetch_recent_upper().await.clone();
+    write_handle.expire().await;
+
+    let Some(as_of) = upper.as_option().and_then(|upper| upper.step_back()) else {
+        // Nothing has been written to the shard yet.
+        read_handle.expire().await;
+        return Ok(vec![]);
+    };
+    let contents = read_handle
+        .snapshot_and_fetch(Antichain::from_elem(as_of))
+        .await
+        .map_err(|since| anyhow!("cannot read {shard} as of {as_of}, its since is {since:?}"));
+    read_handle.expire().await;
+
+    let mut rows = vec![];
+    for ((data, _), _, diff) in contents? {
+        let SourceData(data) = data.map_err(|err| anyhow!(err))?;
+        rows.push((data, diff));
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use mz_postgres_util::desc::{Compatibility, PostgresColumnDesc};
+    use mz_repr::ColumnType;
+
+    use super::*;
+
+    fn column(name: &str, col_num: u16, type_oid: u32, nullable: bool) -> PostgresColumnDesc {
+        PostgresColumnDesc {
+            name: name.into(),
+            col_num: Some(col_num),
+            type_oid,
+            type_mod: -1,
+            nullable,
+            domain_constraints: vec![],
+            default_expr: None,
+        }
+    }
+
+    fn desc(columns: Vec<PostgresColumnDesc>) -> PostgresTableDesc {
+        PostgresTableDesc {
+            oid: 16384,
+            namespace: "public".into(),
+            name: "t".into(),
+            columns,
+            keys: BTreeSet::new(),
+            replica_identity: Default::default(),
+            identity_column_names: vec![],
+        }
+    }
+
+    /// Casts the text encoded columns of a table into `types`.
+    fn casts(types: &[ScalarType]) -> Vec<MirScalarExpr> {
+        types
+            .iter()
+            .enumerate()
+            .map(|(i, ty)| match ty {
+                ScalarType::String => MirScalarExpr::column(i),
+                ScalarType::Int32 => MirScalarExpr::column(i).call_unary(
+                    mz_expr::UnaryFunc::CastStringToInt32(mz_expr::func::CastStringToInt32),
+                ),
+                ty => panic!("unexpected type {ty:?}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn resnapshot_desc_requires_matching_types() {
+        // An int4 column and a column ingested as text.
+        let old = desc(vec![column("a", 1, 23, true), column("b", 2, 16390, false)]);
+        let casts = casts(&[ScalarType::Int32, ScalarType::String]);
+        assert_eq!(
+            casts[0].typ(&[ScalarType::String.nullable(true)]),
+            ColumnType {
+                scalar_type: ScalarType::Int32,
+                nullable: true,
+            }
+        );
+
+        // Dropping and re-adding a column changes its attnum, and any type can be ingested as
+        // text. Columns added upstream are ignored.
+        let new = desc(vec![
+            column("a", 1, 23, false),
+            column("b", 3, 1043, false),
+            column("c", 4, 23, true),
+        ]);
+        let resnapshot = resnapshot_desc(&old, &casts, &new).unwrap();
+        assert_eq!(resnapshot.columns, new.columns[..2]);
+        assert!(resnapshot.determine_compatibility(&new).is_ok());
+
+        // An int8 column no longer casts into an int4.
+        let new = desc(vec![column("a", 1, 20, true), column("b", 2, 25, false)]);
+        assert_eq!(resnapshot_desc(&old, &casts, &new), None);
+
+        // Nor does a renamed or a nullable column fit.
+        let new = desc(vec![column("z", 1, 23, true), column("b", 2, 25, false)]);
+        assert_eq!(resnapshot_desc(&old, &casts, &new), None);
+        let new = desc(vec![column("a", 1, 23, true), column("b", 2, 25, true)]);
+        assert_eq!(resnapshot_desc(&old, &casts, &new), None);
+
+        // Dropping a column is never compatible.
+        let new = desc(vec![column("a", 1, 23, true)]);
+        assert_eq!(resnapshot_desc(&old, &casts, &new), None);
+    }
+
+    #[test]
+    fn widened_columns() {
+        // A numeric(10,2) column, whose typmod packs its precision and scale.
+        let mut old = desc(vec![column("a", 1, 1700, true), column("b", 2, 25, false)]);
+        old.columns[0].type_mod = ((10 << 16) | 2) + 4;
+
+        // Raising its precision to numeric(12,2) keeps the table compatible, as it is still
+        // ingested as a numeric with a scale of 2.
+        let mut new = old.clone();
+        new.columns[0].type_mod = ((12 << 16) | 2) + 4;
+        assert_eq!(
+            old.determine_compatibility(&new).unwrap(),
+            Compatibility::WidenColumn {
+                columns: vec!["a".into()]
+            }
+        );
+        // Lowering it again does not, nor does changing its scale.
+        assert!(new.determine_compatibility(&old).is_err());
+        let mut new = old.clone();
+        new.columns[0].type_mod = ((12 << 16) | 4) + 4;
+        assert!(old.determine_compatibility(&new).is_err());
+
+        // Widening an int4 to an int8 or a varchar(10) to a varchar(20) changes the type they are
+        // ingested as, whose values would fail to decode.
+        let old = desc(vec![column("a", 1, 23, true)]);
+        let mut new = old.clone();
+        new.columns[0].type_oid = 20;
+        assert!(old.determine_compatibility(&new).is_err());
+        let mut old = desc(vec![column("a", 1, 1043, true)]);
+        old.columns[0].type_mod = 14;
+        let mut new = old.clone();
+        new.columns[0].type_mod = 24;
+        assert!(old.determine_compatibility(&new).is_err());
+        new.columns[0].type_mod = -1;
+        assert!(old.determine_compatibility(&new).is_err());
+
+        // Nor are the elements of arrays widened.
+        let mut old = desc(vec![column("a", 1, 1231, true)]);
+        old.columns[0].type_mod = ((10 << 16) | 2) + 4;
+        let mut new = old.clone();
+        new.columns[0].type_mod = ((12 << 16) | 2) + 4;
+        assert!(old.determine_compatibility(&new).is_err());
+    }
+}
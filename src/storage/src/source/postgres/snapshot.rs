@@ -0,0 +1,612 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Snapshotting the tables of a Postgres source by copying them out of a snapshot transaction.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use anyhow::anyhow;
+use futures::StreamExt;
+use tokio_postgres::Client;
+use tracing::{info, warn, Instrument, Span};
+
+use mz_repr::{Datum, DatumVec, GlobalId, Row};
+
+use super::connections::SnapshotClient;
+use super::copy::CopyOutDecoder;
+use super::metrics::PgSourceMetrics;
+use super::query::{self, at_most_one_row, exactly_one_row, parse_column, rows};
+use super::{
+    begin_standby_snapshot, cast_table_row, check_snapshot_privileges, check_snapshot_row_size,
+    column_names, copy_relations, describe_error, describe_estimate, end_snapshot_transaction,
+    existing_slot_lsn, is_read_timeout, is_retryable_error, is_snapshot_killed_error, order_tables,
+    pause_wal_replay, projected_column_list, resume_key, resume_leftover_replay_pause, row_key,
+    snapshot_columns, snapshot_span, table_estimates, validate_snapshot_columns, MissingPrivilege,
+    Operation, OperationResultExt, PgSourceLimits, PostgresTaskInfo, ReplicationError, ResultExt,
+    SnapshotKilled, SnapshotProgress, SourceTable, TableRow, SNAPSHOT_COPY_RETRIES,
+};
+
+/// Copies `tables` out of a new snapshot transaction, creating the main replication slot if it
+/// does not exist yet, and records the tables that were copied completely in `progress`.
+pub(super) async fn snapshot_tables(
+    task_info: &mut PostgresTaskInfo,
+    tables: &BTreeMap<u32, SourceTable>,
+    progress: &mut SnapshotProgress,
+) -> Result<(), ReplicationError> {
+    let client = task_info.connections.replication().await.err_indefinite()?;
+
+    // Snapshotting a large table can take hours, which must not be cut short by a
+    // `statement_timeout` configured upstream.
+    mz_postgres_util::set_statement_timeout(&client, task_info.snapshot_statement_timeout)
+        .await
+        .err_indefinite()?;
+
+    // Technically there is TOCTOU problem here but it makes the code easier and if we end
+    // up attempting to create a slot and it already exists we will simply retry
+    // Also, we must check if the slot exists before we start a transaction because creating a
+    // slot must be the first statement in a transaction
+    let res = client
+        .simple_query(&format!(
+            r#"SELECT confirmed_flush_lsn, restart_lsn, plugin FROM pg_replication_slots
+               WHERE slot_name = '{}'"#,
+            task_info.slot
+        ))
+        .await
+        .err_during(Operation::SlotCreation)?;
+    let slot_row = at_most_one_row(rows(&res)).err_indefinite()?;
+    let slot_lsn = existing_slot_lsn(&task_info.slot, task_info.replication_plugin, slot_row)?;
+    // Creating a slot on a standby waits for its replay to progress, which a pause that an
+    // earlier attempt left behind would hold up forever.
+    if task_info.synchronize_replicas {
+        resume_leftover_replay_pause(&client).await?;
+    }
+    // Before the slot is created, so that a role that can't copy the tables leaves nothing behind.
+    check_snapshot_privileges(&client, tables).await?;
+    client
+        .simple_query("BEGIN READ ONLY ISOLATION LEVEL REPEATABLE READ;")
+        .await?;
+
+    let (slot_lsn, snapshot_lsn, temp_slot) = match slot_lsn {
+        Some(slot_lsn) => {
+            // The main slot already exists which means we can't use it for the snapshot. So
+            // we'll create a temporary replication slot in order to both set the transaction's
+            // snapshot to be a consistent point and also to find out the LSN that the snapshot
+            // is going to run at.
+            //
+            // When this happens we'll most likely be snapshotting at a later LSN than the slot
+            // which we will take care below by rewinding.
+            let temp_slot = uuid::Uuid::new_v4().to_string().replace('-', "");
+            let res = client
+                .simple_query(&format!(
+                    r#"CREATE_REPLICATION_SLOT {:?} TEMPORARY LOGICAL "pgoutput" USE_SNAPSHOT"#,
+                    temp_slot
+                ))
+                .await
+                .err_during(Operation::SlotCreation)?;
+            let snapshot_lsn = exactly_one_row(rows(&res))
+                .and_then(|row| parse_column(row, "consistent_point"))
+                .err_indefinite()?;
+            (slot_lsn, snapshot_lsn, Some(temp_slot))
+        }
+        None => {
+            let res = client
+                .simple_query(&format!(
+                    r#"CREATE_REPLICATION_SLOT {:?} LOGICAL "{}" USE_SNAPSHOT"#,
+                    task_info.slot,
+                    task_info.replication_plugin.name(),
+                ))
+                .await
+                .err_during(Operation::SlotCreation)?;
+            let slot_lsn = exactly_one_row(rows(&res))
+                .and_then(|row| parse_column(row, "consistent_point"))
+                .err_indefinite()?;
+            (slot_lsn, slot_lsn, None)
+        }
+    };
+    // A retry must snapshot against the slot that the previous attempts created, as the rows
+    // they sent are rewound from it.
+    match progress.slot_lsn {
+        Some(expected) if expected != slot_lsn => {
+            return Err(ReplicationError::Irrecoverable(anyhow!(
+                "replication slot {} moved from {expected} to {slot_lsn} while snapshotting",
+                task_info.slot
+            )));
+        }
+        _ => progress.slot_lsn = Some(slot_lsn),
+    }
+
+    // With a standby to copy from, the transaction above only determined the LSN that the
+    // snapshot must include. It ends before the copy starts, so that it does not hold back the
+    // cleanup of the server we replicate from while the standby takes the load.
+    let (client, snapshot_lsn, temp_slot) = match &task_info.snapshot_config {
+        Some(config) => {
+            let (standby_client, standby_lsn) = begin_standby_snapshot(
+                &task_info.connections,
+                config,
+                task_info.snapshot_statement_timeout,
+                snapshot_lsn,
+            )
+            .await?;
+            end_snapshot_transaction(&client, temp_slot).await?;
+            drop(client);
+            info!(
+                "source {} snapshotting from its snapshot standby at {standby_lsn}",
+                task_info.source_id
+            );
+            (SnapshotClient::Standby(standby_client), standby_lsn, None)
+        }
+        None => (SnapshotClient::Replication(client), snapshot_lsn, temp_slot),
+    };
+    assert!(slot_lsn <= snapshot_lsn);
+
+    // A DDL statement that committed after the tables were validated above, but before the
+    // slot's snapshot was taken, would go unnoticed, and could misalign the copied columns
+    // with the ones we ingest. The transaction sees the catalog as of its snapshot, so we
+    // validate the tables again against what it sees.
+    let columns = snapshot_columns(&client, tables).await?;
+    validate_snapshot_columns(tables, columns).err_definite()?;
+
+    let estimates = table_estimates(&client, tables).await?;
+    let ordered = order_tables(task_info.snapshot_order, tables, &estimates);
+    info!(
+        "snapshotting {} tables of source {} in {:?} order: {}",
+        ordered.len(),
+        task_info.source_id,
+        task_info.snapshot_order,
+        ordered
+            .iter()
+            .map(|(oid, table)| describe_estimate(&table.desc.name, estimates.get(oid)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+
+    // Replay is only paused once the slot exists, as creating a slot on a standby waits for
+    // replay to progress.
+    let replay_pause = match task_info.synchronize_replicas {
+        true => pause_wal_replay(&task_info.connections).await?,
+        false => None,
+    };
+
+    // Lets tests lock a table after the slot was created, but before it is copied.
+    fail::fail_point!("pg_snapshot_before_copy");
+
+    // A table whose copy lost its connection in an earlier attempt resumes after the last row it
+    // sent.
+    let resume_after: Vec<_> = ordered
+        .iter()
+        .map(|(oid, _)| {
+            let splits = progress.partial.get(oid)?;
+            splits.last().map(|(key, _)| key.clone())
+        })
+        .collect();
+    let keys: Vec<_> = ordered
+        .iter()
+        .map(|(_, table)| resume_key(&table.desc))
+        .collect();
+
+    let tables_copied = AtomicUsize::new(0);
+    // The index of the table that the last sent row belongs to, and its key if the table's copy
+    // can resume after it
+    let mut last_sent: Option<(usize, Option<Row>)> = None;
+    let snapshot = async {
+        let mut stream = Box::pin(
+            produce_snapshot(
+                &client,
+                task_info.source_id,
+                &task_info.metrics,
+                &task_info.limits,
+                ordered
+                    .iter()
+                    .zip(&resume_after)
+                    .map(|((_, table), after)| (*table, after.as_ref()))
+                    .collect(),
+                task_info.snapshot_decoder,
+                task_info.snapshot_cursor_fetch_size,
+                task_info.copy_read_timeout,
+                &tables_copied,
+            )
+            .enumerate(),
+        );
+
+        while let Some((i, event)) = stream.as_mut().next().await {
+            if i > 0 {
+                // Failure scenario after we have produced at least one row, but before a
+                // successful `COMMIT`
+                fail::fail_point!("pg_snapshot_failure", |_| {
+                    Err(ReplicationError::Indefinite(anyhow::anyhow!(
+                        "recoverable errors should crash the process"
+                    )))
+                });
+            }
+            let (output, row) = match event {
+                Ok(event) => event,
+                Err(err @ ReplicationError::Definite(_)) => return Err(err),
+                Err(ReplicationError::Indefinite(err) | ReplicationError::Irrecoverable(err)) => {
+                    return Err(ReplicationError::Irrecoverable(err))
+                }
+            };
+            let table = tables_copied.load(Ordering::SeqCst);
+            let key = match (&row, &keys[table]) {
+                (Ok(row), Some(key)) => row_key(row, key),
+                _ => None,
+            };
+            last_sent = Some((table, key));
+            progress.sent = true;
+            task_info
+                .row_sender
+                .send_row(output, row, slot_lsn, 1, None)
+                .await;
+        }
+        Ok(())
+    }
+    .await;
+    // Replay must resume even if the snapshot failed, as it would stay paused otherwise.
+    if let Some(replay_pause) = replay_pause {
+        replay_pause.resume().await;
+    }
+    let copied = tables_copied.load(Ordering::SeqCst);
+    for (oid, _) in ordered.iter().take(copied) {
+        progress.done.insert(*oid, snapshot_lsn);
+    }
+    // The rows of a partially copied table were sent at an open timestamp and cannot be taken
+    // back, so the snapshot cannot resume from the start of that table, only from after the last
+    // of them, if the table is copied in the order of its key.
+    if snapshot.is_err() {
+        if let Some((table, key)) = last_sent.filter(|(table, _)| *table == copied) {
+            match key {
+                Some(key) => {
+                    let (oid, _) = ordered[table];
+                    progress
+                        .partial
+                        .entry(oid)
+                        .or_default()
+                        .push((key, snapshot_lsn));
+                }
+                None => progress.unresumable = true,
+            }
+        }
+    }
+    snapshot?;
+
+    end_snapshot_transaction(&client, temp_slot).await?;
+
+    // Drop the client, to ensure that the future `produce_replication` don't conflict with
+    // the above processing. The snapshot stream was already dropped along with the future
+    // that consumed it.
+    //
+    // Its possible we can avoid dropping the `client` value here, but we do it out of an
+    // abundance of caution, as rust-postgres has had curious bugs around this.
+    drop(client);
+
+    Ok(())
+}
+
+/// Produces the initial snapshot of the data by performing a `COPY` query for each of the provided
+/// `source_tables`, whose data is decoded by `decoder`. The copy fails if the next chunk of rows
+/// doesn't arrive within `copy_read_timeout`. A table that comes with a key is only copied from
+/// after the row with that key, see [`resume_key`].
+///
+/// The return stream of data returned is not annotated with LSN numbers. It is up to the caller to
+/// provide a client that is in a known LSN context in which the snapshot will be taken. For
+/// example by calling this method while being in a transaction for which the LSN is known.
+pub(super) fn produce_snapshot<'a>(
+    client: &'a Client,
+    source_id: GlobalId,
+    metrics: &'a PgSourceMetrics,
+    limits: &'a PgSourceLimits,
+    source_tables: Vec<(&'a SourceTable, Option<&'a Row>)>,
+    decoder: &'a dyn CopyOutDecoder,
+    cursor_fetch_size: Option<usize>,
+    copy_read_timeout: Duration,
+    tables_copied: &'a AtomicUsize,
+) -> impl futures::Stream<Item = Result<(usize, TableRow), ReplicationError>> + 'a {
+    async_stream::try_stream! {
+        // Copying a table anew must return its rows in the same order, so that the rows sent
+        // before a retried copy failed can be skipped. Synchronized scans would start the copy
+        // wherever another scan of the table is at.
+        client
+            .simple_query("SET LOCAL synchronize_seqscans TO off")
+            .await?;
+
+        let tables_total = source_tables.len();
+        for (tables_done, (info, after)) in source_tables.into_iter().enumerate() {
+            let span = snapshot_span(source_id, &info.desc);
+            // The number of rows of this table that have been sent
+            let mut rows: u64 = 0;
+            let mut retries = 0;
+
+            loop {
+                // Rolling back to the savepoint keeps the snapshot of the transaction, so that
+                // the table is copied anew at the same point.
+                client
+                    .simple_query("SAVEPOINT mz_snapshot_table")
+                    .instrument(span.clone())
+                    .await?;
+                let mut copy = Box::pin(copy_table(
+                    client,
+                    metrics,
+                    limits,
+                    info,
+                    after,
+                    decoder,
+                    cursor_fetch_size,
+                    copy_read_timeout,
+                    span.clone(),
+                ));
+                let mut copied: u64 = 0;
+                let mut failure = None;
+                while let Some(row) = copy.next().await {
+                    match row {
+                        Ok(row) => {
+                            copied += 1;
+                            if copied > rows {
+                                rows += 1;
+                                yield (info.output_index, row);
+                            }
+                        }
+                        // A read that timed out leaves the copy running upstream, which the
+                        // rollback to the savepoint waits for before the table is copied anew.
+                        Err(ReplicationError::Indefinite(err))
+                            if retries < SNAPSHOT_COPY_RETRIES
+                                && (is_retryable_error(&err) || is_read_timeout(&err)) =>
+                        {
+                            failure = Some(describe_error(&err));
+                            break;
+                        }
+                        Err(ReplicationError::Indefinite(err))
+                            if is_snapshot_killed_error(&err) =>
+                        {
+                            let killed = SnapshotKilled {
+                                table: info.desc.name.clone(),
+                                rows,
+                                tables_done,
+                                tables_total,
+                            };
+                            return Err(ReplicationError::Indefinite(err.context(killed)))?;
+                        }
+                        // The privileges were checked before the snapshot started, but can have
+                        // been revoked since.
+                        Err(ReplicationError::Indefinite(err)) => {
+                            let err = match MissingPrivilege::from_error(&err, Some(&info.desc)) {
+                                Some(missing) => err.context(missing),
+                                None => err,
+                            };
+                            return Err(ReplicationError::Indefinite(err))?;
+                        }
+                        Err(err) => return Err(err)?,
+                    }
+                }
+                drop(copy);
+
+                match failure {
+                    None => {
+                        client
+                            .simple_query("RELEASE SAVEPOINT mz_snapshot_table")
+                            .instrument(span.clone())
+                            .await?;
+                        break;
+                    }
+                    Some(error) => {
+                        retries += 1;
+                        metrics.snapshot_copy_retries.inc();
+                        warn!(
+                            parent: &span,
+                            "retrying snapshot of table after {copied} rows ({retries} of \
+                             {SNAPSHOT_COPY_RETRIES}): {error}"
+                        );
+                        client
+                            .simple_query("ROLLBACK TO SAVEPOINT mz_snapshot_table")
+                            .instrument(span.clone())
+                            .await?;
+                    }
+                }
+            }
+
+            info!(parent: &span, rows, "finished snapshotting table");
+            metrics.tables.inc();
+            tables_copied.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Copies the rows of the table described by `info` out of the snapshot transaction of `client`.
+///
+/// A table with a [`resume_key`] is copied in the order of its key, starting after the row with
+/// the key `after`, if any, so that a copy that lost its connection can resume in a later
+/// snapshot. Other tables are copied in the order they are stored in.
+fn copy_table<'a>(
+    client: &'a Client,
+    metrics: &'a PgSourceMetrics,
+    limits: &'a PgSourceLimits,
+    info: &'a SourceTable,
+    after: Option<&'a Row>,
+    decoder: &'a dyn CopyOutDecoder,
+    cursor_fetch_size: Option<usize>,
+    copy_read_timeout: Duration,
+    span: Span,
+) -> impl futures::Stream<Item = Result<TableRow, ReplicationError>> + 'a {
+    async_stream::try_stream! {
+        // Scratch space to use while evaluating casts
+        let mut datum_vec = DatumVec::new();
+        // Scratch space to use while decoding the text rows. Packing a new row clears it but
+        // keeps its allocation, so it is shared across all rows of the table.
+        let mut text_row = Row::default();
+        // Columns that moved upstream are selected by name, so that the values arrive in the
+        // order of the table's columns.
+        let columns = projected_column_list(info);
+        // A table copied in the order of its key is selected from, which also selects the rows of
+        // the partitions of a partitioned table by the table's columns.
+        let ordered =
+            resume_key(&info.desc).map(|key| ordered_snapshot_query(info, &key, after));
+
+        match cursor_fetch_size {
+            None => {
+                let statements = match &ordered {
+                    Some(query) => {
+                        vec![format!("COPY ({query}) TO STDOUT ({})", decoder.copy_options())]
+                    }
+                    None => {
+                        // `COPY` does not copy the rows of a partitioned table, which live in its
+                        // leaf partitions, so we copy those one by one. A partition may order its
+                        // columns differently from its parent, e.g. if it was attached rather
+                        // than created as a partition, so its columns are always selected by the
+                        // parent's names.
+                        let relations = copy_relations(client, &info.desc)
+                            .instrument(span.clone())
+                            .await?;
+                        relations
+                            .into_iter()
+                            .map(|(namespace, name)| {
+                                let is_partition =
+                                    namespace != info.desc.namespace || name != info.desc.name;
+                                let columns = match &columns {
+                                    Some(columns) => Some(columns.clone()),
+                                    None if is_partition => Some(column_names(&info.desc)),
+                                    None => None,
+                                };
+                                format!(
+                                    "COPY {:?}.{:?}{} TO STDOUT ({})",
+                                    namespace,
+                                    name,
+                                    columns.as_deref().map_or(String::new(), |c| format!(" ({c})")),
+                                    decoder.copy_options()
+                                )
+                            })
+                            .collect()
+                    }
+                };
+                for statement in statements {
+                    let reader = client
+                        .copy_out_simple(statement.as_str())
+                        .instrument(span.clone())
+                        .await
+                        .err_during(Operation::SnapshotCopy)?;
+
+                    tokio::pin!(reader);
+                    // TODO: once tokio-stream is released with
+                    //    https://github.com/tokio-rs/tokio/pull/4502 we can convert this into a
+                    //    single `timeout(...)` call on the reader CopyOutStream
+                    while let Some(b) =
+                        tokio::time::timeout(copy_read_timeout, reader.next())
+                            .instrument(span.clone())
+                            .await?
+                            .transpose()
+                            .err_during(Operation::SnapshotCopy)?
+                    {
+                        metrics.snapshot_bytes_received.inc_by(u64::cast_from(b.len()));
+                        check_snapshot_row_size(b.len(), info, limits, metrics)?;
+                        // Convert raw rows from COPY into repr:Row. Each Row is a relation_id
+                        // and list of string-encoded values, e.g. Row{ 16391 , ["1", "2"] }
+                        if !decoder.decode(&b, &info.desc.columns, &mut text_row)? {
+                            continue;
+                        }
+
+                        let mut datums = datum_vec.borrow_with_len(info.desc.columns.len());
+                        datums.extend(text_row.iter());
+
+                        yield cast_table_row(info, &datums, metrics);
+                    }
+                }
+            }
+            Some(fetch_size) => {
+                // Rows are only fetched as the stream is polled, so the upstream doesn't run
+                // ahead of a consumer that is waiting for room in the channel.
+                let query = ordered.unwrap_or_else(|| {
+                    format!(
+                        "SELECT {} FROM {:?}.{:?}",
+                        columns.as_deref().unwrap_or("*"),
+                        info.desc.namespace,
+                        info.desc.name
+                    )
+                });
+                client
+                    .simple_query(&format!("DECLARE mz_snapshot CURSOR FOR {query}"))
+                    .instrument(span.clone())
+                    .await
+                    .err_during(Operation::SnapshotCopy)?;
+                let fetch = format!("FETCH FORWARD {fetch_size} FROM mz_snapshot");
+                loop {
+                    let res = tokio::time::timeout(copy_read_timeout, client.simple_query(&fetch))
+                        .instrument(span.clone())
+                        .await?
+                        .err_during(Operation::SnapshotCopy)?;
+                    let mut fetched = 0;
+                    for row in query::rows(&res) {
+                        fetched += 1;
+                        let len = info.desc.columns.len();
+                        let mut size = 0;
+                        for i in 0..len {
+                            size += row.try_get(i).err_definite()?.map_or(0, str::len);
+                        }
+                        metrics.snapshot_bytes_received.inc_by(u64::cast_from(size));
+                        check_snapshot_row_size(size, info, limits, metrics)?;
+
+                        let mut packer = text_row.packer();
+                        for i in 0..len {
+                            match row.try_get(i).err_definite()? {
+                                Some(value) => packer.push(Datum::String(value)),
+                                None => packer.push(Datum::Null),
+                            }
+                        }
+
+                        let mut datums = datum_vec.borrow_with_len(len);
+                        datums.extend(text_row.iter());
+
+                        yield cast_table_row(info, &datums, metrics);
+                    }
+                    if fetched < fetch_size {
+                        break;
+                    }
+                }
+                client
+                    .simple_query("CLOSE mz_snapshot")
+                    .instrument(span.clone())
+                    .await?;
+            }
+        }
+    }
+}
+
+/// Returns the query that selects the rows of the table described by `info` in the order of its
+/// key at the positions `key`, starting after the row with the key `after`, if any.
+pub(super) fn ordered_snapshot_query(
+    info: &SourceTable,
+    key: &[usize],
+    after: Option<&Row>,
+) -> String {
+    let key_columns: Vec<_> = key
+        .iter()
+        .map(|position| format!("{:?}", info.desc.columns[*position].name))
+        .collect();
+    let key_columns = key_columns.join(", ");
+    let filter = match after {
+        Some(after) => {
+            let values: Vec<_> = after
+                .iter()
+                .map(|datum| match datum {
+                    Datum::Int16(value) => value.to_string(),
+                    Datum::Int32(value) => value.to_string(),
+                    Datum::Int64(value) => value.to_string(),
+                    Datum::Uuid(value) => format!("'{value}'"),
+                    _ => unreachable!("row_key only keeps the values resume_key allows"),
+                })
+                .collect();
+            format!(" WHERE ({key_columns}) > ({})", values.join(", "))
+        }
+        None => String::new(),
+    };
+    format!(
+        "SELECT {} FROM {:?}.{:?}{filter} ORDER BY {key_columns}",
+        column_names(&info.desc),
+        info.desc.namespace,
+        info.desc.name,
+    )
+}
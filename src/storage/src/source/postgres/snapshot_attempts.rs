@@ -0,0 +1,98 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Keeping track of how far the killed snapshots of Postgres sources got across their restarts.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use mz_repr::GlobalId;
+
+use super::SnapshotKilled;
+
+/// The furthest that the killed snapshots of each Postgres source got. A source restarts when the
+/// upstream kills its snapshot, which loses the state of the source, so this is shared by all
+/// Postgres sources of a worker instead. It only covers the restarts within the current process.
+#[derive(Debug, Default)]
+pub struct PgSnapshotAttempts {
+    killed: Mutex<BTreeMap<GlobalId, Attempts>>,
+}
+
+#[derive(Debug)]
+struct Attempts {
+    /// The killed attempt that got the furthest
+    furthest: SnapshotKilled,
+    /// The number of attempts since `furthest` that got no further
+    stalled: usize,
+}
+
+impl PgSnapshotAttempts {
+    /// Records that the snapshot of the source `id` was killed after getting as far as `killed`,
+    /// returning the number of attempts in a row, including this one, that got no further than an
+    /// earlier one.
+    pub(super) fn record_killed(&self, id: GlobalId, killed: &SnapshotKilled) -> usize {
+        let mut sources = self.killed.lock().expect("lock poisoned");
+        match sources.get_mut(&id) {
+            Some(attempts) if !killed.got_further_than(&attempts.furthest) => {
+                attempts.stalled += 1;
+                attempts.stalled
+            }
+            _ => {
+                let furthest = killed.clone();
+                sources.insert(
+                    id,
+                    Attempts {
+                        furthest,
+                        stalled: 0,
+                    },
+                );
+                0
+            }
+        }
+    }
+
+    /// Forgets the killed snapshots of the source `id`, once its snapshot completed.
+    pub(super) fn clear(&self, id: GlobalId) {
+        self.killed.lock().expect("lock poisoned").remove(&id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn killed(table: &str, rows: u64, tables_done: usize) -> SnapshotKilled {
+        SnapshotKilled {
+            table: table.into(),
+            rows,
+            tables_done,
+            tables_total: 3,
+        }
+    }
+
+    #[test]
+    fn stalled_attempts() {
+        let attempts = PgSnapshotAttempts::default();
+        let id = GlobalId::User(1);
+        assert_eq!(attempts.record_killed(id, &killed("t1", 100, 0)), 0);
+        assert_eq!(attempts.record_killed(id, &killed("t1", 200, 0)), 0);
+        assert_eq!(attempts.record_killed(id, &killed("t1", 150, 0)), 1);
+        assert_eq!(attempts.record_killed(id, &killed("t1", 200, 0)), 2);
+        // Completing a table is progress, however few rows of the next one were copied.
+        assert_eq!(attempts.record_killed(id, &killed("t2", 10, 1)), 0);
+        // Other sources are tracked separately.
+        assert_eq!(
+            attempts.record_killed(GlobalId::User(2), &killed("t1", 0, 0)),
+            0
+        );
+
+        attempts.clear(id);
+        assert_eq!(attempts.record_killed(id, &killed("t1", 0, 0)), 0);
+    }
+}
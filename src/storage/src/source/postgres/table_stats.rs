@@ -0,0 +1,111 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Per-table operation counts of a Postgres source.
+
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use tracing::info;
+
+use mz_repr::GlobalId;
+
+/// How often the counts are logged.
+pub(super) const DEFAULT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Counts the inserts, updates and deletes replicated for each upstream table, and periodically
+/// logs them.
+///
+/// Per-table Prometheus metrics would add a time series per table of every source, so the counts
+/// are surfaced in structured log lines instead, which log-based analytics can aggregate.
+#[derive(Debug)]
+pub(super) struct TableStats {
+    source_id: GlobalId,
+    interval: Duration,
+    last_logged: Instant,
+    /// The insert, update and delete counts of each table, by OID
+    counts: BTreeMap<u32, (u64, u64, u64)>,
+}
+
+impl TableStats {
+    pub(super) fn new(source_id: GlobalId, interval: Duration) -> Self {
+        Self {
+            source_id,
+            interval,
+            last_logged: Instant::now(),
+            counts: BTreeMap::new(),
+        }
+    }
+
+    /// Counts an insert into the table with `oid`.
+    pub(super) fn insert(&mut self, oid: u32) {
+        self.counts.entry(oid).or_default().0 += 1;
+        self.maybe_log(Instant::now());
+    }
+
+    /// Counts an update of the table with `oid`.
+    pub(super) fn update(&mut self, oid: u32) {
+        self.counts.entry(oid).or_default().1 += 1;
+        self.maybe_log(Instant::now());
+    }
+
+    /// Counts a delete from the table with `oid`.
+    pub(super) fn delete(&mut self, oid: u32) {
+        self.counts.entry(oid).or_default().2 += 1;
+        self.maybe_log(Instant::now());
+    }
+
+    /// Logs the counts of every table if `interval` passed since they were last logged at `now`,
+    /// returning whether they were.
+    fn maybe_log(&mut self, now: Instant) -> bool {
+        if now.saturating_duration_since(self.last_logged) < self.interval {
+            return false;
+        }
+        self.last_logged = now;
+        for (table_oid, (inserts, updates, deletes)) in &self.counts {
+            info!(
+                source_id = %self.source_id,
+                table_oid,
+                inserts,
+                updates,
+                deletes,
+                "postgres source table stats"
+            );
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_operations_per_table() {
+        let mut stats = TableStats::new(GlobalId::User(1), Duration::from_secs(60));
+        stats.insert(16384);
+        stats.insert(16384);
+        stats.update(16384);
+        stats.delete(16385);
+        assert_eq!(
+            stats.counts,
+            BTreeMap::from([(16384, (2, 1, 0)), (16385, (0, 0, 1))])
+        );
+    }
+
+    #[test]
+    fn logs_once_per_interval() {
+        let mut stats = TableStats::new(GlobalId::User(1), Duration::from_secs(60));
+        let start = stats.last_logged;
+        assert!(!stats.maybe_log(start + Duration::from_secs(30)));
+        assert!(stats.maybe_log(start + Duration::from_secs(60)));
+        assert!(!stats.maybe_log(start + Duration::from_secs(90)));
+        assert!(stats.maybe_log(start + Duration::from_secs(120)));
+    }
+}
@@ -0,0 +1,166 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Retracting the rows of tables that are truncated upstream, for sources that are configured to
+//! do so instead of erroring.
+
+use std::collections::BTreeMap;
+
+use mz_persist_client::cache::PersistClientCache;
+use mz_repr::{Diff, GlobalId};
+use mz_storage_client::controller::CollectionMetadata;
+
+use super::provenance::strip_provenance;
+use super::{is_soft_deleted, schema_change, soft_deleted_row, SourceTable, TableRow};
+
+/// Reads the rows that a truncated table's output currently holds, which must be retracted.
+pub(super) struct TruncateRetractions<'a> {
+    persist_clients: &'a PersistClientCache,
+    /// The collections the outputs are exported to, by output index
+    outputs: &'a BTreeMap<usize, (GlobalId, CollectionMetadata)>,
+    /// Whether the rows of the outputs carry provenance headers as their last column
+    provenance_headers: bool,
+}
+
+impl<'a> TruncateRetractions<'a> {
+    pub(super) fn new(
+        persist_clients: &'a PersistClientCache,
+        outputs: &'a BTreeMap<usize, (GlobalId, CollectionMetadata)>,
+        provenance_headers: bool,
+    ) -> Self {
+        Self {
+            persist_clients,
+            outputs,
+            provenance_headers,
+        }
+    }
+
+    /// Reads the rows of the output `output_index`, without their provenance headers. Only the
+    /// rows that have been persisted are read, so the caller must wait until everything it emitted
+    /// so far has been.
+    pub(super) async fn read(
+        &self,
+        output_index: usize,
+    ) -> Result<Vec<(TableRow, Diff)>, anyhow::Error> {
+        let Some((id, metadata)) = self.outputs.get(&output_index) else {
+            anyhow::bail!("unknown output {output_index}");
+        };
+        let rows = schema_change::read_collection(self.persist_clients, *id, metadata).await?;
+        match self.provenance_headers {
+            true => Ok(strip_provenance(rows)),
+            false => Ok(rows),
+        }
+    }
+}
+
+/// Replaces the changes to the table `info` buffered in `inserts` and `deletes` by the current
+/// transaction with the retraction of the `rows` its output holds, given that the transaction
+/// truncated the table.
+///
+/// Rows of soft delete tables are flagged as deleted instead, unless they already are. The errors
+/// of rows that failed to cast are retracted either way.
+pub(super) fn retract_truncated(
+    info: &SourceTable,
+    rows: Vec<(TableRow, Diff)>,
+    inserts: &mut Vec<(usize, TableRow)>,
+    deletes: &mut Vec<(usize, TableRow)>,
+) {
+    // The transaction's earlier changes to the table are wiped out along with the table.
+    inserts.retain(|(output, _)| *output != info.output_index);
+    deletes.retain(|(output, _)| *output != info.output_index);
+    for (row, diff) in rows {
+        let deleted = match &row {
+            Ok(row) if info.soft_delete => {
+                if is_soft_deleted(row) {
+                    continue;
+                }
+                Some(soft_deleted_row(row))
+            }
+            _ => None,
+        };
+        for _ in 0..diff {
+            if let Some(deleted) = &deleted {
+                inserts.push((info.output_index, Ok(deleted.clone())));
+            }
+            deletes.push((info.output_index, row.clone()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use mz_postgres_util::desc::PostgresTableDesc;
+    use mz_repr::{Datum, Row};
+    use mz_storage_client::types::errors::SourceErrorDetails;
+
+    use super::*;
+
+    fn source_table(output_index: usize, soft_delete: bool) -> SourceTable {
+        SourceTable {
+            output_index,
+            desc: PostgresTableDesc {
+                oid: 1,
+                namespace: "public".into(),
+                name: "t1".into(),
+                columns: vec![],
+                keys: Default::default(),
+                replica_identity: Default::default(),
+                identity_column_names: vec![],
+            },
+            casts: vec![],
+            projection: None,
+            default_datums: vec![],
+            soft_delete,
+            expected_names: BTreeSet::new(),
+        }
+    }
+
+    #[test]
+    fn truncated_rows() {
+        let a = Row::pack([Datum::Int32(1)]);
+        let b = Row::pack([Datum::Int32(2)]);
+        let other = Row::pack([Datum::Int32(3)]);
+
+        let mut inserts = vec![(1, Ok(b.clone())), (2, Ok(other.clone()))];
+        let mut deletes = vec![(1, Ok(a.clone()))];
+        retract_truncated(
+            &source_table(1, false),
+            vec![(Ok(a.clone()), 2)],
+            &mut inserts,
+            &mut deletes,
+        );
+        assert_eq!(inserts, vec![(2, Ok(other.clone()))]);
+        assert_eq!(deletes, vec![(1, Ok(a.clone())), (1, Ok(a.clone()))]);
+
+        // Rows of soft delete tables are flagged as deleted, unless they already are, while the
+        // errors of rows that failed to cast are retracted.
+        let live = Row::pack([Datum::Int32(1), Datum::False]);
+        let deleted = Row::pack([Datum::Int32(2), Datum::True]);
+        let error = SourceErrorDetails::Other("failed to cast".into());
+        let mut inserts = vec![];
+        let mut deletes = vec![];
+        retract_truncated(
+            &source_table(1, true),
+            vec![
+                (Ok(live.clone()), 1),
+                (Ok(deleted), 1),
+                (Err(error.clone()), 1),
+            ],
+            &mut inserts,
+            &mut deletes,
+        );
+        assert_eq!(
+            inserts,
+            vec![(1, Ok(Row::pack([Datum::Int32(1), Datum::True])))]
+        );
+        assert_eq!(deletes, vec![(1, Ok(live)), (1, Err(error))]);
+    }
+}
@@ -0,0 +1,158 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Capture of the raw replication stream of a Postgres source, for debugging.
+//!
+//! The payload of every `XLogData` message is appended to a file before it is decoded, so that
+//! the exact bytes the output plugin sent can be inspected after the fact, much like `pg_waldump`
+//! does for physical WAL. Each message is written as a frame made of its WAL start and WAL end as
+//! big-endian `u64`s, the length of its payload as a big-endian `u32` and the payload itself.
+//!
+//! The file is written by a separate task. Frames that arrive while its queue is full are
+//! dropped rather than slowing down replication, and the gap is logged when the next frame is
+//! written. Failing to write the file only stops the capture.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use bytes::Bytes;
+use mz_ore::task;
+use mz_repr::GlobalId;
+use tokio::fs::OpenOptions;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tokio_postgres::types::PgLsn;
+use tracing::{info, warn};
+
+/// The number of frames that may be queued for the writer before new ones are dropped.
+const QUEUE_LEN: usize = 1024;
+
+/// The payload of an `XLogData` message, along with the WAL positions it spans.
+#[derive(Debug)]
+struct Frame {
+    wal_start: u64,
+    wal_end: u64,
+    data: Bytes,
+}
+
+/// A handle through which the raw replication stream is captured to a file.
+#[derive(Debug)]
+pub(super) struct WalCapture {
+    tx: mpsc::Sender<Frame>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl WalCapture {
+    /// Starts capturing to the file at `path`, which is created if it does not exist and
+    /// appended to otherwise.
+    pub(super) fn start(source_id: GlobalId, path: String) -> Self {
+        let (tx, rx) = mpsc::channel(QUEUE_LEN);
+        let dropped = Arc::new(AtomicU64::new(0));
+        task::spawn(
+            || format!("postgres_source_wal_capture:{source_id}"),
+            write_frames(source_id, path, rx, Arc::clone(&dropped)),
+        );
+        Self { tx, dropped }
+    }
+
+    /// Queues the payload of an `XLogData` message spanning `wal_start` to `wal_end` to be
+    /// written.
+    pub(super) fn capture(&self, wal_start: u64, wal_end: u64, data: &Bytes) {
+        let frame = Frame {
+            wal_start,
+            wal_end,
+            data: data.clone(),
+        };
+        match self.tx.try_send(frame) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+            // The writer stopped after failing to write the file, which it logged.
+            Err(TrySendError::Closed(_)) => {}
+        }
+    }
+}
+
+/// Appends the encoding of `frame` to `buf`.
+fn encode_frame(frame: &Frame, buf: &mut Vec<u8>) {
+    let len = u32::try_from(frame.data.len()).expect("XLogData payloads are smaller than 4GiB");
+    buf.extend_from_slice(&frame.wal_start.to_be_bytes());
+    buf.extend_from_slice(&frame.wal_end.to_be_bytes());
+    buf.extend_from_slice(&len.to_be_bytes());
+    buf.extend_from_slice(&frame.data);
+}
+
+async fn write_frames(
+    source_id: GlobalId,
+    path: String,
+    mut rx: mpsc::Receiver<Frame>,
+    dropped: Arc<AtomicU64>,
+) {
+    let mut file = match OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .await
+    {
+        Ok(file) => file,
+        Err(err) => {
+            warn!("source {source_id} cannot capture its raw replication stream to {path}: {err}");
+            return;
+        }
+    };
+    info!("source {source_id} capturing its raw replication stream to {path}");
+    let mut buf = vec![];
+    while let Some(frame) = rx.recv().await {
+        let gap = dropped.swap(0, Ordering::Relaxed);
+        if gap > 0 {
+            warn!(
+                "source {source_id} dropped {gap} captured replication messages before {}",
+                PgLsn::from(frame.wal_start)
+            );
+        }
+        buf.clear();
+        encode_frame(&frame, &mut buf);
+        if let Err(err) = file.write_all(&buf).await {
+            warn!("source {source_id} stopped capturing its raw replication stream: {err}");
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_encoding() {
+        let mut buf = vec![];
+        encode_frame(
+            &Frame {
+                wal_start: 0x0102,
+                wal_end: 0x0304,
+                data: Bytes::from_static(b"B"),
+            },
+            &mut buf,
+        );
+        encode_frame(
+            &Frame {
+                wal_start: 5,
+                wal_end: 6,
+                data: Bytes::new(),
+            },
+            &mut buf,
+        );
+        let mut expected = vec![
+            0, 0, 0, 0, 0, 0, 1, 2, 0, 0, 0, 0, 0, 0, 3, 4, 0, 0, 0, 1, b'B',
+        ];
+        expected.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 5, 0, 0, 0, 0, 0, 0, 0, 6, 0, 0, 0, 0]);
+        assert_eq!(buf, expected);
+    }
+}
@@ -718,11 +718,11 @@ mod tests {
         let mut prev = None;
         for (pid, offset) in items {
             assert!(prev.as_ref() < Some(&pid));
-            let gap = Partitioned::with_range(prev.clone(), Some(pid.clone()), MzOffset::from(0));
+            let gap = Partitioned::with_range(prev.clone(), Some(pid.clone()), MzOffset::ZERO);
             frontier.extend([gap, Partitioned::with_partition(pid.clone(), offset)]);
             prev = Some(pid);
         }
-        frontier.insert(Partitioned::with_range(prev, None, MzOffset::from(0)));
+        frontier.insert(Partitioned::with_range(prev, None, MzOffset::ZERO));
         frontier
     }
 
@@ -837,23 +837,23 @@ mod tests {
             BTreeSet::from_iter([
                 // Initial state
                 (
-                    Partitioned::with_range(None, None, MzOffset::from(0)),
+                    Partitioned::with_range(None, None, MzOffset::ZERO),
                     0.into(),
                     1
                 ),
                 // updates from first mint
                 (
-                    Partitioned::with_range(None, Some(1), MzOffset::from(0)),
+                    Partitioned::with_range(None, Some(1), MzOffset::ZERO),
                     1000.into(),
                     1
                 ),
                 (
-                    Partitioned::with_range(None, None, MzOffset::from(0)),
+                    Partitioned::with_range(None, None, MzOffset::ZERO),
                     1000.into(),
                     -1
                 ),
                 (
-                    Partitioned::with_range(Some(1), None, MzOffset::from(0)),
+                    Partitioned::with_range(Some(1), None, MzOffset::ZERO),
                     1000.into(),
                     1
                 ),
@@ -864,17 +864,17 @@ mod tests {
                 ),
                 // updates from second mint
                 (
-                    Partitioned::with_range(Some(1), Some(2), MzOffset::from(0)),
+                    Partitioned::with_range(Some(1), Some(2), MzOffset::ZERO),
                     2000.into(),
                     1
                 ),
                 (
-                    Partitioned::with_range(Some(1), None, MzOffset::from(0)),
+                    Partitioned::with_range(Some(1), None, MzOffset::ZERO),
                     2000.into(),
                     -1
                 ),
                 (
-                    Partitioned::with_range(Some(2), None, MzOffset::from(0)),
+                    Partitioned::with_range(Some(2), None, MzOffset::ZERO),
                     2000.into(),
                     1
                 ),
@@ -60,13 +60,15 @@ use mz_ore::now::NowFn;
 use mz_ore::vec::VecExt;
 use mz_persist_client::cache::PersistClientCache;
 use mz_repr::{Diff, GlobalId, RelationDesc, Row};
-use mz_storage_client::client::SourceStatisticsUpdate;
+use mz_storage_client::client::{ReplicationSlotUpdate, SourceStatisticsUpdate};
 use mz_storage_client::controller::{CollectionMetadata, ResumptionFrontierCalculator};
 use mz_storage_client::healthcheck::MZ_SOURCE_STATUS_HISTORY_DESC;
 use mz_storage_client::types::connections::ConnectionContext;
 use mz_storage_client::types::errors::SourceError;
 use mz_storage_client::types::sources::encoding::SourceDataEncoding;
-use mz_storage_client::types::sources::{MzOffset, SourceConnection, SourceTimestamp, SourceToken};
+use mz_storage_client::types::sources::{
+    MzOffset, SourceConnection, SourceExport, SourceTimestamp, SourceToken,
+};
 use mz_timely_util::antichain::AntichainExt;
 use mz_timely_util::builder_async::{
     AsyncOutputHandle, Event as AsyncEvent, OperatorBuilder as AsyncOperatorBuilder,
@@ -82,6 +84,7 @@ use crate::source::types::{
     HealthStatus, HealthStatusUpdate, MaybeLength, SourceMessage, SourceMetrics, SourceOutput,
     SourceReaderError, SourceRender,
 };
+use crate::source::{PgSnapshotAttempts, PgSourceLimits, PgSourcePauses};
 use crate::statistics::{SourceStatisticsMetrics, StorageStatistics};
 
 /// How long to wait before initiating a `SuspendAndRestart` command, to
@@ -126,6 +129,14 @@ pub struct RawSourceCreationConfig {
     pub source_statistics: StorageStatistics<SourceStatisticsUpdate, SourceStatisticsMetrics>,
     /// Enables reporting the remap operator's write frontier.
     pub shared_remap_upper: Rc<RefCell<Antichain<mz_repr::Timestamp>>>,
+    /// Runtime adjustable limits applied by Postgres sources.
+    pub pg_source_limits: Arc<PgSourceLimits>,
+    /// The Postgres sources whose replication is paused.
+    pub pg_source_pauses: Arc<PgSourcePauses>,
+    /// How far the killed snapshots of Postgres sources got across their restarts.
+    pub pg_snapshot_attempts: Arc<PgSnapshotAttempts>,
+    /// The collections exported by this source, along with the metadata needed to read them.
+    pub source_exports: BTreeMap<GlobalId, SourceExport<CollectionMetadata>>,
 }
 
 /// Creates a source dataflow operator graph from a source connection. The type of SourceConnection
@@ -278,7 +289,7 @@ where
                     Ok(_) => HealthStatusUpdate::from(HealthStatus::Running),
                     Err(ref error) => HealthStatusUpdate::from(HealthStatus::StalledWithError {
                         error: error.inner.to_string(),
-                        hint: None,
+                        hint: error.inner.hint(),
                     }),
                 };
                 if statuses.last() != Some(&status) {
@@ -332,6 +343,7 @@ fn health_operator<G: Scope>(
         storage_metadata,
         persist_clients,
         now,
+        source_statistics,
         ..
     } = config;
 
@@ -355,6 +367,9 @@ fn health_operator<G: Scope>(
     }
 
     let mut last_reported_status = overall_status(&healths).cloned();
+    // The latest details reported by any worker, and whether they were written out yet.
+    let mut details = None;
+    let mut details_changed = false;
 
     let button = health_op.build(move |mut _capabilities| async move {
         let persist_client = persist_clients
@@ -384,19 +399,38 @@ fn health_operator<G: Scope>(
                     let HealthStatusUpdate {
                         update,
                         should_halt,
+                        details: new_details,
                     } = health_event;
                     if should_halt {
                         halt_with = Some(update.clone());
                     }
                     healths[worker_id] = Some(update);
+                    let slot = new_details
+                        .as_ref()
+                        .and_then(|details| details.postgres.as_ref())
+                        .and_then(|postgres| postgres.slot.clone());
+                    if let Some(status) = slot {
+                        source_statistics.set_replication_slot(ReplicationSlotUpdate {
+                            id: source_id,
+                            status,
+                            updated_at: now(),
+                        });
+                    }
+                    if new_details.is_some() && new_details != details {
+                        details = new_details;
+                        details_changed = true;
+                    }
                 }
 
                 if let Some(new_status) = overall_status(&healths) {
-                    if last_reported_status.as_ref() != Some(&new_status) {
-                        info!(
-                            "Health transition for source {source_id}: \
-                              {last_reported_status:?} -> {new_status:?}"
-                        );
+                    let transitioned = last_reported_status.as_ref() != Some(&new_status);
+                    if transitioned || details_changed {
+                        if transitioned {
+                            info!(
+                                "Health transition for source {source_id}: \
+                                  {last_reported_status:?} -> {new_status:?}"
+                            );
+                        }
                         if let Some(status_shard) = storage_metadata.status_shard {
                             write_to_persist(
                                 source_id,
@@ -407,11 +441,13 @@ fn health_operator<G: Scope>(
                                 status_shard,
                                 &*MZ_SOURCE_STATUS_HISTORY_DESC,
                                 new_status.hint(),
+                                details.as_ref(),
                             )
                             .await;
                         }
 
                         last_reported_status = Some(new_status.clone());
+                        details_changed = false;
                     }
                 }
                 // TODO(aljoscha): Instead of threading through the
@@ -518,6 +554,10 @@ where
         persist_clients,
         source_statistics: _,
         shared_remap_upper,
+        pg_source_limits: _,
+        pg_source_pauses: _,
+        pg_snapshot_attempts: _,
+        source_exports: _,
     } = config;
 
     let chosen_worker = usize::cast_from(id.hashed() % u64::cast_from(worker_count));
@@ -672,6 +712,10 @@ where
         persist_clients: _,
         source_statistics: _,
         shared_remap_upper: _,
+        pg_source_limits: _,
+        pg_source_pauses: _,
+        pg_snapshot_attempts: _,
+        source_exports: _,
     } = config;
 
     let bytes_read_counter = base_metrics.bytes_read.clone();
@@ -1001,7 +1045,7 @@ async fn handle_message<K, V, T, D>(
             }
 
             (
-                message.output,
+                message.output_index,
                 Ok(SourceOutput::new(
                     message.key,
                     message.value,
@@ -1013,13 +1057,12 @@ async fn handle_message<K, V, T, D>(
             )
         }
         Err(err) => {
+            let output_index = err.output_index;
             let err = SourceError {
                 source_id,
                 error: err.inner,
             };
-            // XXX(petrosagg): errors should be attributed to a specific output by the source impl
-            // instead of hardcoding it to output zero
-            (0, Err(err))
+            (output_index, Err(err))
         }
     };
 
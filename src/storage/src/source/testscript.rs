@@ -75,7 +75,7 @@ impl SourceRender for TestScriptSourceConnection {
                         // For now we only support `Finalized` messages
                         let msg = Ok(SourceMessage {
                             // For now, we only support single-output, single partition
-                            output: 0,
+                            output_index: 0,
                             upstream_time_millis: None,
                             key: key.map(|k| k.into_bytes()),
                             value: Some(value.into_bytes()),
@@ -27,6 +27,7 @@ use timely::progress::Antichain;
 use mz_expr::PartitionId;
 use mz_ore::metrics::{CounterVecExt, DeleteOnDropCounter, DeleteOnDropGauge, GaugeVecExt};
 use mz_repr::{Diff, GlobalId, Row};
+use mz_storage_client::healthcheck::SourceStatusDetails;
 use mz_storage_client::types::connections::ConnectionContext;
 use mz_storage_client::types::errors::{DecodeError, SourceErrorDetails};
 use mz_storage_client::types::sources::{MzOffset, SourceTimestamp};
@@ -83,6 +84,8 @@ pub trait SourceRender {
 pub struct HealthStatusUpdate {
     pub update: HealthStatus,
     pub should_halt: bool,
+    /// Details about the state of the source, if it reports them.
+    pub details: Option<SourceStatusDetails>,
 }
 
 /// NB: we derive Ord here, so the enum order matters. Generally, statuses later in the list
@@ -92,7 +95,18 @@ pub struct HealthStatusUpdate {
 pub enum HealthStatus {
     Starting,
     Running,
-    StalledWithError { error: String, hint: Option<String> },
+    /// The source keeps ingesting, but is at risk of failing, e.g. because it buffers a large
+    /// upstream transaction in memory.
+    Degraded {
+        error: String,
+        hint: Option<String>,
+    },
+    /// The source deliberately stopped ingesting, e.g. because it was paused by the user.
+    Paused,
+    StalledWithError {
+        error: String,
+        hint: Option<String>,
+    },
 }
 
 impl HealthStatus {
@@ -100,21 +114,26 @@ impl HealthStatus {
         match self {
             HealthStatus::Starting => "starting",
             HealthStatus::Running => "running",
+            HealthStatus::Degraded { .. } => "degraded",
+            HealthStatus::Paused => "paused",
             HealthStatus::StalledWithError { .. } => "stalled",
         }
     }
 
     pub fn error(&self) -> Option<&str> {
         match self {
-            HealthStatus::Starting | HealthStatus::Running => None,
-            HealthStatus::StalledWithError { error, .. } => Some(error),
+            HealthStatus::Starting | HealthStatus::Running | HealthStatus::Paused => None,
+            HealthStatus::Degraded { error, .. } | HealthStatus::StalledWithError { error, .. } => {
+                Some(error)
+            }
         }
     }
 
     pub fn hint(&self) -> Option<&str> {
         match self {
-            HealthStatus::Starting | HealthStatus::Running => None,
-            HealthStatus::StalledWithError { error: _, hint } => hint.as_deref(),
+            HealthStatus::Starting | HealthStatus::Running | HealthStatus::Paused => None,
+            HealthStatus::Degraded { error: _, hint }
+            | HealthStatus::StalledWithError { error: _, hint } => hint.as_deref(),
         }
     }
 }
@@ -124,6 +143,7 @@ impl From<HealthStatus> for HealthStatusUpdate {
         HealthStatusUpdate {
             update,
             should_halt: false,
+            details: None,
         }
     }
 }
@@ -132,9 +152,9 @@ impl From<HealthStatus> for HealthStatusUpdate {
 /// conversion to Message.
 #[derive(Debug, Clone)]
 pub struct SourceMessage<Key, Value> {
-    /// The output stream this message belongs to. Later in the pipeline the stream is partitioned
-    /// based on this value and is fed to the appropriate source exports
-    pub output: usize,
+    /// The index of the output stream this message belongs to. Later in the pipeline the stream
+    /// is partitioned based on this value and is fed to the appropriate source exports
+    pub output_index: usize,
     /// The time that an external system first observed the message
     ///
     /// Milliseconds since the unix epoch
@@ -214,6 +234,9 @@ pub struct DecodeResult {
 #[derive(Debug, Clone)]
 pub struct SourceReaderError {
     pub inner: SourceErrorDetails,
+    /// The output the error is attributed to. Errors that concern the source as a whole are
+    /// attributed to output 0, the source's own collection.
+    pub output_index: usize,
 }
 
 impl SourceReaderError {
@@ -223,6 +246,7 @@ impl SourceReaderError {
     pub fn other_definite(e: anyhow::Error) -> SourceReaderError {
         SourceReaderError {
             inner: SourceErrorDetails::Other(format!("{}", e)),
+            output_index: 0,
         }
     }
 }
@@ -19,7 +19,9 @@ use mz_ore::metric;
 use mz_ore::metrics::{CounterVecExt, DeleteOnDropCounter, DeleteOnDropGauge, GaugeVecExt};
 use mz_ore::metrics::{IntCounterVec, MetricsRegistry, UIntGaugeVec};
 use mz_repr::GlobalId;
-use mz_storage_client::client::{SinkStatisticsUpdate, SourceStatisticsUpdate};
+use mz_storage_client::client::{
+    ReplicationSlotUpdate, SinkStatisticsUpdate, SourceStatisticsUpdate,
+};
 use prometheus::core::AtomicU64;
 
 use crate::sink::metrics::SinkBaseMetrics;
@@ -267,6 +269,7 @@ impl StorageStatistics<SourceStatisticsUpdate, SourceStatisticsMetrics> {
                     updates_staged: 0,
                     updates_committed: 0,
                     bytes_received: 0,
+                    replication_slot: None,
                 },
                 SourceStatisticsMetrics::new(id, worker_id, metrics, parent_source_id, shard_id),
             ))),
@@ -319,6 +322,11 @@ impl StorageStatistics<SourceStatisticsUpdate, SourceStatisticsMetrics> {
         cur.1.bytes_received = cur.1.bytes_received + value;
         cur.2.bytes_received.inc_by(value);
     }
+
+    /// Set the `replication_slot` stat, which has no Prometheus counterpart.
+    pub fn set_replication_slot(&self, value: ReplicationSlotUpdate) {
+        self.stats.borrow_mut().1.replication_slot = Some(value);
+    }
 }
 
 impl StorageStatistics<SinkStatisticsUpdate, SinkStatisticsMetrics> {
@@ -107,6 +107,7 @@ use crate::internal_control::{
 };
 use crate::sink::SinkBaseMetrics;
 use crate::source::metrics::SourceBaseMetrics;
+use crate::source::{PgSnapshotAttempts, PgSourceLimits, PgSourcePauses};
 use crate::statistics::{SinkStatisticsMetrics, SourceStatisticsMetrics, StorageStatistics};
 use crate::storage_state::async_storage_worker::{AsyncStorageWorker, AsyncStorageWorkerResponse};
 
@@ -214,6 +215,9 @@ impl<'w, A: Allocate> Worker<'w, A> {
             internal_cmd_tx: command_sequencer,
             async_worker,
             dataflow_parameters: Default::default(),
+            pg_source_limits: Default::default(),
+            pg_source_pauses: Default::default(),
+            pg_snapshot_attempts: Default::default(),
         };
 
         // TODO(aljoscha): We might want `async_worker` and `internal_cmd_tx` to
@@ -295,6 +299,17 @@ pub struct StorageState {
 
     /// Dynamically configurable parameters that control how dataflows are rendered.
     pub dataflow_parameters: DataflowParameters,
+
+    /// Limits applied by Postgres sources, which can be updated while the sources are running.
+    pub pg_source_limits: Arc<PgSourceLimits>,
+
+    /// The Postgres sources whose replication is paused, which can be updated while the sources
+    /// are running.
+    pub pg_source_pauses: Arc<PgSourcePauses>,
+
+    /// How far the killed snapshots of Postgres sources got, which outlives the restarts of the
+    /// sources.
+    pub pg_snapshot_attempts: Arc<PgSnapshotAttempts>,
 }
 
 /// This maintains an additional read hold on the source data for a sink, alongside
@@ -1064,6 +1079,8 @@ impl StorageState {
             StorageCommand::UpdateConfiguration(params) => {
                 tracing::info!("Applying configuration update: {params:?}");
                 params.persist.apply(self.persist_clients.cfg());
+                self.pg_source_limits.apply(&params);
+                self.pg_source_pauses.apply(&params);
 
                 // This needs to be broadcast by one worker and go through
                 // the internal command fabric, to ensure consistent
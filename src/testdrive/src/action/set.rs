@@ -9,11 +9,12 @@
 
 use std::cmp;
 
-use anyhow::{bail, Context};
+use anyhow::{anyhow, bail, Context};
 use regex::Regex;
 
 use crate::action::{ControlFlow, State};
 use crate::parser::BuiltinCommand;
+use crate::util::postgres::postgres_client;
 
 pub const DEFAULT_REGEX_REPLACEMENT: &str = "<regex_match>";
 
@@ -91,13 +92,27 @@ pub async fn run_set_from_sql(
     state: &mut State,
 ) -> Result<ControlFlow, anyhow::Error> {
     let var = cmd.args.string("var")?;
+    let connection = cmd.args.opt_string("connection");
     cmd.args.done()?;
 
-    let row = state
-        .pgclient
-        .query_one(&cmd.input.join("\n"), &[])
-        .await
-        .context("running query")?;
+    let query = cmd.input.join("\n");
+    let row = match connection {
+        // Queries the upstream PostgreSQL server instead of Materialize.
+        Some(connection) if connection.starts_with("postgres://") => {
+            let (client, _) = postgres_client(&connection).await?;
+            client.query_one(&query, &[]).await
+        }
+        Some(connection) => {
+            state
+                .postgres_clients
+                .get(&connection)
+                .ok_or_else(|| anyhow!("connection '{}' not found", &connection))?
+                .query_one(&query, &[])
+                .await
+        }
+        None => state.pgclient.query_one(&query, &[]).await,
+    }
+    .context("running query")?;
     if row.columns().len() != 1 {
         bail!(
             "set-from-sql query must return exactly one column, but it returned {}",
@@ -69,16 +69,12 @@ class SmokeTest(unittest.TestCase):
                     row = cur.fetchone()
                     self.assertEqual(row, ([1, 2, 3],))
 
-            # Text roundtripping of a two-dimensional integer array is
-            # not supported.
+            # Text roundtripping of a two-dimensional integer array is supported
+            # too.
             with conn.cursor() as cur:
-                with self.assertRaisesRegex(
-                    psycopg3.errors.InvalidParameterValue,
-                    "parsing multi-dimensional arrays is not supported",
-                ):
-                    cur.execute("SELECT %t", ([[1], [2], [3]],))
-                    row = cur.fetchone()
-                    self.assertEqual(row, ([[1], [2], [3]],))
+                cur.execute("SELECT %t", ([[1], [2], [3]],))
+                row = cur.fetchone()
+                self.assertEqual(row, ([[1], [2], [3]],))
 
     def test_sqlalchemy(self) -> None:
         engine = sqlalchemy.engine.create_engine(MATERIALIZED_URL)
@@ -0,0 +1,33 @@
+# Copyright Materialize, Inc. and contributors. All rights reserved.
+#
+# Use of this software is governed by the Business Source License
+# included in the LICENSE file at the root of this repository.
+#
+# As of the Change Date specified in that file, in accordance with
+# the Business Source License, use of this software will be governed
+# by the Apache License, Version 2.0.
+
+from materialize.mzcompose import Composition, WorkflowArgumentParser
+from materialize.mzcompose.services import Materialized, Postgres, Testdrive
+
+SERVICES = [
+    Materialized(),
+    Testdrive(),
+    # The Debezium image ships the decoderbufs plugin, which ours does not.
+    Postgres(image="debezium/postgres:15"),
+]
+
+
+def workflow_default(c: Composition, parser: WorkflowArgumentParser) -> None:
+    """Test Postgres sources that replicate through the decoderbufs plugin"""
+
+    parser.add_argument(
+        "filter",
+        nargs="*",
+        default=["*.td"],
+        help="limit to only the files matching filter",
+    )
+    args = parser.parse_args()
+
+    c.up("postgres", "materialized")
+    c.run("testdrive", *args.filter)
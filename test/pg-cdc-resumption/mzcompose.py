@@ -7,6 +7,8 @@
 # the Business Source License, use of this software will be governed
 # by the Apache License, Version 2.0.
 
+from typing import Callable
+
 from materialize.mzcompose import Composition
 from materialize.mzcompose.services import Materialized, Postgres, Testdrive, Toxiproxy
 
@@ -33,12 +35,20 @@ def workflow_default(c: Composition) -> None:
         restart_mz_during_snapshot,
         restart_pg_during_replication,
         restart_mz_during_replication,
+        fail_during_replication("pg_replication_before_commit"),
+        fail_during_replication("pg_replication_after_progress"),
+        fail_during_replication("pg_offset_commit_failure"),
     ]:
         print(f">>> Running scenario {scenario.__name__}")
         begin(c)
         scenario(c)
         end(c)
 
+    workflow_alter_table_during_snapshot(c)
+    workflow_copy_read_timeout_during_snapshot(c)
+    workflow_revoke_privileges_during_snapshot(c)
+    workflow_disconnect_mid_table_during_snapshot(c)
+
 
 def initialize(c: Composition) -> None:
     c.up("materialized", "postgres", "toxiproxy")
@@ -124,3 +134,84 @@ def restart_mz_during_replication(c: Composition) -> None:
     restart_mz(c)
 
     c.run("testdrive", "delete-rows-t2.td")
+
+
+def fail_during_replication(failpoint: str) -> Callable[[Composition], None]:
+    """Trigger the given failpoint once while replicating, then let the
+    source resume and check that every change was ingested exactly once"""
+
+    def scenario(c: Composition) -> None:
+        c.run("testdrive", "wait-for-snapshot.td")
+
+        with c.override(
+            Materialized(environment_extra=[f"FAILPOINTS={failpoint}=1*return"])
+        ):
+            restart_mz(c)
+            c.run("testdrive", "delete-rows-t1.td", "alter-table.td")
+
+        restart_mz(c)
+
+        c.run("testdrive", "delete-rows-t2.td")
+
+    scenario.__name__ = f"{failpoint}_during_replication"
+    return scenario
+
+
+def workflow_alter_table_during_snapshot(c: Composition) -> None:
+    """Alter a table after a source validated it, but before the source's
+    snapshot starts, and check that the source errors instead of ingesting
+    misaligned columns"""
+
+    with c.override(
+        Materialized(
+            environment_extra=[
+                "FAILPOINTS=pg_snapshot_before_slot_creation=1*sleep(10000)"
+            ]
+        )
+    ):
+        c.up("materialized", "postgres")
+        c.run("testdrive", "alter-table-during-snapshot.td")
+
+
+def workflow_revoke_privileges_during_snapshot(c: Composition) -> None:
+    """Revoke a privilege of the upstream role after a source validated its
+    tables, but before the source's snapshot starts, and check that the source
+    stalls with a hint until the privilege is granted again"""
+
+    with c.override(
+        Materialized(
+            environment_extra=[
+                "FAILPOINTS=pg_snapshot_before_slot_creation=1*sleep(10000)"
+            ]
+        )
+    ):
+        c.up("materialized", "postgres")
+        c.run("testdrive", "revoke-privileges-during-snapshot.td")
+
+
+def workflow_copy_read_timeout_during_snapshot(c: Composition) -> None:
+    """Lock a table after a source created its slot, but before it copies the
+    table, and check that the copy is retried once it exceeds its
+    COPY READ TIMEOUT instead of stalling the source"""
+
+    with c.override(
+        Materialized(
+            environment_extra=["FAILPOINTS=pg_snapshot_before_copy=1*sleep(10000)"]
+        )
+    ):
+        c.up("materialized", "postgres")
+        c.run("testdrive", "copy-read-timeout-during-snapshot.td")
+
+
+def workflow_disconnect_mid_table_during_snapshot(c: Composition) -> None:
+    """Disconnect Postgres while a source copies a table with a primary key,
+    and check that the snapshot resumes after the last row it sent, ingesting
+    every row exactly once without stalling the source"""
+
+    c.up("materialized", "postgres", "toxiproxy")
+    c.run(
+        "testdrive",
+        "configure-toxiproxy.td",
+        "disconnect-mid-table-during-snapshot.td",
+        "toxiproxy-remove.td",
+    )
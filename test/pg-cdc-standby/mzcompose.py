@@ -0,0 +1,74 @@
+# Copyright Materialize, Inc. and contributors. All rights reserved.
+#
+# Use of this software is governed by the Business Source License
+# included in the LICENSE file at the root of this repository.
+#
+# As of the Change Date specified in that file, in accordance with
+# the Business Source License, use of this software will be governed
+# by the Apache License, Version 2.0.
+
+from materialize.mzcompose import Composition, Service, WorkflowArgumentParser
+from materialize.mzcompose.services import Materialized, Postgres, Testdrive
+
+# Logical decoding on a standby requires PostgreSQL 16.
+POSTGRES_IMAGE = "postgres:16.1-bullseye"
+
+SERVICES = [
+    Materialized(),
+    Testdrive(),
+    Postgres(image=POSTGRES_IMAGE),
+    # A hot standby of the `postgres` service, cloned from it on startup.
+    Service(
+        name="postgres-standby",
+        config={
+            "image": POSTGRES_IMAGE,
+            "command": [
+                "bash",
+                "-c",
+                "until rm -rf \"$$PGDATA\"/* && pg_basebackup --host=postgres"
+                " --username=postgres --pgdata=\"$$PGDATA\" --write-recovery-conf"
+                " --wal-method=stream; do sleep 1; done"
+                " && exec docker-entrypoint.sh postgres -c wal_level=logical"
+                " -c hot_standby=on -c hot_standby_feedback=on"
+                # Records the statements that sources run on the standby.
+                " -c shared_preload_libraries=pg_stat_statements",
+            ],
+            "environment": ["POSTGRES_PASSWORD=postgres", "PGPASSWORD=postgres"],
+            "ports": [5432],
+            "healthcheck": {
+                "test": ["CMD", "pg_isready"],
+                "interval": "1s",
+                "start_period": "30s",
+            },
+        },
+    ),
+]
+
+
+def workflow_default(c: Composition, parser: WorkflowArgumentParser) -> None:
+    """Test Postgres sources that snapshot or replicate from a hot standby"""
+
+    parser.add_argument(
+        "filter",
+        nargs="*",
+        default=["*.td"],
+        help="limit to only the files matching filter",
+    )
+    args = parser.parse_args()
+
+    c.up("postgres")
+    # The standby clones the primary over a replication connection.
+    c.exec(
+        "postgres",
+        "bash",
+        "-c",
+        "echo 'host replication all all scram-sha-256' >> \"$PGDATA/pg_hba.conf\""
+        " && psql --username=postgres --command='SELECT pg_reload_conf()'",
+    )
+    # Sources fall back to limited peeks right away, which replicating from the
+    # standby must support.
+    with c.override(
+        Materialized(environment_extra=["FAILPOINTS=pg_peek_unsupported=return"])
+    ):
+        c.up("postgres-standby", "materialized")
+        c.run("testdrive", *args.filter)
@@ -8,13 +8,20 @@
 # by the Apache License, Version 2.0.
 
 from materialize.mzcompose import Composition, WorkflowArgumentParser
-from materialize.mzcompose.services import Materialized, Postgres, TestCerts, Testdrive
+from materialize.mzcompose.services import (
+    Materialized,
+    Postgres,
+    Redpanda,
+    TestCerts,
+    Testdrive,
+)
 
 SERVICES = [
     Materialized(volumes_extra=["secrets:/share/secrets"]),
     Testdrive(),
     TestCerts(),
     Postgres(),
+    Redpanda(),
 ]
 
 
@@ -37,7 +44,7 @@ def workflow_default(c: Composition, parser: WorkflowArgumentParser) -> None:
         "test-certs", "cat", "/secrets/postgres.key", capture=True
     ).stdout
 
-    c.up("materialized", "test-certs", "testdrive", "postgres")
+    c.up("materialized", "test-certs", "testdrive", "postgres", "redpanda")
     c.run(
         "testdrive",
         f"--var=ssl-ca={ssl_ca}",